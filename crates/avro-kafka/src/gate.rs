@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Switch that makes a publisher fail every publish and probe with
+/// [`crate::PublishError::Blocked`] while it is closed, as if the cluster
+/// were unreachable, without touching the brokers. Clones share the switch.
+/// Open by default.
+#[derive(Debug, Clone, Default)]
+pub struct SinkGate {
+    closed: Arc<AtomicBool>,
+}
+
+impl SinkGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn open(&self) {
+        self.closed.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,163 @@
+//! JSON Schema for the JSON encoding. It is derived from the Avro schema of
+//! the same topic, so consumers get one contract whichever encoding is used.
+
+use std::collections::HashMap;
+
+use apache_avro::Schema;
+use serde_json::{json, Map, Value};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema describing one record of `schema` as serde_json writes it.
+/// Records are closed (`additionalProperties: false`) and every field is
+/// required, because the collector always writes every field.
+pub fn avro_to_json_schema(schema: &Schema) -> Value {
+    let mut records = HashMap::new();
+    let mut root = convert(schema, &mut records);
+    if let Value::Object(map) = &mut root {
+        map.insert("$schema".to_string(), json!(DRAFT));
+    }
+    root
+}
+
+fn convert(schema: &Schema, records: &mut HashMap<String, Value>) -> Value {
+    match schema {
+        Schema::Null => json!({"type": "null"}),
+        Schema::Boolean => json!({"type": "boolean"}),
+        Schema::Int | Schema::Date | Schema::TimeMillis => {
+            json!({"type": "integer", "minimum": i32::MIN, "maximum": i32::MAX})
+        }
+        Schema::Long
+        | Schema::TimeMicros
+        | Schema::TimestampMillis
+        | Schema::TimestampMicros
+        | Schema::LocalTimestampMillis
+        | Schema::LocalTimestampMicros => json!({"type": "integer"}),
+        Schema::Float | Schema::Double => json!({"type": "number"}),
+        Schema::String | Schema::Uuid => json!({"type": "string"}),
+        // serde writes byte buffers as arrays of numbers.
+        Schema::Bytes | Schema::Fixed(_) | Schema::Decimal(_) | Schema::Duration => json!({
+            "type": "array",
+            "items": {"type": "integer", "minimum": 0, "maximum": 255}
+        }),
+        Schema::Array(items) => json!({"type": "array", "items": convert(items, records)}),
+        Schema::Map(values) => {
+            json!({"type": "object", "additionalProperties": convert(values, records)})
+        }
+        Schema::Union(union) => json!({
+            "anyOf": union
+                .variants()
+                .iter()
+                .map(|variant| convert(variant, records))
+                .collect::<Vec<_>>()
+        }),
+        Schema::Enum(schema) => json!({"type": "string", "enum": schema.symbols}),
+        Schema::Record(record) => {
+            let mut properties = Map::new();
+            for field in &record.fields {
+                properties.insert(field.name.clone(), convert(&field.schema, records));
+            }
+            let required: Vec<&str> = record.fields.iter().map(|f| f.name.as_str()).collect();
+            let value = json!({
+                "title": record.name.name,
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false
+            });
+            records.insert(record.name.name.clone(), value.clone());
+            value
+        }
+        // Named types are inlined wherever they are referenced again.
+        Schema::Ref { name } => records.get(&name.name).cloned().unwrap_or_else(|| json!({})),
+    }
+}
+
+/// Checks `value` against a schema produced by [`avro_to_json_schema`]. Only
+/// the keywords that function emits are supported. The error names the path
+/// of the first offending value, e.g. `$.device.unit_id`.
+pub fn validate_json(schema: &Value, value: &Value) -> Result<(), String> {
+    validate_at(schema, value, "$")
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    if let Some(variants) = schema.get("anyOf").and_then(Value::as_array) {
+        return if variants
+            .iter()
+            .any(|variant| validate_at(variant, value, path).is_ok())
+        {
+            Ok(())
+        } else {
+            Err(format!("{path}: matches none of the allowed types"))
+        };
+    }
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let matches = match expected {
+            "null" => value.is_null(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "string" => value.is_string(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!("{path}: expected {expected}, found {value}"));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        let below = schema
+            .get("minimum")
+            .and_then(Value::as_f64)
+            .is_some_and(|minimum| number < minimum);
+        let above = schema
+            .get("maximum")
+            .and_then(Value::as_f64)
+            .is_some_and(|maximum| number > maximum);
+        if below || above {
+            return Err(format!("{path}: {value} is out of range"));
+        }
+    }
+    if let Some(symbols) = schema.get("enum").and_then(Value::as_array) {
+        if !symbols.contains(value) {
+            return Err(format!("{path}: {value} is not an allowed symbol"));
+        }
+    }
+    match value {
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{path}[{index}]"))?;
+                }
+            }
+        }
+        Value::Object(map) => {
+            let required = schema.get("required").and_then(Value::as_array);
+            for name in required.into_iter().flatten().filter_map(Value::as_str) {
+                if !map.contains_key(name) {
+                    return Err(format!("{path}: missing property {name}"));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, item) in map {
+                let item_path = format!("{path}.{name}");
+                match (
+                    properties.and_then(|properties| properties.get(name)),
+                    schema.get("additionalProperties"),
+                ) {
+                    (Some(property), _) => validate_at(property, item, &item_path)?,
+                    (None, Some(Value::Bool(false))) => {
+                        return Err(format!("{path}: unexpected property {name}"));
+                    }
+                    (None, Some(additional @ Value::Object(_))) => {
+                        validate_at(additional, item, &item_path)?
+                    }
+                    (None, _) => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
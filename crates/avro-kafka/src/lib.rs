@@ -1,21 +1,144 @@
 #![allow(dead_code)]
 
-use std::time::Duration;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use apache_avro::{Schema, Writer};
+use apache_avro::{Codec, GenericSingleObjectWriter, Schema, Writer};
 use rdkafka::config::ClientConfig;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::util::Timeout;
 use serde::Serialize;
+use serde_json::Value;
 use thiserror::Error;
-use tracing::info;
+use tracing::{info, instrument};
 
-#[derive(Debug, Clone)]
+pub mod gate;
+pub mod json_schema;
+pub mod security;
+pub mod transactions;
+
+pub use gate::SinkGate;
+pub use json_schema::{avro_to_json_schema, validate_json};
+pub use security::KafkaSecurity;
+pub use transactions::Transactions;
+
+/// librdkafka's default `message.max.bytes`.
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 1_000_000;
+
+/// Wire format of published payloads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Avro object container files, schema embedded. Batches share one
+    /// container, compressed with the publisher's [`AvroCodec`].
+    #[default]
+    Avro,
+    /// Avro single-object encoding: a 2-byte marker, the 8-byte Rabin
+    /// fingerprint of the schema and the record, one record per message.
+    /// Consumers look the schema up by its fingerprint.
+    AvroSingle,
+    /// UTF-8 JSON: an object per record, an array for batches. Consumers get
+    /// the schema from [`Publisher::json_schema`] instead.
+    Json,
+}
+
+impl Encoding {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "avro" => Some(Self::Avro),
+            "avro_single" => Some(Self::AvroSingle),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Avro => "avro",
+            Self::AvroSingle => "avro_single",
+            Self::Json => "json",
+        }
+    }
+
+    /// Whether several records can share one message.
+    pub fn batches(self) -> bool {
+        self != Self::AvroSingle
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Compression of the blocks of an Avro container.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AvroCodec {
+    Null,
+    #[default]
+    Deflate,
+    Zstd,
+}
+
+impl AvroCodec {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "null" => Some(Self::Null),
+            "deflate" => Some(Self::Deflate),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Deflate => "deflate",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    fn codec(self) -> Codec {
+        match self {
+            Self::Null => Codec::Null,
+            Self::Deflate => Codec::Deflate,
+            Self::Zstd => Codec::Zstandard,
+        }
+    }
+}
+
+impl fmt::Display for AvroCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What [`Publisher::probe`] found out about the cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BrokerHealth {
+    /// Brokers listed in the metadata response; 0 for a mock publisher.
+    pub brokers: usize,
+    pub latency_ms: u64,
+}
+
+#[derive(Clone)]
 pub struct Publisher {
     schema: Schema,
     topic: String,
+    /// Schemas of other topics served by this producer, see
+    /// [`Self::with_topic_schema`]. Topics not listed use `schema`.
+    schemas: Arc<HashMap<String, Schema>>,
     producer: Option<FutureProducer>,
     timeout: Duration,
+    encoding: Encoding,
+    avro_codec: AvroCodec,
+    max_message_bytes: usize,
+    /// Second producer for exactly-once drains, when a transactional id is set.
+    transactions: Option<Transactions>,
+    gate: SinkGate,
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +149,14 @@ pub struct KafkaConfig {
     pub compression: String,
     pub message_timeout_ms: u64,
     pub enable_idempotence: bool,
+    /// Largest payload the producer accepts (`message.max.bytes`).
+    pub max_message_bytes: usize,
+    /// Also build a transactional producer with this `transactional.id`,
+    /// see [`Publisher::transactions`]. Must be unique per collector and
+    /// stable across restarts.
+    pub transactional_id: Option<String>,
+    /// SASL and TLS settings shared by every client built from this config.
+    pub security: KafkaSecurity,
 }
 
 impl Publisher {
@@ -33,8 +164,14 @@ impl Publisher {
         Self {
             schema,
             topic: topic.into(),
+            schemas: Arc::default(),
             producer: None,
             timeout: Duration::from_millis(0),
+            encoding: Encoding::Avro,
+            avro_codec: AvroCodec::default(),
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            transactions: None,
+            gate: SinkGate::default(),
         }
     }
 
@@ -44,44 +181,126 @@ impl Publisher {
         config: KafkaConfig,
     ) -> Result<Self, PublishError> {
         let timeout = Duration::from_millis(config.message_timeout_ms);
-        let producer: FutureProducer = ClientConfig::new()
-            .set("bootstrap.servers", &config.brokers)
-            .set("client.id", &config.client_id)
-            .set("acks", &config.acks)
-            .set("compression.type", &config.compression)
-            .set(
-                "enable.idempotence",
-                if config.enable_idempotence {
-                    "true"
-                } else {
-                    "false"
-                },
-            )
-            .set("message.timeout.ms", &config.message_timeout_ms.to_string())
+        let producer: FutureProducer = producer_config(&config)
             .create()
             .map_err(PublishError::KafkaConfig)?;
+        let transactions = config
+            .transactional_id
+            .as_deref()
+            .map(|id| Transactions::new(&config, id))
+            .transpose()?;
 
         Ok(Self {
             schema,
             topic: topic.into(),
+            schemas: Arc::default(),
             producer: Some(producer),
             timeout,
+            encoding: Encoding::Avro,
+            avro_codec: AvroCodec::default(),
+            max_message_bytes: config.max_message_bytes,
+            transactions,
+            gate: SinkGate::default(),
         })
     }
 
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Serializes records published to `topic` with `schema`, e.g. alarms
+    /// next to telemetry, so one producer serves every record type.
+    pub fn with_topic_schema(mut self, topic: impl Into<String>, schema: Schema) -> Self {
+        Arc::make_mut(&mut self.schemas).insert(topic.into(), schema);
+        self
+    }
+
+    /// The schema records published to `topic` are serialized with.
+    pub fn schema_for(&self, topic: &str) -> &Schema {
+        if topic == self.topic {
+            return &self.schema;
+        }
+        self.schemas.get(topic).unwrap_or(&self.schema)
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Compresses Avro containers with `codec`; other encodings ignore it.
+    pub fn with_avro_codec(mut self, codec: AvroCodec) -> Self {
+        self.avro_codec = codec;
+        self
+    }
+
+    pub fn avro_codec(&self) -> AvroCodec {
+        self.avro_codec
+    }
+
+    /// Overrides the payload size limit, e.g. to exercise it with a mock.
+    pub fn with_max_message_bytes(mut self, max_message_bytes: usize) -> Self {
+        self.max_message_bytes = max_message_bytes;
+        self
+    }
+
+    pub fn max_message_bytes(&self) -> usize {
+        self.max_message_bytes
+    }
+
+    /// Blocks publishes and probes while `gate` is closed, e.g. to rehearse
+    /// an outage. The transactional producer is not gated.
+    pub fn with_gate(mut self, gate: SinkGate) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    /// The transactional producer, built when [`KafkaConfig::transactional_id`]
+    /// is set. Never set on a mock.
+    pub fn transactions(&self) -> Option<&Transactions> {
+        self.transactions.as_ref()
+    }
+
     pub async fn publish<T: Serialize>(&self, value: &T) -> Result<(), PublishError> {
-        let payload = self.serialize_batch(std::slice::from_ref(value))?;
+        let payload = self.serialize(value)?;
         self.publish_bytes(&self.topic, &payload).await
     }
 
+    /// Publishes `value` to `topic` with the schema registered for it.
+    pub async fn publish_to<T: Serialize>(
+        &self,
+        topic: &str,
+        value: &T,
+    ) -> Result<(), PublishError> {
+        let payload = self.serialize_for(topic, value)?;
+        self.publish_bytes(topic, &payload).await
+    }
+
+    /// Publishes `value` to `topic` serialized with `schema`, whatever is
+    /// registered for the topic.
+    pub async fn publish_with_schema<T: Serialize>(
+        &self,
+        topic: &str,
+        schema: &Schema,
+        value: &T,
+    ) -> Result<(), PublishError> {
+        let payload = self.serialize_with(schema, value)?;
+        self.publish_bytes(topic, &payload).await
+    }
+
+    /// Publishes a pre-encoded payload. Payloads above
+    /// [`Self::max_message_bytes`] are rejected before they reach the producer.
+    #[instrument(name = "kafka_publish", skip_all, fields(topic = %topic, bytes = payload.len()))]
     pub async fn publish_bytes(&self, topic: &str, payload: &[u8]) -> Result<(), PublishError> {
+        self.check_gate()?;
+        self.check_size(payload)?;
         match &self.producer {
             Some(producer) => {
-                let record = FutureRecord::to(topic).payload(payload);
+                let record = FutureRecord::<(), [u8]>::to(topic).payload(payload);
                 producer
                     .send(record, Timeout::After(self.timeout))
                     .await
-                    .map_err(|(err, _)| PublishError::Kafka(err))?;
+                    .map_err(|(err, _)| PublishError::from_kafka(err))?;
                 Ok(())
             }
             None => {
@@ -91,8 +310,145 @@ impl Publisher {
         }
     }
 
+    /// Publishes a pre-encoded payload with a record key, e.g. the Sparkplug
+    /// topic when Sparkplug messages are carried over Kafka.
+    #[instrument(name = "kafka_publish", skip_all, fields(topic = %topic, bytes = payload.len()))]
+    pub async fn publish_keyed(
+        &self,
+        topic: &str,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<(), PublishError> {
+        self.check_gate()?;
+        self.check_size(payload)?;
+        match &self.producer {
+            Some(producer) => {
+                let record = FutureRecord::<str, [u8]>::to(topic)
+                    .key(key)
+                    .payload(payload);
+                producer
+                    .send(record, Timeout::After(self.timeout))
+                    .await
+                    .map_err(|(err, _)| PublishError::from_kafka(err))?;
+                Ok(())
+            }
+            None => {
+                info!(topic = %topic, key = %key, bytes = payload.len(), "mock publish invoked");
+                Ok(())
+            }
+        }
+    }
+
+    /// Fetches the metadata of this publisher's topic, bounded by the
+    /// producer's message timeout. Succeeds when a broker answered, so a
+    /// failure means the cluster is unreachable or rejects the client, not
+    /// that a payload was bad. A mock always succeeds.
+    pub async fn probe(&self) -> Result<BrokerHealth, PublishError> {
+        self.check_gate()?;
+        let Some(producer) = self.producer.clone() else {
+            return Ok(BrokerHealth {
+                brokers: 0,
+                latency_ms: 0,
+            });
+        };
+        let topic = self.topic.clone();
+        let timeout = self.timeout;
+        let start = Instant::now();
+        let brokers = tokio::task::spawn_blocking(move || {
+            producer
+                .client()
+                .fetch_metadata(Some(&topic), timeout)
+                .map(|metadata| metadata.brokers().len())
+        })
+        .await
+        .map_err(|err| PublishError::Probe(err.to_string()))?
+        .map_err(|err| PublishError::Probe(err.to_string()))?;
+        if brokers == 0 {
+            return Err(PublishError::Probe("metadata lists no brokers".to_string()));
+        }
+        Ok(BrokerHealth {
+            brokers,
+            latency_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
+    fn check_gate(&self) -> Result<(), PublishError> {
+        if self.gate.is_closed() {
+            return Err(PublishError::Blocked);
+        }
+        Ok(())
+    }
+
+    fn check_size(&self, payload: &[u8]) -> Result<(), PublishError> {
+        if payload.len() > self.max_message_bytes {
+            return Err(PublishError::Oversized {
+                bytes: payload.len(),
+                max: self.max_message_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, PublishError> {
+        self.serialize_with(&self.schema, value)
+    }
+
     pub fn serialize_batch<T: Serialize>(&self, values: &[T]) -> Result<Vec<u8>, PublishError> {
-        let mut writer = Writer::with_codec(&self.schema, Vec::new(), apache_avro::Codec::Deflate);
+        self.serialize_batch_with(&self.schema, values)
+    }
+
+    /// Serializes `value` with the schema registered for `topic`.
+    pub fn serialize_for<T: Serialize>(
+        &self,
+        topic: &str,
+        value: &T,
+    ) -> Result<Vec<u8>, PublishError> {
+        self.serialize_with(self.schema_for(topic), value)
+    }
+
+    /// Serializes `values` with the schema registered for `topic`.
+    pub fn serialize_batch_for<T: Serialize>(
+        &self,
+        topic: &str,
+        values: &[T],
+    ) -> Result<Vec<u8>, PublishError> {
+        self.serialize_batch_with(self.schema_for(topic), values)
+    }
+
+    fn serialize_with<T: Serialize>(
+        &self,
+        schema: &Schema,
+        value: &T,
+    ) -> Result<Vec<u8>, PublishError> {
+        match self.encoding {
+            Encoding::Avro | Encoding::AvroSingle => {
+                self.serialize_batch_with(schema, std::slice::from_ref(value))
+            }
+            Encoding::Json => serde_json::to_vec(&json_record(schema, value)?)
+                .map_err(|err| PublishError::Json(err.to_string())),
+        }
+    }
+
+    /// Fails for more than one record with [`Encoding::AvroSingle`], see
+    /// [`Encoding::batches`].
+    fn serialize_batch_with<T: Serialize>(
+        &self,
+        schema: &Schema,
+        values: &[T],
+    ) -> Result<Vec<u8>, PublishError> {
+        match self.encoding {
+            Encoding::Avro => {}
+            Encoding::AvroSingle => return single_object(schema, values),
+            Encoding::Json => {
+                let records = values
+                    .iter()
+                    .map(|value| json_record(schema, value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return serde_json::to_vec(&records)
+                    .map_err(|err| PublishError::Json(err.to_string()));
+            }
+        }
+        let mut writer = Writer::with_codec(schema, Vec::new(), self.avro_codec.codec());
         for value in values {
             let avro_value =
                 apache_avro::to_value(value).map_err(|err| PublishError::Encode(err.to_string()))?;
@@ -101,15 +457,60 @@ impl Publisher {
                 .map_err(|err| PublishError::Encode(err.to_string()))?;
         }
         writer
-            .flush()
-            .map_err(|err| PublishError::Encode(err.to_string()))?;
-        Ok(writer.into_inner())
+            .into_inner()
+            .map_err(|err| PublishError::Encode(err.to_string()))
+    }
+
+    /// JSON Schema of this publisher's records, derived from its Avro schema.
+    pub fn json_schema(&self) -> Value {
+        avro_to_json_schema(&self.schema)
+    }
+
+    /// Publishes [`Self::json_schema`] to `schema_topic`, keyed by the data
+    /// topic so a compacted schema topic keeps the latest schema per topic.
+    pub async fn publish_json_schema(&self, schema_topic: &str) -> Result<(), PublishError> {
+        let payload = serde_json::to_vec(&self.json_schema())
+            .map_err(|err| PublishError::Json(err.to_string()))?;
+        self.publish_keyed(schema_topic, &self.topic, &payload).await
     }
 
     pub fn default_schema() -> Schema {
         Schema::parse_str(DEFAULT_SCHEMA).expect("valid avro schema")
     }
 
+    pub fn daily_summary_schema() -> Schema {
+        Schema::parse_str(DAILY_SUMMARY_SCHEMA).expect("valid avro schema")
+    }
+
+    pub fn alarm_schema() -> Schema {
+        Schema::parse_str(ALARM_SCHEMA).expect("valid avro schema")
+    }
+
+    pub fn alert_schema() -> Schema {
+        Schema::parse_str(ALERT_SCHEMA).expect("valid avro schema")
+    }
+
+    pub fn plant_snapshot_schema() -> Schema {
+        Schema::parse_str(PLANT_SNAPSHOT_SCHEMA).expect("valid avro schema")
+    }
+
+    /// A publisher for another topic and schema that shares this one's producer
+    /// and the schemas registered with it.
+    pub fn for_topic(&self, schema: Schema, topic: impl Into<String>) -> Self {
+        Self {
+            schema,
+            topic: topic.into(),
+            schemas: self.schemas.clone(),
+            producer: self.producer.clone(),
+            timeout: self.timeout,
+            encoding: self.encoding,
+            avro_codec: self.avro_codec,
+            max_message_bytes: self.max_message_bytes,
+            transactions: self.transactions.clone(),
+            gate: self.gate.clone(),
+        }
+    }
+
     pub fn topic(&self) -> &str {
         &self.topic
     }
@@ -119,10 +520,142 @@ impl Publisher {
 pub enum PublishError {
     #[error("avro encode error: {0}")]
     Encode(String),
+    #[error("json encode error: {0}")]
+    Json(String),
+    #[error("json schema violation: {0}")]
+    Schema(String),
+    #[error("payload of {bytes} bytes exceeds the {max} byte message limit")]
+    Oversized { bytes: usize, max: usize },
     #[error("kafka config error: {0}")]
     KafkaConfig(rdkafka::error::KafkaError),
+    /// A broker error that can clear up by itself, e.g. a full producer
+    /// queue, a timeout or a leader election.
     #[error("kafka publish error: {0}")]
-    Kafka(rdkafka::error::KafkaError),
+    Retriable(rdkafka::error::KafkaError),
+    /// A broker error that sending the same record to the same topic again
+    /// cannot get past: the payload or the topic was refused.
+    #[error("kafka publish error (fatal): {0}")]
+    Fatal(rdkafka::error::KafkaError),
+    #[error("kafka transaction error: {0}")]
+    Transaction(String),
+    #[error("kafka probe failed: {0}")]
+    Probe(String),
+    #[error("sink blocked")]
+    Blocked,
+}
+
+impl PublishError {
+    /// Classifies a librdkafka error as [`Self::Fatal`] or
+    /// [`Self::Retriable`].
+    pub fn from_kafka(err: KafkaError) -> Self {
+        if is_fatal_rejection(&err) {
+            Self::Fatal(err)
+        } else {
+            Self::Retriable(err)
+        }
+    }
+
+    /// Whether the payload itself was refused, so publishing it again fails
+    /// the same way however healthy the cluster is.
+    pub fn is_payload_error(&self) -> bool {
+        match self {
+            Self::Encode(_) | Self::Json(_) | Self::Schema(_) | Self::Oversized { .. } => true,
+            Self::Fatal(err) => is_payload_rejection(err),
+            Self::Retriable(_)
+            | Self::KafkaConfig(_)
+            | Self::Transaction(_)
+            | Self::Probe(_)
+            | Self::Blocked => false,
+        }
+    }
+
+    /// Whether publishing the record again fails the same way, because its
+    /// payload or its topic was refused. Such records are dead-lettered
+    /// rather than retried.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Self::Fatal(_)) || self.is_payload_error()
+    }
+}
+
+/// One record in Avro single-object encoding.
+fn single_object<T: Serialize>(schema: &Schema, values: &[T]) -> Result<Vec<u8>, PublishError> {
+    let [value] = values else {
+        return Err(PublishError::Encode(format!(
+            "single-object encoding holds one record, got {}",
+            values.len()
+        )));
+    };
+    let avro_value =
+        apache_avro::to_value(value).map_err(|err| PublishError::Encode(err.to_string()))?;
+    let mut writer = GenericSingleObjectWriter::new_with_capacity(schema, 1024)
+        .map_err(|err| PublishError::Encode(err.to_string()))?;
+    let mut payload = Vec::new();
+    writer
+        .write_value(avro_value, &mut payload)
+        .map_err(|err| PublishError::Encode(err.to_string()))?;
+    Ok(payload)
+}
+
+/// One record as JSON. Debug builds check it against the JSON Schema derived
+/// from `schema` so payloads drifting from the published contract fail loudly
+/// in tests.
+fn json_record<T: Serialize>(schema: &Schema, value: &T) -> Result<Value, PublishError> {
+    let record = serde_json::to_value(value).map_err(|err| PublishError::Json(err.to_string()))?;
+    if cfg!(debug_assertions) {
+        validate_json(&avro_to_json_schema(schema), &record).map_err(PublishError::Schema)?;
+    }
+    Ok(record)
+}
+
+fn is_payload_rejection(err: &KafkaError) -> bool {
+    matches!(
+        err.rdkafka_error_code(),
+        Some(
+            RDKafkaErrorCode::InvalidMessage
+                | RDKafkaErrorCode::InvalidMessageSize
+                | RDKafkaErrorCode::MessageSizeTooLarge
+                | RDKafkaErrorCode::InvalidRecord
+        )
+    )
+}
+
+/// Errors that stay until the payload or the topic changes: a refused
+/// payload, a topic that does not exist or one the client may not write to.
+/// Failures that affect every topic, such as rejected credentials, are left
+/// retriable so a cluster-wide fault does not dead-letter the whole buffer.
+fn is_fatal_rejection(err: &KafkaError) -> bool {
+    is_payload_rejection(err)
+        || matches!(
+            err.rdkafka_error_code(),
+            Some(
+                RDKafkaErrorCode::UnknownTopic
+                    | RDKafkaErrorCode::UnknownTopicOrPartition
+                    | RDKafkaErrorCode::InvalidTopic
+                    | RDKafkaErrorCode::TopicAuthorizationFailed
+            )
+        )
+}
+
+/// Settings shared by the producers built from `config`.
+fn producer_config(config: &KafkaConfig) -> ClientConfig {
+    let mut client = ClientConfig::new();
+    client
+        .set("bootstrap.servers", &config.brokers)
+        .set("client.id", &config.client_id)
+        .set("acks", &config.acks)
+        .set("compression.type", &config.compression)
+        .set(
+            "enable.idempotence",
+            if config.enable_idempotence {
+                "true"
+            } else {
+                "false"
+            },
+        )
+        .set("message.timeout.ms", config.message_timeout_ms.to_string())
+        .set("message.max.bytes", config.max_message_bytes.to_string());
+    config.security.apply(&mut client);
+    client
 }
 
 impl Default for KafkaConfig {
@@ -134,6 +667,9 @@ impl Default for KafkaConfig {
             compression: "zstd".to_string(),
             message_timeout_ms: 5_000,
             enable_idempotence: true,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            transactional_id: None,
+            security: KafkaSecurity::default(),
         }
     }
 }
@@ -151,7 +687,8 @@ const DEFAULT_SCHEMA: &str = r#"
         "name": "DeviceIdentity",
         "fields": [
           {"name": "ip", "type": "string"},
-          {"name": "unit_id", "type": "int"}
+          {"name": "unit_id", "type": "int"},
+          {"name": "base_address", "type": ["null", "int"], "default": null}
         ]
       }
     },
@@ -159,7 +696,203 @@ const DEFAULT_SCHEMA: &str = r#"
     {"name": "model_name", "type": "string"},
     {"name": "start", "type": "int"},
     {"name": "registers", "type": {"type": "array", "items": "int"}},
-    {"name": "collected_at_ms", "type": "long"}
+    {"name": "collected_at_ms", "type": "long"},
+    {
+      "name": "window",
+      "type": [
+        "null",
+        {
+          "type": "record",
+          "name": "SampleWindow",
+          "fields": [
+            {"name": "start_ms", "type": "long"},
+            {"name": "end_ms", "type": "long"},
+            {"name": "samples", "type": "long"},
+            {
+              "name": "points",
+              "type": {
+                "type": "array",
+                "items": {
+                  "type": "record",
+                  "name": "PointStats",
+                  "fields": [
+                    {"name": "name", "type": "string"},
+                    {"name": "min", "type": "double"},
+                    {"name": "avg", "type": "double"},
+                    {"name": "max", "type": "double"}
+                  ]
+                }
+              }
+            }
+          ]
+        }
+      ],
+      "default": null
+    },
+    {"name": "maintenance", "type": "boolean", "default": false},
+    {"name": "baseline", "type": "boolean", "default": false},
+    {"name": "sequence", "type": "long", "default": 0},
+    {"name": "cycle_id", "type": "long", "default": 0},
+    {
+      "name": "site",
+      "type": [
+        "null",
+        {
+          "type": "record",
+          "name": "SiteInfo",
+          "fields": [
+            {"name": "site_id", "type": "string"},
+            {"name": "plant", "type": ["null", "string"], "default": null},
+            {"name": "latitude", "type": ["null", "double"], "default": null},
+            {"name": "longitude", "type": ["null", "double"], "default": null},
+            {"name": "labels", "type": {"type": "map", "values": "string"}, "default": {}}
+          ]
+        }
+      ],
+      "default": null
+    },
+    {"name": "monotonic_ms", "type": "long", "default": 0},
+    {"name": "clock_unsynchronized", "type": "boolean", "default": false},
+    {"name": "namespace", "type": ["null", "string"], "default": null}
+  ]
+}
+"#;
+
+const DAILY_SUMMARY_SCHEMA: &str = r#"
+{
+  "type": "record",
+  "name": "SunspecDailySummary",
+  "namespace": "com.rusty.sunspec",
+  "fields": [
+    {"name": "date", "type": "string"},
+    {
+      "name": "device",
+      "type": {
+        "type": "record",
+        "name": "DeviceIdentity",
+        "fields": [
+          {"name": "ip", "type": "string"},
+          {"name": "unit_id", "type": "int"},
+          {"name": "base_address", "type": ["null", "int"], "default": null}
+        ]
+      }
+    },
+    {"name": "energy_wh", "type": "double"},
+    {"name": "max_power_w", "type": "double"},
+    {"name": "uptime_minutes", "type": "long"},
+    {"name": "fault_minutes", "type": "long"},
+    {"name": "samples", "type": "long"}
+  ]
+}
+"#;
+
+const ALARM_SCHEMA: &str = r#"
+{
+  "type": "record",
+  "name": "SunspecAlarm",
+  "namespace": "com.rusty.sunspec",
+  "fields": [
+    {
+      "name": "device",
+      "type": {
+        "type": "record",
+        "name": "DeviceIdentity",
+        "fields": [
+          {"name": "ip", "type": "string"},
+          {"name": "unit_id", "type": "int"},
+          {"name": "base_address", "type": ["null", "int"], "default": null}
+        ]
+      }
+    },
+    {"name": "model_id", "type": "int"},
+    {"name": "point", "type": "string"},
+    {"name": "event", "type": "string"},
+    {
+      "name": "transition",
+      "type": {"type": "enum", "name": "AlarmTransition", "symbols": ["raised", "cleared"]}
+    },
+    {"name": "state", "type": ["null", "string"], "default": null},
+    {"name": "collected_at_ms", "type": "long"},
+    {"name": "maintenance", "type": "boolean", "default": false}
+  ]
+}
+"#;
+
+const ALERT_SCHEMA: &str = r#"
+{
+  "type": "record",
+  "name": "SunspecAlert",
+  "namespace": "com.rusty.sunspec",
+  "fields": [
+    {"name": "rule", "type": "string"},
+    {
+      "name": "device",
+      "type": {
+        "type": "record",
+        "name": "DeviceIdentity",
+        "fields": [
+          {"name": "ip", "type": "string"},
+          {"name": "unit_id", "type": "int"},
+          {"name": "base_address", "type": ["null", "int"], "default": null}
+        ]
+      }
+    },
+    {"name": "model_id", "type": "int"},
+    {"name": "point", "type": "string"},
+    {
+      "name": "transition",
+      "type": {"type": "enum", "name": "AlarmTransition", "symbols": ["raised", "cleared"]}
+    },
+    {"name": "value", "type": "double"},
+    {"name": "limit", "type": "double"},
+    {"name": "collected_at_ms", "type": "long"},
+    {"name": "maintenance", "type": "boolean", "default": false}
+  ]
+}
+"#;
+
+const PLANT_SNAPSHOT_SCHEMA: &str = r#"
+{
+  "type": "record",
+  "name": "SunspecPlantSnapshot",
+  "namespace": "com.rusty.sunspec",
+  "fields": [
+    {"name": "snapshot_id", "type": "long"},
+    {"name": "triggered_at_ms", "type": "long"},
+    {"name": "spread_ms", "type": "long"},
+    {"name": "inverter_w", "type": "double"},
+    {"name": "meter_w", "type": ["null", "double"], "default": null},
+    {"name": "devices_expected", "type": "int"},
+    {"name": "devices_read", "type": "int"},
+    {"name": "late", "type": "int"},
+    {
+      "name": "inverters",
+      "type": {
+        "type": "array",
+        "items": {
+          "type": "record",
+          "name": "PlantReading",
+          "fields": [
+            {
+              "name": "device",
+              "type": {
+                "type": "record",
+                "name": "DeviceIdentity",
+                "fields": [
+                  {"name": "ip", "type": "string"},
+                  {"name": "unit_id", "type": "int"},
+                  {"name": "base_address", "type": ["null", "int"], "default": null}
+                ]
+              }
+            },
+            {"name": "model_id", "type": "int"},
+            {"name": "collected_at_ms", "type": "long"},
+            {"name": "power_w", "type": "double"}
+          ]
+        }
+      }
+    },
+    {"name": "meters", "type": {"type": "array", "items": "PlantReading"}}
   ]
 }
 "#;
@@ -0,0 +1,59 @@
+use std::fmt;
+
+use rdkafka::config::ClientConfig;
+
+/// How the clients authenticate to the brokers. Unset fields are left to
+/// librdkafka's defaults, so the default is plaintext without SASL.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct KafkaSecurity {
+    /// `plaintext`, `ssl`, `sasl_plaintext` or `sasl_ssl`.
+    pub protocol: Option<String>,
+    /// `PLAIN`, `SCRAM-SHA-256` or `SCRAM-SHA-512`.
+    pub sasl_mechanism: Option<String>,
+    pub sasl_username: Option<String>,
+    pub sasl_password: Option<String>,
+    /// CA bundle the broker certificates are verified against; the system
+    /// store when unset.
+    pub ssl_ca_location: Option<String>,
+    /// Client certificate and key for mutual TLS.
+    pub ssl_certificate_location: Option<String>,
+    pub ssl_key_location: Option<String>,
+}
+
+impl KafkaSecurity {
+    /// Sets the configured properties on a producer or consumer config.
+    pub fn apply(&self, client: &mut ClientConfig) {
+        let properties = [
+            ("security.protocol", &self.protocol),
+            ("sasl.mechanism", &self.sasl_mechanism),
+            ("sasl.username", &self.sasl_username),
+            ("sasl.password", &self.sasl_password),
+            ("ssl.ca.location", &self.ssl_ca_location),
+            ("ssl.certificate.location", &self.ssl_certificate_location),
+            ("ssl.key.location", &self.ssl_key_location),
+        ];
+        for (key, value) in properties {
+            if let Some(value) = value {
+                client.set(key, value);
+            }
+        }
+    }
+}
+
+/// Leaves the password out, so configs can be logged.
+impl fmt::Debug for KafkaSecurity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KafkaSecurity")
+            .field("protocol", &self.protocol)
+            .field("sasl_mechanism", &self.sasl_mechanism)
+            .field("sasl_username", &self.sasl_username)
+            .field(
+                "sasl_password",
+                &self.sasl_password.as_ref().map(|_| "<redacted>"),
+            )
+            .field("ssl_ca_location", &self.ssl_ca_location)
+            .field("ssl_certificate_location", &self.ssl_certificate_location)
+            .field("ssl_key_location", &self.ssl_key_location)
+            .finish()
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::util::Timeout;
+use rdkafka::{Message, Offset, TopicPartitionList};
+use tracing::instrument;
+
+use crate::{KafkaConfig, PublishError};
+
+/// Records scanned back from the end of a topic by [`Transactions::read_latest`].
+const LATEST_SCAN_RECORDS: i64 = 1_000;
+
+/// A producer with `transactional.id` set. Every send has to happen inside a
+/// transaction, so it is kept apart from the producer used for everything
+/// else. Clones share the producer.
+#[derive(Clone)]
+pub struct Transactions {
+    producer: FutureProducer,
+    config: KafkaConfig,
+    transactional_id: String,
+    timeout: Duration,
+    /// Set once `init_transactions` succeeded; shared by clones.
+    ready: Arc<AtomicBool>,
+}
+
+impl Transactions {
+    pub(crate) fn new(config: &KafkaConfig, transactional_id: &str) -> Result<Self, PublishError> {
+        let producer: FutureProducer = crate::producer_config(config)
+            .set("transactional.id", transactional_id)
+            .set("enable.idempotence", "true")
+            .create()
+            .map_err(PublishError::KafkaConfig)?;
+        Ok(Self {
+            producer,
+            config: config.clone(),
+            transactional_id: transactional_id.to_string(),
+            timeout: Duration::from_millis(config.message_timeout_ms),
+            ready: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn transactional_id(&self) -> &str {
+        &self.transactional_id
+    }
+
+    /// Registers the transactional id with the cluster the first time. This
+    /// fences off an earlier producer with the same id and settles the
+    /// transaction it left open, so it is done before anything is read back.
+    async fn init(&self) -> Result<(), PublishError> {
+        if self.ready.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        let timeout = self.timeout;
+        self.blocking(move |producer| producer.init_transactions(timeout))
+            .await?;
+        self.ready.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    pub async fn begin(&self) -> Result<(), PublishError> {
+        self.init().await?;
+        self.blocking(|producer| producer.begin_transaction()).await
+    }
+
+    /// Sends within the open transaction and waits for the delivery report.
+    #[instrument(name = "kafka_publish", skip_all, fields(topic = %topic, bytes = payload.len()))]
+    pub async fn send(
+        &self,
+        topic: &str,
+        key: Option<&str>,
+        payload: &[u8],
+    ) -> Result<(), PublishError> {
+        if payload.len() > self.config.max_message_bytes {
+            return Err(PublishError::Oversized {
+                bytes: payload.len(),
+                max: self.config.max_message_bytes,
+            });
+        }
+        let mut record = FutureRecord::<str, [u8]>::to(topic).payload(payload);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        self.producer
+            .send(record, Timeout::After(self.timeout))
+            .await
+            .map_err(|(err, _)| PublishError::from_kafka(err))?;
+        Ok(())
+    }
+
+    pub async fn commit(&self) -> Result<(), PublishError> {
+        let timeout = self.timeout;
+        self.blocking(move |producer| producer.commit_transaction(timeout))
+            .await
+    }
+
+    pub async fn abort(&self) -> Result<(), PublishError> {
+        let timeout = self.timeout;
+        self.blocking(move |producer| producer.abort_transaction(timeout))
+            .await
+    }
+
+    /// Payload of the newest committed record with `key` among the last
+    /// records of partition 0 of `topic`, e.g. a commit marker. Reads with
+    /// `read_committed`, so records of aborted or open transactions are not
+    /// seen.
+    pub async fn read_latest(
+        &self,
+        topic: &str,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, PublishError> {
+        self.init().await?;
+        let config = self.config.clone();
+        let timeout = self.timeout;
+        let (topic, key) = (topic.to_string(), key.to_string());
+        tokio::task::spawn_blocking(move || read_latest(&config, &topic, &key, timeout))
+            .await
+            .map_err(|err| PublishError::Transaction(err.to_string()))?
+    }
+
+    /// Runs a blocking librdkafka transaction call off the async runtime.
+    async fn blocking<F>(&self, call: F) -> Result<(), PublishError>
+    where
+        F: FnOnce(&FutureProducer) -> Result<(), rdkafka::error::KafkaError> + Send + 'static,
+    {
+        let producer = self.producer.clone();
+        tokio::task::spawn_blocking(move || call(&producer))
+            .await
+            .map_err(|err| PublishError::Transaction(err.to_string()))?
+            .map_err(PublishError::from_kafka)
+    }
+}
+
+fn read_latest(
+    config: &KafkaConfig,
+    topic: &str,
+    key: &str,
+    timeout: Duration,
+) -> Result<Option<Vec<u8>>, PublishError> {
+    let mut client = ClientConfig::new();
+    client
+        .set("bootstrap.servers", &config.brokers)
+        .set("client.id", &config.client_id)
+        .set("group.id", format!("{}-read-latest", config.client_id))
+        .set("enable.auto.commit", "false")
+        .set("isolation.level", "read_committed");
+    config.security.apply(&mut client);
+    let consumer: BaseConsumer = client.create().map_err(PublishError::KafkaConfig)?;
+    let (low, high) = consumer
+        .fetch_watermarks(topic, 0, timeout)
+        .map_err(PublishError::from_kafka)?;
+    if high <= low {
+        return Ok(None);
+    }
+    let mut partitions = TopicPartitionList::new();
+    partitions
+        .add_partition_offset(
+            topic,
+            0,
+            Offset::Offset((high - LATEST_SCAN_RECORDS).max(low)),
+        )
+        .map_err(PublishError::from_kafka)?;
+    consumer
+        .assign(&partitions)
+        .map_err(PublishError::from_kafka)?;
+
+    // Transaction markers take offsets but are never delivered, so the end
+    // is reached when the position, not the last message, gets to `high`.
+    let deadline = Instant::now() + timeout;
+    let mut latest = None;
+    while Instant::now() < deadline {
+        match consumer.poll(Duration::from_millis(100)) {
+            Some(Ok(message)) if message.key() == Some(key.as_bytes()) => {
+                latest = message.payload().map(<[u8]>::to_vec);
+            }
+            Some(Err(err)) => return Err(PublishError::from_kafka(err)),
+            _ => {}
+        }
+        let position = consumer
+            .position()
+            .map_err(PublishError::from_kafka)?
+            .find_partition(topic, 0)
+            .map(|partition| partition.offset());
+        if matches!(position, Some(Offset::Offset(offset)) if offset >= high) {
+            return Ok(latest);
+        }
+    }
+    Err(PublishError::Transaction(format!(
+        "{topic} not read to its end within {} ms",
+        timeout.as_millis()
+    )))
+}
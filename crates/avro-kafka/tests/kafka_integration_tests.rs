@@ -9,12 +9,34 @@ struct Sample {
     start: i32,
     registers: Vec<i32>,
     collected_at_ms: i64,
+    window: Option<Window>,
+    maintenance: bool,
+    baseline: bool,
+    sequence: i64,
+    cycle_id: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct Window {
+    start_ms: i64,
+    end_ms: i64,
+    samples: i64,
+    points: Vec<Point>,
+}
+
+#[derive(Debug, Serialize)]
+struct Point {
+    name: String,
+    min: f64,
+    avg: f64,
+    max: f64,
 }
 
 #[derive(Debug, Serialize)]
 struct Device {
     ip: String,
     unit_id: i32,
+    base_address: Option<i32>,
 }
 
 #[tokio::test]
@@ -25,10 +47,12 @@ async fn kafka_publish_integration() {
     };
     let topic = std::env::var("SUNSPEC_KAFKA_TOPIC").unwrap_or_else(|_| "sunspec.telemetry".to_string());
 
-    let mut config = KafkaConfig::default();
-    config.brokers = brokers;
-    config.client_id = std::env::var("SUNSPEC_KAFKA_CLIENT_ID")
-        .unwrap_or_else(|_| "sunspec-collector-tests".to_string());
+    let config = KafkaConfig {
+        brokers,
+        client_id: std::env::var("SUNSPEC_KAFKA_CLIENT_ID")
+            .unwrap_or_else(|_| "sunspec-collector-tests".to_string()),
+        ..KafkaConfig::default()
+    };
 
     let publisher = Publisher::new_kafka(Publisher::default_schema(), &topic, config)
         .expect("publisher init");
@@ -37,13 +61,57 @@ async fn kafka_publish_integration() {
         device: Device {
             ip: "127.0.0.1".to_string(),
             unit_id: 1,
+            base_address: None,
         },
         model_id: 103,
         model_name: "three_phase_inverter".to_string(),
         start: 40002,
         registers: vec![1, 2, 3],
         collected_at_ms: 1_700_000_000,
+        window: None,
+        maintenance: false,
+        baseline: false,
+        sequence: 1,
+        cycle_id: 1,
     };
 
     publisher.publish(&payload).await.expect("publish");
 }
+
+#[tokio::test]
+async fn kafka_transaction_integration() {
+    let brokers = match std::env::var("SUNSPEC_KAFKA_BROKERS") {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+    let topic = std::env::var("SUNSPEC_KAFKA_COMMIT_TOPIC")
+        .unwrap_or_else(|_| "sunspec.uplink-commits".to_string());
+
+    let config = KafkaConfig {
+        brokers,
+        client_id: "sunspec-collector-tests".to_string(),
+        transactional_id: Some("sunspec-collector-tests".to_string()),
+        ..KafkaConfig::default()
+    };
+    let publisher = Publisher::new_kafka(Publisher::default_schema(), &topic, config)
+        .expect("publisher init");
+    let transactions = publisher.transactions().expect("transactional producer");
+    let key = transactions.transactional_id().to_string();
+
+    transactions.begin().await.expect("begin");
+    transactions
+        .send(&topic, Some(&key), b"committed")
+        .await
+        .expect("send");
+    transactions.commit().await.expect("commit");
+
+    transactions.begin().await.expect("begin");
+    transactions
+        .send(&topic, Some(&key), b"aborted")
+        .await
+        .expect("send");
+    transactions.abort().await.expect("abort");
+
+    let latest = transactions.read_latest(&topic, &key).await.expect("read");
+    assert_eq!(latest.as_deref(), Some(&b"committed"[..]));
+}
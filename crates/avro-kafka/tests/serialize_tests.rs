@@ -1,5 +1,13 @@
-use avro_kafka::Publisher;
+use std::collections::BTreeMap;
+
+use apache_avro::{GenericSingleObjectReader, Reader, Schema};
+use avro_kafka::{
+    validate_json, AvroCodec, Encoding, PublishError, Publisher, SinkGate,
+    DEFAULT_MAX_MESSAGE_BYTES,
+};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
 use serde::Serialize;
+use serde_json::{json, Value};
 
 #[derive(Debug, Serialize)]
 struct Sample {
@@ -9,12 +17,47 @@ struct Sample {
     start: i32,
     registers: Vec<i32>,
     collected_at_ms: i64,
+    window: Option<Window>,
+    maintenance: bool,
+    baseline: bool,
+    sequence: i64,
+    cycle_id: i64,
+    site: Option<Site>,
+    monotonic_ms: i64,
+    clock_unsynchronized: bool,
+    namespace: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Site {
+    site_id: String,
+    plant: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Window {
+    start_ms: i64,
+    end_ms: i64,
+    samples: i64,
+    points: Vec<Point>,
+}
+
+#[derive(Debug, Serialize)]
+struct Point {
+    name: String,
+    min: f64,
+    avg: f64,
+    max: f64,
 }
 
 #[derive(Debug, Serialize)]
 struct Device {
     ip: String,
     unit_id: i32,
+    base_address: Option<i32>,
 }
 
 #[test]
@@ -24,15 +67,396 @@ fn serialize_default_schema() {
         device: Device {
             ip: "127.0.0.1".to_string(),
             unit_id: 1,
+            base_address: None,
         },
         model_id: 103,
         model_name: "three_phase_inverter".to_string(),
         start: 40002,
         registers: vec![1, 2, 3],
         collected_at_ms: 1_700_000_000,
+        window: None,
+        maintenance: false,
+        baseline: true,
+        sequence: 1,
+        cycle_id: 1,
+        site: None,
+        monotonic_ms: 0,
+        clock_unsynchronized: false,
+        namespace: None,
     };
 
     let bytes = publisher.serialize(&payload).expect("serialize ok");
     assert!(!bytes.is_empty());
     assert_eq!(publisher.topic(), "topic");
 }
+
+#[test]
+fn serialize_aggregated_window() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "topic");
+    let payload = Sample {
+        device: Device {
+            ip: "127.0.0.1".to_string(),
+            unit_id: 1,
+            base_address: Some(40_000),
+        },
+        model_id: 103,
+        model_name: "three_phase_inverter".to_string(),
+        start: 40002,
+        registers: vec![1, 2, 3],
+        collected_at_ms: 1_700_000_059,
+        window: Some(Window {
+            start_ms: 1_700_000_000,
+            end_ms: 1_700_000_060,
+            samples: 60,
+            points: vec![Point {
+                name: "W".to_string(),
+                min: 900.0,
+                avg: 1_000.0,
+                max: 1_100.0,
+            }],
+        }),
+        maintenance: true,
+        baseline: false,
+        sequence: 1,
+        cycle_id: 1,
+        site: None,
+        monotonic_ms: 0,
+        clock_unsynchronized: false,
+        namespace: None,
+    };
+
+    let bytes = publisher.serialize(&payload).expect("serialize ok");
+    assert!(!bytes.is_empty());
+}
+
+#[test]
+fn serialize_site() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "topic");
+    let payload = Sample {
+        site: Some(site()),
+        ..sample()
+    };
+    let bytes = publisher.serialize(&payload).expect("serialize ok");
+    assert!(!bytes.is_empty());
+
+    let publisher = publisher.with_encoding(Encoding::Json);
+    let bytes = publisher.serialize(&payload).expect("serialize ok");
+    let record: Value = serde_json::from_slice(&bytes).expect("json");
+    assert_eq!(record["site"]["site_id"], "za-wc-017");
+    assert_eq!(record["site"]["labels"]["operator"], "acme");
+}
+
+#[test]
+fn serialize_clock_flags_and_namespace() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "topic");
+    let payload = Sample {
+        monotonic_ms: 86_400_000,
+        clock_unsynchronized: true,
+        namespace: Some("acme-site17".to_string()),
+        ..sample()
+    };
+    let bytes = publisher.serialize(&payload).expect("serialize ok");
+    assert!(!bytes.is_empty());
+
+    let publisher = publisher.with_encoding(Encoding::Json);
+    let bytes = publisher.serialize(&payload).expect("serialize ok");
+    let record: Value = serde_json::from_slice(&bytes).expect("json");
+    assert_eq!(record["monotonic_ms"], 86_400_000);
+    assert_eq!(record["clock_unsynchronized"], true);
+    assert_eq!(record["namespace"], "acme-site17");
+}
+
+fn sample() -> Sample {
+    Sample {
+        device: Device {
+            ip: "127.0.0.1".to_string(),
+            unit_id: 1,
+            base_address: Some(40_000),
+        },
+        model_id: 103,
+        model_name: "three_phase_inverter".to_string(),
+        start: 40002,
+        registers: vec![1, 2, 3],
+        collected_at_ms: 1_700_000_000,
+        window: None,
+        maintenance: false,
+        baseline: false,
+        sequence: 1,
+        cycle_id: 1,
+        site: None,
+        monotonic_ms: 0,
+        clock_unsynchronized: false,
+        namespace: None,
+    }
+}
+
+fn site() -> Site {
+    Site {
+        site_id: "za-wc-017".to_string(),
+        plant: Some("Darling Solar".to_string()),
+        latitude: Some(-33.38),
+        longitude: Some(18.38),
+        labels: BTreeMap::from([("operator".to_string(), "acme".to_string())]),
+    }
+}
+
+#[test]
+fn serialize_json_encoding() {
+    let publisher =
+        Publisher::new_mock(Publisher::default_schema(), "topic").with_encoding(Encoding::Json);
+    assert_eq!(publisher.encoding(), Encoding::Json);
+
+    let bytes = publisher.serialize(&sample()).expect("serialize ok");
+    let record: Value = serde_json::from_slice(&bytes).expect("json");
+    assert_eq!(record["device"]["base_address"], json!(40_000));
+    assert_eq!(record["registers"], json!([1, 2, 3]));
+    assert_eq!(record["window"], Value::Null);
+
+    let bytes = publisher
+        .serialize_batch(&[sample(), sample()])
+        .expect("serialize ok");
+    let records: Value = serde_json::from_slice(&bytes).expect("json");
+    assert_eq!(records.as_array().map(Vec::len), Some(2));
+
+    // Topic-specific publishers keep the encoding.
+    let daily = publisher.for_topic(Publisher::daily_summary_schema(), "daily");
+    assert_eq!(daily.encoding(), Encoding::Json);
+}
+
+#[derive(Debug, Serialize)]
+struct Event {
+    name: String,
+    at_ms: i64,
+}
+
+fn event_schema() -> Schema {
+    Schema::parse_str(
+        r#"{"type": "record", "name": "Event", "fields": [
+            {"name": "name", "type": "string"},
+            {"name": "at_ms", "type": "long"}
+        ]}"#,
+    )
+    .expect("valid schema")
+}
+
+fn event() -> Event {
+    Event {
+        name: "door_open".to_string(),
+        at_ms: 1_000,
+    }
+}
+
+#[test]
+fn topics_are_serialized_with_their_own_schema() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "telemetry")
+        .with_topic_schema("events", event_schema());
+    assert_eq!(publisher.schema_for("events"), &event_schema());
+    assert_eq!(
+        publisher.schema_for("telemetry"),
+        &Publisher::default_schema()
+    );
+    // Topics without a schema of their own use the publisher's.
+    assert_eq!(publisher.schema_for("routed"), &Publisher::default_schema());
+
+    let bytes = publisher
+        .serialize_for("events", &event())
+        .expect("serialize ok");
+    let reader = Reader::new(&bytes[..]).expect("avro reader");
+    assert_eq!(reader.writer_schema(), &event_schema());
+    assert!(publisher.serialize_for("events", &sample()).is_err());
+    assert!(publisher.serialize_for("telemetry", &event()).is_err());
+    assert!(publisher
+        .serialize_batch_for("routed", &[sample(), sample()])
+        .is_ok());
+
+    // Registered schemas are shared with topic-specific publishers.
+    let daily = publisher.for_topic(Publisher::daily_summary_schema(), "daily");
+    assert_eq!(daily.schema_for("events"), &event_schema());
+    assert_eq!(
+        daily.schema_for("routed"),
+        &Publisher::daily_summary_schema()
+    );
+}
+
+#[tokio::test]
+async fn publish_with_schema_overrides_the_topic_schema() {
+    let publisher =
+        Publisher::new_mock(Publisher::default_schema(), "telemetry").with_encoding(Encoding::Json);
+    assert!(publisher
+        .publish_with_schema("telemetry", &event_schema(), &event())
+        .await
+        .is_ok());
+    // JSON records are checked against the schema of their topic.
+    assert!(matches!(
+        publisher.publish_to("telemetry", &event()).await,
+        Err(PublishError::Schema(_))
+    ));
+
+    let publisher = publisher.with_topic_schema("events", event_schema());
+    assert!(publisher.publish_to("events", &event()).await.is_ok());
+    assert!(publisher.publish_to("telemetry", &sample()).await.is_ok());
+}
+
+#[test]
+fn json_schema_mirrors_avro_schema() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "topic");
+    let schema = publisher.json_schema();
+    assert_eq!(schema["title"], "SunspecTelemetry");
+    assert_eq!(schema["additionalProperties"], false);
+    assert_eq!(schema["properties"]["collected_at_ms"]["type"], "integer");
+    assert_eq!(schema["properties"]["registers"]["items"]["type"], "integer");
+    let required = schema["required"].as_array().expect("required");
+    assert!(required.contains(&json!("baseline")));
+    assert!(required.contains(&json!("sequence")));
+    assert!(required.contains(&json!("cycle_id")));
+
+    let record = serde_json::to_value(sample()).expect("json");
+    assert_eq!(validate_json(&schema, &record), Ok(()));
+
+    let mut missing = record.clone();
+    missing.as_object_mut().expect("object").remove("baseline");
+    assert_eq!(
+        validate_json(&schema, &missing),
+        Err("$: missing property baseline".to_string())
+    );
+
+    let mut out_of_range = record.clone();
+    out_of_range["device"]["unit_id"] = json!(3_000_000_000_u64);
+    assert!(validate_json(&schema, &out_of_range)
+        .unwrap_err()
+        .starts_with("$.device.unit_id"));
+
+    let mut wrong_union = record;
+    wrong_union["window"] = json!("none");
+    assert!(validate_json(&schema, &wrong_union).is_err());
+}
+
+#[test]
+fn encoding_names() {
+    assert_eq!(Encoding::parse("JSON"), Some(Encoding::Json));
+    assert_eq!(Encoding::parse(" avro "), Some(Encoding::Avro));
+    assert_eq!(Encoding::parse("avro_single"), Some(Encoding::AvroSingle));
+    assert_eq!(Encoding::parse("protobuf"), None);
+    assert_eq!(Encoding::default().as_str(), "avro");
+    assert!(!Encoding::AvroSingle.batches());
+
+    assert_eq!(AvroCodec::parse("Zstd"), Some(AvroCodec::Zstd));
+    assert_eq!(AvroCodec::parse("null"), Some(AvroCodec::Null));
+    assert_eq!(AvroCodec::parse("snappy"), None);
+    assert_eq!(AvroCodec::default().as_str(), "deflate");
+}
+
+#[test]
+fn containers_use_the_configured_codec() {
+    let batch: Vec<Sample> = (0..20).map(|_| sample()).collect();
+    let mut sizes = Vec::new();
+    for codec in [AvroCodec::Null, AvroCodec::Deflate, AvroCodec::Zstd] {
+        let publisher =
+            Publisher::new_mock(Publisher::default_schema(), "topic").with_avro_codec(codec);
+        assert_eq!(publisher.avro_codec(), codec);
+        let bytes = publisher.serialize_batch(&batch).expect("serialize ok");
+        let records = Reader::new(&bytes[..]).expect("avro reader").count();
+        assert_eq!(records, batch.len());
+        sizes.push(bytes.len());
+    }
+    assert!(sizes[1] < sizes[0]);
+    assert!(sizes[2] < sizes[0]);
+
+    // Topic-specific publishers keep the codec.
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "topic")
+        .with_avro_codec(AvroCodec::Zstd)
+        .for_topic(Publisher::daily_summary_schema(), "daily");
+    assert_eq!(publisher.avro_codec(), AvroCodec::Zstd);
+}
+
+#[test]
+fn single_object_encoding_carries_the_schema_fingerprint() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "topic")
+        .with_encoding(Encoding::AvroSingle);
+    let single = publisher.serialize(&sample()).expect("serialize ok");
+    let container = Publisher::new_mock(Publisher::default_schema(), "topic")
+        .serialize(&sample())
+        .expect("serialize ok");
+    assert_eq!(single[..2], [0xC3, 0x01]);
+    assert!(single.len() < container.len());
+
+    let reader = GenericSingleObjectReader::new(Publisher::default_schema()).expect("reader");
+    assert!(reader.read_value(&mut &single[..]).is_ok());
+    // One record per message.
+    assert!(publisher.serialize_batch(&[sample(), sample()]).is_err());
+}
+
+#[test]
+fn broker_errors_are_classified_as_retriable_or_fatal() {
+    let classify = |code| PublishError::from_kafka(KafkaError::MessageProduction(code));
+
+    for code in [
+        RDKafkaErrorCode::QueueFull,
+        RDKafkaErrorCode::MessageTimedOut,
+        RDKafkaErrorCode::RequestTimedOut,
+        RDKafkaErrorCode::LeaderNotAvailable,
+        RDKafkaErrorCode::Authentication,
+    ] {
+        let err = classify(code);
+        assert!(matches!(err, PublishError::Retriable(_)), "{code:?}");
+        assert!(!err.is_fatal(), "{code:?}");
+    }
+
+    let too_large = classify(RDKafkaErrorCode::MessageSizeTooLarge);
+    assert!(matches!(too_large, PublishError::Fatal(_)));
+    assert!(too_large.is_fatal() && too_large.is_payload_error());
+
+    for code in [
+        RDKafkaErrorCode::UnknownTopic,
+        RDKafkaErrorCode::UnknownTopicOrPartition,
+        RDKafkaErrorCode::TopicAuthorizationFailed,
+    ] {
+        let err = classify(code);
+        assert!(err.is_fatal(), "{code:?}");
+        assert!(!err.is_payload_error(), "{code:?}");
+    }
+
+    // Errors raised before the broker is involved are fatal as well.
+    assert!(PublishError::Encode("missing field".to_string()).is_fatal());
+    assert!(!PublishError::Blocked.is_fatal());
+}
+
+#[tokio::test]
+async fn oversized_payloads_are_rejected_before_publishing() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "topic");
+    assert_eq!(publisher.max_message_bytes(), DEFAULT_MAX_MESSAGE_BYTES);
+
+    let publisher = publisher.with_max_message_bytes(16);
+    assert!(publisher.publish_bytes("topic", &[0; 16]).await.is_ok());
+    match publisher.publish_bytes("topic", &[0; 17]).await {
+        Err(PublishError::Oversized { bytes, max }) => assert_eq!((bytes, max), (17, 16)),
+        other => panic!("expected an oversized error, got {other:?}"),
+    }
+    // Topic-specific publishers keep the limit.
+    let daily = publisher.for_topic(Publisher::daily_summary_schema(), "daily");
+    assert_eq!(daily.max_message_bytes(), 16);
+}
+
+#[tokio::test]
+async fn closed_gate_blocks_publishes_and_probes() {
+    let gate = SinkGate::new();
+    let publisher =
+        Publisher::new_mock(Publisher::default_schema(), "topic").with_gate(gate.clone());
+    let daily = publisher.for_topic(Publisher::daily_summary_schema(), "daily");
+
+    gate.close();
+    assert!(matches!(
+        publisher.publish_bytes("topic", &[0; 4]).await,
+        Err(PublishError::Blocked)
+    ));
+    assert!(matches!(
+        daily.publish_keyed("daily", "key", &[0; 4]).await,
+        Err(PublishError::Blocked)
+    ));
+    assert!(publisher.probe().await.is_err());
+    assert!(!PublishError::Blocked.is_payload_error());
+
+    gate.open();
+    assert!(publisher.publish_bytes("topic", &[0; 4]).await.is_ok());
+    assert!(publisher.probe().await.is_ok());
+}
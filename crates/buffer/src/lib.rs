@@ -1,15 +1,19 @@
 #![allow(dead_code)]
 
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::sqlite::{SqliteAutoVacuum, SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
 use sqlx::{Row, SqlitePool};
 use thiserror::Error;
-use tracing::info;
+use tracing::{info, instrument};
 
 #[derive(Debug, Clone)]
 pub struct BufferStore {
     pool: SqlitePool,
+    /// The database file, when opened by path rather than `sqlite:` URL.
+    file: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +38,54 @@ pub struct BufferedMessage {
     pub payload: Vec<u8>,
 }
 
+/// A buffered row that could not be published, kept with the reason.
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    pub id: i64,
+    /// Id the row had in `telemetry_queue`.
+    pub buffer_id: i64,
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub error: String,
+    pub created_at_ms: i64,
+}
+
+/// Rows published in a Kafka transaction that is being committed. They
+/// stay queued until the commit is confirmed, see
+/// [`BufferStore::prepare_commit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingCommit {
+    /// Increases with every prepared commit.
+    pub seq: i64,
+    pub ids: Vec<i64>,
+    pub prepared_at_ms: i64,
+}
+
+/// What [`BufferStore::compact`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compaction {
+    /// Free pages returned to the file system.
+    pub freed_pages: i64,
+    /// The file was rebuilt with `VACUUM`; happens once for buffers created
+    /// before incremental vacuum was enabled, when little data is buffered.
+    pub rebuilt: bool,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// A device as last discovered, kept in the `inventory` table. The model
+/// chain and identity block are stored as the JSON the collector wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryEntry {
+    pub ip: String,
+    pub unit_id: u8,
+    pub base_address: Option<u16>,
+    pub models: String,
+    pub identity: Option<String>,
+    pub first_seen_ms: i64,
+    pub last_seen_ms: i64,
+}
+
 #[derive(Debug, Error)]
 pub enum BufferError {
     #[error("sqlx error: {0}")]
@@ -43,9 +95,13 @@ pub enum BufferError {
 impl BufferStore {
     pub async fn new(path: &str) -> Result<Self, BufferError> {
         let url = sqlite_url(path);
+        // Only applies to new files; compact() converts older ones.
+        let options = SqliteConnectOptions::from_str(&url)?
+            .create_if_missing(true)
+            .auto_vacuum(SqliteAutoVacuum::Incremental);
         let pool = SqlitePoolOptions::new()
             .max_connections(5)
-            .connect(&url)
+            .connect_with(options)
             .await?;
 
         sqlx::query("PRAGMA journal_mode = WAL;")
@@ -68,12 +124,54 @@ impl BufferStore {
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_created_at ON telemetry_queue(created_at)")
             .execute(&pool)
             .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS dead_letter (\
+                id INTEGER PRIMARY KEY AUTOINCREMENT,\
+                buffer_id INTEGER NOT NULL,\
+                topic TEXT NOT NULL,\
+                payload BLOB NOT NULL,\
+                error TEXT NOT NULL,\
+                created_at INTEGER NOT NULL\
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        // At most one commit is pending; the row also keeps the last sequence.
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS uplink_commit (\
+                id INTEGER PRIMARY KEY CHECK (id = 1),\
+                seq INTEGER NOT NULL,\
+                ids TEXT,\
+                prepared_at INTEGER\
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        // One row per device, as last discovered.
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS inventory (\
+                ip TEXT NOT NULL,\
+                unit_id INTEGER NOT NULL,\
+                base_address INTEGER,\
+                models TEXT NOT NULL,\
+                identity TEXT,\
+                first_seen INTEGER NOT NULL,\
+                last_seen INTEGER NOT NULL,\
+                PRIMARY KEY (ip, unit_id)\
+            )",
+        )
+        .execute(&pool)
+        .await?;
 
         info!(path = %path, "buffer initialized");
 
-        Ok(Self { pool })
+        let file = (!path.starts_with("sqlite:")).then(|| PathBuf::from(path));
+        Ok(Self { pool, file })
     }
 
+    #[instrument(name = "buffer_enqueue", skip_all, fields(topic = %topic, bytes = payload.len()))]
     pub async fn enqueue(&self, topic: &str, payload: &[u8]) -> Result<(), BufferError> {
         sqlx::query("INSERT INTO telemetry_queue (topic, payload, created_at) VALUES (?, ?, ?)")
             .bind(topic)
@@ -85,6 +183,7 @@ impl BufferStore {
         Ok(())
     }
 
+    #[instrument(name = "buffer_dequeue", skip(self))]
     pub async fn dequeue_batch(&self, limit: i64) -> Result<Vec<BufferedMessage>, BufferError> {
         let rows =
             sqlx::query("SELECT id, topic, payload FROM telemetry_queue ORDER BY id ASC LIMIT ?")
@@ -92,18 +191,84 @@ impl BufferStore {
                 .fetch_all(&self.pool)
                 .await?;
 
-        let messages = rows
-            .into_iter()
-            .map(|row| BufferedMessage {
-                id: row.get::<i64, _>("id"),
-                topic: row.get::<String, _>("topic"),
-                payload: row.get::<Vec<u8>, _>("payload"),
-            })
-            .collect();
+        Ok(to_messages(rows))
+    }
+
+    /// Reads up to `limit` rows with an id above `after_id` without removing
+    /// them, for replaying a buffer left behind by an earlier run.
+    pub async fn read_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<BufferedMessage>, BufferError> {
+        let rows = sqlx::query(
+            "SELECT id, topic, payload FROM telemetry_queue WHERE id > ? ORDER BY id ASC LIMIT ?",
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(to_messages(rows))
+    }
+
+    /// Reads the newest `limit` rows without removing them, returned oldest
+    /// first, for drains that publish recent samples before the backlog.
+    #[instrument(name = "buffer_dequeue_newest", skip(self))]
+    pub async fn dequeue_newest(&self, limit: i64) -> Result<Vec<BufferedMessage>, BufferError> {
+        let rows = sqlx::query(
+            "SELECT id, topic, payload FROM (\
+                SELECT id, topic, payload FROM telemetry_queue ORDER BY id DESC LIMIT ?\
+            ) ORDER BY id ASC",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(to_messages(rows))
+    }
+
+    /// Reads up to `limit` of the oldest rows buffered at or after
+    /// `created_at_ms`, without removing them.
+    #[instrument(name = "buffer_dequeue_since", skip(self))]
+    pub async fn dequeue_since(
+        &self,
+        created_at_ms: i64,
+        limit: i64,
+    ) -> Result<Vec<BufferedMessage>, BufferError> {
+        let rows = sqlx::query(
+            "SELECT id, topic, payload FROM telemetry_queue \
+             WHERE created_at >= ? ORDER BY id ASC LIMIT ?",
+        )
+        .bind(created_at_ms)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(to_messages(rows))
+    }
 
-        Ok(messages)
+    /// Reads up to `limit` of the oldest rows buffered before
+    /// `created_at_ms`, without removing them.
+    #[instrument(name = "buffer_dequeue_before", skip(self))]
+    pub async fn dequeue_before(
+        &self,
+        created_at_ms: i64,
+        limit: i64,
+    ) -> Result<Vec<BufferedMessage>, BufferError> {
+        let rows = sqlx::query(
+            "SELECT id, topic, payload FROM telemetry_queue \
+             WHERE created_at < ? ORDER BY id ASC LIMIT ?",
+        )
+        .bind(created_at_ms)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(to_messages(rows))
     }
 
+    #[instrument(name = "buffer_delete", skip_all, fields(rows = ids.len()))]
     pub async fn delete_batch(&self, ids: &[i64]) -> Result<(), BufferError> {
         if ids.is_empty() {
             return Ok(());
@@ -127,12 +292,330 @@ impl BufferStore {
         Ok(())
     }
 
+    /// Removes every row up to and including `last_id` in one statement, for
+    /// sinks that acknowledge a checkpoint (the last message they accepted)
+    /// instead of individual messages. Returns the number of rows removed.
+    pub async fn truncate_through(&self, last_id: i64) -> Result<u64, BufferError> {
+        let result = sqlx::query("DELETE FROM telemetry_queue WHERE id <= ?")
+            .bind(last_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Removes the oldest `limit` rows, unpublished, to make room when the
+    /// disk runs full. Returns the number of rows removed.
+    #[instrument(name = "buffer_evict", skip(self))]
+    pub async fn evict_oldest(&self, limit: i64) -> Result<u64, BufferError> {
+        let result = sqlx::query(
+            "DELETE FROM telemetry_queue WHERE id IN \
+             (SELECT id FROM telemetry_queue ORDER BY id ASC LIMIT ?)",
+        )
+        .bind(limit)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Moves `message` from the queue to the `dead_letter` table with the
+    /// reason it could not be published, in one transaction.
+    pub async fn dead_letter(
+        &self,
+        message: &BufferedMessage,
+        error: &str,
+    ) -> Result<(), BufferError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO dead_letter (buffer_id, topic, payload, error, created_at) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(message.id)
+        .bind(&message.topic)
+        .bind(&message.payload)
+        .bind(error)
+        .bind(unix_ms())
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM telemetry_queue WHERE id = ?")
+            .bind(message.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Reads up to `limit` dead letters with an id above `after_id`.
+    pub async fn read_dead_letters(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter>, BufferError> {
+        let rows = sqlx::query(
+            "SELECT id, buffer_id, topic, payload, error, created_at FROM dead_letter \
+             WHERE id > ? ORDER BY id ASC LIMIT ?",
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let letters = rows
+            .into_iter()
+            .map(|row| DeadLetter {
+                id: row.get::<i64, _>("id"),
+                buffer_id: row.get::<i64, _>("buffer_id"),
+                topic: row.get::<String, _>("topic"),
+                payload: row.get::<Vec<u8>, _>("payload"),
+                error: row.get::<String, _>("error"),
+                created_at_ms: row.get::<i64, _>("created_at"),
+            })
+            .collect();
+
+        Ok(letters)
+    }
+
+    pub async fn dead_letter_count(&self) -> Result<i64, BufferError> {
+        let row = sqlx::query("SELECT COUNT(*) AS count FROM dead_letter")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.get::<i64, _>("count"))
+    }
+
+    /// Records `ids` as the rows of the commit about to be made, under the
+    /// next sequence number. Replaces a commit still pending.
+    pub async fn prepare_commit(&self, ids: &[i64]) -> Result<PendingCommit, BufferError> {
+        let mut tx = self.pool.begin().await?;
+        let last = sqlx::query("SELECT seq FROM uplink_commit WHERE id = 1")
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|row| row.get::<i64, _>("seq"))
+            .unwrap_or(0);
+        let commit = PendingCommit {
+            seq: last + 1,
+            ids: ids.to_vec(),
+            prepared_at_ms: unix_ms(),
+        };
+        let ids = commit
+            .ids
+            .iter()
+            .map(i64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        sqlx::query(
+            "INSERT INTO uplink_commit (id, seq, ids, prepared_at) VALUES (1, ?, ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET seq = excluded.seq, ids = excluded.ids, \
+             prepared_at = excluded.prepared_at",
+        )
+        .bind(commit.seq)
+        .bind(ids)
+        .bind(commit.prepared_at_ms)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(commit)
+    }
+
+    /// The commit prepared but neither completed nor cancelled, e.g. because
+    /// the process stopped in between.
+    pub async fn pending_commit(&self) -> Result<Option<PendingCommit>, BufferError> {
+        let row = sqlx::query(
+            "SELECT seq, ids, prepared_at FROM uplink_commit WHERE id = 1 AND ids IS NOT NULL",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| PendingCommit {
+            seq: row.get::<i64, _>("seq"),
+            ids: row
+                .get::<String, _>("ids")
+                .split(',')
+                .filter_map(|id| id.parse().ok())
+                .collect(),
+            prepared_at_ms: row.get::<i64, _>("prepared_at"),
+        }))
+    }
+
+    /// Deletes the rows of `commit` once it is confirmed, and clears it, in
+    /// one transaction.
+    pub async fn complete_commit(&self, commit: &PendingCommit) -> Result<(), BufferError> {
+        let mut tx = self.pool.begin().await?;
+        for ids in commit.ids.chunks(DELETE_CHUNK) {
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            let query = format!("DELETE FROM telemetry_queue WHERE id IN ({placeholders})");
+            let mut statement = sqlx::query(&query);
+            for id in ids {
+                statement = statement.bind(id);
+            }
+            statement.execute(&mut *tx).await?;
+        }
+        clear_commit(&mut tx, commit.seq).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Clears `commit` without deleting its rows, which are then published
+    /// again.
+    pub async fn cancel_commit(&self, commit: &PendingCommit) -> Result<(), BufferError> {
+        let mut tx = self.pool.begin().await?;
+        clear_commit(&mut tx, commit.seq).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Stores what discovery found for a device. A device seen before keeps
+    /// its `first_seen_ms`; everything else is replaced.
+    pub async fn save_inventory(&self, entry: &InventoryEntry) -> Result<(), BufferError> {
+        sqlx::query(
+            "INSERT INTO inventory \
+                (ip, unit_id, base_address, models, identity, first_seen, last_seen) \
+             VALUES (?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT (ip, unit_id) DO UPDATE SET \
+                base_address = excluded.base_address,\
+                models = excluded.models,\
+                identity = excluded.identity,\
+                last_seen = excluded.last_seen",
+        )
+        .bind(&entry.ip)
+        .bind(i64::from(entry.unit_id))
+        .bind(entry.base_address.map(i64::from))
+        .bind(&entry.models)
+        .bind(&entry.identity)
+        .bind(entry.first_seen_ms)
+        .bind(entry.last_seen_ms)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Every device ever discovered, by IP and unit ID.
+    pub async fn read_inventory(&self) -> Result<Vec<InventoryEntry>, BufferError> {
+        let rows = sqlx::query(
+            "SELECT ip, unit_id, base_address, models, identity, first_seen, last_seen \
+             FROM inventory ORDER BY ip ASC, unit_id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| InventoryEntry {
+                ip: row.get("ip"),
+                unit_id: row.get::<i64, _>("unit_id") as u8,
+                base_address: row
+                    .get::<Option<i64>, _>("base_address")
+                    .map(|address| address as u16),
+                models: row.get("models"),
+                identity: row.get("identity"),
+                first_seen_ms: row.get("first_seen"),
+                last_seen_ms: row.get("last_seen"),
+            })
+            .collect())
+    }
+
     pub async fn pending_count(&self) -> Result<i64, BufferError> {
         let row = sqlx::query("SELECT COUNT(*) AS count FROM telemetry_queue")
             .fetch_one(&self.pool)
             .await?;
         Ok(row.get::<i64, _>("count"))
     }
+
+    /// Bytes the buffer takes on disk, database file and WAL together.
+    pub async fn disk_size(&self) -> Result<u64, BufferError> {
+        if let Some(ref file) = self.file {
+            let mut wal = file.clone().into_os_string();
+            wal.push("-wal");
+            let mut bytes = 0;
+            for path in [file.clone(), PathBuf::from(wal)] {
+                bytes += tokio::fs::metadata(&path)
+                    .await
+                    .map_or(0, |meta| meta.len());
+            }
+            return Ok(bytes);
+        }
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(&self.pool)
+            .await?;
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(u64::try_from(page_count * page_size).unwrap_or(0))
+    }
+
+    /// Returns the pages of deleted rows to the file system, refreshes the
+    /// query planner's statistics and truncates the WAL. Drained rows leave
+    /// free pages behind, so without this a buffer keeps the size of its
+    /// longest outage.
+    #[instrument(name = "buffer_compact", skip_all)]
+    pub async fn compact(&self) -> Result<Compaction, BufferError> {
+        let bytes_before = self.disk_size().await?;
+        let mut conn = self.pool.acquire().await?;
+        let freed_pages: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+            .fetch_one(&mut *conn)
+            .await?;
+        let auto_vacuum: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+            .fetch_one(&mut *conn)
+            .await?;
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(&mut *conn)
+            .await?;
+        let legacy = auto_vacuum != AUTO_VACUUM_INCREMENTAL;
+        // VACUUM copies every live page while holding the write lock, so it
+        // waits until the backlog is drained.
+        let rebuilt =
+            legacy && freed_pages > 0 && page_count - freed_pages <= REBUILD_MAX_LIVE_PAGES;
+        let freed_pages = if legacy && !rebuilt { 0 } else { freed_pages };
+        if rebuilt {
+            // The mode of an existing file only changes with a full VACUUM.
+            sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query("VACUUM").execute(&mut *conn).await?;
+        } else if freed_pages > 0 {
+            sqlx::query("PRAGMA incremental_vacuum")
+                .execute(&mut *conn)
+                .await?;
+        }
+        sqlx::query("PRAGMA optimize").execute(&mut *conn).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&mut *conn)
+            .await?;
+        drop(conn);
+        Ok(Compaction {
+            freed_pages,
+            rebuilt,
+            bytes_before,
+            bytes_after: self.disk_size().await?,
+        })
+    }
+}
+
+/// `PRAGMA auto_vacuum` value of incremental mode.
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+/// Live pages up to which a file without incremental vacuum is rebuilt,
+/// 16 MiB with the default 4 KiB pages.
+const REBUILD_MAX_LIVE_PAGES: i64 = 4_096;
+
+/// Rows deleted per statement, below SQLite's limit on bound parameters.
+const DELETE_CHUNK: usize = 500;
+
+async fn clear_commit(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    seq: i64,
+) -> Result<(), BufferError> {
+    sqlx::query("UPDATE uplink_commit SET ids = NULL, prepared_at = NULL WHERE id = 1 AND seq = ?")
+        .bind(seq)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+fn to_messages(rows: Vec<SqliteRow>) -> Vec<BufferedMessage> {
+    rows.into_iter()
+        .map(|row| BufferedMessage {
+            id: row.get::<i64, _>("id"),
+            topic: row.get::<String, _>("topic"),
+            payload: row.get::<Vec<u8>, _>("payload"),
+        })
+        .collect()
 }
 
 fn sqlite_url(path: &str) -> String {
@@ -1,7 +1,10 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use buffer::BufferStore;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::SqlitePool;
 
 #[tokio::test]
 async fn buffer_enqueue_dequeue_delete() {
@@ -47,6 +50,236 @@ async fn buffer_delete_empty_is_noop() {
     cleanup_db(&path);
 }
 
+#[tokio::test]
+async fn buffer_read_after_keeps_rows() {
+    let path = temp_db_path("buffer_read_after_keeps_rows");
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("init");
+
+    for payload in [b"one", b"two", b"six"] {
+        store.enqueue("topic", payload).await.expect("enqueue");
+    }
+
+    let first = store.read_after(0, 2).await.expect("read");
+    assert_eq!(first.len(), 2);
+    let rest = store.read_after(first[1].id, 2).await.expect("read");
+    assert_eq!(rest.len(), 1);
+    assert_eq!(rest[0].payload, b"six");
+    assert_eq!(store.pending_count().await.expect("count"), 3);
+
+    drop(store);
+    cleanup_db(&path);
+}
+
+#[tokio::test]
+async fn buffer_reads_newest_or_split_by_age() {
+    let path = temp_db_path("buffer_reads_newest_or_split_by_age");
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("init");
+    for payload in [b"one", b"two", b"six", b"ten"] {
+        store.enqueue("topic", payload).await.expect("enqueue");
+    }
+    // Backdate the first two rows, as if buffered during an outage.
+    let url = format!("sqlite://{}", path.display());
+    let options = SqliteConnectOptions::from_str(&url).expect("options");
+    let pool = SqlitePool::connect_with(options).await.expect("connect");
+    sqlx::query("UPDATE telemetry_queue SET created_at = 1000 WHERE id <= 2")
+        .execute(&pool)
+        .await
+        .expect("backdate");
+    pool.close().await;
+
+    let payloads = |rows: Vec<buffer::BufferedMessage>| -> Vec<Vec<u8>> {
+        rows.into_iter().map(|row| row.payload).collect()
+    };
+    let newest = store.dequeue_newest(3).await.expect("newest");
+    assert_eq!(
+        payloads(newest),
+        vec![b"two".to_vec(), b"six".to_vec(), b"ten".to_vec()]
+    );
+    let live = store.dequeue_since(2_000, 1).await.expect("live");
+    assert_eq!(payloads(live), vec![b"six".to_vec()]);
+    let backlog = store.dequeue_before(2_000, 10).await.expect("backlog");
+    assert_eq!(payloads(backlog), vec![b"one".to_vec(), b"two".to_vec()]);
+    assert_eq!(store.pending_count().await.expect("count"), 4);
+
+    drop(store);
+    cleanup_db(&path);
+}
+
+#[tokio::test]
+async fn buffer_truncate_through_checkpoint() {
+    let path = temp_db_path("buffer_truncate_through_checkpoint");
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("init");
+
+    for payload in [b"one", b"two", b"six"] {
+        store.enqueue("topic", payload).await.expect("enqueue");
+    }
+    let batch = store.dequeue_batch(10).await.expect("dequeue");
+
+    let removed = store.truncate_through(batch[1].id).await.expect("truncate");
+    assert_eq!(removed, 2);
+    let remaining = store.dequeue_batch(10).await.expect("dequeue");
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].payload, b"six");
+
+    // A stale checkpoint removes nothing.
+    let removed = store.truncate_through(batch[0].id).await.expect("truncate");
+    assert_eq!(removed, 0);
+
+    drop(store);
+    cleanup_db(&path);
+}
+
+#[tokio::test]
+async fn buffer_evict_oldest_keeps_the_newest_rows() {
+    let path = temp_db_path("buffer_evict_oldest_keeps_the_newest_rows");
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("init");
+
+    for payload in [b"one", b"two", b"six"] {
+        store.enqueue("topic", payload).await.expect("enqueue");
+    }
+    let removed = store.evict_oldest(2).await.expect("evict");
+    assert_eq!(removed, 2);
+    let remaining = store.dequeue_batch(10).await.expect("dequeue");
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].payload, b"six");
+
+    let removed = store.evict_oldest(10).await.expect("evict");
+    assert_eq!(removed, 1);
+    assert_eq!(store.evict_oldest(10).await.expect("evict"), 0);
+
+    drop(store);
+    cleanup_db(&path);
+}
+
+#[tokio::test]
+async fn buffer_dead_letter_moves_the_row() {
+    let path = temp_db_path("buffer_dead_letter_moves_the_row");
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("init");
+
+    store.enqueue("topic", b"good").await.expect("enqueue");
+    store.enqueue("topic", b"{broken").await.expect("enqueue");
+    let batch = store.dequeue_batch(10).await.expect("dequeue");
+
+    store
+        .dead_letter(&batch[1], "json decode failed")
+        .await
+        .expect("dead letter");
+    assert_eq!(store.pending_count().await.expect("count"), 1);
+    assert_eq!(store.dead_letter_count().await.expect("count"), 1);
+
+    let letters = store.read_dead_letters(0, 10).await.expect("read");
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].buffer_id, batch[1].id);
+    assert_eq!(letters[0].topic, "topic");
+    assert_eq!(letters[0].payload, b"{broken");
+    assert_eq!(letters[0].error, "json decode failed");
+    assert!(store
+        .read_dead_letters(letters[0].id, 10)
+        .await
+        .expect("read")
+        .is_empty());
+
+    drop(store);
+    cleanup_db(&path);
+}
+
+#[tokio::test]
+async fn buffer_commit_bookkeeping() {
+    let path = temp_db_path("buffer_commit_bookkeeping");
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("init");
+
+    for payload in [b"a", b"b", b"c"] {
+        store.enqueue("topic", payload).await.expect("enqueue");
+    }
+    let batch = store.dequeue_batch(10).await.expect("dequeue");
+    assert!(store.pending_commit().await.expect("pending").is_none());
+
+    // A cancelled commit keeps its rows queued.
+    let first = store.prepare_commit(&[batch[0].id]).await.expect("prepare");
+    assert_eq!(first.seq, 1);
+    store.cancel_commit(&first).await.expect("cancel");
+    assert!(store.pending_commit().await.expect("pending").is_none());
+    assert_eq!(store.pending_count().await.expect("count"), 3);
+
+    // A pending commit survives a restart and is completed afterwards.
+    let ids = [batch[0].id, batch[1].id];
+    let second = store.prepare_commit(&ids).await.expect("prepare");
+    assert_eq!(second.seq, 2);
+    drop(store);
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("reopen");
+    let pending = store.pending_commit().await.expect("pending").expect("commit");
+    assert_eq!(pending, second);
+    store.complete_commit(&pending).await.expect("complete");
+    assert!(store.pending_commit().await.expect("pending").is_none());
+    let remaining = store.dequeue_batch(10).await.expect("dequeue");
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].payload, b"c");
+    assert_eq!(store.prepare_commit(&[]).await.expect("prepare").seq, 3);
+
+    drop(store);
+    cleanup_db(&path);
+}
+
+#[tokio::test]
+async fn buffer_compact_returns_drained_pages() {
+    let path = temp_db_path("buffer_compact_returns_drained_pages");
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("init");
+    let last_id = fill(&store, 2_000).await;
+    store.truncate_through(last_id).await.expect("truncate");
+
+    let compaction = store.compact().await.expect("compact");
+    assert!(compaction.freed_pages > 0);
+    assert!(!compaction.rebuilt);
+    assert!(compaction.bytes_after < compaction.bytes_before);
+    assert_eq!(store.disk_size().await.expect("size"), compaction.bytes_after);
+
+    let again = store.compact().await.expect("compact");
+    assert_eq!(again.freed_pages, 0);
+
+    drop(store);
+    cleanup_db(&path);
+}
+
+#[tokio::test]
+async fn buffer_compact_converts_old_files_once() {
+    let path = temp_db_path("buffer_compact_converts_old_files_once");
+    // A file created before incremental vacuum was enabled.
+    let options = SqliteConnectOptions::from_str(&format!("sqlite://{}", path.display()))
+        .expect("options")
+        .create_if_missing(true);
+    let pool = SqlitePool::connect_with(options).await.expect("connect");
+    sqlx::query("CREATE TABLE legacy (id INTEGER)")
+        .execute(&pool)
+        .await
+        .expect("create");
+    pool.close().await;
+
+    let store = BufferStore::new(path.to_str().expect("path")).await.expect("init");
+    let last_id = fill(&store, 1_000).await;
+    store.truncate_through(last_id).await.expect("truncate");
+    let compaction = store.compact().await.expect("compact");
+    assert!(compaction.rebuilt);
+    assert!(compaction.bytes_after < compaction.bytes_before);
+
+    let last_id = fill(&store, 1_000).await;
+    store.truncate_through(last_id).await.expect("truncate");
+    let compaction = store.compact().await.expect("compact");
+    assert!(!compaction.rebuilt);
+    assert!(compaction.freed_pages > 0);
+
+    drop(store);
+    cleanup_db(&path);
+}
+
+/// Buffers `rows` 1 KiB payloads and returns the last id.
+async fn fill(store: &BufferStore, rows: usize) -> i64 {
+    for _ in 0..rows {
+        store.enqueue("topic-a", &[7u8; 1024]).await.expect("enqueue");
+    }
+    let batch = store.read_after(0, rows as i64).await.expect("read");
+    batch.last().expect("rows").id
+}
+
 fn temp_db_path(prefix: &str) -> PathBuf {
     let mut path = std::env::temp_dir();
     let pid = std::process::id();
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use poller_actor::{PointStats, PollSample, SampleWindow};
+
+use crate::model_map::ModelMaps;
+
+/// (ip, unit id, model id, model start) of a polled register block.
+type BlockKey = (String, u8, u16, u16);
+
+/// Late reads (channel latency) still land in their window if it is closed
+/// this long after its end.
+const FLUSH_GRACE_MS: u64 = 2_000;
+
+/// Aggregation window length for one SunSpec model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelWindow {
+    pub model_id: u16,
+    pub window_ms: u64,
+}
+
+#[derive(Debug)]
+struct PointAccumulator {
+    name: String,
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Debug)]
+struct OpenWindow {
+    start_ms: u64,
+    end_ms: u64,
+    last: PollSample,
+    samples: u64,
+    points: Vec<PointAccumulator>,
+}
+
+impl OpenWindow {
+    fn new(start_ms: u64, window_ms: u64, sample: PollSample, maps: &ModelMaps) -> Self {
+        let mut window = Self {
+            start_ms,
+            end_ms: start_ms + window_ms,
+            last: sample,
+            samples: 0,
+            points: Vec::new(),
+        };
+        window.fold_last(maps);
+        window
+    }
+
+    fn push(&mut self, sample: PollSample, maps: &ModelMaps) {
+        self.last = sample;
+        self.fold_last(maps);
+    }
+
+    fn fold_last(&mut self, maps: &ModelMaps) {
+        self.samples += 1;
+        let points = maps.points(&self.last);
+        for (name, value) in points {
+            match self.points.iter_mut().find(|point| point.name == name) {
+                Some(point) => {
+                    point.min = point.min.min(value);
+                    point.max = point.max.max(value);
+                    point.sum += value;
+                    point.count += 1;
+                }
+                None => self.points.push(PointAccumulator {
+                    name,
+                    min: value,
+                    max: value,
+                    sum: value,
+                    count: 1,
+                }),
+            }
+        }
+    }
+
+    fn close(self) -> PollSample {
+        let points = self
+            .points
+            .into_iter()
+            .map(|point| PointStats {
+                name: point.name,
+                min: point.min,
+                avg: point.sum / point.count as f64,
+                max: point.max,
+            })
+            .collect();
+        PollSample {
+            window: Some(SampleWindow {
+                start_ms: self.start_ms,
+                end_ms: self.end_ms,
+                samples: self.samples,
+                points,
+            }),
+            ..self.last
+        }
+    }
+}
+
+/// Folds the reads of configured models into fixed, clock-aligned windows and
+/// emits one sample per window carrying the last read plus min/avg/max of the
+/// decoded points. Models without a window pass through unchanged.
+#[derive(Debug, Default)]
+pub struct WindowAggregator {
+    windows: HashMap<u16, u64>,
+    open: HashMap<BlockKey, OpenWindow>,
+    maps: ModelMaps,
+}
+
+impl WindowAggregator {
+    pub fn new(windows: &[ModelWindow]) -> Self {
+        Self {
+            windows: windows
+                .iter()
+                .map(|window| (window.model_id, window.window_ms))
+                .collect(),
+            open: HashMap::new(),
+            maps: ModelMaps::default(),
+        }
+    }
+
+    /// Points of mapped models are decoded as `maps` configures them.
+    pub fn with_model_maps(mut self, maps: ModelMaps) -> Self {
+        self.maps = maps;
+        self
+    }
+
+    /// Samples ready for buffering after `sample` arrived: the sample itself
+    /// for models that are not aggregated and for baseline snapshots,
+    /// otherwise any window it closed.
+    pub fn push(&mut self, sample: PollSample) -> Vec<PollSample> {
+        let Some(&window_ms) = self
+            .windows
+            .get(&sample.model_id)
+            .filter(|_| !sample.baseline)
+        else {
+            return vec![sample];
+        };
+        let start_ms = sample.collected_at_ms - sample.collected_at_ms % window_ms;
+        let key = (
+            sample.device.ip.clone(),
+            sample.device.unit_id,
+            sample.model_id,
+            sample.start,
+        );
+
+        let mut ready = Vec::new();
+        match self.open.remove(&key) {
+            Some(mut open) if open.start_ms == start_ms => {
+                open.push(sample, &self.maps);
+                self.open.insert(key, open);
+            }
+            previous => {
+                ready.extend(previous.map(OpenWindow::close));
+                self.open.insert(
+                    key,
+                    OpenWindow::new(start_ms, window_ms, sample, &self.maps),
+                );
+            }
+        }
+        ready
+    }
+
+    /// Closes windows that ended before `now_ms` (minus a short grace), so a
+    /// device that stops answering still gets its last window published.
+    pub fn flush_expired(&mut self, now_ms: u64) -> Vec<PollSample> {
+        let expired: Vec<BlockKey> = self
+            .open
+            .iter()
+            .filter(|(_, open)| open.end_ms + FLUSH_GRACE_MS <= now_ms)
+            .map(|(key, _)| key.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|key| self.open.remove(&key))
+            .map(OpenWindow::close)
+            .collect()
+    }
+
+    /// Closes every open window, partial ones included (used on shutdown).
+    pub fn flush_all(&mut self) -> Vec<PollSample> {
+        self.open.drain().map(|(_, open)| open.close()).collect()
+    }
+}
+
+/// Window statistics of `name`, when `sample` is an aggregated one.
+pub fn window_point<'a>(sample: &'a PollSample, name: &str) -> Option<&'a PointStats> {
+    sample
+        .window
+        .as_ref()?
+        .points
+        .iter()
+        .find(|point| point.name == name)
+}
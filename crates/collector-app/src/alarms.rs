@@ -0,0 +1,94 @@
+use std::collections::{BTreeSet, HashMap};
+
+use poller_actor::PollSample;
+use serde::Serialize;
+use sunspec_parser::decode_status;
+use types::DeviceIdentity;
+
+/// Device (`ip`, `unit_id`) and model an alarm set belongs to.
+type AlarmKey = (String, u8, u16);
+/// Active alarms as (point, event) pairs.
+type ActiveAlarms = BTreeSet<(&'static str, String)>;
+
+/// Event name under the state point while the device is in its fault state.
+pub const FAULT_EVENT: &str = "FAULT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlarmTransition {
+    Raised,
+    Cleared,
+}
+
+/// A fault state or event bit of one device that was raised or cleared.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlarmRecord {
+    pub device: DeviceIdentity,
+    pub model_id: u16,
+    /// `St`/`InvSt`/`State` for the fault state, `Evt1`, `Evt2` or `Alrm`
+    /// for events.
+    pub point: String,
+    /// Event flag name (`GROUND_FAULT`, ...) or [`FAULT_EVENT`].
+    pub event: String,
+    pub transition: AlarmTransition,
+    /// Symbol of the operating state when the transition was seen.
+    pub state: Option<String>,
+    pub collected_at_ms: u64,
+    /// Seen during a maintenance window.
+    pub maintenance: bool,
+}
+
+/// Turns the state and event bits of inverter and battery samples (models
+/// 101-103, 701 and 802) into raised/cleared transitions. The first sample of a device raises
+/// whatever is active, so alarms standing at startup are reported too.
+#[derive(Debug, Default)]
+pub struct AlarmTracker {
+    active: HashMap<AlarmKey, ActiveAlarms>,
+}
+
+impl AlarmTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transitions since the previous sample of the same device and model,
+    /// raised before cleared. Samples of other models yield nothing.
+    pub fn observe(&mut self, sample: &PollSample) -> Vec<AlarmRecord> {
+        let Some(status) = decode_status(sample.model_id, &sample.registers) else {
+            return Vec::new();
+        };
+        let mut now: ActiveAlarms = status
+            .events
+            .into_iter()
+            .map(|event| (event.point, event.name))
+            .collect();
+        if status.fault {
+            now.insert((status.state_point, FAULT_EVENT.to_string()));
+        }
+
+        let key = (
+            sample.device.ip.clone(),
+            sample.device.unit_id,
+            sample.model_id,
+        );
+        let before = self.active.insert(key, now.clone()).unwrap_or_default();
+        let record = |(point, event): &(&'static str, String), transition| AlarmRecord {
+            device: sample.device.clone(),
+            model_id: sample.model_id,
+            point: point.to_string(),
+            event: event.clone(),
+            transition,
+            state: status.state_symbol.clone(),
+            collected_at_ms: sample.collected_at_ms,
+            maintenance: sample.maintenance,
+        };
+        now.difference(&before)
+            .map(|alarm| record(alarm, AlarmTransition::Raised))
+            .chain(
+                before
+                    .difference(&now)
+                    .map(|alarm| record(alarm, AlarmTransition::Cleared)),
+            )
+            .collect()
+    }
+}
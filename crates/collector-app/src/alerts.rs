@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use poller_actor::PollSample;
+use serde::Serialize;
+use types::DeviceIdentity;
+
+use crate::alarms::AlarmTransition;
+
+/// When an [`AlertRule`] raises; the `*PerMin` conditions compare the change
+/// between two samples of the same block, in point units per minute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertCondition {
+    Above(f64),
+    Below(f64),
+    RisingPerMin(f64),
+    FallingPerMin(f64),
+}
+
+impl AlertCondition {
+    pub fn limit(self) -> f64 {
+        match self {
+            Self::Above(limit)
+            | Self::Below(limit)
+            | Self::RisingPerMin(limit)
+            | Self::FallingPerMin(limit) => limit,
+        }
+    }
+
+    pub fn is_rate(self) -> bool {
+        matches!(self, Self::RisingPerMin(_) | Self::FallingPerMin(_))
+    }
+
+    /// Whether `measured` is past the limit moved back by `margin`: 0 to
+    /// raise, the deadband to stay raised.
+    fn exceeds(self, measured: f64, margin: f64) -> bool {
+        match self {
+            Self::Above(limit) | Self::RisingPerMin(limit) => measured > limit - margin,
+            Self::Below(limit) => measured < limit + margin,
+            Self::FallingPerMin(limit) => -measured > limit - margin,
+        }
+    }
+}
+
+/// A threshold on one decoded point, e.g. DC voltage above 950 V.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub name: String,
+    /// Decoded point name (`DCV`, `TmpCab`, or a configured point).
+    pub point: String,
+    /// Only blocks of this model; None matches every model with the point.
+    pub model_id: Option<u16>,
+    pub condition: AlertCondition,
+    /// How far back past the limit the value has to come before a raised
+    /// alert clears, so a value hovering at the limit does not flap.
+    pub deadband: f64,
+}
+
+/// A threshold alert of one device that was raised or cleared.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertRecord {
+    pub rule: String,
+    pub device: DeviceIdentity,
+    pub model_id: u16,
+    pub point: String,
+    pub transition: AlarmTransition,
+    /// The point value, or its change per minute for rate rules.
+    pub value: f64,
+    pub limit: f64,
+    pub collected_at_ms: u64,
+    /// Seen during a maintenance window.
+    pub maintenance: bool,
+}
+
+/// Device (`ip`, `unit_id`), model and index of the rule.
+type RuleKey = (String, u8, u16, usize);
+
+#[derive(Debug, Default)]
+struct RuleState {
+    /// Previous value and when it was read, for rate rules.
+    last: Option<(f64, u64)>,
+    /// The record that raised the alert, while it is active.
+    raised: Option<AlertRecord>,
+}
+
+/// Evaluates the configured rules on the decoded points of every sample and
+/// turns limit crossings into raised/cleared transitions. Like alarms, the
+/// first sample of a device raises what is already over the limit.
+#[derive(Debug, Default)]
+pub struct AlertTracker {
+    rules: Vec<AlertRule>,
+    states: HashMap<RuleKey, RuleState>,
+}
+
+impl AlertTracker {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            states: HashMap::new(),
+        }
+    }
+
+    /// Transitions caused by `sample`, whose decoded points are `points`.
+    pub fn observe(&mut self, sample: &PollSample, points: &[(String, f64)]) -> Vec<AlertRecord> {
+        let mut records = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule
+                .model_id
+                .is_some_and(|model_id| model_id != sample.model_id)
+            {
+                continue;
+            }
+            let Some(&(_, value)) = points.iter().find(|(name, _)| *name == rule.point) else {
+                continue;
+            };
+            let key = (
+                sample.device.ip.clone(),
+                sample.device.unit_id,
+                sample.model_id,
+                index,
+            );
+            let state = self.states.entry(key).or_default();
+            let at_ms = sample.collected_at_ms;
+            let measured = if rule.condition.is_rate() {
+                let previous = state.last.replace((value, at_ms));
+                match previous {
+                    Some((before, before_ms)) if at_ms > before_ms => {
+                        (value - before) / ((at_ms - before_ms) as f64 / 60_000.0)
+                    }
+                    _ => continue,
+                }
+            } else {
+                value
+            };
+            let record = |transition| AlertRecord {
+                rule: rule.name.clone(),
+                device: sample.device.clone(),
+                model_id: sample.model_id,
+                point: rule.point.clone(),
+                transition,
+                value: measured,
+                limit: rule.condition.limit(),
+                collected_at_ms: at_ms,
+                maintenance: sample.maintenance,
+            };
+            if state.raised.is_none() && rule.condition.exceeds(measured, 0.0) {
+                let raised = record(AlarmTransition::Raised);
+                state.raised = Some(raised.clone());
+                records.push(raised);
+            } else if state.raised.is_some() && !rule.condition.exceeds(measured, rule.deadband) {
+                state.raised = None;
+                records.push(record(AlarmTransition::Cleared));
+            }
+        }
+        records
+    }
+
+    /// Alerts raised and not cleared yet, oldest first.
+    pub fn active(&self) -> Vec<AlertRecord> {
+        let mut active: Vec<AlertRecord> = self
+            .states
+            .values()
+            .filter_map(|state| state.raised.clone())
+            .collect();
+        active.sort_by(|a, b| {
+            (a.collected_at_ms, &a.device, &a.rule).cmp(&(b.collected_at_ms, &b.device, &b.rule))
+        });
+        active
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use poller_actor::PollSample;
+use sunspec_parser::decode_inverter;
+use types::DeviceIdentity;
+
+use crate::aggregate::window_point;
+
+/// Devices expected to produce alike (same orientation, same irradiance).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceGroup {
+    pub name: String,
+    /// Device IPs in the group.
+    pub devices: Vec<String>,
+}
+
+/// Latest AC power of one device in a group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerReading {
+    pub device: DeviceIdentity,
+    pub power_w: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnderperformanceEvent {
+    pub group: String,
+    pub detector: &'static str,
+    pub device: DeviceIdentity,
+    pub power_w: f64,
+    /// Mean power of the other devices in the group.
+    pub peer_mean_w: f64,
+    /// How far below the peer mean the device is, in percent.
+    pub deficit_pct: f64,
+    pub z_score: f64,
+}
+
+/// Compares the devices of one group at one point in time.
+pub trait AnomalyDetector: Send {
+    fn name(&self) -> &'static str;
+
+    fn evaluate(&mut self, group: &str, readings: &[PowerReading]) -> Vec<UnderperformanceEvent>;
+}
+
+/// Flags a device whose power is at least `min_deficit_pct` below the mean
+/// of its peers and `z_threshold` standard deviations below it. Peers are
+/// the other devices of the group, so one bad inverter does not drag down
+/// the reference it is compared against.
+#[derive(Debug, Clone)]
+pub struct ZScoreDetector {
+    pub z_threshold: f64,
+    pub min_deficit_pct: f64,
+    /// Smallest number of peers a device needs to be judged.
+    pub min_peers: usize,
+}
+
+impl ZScoreDetector {
+    pub fn new(z_threshold: f64, min_deficit_pct: f64) -> Self {
+        Self {
+            z_threshold,
+            min_deficit_pct,
+            min_peers: 2,
+        }
+    }
+}
+
+impl AnomalyDetector for ZScoreDetector {
+    fn name(&self) -> &'static str {
+        "zscore"
+    }
+
+    fn evaluate(&mut self, group: &str, readings: &[PowerReading]) -> Vec<UnderperformanceEvent> {
+        if readings.len() < self.min_peers + 1 {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        for (index, reading) in readings.iter().enumerate() {
+            let peers: Vec<f64> = readings
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, peer)| peer.power_w)
+                .collect();
+            let count = peers.len() as f64;
+            let mean = peers.iter().sum::<f64>() / count;
+            if mean <= 0.0 {
+                continue;
+            }
+            let variance = peers
+                .iter()
+                .map(|power| (power - mean).powi(2))
+                .sum::<f64>()
+                / count;
+            let std_dev = variance.sqrt();
+            let z_score = if std_dev > f64::EPSILON {
+                (reading.power_w - mean) / std_dev
+            } else if reading.power_w < mean {
+                f64::NEG_INFINITY
+            } else {
+                0.0
+            };
+            let deficit_pct = (mean - reading.power_w) / mean * 100.0;
+
+            if deficit_pct >= self.min_deficit_pct && z_score <= -self.z_threshold {
+                events.push(UnderperformanceEvent {
+                    group: group.to_string(),
+                    detector: self.name(),
+                    device: reading.device.clone(),
+                    power_w: reading.power_w,
+                    peer_mean_w: mean,
+                    deficit_pct,
+                    z_score,
+                });
+            }
+        }
+        events
+    }
+}
+
+/// Tracks the latest inverter power per device and runs the registered
+/// detectors over each group. Without configured groups every device is
+/// compared against every other one.
+pub struct AnomalyStage {
+    groups: Vec<DeviceGroup>,
+    detectors: Vec<Box<dyn AnomalyDetector>>,
+    latest: HashMap<(String, u8), (PowerReading, u64)>,
+    max_age_ms: u64,
+    min_group_power_w: f64,
+}
+
+impl AnomalyStage {
+    /// Readings older than `max_age_ms` are left out, so only devices polled
+    /// at about the same time (same irradiance) are compared. Groups whose
+    /// mean power is below `min_group_power_w` (night, heavy overcast) are skipped.
+    pub fn new(groups: Vec<DeviceGroup>, max_age_ms: u64, min_group_power_w: f64) -> Self {
+        Self {
+            groups,
+            detectors: Vec::new(),
+            latest: HashMap::new(),
+            max_age_ms,
+            min_group_power_w,
+        }
+    }
+
+    pub fn with_detector(mut self, detector: impl AnomalyDetector + 'static) -> Self {
+        self.detectors.push(Box::new(detector));
+        self
+    }
+
+    /// Aggregated samples contribute their window average.
+    pub fn record(&mut self, sample: &PollSample) {
+        let window_avg = window_point(sample, "W").map(|point| point.avg);
+        let Some(power_w) = window_avg.or_else(|| {
+            decode_inverter(sample.model_id, &sample.registers).and_then(|reading| reading.power_w)
+        }) else {
+            return;
+        };
+        self.latest.insert(
+            (sample.device.ip.clone(), sample.device.unit_id),
+            (
+                PowerReading {
+                    device: sample.device.clone(),
+                    power_w,
+                },
+                sample.collected_at_ms,
+            ),
+        );
+    }
+
+    pub fn evaluate(&mut self, now_ms: u64) -> Vec<UnderperformanceEvent> {
+        let max_age_ms = self.max_age_ms;
+        self.latest
+            .retain(|_, (_, at_ms)| now_ms.saturating_sub(*at_ms) <= max_age_ms);
+
+        let groups: Vec<(String, Vec<PowerReading>)> = if self.groups.is_empty() {
+            vec![("all".to_string(), self.readings(|_| true))]
+        } else {
+            self.groups
+                .iter()
+                .map(|group| {
+                    let readings = self.readings(|ip| group.devices.iter().any(|d| d == ip));
+                    (group.name.clone(), readings)
+                })
+                .collect()
+        };
+
+        let mut events = Vec::new();
+        for (name, readings) in groups {
+            if readings.is_empty() {
+                continue;
+            }
+            let mean = readings.iter().map(|r| r.power_w).sum::<f64>() / readings.len() as f64;
+            if mean < self.min_group_power_w {
+                continue;
+            }
+            for detector in &mut self.detectors {
+                events.extend(detector.evaluate(&name, &readings));
+            }
+        }
+        events
+    }
+
+    fn readings(&self, member: impl Fn(&str) -> bool) -> Vec<PowerReading> {
+        let mut readings: Vec<PowerReading> = self
+            .latest
+            .values()
+            .filter(|(reading, _)| member(&reading.device.ip))
+            .map(|(reading, _)| reading.clone())
+            .collect();
+        readings.sort_by(|a, b| {
+            (&a.device.ip, a.device.unit_id).cmp(&(&b.device.ip, b.device.unit_id))
+        });
+        readings
+    }
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+/// Models that give a device its class when no profile names one. A device
+/// with a meter model is a meter, whatever else it implements.
+const METER_MODELS: &[u16] = &[201, 202, 203, 204, 211, 212, 213, 214];
+const INVERTER_MODELS: &[u16] = &[101, 102, 103, 111, 112, 113];
+const STORAGE_MODELS: &[u16] = &[124, 802, 803];
+
+/// `meter`, `inverter` or `storage` by the models a device implements.
+pub fn device_class(models: &[u16]) -> Option<&'static str> {
+    let implements = |class: &[u16]| models.iter().any(|model| class.contains(model));
+    if implements(METER_MODELS) {
+        Some("meter")
+    } else if implements(INVERTER_MODELS) {
+        Some("inverter")
+    } else if implements(STORAGE_MODELS) {
+        Some("storage")
+    } else {
+        None
+    }
+}
+
+/// Order in which device classes start polling when the collector starts,
+/// so the devices listed first are polled and published first.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BootOrder {
+    pub classes: Vec<String>,
+    /// Time between the start of one class and the next.
+    pub step: Duration,
+}
+
+impl BootOrder {
+    /// How long a device of `class` waits before its first poll: one step
+    /// per class ahead of it. Devices of classes not listed, or without a
+    /// class, start one step after the last listed class.
+    pub fn delay(&self, class: Option<&str>) -> Duration {
+        if self.classes.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = class
+            .and_then(|class| {
+                self.classes
+                    .iter()
+                    .position(|listed| listed.eq_ignore_ascii_case(class))
+            })
+            .unwrap_or(self.classes.len());
+        self.step.saturating_mul(rank as u32)
+    }
+}
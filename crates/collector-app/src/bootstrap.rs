@@ -0,0 +1,71 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::net::SocketAddr;
+
+use tokio::net::lookup_host;
+
+/// Port librdkafka assumes for bootstrap servers listed without one.
+pub const DEFAULT_KAFKA_PORT: u16 = 9092;
+
+/// Resolves every bootstrap server of a librdkafka broker list
+/// (`host:port,host:port`, optionally with a `PLAINTEXT://`-style prefix).
+/// Fails when any server does not resolve, so a transient DNS error never
+/// looks like a changed cluster.
+pub async fn resolve_brokers(brokers: &str) -> io::Result<BTreeSet<SocketAddr>> {
+    let mut addrs = BTreeSet::new();
+    for broker in brokers.split(',').map(str::trim).filter(|b| !b.is_empty()) {
+        let server = broker
+            .split_once("://")
+            .map_or(broker, |(_, server)| server);
+        let resolved: Vec<SocketAddr> = if has_port(server) {
+            lookup_host(server).await?.collect()
+        } else {
+            lookup_host((server, DEFAULT_KAFKA_PORT)).await?.collect()
+        };
+        if resolved.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{server} resolved to no addresses"),
+            ));
+        }
+        addrs.extend(resolved);
+    }
+    Ok(addrs)
+}
+
+fn has_port(server: &str) -> bool {
+    server.rsplit_once(':').is_some_and(|(host, port)| {
+        port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']'))
+    })
+}
+
+/// Addresses the bootstrap servers resolved to last time. librdkafka only
+/// resolves them when the producer is created, so a cluster moved behind the
+/// same names is picked up by rebuilding the producer when they change.
+#[derive(Debug, Default)]
+pub struct BootstrapWatch {
+    last: Option<BTreeSet<SocketAddr>>,
+}
+
+impl BootstrapWatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `resolved` differs from the last recorded resolution. Nothing
+    /// has changed before the first one.
+    pub fn changed(&self, resolved: &BTreeSet<SocketAddr>) -> bool {
+        self.last.as_ref().is_some_and(|last| last != resolved)
+    }
+
+    /// Keeps `resolved` as the addresses the current producer was built with.
+    pub fn record(&mut self, resolved: BTreeSet<SocketAddr>) {
+        self.last = Some(resolved);
+    }
+
+    /// Forgets the last resolution, e.g. after the producer was rebuilt for
+    /// another reason.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
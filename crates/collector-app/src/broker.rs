@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+
+use avro_kafka::{BrokerHealth, PublishError};
+use serde::Serialize;
+
+/// Whether the uplink can reach the Kafka cluster.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokerState {
+    /// Nothing was published or probed yet.
+    #[default]
+    Unknown,
+    Up,
+    Down,
+}
+
+/// Connection state of the uplink as served by `/api/broker`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BrokerReport {
+    pub state: BrokerState,
+    /// When the current state was entered.
+    pub since_ms: Option<u64>,
+    pub last_probe_ms: Option<u64>,
+    pub last_probe_latency_ms: Option<u64>,
+    /// Brokers in the last successful probe.
+    pub brokers: usize,
+    /// Why the last probe failed, while down.
+    pub error: Option<String>,
+    /// Times the cluster came back after being down.
+    pub reconnects: u64,
+}
+
+/// Shared between the uplink, which records probes and publishes, and the
+/// admin API.
+#[derive(Debug, Clone, Default)]
+pub struct BrokerStatus {
+    report: Arc<Mutex<BrokerReport>>,
+}
+
+impl BrokerStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self) -> BrokerState {
+        self.lock().state
+    }
+
+    pub fn snapshot(&self) -> BrokerReport {
+        self.lock().clone()
+    }
+
+    /// Records the result of [`avro_kafka::Publisher::probe`]. Returns the
+    /// previous state when this one differs.
+    pub fn probed(
+        &self,
+        result: &Result<BrokerHealth, PublishError>,
+        now_ms: u64,
+    ) -> Option<BrokerState> {
+        let mut report = self.lock();
+        report.last_probe_ms = Some(now_ms);
+        match result {
+            Ok(health) => {
+                report.last_probe_latency_ms = Some(health.latency_ms);
+                report.brokers = health.brokers;
+                report.error = None;
+                transition(&mut report, BrokerState::Up, now_ms)
+            }
+            Err(err) => {
+                report.last_probe_latency_ms = None;
+                report.error = Some(err.to_string());
+                transition(&mut report, BrokerState::Down, now_ms)
+            }
+        }
+    }
+
+    /// A delivered payload shows the cluster is up as well as a probe does.
+    pub fn delivered(&self, now_ms: u64) -> Option<BrokerState> {
+        let mut report = self.lock();
+        report.error = None;
+        transition(&mut report, BrokerState::Up, now_ms)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BrokerReport> {
+        self.report
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn transition(report: &mut BrokerReport, state: BrokerState, now_ms: u64) -> Option<BrokerState> {
+    let previous = report.state;
+    if previous == state {
+        return None;
+    }
+    if previous == BrokerState::Down && state == BrokerState::Up {
+        report.reconnects += 1;
+    }
+    report.state = state;
+    report.since_ms = Some(now_ms);
+    Some(previous)
+}
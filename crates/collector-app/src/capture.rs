@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use metrics::counter;
+use modbus_client::{CaptureTap, Exchange};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// Name of the capture file being written; rotated files get `.1`, `.2`, ...
+pub const CAPTURE_FILE: &str = "modbus-capture.jsonl";
+/// Exchanges waiting for the writer; further ones are dropped.
+const CHANNEL_CAPACITY: usize = 4_096;
+
+/// The `[capture]` section: where Modbus exchanges of devices with capture
+/// switched on through the admin API are written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureSettings {
+    pub dir: String,
+    /// Size at which the capture file is rotated.
+    pub max_file_bytes: u64,
+    /// Rotated files kept besides the one being written.
+    pub max_files: usize,
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            dir: "sunspec-captures".to_string(),
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+/// Capture taps by poller ID (`ip:unit_id`). A device keeps its tap across
+/// poller restarts, so capture stays on until it is switched off.
+#[derive(Debug, Clone)]
+pub struct CaptureRegistry {
+    taps: Arc<Mutex<HashMap<String, CaptureTap>>>,
+    sender: mpsc::Sender<Exchange>,
+}
+
+impl CaptureRegistry {
+    /// A registry and the receiver of every exchange its taps capture.
+    pub fn new() -> (Self, mpsc::Receiver<Exchange>) {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let registry = Self {
+            taps: Arc::default(),
+            sender,
+        };
+        (registry, receiver)
+    }
+
+    /// The tap for the poller `id`, created switched off on first use.
+    pub fn tap(&self, id: &str) -> CaptureTap {
+        self.lock()
+            .entry(id.to_string())
+            .or_insert_with(|| CaptureTap::new(self.sender.clone()))
+            .clone()
+    }
+
+    /// Switches capture for `id`; false when no poller has that ID.
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> bool {
+        match self.lock().get(id) {
+            Some(tap) => {
+                tap.set_enabled(enabled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// None when no poller has the ID `id`.
+    pub fn is_enabled(&self, id: &str) -> Option<bool> {
+        self.lock().get(id).map(CaptureTap::is_enabled)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, CaptureTap>> {
+        self.taps
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CaptureState {
+    id: String,
+    enabled: bool,
+}
+
+/// `GET` shows, `PUT` starts and `DELETE` stops the capture of a device at
+/// `/api/devices/:id/capture`.
+pub fn router(captures: CaptureRegistry) -> Router {
+    Router::new()
+        .route(
+            "/api/devices/:id/capture",
+            get(capture_state).put(start_capture).delete(stop_capture),
+        )
+        .with_state(captures)
+}
+
+async fn capture_state(
+    State(captures): State<CaptureRegistry>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<Json<CaptureState>, StatusCode> {
+    let enabled = captures.is_enabled(&id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(CaptureState { id, enabled }))
+}
+
+async fn start_capture(
+    State(captures): State<CaptureRegistry>,
+    UrlPath(id): UrlPath<String>,
+) -> StatusCode {
+    switch(&captures, &id, true)
+}
+
+async fn stop_capture(
+    State(captures): State<CaptureRegistry>,
+    UrlPath(id): UrlPath<String>,
+) -> StatusCode {
+    switch(&captures, &id, false)
+}
+
+fn switch(captures: &CaptureRegistry, id: &str, enabled: bool) -> StatusCode {
+    if !captures.set_enabled(id, enabled) {
+        return StatusCode::NOT_FOUND;
+    }
+    if enabled {
+        info!(poller = %id, "modbus capture started");
+    } else {
+        info!(poller = %id, "modbus capture stopped");
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// One line of the capture file. Frames are hex bytes separated by spaces,
+/// as Wireshark shows them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptureRecord {
+    pub at_ms: u64,
+    /// Poller ID of the device.
+    pub device: String,
+    pub port: u16,
+    pub transaction_id: u16,
+    pub function: u8,
+    pub start: u16,
+    pub count: u16,
+    pub request: String,
+    pub response: Option<String>,
+    pub exception: Option<u8>,
+    pub error: Option<String>,
+    pub latency_us: u64,
+}
+
+impl From<&Exchange> for CaptureRecord {
+    fn from(exchange: &Exchange) -> Self {
+        Self {
+            at_ms: exchange.at_ms,
+            device: format!("{}:{}", exchange.host, exchange.unit_id),
+            port: exchange.port,
+            transaction_id: exchange.transaction_id,
+            function: exchange.function,
+            start: exchange.start,
+            count: exchange.count,
+            request: hex(&exchange.request),
+            response: exchange.response.as_deref().map(hex),
+            exception: exchange.exception,
+            error: exchange.error.clone(),
+            latency_us: exchange.latency_us,
+        }
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Appends capture records as JSON lines to [`CAPTURE_FILE`] in one
+/// directory. The file is created on the first write; once it reaches the
+/// size limit it becomes `.1`, earlier rotations move up one number and
+/// those beyond `max_files` are deleted.
+#[derive(Debug)]
+pub struct CaptureFile {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    max_files: usize,
+    current: Option<(BufWriter<File>, u64)>,
+}
+
+impl CaptureFile {
+    pub fn new(settings: &CaptureSettings) -> Self {
+        Self {
+            dir: PathBuf::from(&settings.dir),
+            max_file_bytes: settings.max_file_bytes,
+            max_files: settings.max_files,
+            current: None,
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(CAPTURE_FILE)
+    }
+
+    pub fn write(&mut self, record: &CaptureRecord) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|(_, size)| *size >= self.max_file_bytes)
+        {
+            self.rotate()?;
+        }
+        if self.current.is_none() {
+            fs::create_dir_all(&self.dir)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.path())?;
+            let size = file.metadata()?.len();
+            self.current = Some((BufWriter::new(file), size));
+        }
+        if let Some((writer, size)) = self.current.as_mut() {
+            writer.write_all(&line)?;
+            *size += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.current.as_mut() {
+            Some((writer, _)) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.current = None;
+        let oldest = rotated_path(&self.dir, self.max_files + 1);
+        let _ = fs::remove_file(oldest);
+        for n in (1..=self.max_files).rev() {
+            let from = if n == 1 {
+                self.path()
+            } else {
+                rotated_path(&self.dir, n - 1)
+            };
+            if from.exists() {
+                fs::rename(from, rotated_path(&self.dir, n))?;
+            }
+        }
+        if self.max_files == 0 {
+            fs::remove_file(self.path())?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CaptureFile {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Path of the `n`th rotated capture file in `dir`, 1 being the newest.
+pub fn rotated_path(dir: &Path, n: usize) -> PathBuf {
+    dir.join(format!("{CAPTURE_FILE}.{n}"))
+}
+
+/// Writes every captured exchange to `file` until the channel closes,
+/// flushing whenever no more are waiting.
+pub async fn run(mut file: CaptureFile, mut exchanges: mpsc::Receiver<Exchange>) {
+    while let Some(exchange) = exchanges.recv().await {
+        write(&mut file, &exchange);
+        while let Ok(exchange) = exchanges.try_recv() {
+            write(&mut file, &exchange);
+        }
+        if let Err(err) = file.flush() {
+            warn!(path = %file.path().display(), error = %err, "modbus capture flush failed");
+        }
+    }
+}
+
+fn write(file: &mut CaptureFile, exchange: &Exchange) {
+    match file.write(&CaptureRecord::from(exchange)) {
+        Ok(()) => counter!("modbus_capture_written").increment(1),
+        Err(err) => {
+            warn!(path = %file.path().display(), error = %err, "modbus capture write failed");
+            counter!("modbus_capture_error").increment(1);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use poller_actor::{ClockConfig, ClockEpoch};
+use sunspec_parser::ModelDefinition;
+use types::DeviceIdentity;
+
+/// Model 122 (measurements and status) carries the device time as `Tms`.
+const MEASUREMENT_STATUS_MODEL: u16 = 122;
+/// Offset of `Tms` from the model 122 ID register.
+const TMS_OFFSET: u16 = 41;
+
+/// Clock registers of the units behind one IP, or of one unit when `unit_id`
+/// is set, for devices whose clock is not (only) the model 122 `Tms` point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceClock {
+    pub ip: String,
+    pub unit_id: Option<u8>,
+    /// Address of the two clock registers.
+    pub address: u16,
+    pub epoch: ClockEpoch,
+    /// The device accepts the time written to `address`.
+    pub writable: bool,
+}
+
+impl DeviceClock {
+    pub fn matches(&self, device: &DeviceIdentity) -> bool {
+        self.ip == device.ip && self.unit_id.is_none_or(|unit_id| unit_id == device.unit_id)
+    }
+}
+
+/// Drift reporting and correction settings shared by every device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClockSettings {
+    pub interval_ms: u64,
+    /// Drift beyond which writable clocks are set (0 = never set).
+    pub set_threshold_ms: u64,
+    /// Allow writing to devices at all.
+    pub writes_enabled: bool,
+    pub devices: Vec<DeviceClock>,
+}
+
+impl ClockSettings {
+    /// Clock of `device`: a configured one first, otherwise the read-only
+    /// `Tms` point when the device exposes model 122. None when the device
+    /// has no known clock.
+    pub fn resolve(
+        &self,
+        device: &DeviceIdentity,
+        models: &[ModelDefinition],
+    ) -> Option<ClockConfig> {
+        let (address, epoch, writable) =
+            match self.devices.iter().find(|clock| clock.matches(device)) {
+                Some(clock) => (clock.address, clock.epoch, clock.writable),
+                None => {
+                    let model = models.iter().find(|model| {
+                        model.id == MEASUREMENT_STATUS_MODEL && model.length > TMS_OFFSET + 1
+                    })?;
+                    (
+                        model.start.checked_add(TMS_OFFSET)?,
+                        ClockEpoch::Sunspec,
+                        false,
+                    )
+                }
+            };
+        let settable = writable && self.writes_enabled && self.set_threshold_ms > 0;
+        Some(ClockConfig {
+            address,
+            epoch,
+            interval: Duration::from_millis(self.interval_ms),
+            set_threshold: settable.then(|| Duration::from_millis(self.set_threshold_ms)),
+        })
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
 use std::net::Ipv4Addr;
@@ -7,39 +8,315 @@ use std::time::Duration;
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
+use avro_kafka::{AvroCodec, Encoding, KafkaSecurity};
 use discovery::DiscoveryConfig;
 use modbus_client::ClientConfig;
-use poller_actor::ActorConfig;
-use types::DeviceIdentity;
+use poller_actor::{ActorConfig, ClockEpoch, PollSample};
+use types::{DeviceIdentity, SiteInfo};
+
+use crate::aggregate::ModelWindow;
+use crate::alerts::{AlertCondition, AlertRule};
+use crate::anomaly::DeviceGroup;
+use crate::boot::BootOrder;
+use crate::capture::CaptureSettings;
+use crate::clock::{ClockSettings, DeviceClock};
+use crate::expiry::TopicMaxAge;
+use crate::grpc_sink::GrpcSinkSettings;
+use crate::influx_sink::{InfluxSinkSettings, LineEncoder};
+use crate::inventory::{load_inventory, InventoryDevice};
+use crate::leader::{self, LeaderSettings};
+use crate::logging::LogSettings;
+use crate::maintenance::{MaintenanceCalendar, MaintenanceWindow, MAX_WINDOW_MS};
+use crate::model_map::{PointType, StaticModel, StaticModelMap, StaticPoint};
+use crate::otel::OtelSettings;
+use crate::postgres_sink::{self, PostgresSinkSettings};
+use crate::quarantine::QuarantineSettings;
+use crate::resources::ResourceSettings;
+use crate::restart::RestartPolicy;
+use crate::routing::{check_template, render_topic, TopicRoute, TopicRouter};
+use crate::rules::{
+    DeviceModelFilter, DeviceProfile, DeviceRule, ModelFilter, ModelInterval, ModelRange,
+    ScanClassConfig,
+};
+use crate::simulator::{SyntheticDevice, MAX_RATED_W, SIMULATED_MODELS};
+use crate::timesync::TimeSyncSettings;
+use crate::uplink::DrainOrder;
 
 const DEFAULT_BASE_ADDRESS: u16 = 40_000;
 const DEFAULT_DISCOVERY_REG_COUNT: u16 = 200;
 const DEFAULT_CHANNEL_CAPACITY: usize = 256;
 const DEFAULT_RESPAWN_DELAY_MS: u64 = 1_000;
+const DEFAULT_RESPAWN_MAX_DELAY_MS: u64 = 60_000;
+const DEFAULT_CIRCUIT_BREAKER_FAILURES: u32 = 10;
+const DEFAULT_STALL_TIMEOUT_MS: u64 = 60_000;
+const DEFAULT_BOOT_STEP_MS: u64 = 5_000;
+const DEFAULT_SHUTDOWN_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_HEARTBEAT_INTERVAL_MS: u64 = 60_000;
+const DEFAULT_LOG_ROLLUP_WINDOW_MS: u64 = 60_000;
 const DEFAULT_BUFFER_PATH: &str = "sunspec-buffer.sqlite";
+const DEFAULT_DEVICE_IDS_PATH: &str = "sunspec-device-ids.json";
 const DEFAULT_BUFFER_BATCH_SIZE: i64 = 100;
 const DEFAULT_BUFFER_DRAIN_INTERVAL_MS: u64 = 500;
+const DEFAULT_BUFFER_MAX_IN_FLIGHT: usize = 1;
+const MAX_BUFFER_MAX_IN_FLIGHT: usize = 32;
+const DEFAULT_BUFFER_LIVE_WINDOW_MS: u64 = 300_000;
+const DEFAULT_BUFFER_BACKLOG_SHARE_PCT: u8 = 20;
+const DEFAULT_DEDUP_MAX_SUPPRESSION_MS: u64 = 60_000;
+const DEFAULT_DAILY_TOPIC: &str = "sunspec.daily";
+const DEFAULT_SCHEMA_TOPIC: &str = "sunspec.schemas";
+/// Telemetry topic when `kafka.topic` is not set.
+const DEFAULT_KAFKA_TOPIC: &str = "sunspec.telemetry";
+/// Longest `kafka.namespace`, leaving room for the topic names it prefixes.
+const MAX_KAFKA_NAMESPACE_LEN: usize = 64;
+const DEFAULT_KAFKA_BOOTSTRAP_REFRESH_MS: u64 = 300_000;
+const DEFAULT_KAFKA_COMMIT_TOPIC: &str = "sunspec.uplink-commits";
+/// librdkafka's bounds for `message.max.bytes`.
+const MIN_KAFKA_MESSAGE_BYTES: usize = 1_000;
+const MAX_KAFKA_MESSAGE_BYTES: usize = 1_000_000_000;
+const DEFAULT_DAILY_CSV_PATH: &str = "sunspec-daily.csv";
+const DEFAULT_ALARMS_TOPIC: &str = "sunspec.alarms";
+const DEFAULT_ALERTS_TOPIC: &str = "sunspec.alerts";
+const DEFAULT_PLANT_SNAPSHOT_TOPIC: &str = "sunspec.plant";
+const DEFAULT_PLANT_SNAPSHOT_INTERVAL_MS: u64 = 60_000;
+const DEFAULT_PLANT_SNAPSHOT_WINDOW_MS: u64 = 2_000;
+const DEFAULT_CLOCK_INTERVAL_MS: u64 = 300_000;
+const DEFAULT_QUARANTINE_INITIAL_PROBE_MS: u64 = 60_000;
+const DEFAULT_QUARANTINE_MAX_PROBE_MS: u64 = 3_600_000;
+const DEFAULT_QUARANTINE_AFTER_FAILURES: u32 = 3;
+const DEFAULT_FILE_SINK_DIR: &str = "sunspec-archive";
+/// One week of hourly files.
+const DEFAULT_FILE_SINK_MAX_FILES: usize = 168;
+const DEFAULT_SPARKPLUG_TOPIC: &str = "sunspec.sparkplug";
+const DEFAULT_SPARKPLUG_GROUP_ID: &str = "sunspec";
+const DEFAULT_SPARKPLUG_EDGE_NODE_ID: &str = "sunspec-collector";
+const DEFAULT_ANOMALY_INTERVAL_MS: u64 = 60_000;
+const DEFAULT_ANOMALY_Z_THRESHOLD: f64 = 2.0;
+const DEFAULT_ANOMALY_MIN_DEFICIT_PCT: f64 = 20.0;
+const DEFAULT_ANOMALY_MIN_POWER_W: f64 = 100.0;
+const DEFAULT_SIMULATOR_PORT: u16 = 1502;
+const DEFAULT_SYNTHETIC_RATED_W: f64 = 5_000.0;
 
 #[derive(Clone, Debug)]
 pub struct CollectorConfig {
     pub discovery: DiscoveryConfig,
     pub modbus: ClientConfig,
     pub poller: ActorConfig,
+    /// Base address probed first; see `detect_base_address`.
     pub base_address: u16,
+    /// Also probe the other standard base addresses (and their ±1 shifts)
+    /// when a device has no `SunS` marker at `base_address`.
+    pub detect_base_address: bool,
     pub discovery_register_count: u16,
+    /// JSON file keeping the short numeric ID assigned to each device.
+    pub device_ids_path: String,
+    /// CSV or JSON file listing static devices (ip, unit, site, alias,
+    /// profile), added to `discovery.static_devices` when the config loads.
+    pub inventory_path: Option<String>,
+    /// Devices read from `inventory_path`, for their site, alias and profile.
+    pub inventory: Vec<InventoryDevice>,
+    /// Directory of official SunSpec model files (`model_NNN.json`) loaded at
+    /// startup; their model names replace the built-in ones.
+    pub sunspec_model_dir: Option<String>,
+    /// File the model catalog is saved to after startup and restored from on
+    /// the next start, so model names survive an unreachable `model_dir`.
+    pub sunspec_catalog_path: Option<String>,
     pub channel_capacity: usize,
+    /// Read every discovered model once when a poller starts and publish it
+    /// as baseline samples, including models the device rules skip.
+    pub initial_snapshot: bool,
+    /// Device classes (`meter`, `inverter`, `storage` or a profile's
+    /// `class`) in the order they start polling at startup.
+    pub boot_order: Vec<String>,
+    /// Time between the start of one class of the boot order and the next.
+    pub boot_step_ms: u64,
+    /// Delay before a failed poller is respawned; it doubles with every
+    /// failure in a row up to `respawn_max_delay_ms`.
     pub respawn_delay_ms: u64,
+    pub respawn_max_delay_ms: u64,
+    /// Failures in a row without a clean cycle after which a poller is no
+    /// longer respawned but quarantined until a probe rediscovers the
+    /// device; 0 keeps respawning.
+    pub circuit_breaker_failures: u32,
+    /// A poller without a clean cycle for this long is reported as stalled.
+    pub stall_timeout_ms: u64,
+    /// Interval of the per-device heartbeat (status and traffic accounting).
+    pub heartbeat_interval_ms: u64,
+    /// Identical poller and Modbus client log events within this window are
+    /// logged once, then summarized with a repeat count (0 = log all).
+    pub log_rollup_window_ms: u64,
+    /// Log format, default level and per-crate levels; read once at startup.
+    pub logging: LogSettings,
+    /// OTLP span export; needs the `otel` feature.
+    pub otel: OtelSettings,
+    /// Stream decoded telemetry to a `TelemetryIngest` gRPC service.
+    pub grpc_sink: GrpcSinkSettings,
+    /// Write decoded points to InfluxDB (or any line-protocol endpoint).
+    pub influx: InfluxSinkSettings,
+    /// Insert decoded samples into a Postgres/TimescaleDB table.
+    pub postgres: PostgresSinkSettings,
+    /// Where Modbus captures switched on through the admin API go.
+    pub capture: CaptureSettings,
+    /// Names this collector among the instances of a redundant pair.
+    pub instance_id: String,
+    /// Active/standby coordination with the other collector of a pair.
+    pub leader: LeaderSettings,
+    /// Host clock synchronization check and sample timestamping.
+    pub timesync: TimeSyncSettings,
+    /// Disk, memory and channel limits with throttling and eviction.
+    pub resources: ResourceSettings,
     pub buffer_path: String,
     pub buffer_batch_size: i64,
     pub buffer_drain_interval_ms: u64,
+    /// Batches published concurrently per drain, each to its own lane of
+    /// devices so a device's samples stay in order.
+    pub buffer_max_in_flight: usize,
+    /// Buffered samples older than this are dropped instead of published (0 = keep).
+    pub buffer_max_age_ms: u64,
+    /// Per-topic overrides of `buffer_max_age_ms`.
+    pub buffer_topic_max_age: Vec<TopicMaxAge>,
+    /// Samples that cannot be encoded or exceed `kafka_max_message_bytes`
+    /// are published here with their error; without a topic (or when that
+    /// publish fails) they go to the buffer's `dead_letter` table.
+    pub buffer_dead_letter_topic: Option<String>,
+    /// Publish samples right away while the broker is up and nothing is
+    /// buffered, and only buffer them when that fails.
+    pub buffer_write_through: bool,
+    /// `oldest_first` (default), `newest_first` or `live_priority`; see
+    /// [`CollectorConfig::drain_order`].
+    pub buffer_drain_order: Option<String>,
+    /// With `live_priority`, rows buffered within this window count as live.
+    pub buffer_live_window_ms: u64,
+    /// With `live_priority`, the share of every drain reserved for the
+    /// oldest backlog rows, in percent.
+    pub buffer_backlog_share_pct: u8,
+    /// Start polling the devices in the buffer's inventory table right away
+    /// and rediscover in the background.
+    pub buffer_start_from_inventory: bool,
+    /// Interval of buffer compaction: incremental vacuum, `PRAGMA optimize`
+    /// and a WAL checkpoint (0 = never).
+    pub buffer_compact_interval_ms: u64,
+    /// Drop samples whose registers match the last published block of the same device/model.
+    pub dedup_enabled: bool,
+    /// An unchanged block is still published once it is this old, as a heartbeat.
+    pub dedup_max_suppression_ms: u64,
+    /// Also write every buffered sample to hourly CSV files in `file_sink_dir`.
+    pub file_sink_enabled: bool,
+    pub file_sink_dir: String,
+    /// Oldest files are deleted beyond this many files / bytes (0 = no limit).
+    pub file_sink_max_files: usize,
+    pub file_sink_max_bytes: u64,
+    /// Publish a per-device energy summary at local midnight.
+    pub daily_enabled: bool,
+    pub daily_topic: String,
+    pub daily_csv_path: String,
+    /// Offset of local time from UTC, used to find midnight.
+    pub daily_utc_offset_minutes: i32,
+    /// Publish inverter fault states and event bits as raised/cleared alarms.
+    pub alarms_enabled: bool,
+    pub alarms_topic: String,
+    /// Publish threshold and rate-of-change alerts on decoded points.
+    pub alerts_enabled: bool,
+    pub alerts_topic: String,
+    pub alert_rules: Vec<AlertRule>,
+    /// Read AC power on every inverter and meter at the same moment once per
+    /// interval and publish the plant-wide snapshot.
+    pub plant_snapshot_enabled: bool,
+    pub plant_snapshot_topic: String,
+    pub plant_snapshot_interval_ms: u64,
+    /// Reads later than this after the trigger are left out of the snapshot.
+    pub plant_snapshot_window_ms: u64,
+    /// Read device clocks and report their drift from the collector clock.
+    pub clock_enabled: bool,
+    pub clock_interval_ms: u64,
+    /// Writable clocks drifting further than this are set (0 = never set).
+    pub clock_set_threshold_ms: u64,
+    /// Allow the collector to write to devices; off, clocks are only read.
+    pub clock_writes_enabled: bool,
+    /// Clock registers of devices without model 122, or writable ones.
+    pub clock_devices: Vec<DeviceClock>,
+    /// Register layouts of devices without the SunSpec marker; their devices
+    /// join the static devices.
+    pub model_maps: Vec<StaticModelMap>,
+    /// Devices that do not answer are probed after this long, then at
+    /// doubling intervals up to `quarantine_max_probe_ms`.
+    pub quarantine_initial_probe_ms: u64,
+    pub quarantine_max_probe_ms: u64,
+    /// Failed connects in a row before a poller is quarantined.
+    pub quarantine_after_failures: u32,
+    /// Compare inverters within each group and log underperformers.
+    pub anomaly_enabled: bool,
+    pub anomaly_interval_ms: u64,
+    pub anomaly_z_threshold: f64,
+    pub anomaly_min_deficit_pct: f64,
+    /// Groups producing less than this on average are not evaluated.
+    pub anomaly_min_power_w: f64,
+    pub anomaly_groups: Vec<DeviceGroup>,
+    /// Models folded into min/avg/max windows before buffering; others pass through.
+    pub aggregation_windows: Vec<ModelWindow>,
+    /// Polling profiles applied through `device_rules` after the common model is read.
+    pub device_profiles: Vec<DeviceProfile>,
+    pub device_rules: Vec<DeviceRule>,
+    /// Model IDs every device may or may not poll, applied with the profiles.
+    pub model_filter: ModelFilter,
+    /// Further include/exclude lists for single gateways or units.
+    pub device_model_filters: Vec<DeviceModelFilter>,
+    /// Scheduled windows that defer discovery, optionally pause polling and
+    /// flag the samples collected meanwhile.
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+    /// Offset of local time from UTC, used to evaluate the schedules.
+    pub maintenance_utc_offset_minutes: i32,
+    /// Local port of the built-in device emulator started by `--simulate`.
+    pub simulator_port: u16,
+    /// Unit IDs the emulator answers for; each gets its own register map.
+    pub simulator_unit_ids: Vec<u8>,
+    /// Models emulated after the common model, in register order.
+    pub simulator_models: Vec<u16>,
+    /// In-process devices whose simulated samples bypass Modbus entirely.
+    pub synthetic_devices: Vec<SyntheticDevice>,
+    /// Publish decoded points as Sparkplug B messages, keyed by Sparkplug topic.
+    pub sparkplug_enabled: bool,
+    pub sparkplug_topic: String,
+    pub sparkplug_group_id: String,
+    pub sparkplug_edge_node_id: String,
     pub kafka_brokers: Option<String>,
     pub kafka_client_id: Option<String>,
     pub kafka_acks: Option<String>,
     pub kafka_compression: Option<String>,
     pub kafka_timeout_ms: Option<u64>,
     pub kafka_topic: Option<String>,
+    /// Tenant of this collector on a shared cluster: prefixed to every Kafka
+    /// topic as `<namespace>.` when the config is loaded, and stamped onto
+    /// every sample.
+    pub kafka_namespace: Option<String>,
     pub kafka_enable_idempotence: Option<bool>,
+    /// Largest payload handed to the producer (librdkafka `message.max.bytes`).
+    pub kafka_max_message_bytes: Option<usize>,
+    /// `avro` (default), `avro_single` or `json`.
+    pub kafka_encoding: Option<String>,
+    /// Compression of Avro containers: `null`, `deflate` (default) or `zstd`.
+    pub kafka_avro_codec: Option<String>,
+    /// Where the JSON Schema of each data topic is published when the
+    /// encoding is `json`.
+    pub kafka_schema_topic: String,
+    /// How often the bootstrap servers are re-resolved; the producer is
+    /// rebuilt when their addresses change (0 = never).
+    pub kafka_bootstrap_refresh_ms: u64,
+    /// Publish buffered samples exactly once: every drain is one Kafka
+    /// transaction under this id, and its rows leave the buffer only once the
+    /// commit is confirmed. Unique per collector and stable across restarts.
+    pub kafka_transactional_id: Option<String>,
+    /// Compacted topic the commit marker of each transaction goes to, read
+    /// back after a crash to settle the last commit.
+    pub kafka_commit_topic: String,
+    /// `security.protocol`, SASL credentials and TLS files for the brokers.
+    pub kafka_security: KafkaSecurity,
+    /// Per-sample topic templates; samples no route matches go to `kafka.topic`.
+    pub kafka_routes: Vec<TopicRoute>,
+    /// Site of the collector, stamped onto every sample when set.
+    pub site: Option<SiteInfo>,
     pub metrics_port: u16,
+    /// Upper bound for the ordered shutdown (poller stop, channel drain, final uplink flush).
+    pub shutdown_timeout_ms: u64,
 }
 
 impl CollectorConfig {
@@ -55,9 +332,116 @@ impl CollectorConfig {
         }
 
         apply_env_overrides(&mut config);
+        config.apply_kafka_namespace();
+        // An empty path is left to `validate`.
+        if let Some(path) = config
+            .inventory_path
+            .clone()
+            .filter(|path| !path.trim().is_empty())
+        {
+            config.load_inventory(&path)?;
+        }
+        for map in &config.model_maps {
+            let device = map.identity();
+            let listed = config
+                .discovery
+                .static_devices
+                .iter()
+                .any(|listed| map.matches(listed));
+            if !listed {
+                config.discovery.static_devices.push(device);
+            }
+        }
         Ok(config)
     }
 
+    /// `topic` in this collector's namespace: `<namespace>.<topic>`, or
+    /// `topic` itself without a namespace.
+    pub fn namespaced_topic(&self, topic: &str) -> String {
+        match self.kafka_namespace.as_deref() {
+            Some(namespace) => format!("{namespace}.{topic}"),
+            None => topic.to_string(),
+        }
+    }
+
+    /// Moves every configured topic into the namespace, so no record leaves
+    /// the collector outside of it.
+    fn apply_kafka_namespace(&mut self) {
+        let Some(namespace) = self.kafka_namespace.clone() else {
+            return;
+        };
+        let prefix = |topic: &mut String| *topic = format!("{namespace}.{topic}");
+        let telemetry = self
+            .kafka_topic
+            .get_or_insert_with(|| DEFAULT_KAFKA_TOPIC.to_string());
+        prefix(telemetry);
+        for topic in [
+            &mut self.kafka_schema_topic,
+            &mut self.kafka_commit_topic,
+            &mut self.daily_topic,
+            &mut self.alarms_topic,
+            &mut self.alerts_topic,
+            &mut self.plant_snapshot_topic,
+            &mut self.sparkplug_topic,
+        ] {
+            prefix(topic);
+        }
+        if let Some(topic) = self.buffer_dead_letter_topic.as_mut() {
+            prefix(topic);
+        }
+        for entry in &mut self.buffer_topic_max_age {
+            prefix(&mut entry.topic);
+        }
+        for route in &mut self.kafka_routes {
+            prefix(&mut route.topic);
+        }
+    }
+
+    /// Adds the devices of the inventory at `path` to the static devices.
+    fn load_inventory(&mut self, path: &str) -> Result<()> {
+        let devices = load_inventory(Path::new(path))?;
+        for device in &devices {
+            if self
+                .discovery
+                .static_devices
+                .iter()
+                .any(|listed| device.matches(listed))
+            {
+                anyhow::bail!(
+                    "device {}:{} is in both discovery.static_devices and the inventory file {path}",
+                    device.ip,
+                    device.unit_id
+                );
+            }
+        }
+        self.discovery
+            .static_devices
+            .extend(devices.iter().map(InventoryDevice::identity));
+        self.inventory = devices;
+        Ok(())
+    }
+
+    /// Inventory entry of `device`, when it came from the inventory file.
+    pub fn inventory_device(&self, device: &DeviceIdentity) -> Option<&InventoryDevice> {
+        self.inventory.iter().find(|entry| entry.matches(device))
+    }
+
+    /// Client settings for `device`: `[modbus]`, with the gateway addressing
+    /// of its inventory entry when that sets any.
+    pub fn modbus_for(&self, device: &DeviceIdentity) -> ClientConfig {
+        let mut modbus = self.modbus.clone();
+        modbus.host = device.ip.clone();
+        if let Some(entry) = self.inventory_device(device) {
+            if let Some(offset) = entry.address_offset {
+                modbus.address_offset = offset;
+            }
+            if let Some(one_based) = entry.one_based_addressing {
+                modbus.one_based_addressing = one_based;
+            }
+        }
+        modbus
+    }
+
     pub fn validate(&self) -> Result<()> {
         if self.discovery.port == 0 {
             anyhow::bail!("discovery.port must be between 1 and 65535");
@@ -69,12 +453,21 @@ impl CollectorConfig {
             anyhow::bail!("discovery.per_host_timeout_ms must be >= 1");
         }
         validate_cidr(&self.discovery.subnet)?;
+        for device in &self.discovery.static_devices {
+            validate_device_host(&device.ip)?;
+        }
         if self.poller.poll_interval.as_millis() == 0 {
             anyhow::bail!("poller.poll_interval_ms must be >= 1");
         }
         if self.poller.request_timeout.as_millis() == 0 {
             anyhow::bail!("poller.request_timeout_ms must be >= 1");
         }
+        if self.poller.model_probe_interval.as_millis() == 0 {
+            anyhow::bail!("poller.model_probe_interval_ms must be >= 1");
+        }
+        if self.poller.align_to_clock && self.poller.jitter_ms > 0 {
+            anyhow::bail!("poller.jitter_ms must be 0 when poller.align_to_clock is set");
+        }
         if self.modbus.port == 0 {
             anyhow::bail!("modbus.port must be between 1 and 65535");
         }
@@ -82,6 +475,11 @@ impl CollectorConfig {
             if max_batch == 0 {
                 anyhow::bail!("modbus.max_batch_size must be >= 1");
             }
+            if self.poller.coalesce_reads && max_batch > 125 {
+                anyhow::bail!(
+                    "modbus.max_batch_size must be <= 125 when poller.coalesce_reads is set"
+                );
+            }
         }
         if self.modbus.timeout_ms == 0 {
             anyhow::bail!("modbus.timeout_ms must be >= 1");
@@ -97,24 +495,343 @@ impl CollectorConfig {
                 anyhow::bail!("modbus.inter_read_delay_ms must be >= 1 when set");
             }
         }
-        if self.base_address == 0 {
-            anyhow::bail!("sunspec.base_address must be >= 1");
+        if !valid_address_offset(self.modbus.address_offset) {
+            anyhow::bail!("modbus.address_offset must be between -65535 and 65535");
         }
         if self.discovery_register_count == 0 {
             anyhow::bail!("sunspec.discovery_register_count must be >= 1");
         }
+        if let Some(ref dir) = self.sunspec_model_dir {
+            if dir.trim().is_empty() {
+                anyhow::bail!("sunspec.model_dir must be non-empty when set");
+            }
+        }
+        if let Some(ref path) = self.sunspec_catalog_path {
+            if path.trim().is_empty() {
+                anyhow::bail!("sunspec.catalog_path must be non-empty when set");
+            }
+        }
+        if self.device_ids_path.trim().is_empty() {
+            anyhow::bail!("discovery.device_ids_path must be non-empty");
+        }
+        if let Some(ref path) = self.inventory_path {
+            if path.trim().is_empty() {
+                anyhow::bail!("discovery.inventory_path must be non-empty when set");
+            }
+        }
         if self.channel_capacity == 0 {
             anyhow::bail!("channel_capacity must be >= 1");
         }
         if self.respawn_delay_ms == 0 {
             anyhow::bail!("respawn_delay_ms must be >= 1");
         }
+        if self.respawn_max_delay_ms < self.respawn_delay_ms {
+            anyhow::bail!("poller.respawn_max_delay_ms must be >= poller.respawn_delay_ms");
+        }
+        if self.stall_timeout_ms == 0 {
+            anyhow::bail!("poller.stall_timeout_ms must be >= 1");
+        }
+        if self.heartbeat_interval_ms == 0 {
+            anyhow::bail!("poller.heartbeat_interval_ms must be >= 1");
+        }
         if self.buffer_batch_size <= 0 {
             anyhow::bail!("buffer.batch_size must be >= 1");
         }
         if self.buffer_drain_interval_ms == 0 {
             anyhow::bail!("buffer.drain_interval_ms must be >= 1");
         }
+        if !(1..=MAX_BUFFER_MAX_IN_FLIGHT).contains(&self.buffer_max_in_flight) {
+            anyhow::bail!("buffer.max_in_flight must be between 1 and {MAX_BUFFER_MAX_IN_FLIGHT}");
+        }
+        if let Some(ref order) = self.buffer_drain_order {
+            if DrainOrder::parse(order).is_none() {
+                anyhow::bail!(
+                    "buffer.drain_order must be oldest_first, newest_first or live_priority"
+                );
+            }
+        }
+        if self.drain_order() == DrainOrder::LivePriority {
+            if self.buffer_live_window_ms == 0 {
+                anyhow::bail!("buffer.live_window_ms must be >= 1 with live_priority");
+            }
+            if !(1..=99).contains(&self.buffer_backlog_share_pct) {
+                anyhow::bail!("buffer.backlog_share_pct must be between 1 and 99");
+            }
+        }
+        if self.buffer_compact_interval_ms > 0 && self.buffer_compact_interval_ms < 60_000 {
+            anyhow::bail!("buffer.compact_interval_ms must be 0 or at least 60000");
+        }
+        if let Some(ref namespace) = self.kafka_namespace {
+            validate_kafka_namespace(namespace)?;
+        }
+        for (index, entry) in self.buffer_topic_max_age.iter().enumerate() {
+            validate_kafka_topic(&entry.topic)?;
+            if self.buffer_topic_max_age[..index]
+                .iter()
+                .any(|other| other.topic == entry.topic)
+            {
+                anyhow::bail!("buffer.topic_max_age for '{}' is configured twice", entry.topic);
+            }
+        }
+        if let Some(ref topic) = self.buffer_dead_letter_topic {
+            validate_kafka_topic(topic)?;
+        }
+        if self.dedup_enabled && self.dedup_max_suppression_ms == 0 {
+            anyhow::bail!("dedup.max_suppression_ms must be >= 1 when dedup is enabled");
+        }
+        if self.file_sink_enabled && self.file_sink_dir.trim().is_empty() {
+            anyhow::bail!("file_sink.dir must be non-empty when the file sink is enabled");
+        }
+        if self.daily_enabled {
+            validate_kafka_topic(&self.daily_topic)?;
+            if self.daily_csv_path.trim().is_empty() {
+                anyhow::bail!("daily.csv_path must be non-empty");
+            }
+            if self.daily_utc_offset_minutes.abs() > 14 * 60 {
+                anyhow::bail!("daily.utc_offset_minutes must be between -840 and 840");
+            }
+        }
+        if self.alarms_enabled {
+            validate_kafka_topic(&self.alarms_topic)?;
+        }
+        if self.alerts_enabled {
+            validate_kafka_topic(&self.alerts_topic)?;
+            if self.alert_rules.is_empty() {
+                anyhow::bail!("alerts.rules must list at least one rule");
+            }
+        }
+        for (index, rule) in self.alert_rules.iter().enumerate() {
+            validate_alert_rule(rule)?;
+            if self.alert_rules[..index]
+                .iter()
+                .any(|other| other.name == rule.name)
+            {
+                anyhow::bail!("alerts.rules lists {} twice", rule.name);
+            }
+        }
+        if self.plant_snapshot_enabled {
+            validate_kafka_topic(&self.plant_snapshot_topic)?;
+            if self.plant_snapshot_window_ms == 0
+                || self.plant_snapshot_window_ms >= self.plant_snapshot_interval_ms
+            {
+                anyhow::bail!(
+                    "plant_snapshot.window_ms must be >= 1 and below plant_snapshot.interval_ms"
+                );
+            }
+        }
+        if self.clock_enabled && self.clock_interval_ms == 0 {
+            anyhow::bail!("clock.interval_ms must be >= 1");
+        }
+        if self.quarantine_initial_probe_ms == 0 {
+            anyhow::bail!("quarantine.initial_probe_ms must be >= 1");
+        }
+        if self.quarantine_max_probe_ms < self.quarantine_initial_probe_ms {
+            anyhow::bail!("quarantine.max_probe_ms must be >= quarantine.initial_probe_ms");
+        }
+        if self.quarantine_after_failures == 0 {
+            anyhow::bail!("quarantine.after_failures must be >= 1");
+        }
+        for (index, map) in self.model_maps.iter().enumerate() {
+            map.validate().map_err(anyhow::Error::msg)?;
+            if self.model_maps[..index]
+                .iter()
+                .any(|other| other.ip == map.ip && other.unit_id == map.unit_id)
+            {
+                anyhow::bail!("model_maps lists {}:{} twice", map.ip, map.unit_id);
+            }
+        }
+        for clock in &self.clock_devices {
+            if clock.ip.trim().is_empty() {
+                anyhow::bail!("clock.devices entries need an ip");
+            }
+            if clock.address == u16::MAX {
+                anyhow::bail!(
+                    "clock.devices entry for {}: address must leave room for two registers",
+                    clock.ip
+                );
+            }
+        }
+        if self.anomaly_enabled {
+            if self.anomaly_interval_ms == 0 {
+                anyhow::bail!("anomaly.interval_ms must be >= 1");
+            }
+            if self.anomaly_z_threshold.is_nan() || self.anomaly_z_threshold < 0.0 {
+                anyhow::bail!("anomaly.z_threshold must be >= 0");
+            }
+            if !(0.0..=100.0).contains(&self.anomaly_min_deficit_pct) {
+                anyhow::bail!("anomaly.min_deficit_pct must be between 0 and 100");
+            }
+            if self.anomaly_groups.iter().any(|group| group.name.trim().is_empty()) {
+                anyhow::bail!("anomaly.groups entries need a name");
+            }
+        }
+        for (index, window) in self.aggregation_windows.iter().enumerate() {
+            if window.window_ms == 0 {
+                anyhow::bail!(
+                    "aggregation window for model {} must be >= 1 ms",
+                    window.model_id
+                );
+            }
+            if self.aggregation_windows[..index]
+                .iter()
+                .any(|other| other.model_id == window.model_id)
+            {
+                anyhow::bail!(
+                    "aggregation window for model {} is configured twice",
+                    window.model_id
+                );
+            }
+        }
+        for profile in &self.device_profiles {
+            if profile
+                .model_intervals
+                .iter()
+                .any(|interval| interval.interval_ms == 0)
+            {
+                anyhow::bail!("profiles.{}: model interval_ms must be >= 1", profile.name);
+            }
+            for class in &profile.scan_classes {
+                if class.points.is_empty() {
+                    anyhow::bail!(
+                        "profiles.{}: scan class for model {} needs at least one point",
+                        profile.name,
+                        class.model_id
+                    );
+                }
+                if class.full_interval_ms == 0 {
+                    anyhow::bail!(
+                        "profiles.{}: scan class for model {}: full_interval_ms must be >= 1",
+                        profile.name,
+                        class.model_id
+                    );
+                }
+                if let Err(err) = class.scan_class() {
+                    anyhow::bail!("profiles.{}: scan class: {err}", profile.name);
+                }
+            }
+            if profile
+                .class
+                .as_deref()
+                .is_some_and(|class| class.trim().is_empty())
+            {
+                anyhow::bail!("profiles.{}: class must be non-empty when set", profile.name);
+            }
+        }
+        for (index, class) in self.boot_order.iter().enumerate() {
+            if class.trim().is_empty() {
+                anyhow::bail!("poller.boot_order entries must be non-empty");
+            }
+            if self.boot_order[..index]
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(class))
+            {
+                anyhow::bail!("poller.boot_order lists '{class}' twice");
+            }
+        }
+        if !self.boot_order.is_empty() && self.boot_step_ms == 0 {
+            anyhow::bail!("poller.boot_step_ms must be >= 1 when poller.boot_order is set");
+        }
+        for rule in &self.device_rules {
+            if !self
+                .device_profiles
+                .iter()
+                .any(|profile| profile.name == rule.profile)
+            {
+                anyhow::bail!("rules: unknown profile '{}'", rule.profile);
+            }
+        }
+        for device in &self.inventory {
+            if !device.address_offset.is_none_or(valid_address_offset) {
+                anyhow::bail!(
+                    "inventory: device {}:{} address_offset must be between -65535 and 65535",
+                    device.ip,
+                    device.unit_id
+                );
+            }
+            let Some(ref name) = device.profile else {
+                continue;
+            };
+            if !self
+                .device_profiles
+                .iter()
+                .any(|profile| &profile.name == name)
+            {
+                anyhow::bail!(
+                    "inventory: device {}:{} has unknown profile '{name}'",
+                    device.ip,
+                    device.unit_id
+                );
+            }
+        }
+        for filter in &self.device_model_filters {
+            if filter.ip.trim().is_empty() {
+                anyhow::bail!("models.devices entries need an ip");
+            }
+        }
+        for window in &self.maintenance_windows {
+            if window.name.trim().is_empty() {
+                anyhow::bail!("maintenance.windows entries need a name");
+            }
+            if window.duration_ms == 0 || window.duration_ms > MAX_WINDOW_MS {
+                anyhow::bail!(
+                    "maintenance window '{}': duration_ms must be between 1 and {MAX_WINDOW_MS}",
+                    window.name
+                );
+            }
+        }
+        if self.maintenance_utc_offset_minutes.abs() > 14 * 60 {
+            anyhow::bail!("maintenance.utc_offset_minutes must be between -840 and 840");
+        }
+        MaintenanceCalendar::new(&self.maintenance_windows, self.maintenance_utc_offset_minutes)?;
+        if self.simulator_port == 0 {
+            anyhow::bail!("simulator.port must be between 1 and 65535");
+        }
+        if self.simulator_unit_ids.is_empty() {
+            anyhow::bail!("simulator.unit_ids must not be empty");
+        }
+        if let Some(model) = self
+            .simulator_models
+            .iter()
+            .find(|model| !SIMULATED_MODELS.contains(model))
+        {
+            anyhow::bail!("simulator.models: model {model} cannot be simulated");
+        }
+        for (index, device) in self.synthetic_devices.iter().enumerate() {
+            if device.name.trim().is_empty() {
+                anyhow::bail!("synthetic.devices entries need a name");
+            }
+            if self.synthetic_devices[..index]
+                .iter()
+                .any(|other| other.name == device.name && other.unit_id == device.unit_id)
+            {
+                anyhow::bail!(
+                    "synthetic device '{}' unit {} is configured twice",
+                    device.name,
+                    device.unit_id
+                );
+            }
+            if let Some(model) = device
+                .models
+                .iter()
+                .find(|model| !SIMULATED_MODELS.contains(model))
+            {
+                anyhow::bail!(
+                    "synthetic device '{}': model {model} cannot be simulated",
+                    device.name
+                );
+            }
+            if !(device.rated_w > 0.0 && device.rated_w <= MAX_RATED_W) {
+                anyhow::bail!(
+                    "synthetic device '{}': rated_w must be between 1 and {MAX_RATED_W}",
+                    device.name
+                );
+            }
+        }
+        if self.sparkplug_enabled {
+            validate_kafka_topic(&self.sparkplug_topic)?;
+            validate_sparkplug_id("sparkplug.group_id", &self.sparkplug_group_id)?;
+            validate_sparkplug_id("sparkplug.edge_node_id", &self.sparkplug_edge_node_id)?;
+        }
         if let Some(timeout_ms) = self.kafka_timeout_ms {
             if timeout_ms == 0 {
                 anyhow::bail!("kafka.timeout_ms must be >= 1");
@@ -128,9 +845,131 @@ impl CollectorConfig {
         if let Some(ref topic) = self.kafka_topic {
             validate_kafka_topic(topic)?;
         }
+        if let Some(max_bytes) = self.kafka_max_message_bytes {
+            if !(MIN_KAFKA_MESSAGE_BYTES..=MAX_KAFKA_MESSAGE_BYTES).contains(&max_bytes) {
+                anyhow::bail!(
+                    "kafka.max_message_bytes must be between {MIN_KAFKA_MESSAGE_BYTES} and {MAX_KAFKA_MESSAGE_BYTES}"
+                );
+            }
+        }
+        if let Some(ref encoding) = self.kafka_encoding {
+            if Encoding::parse(encoding).is_none() {
+                anyhow::bail!("kafka.encoding must be avro, avro_single or json");
+            }
+        }
+        if let Some(ref codec) = self.kafka_avro_codec {
+            if AvroCodec::parse(codec).is_none() {
+                anyhow::bail!("kafka.avro_codec must be null, deflate or zstd");
+            }
+        }
+        if self.encoding() == Encoding::Json {
+            validate_kafka_topic(&self.kafka_schema_topic)?;
+        }
+        if self.buffer_write_through && self.kafka_transactional_id.is_some() {
+            anyhow::bail!("buffer.write_through cannot be used with kafka.transactional_id");
+        }
+        if let Some(ref id) = self.kafka_transactional_id {
+            if id.trim().is_empty() {
+                anyhow::bail!("kafka.transactional_id must be non-empty when set");
+            }
+            if self.kafka_enable_idempotence == Some(false) {
+                anyhow::bail!("kafka.transactional_id requires kafka.enable_idempotence");
+            }
+            if self
+                .kafka_acks
+                .as_deref()
+                .is_some_and(|acks| !matches!(acks.trim(), "all" | "-1"))
+            {
+                anyhow::bail!("kafka.transactional_id requires kafka.acks = \"all\"");
+            }
+            validate_kafka_topic(&self.kafka_commit_topic)?;
+        }
+        validate_kafka_security(&self.kafka_security)?;
+        for (index, route) in self.kafka_routes.iter().enumerate() {
+            validate_kafka_route(route).with_context(|| format!("kafka.routes[{index}]"))?;
+        }
+        if let Some(ref site) = self.site {
+            validate_site(site)?;
+        }
+        self.logging.format().map_err(anyhow::Error::msg)?;
+        self.logging.targets().map_err(anyhow::Error::msg)?;
+        validate_otel(&self.otel)?;
+        validate_grpc_sink(&self.grpc_sink)?;
+        validate_influx(&self.influx)?;
+        validate_postgres(&self.postgres, &self.buffer_path)?;
+        validate_capture(&self.capture)?;
+        validate_leader(&self.instance_id, &self.leader)?;
+        validate_timesync(&self.timesync)?;
+        validate_resources(&self.resources)?;
+        if self.shutdown_timeout_ms == 0 {
+            anyhow::bail!("shutdown_timeout_ms must be >= 1");
+        }
 
         Ok(())
     }
+
+    pub fn boot_order(&self) -> BootOrder {
+        BootOrder {
+            classes: self.boot_order.clone(),
+            step: Duration::from_millis(self.boot_step_ms),
+        }
+    }
+
+    pub fn topic_router(&self) -> TopicRouter {
+        TopicRouter::new(self.kafka_routes.clone())
+    }
+
+    pub fn quarantine_settings(&self) -> QuarantineSettings {
+        QuarantineSettings {
+            initial_probe_ms: self.quarantine_initial_probe_ms,
+            max_probe_ms: self.quarantine_max_probe_ms,
+            after_failures: self.quarantine_after_failures,
+        }
+    }
+
+    pub fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy {
+            initial_delay_ms: self.respawn_delay_ms,
+            max_delay_ms: self.respawn_max_delay_ms,
+            breaker_failures: self.circuit_breaker_failures,
+        }
+    }
+
+    /// Clock settings handed to the pollers, when clock reading is enabled.
+    pub fn clock_settings(&self) -> Option<ClockSettings> {
+        self.clock_enabled.then(|| ClockSettings {
+            interval_ms: self.clock_interval_ms,
+            set_threshold_ms: self.clock_set_threshold_ms,
+            writes_enabled: self.clock_writes_enabled,
+            devices: self.clock_devices.clone(),
+        })
+    }
+
+    /// Payload encoding; an unset or (before validation) unknown value means Avro.
+    /// Buffer drain order; an unset or (before validation) unknown value
+    /// means oldest first.
+    pub fn drain_order(&self) -> DrainOrder {
+        self.buffer_drain_order
+            .as_deref()
+            .and_then(DrainOrder::parse)
+            .unwrap_or_default()
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.kafka_encoding
+            .as_deref()
+            .and_then(Encoding::parse)
+            .unwrap_or_default()
+    }
+
+    /// Avro container codec; an unset or (before validation) unknown value
+    /// means deflate.
+    pub fn avro_codec(&self) -> AvroCodec {
+        self.kafka_avro_codec
+            .as_deref()
+            .and_then(AvroCodec::parse)
+            .unwrap_or_default()
+    }
 }
 
 impl Default for CollectorConfig {
@@ -140,23 +979,115 @@ impl Default for CollectorConfig {
             modbus: ClientConfig::default(),
             poller: ActorConfig::default(),
             base_address: DEFAULT_BASE_ADDRESS,
+            detect_base_address: true,
             discovery_register_count: DEFAULT_DISCOVERY_REG_COUNT,
-            discovery_unit_ids: vec![1],
+            device_ids_path: DEFAULT_DEVICE_IDS_PATH.to_string(),
+            inventory_path: None,
+            inventory: Vec::new(),
+            sunspec_model_dir: None,
+            sunspec_catalog_path: None,
             channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            initial_snapshot: false,
+            boot_order: Vec::new(),
+            boot_step_ms: DEFAULT_BOOT_STEP_MS,
             respawn_delay_ms: DEFAULT_RESPAWN_DELAY_MS,
+            respawn_max_delay_ms: DEFAULT_RESPAWN_MAX_DELAY_MS,
+            circuit_breaker_failures: DEFAULT_CIRCUIT_BREAKER_FAILURES,
+            stall_timeout_ms: DEFAULT_STALL_TIMEOUT_MS,
+            heartbeat_interval_ms: DEFAULT_HEARTBEAT_INTERVAL_MS,
+            log_rollup_window_ms: DEFAULT_LOG_ROLLUP_WINDOW_MS,
+            logging: LogSettings::default(),
+            otel: OtelSettings::default(),
+            grpc_sink: GrpcSinkSettings::default(),
+            influx: InfluxSinkSettings::default(),
+            postgres: PostgresSinkSettings::default(),
+            capture: CaptureSettings::default(),
+            instance_id: leader::default_instance_id(),
+            leader: LeaderSettings::default(),
+            timesync: TimeSyncSettings::default(),
+            resources: ResourceSettings::default(),
             buffer_path: DEFAULT_BUFFER_PATH.to_string(),
             buffer_batch_size: DEFAULT_BUFFER_BATCH_SIZE,
             buffer_drain_interval_ms: DEFAULT_BUFFER_DRAIN_INTERVAL_MS,
+            buffer_max_in_flight: DEFAULT_BUFFER_MAX_IN_FLIGHT,
+            buffer_max_age_ms: 0,
+            buffer_topic_max_age: Vec::new(),
+            buffer_dead_letter_topic: None,
+            buffer_write_through: false,
+            buffer_drain_order: None,
+            buffer_start_from_inventory: false,
+            buffer_live_window_ms: DEFAULT_BUFFER_LIVE_WINDOW_MS,
+            buffer_backlog_share_pct: DEFAULT_BUFFER_BACKLOG_SHARE_PCT,
+            buffer_compact_interval_ms: 3_600_000,
+            dedup_enabled: false,
+            dedup_max_suppression_ms: DEFAULT_DEDUP_MAX_SUPPRESSION_MS,
+            file_sink_enabled: false,
+            file_sink_dir: DEFAULT_FILE_SINK_DIR.to_string(),
+            file_sink_max_files: DEFAULT_FILE_SINK_MAX_FILES,
+            file_sink_max_bytes: 0,
+            daily_enabled: false,
+            daily_topic: DEFAULT_DAILY_TOPIC.to_string(),
+            daily_csv_path: DEFAULT_DAILY_CSV_PATH.to_string(),
+            daily_utc_offset_minutes: 0,
+            alarms_enabled: false,
+            alarms_topic: DEFAULT_ALARMS_TOPIC.to_string(),
+            alerts_enabled: false,
+            alerts_topic: DEFAULT_ALERTS_TOPIC.to_string(),
+            alert_rules: Vec::new(),
+            plant_snapshot_enabled: false,
+            plant_snapshot_topic: DEFAULT_PLANT_SNAPSHOT_TOPIC.to_string(),
+            plant_snapshot_interval_ms: DEFAULT_PLANT_SNAPSHOT_INTERVAL_MS,
+            plant_snapshot_window_ms: DEFAULT_PLANT_SNAPSHOT_WINDOW_MS,
+            clock_enabled: false,
+            clock_interval_ms: DEFAULT_CLOCK_INTERVAL_MS,
+            clock_set_threshold_ms: 0,
+            clock_writes_enabled: false,
+            clock_devices: Vec::new(),
+            model_maps: Vec::new(),
+            quarantine_initial_probe_ms: DEFAULT_QUARANTINE_INITIAL_PROBE_MS,
+            quarantine_max_probe_ms: DEFAULT_QUARANTINE_MAX_PROBE_MS,
+            quarantine_after_failures: DEFAULT_QUARANTINE_AFTER_FAILURES,
+            anomaly_enabled: false,
+            anomaly_interval_ms: DEFAULT_ANOMALY_INTERVAL_MS,
+            anomaly_z_threshold: DEFAULT_ANOMALY_Z_THRESHOLD,
+            anomaly_min_deficit_pct: DEFAULT_ANOMALY_MIN_DEFICIT_PCT,
+            anomaly_min_power_w: DEFAULT_ANOMALY_MIN_POWER_W,
+            anomaly_groups: Vec::new(),
+            aggregation_windows: Vec::new(),
+            device_profiles: Vec::new(),
+            device_rules: Vec::new(),
+            model_filter: ModelFilter::default(),
+            device_model_filters: Vec::new(),
+            maintenance_windows: Vec::new(),
+            maintenance_utc_offset_minutes: 0,
+            simulator_port: DEFAULT_SIMULATOR_PORT,
+            simulator_unit_ids: vec![1],
+            simulator_models: vec![103],
+            synthetic_devices: Vec::new(),
+            sparkplug_enabled: false,
+            sparkplug_topic: DEFAULT_SPARKPLUG_TOPIC.to_string(),
+            sparkplug_group_id: DEFAULT_SPARKPLUG_GROUP_ID.to_string(),
+            sparkplug_edge_node_id: DEFAULT_SPARKPLUG_EDGE_NODE_ID.to_string(),
             kafka_brokers: None,
             kafka_client_id: None,
             kafka_acks: None,
             kafka_compression: None,
             kafka_timeout_ms: None,
             kafka_topic: None,
+            kafka_namespace: None,
             kafka_enable_idempotence: None,
-            kafka_topic: None,
-            kafka_enable_idempotence: None,
+            kafka_max_message_bytes: None,
+            kafka_encoding: None,
+            kafka_avro_codec: None,
+            kafka_schema_topic: DEFAULT_SCHEMA_TOPIC.to_string(),
+            kafka_bootstrap_refresh_ms: DEFAULT_KAFKA_BOOTSTRAP_REFRESH_MS,
+            kafka_transactional_id: None,
+            kafka_commit_topic: DEFAULT_KAFKA_COMMIT_TOPIC.to_string(),
+            kafka_security: KafkaSecurity::default(),
+            kafka_routes: Vec::new(),
+            site: None,
             metrics_port: 9090,
+            shutdown_timeout_ms: DEFAULT_SHUTDOWN_TIMEOUT_MS,
         }
     }
 }
@@ -166,9 +1097,8 @@ fn apply_env_overrides(config: &mut CollectorConfig) {
         config.discovery.subnet = value;
     }
 
-    if let Some(ids) = env::var("SUNSPEC_DISCOVERY_UNIT_IDS").ok() {
-        config.discovery_unit_ids = parse_unit_id_list(&ids);
-        config.discovery.unit_ids = config.discovery_unit_ids.clone();
+    if let Ok(ids) = env::var("SUNSPEC_DISCOVERY_UNIT_IDS") {
+        config.discovery.unit_ids = parse_unit_id_list(&ids);
     }
 
     if let Some(port) = parse_env_u16("SUNSPEC_PORT") {
@@ -176,31 +1106,92 @@ fn apply_env_overrides(config: &mut CollectorConfig) {
         config.modbus.port = port;
     }
 
-    if let Some(timeout_ms) = parse_env_u64("SUNSPEC_REQUEST_TIMEOUT_MS") {
+    if let Some(timeout_ms) = parse_env_duration_ms("SUNSPEC_REQUEST_TIMEOUT_MS") {
         config.poller.request_timeout = Duration::from_millis(timeout_ms);
     }
 
-    if let Some(interval_ms) = parse_env_u64("SUNSPEC_POLL_INTERVAL_MS") {
+    if let Some(interval_ms) = parse_env_duration_ms("SUNSPEC_POLL_INTERVAL_MS") {
         config.poller.poll_interval = Duration::from_millis(interval_ms);
     }
 
-    if let Some(jitter_ms) = parse_env_u64("SUNSPEC_JITTER_MS") {
+    if let Some(jitter_ms) = parse_env_duration_ms("SUNSPEC_JITTER_MS") {
         config.poller.jitter_ms = jitter_ms;
     }
 
+    if let Some(enabled) = parse_env_bool("SUNSPEC_POLL_ALIGN_TO_CLOCK") {
+        config.poller.align_to_clock = enabled;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_COALESCE_READS") {
+        config.poller.coalesce_reads = enabled;
+    }
+
+    if let Some(failures) = parse_env_u32("SUNSPEC_MODEL_QUARANTINE_AFTER") {
+        config.poller.model_quarantine_after = failures;
+    }
+
+    if let Some(interval_ms) = parse_env_duration_ms("SUNSPEC_MODEL_PROBE_INTERVAL_MS") {
+        config.poller.model_probe_interval = Duration::from_millis(interval_ms);
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_INITIAL_SNAPSHOT") {
+        config.initial_snapshot = enabled;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_BOOT_ORDER") {
+        config.boot_order = value
+            .split(',')
+            .map(str::trim)
+            .filter(|class| !class.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+
+    if let Some(step_ms) = parse_env_duration_ms("SUNSPEC_BOOT_STEP_MS") {
+        config.boot_step_ms = step_ms;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_MODEL_INCLUDE") {
+        config.model_filter.include = parse_model_ranges(&value);
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_MODEL_EXCLUDE") {
+        config.model_filter.exclude = parse_model_ranges(&value);
+    }
+
     if let Some(max_batch) = parse_env_u16("SUNSPEC_MAX_BATCH_SIZE") {
         config.modbus.max_batch_size = Some(max_batch);
     }
 
-    if let Some(timeout_ms) = parse_env_u64("SUNSPEC_MODBUS_TIMEOUT_MS") {
+    if let Some(timeout_ms) = parse_env_duration_ms("SUNSPEC_MODBUS_TIMEOUT_MS") {
         config.modbus.timeout_ms = timeout_ms;
     }
 
-    if let Some(value) = env::var("SUNSPEC_STATIC_DEVICES").ok() {
+    if let Some(ttl_ms) = parse_env_duration_ms("SUNSPEC_MODBUS_DNS_TTL_MS") {
+        config.modbus.dns_ttl_ms = ttl_ms;
+    }
+
+    if let Some(offset) = parse_env_i32("SUNSPEC_MODBUS_ADDRESS_OFFSET") {
+        config.modbus.address_offset = offset;
+    }
+
+    if let Some(one_based) = parse_env_bool("SUNSPEC_MODBUS_ONE_BASED_ADDRESSING") {
+        config.modbus.one_based_addressing = one_based;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_STATIC_DEVICES") {
         config.discovery.static_devices = parse_static_devices(&value);
     }
 
-    if let Some(value) = env::var("SUNSPEC_BUFFER_PATH").ok() {
+    if let Ok(value) = env::var("SUNSPEC_DEVICE_IDS_PATH") {
+        config.device_ids_path = value;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_INVENTORY_PATH") {
+        config.inventory_path = Some(value);
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_BUFFER_PATH") {
         config.buffer_path = value;
     }
 
@@ -208,102 +1199,1085 @@ fn apply_env_overrides(config: &mut CollectorConfig) {
         config.buffer_batch_size = value.max(1);
     }
 
-    if let Some(value) = parse_env_u64("SUNSPEC_BUFFER_DRAIN_MS") {
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_BUFFER_DRAIN_MS") {
         config.buffer_drain_interval_ms = value;
     }
 
-    config.base_address =
-        parse_env_u16("SUNSPEC_BASE_ADDRESS").unwrap_or(config.base_address);
-    config.discovery_register_count = parse_env_u16("SUNSPEC_DISCOVERY_REG_COUNT")
-        .unwrap_or(config.discovery_register_count);
-    config.channel_capacity =
-        parse_env_usize("SUNSPEC_CHANNEL_CAPACITY").unwrap_or(config.channel_capacity);
-    config.respawn_delay_ms =
-        parse_env_u64("SUNSPEC_RESPAWN_DELAY_MS").unwrap_or(config.respawn_delay_ms);
+    if let Some(value) = parse_env_usize("SUNSPEC_BUFFER_MAX_IN_FLIGHT") {
+        config.buffer_max_in_flight = value;
+    }
 
-    config.kafka_brokers = env::var("SUNSPEC_KAFKA_BROKERS").ok().or(config.kafka_brokers);
-    config.kafka_client_id =
-        env::var("SUNSPEC_KAFKA_CLIENT_ID").ok().or(config.kafka_client_id);
-    config.kafka_acks = env::var("SUNSPEC_KAFKA_ACKS").ok().or(config.kafka_acks);
-    config.kafka_compression =
-        env::var("SUNSPEC_KAFKA_COMPRESSION").ok().or(config.kafka_compression);
-    config.kafka_timeout_ms =
-        parse_env_u64("SUNSPEC_KAFKA_TIMEOUT_MS").or(config.kafka_timeout_ms);
-    config.kafka_topic =
-        env::var("SUNSPEC_KAFKA_TOPIC").ok().or(config.kafka_topic);
-    config.kafka_enable_idempotence =
-        parse_env_bool("SUNSPEC_KAFKA_IDEMPOTENCE").or(config.kafka_enable_idempotence);
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_BUFFER_MAX_AGE_MS") {
+        config.buffer_max_age_ms = value;
+    }
 
-    if let Some(port) = parse_env_u16("SUNSPEC_METRICS_PORT") {
-        config.metrics_port = port;
+    if let Ok(value) = env::var("SUNSPEC_BUFFER_TOPIC_MAX_AGE") {
+        config.buffer_topic_max_age = parse_topic_max_ages(&value);
     }
-}
 
-#[derive(Debug, Deserialize)]
-struct FileConfig {
-    discovery: Option<FileDiscoveryConfig>,
-    poller: Option<FilePollerConfig>,
-    modbus: Option<FileModbusConfig>,
-    sunspec: Option<FileSunspecConfig>,
-    buffer: Option<FileBufferConfig>,
-    kafka: Option<FileKafkaConfig>,
-}
+    if let Ok(value) = env::var("SUNSPEC_BUFFER_DEAD_LETTER_TOPIC") {
+        config.buffer_dead_letter_topic = Some(value);
+    }
 
-#[derive(Debug, Deserialize)]
-struct FileDiscoveryConfig {
-    subnet: Option<String>,
-    port: Option<u16>,
-    max_concurrency: Option<usize>,
-    per_host_timeout_ms: Option<u64>,
-    static_devices: Option<Vec<FileDeviceConfig>>,
-}
+    if let Some(enabled) = parse_env_bool("SUNSPEC_BUFFER_WRITE_THROUGH") {
+        config.buffer_write_through = enabled;
+    }
 
-#[derive(Debug, Deserialize)]
-struct FileDeviceConfig {
-    ip: String,
-    unit_id: Option<u8>,
-}
+    if let Ok(value) = env::var("SUNSPEC_BUFFER_DRAIN_ORDER") {
+        config.buffer_drain_order = Some(value);
+    }
 
-#[derive(Debug, Deserialize)]
-struct FilePollerConfig {
-    poll_interval_ms: Option<u64>,
-    request_timeout_ms: Option<u64>,
-    jitter_ms: Option<u64>,
-}
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_BUFFER_LIVE_WINDOW_MS") {
+        config.buffer_live_window_ms = value;
+    }
 
-#[derive(Debug, Deserialize)]
-struct FileModbusConfig {
-    port: Option<u16>,
-    max_batch_size: Option<u16>,
-    timeout_ms: Option<u64>,
-    retry_count: Option<usize>,
-    retry_backoff_ms: Option<u64>,
-    retry_max_backoff_ms: Option<u64>,
-    inter_read_delay_ms: Option<u64>,
-}
+    if let Some(value) = parse_env_u8("SUNSPEC_BUFFER_BACKLOG_SHARE_PCT") {
+        config.buffer_backlog_share_pct = value;
+    }
 
-#[derive(Debug, Deserialize)]
-struct FileSunspecConfig {
-    base_address: Option<u16>,
-    discovery_register_count: Option<u16>,
-}
+    if let Some(enabled) = parse_env_bool("SUNSPEC_BUFFER_START_FROM_INVENTORY") {
+        config.buffer_start_from_inventory = enabled;
+    }
 
-#[derive(Debug, Deserialize)]
-struct FileBufferConfig {
-    path: Option<String>,
-    batch_size: Option<i64>,
-    drain_interval_ms: Option<u64>,
-}
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_BUFFER_COMPACT_INTERVAL_MS") {
+        config.buffer_compact_interval_ms = value;
+    }
 
-#[derive(Debug, Deserialize)]
-struct FileKafkaConfig {
-    brokers: Option<String>,
-    topic: Option<String>,
-    client_id: Option<String>,
-    acks: Option<String>,
-    compression: Option<String>,
-    timeout_ms: Option<u64>,
+    if let Some(enabled) = parse_env_bool("SUNSPEC_DEDUP_ENABLED") {
+        config.dedup_enabled = enabled;
+    }
+
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_DEDUP_MAX_SUPPRESSION_MS") {
+        config.dedup_max_suppression_ms = value;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_DAILY_ENABLED") {
+        config.daily_enabled = enabled;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_DAILY_TOPIC") {
+        config.daily_topic = value;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_DAILY_CSV_PATH") {
+        config.daily_csv_path = value;
+    }
+
+    if let Some(offset) = parse_env_i32("SUNSPEC_DAILY_UTC_OFFSET_MINUTES") {
+        config.daily_utc_offset_minutes = offset;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_ALARMS_ENABLED") {
+        config.alarms_enabled = enabled;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_ALARMS_TOPIC") {
+        config.alarms_topic = value;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_ALERTS_ENABLED") {
+        config.alerts_enabled = enabled;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_ALERTS_TOPIC") {
+        config.alerts_topic = value;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_PLANT_SNAPSHOT_ENABLED") {
+        config.plant_snapshot_enabled = enabled;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_PLANT_SNAPSHOT_TOPIC") {
+        config.plant_snapshot_topic = value;
+    }
+
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_PLANT_SNAPSHOT_INTERVAL_MS") {
+        config.plant_snapshot_interval_ms = value;
+    }
+
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_PLANT_SNAPSHOT_WINDOW_MS") {
+        config.plant_snapshot_window_ms = value;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_CLOCK_ENABLED") {
+        config.clock_enabled = enabled;
+    }
+
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_CLOCK_INTERVAL_MS") {
+        config.clock_interval_ms = value;
+    }
+
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_CLOCK_SET_THRESHOLD_MS") {
+        config.clock_set_threshold_ms = value;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_CLOCK_WRITES_ENABLED") {
+        config.clock_writes_enabled = enabled;
+    }
+
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_QUARANTINE_INITIAL_PROBE_MS") {
+        config.quarantine_initial_probe_ms = value;
+    }
+
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_QUARANTINE_MAX_PROBE_MS") {
+        config.quarantine_max_probe_ms = value;
+    }
+
+    if let Some(value) = parse_env_u32("SUNSPEC_QUARANTINE_AFTER_FAILURES") {
+        config.quarantine_after_failures = value;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_ANOMALY_ENABLED") {
+        config.anomaly_enabled = enabled;
+    }
+
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_ANOMALY_INTERVAL_MS") {
+        config.anomaly_interval_ms = value;
+    }
+
+    if let Some(value) = parse_env_f64("SUNSPEC_ANOMALY_Z_THRESHOLD") {
+        config.anomaly_z_threshold = value;
+    }
+
+    if let Some(value) = parse_env_f64("SUNSPEC_ANOMALY_MIN_DEFICIT_PCT") {
+        config.anomaly_min_deficit_pct = value;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_FILE_SINK_ENABLED") {
+        config.file_sink_enabled = enabled;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_FILE_SINK_DIR") {
+        config.file_sink_dir = value;
+    }
+
+    if let Some(value) = parse_env_usize("SUNSPEC_FILE_SINK_MAX_FILES") {
+        config.file_sink_max_files = value;
+    }
+
+    if let Some(value) = parse_env_u64("SUNSPEC_FILE_SINK_MAX_BYTES") {
+        config.file_sink_max_bytes = value;
+    }
+
+    if let Some(enabled) = parse_env_bool("SUNSPEC_SPARKPLUG_ENABLED") {
+        config.sparkplug_enabled = enabled;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_SPARKPLUG_TOPIC") {
+        config.sparkplug_topic = value;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_SPARKPLUG_GROUP_ID") {
+        config.sparkplug_group_id = value;
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_SPARKPLUG_EDGE_NODE_ID") {
+        config.sparkplug_edge_node_id = value;
+    }
+
+    if let Some(offset) = parse_env_i32("SUNSPEC_MAINTENANCE_UTC_OFFSET_MINUTES") {
+        config.maintenance_utc_offset_minutes = offset;
+    }
+
+    if let Some(port) = parse_env_u16("SUNSPEC_SIMULATOR_PORT") {
+        config.simulator_port = port;
+    }
+
+    if let Ok(ids) = env::var("SUNSPEC_SIMULATOR_UNIT_IDS") {
+        config.simulator_unit_ids = parse_unit_id_list(&ids);
+    }
+
+    if let Ok(models) = env::var("SUNSPEC_SIMULATOR_MODELS") {
+        config.simulator_models = parse_model_list(&models);
+    }
+
+    if let Ok(value) = env::var("SUNSPEC_AGGREGATE_MODELS") {
+        config.aggregation_windows = parse_model_windows(&value);
+    }
+
+    config.base_address =
+        parse_env_u16("SUNSPEC_BASE_ADDRESS").unwrap_or(config.base_address);
+    config.detect_base_address =
+        parse_env_bool("SUNSPEC_DETECT_BASE_ADDRESS").unwrap_or(config.detect_base_address);
+    config.discovery_register_count = parse_env_u16("SUNSPEC_DISCOVERY_REG_COUNT")
+        .unwrap_or(config.discovery_register_count);
+    config.sunspec_model_dir =
+        env::var("SUNSPEC_MODEL_DIR").ok().or(config.sunspec_model_dir.take());
+    config.sunspec_catalog_path =
+        env::var("SUNSPEC_CATALOG_PATH").ok().or(config.sunspec_catalog_path.take());
+    config.channel_capacity =
+        parse_env_usize("SUNSPEC_CHANNEL_CAPACITY").unwrap_or(config.channel_capacity);
+    config.respawn_delay_ms =
+        parse_env_duration_ms("SUNSPEC_RESPAWN_DELAY_MS").unwrap_or(config.respawn_delay_ms);
+    config.respawn_max_delay_ms = parse_env_duration_ms("SUNSPEC_RESPAWN_MAX_DELAY_MS")
+        .unwrap_or(config.respawn_max_delay_ms);
+    config.circuit_breaker_failures = parse_env_u32("SUNSPEC_CIRCUIT_BREAKER_FAILURES")
+        .unwrap_or(config.circuit_breaker_failures);
+    config.stall_timeout_ms =
+        parse_env_duration_ms("SUNSPEC_STALL_TIMEOUT_MS").unwrap_or(config.stall_timeout_ms);
+    config.heartbeat_interval_ms = parse_env_duration_ms("SUNSPEC_HEARTBEAT_INTERVAL_MS")
+        .unwrap_or(config.heartbeat_interval_ms);
+    config.log_rollup_window_ms = parse_env_duration_ms("SUNSPEC_LOG_ROLLUP_WINDOW_MS")
+        .unwrap_or(config.log_rollup_window_ms);
+    if let Ok(format) = env::var("SUNSPEC_LOG_FORMAT") {
+        config.logging.format = format;
+    }
+    if let Ok(level) = env::var("SUNSPEC_LOG_LEVEL") {
+        config.logging.level = level;
+    }
+    if let Ok(filters) = env::var("SUNSPEC_LOG_FILTERS") {
+        config.logging.filters = parse_label_list(&filters);
+    }
+    if let Ok(endpoint) = env::var("SUNSPEC_OTEL_ENDPOINT") {
+        config.otel.endpoint = Some(endpoint);
+    }
+    if let Ok(name) = env::var("SUNSPEC_OTEL_SERVICE_NAME") {
+        config.otel.service_name = name;
+    }
+    if let Some(ratio) = parse_env_f64("SUNSPEC_OTEL_SAMPLE_RATIO") {
+        config.otel.sample_ratio = ratio;
+    }
+    if let Ok(endpoint) = env::var("SUNSPEC_GRPC_SINK_ENDPOINT") {
+        config.grpc_sink.endpoint = Some(endpoint);
+    }
+    if let Some(size) = parse_env_usize("SUNSPEC_GRPC_SINK_BATCH_SIZE") {
+        config.grpc_sink.batch_size = size;
+    }
+    if let Some(max) = parse_env_usize("SUNSPEC_GRPC_SINK_MAX_IN_FLIGHT") {
+        config.grpc_sink.max_in_flight = max;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_GRPC_SINK_RETRY_BACKOFF_MS") {
+        config.grpc_sink.retry_backoff_ms = ms;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_GRPC_SINK_RETRY_MAX_BACKOFF_MS") {
+        config.grpc_sink.retry_max_backoff_ms = ms;
+    }
+    for (key, field) in [
+        ("SUNSPEC_INFLUX_URL", &mut config.influx.url),
+        ("SUNSPEC_INFLUX_ORG", &mut config.influx.org),
+        ("SUNSPEC_INFLUX_BUCKET", &mut config.influx.bucket),
+        ("SUNSPEC_INFLUX_TOKEN", &mut config.influx.token),
+    ] {
+        if let Ok(value) = env::var(key) {
+            *field = Some(value);
+        }
+    }
+    if let Ok(measurement) = env::var("SUNSPEC_INFLUX_MEASUREMENT") {
+        config.influx.measurement = measurement;
+    }
+    if let Ok(tags) = env::var("SUNSPEC_INFLUX_TAGS") {
+        config.influx.tags = parse_label_list(&tags);
+    }
+    if let Some(size) = parse_env_usize("SUNSPEC_INFLUX_BATCH_SIZE") {
+        config.influx.batch_size = size;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_INFLUX_FLUSH_INTERVAL_MS") {
+        config.influx.flush_interval_ms = ms;
+    }
+    if let Ok(dsn) = env::var("SUNSPEC_POSTGRES_DSN") {
+        config.postgres.dsn = Some(dsn);
+    }
+    if let Ok(table) = env::var("SUNSPEC_POSTGRES_TABLE") {
+        config.postgres.table = table;
+    }
+    if let Some(size) = parse_env_usize("SUNSPEC_POSTGRES_BATCH_SIZE") {
+        config.postgres.batch_size = size;
+    }
+    if let Ok(mode) = env::var("SUNSPEC_POSTGRES_ON_CONFLICT") {
+        config.postgres.on_conflict = mode;
+    }
+    if let Some(create) = parse_env_bool("SUNSPEC_POSTGRES_CREATE_TABLE") {
+        config.postgres.create_table = create;
+    }
+    if let Ok(path) = env::var("SUNSPEC_POSTGRES_BUFFER_PATH") {
+        config.postgres.buffer_path = path;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_POSTGRES_CONNECT_TIMEOUT_MS") {
+        config.postgres.connect_timeout_ms = ms;
+    }
+    if let Ok(dir) = env::var("SUNSPEC_CAPTURE_DIR") {
+        config.capture.dir = dir;
+    }
+    if let Some(bytes) = parse_env_u64("SUNSPEC_CAPTURE_MAX_FILE_BYTES") {
+        config.capture.max_file_bytes = bytes;
+    }
+    if let Some(max) = parse_env_usize("SUNSPEC_CAPTURE_MAX_FILES") {
+        config.capture.max_files = max;
+    }
+    if let Ok(id) = env::var("SUNSPEC_INSTANCE_ID") {
+        config.instance_id = id;
+    }
+    if let Some(enabled) = parse_env_bool("SUNSPEC_LEADER_ENABLED") {
+        config.leader.enabled = enabled;
+    }
+    if let Ok(path) = env::var("SUNSPEC_LEADER_LEASE_PATH") {
+        config.leader.lease_path = path;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_LEADER_LEASE_MS") {
+        config.leader.lease_ms = ms;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_LEADER_RENEW_INTERVAL_MS") {
+        config.leader.renew_interval_ms = ms;
+    }
+    if let Ok(check) = env::var("SUNSPEC_TIMESYNC_CHECK") {
+        config.timesync.check = check;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_TIMESYNC_INTERVAL_MS") {
+        config.timesync.interval_ms = ms;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_TIMESYNC_MAX_OFFSET_MS") {
+        config.timesync.max_offset_ms = ms;
+    }
+    if let Some(enabled) = parse_env_bool("SUNSPEC_TIMESYNC_MONOTONIC_TIMESTAMPS") {
+        config.timesync.monotonic_timestamps = enabled;
+    }
+    if let Some(ms) = parse_env_duration_ms("SUNSPEC_RESOURCES_INTERVAL_MS") {
+        config.resources.interval_ms = ms;
+    }
+    if let Some(bytes) = parse_env_u64("SUNSPEC_RESOURCES_MIN_FREE_DISK_BYTES") {
+        config.resources.min_free_disk_bytes = bytes;
+    }
+    if let Some(bytes) = parse_env_u64("SUNSPEC_RESOURCES_MAX_BUFFER_BYTES") {
+        config.resources.max_buffer_bytes = bytes;
+    }
+    if let Some(bytes) = parse_env_u64("SUNSPEC_RESOURCES_MAX_RSS_BYTES") {
+        config.resources.max_rss_bytes = bytes;
+    }
+    if let Some(pct) = parse_env_u8("SUNSPEC_RESOURCES_MAX_CHANNEL_FILL_PCT") {
+        config.resources.max_channel_fill_pct = pct;
+    }
+    if let Some(factor) = parse_env_u32("SUNSPEC_RESOURCES_THROTTLE_FACTOR") {
+        config.resources.throttle_factor = factor;
+    }
+    if let Some(rows) = parse_env_u64("SUNSPEC_RESOURCES_EVICT_ROWS") {
+        config.resources.evict_rows = rows;
+    }
+
+    config.kafka_brokers = env::var("SUNSPEC_KAFKA_BROKERS").ok().or(config.kafka_brokers.take());
+    config.kafka_client_id =
+        env::var("SUNSPEC_KAFKA_CLIENT_ID").ok().or(config.kafka_client_id.take());
+    config.kafka_acks = env::var("SUNSPEC_KAFKA_ACKS").ok().or(config.kafka_acks.take());
+    config.kafka_compression =
+        env::var("SUNSPEC_KAFKA_COMPRESSION").ok().or(config.kafka_compression.take());
+    config.kafka_timeout_ms =
+        parse_env_duration_ms("SUNSPEC_KAFKA_TIMEOUT_MS").or(config.kafka_timeout_ms);
+    config.kafka_topic =
+        env::var("SUNSPEC_KAFKA_TOPIC").ok().or(config.kafka_topic.take());
+    config.kafka_namespace = env::var("SUNSPEC_KAFKA_NAMESPACE")
+        .ok()
+        .or(config.kafka_namespace.take());
+    config.kafka_enable_idempotence =
+        parse_env_bool("SUNSPEC_KAFKA_IDEMPOTENCE").or(config.kafka_enable_idempotence);
+    config.kafka_max_message_bytes =
+        parse_env_usize("SUNSPEC_KAFKA_MAX_MESSAGE_BYTES").or(config.kafka_max_message_bytes);
+    config.kafka_encoding =
+        env::var("SUNSPEC_KAFKA_ENCODING").ok().or(config.kafka_encoding.take());
+    config.kafka_avro_codec =
+        env::var("SUNSPEC_KAFKA_AVRO_CODEC").ok().or(config.kafka_avro_codec.take());
+    if let Ok(value) = env::var("SUNSPEC_KAFKA_SCHEMA_TOPIC") {
+        config.kafka_schema_topic = value;
+    }
+    if let Some(value) = parse_env_duration_ms("SUNSPEC_KAFKA_BOOTSTRAP_REFRESH_MS") {
+        config.kafka_bootstrap_refresh_ms = value;
+    }
+    config.kafka_transactional_id = env::var("SUNSPEC_KAFKA_TRANSACTIONAL_ID")
+        .ok()
+        .or(config.kafka_transactional_id.take());
+    if let Ok(value) = env::var("SUNSPEC_KAFKA_COMMIT_TOPIC") {
+        config.kafka_commit_topic = value;
+    }
+    let security = &mut config.kafka_security;
+    for (key, field) in [
+        ("SUNSPEC_KAFKA_SECURITY_PROTOCOL", &mut security.protocol),
+        ("SUNSPEC_KAFKA_SASL_MECHANISM", &mut security.sasl_mechanism),
+        ("SUNSPEC_KAFKA_SASL_USERNAME", &mut security.sasl_username),
+        ("SUNSPEC_KAFKA_SASL_PASSWORD", &mut security.sasl_password),
+        (
+            "SUNSPEC_KAFKA_SSL_CA_LOCATION",
+            &mut security.ssl_ca_location,
+        ),
+        (
+            "SUNSPEC_KAFKA_SSL_CERTIFICATE_LOCATION",
+            &mut security.ssl_certificate_location,
+        ),
+        (
+            "SUNSPEC_KAFKA_SSL_KEY_LOCATION",
+            &mut security.ssl_key_location,
+        ),
+    ] {
+        if let Ok(value) = env::var(key) {
+            *field = Some(value);
+        }
+    }
+
+    let site_id = env::var("SUNSPEC_SITE_ID").ok();
+    let plant = env::var("SUNSPEC_SITE_PLANT").ok();
+    let latitude = parse_env_f64("SUNSPEC_SITE_LATITUDE");
+    let longitude = parse_env_f64("SUNSPEC_SITE_LONGITUDE");
+    let labels = env::var("SUNSPEC_SITE_LABELS").ok();
+    if site_id.is_some()
+        || plant.is_some()
+        || latitude.is_some()
+        || longitude.is_some()
+        || labels.is_some()
+    {
+        let site = config.site.get_or_insert_with(SiteInfo::default);
+        if let Some(site_id) = site_id {
+            site.site_id = site_id;
+        }
+        if plant.is_some() {
+            site.plant = plant;
+        }
+        if latitude.is_some() {
+            site.latitude = latitude;
+        }
+        if longitude.is_some() {
+            site.longitude = longitude;
+        }
+        if let Some(labels) = labels {
+            site.labels = parse_label_list(&labels);
+        }
+    }
+
+    if let Some(port) = parse_env_u16("SUNSPEC_METRICS_PORT") {
+        config.metrics_port = port;
+    }
+
+    if let Some(timeout_ms) = parse_env_duration_ms("SUNSPEC_SHUTDOWN_TIMEOUT_MS") {
+        config.shutdown_timeout_ms = timeout_ms;
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FileConfig {
+    discovery: Option<FileDiscoveryConfig>,
+    poller: Option<FilePollerConfig>,
+    modbus: Option<FileModbusConfig>,
+    sunspec: Option<FileSunspecConfig>,
+    buffer: Option<FileBufferConfig>,
+    dedup: Option<FileDedupConfig>,
+    daily: Option<FileDailyConfig>,
+    alarms: Option<FileAlarmsConfig>,
+    alerts: Option<FileAlertsConfig>,
+    plant_snapshot: Option<FilePlantSnapshotConfig>,
+    clock: Option<FileClockConfig>,
+    quarantine: Option<FileQuarantineConfig>,
+    sparkplug: Option<FileSparkplugConfig>,
+    file_sink: Option<FileSinkConfig>,
+    anomaly: Option<FileAnomalyConfig>,
+    aggregation: Option<FileAggregationConfig>,
+    profiles: Option<HashMap<String, FileDeviceProfile>>,
+    rules: Option<Vec<FileDeviceRule>>,
+    models: Option<FileModelsConfig>,
+    maintenance: Option<FileMaintenanceConfig>,
+    simulator: Option<FileSimulatorConfig>,
+    synthetic: Option<FileSyntheticConfig>,
+    kafka: Option<FileKafkaConfig>,
+    site: Option<FileSiteConfig>,
+    logging: Option<FileLoggingConfig>,
+    otel: Option<FileOtelConfig>,
+    grpc_sink: Option<FileGrpcSinkConfig>,
+    influx: Option<FileInfluxConfig>,
+    postgres: Option<FilePostgresConfig>,
+    capture: Option<FileCaptureConfig>,
+    instance_id: Option<String>,
+    leader: Option<FileLeaderConfig>,
+    timesync: Option<FileTimeSyncConfig>,
+    resources: Option<FileResourcesConfig>,
+    model_maps: Option<Vec<FileModelMap>>,
+    #[serde(default, alias = "shutdown_timeout", with = "duration_ms::option")]
+    shutdown_timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileModelMap {
+    ip: String,
+    unit_id: Option<u8>,
+    models: Vec<FileStaticModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileStaticModel {
+    id: u16,
+    name: Option<String>,
+    start: u16,
+    length: u16,
+    #[serde(default)]
+    points: Vec<FileStaticPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileStaticPoint {
+    name: String,
+    offset: u16,
+    #[serde(rename = "type")]
+    point_type: PointType,
+    scale: Option<i16>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSiteConfig {
+    site_id: Option<String>,
+    plant: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    labels: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileLoggingConfig {
+    format: Option<String>,
+    level: Option<String>,
+    filters: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileOtelConfig {
+    endpoint: Option<String>,
+    service_name: Option<String>,
+    sample_ratio: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileGrpcSinkConfig {
+    endpoint: Option<String>,
+    batch_size: Option<usize>,
+    max_in_flight: Option<usize>,
+    #[serde(default, alias = "retry_backoff", with = "duration_ms::option")]
+    retry_backoff_ms: Option<u64>,
+    #[serde(default, alias = "retry_max_backoff", with = "duration_ms::option")]
+    retry_max_backoff_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileInfluxConfig {
+    url: Option<String>,
+    org: Option<String>,
+    bucket: Option<String>,
+    token: Option<String>,
+    measurement: Option<String>,
+    tags: Option<BTreeMap<String, String>>,
+    batch_size: Option<usize>,
+    #[serde(default, alias = "flush_interval", with = "duration_ms::option")]
+    flush_interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FilePostgresConfig {
+    dsn: Option<String>,
+    table: Option<String>,
+    batch_size: Option<usize>,
+    on_conflict: Option<String>,
+    create_table: Option<bool>,
+    buffer_path: Option<String>,
+    #[serde(default, alias = "connect_timeout", with = "duration_ms::option")]
+    connect_timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileCaptureConfig {
+    dir: Option<String>,
+    max_file_bytes: Option<u64>,
+    max_files: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileLeaderConfig {
+    enabled: Option<bool>,
+    lease_path: Option<String>,
+    #[serde(default, alias = "lease", with = "duration_ms::option")]
+    lease_ms: Option<u64>,
+    #[serde(default, alias = "renew_interval", with = "duration_ms::option")]
+    renew_interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileTimeSyncConfig {
+    check: Option<String>,
+    #[serde(default, alias = "interval", with = "duration_ms::option")]
+    interval_ms: Option<u64>,
+    #[serde(default, alias = "max_offset", with = "duration_ms::option")]
+    max_offset_ms: Option<u64>,
+    monotonic_timestamps: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileResourcesConfig {
+    #[serde(default, alias = "interval", with = "duration_ms::option")]
+    interval_ms: Option<u64>,
+    min_free_disk_bytes: Option<u64>,
+    max_buffer_bytes: Option<u64>,
+    max_rss_bytes: Option<u64>,
+    max_channel_fill_pct: Option<u8>,
+    throttle_factor: Option<u32>,
+    evict_rows: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDiscoveryConfig {
+    subnet: Option<String>,
+    port: Option<u16>,
+    max_concurrency: Option<usize>,
+    #[serde(default, alias = "per_host_timeout", with = "duration_ms::option")]
+    per_host_timeout_ms: Option<u64>,
+    unit_ids: Option<Vec<u8>>,
+    static_devices: Option<Vec<FileDeviceConfig>>,
+    device_ids_path: Option<String>,
+    inventory_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDeviceConfig {
+    ip: String,
+    unit_id: Option<u8>,
+    base_address: Option<u16>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FilePollerConfig {
+    #[serde(default, alias = "poll_interval", with = "duration_ms::option")]
+    poll_interval_ms: Option<u64>,
+    #[serde(default, alias = "request_timeout", with = "duration_ms::option")]
+    request_timeout_ms: Option<u64>,
+    #[serde(default, alias = "jitter", with = "duration_ms::option")]
+    jitter_ms: Option<u64>,
+    align_to_clock: Option<bool>,
+    coalesce_reads: Option<bool>,
+    model_quarantine_after: Option<u32>,
+    #[serde(default, alias = "model_probe_interval", with = "duration_ms::option")]
+    model_probe_interval_ms: Option<u64>,
+    #[serde(default, alias = "stall_timeout", with = "duration_ms::option")]
+    stall_timeout_ms: Option<u64>,
+    #[serde(default, alias = "heartbeat_interval", with = "duration_ms::option")]
+    heartbeat_interval_ms: Option<u64>,
+    #[serde(default, alias = "log_rollup_window", with = "duration_ms::option")]
+    log_rollup_window_ms: Option<u64>,
+    initial_snapshot: Option<bool>,
+    boot_order: Option<Vec<String>>,
+    #[serde(default, alias = "boot_step", with = "duration_ms::option")]
+    boot_step_ms: Option<u64>,
+    #[serde(default, alias = "respawn_delay", with = "duration_ms::option")]
+    respawn_delay_ms: Option<u64>,
+    #[serde(default, alias = "respawn_max_delay", with = "duration_ms::option")]
+    respawn_max_delay_ms: Option<u64>,
+    circuit_breaker_failures: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileModbusConfig {
+    port: Option<u16>,
+    max_batch_size: Option<u16>,
+    #[serde(default, alias = "timeout", with = "duration_ms::option")]
+    timeout_ms: Option<u64>,
+    retry_count: Option<usize>,
+    #[serde(default, alias = "retry_backoff", with = "duration_ms::option")]
+    retry_backoff_ms: Option<u64>,
+    #[serde(default, alias = "retry_max_backoff", with = "duration_ms::option")]
+    retry_max_backoff_ms: Option<u64>,
+    #[serde(default, alias = "inter_read_delay", with = "duration_ms::option")]
+    inter_read_delay_ms: Option<u64>,
+    #[serde(default, alias = "dns_ttl", with = "duration_ms::option")]
+    dns_ttl_ms: Option<u64>,
+    address_offset: Option<i32>,
+    one_based_addressing: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSunspecConfig {
+    base_address: Option<u16>,
+    detect_base_address: Option<bool>,
+    discovery_register_count: Option<u16>,
+    model_dir: Option<String>,
+    catalog_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileBufferConfig {
+    path: Option<String>,
+    batch_size: Option<i64>,
+    #[serde(default, alias = "drain_interval", with = "duration_ms::option")]
+    drain_interval_ms: Option<u64>,
+    max_in_flight: Option<usize>,
+    #[serde(default, alias = "max_age", with = "duration_ms::option")]
+    max_age_ms: Option<u64>,
+    topic_max_age: Option<Vec<FileTopicMaxAge>>,
+    dead_letter_topic: Option<String>,
+    write_through: Option<bool>,
+    drain_order: Option<String>,
+    #[serde(default, alias = "live_window", with = "duration_ms::option")]
+    live_window_ms: Option<u64>,
+    backlog_share_pct: Option<u8>,
+    start_from_inventory: Option<bool>,
+    #[serde(default, alias = "compact_interval", with = "duration_ms::option")]
+    compact_interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileTopicMaxAge {
+    topic: String,
+    #[serde(alias = "max_age", with = "duration_ms")]
+    max_age_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDedupConfig {
+    enabled: Option<bool>,
+    #[serde(default, alias = "max_suppression", with = "duration_ms::option")]
+    max_suppression_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDailyConfig {
+    enabled: Option<bool>,
+    topic: Option<String>,
+    csv_path: Option<String>,
+    utc_offset_minutes: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileAlarmsConfig {
+    enabled: Option<bool>,
+    topic: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileAlertsConfig {
+    enabled: Option<bool>,
+    topic: Option<String>,
+    rules: Option<Vec<FileAlertRule>>,
+}
+
+/// An alert rule with exactly one of `above`, `below`, `rising_per_min` and
+/// `falling_per_min`.
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawAlertRule")]
+struct FileAlertRule(AlertRule);
+
+#[derive(Debug, Deserialize)]
+struct RawAlertRule {
+    name: String,
+    point: String,
+    model_id: Option<u16>,
+    above: Option<f64>,
+    below: Option<f64>,
+    rising_per_min: Option<f64>,
+    falling_per_min: Option<f64>,
+    deadband: Option<f64>,
+}
+
+impl TryFrom<RawAlertRule> for FileAlertRule {
+    type Error = String;
+
+    fn try_from(raw: RawAlertRule) -> Result<Self, Self::Error> {
+        let conditions: Vec<AlertCondition> = [
+            raw.above.map(AlertCondition::Above),
+            raw.below.map(AlertCondition::Below),
+            raw.rising_per_min.map(AlertCondition::RisingPerMin),
+            raw.falling_per_min.map(AlertCondition::FallingPerMin),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let [condition] = conditions[..] else {
+            return Err(format!(
+                "alert rule '{}' needs exactly one of above, below, rising_per_min and falling_per_min",
+                raw.name
+            ));
+        };
+        Ok(Self(AlertRule {
+            name: raw.name,
+            point: raw.point,
+            model_id: raw.model_id,
+            condition,
+            deadband: raw.deadband.unwrap_or(0.0),
+        }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FilePlantSnapshotConfig {
+    enabled: Option<bool>,
+    topic: Option<String>,
+    #[serde(default, alias = "interval", with = "duration_ms::option")]
+    interval_ms: Option<u64>,
+    #[serde(default, alias = "window", with = "duration_ms::option")]
+    window_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSinkConfig {
+    enabled: Option<bool>,
+    dir: Option<String>,
+    max_files: Option<usize>,
+    max_bytes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSparkplugConfig {
+    enabled: Option<bool>,
+    topic: Option<String>,
+    group_id: Option<String>,
+    edge_node_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileClockConfig {
+    enabled: Option<bool>,
+    #[serde(default, alias = "interval", with = "duration_ms::option")]
+    interval_ms: Option<u64>,
+    #[serde(default, alias = "set_threshold", with = "duration_ms::option")]
+    set_threshold_ms: Option<u64>,
+    writes_enabled: Option<bool>,
+    devices: Option<Vec<FileDeviceClock>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileQuarantineConfig {
+    #[serde(default, alias = "initial_probe", with = "duration_ms::option")]
+    initial_probe_ms: Option<u64>,
+    #[serde(default, alias = "max_probe", with = "duration_ms::option")]
+    max_probe_ms: Option<u64>,
+    after_failures: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDeviceClock {
+    ip: String,
+    unit_id: Option<u8>,
+    address: u16,
+    /// `sunspec` (seconds since 2000, the default) or `unix`.
+    epoch: Option<ClockEpoch>,
+    writable: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileAnomalyConfig {
+    enabled: Option<bool>,
+    #[serde(default, alias = "interval", with = "duration_ms::option")]
+    interval_ms: Option<u64>,
+    z_threshold: Option<f64>,
+    min_deficit_pct: Option<f64>,
+    min_power_w: Option<f64>,
+    groups: Option<Vec<FileDeviceGroup>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDeviceGroup {
+    name: String,
+    devices: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileAggregationConfig {
+    models: Option<Vec<FileModelWindow>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileModelWindow {
+    model_id: u16,
+    #[serde(alias = "window", with = "duration_ms")]
+    window_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDeviceProfile {
+    only_models: Option<Vec<u16>>,
+    skip_models: Option<Vec<u16>>,
+    model_intervals: Option<Vec<FileModelInterval>>,
+    scan_classes: Option<Vec<FileScanClass>>,
+    class: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileModelInterval {
+    model_id: u16,
+    #[serde(alias = "interval", with = "duration_ms")]
+    interval_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileScanClass {
+    model_id: u16,
+    points: Vec<String>,
+    #[serde(alias = "full_interval", with = "duration_ms")]
+    full_interval_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDeviceRule {
+    manufacturer: Option<String>,
+    model_contains: Option<String>,
+    profile: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileModelsConfig {
+    include: Option<Vec<FileModelRange>>,
+    exclude: Option<Vec<FileModelRange>>,
+    devices: Option<Vec<FileDeviceModelFilter>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDeviceModelFilter {
+    ip: String,
+    unit_id: Option<u8>,
+    include: Option<Vec<FileModelRange>>,
+    exclude: Option<Vec<FileModelRange>>,
+}
+
+/// A model ID (`1`) or an inclusive range (`"64001-65535"`).
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawModelRange")]
+struct FileModelRange(ModelRange);
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawModelRange {
+    Id(u16),
+    Range(String),
+}
+
+impl TryFrom<RawModelRange> for FileModelRange {
+    type Error = String;
+
+    fn try_from(raw: RawModelRange) -> Result<Self, Self::Error> {
+        match raw {
+            RawModelRange::Id(model_id) => Ok(Self(ModelRange::single(model_id))),
+            RawModelRange::Range(value) => ModelRange::parse(&value)
+                .map(Self)
+                .ok_or_else(|| format!("invalid model range '{value}'")),
+        }
+    }
+}
+
+/// Durations in the config file: milliseconds (`500`) or a humantime
+/// string (`"500ms"`, `"2s"`, `"5m"`).
+mod duration_ms {
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawDuration {
+        Millis(u64),
+        Text(String),
+    }
+
+    impl RawDuration {
+        fn millis(self) -> Result<u64, String> {
+            match self {
+                RawDuration::Millis(millis) => Ok(millis),
+                RawDuration::Text(value) => parse(&value),
+            }
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<u64, String> {
+        let value = value.trim();
+        if let Ok(millis) = value.parse() {
+            return Ok(millis);
+        }
+        let duration = humantime::parse_duration(value)
+            .map_err(|err| format!("invalid duration '{value}': {err}"))?;
+        if duration.subsec_nanos() % 1_000_000 != 0 {
+            return Err(format!(
+                "duration '{value}' is not a whole number of milliseconds"
+            ));
+        }
+        u64::try_from(duration.as_millis()).map_err(|_| format!("duration '{value}' is too long"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        RawDuration::deserialize(deserializer)?
+            .millis()
+            .map_err(serde::de::Error::custom)
+    }
+
+    pub mod option {
+        use serde::{Deserialize, Deserializer};
+
+        use super::RawDuration;
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<u64>, D::Error> {
+            Option::<RawDuration>::deserialize(deserializer)?
+                .map(RawDuration::millis)
+                .transpose()
+                .map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FileMaintenanceConfig {
+    utc_offset_minutes: Option<i32>,
+    windows: Option<Vec<FileMaintenanceWindow>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileMaintenanceWindow {
+    name: String,
+    schedule: String,
+    #[serde(alias = "duration", with = "duration_ms")]
+    duration_ms: u64,
+    pause_polling: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSimulatorConfig {
+    port: Option<u16>,
+    unit_ids: Option<Vec<u8>>,
+    models: Option<Vec<u16>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSyntheticConfig {
+    devices: Option<Vec<FileSyntheticDevice>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSyntheticDevice {
+    name: String,
+    unit_id: Option<u8>,
+    models: Option<Vec<u16>>,
+    rated_w: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileKafkaConfig {
+    brokers: Option<String>,
+    topic: Option<String>,
+    namespace: Option<String>,
+    client_id: Option<String>,
+    acks: Option<String>,
+    compression: Option<String>,
+    #[serde(default, alias = "timeout", with = "duration_ms::option")]
+    timeout_ms: Option<u64>,
     enable_idempotence: Option<bool>,
+    max_message_bytes: Option<usize>,
+    encoding: Option<String>,
+    avro_codec: Option<String>,
+    schema_topic: Option<String>,
+    #[serde(default, alias = "bootstrap_refresh", with = "duration_ms::option")]
+    bootstrap_refresh_ms: Option<u64>,
+    transactional_id: Option<String>,
+    commit_topic: Option<String>,
+    security_protocol: Option<String>,
+    sasl_mechanism: Option<String>,
+    sasl_username: Option<String>,
+    sasl_password: Option<String>,
+    ssl_ca_location: Option<String>,
+    ssl_certificate_location: Option<String>,
+    ssl_key_location: Option<String>,
+    routes: Option<Vec<FileTopicRoute>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileTopicRoute {
+    models: Option<Vec<FileModelRange>>,
+    ip: Option<String>,
+    unit_id: Option<u8>,
+    topic: String,
 }
 
 fn load_file_config(config_path: Option<&str>) -> Result<Option<FileConfig>> {
@@ -328,126 +2302,764 @@ fn load_file_config(config_path: Option<&str>) -> Result<Option<FileConfig>> {
 }
 
 fn apply_file_config(config: &mut CollectorConfig, file: FileConfig) {
+    if let Some(timeout_ms) = file.shutdown_timeout_ms {
+        config.shutdown_timeout_ms = timeout_ms;
+    }
+
     if let Some(discovery) = file.discovery {
         if let Some(subnet) = discovery.subnet {
             config.discovery.subnet = subnet;
         }
         if let Some(ids) = discovery.unit_ids {
-            config.discovery_unit_ids = ids.clone();
             config.discovery.unit_ids = ids;
         }
         if let Some(port) = discovery.port {
             config.discovery.port = port;
             config.modbus.port = port;
         }
-        if let Some(max) = discovery.max_concurrency {
-            config.discovery.max_concurrency = max;
+        if let Some(max) = discovery.max_concurrency {
+            config.discovery.max_concurrency = max;
+        }
+        if let Some(timeout) = discovery.per_host_timeout_ms {
+            config.discovery.per_host_timeout_ms = timeout;
+        }
+        if let Some(path) = discovery.device_ids_path {
+            config.device_ids_path = path;
+        }
+        if let Some(path) = discovery.inventory_path {
+            config.inventory_path = Some(path);
+        }
+        if let Some(devices) = discovery.static_devices {
+            config.discovery.static_devices = devices
+                .into_iter()
+                .map(|device| DeviceIdentity {
+                    ip: device.ip,
+                    unit_id: device.unit_id.unwrap_or(1),
+                    base_address: device.base_address,
+                })
+                .collect();
+        }
+    }
+
+    if let Some(poller) = file.poller {
+        if let Some(interval_ms) = poller.poll_interval_ms {
+            config.poller.poll_interval = Duration::from_millis(interval_ms);
+        }
+        if let Some(timeout_ms) = poller.request_timeout_ms {
+            config.poller.request_timeout = Duration::from_millis(timeout_ms);
+        }
+        if let Some(jitter_ms) = poller.jitter_ms {
+            config.poller.jitter_ms = jitter_ms;
+        }
+        if let Some(enabled) = poller.align_to_clock {
+            config.poller.align_to_clock = enabled;
+        }
+        if let Some(enabled) = poller.coalesce_reads {
+            config.poller.coalesce_reads = enabled;
+        }
+        if let Some(failures) = poller.model_quarantine_after {
+            config.poller.model_quarantine_after = failures;
+        }
+        if let Some(interval_ms) = poller.model_probe_interval_ms {
+            config.poller.model_probe_interval = Duration::from_millis(interval_ms);
+        }
+        if let Some(stall_ms) = poller.stall_timeout_ms {
+            config.stall_timeout_ms = stall_ms;
+        }
+        if let Some(heartbeat_ms) = poller.heartbeat_interval_ms {
+            config.heartbeat_interval_ms = heartbeat_ms;
+        }
+        if let Some(window_ms) = poller.log_rollup_window_ms {
+            config.log_rollup_window_ms = window_ms;
+        }
+        if let Some(enabled) = poller.initial_snapshot {
+            config.initial_snapshot = enabled;
+        }
+        if let Some(classes) = poller.boot_order {
+            config.boot_order = classes;
+        }
+        if let Some(step_ms) = poller.boot_step_ms {
+            config.boot_step_ms = step_ms;
+        }
+        if let Some(delay_ms) = poller.respawn_delay_ms {
+            config.respawn_delay_ms = delay_ms;
+        }
+        if let Some(delay_ms) = poller.respawn_max_delay_ms {
+            config.respawn_max_delay_ms = delay_ms;
+        }
+        if let Some(failures) = poller.circuit_breaker_failures {
+            config.circuit_breaker_failures = failures;
+        }
+    }
+
+    if let Some(modbus) = file.modbus {
+        if let Some(port) = modbus.port {
+            config.modbus.port = port;
+            config.discovery.port = port;
+        }
+        if let Some(max_batch) = modbus.max_batch_size {
+            config.modbus.max_batch_size = Some(max_batch);
+        }
+        if let Some(timeout_ms) = modbus.timeout_ms {
+            config.modbus.timeout_ms = timeout_ms;
+        }
+        if let Some(retry_count) = modbus.retry_count {
+            config.modbus.retry_count = retry_count;
+        }
+        if let Some(backoff) = modbus.retry_backoff_ms {
+            config.modbus.retry_backoff_ms = backoff;
+        }
+        if let Some(max_backoff) = modbus.retry_max_backoff_ms {
+            config.modbus.retry_max_backoff_ms = max_backoff;
+        }
+        if let Some(delay) = modbus.inter_read_delay_ms {
+            config.modbus.inter_read_delay_ms = Some(delay);
+        }
+        if let Some(ttl_ms) = modbus.dns_ttl_ms {
+            config.modbus.dns_ttl_ms = ttl_ms;
+        }
+        if let Some(offset) = modbus.address_offset {
+            config.modbus.address_offset = offset;
+        }
+        if let Some(one_based) = modbus.one_based_addressing {
+            config.modbus.one_based_addressing = one_based;
+        }
+    }
+
+    if let Some(sunspec) = file.sunspec {
+        if let Some(base) = sunspec.base_address {
+            config.base_address = base;
+        }
+        if let Some(detect) = sunspec.detect_base_address {
+            config.detect_base_address = detect;
+        }
+        if let Some(count) = sunspec.discovery_register_count {
+            config.discovery_register_count = count;
+        }
+        if let Some(dir) = sunspec.model_dir {
+            config.sunspec_model_dir = Some(dir);
+        }
+        if let Some(path) = sunspec.catalog_path {
+            config.sunspec_catalog_path = Some(path);
+        }
+    }
+
+    if let Some(buffer) = file.buffer {
+        if let Some(path) = buffer.path {
+            config.buffer_path = path;
+        }
+        if let Some(batch) = buffer.batch_size {
+            config.buffer_batch_size = batch.max(1);
+        }
+        if let Some(interval) = buffer.drain_interval_ms {
+            config.buffer_drain_interval_ms = interval;
+        }
+        if let Some(max_in_flight) = buffer.max_in_flight {
+            config.buffer_max_in_flight = max_in_flight;
+        }
+        if let Some(max_age) = buffer.max_age_ms {
+            config.buffer_max_age_ms = max_age;
+        }
+        if let Some(topics) = buffer.topic_max_age {
+            config.buffer_topic_max_age = topics
+                .into_iter()
+                .map(|topic| TopicMaxAge {
+                    topic: topic.topic,
+                    max_age_ms: topic.max_age_ms,
+                })
+                .collect();
+        }
+        if let Some(topic) = buffer.dead_letter_topic {
+            config.buffer_dead_letter_topic = Some(topic);
+        }
+        if let Some(enabled) = buffer.write_through {
+            config.buffer_write_through = enabled;
+        }
+        if let Some(order) = buffer.drain_order {
+            config.buffer_drain_order = Some(order);
+        }
+        if let Some(window) = buffer.live_window_ms {
+            config.buffer_live_window_ms = window;
+        }
+        if let Some(share) = buffer.backlog_share_pct {
+            config.buffer_backlog_share_pct = share;
+        }
+        if let Some(enabled) = buffer.start_from_inventory {
+            config.buffer_start_from_inventory = enabled;
+        }
+        if let Some(interval) = buffer.compact_interval_ms {
+            config.buffer_compact_interval_ms = interval;
+        }
+    }
+
+    if let Some(dedup) = file.dedup {
+        if let Some(enabled) = dedup.enabled {
+            config.dedup_enabled = enabled;
+        }
+        if let Some(max_ms) = dedup.max_suppression_ms {
+            config.dedup_max_suppression_ms = max_ms;
+        }
+    }
+
+    if let Some(daily) = file.daily {
+        if let Some(enabled) = daily.enabled {
+            config.daily_enabled = enabled;
+        }
+        if let Some(topic) = daily.topic {
+            config.daily_topic = topic;
+        }
+        if let Some(path) = daily.csv_path {
+            config.daily_csv_path = path;
+        }
+        if let Some(offset) = daily.utc_offset_minutes {
+            config.daily_utc_offset_minutes = offset;
+        }
+    }
+
+    if let Some(alarms) = file.alarms {
+        if let Some(enabled) = alarms.enabled {
+            config.alarms_enabled = enabled;
+        }
+        if let Some(topic) = alarms.topic {
+            config.alarms_topic = topic;
+        }
+    }
+
+    if let Some(alerts) = file.alerts {
+        if let Some(enabled) = alerts.enabled {
+            config.alerts_enabled = enabled;
+        }
+        if let Some(topic) = alerts.topic {
+            config.alerts_topic = topic;
+        }
+        if let Some(rules) = alerts.rules {
+            config.alert_rules = rules.into_iter().map(|rule| rule.0).collect();
+        }
+    }
+
+    if let Some(plant) = file.plant_snapshot {
+        if let Some(enabled) = plant.enabled {
+            config.plant_snapshot_enabled = enabled;
+        }
+        if let Some(topic) = plant.topic {
+            config.plant_snapshot_topic = topic;
+        }
+        if let Some(interval_ms) = plant.interval_ms {
+            config.plant_snapshot_interval_ms = interval_ms;
+        }
+        if let Some(window_ms) = plant.window_ms {
+            config.plant_snapshot_window_ms = window_ms;
+        }
+    }
+
+    if let Some(sink) = file.file_sink {
+        if let Some(enabled) = sink.enabled {
+            config.file_sink_enabled = enabled;
+        }
+        if let Some(dir) = sink.dir {
+            config.file_sink_dir = dir;
+        }
+        if let Some(max_files) = sink.max_files {
+            config.file_sink_max_files = max_files;
+        }
+        if let Some(max_bytes) = sink.max_bytes {
+            config.file_sink_max_bytes = max_bytes;
+        }
+    }
+
+    if let Some(sparkplug) = file.sparkplug {
+        if let Some(enabled) = sparkplug.enabled {
+            config.sparkplug_enabled = enabled;
+        }
+        if let Some(topic) = sparkplug.topic {
+            config.sparkplug_topic = topic;
+        }
+        if let Some(group_id) = sparkplug.group_id {
+            config.sparkplug_group_id = group_id;
+        }
+        if let Some(edge_node_id) = sparkplug.edge_node_id {
+            config.sparkplug_edge_node_id = edge_node_id;
+        }
+    }
+
+    if let Some(clock) = file.clock {
+        if let Some(enabled) = clock.enabled {
+            config.clock_enabled = enabled;
+        }
+        if let Some(interval) = clock.interval_ms {
+            config.clock_interval_ms = interval;
+        }
+        if let Some(threshold) = clock.set_threshold_ms {
+            config.clock_set_threshold_ms = threshold;
+        }
+        if let Some(enabled) = clock.writes_enabled {
+            config.clock_writes_enabled = enabled;
+        }
+        if let Some(devices) = clock.devices {
+            config.clock_devices = devices
+                .into_iter()
+                .map(|device| DeviceClock {
+                    ip: device.ip,
+                    unit_id: device.unit_id,
+                    address: device.address,
+                    epoch: device.epoch.unwrap_or_default(),
+                    writable: device.writable.unwrap_or(false),
+                })
+                .collect();
+        }
+    }
+
+    if let Some(maps) = file.model_maps {
+        config.model_maps = maps
+            .into_iter()
+            .map(|map| StaticModelMap {
+                ip: map.ip,
+                unit_id: map.unit_id.unwrap_or(1),
+                models: map
+                    .models
+                    .into_iter()
+                    .map(|model| StaticModel {
+                        id: model.id,
+                        name: model.name.unwrap_or_else(|| format!("model_{}", model.id)),
+                        start: model.start,
+                        length: model.length,
+                        points: model
+                            .points
+                            .into_iter()
+                            .map(|point| StaticPoint {
+                                name: point.name,
+                                offset: point.offset,
+                                point_type: point.point_type,
+                                scale: point.scale.unwrap_or(0),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+    }
+
+    if let Some(quarantine) = file.quarantine {
+        if let Some(delay) = quarantine.initial_probe_ms {
+            config.quarantine_initial_probe_ms = delay;
+        }
+        if let Some(delay) = quarantine.max_probe_ms {
+            config.quarantine_max_probe_ms = delay;
+        }
+        if let Some(failures) = quarantine.after_failures {
+            config.quarantine_after_failures = failures;
+        }
+    }
+
+    if let Some(anomaly) = file.anomaly {
+        if let Some(enabled) = anomaly.enabled {
+            config.anomaly_enabled = enabled;
+        }
+        if let Some(interval) = anomaly.interval_ms {
+            config.anomaly_interval_ms = interval;
+        }
+        if let Some(threshold) = anomaly.z_threshold {
+            config.anomaly_z_threshold = threshold;
+        }
+        if let Some(deficit) = anomaly.min_deficit_pct {
+            config.anomaly_min_deficit_pct = deficit;
+        }
+        if let Some(power) = anomaly.min_power_w {
+            config.anomaly_min_power_w = power;
+        }
+        if let Some(groups) = anomaly.groups {
+            config.anomaly_groups = groups
+                .into_iter()
+                .map(|group| DeviceGroup {
+                    name: group.name,
+                    devices: group.devices,
+                })
+                .collect();
+        }
+    }
+
+    if let Some(models) = file.aggregation.and_then(|aggregation| aggregation.models) {
+        config.aggregation_windows = models
+            .into_iter()
+            .map(|model| ModelWindow {
+                model_id: model.model_id,
+                window_ms: model.window_ms,
+            })
+            .collect();
+    }
+
+    if let Some(profiles) = file.profiles {
+        let mut profiles: Vec<DeviceProfile> = profiles
+            .into_iter()
+            .map(|(name, profile)| DeviceProfile {
+                name,
+                only_models: profile.only_models.unwrap_or_default(),
+                skip_models: profile.skip_models.unwrap_or_default(),
+                model_intervals: profile
+                    .model_intervals
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|interval| ModelInterval {
+                        model_id: interval.model_id,
+                        interval_ms: interval.interval_ms,
+                    })
+                    .collect(),
+                scan_classes: profile
+                    .scan_classes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|class| ScanClassConfig {
+                        model_id: class.model_id,
+                        points: class.points,
+                        full_interval_ms: class.full_interval_ms,
+                    })
+                    .collect(),
+                class: profile.class,
+            })
+            .collect();
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        config.device_profiles = profiles;
+    }
+
+    if let Some(rules) = file.rules {
+        config.device_rules = rules
+            .into_iter()
+            .map(|rule| DeviceRule {
+                manufacturer: rule.manufacturer,
+                model_contains: rule.model_contains,
+                profile: rule.profile,
+            })
+            .collect();
+    }
+
+    if let Some(models) = file.models {
+        config.model_filter = model_filter(models.include, models.exclude);
+        if let Some(devices) = models.devices {
+            config.device_model_filters = devices
+                .into_iter()
+                .map(|device| DeviceModelFilter {
+                    ip: device.ip,
+                    unit_id: device.unit_id,
+                    filter: model_filter(device.include, device.exclude),
+                })
+                .collect();
+        }
+    }
+
+    if let Some(maintenance) = file.maintenance {
+        if let Some(offset) = maintenance.utc_offset_minutes {
+            config.maintenance_utc_offset_minutes = offset;
+        }
+        if let Some(windows) = maintenance.windows {
+            config.maintenance_windows = windows
+                .into_iter()
+                .map(|window| MaintenanceWindow {
+                    name: window.name,
+                    schedule: window.schedule,
+                    duration_ms: window.duration_ms,
+                    pause_polling: window.pause_polling.unwrap_or(false),
+                })
+                .collect();
+        }
+    }
+
+    if let Some(simulator) = file.simulator {
+        if let Some(port) = simulator.port {
+            config.simulator_port = port;
+        }
+        if let Some(unit_ids) = simulator.unit_ids {
+            config.simulator_unit_ids = unit_ids;
+        }
+        if let Some(models) = simulator.models {
+            config.simulator_models = models;
+        }
+    }
+
+    if let Some(devices) = file.synthetic.and_then(|synthetic| synthetic.devices) {
+        config.synthetic_devices = devices
+            .into_iter()
+            .map(|device| SyntheticDevice {
+                name: device.name,
+                unit_id: device.unit_id.unwrap_or(1),
+                models: device.models.unwrap_or_else(|| vec![103]),
+                rated_w: device.rated_w.unwrap_or(DEFAULT_SYNTHETIC_RATED_W),
+            })
+            .collect();
+    }
+
+    if let Some(kafka) = file.kafka {
+        if let Some(brokers) = kafka.brokers {
+            config.kafka_brokers = Some(brokers);
+        }
+        if let Some(topic) = kafka.topic {
+            config.kafka_topic = Some(topic);
+        }
+        if let Some(namespace) = kafka.namespace {
+            config.kafka_namespace = Some(namespace);
+        }
+        if let Some(client_id) = kafka.client_id {
+            config.kafka_client_id = Some(client_id);
+        }
+        if let Some(acks) = kafka.acks {
+            config.kafka_acks = Some(acks);
+        }
+        if let Some(compression) = kafka.compression {
+            config.kafka_compression = Some(compression);
+        }
+        if let Some(timeout_ms) = kafka.timeout_ms {
+            config.kafka_timeout_ms = Some(timeout_ms);
+        }
+        if let Some(enable_idempotence) = kafka.enable_idempotence {
+            config.kafka_enable_idempotence = Some(enable_idempotence);
+        }
+        if let Some(max_bytes) = kafka.max_message_bytes {
+            config.kafka_max_message_bytes = Some(max_bytes);
+        }
+        if let Some(encoding) = kafka.encoding {
+            config.kafka_encoding = Some(encoding);
+        }
+        if let Some(codec) = kafka.avro_codec {
+            config.kafka_avro_codec = Some(codec);
+        }
+        if let Some(schema_topic) = kafka.schema_topic {
+            config.kafka_schema_topic = schema_topic;
+        }
+        if let Some(refresh_ms) = kafka.bootstrap_refresh_ms {
+            config.kafka_bootstrap_refresh_ms = refresh_ms;
+        }
+        if let Some(id) = kafka.transactional_id {
+            config.kafka_transactional_id = Some(id);
         }
-        if let Some(timeout) = discovery.per_host_timeout_ms {
-            config.discovery.per_host_timeout_ms = timeout;
+        if let Some(topic) = kafka.commit_topic {
+            config.kafka_commit_topic = topic;
         }
-        if let Some(devices) = discovery.static_devices {
-            config.discovery.static_devices = devices
+        let security = &mut config.kafka_security;
+        for (value, field) in [
+            (kafka.security_protocol, &mut security.protocol),
+            (kafka.sasl_mechanism, &mut security.sasl_mechanism),
+            (kafka.sasl_username, &mut security.sasl_username),
+            (kafka.sasl_password, &mut security.sasl_password),
+            (kafka.ssl_ca_location, &mut security.ssl_ca_location),
+            (
+                kafka.ssl_certificate_location,
+                &mut security.ssl_certificate_location,
+            ),
+            (kafka.ssl_key_location, &mut security.ssl_key_location),
+        ] {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+        if let Some(routes) = kafka.routes {
+            config.kafka_routes = routes
                 .into_iter()
-                .map(|device| DeviceIdentity {
-                    ip: device.ip,
-                    unit_id: device.unit_id.unwrap_or(1),
+                .map(|route| TopicRoute {
+                    models: route
+                        .models
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|range| range.0)
+                        .collect(),
+                    ip: route.ip,
+                    unit_id: route.unit_id,
+                    topic: route.topic,
                 })
                 .collect();
         }
     }
 
-    if let Some(poller) = file.poller {
-        if let Some(interval_ms) = poller.poll_interval_ms {
-            config.poller.poll_interval = Duration::from_millis(interval_ms);
+    if let Some(site) = file.site {
+        config.site = Some(SiteInfo {
+            site_id: site.site_id.unwrap_or_default(),
+            plant: site.plant,
+            latitude: site.latitude,
+            longitude: site.longitude,
+            labels: site.labels.unwrap_or_default(),
+        });
+    }
+
+    if let Some(logging) = file.logging {
+        if let Some(format) = logging.format {
+            config.logging.format = format;
         }
-        if let Some(timeout_ms) = poller.request_timeout_ms {
-            config.poller.request_timeout = Duration::from_millis(timeout_ms);
+        if let Some(level) = logging.level {
+            config.logging.level = level;
         }
-        if let Some(jitter_ms) = poller.jitter_ms {
-            config.poller.jitter_ms = jitter_ms;
+        if let Some(filters) = logging.filters {
+            config.logging.filters = filters;
         }
     }
 
-    if let Some(modbus) = file.modbus {
-        if let Some(port) = modbus.port {
-            config.modbus.port = port;
-            config.discovery.port = port;
+    if let Some(otel) = file.otel {
+        if otel.endpoint.is_some() {
+            config.otel.endpoint = otel.endpoint;
         }
-        if let Some(max_batch) = modbus.max_batch_size {
-            config.modbus.max_batch_size = Some(max_batch);
+        if let Some(name) = otel.service_name {
+            config.otel.service_name = name;
         }
-        if let Some(timeout_ms) = modbus.timeout_ms {
-            config.modbus.timeout_ms = timeout_ms;
+        if let Some(ratio) = otel.sample_ratio {
+            config.otel.sample_ratio = ratio;
         }
-        if let Some(retry_count) = modbus.retry_count {
-            config.modbus.retry_count = retry_count;
+    }
+
+    if let Some(sink) = file.grpc_sink {
+        if sink.endpoint.is_some() {
+            config.grpc_sink.endpoint = sink.endpoint;
         }
-        if let Some(backoff) = modbus.retry_backoff_ms {
-            config.modbus.retry_backoff_ms = backoff;
+        if let Some(size) = sink.batch_size {
+            config.grpc_sink.batch_size = size;
         }
-        if let Some(max_backoff) = modbus.retry_max_backoff_ms {
-            config.modbus.retry_max_backoff_ms = max_backoff;
+        if let Some(max) = sink.max_in_flight {
+            config.grpc_sink.max_in_flight = max;
         }
-        if let Some(delay) = modbus.inter_read_delay_ms {
-            config.modbus.inter_read_delay_ms = Some(delay);
+        if let Some(ms) = sink.retry_backoff_ms {
+            config.grpc_sink.retry_backoff_ms = ms;
+        }
+        if let Some(ms) = sink.retry_max_backoff_ms {
+            config.grpc_sink.retry_max_backoff_ms = ms;
         }
     }
 
-    if let Some(sunspec) = file.sunspec {
-        if let Some(base) = sunspec.base_address {
-            config.base_address = base;
+    if let Some(influx) = file.influx {
+        for (value, field) in [
+            (influx.url, &mut config.influx.url),
+            (influx.org, &mut config.influx.org),
+            (influx.bucket, &mut config.influx.bucket),
+            (influx.token, &mut config.influx.token),
+        ] {
+            if value.is_some() {
+                *field = value;
+            }
         }
-        if let Some(count) = sunspec.discovery_register_count {
-            config.discovery_register_count = count;
+        if let Some(measurement) = influx.measurement {
+            config.influx.measurement = measurement;
+        }
+        if let Some(tags) = influx.tags {
+            config.influx.tags = tags;
+        }
+        if let Some(size) = influx.batch_size {
+            config.influx.batch_size = size;
+        }
+        if let Some(ms) = influx.flush_interval_ms {
+            config.influx.flush_interval_ms = ms;
         }
     }
 
-    if let Some(buffer) = file.buffer {
-        if let Some(path) = buffer.path {
-            config.buffer_path = path;
+    if let Some(postgres) = file.postgres {
+        if postgres.dsn.is_some() {
+            config.postgres.dsn = postgres.dsn;
         }
-        if let Some(batch) = buffer.batch_size {
-            config.buffer_batch_size = batch.max(1);
+        if let Some(table) = postgres.table {
+            config.postgres.table = table;
         }
-        if let Some(interval) = buffer.drain_interval_ms {
-            config.buffer_drain_interval_ms = interval;
+        if let Some(size) = postgres.batch_size {
+            config.postgres.batch_size = size;
+        }
+        if let Some(mode) = postgres.on_conflict {
+            config.postgres.on_conflict = mode;
+        }
+        if let Some(create) = postgres.create_table {
+            config.postgres.create_table = create;
+        }
+        if let Some(path) = postgres.buffer_path {
+            config.postgres.buffer_path = path;
+        }
+        if let Some(ms) = postgres.connect_timeout_ms {
+            config.postgres.connect_timeout_ms = ms;
         }
     }
 
-    if let Some(kafka) = file.kafka {
-        if let Some(brokers) = kafka.brokers {
-            config.kafka_brokers = Some(brokers);
+    if let Some(capture) = file.capture {
+        if let Some(dir) = capture.dir {
+            config.capture.dir = dir;
         }
-        if let Some(topic) = kafka.topic {
-            config.kafka_topic = Some(topic);
+        if let Some(bytes) = capture.max_file_bytes {
+            config.capture.max_file_bytes = bytes;
         }
-        if let Some(client_id) = kafka.client_id {
-            config.kafka_client_id = Some(client_id);
+        if let Some(max) = capture.max_files {
+            config.capture.max_files = max;
         }
-        if let Some(acks) = kafka.acks {
-            config.kafka_acks = Some(acks);
+    }
+
+    if let Some(id) = file.instance_id {
+        config.instance_id = id;
+    }
+    if let Some(leader) = file.leader {
+        if let Some(enabled) = leader.enabled {
+            config.leader.enabled = enabled;
         }
-        if let Some(compression) = kafka.compression {
-            config.kafka_compression = Some(compression);
+        if let Some(path) = leader.lease_path {
+            config.leader.lease_path = path;
         }
-        if let Some(timeout_ms) = kafka.timeout_ms {
-            config.kafka_timeout_ms = Some(timeout_ms);
+        if let Some(ms) = leader.lease_ms {
+            config.leader.lease_ms = ms;
         }
-        if let Some(enable_idempotence) = kafka.enable_idempotence {
-            config.kafka_enable_idempotence = Some(enable_idempotence);
+        if let Some(ms) = leader.renew_interval_ms {
+            config.leader.renew_interval_ms = ms;
+        }
+    }
+    if let Some(timesync) = file.timesync {
+        if let Some(check) = timesync.check {
+            config.timesync.check = check;
+        }
+        if let Some(ms) = timesync.interval_ms {
+            config.timesync.interval_ms = ms;
+        }
+        if let Some(ms) = timesync.max_offset_ms {
+            config.timesync.max_offset_ms = ms;
+        }
+        if let Some(enabled) = timesync.monotonic_timestamps {
+            config.timesync.monotonic_timestamps = enabled;
+        }
+    }
+    if let Some(resources) = file.resources {
+        if let Some(ms) = resources.interval_ms {
+            config.resources.interval_ms = ms;
+        }
+        if let Some(bytes) = resources.min_free_disk_bytes {
+            config.resources.min_free_disk_bytes = bytes;
+        }
+        if let Some(bytes) = resources.max_buffer_bytes {
+            config.resources.max_buffer_bytes = bytes;
+        }
+        if let Some(bytes) = resources.max_rss_bytes {
+            config.resources.max_rss_bytes = bytes;
+        }
+        if let Some(pct) = resources.max_channel_fill_pct {
+            config.resources.max_channel_fill_pct = pct;
+        }
+        if let Some(factor) = resources.throttle_factor {
+            config.resources.throttle_factor = factor;
+        }
+        if let Some(rows) = resources.evict_rows {
+            config.resources.evict_rows = rows;
         }
     }
 }
 
+fn parse_env_u8(key: &str) -> Option<u8> {
+    env::var(key).ok().and_then(|value| value.parse().ok())
+}
+
 fn parse_env_u16(key: &str) -> Option<u16> {
     env::var(key).ok().and_then(|value| value.parse().ok())
 }
 
+fn parse_env_u32(key: &str) -> Option<u32> {
+    env::var(key).ok().and_then(|value| value.parse().ok())
+}
+
 fn parse_env_u64(key: &str) -> Option<u64> {
     env::var(key).ok().and_then(|value| value.parse().ok())
 }
 
+/// Milliseconds (`500`) or a humantime duration (`"2s"`, `"5m"`).
+fn parse_env_duration_ms(key: &str) -> Option<u64> {
+    env::var(key)
+        .ok()
+        .and_then(|value| duration_ms::parse(&value).ok())
+}
+
 fn parse_env_usize(key: &str) -> Option<usize> {
     env::var(key).ok().and_then(|value| value.parse().ok())
 }
@@ -456,10 +3068,72 @@ fn parse_env_i64(key: &str) -> Option<i64> {
     env::var(key).ok().and_then(|value| value.parse().ok())
 }
 
+fn parse_env_i32(key: &str) -> Option<i32> {
+    env::var(key).ok().and_then(|value| value.parse().ok())
+}
+
+fn parse_env_f64(key: &str) -> Option<f64> {
+    env::var(key).ok().and_then(|value| value.parse().ok())
+}
+
 fn parse_env_bool(key: &str) -> Option<bool> {
     env::var(key).ok().and_then(|value| value.parse().ok())
 }
 
+fn parse_unit_id_list(value: &str) -> Vec<u8> {
+    value
+        .split(',')
+        .filter_map(|entry| entry.trim().parse::<u8>().ok())
+        .collect()
+}
+
+/// `region=north,operator=acme`; an entry without `=` becomes a label with
+/// an empty value, which validation rejects.
+fn parse_label_list(value: &str) -> BTreeMap<String, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once('=') {
+            Some((key, value)) => (key.trim().to_string(), value.trim().to_string()),
+            None => (entry.to_string(), String::new()),
+        })
+        .collect()
+}
+
+fn parse_model_list(value: &str) -> Vec<u16> {
+    value
+        .split(',')
+        .filter_map(|entry| entry.trim().parse::<u16>().ok())
+        .collect()
+}
+
+fn model_filter(
+    include: Option<Vec<FileModelRange>>,
+    exclude: Option<Vec<FileModelRange>>,
+) -> ModelFilter {
+    let ranges = |ranges: Option<Vec<FileModelRange>>| {
+        ranges
+            .unwrap_or_default()
+            .into_iter()
+            .map(|range| range.0)
+            .collect()
+    };
+    ModelFilter {
+        include: ranges(include),
+        exclude: ranges(exclude),
+    }
+}
+
+/// Model IDs and ranges, e.g. `1,64001-65535`.
+fn parse_model_ranges(value: &str) -> Vec<ModelRange> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(ModelRange::parse)
+        .collect()
+}
+
 fn parse_static_devices(value: &str) -> Vec<DeviceIdentity> {
     value
         .split(',')
@@ -475,6 +3149,36 @@ fn parse_static_devices(value: &str) -> Vec<DeviceIdentity> {
             Some(DeviceIdentity {
                 ip: ip.to_string(),
                 unit_id: unit,
+                base_address: None,
+            })
+        })
+        .collect()
+}
+
+/// `model:window` pairs, e.g. `103:60000,160:1m`.
+fn parse_model_windows(value: &str) -> Vec<ModelWindow> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (model_id, window_ms) = entry.trim().split_once(':')?;
+            Some(ModelWindow {
+                model_id: model_id.trim().parse().ok()?,
+                window_ms: duration_ms::parse(window_ms).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// `topic:max_age` pairs, e.g. `sunspec.telemetry:604800000` or
+/// `sunspec.telemetry:7d`.
+fn parse_topic_max_ages(value: &str) -> Vec<TopicMaxAge> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (topic, max_age_ms) = entry.trim().split_once(':')?;
+            Some(TopicMaxAge {
+                topic: topic.trim().to_string(),
+                max_age_ms: duration_ms::parse(max_age_ms).ok()?,
             })
         })
         .collect()
@@ -495,6 +3199,328 @@ fn validate_cidr(value: &str) -> Result<()> {
     Ok(())
 }
 
+/// Static devices are addressed by IPv4 address or host name; names are
+/// resolved when the device is connected.
+fn validate_device_host(host: &str) -> Result<()> {
+    if host.parse::<Ipv4Addr>().is_ok() {
+        return Ok(());
+    }
+    let valid_name = host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if !valid_name {
+        anyhow::bail!("static device '{host}' is not an IPv4 address or host name");
+    }
+    Ok(())
+}
+
+/// Sparkplug IDs are MQTT topic levels and may not contain `/`, `+` or `#`.
+fn validate_sparkplug_id(field: &str, value: &str) -> Result<()> {
+    if value.trim().is_empty() {
+        anyhow::bail!("{field} must be non-empty");
+    }
+    if value.contains(['/', '+', '#']) {
+        anyhow::bail!("{field} must not contain '/', '+' or '#'");
+    }
+    Ok(())
+}
+
+/// Protocols and SASL mechanisms that work with a username and password; the
+/// names are librdkafka's.
+const KAFKA_SECURITY_PROTOCOLS: &[&str] = &["plaintext", "ssl", "sasl_plaintext", "sasl_ssl"];
+const KAFKA_SASL_MECHANISMS: &[&str] = &["PLAIN", "SCRAM-SHA-256", "SCRAM-SHA-512"];
+
+fn validate_kafka_security(security: &KafkaSecurity) -> Result<()> {
+    let protocol = security
+        .protocol
+        .as_deref()
+        .unwrap_or("plaintext")
+        .to_ascii_lowercase();
+    if !KAFKA_SECURITY_PROTOCOLS.contains(&protocol.as_str()) {
+        anyhow::bail!("kafka.security_protocol must be plaintext, ssl, sasl_plaintext or sasl_ssl");
+    }
+    let sasl_fields = [
+        ("sasl_mechanism", &security.sasl_mechanism),
+        ("sasl_username", &security.sasl_username),
+        ("sasl_password", &security.sasl_password),
+    ];
+    if protocol.starts_with("sasl_") {
+        for (name, value) in sasl_fields {
+            let value = value.as_deref().unwrap_or_default();
+            if value.trim().is_empty() {
+                anyhow::bail!(
+                    "kafka.{name} must be set when kafka.security_protocol is {protocol}"
+                );
+            }
+        }
+        let mechanism = security.sasl_mechanism.as_deref().unwrap_or_default();
+        if !KAFKA_SASL_MECHANISMS.contains(&mechanism) {
+            anyhow::bail!("kafka.sasl_mechanism must be PLAIN, SCRAM-SHA-256 or SCRAM-SHA-512");
+        }
+    } else if let Some((name, _)) = sasl_fields.iter().find(|(_, value)| value.is_some()) {
+        anyhow::bail!("kafka.{name} requires kafka.security_protocol sasl_plaintext or sasl_ssl");
+    }
+
+    let ssl_fields = [
+        ("ssl_ca_location", &security.ssl_ca_location),
+        (
+            "ssl_certificate_location",
+            &security.ssl_certificate_location,
+        ),
+        ("ssl_key_location", &security.ssl_key_location),
+    ];
+    for (name, value) in ssl_fields {
+        if value.is_none() {
+            continue;
+        }
+        if !protocol.ends_with("ssl") {
+            anyhow::bail!("kafka.{name} requires kafka.security_protocol ssl or sasl_ssl");
+        }
+        if value.as_deref().is_some_and(|path| path.trim().is_empty()) {
+            anyhow::bail!("kafka.{name} must be non-empty when set");
+        }
+    }
+    if security.ssl_certificate_location.is_some() != security.ssl_key_location.is_some() {
+        anyhow::bail!(
+            "kafka.ssl_certificate_location and kafka.ssl_key_location must be set together"
+        );
+    }
+    Ok(())
+}
+
+/// Checks the template, then the topic it renders for a typical inverter,
+/// so a route that could never produce a valid name fails at load.
+fn validate_kafka_route(route: &TopicRoute) -> Result<()> {
+    check_template(&route.topic).map_err(anyhow::Error::msg)?;
+    if let Some(ref ip) = route.ip {
+        if ip.parse::<Ipv4Addr>().is_err() {
+            anyhow::bail!("ip '{ip}' is not an IPv4 address");
+        }
+    }
+    let device = DeviceIdentity {
+        ip: route
+            .ip
+            .clone()
+            .unwrap_or_else(|| "192.168.1.20".to_string()),
+        unit_id: route.unit_id.unwrap_or(1),
+        base_address: None,
+    };
+    let model_id = route.models.first().map_or(103, |range| range.first);
+    let sample = PollSample::new(
+        device,
+        model_id,
+        "three_phase_inverter",
+        40_070,
+        Vec::new(),
+        0,
+    );
+    validate_kafka_topic(&render_topic(&route.topic, &sample))
+}
+
+fn validate_otel(otel: &OtelSettings) -> Result<()> {
+    if let Some(ref endpoint) = otel.endpoint {
+        if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) {
+            anyhow::bail!("otel.endpoint must be an http:// or https:// URL");
+        }
+    }
+    if otel.service_name.trim().is_empty() {
+        anyhow::bail!("otel.service_name must be non-empty");
+    }
+    if !(0.0..=1.0).contains(&otel.sample_ratio) {
+        anyhow::bail!("otel.sample_ratio must be between 0 and 1");
+    }
+    Ok(())
+}
+
+fn validate_grpc_sink(sink: &GrpcSinkSettings) -> Result<()> {
+    sink.channel_endpoint().map_err(anyhow::Error::msg)?;
+    if sink.batch_size == 0 {
+        anyhow::bail!("grpc_sink.batch_size must be >= 1");
+    }
+    if sink.max_in_flight == 0 {
+        anyhow::bail!("grpc_sink.max_in_flight must be >= 1");
+    }
+    if sink.retry_backoff_ms == 0 {
+        anyhow::bail!("grpc_sink.retry_backoff_ms must be >= 1");
+    }
+    if sink.retry_max_backoff_ms < sink.retry_backoff_ms {
+        anyhow::bail!("grpc_sink.retry_max_backoff_ms must be >= grpc_sink.retry_backoff_ms");
+    }
+    Ok(())
+}
+
+fn validate_influx(influx: &InfluxSinkSettings) -> Result<()> {
+    influx.write_url().map_err(anyhow::Error::msg)?;
+    LineEncoder::new(influx).map_err(anyhow::Error::msg)?;
+    if influx.batch_size == 0 {
+        anyhow::bail!("influx.batch_size must be >= 1");
+    }
+    if influx.flush_interval_ms == 0 {
+        anyhow::bail!("influx.flush_interval_ms must be >= 1");
+    }
+    Ok(())
+}
+
+fn validate_postgres(postgres: &PostgresSinkSettings, buffer_path: &str) -> Result<()> {
+    postgres.connect_options().map_err(anyhow::Error::msg)?;
+    postgres.table().map_err(anyhow::Error::msg)?;
+    postgres.on_conflict().map_err(anyhow::Error::msg)?;
+    if !(1..=postgres_sink::MAX_BATCH_SIZE).contains(&postgres.batch_size) {
+        anyhow::bail!(
+            "postgres.batch_size must be between 1 and {}",
+            postgres_sink::MAX_BATCH_SIZE
+        );
+    }
+    if postgres.dsn.is_some() {
+        if postgres.buffer_path.trim().is_empty() {
+            anyhow::bail!("postgres.buffer_path must be non-empty");
+        }
+        if postgres.buffer_path == buffer_path {
+            anyhow::bail!("postgres.buffer_path must differ from buffer.path");
+        }
+    }
+    if postgres.connect_timeout_ms == 0 {
+        anyhow::bail!("postgres.connect_timeout_ms must be >= 1");
+    }
+    Ok(())
+}
+
+fn validate_capture(capture: &CaptureSettings) -> Result<()> {
+    if capture.dir.trim().is_empty() {
+        anyhow::bail!("capture.dir must be non-empty");
+    }
+    if capture.max_file_bytes == 0 {
+        anyhow::bail!("capture.max_file_bytes must be >= 1");
+    }
+    Ok(())
+}
+
+fn validate_leader(instance_id: &str, leader: &LeaderSettings) -> Result<()> {
+    if instance_id.trim().is_empty() {
+        anyhow::bail!("instance_id must be non-empty");
+    }
+    if !leader.enabled {
+        return Ok(());
+    }
+    if leader.lease_path.trim().is_empty() {
+        anyhow::bail!("leader.lease_path must be non-empty");
+    }
+    if leader.renew_interval_ms == 0 {
+        anyhow::bail!("leader.renew_interval_ms must be >= 1");
+    }
+    // A leader renews at least twice per lease, so one slow renewal does
+    // not hand the lease over.
+    if leader.lease_ms < leader.renew_interval_ms * 2 {
+        anyhow::bail!("leader.lease_ms must be at least twice leader.renew_interval_ms");
+    }
+    Ok(())
+}
+
+fn validate_timesync(timesync: &TimeSyncSettings) -> Result<()> {
+    timesync.check().map_err(anyhow::Error::msg)?;
+    if timesync.interval_ms == 0 {
+        anyhow::bail!("timesync.interval_ms must be >= 1");
+    }
+    Ok(())
+}
+
+fn validate_resources(resources: &ResourceSettings) -> Result<()> {
+    if resources.interval_ms == 0 {
+        anyhow::bail!("resources.interval_ms must be >= 1");
+    }
+    if resources.max_channel_fill_pct > 100 {
+        anyhow::bail!("resources.max_channel_fill_pct must be between 0 and 100");
+    }
+    if resources.throttle_factor == 0 {
+        anyhow::bail!("resources.throttle_factor must be >= 1");
+    }
+    Ok(())
+}
+
+fn validate_site(site: &SiteInfo) -> Result<()> {
+    if site.site_id.trim().is_empty() {
+        anyhow::bail!("site.site_id must be non-empty when the site is configured");
+    }
+    if site
+        .plant
+        .as_deref()
+        .is_some_and(|plant| plant.trim().is_empty())
+    {
+        anyhow::bail!("site.plant must be non-empty when set");
+    }
+    if site.latitude.is_some() != site.longitude.is_some() {
+        anyhow::bail!("site.latitude and site.longitude must be set together");
+    }
+    if site
+        .latitude
+        .is_some_and(|latitude| !(-90.0..=90.0).contains(&latitude))
+    {
+        anyhow::bail!("site.latitude must be between -90 and 90");
+    }
+    if site
+        .longitude
+        .is_some_and(|longitude| !(-180.0..=180.0).contains(&longitude))
+    {
+        anyhow::bail!("site.longitude must be between -180 and 180");
+    }
+    for (key, value) in &site.labels {
+        if key.trim().is_empty() || value.trim().is_empty() {
+            anyhow::bail!("site.labels entries need a non-empty key and value ('{key}')");
+        }
+    }
+    Ok(())
+}
+
+fn validate_alert_rule(rule: &AlertRule) -> Result<()> {
+    if rule.name.trim().is_empty() {
+        anyhow::bail!("alerts.rules entries need a name");
+    }
+    if rule.point.trim().is_empty() {
+        anyhow::bail!("alerts.rules {}: point must be non-empty", rule.name);
+    }
+    let limit = rule.condition.limit();
+    if !limit.is_finite() {
+        anyhow::bail!("alerts.rules {}: limit must be a finite number", rule.name);
+    }
+    if rule.condition.is_rate() && limit <= 0.0 {
+        anyhow::bail!("alerts.rules {}: rates must be > 0", rule.name);
+    }
+    if !rule.deadband.is_finite() || rule.deadband < 0.0 {
+        anyhow::bail!("alerts.rules {}: deadband must be >= 0", rule.name);
+    }
+    Ok(())
+}
+
+/// Offsets that leave some register addresses in range.
+fn valid_address_offset(offset: i32) -> bool {
+    offset.unsigned_abs() <= u32::from(u16::MAX)
+}
+
+/// Namespaces are joined to the topic with a dot, so they may not contain
+/// one themselves; that keeps `a.b` + `c` apart from `a` + `b.c`.
+fn validate_kafka_namespace(namespace: &str) -> Result<()> {
+    if namespace.is_empty() || namespace.len() > MAX_KAFKA_NAMESPACE_LEN {
+        anyhow::bail!("kafka.namespace must be 1 to {MAX_KAFKA_NAMESPACE_LEN} characters");
+    }
+    if !namespace
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+    {
+        anyhow::bail!(
+            "kafka.namespace may only contain letters, digits, '_' and '-', got '{namespace}'"
+        );
+    }
+    if !namespace.starts_with(|ch: char| ch.is_ascii_alphanumeric()) {
+        anyhow::bail!("kafka.namespace must start with a letter or digit");
+    }
+    Ok(())
+}
+
 fn validate_kafka_topic(topic: &str) -> Result<()> {
     if topic.trim().is_empty() {
         anyhow::bail!("kafka.topic must be non-empty when set");
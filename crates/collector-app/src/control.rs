@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use poller_actor::{CommandError, PollSample, PollerCommand};
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::timeout;
+use tracing::info;
+
+use crate::model_map::ModelMaps;
+
+/// Commands waiting for a poller; more are refused until it catches up.
+const COMMAND_CAPACITY: usize = 16;
+/// How long an on-demand read may take, including the wait for the poller
+/// to finish its current cycle.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Why a command did not reach its poller or got no answer.
+#[derive(Debug)]
+pub enum ControlError {
+    UnknownPoller(String),
+    /// The poller stopped and was not started again (yet).
+    NotRunning,
+    /// Too many commands are queued for the poller.
+    Busy,
+    Timeout,
+    /// The poller refused the command or the read failed.
+    Command(CommandError),
+}
+
+impl fmt::Display for ControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPoller(id) => write!(f, "no poller {id}"),
+            Self::NotRunning => write!(f, "poller is not running"),
+            Self::Busy => write!(f, "poller has too many commands queued"),
+            Self::Timeout => write!(
+                f,
+                "poller did not answer within {}s",
+                READ_TIMEOUT.as_secs()
+            ),
+            Self::Command(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<CommandError> for ControlError {
+    fn from(err: CommandError) -> Self {
+        Self::Command(err)
+    }
+}
+
+impl ControlError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::UnknownPoller(_) | Self::Command(CommandError::UnknownModel(_)) => {
+                StatusCode::NOT_FOUND
+            }
+            Self::NotRunning | Self::Busy => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Command(CommandError::Paused) => StatusCode::CONFLICT,
+            Self::Command(CommandError::Read(_)) => StatusCode::BAD_GATEWAY,
+        }
+    }
+}
+
+/// Command channel of a poller's current actor, and whether it was paused
+/// by command.
+#[derive(Debug)]
+struct ControlEntry {
+    commands: mpsc::Sender<PollerCommand>,
+    paused: bool,
+}
+
+/// Sends [`PollerCommand`]s to the pollers by poller ID (`ip:unit_id`). A
+/// pause outlives the actor it was sent to: the next actor of the device
+/// starts paused.
+#[derive(Debug, Clone, Default)]
+pub struct PollerControl {
+    pollers: Arc<Mutex<HashMap<String, ControlEntry>>>,
+    maps: ModelMaps,
+}
+
+impl PollerControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Points of mapped models in read answers are decoded as `maps`
+    /// configures them.
+    pub fn with_model_maps(mut self, maps: ModelMaps) -> Self {
+        self.maps = maps;
+        self
+    }
+
+    /// A command channel for a new actor of `id`, replacing the previous
+    /// actor's, and whether the actor starts paused.
+    pub fn attach(&self, id: &str) -> (mpsc::Receiver<PollerCommand>, bool) {
+        let (commands, receiver) = mpsc::channel(COMMAND_CAPACITY);
+        let mut pollers = self.lock();
+        let paused = pollers.get(id).is_some_and(|entry| entry.paused);
+        pollers.insert(id.to_string(), ControlEntry { commands, paused });
+        (receiver, paused)
+    }
+
+    /// Forgets a poller that was removed for good.
+    pub fn remove(&self, id: &str) {
+        self.lock().remove(id);
+    }
+
+    pub fn is_paused(&self, id: &str) -> Option<bool> {
+        self.lock().get(id).map(|entry| entry.paused)
+    }
+
+    /// Pauses (or resumes) the poller. A poller that is restarting picks
+    /// the pause up when it starts again.
+    pub fn set_paused(&self, id: &str, paused: bool) -> Result<(), ControlError> {
+        let mut pollers = self.lock();
+        let entry = pollers
+            .get_mut(id)
+            .ok_or_else(|| ControlError::UnknownPoller(id.to_string()))?;
+        let command = if paused {
+            PollerCommand::Pause
+        } else {
+            PollerCommand::Resume
+        };
+        match entry.commands.try_send(command) {
+            Ok(()) | Err(mpsc::error::TrySendError::Closed(_)) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => return Err(ControlError::Busy),
+        }
+        entry.paused = paused;
+        Ok(())
+    }
+
+    /// Reads `model_id` from the poller's device right away.
+    pub async fn read(&self, id: &str, model_id: u16) -> Result<PollSample, ControlError> {
+        let (reply, answer) = oneshot::channel();
+        let command = PollerCommand::Read { model_id, reply };
+        {
+            let pollers = self.lock();
+            let entry = pollers
+                .get(id)
+                .ok_or_else(|| ControlError::UnknownPoller(id.to_string()))?;
+            entry.commands.try_send(command).map_err(|err| match err {
+                mpsc::error::TrySendError::Full(_) => ControlError::Busy,
+                mpsc::error::TrySendError::Closed(_) => ControlError::NotRunning,
+            })?;
+        }
+        match timeout(READ_TIMEOUT, answer).await {
+            Ok(Ok(result)) => Ok(result?),
+            // The actor stopped before it got to the command.
+            Ok(Err(_)) => Err(ControlError::NotRunning),
+            Err(_) => Err(ControlError::Timeout),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, ControlEntry>> {
+        self.pollers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Answer to an on-demand read.
+#[derive(Debug, Serialize)]
+pub struct ModelRead {
+    pub id: String,
+    pub model_id: u16,
+    pub model_name: String,
+    pub start: u16,
+    pub collected_at_ms: u64,
+    pub registers: Vec<u16>,
+    pub points: Vec<PointValue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PointValue {
+    pub name: String,
+    pub value: f64,
+}
+
+impl ModelRead {
+    pub fn new(id: &str, sample: PollSample, maps: &ModelMaps) -> Self {
+        let points = maps
+            .points(&sample)
+            .into_iter()
+            .map(|(name, value)| PointValue { name, value })
+            .collect();
+        Self {
+            id: id.to_string(),
+            model_id: sample.model_id,
+            model_name: sample.model_name,
+            start: sample.start,
+            collected_at_ms: sample.collected_at_ms,
+            registers: sample.registers,
+            points,
+        }
+    }
+}
+
+/// `POST /api/devices/:id/pause` and `.../resume` pause and resume a
+/// poller; `POST /api/devices/:id/models/:model_id/read` reads one model
+/// right away and answers with its registers and points.
+pub fn router(control: PollerControl) -> Router {
+    Router::new()
+        .route("/api/devices/:id/pause", post(pause))
+        .route("/api/devices/:id/resume", post(resume))
+        .route("/api/devices/:id/models/:model_id/read", post(read))
+        .with_state(control)
+}
+
+async fn pause(
+    State(control): State<PollerControl>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    switch(&control, &id, true)
+}
+
+async fn resume(
+    State(control): State<PollerControl>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    switch(&control, &id, false)
+}
+
+fn switch(
+    control: &PollerControl,
+    id: &str,
+    paused: bool,
+) -> Result<StatusCode, (StatusCode, String)> {
+    control.set_paused(id, paused).map_err(reject)?;
+    info!(poller = %id, paused, "poller pause requested through the admin API");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn read(
+    State(control): State<PollerControl>,
+    UrlPath((id, model_id)): UrlPath<(String, u16)>,
+) -> Result<Json<ModelRead>, (StatusCode, String)> {
+    let sample = control.read(&id, model_id).await.map_err(reject)?;
+    Ok(Json(ModelRead::new(&id, sample, &control.maps)))
+}
+
+fn reject(err: ControlError) -> (StatusCode, String) {
+    (err.status(), err.to_string())
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use poller_actor::PollSample;
+use serde::Serialize;
+use sunspec_parser::{
+    decode_accumulator, decode_inverter, AccumulatorTracker, INVERTER_STATE_FAULT,
+};
+use types::DeviceIdentity;
+
+use crate::aggregate::window_point;
+
+const DAY_MS: i64 = 86_400_000;
+/// Gaps between samples longer than this (device offline, collector down)
+/// are not counted as uptime or fault time.
+const MAX_SAMPLE_GAP_MS: u64 = 300_000;
+
+pub const CSV_HEADER: &str =
+    "date,ip,unit_id,energy_wh,max_power_w,uptime_minutes,fault_minutes,samples";
+
+/// One device's production for one local day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailySummary {
+    /// Local date, `YYYY-MM-DD`.
+    pub date: String,
+    pub device: DeviceIdentity,
+    pub energy_wh: f64,
+    pub max_power_w: f64,
+    pub uptime_minutes: u64,
+    pub fault_minutes: u64,
+    pub samples: u64,
+}
+
+impl DailySummary {
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{:.1},{:.1},{},{},{}",
+            self.date,
+            self.device.ip,
+            self.device.unit_id,
+            self.energy_wh,
+            self.max_power_w,
+            self.uptime_minutes,
+            self.fault_minutes,
+            self.samples
+        )
+    }
+}
+
+#[derive(Debug)]
+struct DeviceDay {
+    device: DeviceIdentity,
+    last_ms: Option<u64>,
+    last_state: Option<u16>,
+    /// Last `WH` of a float inverter, which has no counter to track.
+    last_float_wh: Option<f64>,
+    energy_wh: f64,
+    max_power_w: f64,
+    uptime_ms: u64,
+    fault_ms: u64,
+    samples: u64,
+}
+
+impl DeviceDay {
+    fn new(device: DeviceIdentity) -> Self {
+        Self {
+            device,
+            last_ms: None,
+            last_state: None,
+            last_float_wh: None,
+            energy_wh: 0.0,
+            max_power_w: 0.0,
+            uptime_ms: 0,
+            fault_ms: 0,
+            samples: 0,
+        }
+    }
+}
+
+/// Accumulates inverter samples (models 101-103 and 111-113) into
+/// per-device daily totals. Energy comes from the lifetime `WH` counter, so a
+/// counter reset only loses the interval in which it happened; a rollover
+/// past the top of the counter is counted. The float models report `WH` as a
+/// plain float32 without rollover, so a drop there is always a reset.
+#[derive(Debug, Default)]
+pub struct DailyAccumulator {
+    devices: HashMap<(String, u8), DeviceDay>,
+    energy: AccumulatorTracker<(String, u8)>,
+}
+
+impl DailyAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, sample: &PollSample) {
+        let Some(reading) = decode_inverter(sample.model_id, &sample.registers) else {
+            return;
+        };
+        let key = (sample.device.ip.clone(), sample.device.unit_id);
+        let day = self
+            .devices
+            .entry(key.clone())
+            .or_insert_with(|| DeviceDay::new(sample.device.clone()));
+
+        // Aggregated samples arrive once per window.
+        let max_gap = sample.window.as_ref().map_or(MAX_SAMPLE_GAP_MS, |window| {
+            MAX_SAMPLE_GAP_MS.max(2 * window.end_ms.saturating_sub(window.start_ms))
+        });
+        if let Some(last_ms) = day.last_ms {
+            let gap = sample.collected_at_ms.saturating_sub(last_ms);
+            if gap <= max_gap {
+                day.uptime_ms += gap;
+                if day.last_state == Some(INVERTER_STATE_FAULT) {
+                    day.fault_ms += gap;
+                }
+            }
+        }
+        if let Some(energy) = decode_accumulator(sample.model_id, &sample.registers, "WH") {
+            day.energy_wh += self.energy.update(key, energy).interval;
+        } else if let Some(total) = reading.energy_wh {
+            if let Some(last) = day.last_float_wh.filter(|last| total >= *last) {
+                day.energy_wh += total - last;
+            }
+            day.last_float_wh = Some(total);
+        }
+        let window_max = window_point(sample, "W").map(|point| point.max);
+        if let Some(power) = window_max.or(reading.power_w) {
+            day.max_power_w = day.max_power_w.max(power);
+        }
+
+        day.last_ms = Some(sample.collected_at_ms);
+        day.last_state = reading.state;
+        day.samples += 1;
+    }
+
+    /// Closes the day: returns a summary per device that reported since the
+    /// previous call and resets the totals. The last reading of each device is
+    /// kept so the interval spanning midnight counts towards the new day.
+    pub fn finish_day(&mut self, date: &str) -> Vec<DailySummary> {
+        self.devices.retain(|key, day| {
+            if day.samples == 0 {
+                self.energy.remove(key);
+            }
+            day.samples > 0
+        });
+        let mut summaries: Vec<DailySummary> = self
+            .devices
+            .values_mut()
+            .map(|day| {
+                let summary = DailySummary {
+                    date: date.to_string(),
+                    device: day.device.clone(),
+                    energy_wh: day.energy_wh,
+                    max_power_w: day.max_power_w,
+                    uptime_minutes: day.uptime_ms / 60_000,
+                    fault_minutes: day.fault_ms / 60_000,
+                    samples: day.samples,
+                };
+                day.energy_wh = 0.0;
+                day.max_power_w = 0.0;
+                day.uptime_ms = 0;
+                day.fault_ms = 0;
+                day.samples = 0;
+                summary
+            })
+            .collect();
+        summaries.sort_by(|a, b| {
+            (&a.device.ip, a.device.unit_id).cmp(&(&b.device.ip, b.device.unit_id))
+        });
+        summaries
+    }
+}
+
+/// Appends summaries to the CSV at `path`, writing the header when the file is new.
+pub fn append_csv(path: &Path, summaries: &[DailySummary]) -> io::Result<()> {
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if is_new {
+        writeln!(file, "{CSV_HEADER}")?;
+    }
+    for summary in summaries {
+        writeln!(file, "{}", summary.csv_row())?;
+    }
+    file.flush()
+}
+
+/// Unix time (ms) of the first local midnight after `now_ms`.
+pub fn next_local_midnight_ms(now_ms: u64, utc_offset_minutes: i32) -> u64 {
+    let offset_ms = i64::from(utc_offset_minutes) * 60_000;
+    let local = now_ms as i64 + offset_ms;
+    let next_local = (local.div_euclid(DAY_MS) + 1) * DAY_MS;
+    (next_local - offset_ms).max(0) as u64
+}
+
+/// Local calendar date (`YYYY-MM-DD`) of `unix_ms`.
+pub fn local_date(unix_ms: u64, utc_offset_minutes: i32) -> String {
+    let local = unix_ms as i64 + i64::from(utc_offset_minutes) * 60_000;
+    let (year, month, day) = civil_from_days(local.div_euclid(DAY_MS));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian date (H. Hinnant's algorithm).
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Proleptic Gregorian date to days since 1970-01-01 (inverse of `civil_from_days`).
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,150 @@
+use avro_kafka::{PublishError, Publisher};
+use serde::Serialize;
+
+/// Why a buffered row could not be published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadLetterReason {
+    /// The buffered payload is not a sample.
+    Decode,
+    /// The sample does not fit the schema or encoding.
+    Encode,
+    /// The encoded sample alone exceeds the Kafka message size limit.
+    Oversized,
+    /// The cluster refused the payload, e.g. a broker or topic with a lower
+    /// size limit than the producer.
+    Rejected,
+    /// The cluster refused the topic, e.g. it does not exist or the
+    /// collector may not write to it.
+    Undeliverable,
+}
+
+impl DeadLetterReason {
+    pub fn from_publish_error(err: &PublishError) -> Self {
+        match err {
+            PublishError::Oversized { .. } => Self::Oversized,
+            PublishError::Fatal(_) if !err.is_payload_error() => Self::Undeliverable,
+            PublishError::Fatal(_) | PublishError::Retriable(_) => Self::Rejected,
+            _ => Self::Encode,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Decode => "decode",
+            Self::Encode => "encode",
+            Self::Oversized => "oversized",
+            Self::Rejected => "rejected",
+            Self::Undeliverable => "undeliverable",
+        }
+    }
+}
+
+/// What is published to the dead-letter topic for one buffered row.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeadLetterRecord {
+    pub reason: &'static str,
+    pub error: String,
+    /// Topic the row was buffered for.
+    pub source_topic: String,
+    pub buffer_id: i64,
+    pub failed_at_ms: u64,
+    /// The buffered payload (a sample as JSON), decoded lossily as UTF-8.
+    pub payload: String,
+}
+
+impl DeadLetterRecord {
+    pub fn new(
+        reason: DeadLetterReason,
+        error: impl Into<String>,
+        source_topic: impl Into<String>,
+        buffer_id: i64,
+        payload: &[u8],
+        failed_at_ms: u64,
+    ) -> Self {
+        Self {
+            reason: reason.as_str(),
+            error: error.into(),
+            source_topic: source_topic.into(),
+            buffer_id,
+            failed_at_ms,
+            payload: String::from_utf8_lossy(payload).into_owned(),
+        }
+    }
+}
+
+/// One payload to publish and the samples it carries, by index.
+#[derive(Debug)]
+pub struct EncodedPayload {
+    pub samples: Vec<usize>,
+    pub bytes: Vec<u8>,
+}
+
+/// Samples of one drain encoded for publishing.
+#[derive(Debug, Default)]
+pub struct EncodedSamples {
+    /// Payloads to publish, in sample order.
+    pub payloads: Vec<EncodedPayload>,
+    /// Samples that cannot be published, by index, with the error.
+    pub rejected: Vec<(usize, PublishError)>,
+}
+
+/// Encodes `samples` as one batch. When the batch fails to encode or exceeds
+/// the publisher's size limit, each sample is encoded on its own so only the
+/// ones that fail by themselves are rejected; the rest go out as one batch
+/// again when that fits, else one payload per sample. Encodings that do not
+/// batch always get one payload per sample.
+pub fn encode_samples<T: Serialize>(publisher: &Publisher, samples: &[T]) -> EncodedSamples {
+    let max_bytes = publisher.max_message_bytes();
+    let batches = publisher.encoding().batches();
+    let batch = batches
+        .then(|| publisher.serialize_batch(samples).ok())
+        .flatten();
+    if let Some(bytes) = batch.filter(|bytes| bytes.len() <= max_bytes) {
+        return EncodedSamples {
+            payloads: vec![EncodedPayload {
+                samples: (0..samples.len()).collect(),
+                bytes,
+            }],
+            rejected: Vec::new(),
+        };
+    }
+
+    let mut encoded = EncodedSamples::default();
+    let mut accepted = Vec::new();
+    let mut singles = Vec::new();
+    for (index, sample) in samples.iter().enumerate() {
+        // A batch of one, so JSON payloads stay arrays.
+        match publisher.serialize_batch(std::slice::from_ref(sample)) {
+            Ok(bytes) if bytes.len() > max_bytes => encoded.rejected.push((
+                index,
+                PublishError::Oversized {
+                    bytes: bytes.len(),
+                    max: max_bytes,
+                },
+            )),
+            Ok(bytes) => {
+                accepted.push(sample);
+                singles.push(EncodedPayload {
+                    samples: vec![index],
+                    bytes,
+                });
+            }
+            Err(err) => encoded.rejected.push((index, err)),
+        }
+    }
+    if accepted.is_empty() || !batches {
+        encoded.payloads = singles;
+        return encoded;
+    }
+    encoded.payloads = match publisher.serialize_batch(&accepted) {
+        Ok(bytes) if bytes.len() <= max_bytes => vec![EncodedPayload {
+            samples: singles
+                .into_iter()
+                .flat_map(|single| single.samples)
+                .collect(),
+            bytes,
+        }],
+        _ => singles,
+    };
+    encoded
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use poller_actor::PollSample;
+
+/// (ip, unit id, model id, model start) of a polled register block.
+type BlockKey = (String, u8, u16, u16);
+
+/// Suppresses samples whose register block is identical to the last one
+/// published for the same device and model. A block is let through again
+/// once `max_suppression` has passed, so unchanged devices still show up
+/// downstream.
+#[derive(Debug)]
+pub struct SampleDeduplicator {
+    max_suppression: Duration,
+    last: HashMap<BlockKey, (Vec<u16>, u64)>,
+}
+
+impl SampleDeduplicator {
+    pub fn new(max_suppression: Duration) -> Self {
+        Self {
+            max_suppression,
+            last: HashMap::new(),
+        }
+    }
+
+    /// True when `sample` should be published. Ages are measured on
+    /// `collected_at_ms`, not on arrival time. Aggregated samples always pass.
+    pub fn admit(&mut self, sample: &PollSample) -> bool {
+        if sample.window.is_some() {
+            return true;
+        }
+        let key = (
+            sample.device.ip.clone(),
+            sample.device.unit_id,
+            sample.model_id,
+            sample.start,
+        );
+        if let Some((registers, published_at_ms)) = self.last.get(&key) {
+            let age_ms = sample.collected_at_ms.saturating_sub(*published_at_ms);
+            if *registers == sample.registers && age_ms < self.max_suppression.as_millis() as u64 {
+                return false;
+            }
+        }
+        self.last
+            .insert(key, (sample.registers.clone(), sample.collected_at_ms));
+        true
+    }
+
+    /// Number of register blocks currently tracked.
+    pub fn len(&self) -> usize {
+        self.last.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last.is_empty()
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use serde::{Deserialize, Serialize};
+use types::DeviceIdentity;
+
+/// Compact numeric IDs of devices, keyed by `ip:unit_id`, persisted to a
+/// JSON file so a device keeps its number across restarts. Numbers start at
+/// 1 and are never reused, also not after a device is removed. Clones share
+/// one table.
+#[derive(Debug, Clone)]
+pub struct DeviceIds {
+    path: PathBuf,
+    table: Arc<Mutex<IdTable>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IdTable {
+    next: u32,
+    devices: BTreeMap<String, u32>,
+}
+
+impl DeviceIds {
+    /// Loads the table from `path`; a missing file starts an empty one.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let table = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => IdTable::default(),
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            path,
+            table: Arc::new(Mutex::new(table)),
+        })
+    }
+
+    /// Table key of a device, the same `ip:unit_id` its poller is known by.
+    pub fn key(device: &DeviceIdentity) -> String {
+        device.key().to_string()
+    }
+
+    pub fn get(&self, key: &str) -> Option<u32> {
+        self.lock().devices.get(key).copied()
+    }
+
+    /// Number of `device`, assigning and persisting the next free one the
+    /// first time. When the file cannot be written the number is still
+    /// assigned for this run and the error returned alongside it.
+    pub fn assign(&self, device: &DeviceIdentity) -> (u32, Option<io::Error>) {
+        let key = Self::key(device);
+        let mut table = self.lock();
+        if let Some(id) = table.devices.get(&key) {
+            return (*id, None);
+        }
+        table.next = table.next.max(1);
+        let id = table.next;
+        table.next += 1;
+        table.devices.insert(key, id);
+        (id, save(&self.path, &table).err())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IdTable> {
+        self.table.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Writes through a temporary file so a crash never leaves a truncated table.
+fn save(path: &Path, table: &IdTable) -> io::Result<()> {
+    let content = serde_json::to_string_pretty(table)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)
+}
@@ -0,0 +1,108 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use buffer::{BufferStore, InventoryEntry};
+use serde::{Deserialize, Serialize};
+use sunspec_parser::{CommonModel, ModelDefinition};
+use tracing::warn;
+use types::DeviceIdentity;
+
+/// A device as discovery last found it, persisted in the buffer database so
+/// the collector can start polling before rediscovery completes. Served by
+/// `GET /api/inventory`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveredDevice {
+    /// Carries the base address the device answered on.
+    pub device: DeviceIdentity,
+    /// The model chain, as read from the device.
+    pub models: Vec<ModelDefinition>,
+    /// Identity strings of the common model, when the device has one.
+    pub common: Option<CommonModel>,
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+}
+
+impl DiscoveredDevice {
+    pub fn new(
+        device: DeviceIdentity,
+        models: Vec<ModelDefinition>,
+        common: Option<CommonModel>,
+        seen_ms: u64,
+    ) -> Self {
+        Self {
+            device,
+            models,
+            common,
+            first_seen_ms: seen_ms,
+            last_seen_ms: seen_ms,
+        }
+    }
+
+    pub fn to_entry(&self) -> Result<InventoryEntry, serde_json::Error> {
+        Ok(InventoryEntry {
+            ip: self.device.ip.clone(),
+            unit_id: self.device.unit_id,
+            base_address: self.device.base_address,
+            models: serde_json::to_string(&self.models)?,
+            identity: self
+                .common
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+            first_seen_ms: self.first_seen_ms as i64,
+            last_seen_ms: self.last_seen_ms as i64,
+        })
+    }
+
+    pub fn from_entry(entry: &InventoryEntry) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            device: DeviceIdentity {
+                ip: entry.ip.clone(),
+                unit_id: entry.unit_id,
+                base_address: entry.base_address,
+            },
+            models: serde_json::from_str(&entry.models)?,
+            common: entry
+                .identity
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?,
+            first_seen_ms: entry.first_seen_ms.max(0) as u64,
+            last_seen_ms: entry.last_seen_ms.max(0) as u64,
+        })
+    }
+}
+
+/// Records a discovery in the buffer's `inventory` table.
+pub async fn record(buffer: &BufferStore, device: &DiscoveredDevice) -> anyhow::Result<()> {
+    buffer.save_inventory(&device.to_entry()?).await?;
+    Ok(())
+}
+
+/// Every device discovered so far, including ones no longer found. Rows
+/// that no longer decode are skipped; the next discovery overwrites them.
+pub async fn load(buffer: &BufferStore) -> anyhow::Result<Vec<DiscoveredDevice>> {
+    let entries = buffer.read_inventory().await?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| DiscoveredDevice::from_entry(entry).ok())
+        .collect())
+}
+
+/// `GET /api/inventory` lists every device discovered so far with its model
+/// chain, identity block and when it was first and last seen.
+pub fn router(buffer: BufferStore) -> Router {
+    Router::new()
+        .route("/api/inventory", get(inventory))
+        .with_state(buffer)
+}
+
+async fn inventory(
+    State(buffer): State<BufferStore>,
+) -> Result<Json<Vec<DiscoveredDevice>>, StatusCode> {
+    load(&buffer).await.map(Json).map_err(|err| {
+        warn!(error = %err, "inventory not readable");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+
+/// What the recovery drill observed in each phase.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DrillCounts {
+    /// Samples fed into the scratch buffer while the sink was blocked.
+    pub generated: usize,
+    /// Samples published while the sink was blocked; must stay 0.
+    pub sent_while_blocked: usize,
+    /// Rows in the scratch buffer when the sink was released.
+    pub buffered: i64,
+    /// Samples published after the release.
+    pub sent_after_release: usize,
+    /// Rows still buffered when the drill stopped draining.
+    pub left_in_buffer: i64,
+    pub dead_lettered: i64,
+    /// Drains it took to empty the buffer, and how long they took.
+    pub drains: usize,
+    pub drain_ms: u64,
+}
+
+/// One expectation of the drill and whether it held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrillCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Pass/fail report printed by `--drill`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrillReport {
+    pub topic: String,
+    pub checks: Vec<DrillCheck>,
+}
+
+impl DrillReport {
+    pub fn evaluate(topic: &str, counts: &DrillCounts) -> Self {
+        let generated = counts.generated as i64;
+        let checks = vec![
+            DrillCheck {
+                name: "sink blocked",
+                passed: counts.sent_while_blocked == 0,
+                detail: format!(
+                    "{} samples published during the outage",
+                    counts.sent_while_blocked
+                ),
+            },
+            DrillCheck {
+                name: "buffered during outage",
+                passed: counts.buffered == generated,
+                detail: format!("{} of {} samples buffered", counts.buffered, generated),
+            },
+            DrillCheck {
+                name: "drained after release",
+                passed: counts.left_in_buffer == 0,
+                detail: format!(
+                    "{} rows left after {} drains in {} ms",
+                    counts.left_in_buffer, counts.drains, counts.drain_ms
+                ),
+            },
+            DrillCheck {
+                name: "no loss",
+                passed: counts.sent_after_release == counts.generated && counts.dead_lettered == 0,
+                detail: format!(
+                    "{} of {} samples published, {} dead-lettered",
+                    counts.sent_after_release, counts.generated, counts.dead_lettered
+                ),
+            },
+        ];
+        Self {
+            topic: topic.to_string(),
+            checks,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
+
+impl fmt::Display for DrillReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "recovery drill on topic {}", self.topic)?;
+        for check in &self.checks {
+            let verdict = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(f, "  {verdict}  {}: {}", check.name, check.detail)?;
+        }
+        write!(f, "result: {}", if self.passed() { "PASS" } else { "FAIL" })
+    }
+}
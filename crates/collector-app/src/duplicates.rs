@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use types::{DeviceIdentity, SerialIdentity};
+
+/// Serial numbers of the polled units, per gateway IP. A misconfigured bus
+/// (two unit IDs mapped to the same device) shows up as a second unit on the
+/// same IP answering with a serial number that is already registered.
+#[derive(Debug, Default)]
+pub struct SerialRegistry {
+    units: HashMap<(String, SerialIdentity), DeviceIdentity>,
+}
+
+impl SerialRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `serial` for `device`. Returns the unit that already holds
+    /// it behind the same IP, in which case `device` should not be polled.
+    pub fn claim(
+        &mut self,
+        device: &DeviceIdentity,
+        serial: &SerialIdentity,
+    ) -> Option<DeviceIdentity> {
+        let key = (device.ip.clone(), serial.clone());
+        match self.units.get(&key) {
+            Some(primary) if !primary.same_unit(device) => Some(primary.clone()),
+            Some(_) => None,
+            None => {
+                self.units.insert(key, device.clone());
+                None
+            }
+        }
+    }
+
+    /// Forgets the serial number held by `device`, e.g. after it was removed.
+    pub fn release(&mut self, device: &DeviceIdentity) {
+        self.units.retain(|_, unit| !unit.same_unit(device));
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+/// Maximum age of buffered samples for one topic. Zero keeps them forever.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicMaxAge {
+    pub topic: String,
+    pub max_age_ms: u64,
+}
+
+/// Decides which buffered samples are too old to be worth publishing after
+/// a long outage. Topics without their own limit use the default one.
+#[derive(Debug, Clone, Default)]
+pub struct ExpiryPolicy {
+    default_max_age_ms: u64,
+    topics: HashMap<String, u64>,
+}
+
+impl ExpiryPolicy {
+    pub fn new(default_max_age_ms: u64, topics: &[TopicMaxAge]) -> Self {
+        Self {
+            default_max_age_ms,
+            topics: topics
+                .iter()
+                .map(|topic| (topic.topic.clone(), topic.max_age_ms))
+                .collect(),
+        }
+    }
+
+    /// Age limit for `topic`, if it has one.
+    pub fn max_age_ms(&self, topic: &str) -> Option<u64> {
+        let max_age_ms = self
+            .topics
+            .get(topic)
+            .copied()
+            .unwrap_or(self.default_max_age_ms);
+        (max_age_ms > 0).then_some(max_age_ms)
+    }
+
+    pub fn is_expired(&self, topic: &str, collected_at_ms: u64, now_ms: u64) -> bool {
+        self.max_age_ms(topic)
+            .is_some_and(|max_age_ms| now_ms.saturating_sub(collected_at_ms) > max_age_ms)
+    }
+}
@@ -0,0 +1,229 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+use crate::daily::{days_from_civil, local_date};
+use crate::model_map::ModelMaps;
+
+pub const CSV_HEADER: &str =
+    "collected_at_ms,ip,unit_id,model_id,model_name,start,registers,points";
+
+const FILE_PREFIX: &str = "telemetry-";
+const FILE_SUFFIX: &str = ".csv";
+const HOUR_MS: u64 = 3_600_000;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How much archived telemetry is kept. Zero disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Retention {
+    pub max_files: usize,
+    pub max_bytes: u64,
+}
+
+/// Appends samples to hourly CSV files (`telemetry-YYYY-MM-DDTHH.csv`, UTC)
+/// in one directory and prunes the oldest files on rotation. Each row holds
+/// the raw registers, so the files can be replayed, and the decoded points
+/// (`W=1234;Hz=50`) for direct use.
+#[derive(Debug)]
+pub struct FileSink {
+    dir: PathBuf,
+    retention: Retention,
+    current: Option<(u64, BufWriter<File>)>,
+    last_flush: Instant,
+    maps: ModelMaps,
+}
+
+impl FileSink {
+    pub fn new(dir: impl Into<PathBuf>, retention: Retention) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            retention,
+            current: None,
+            last_flush: Instant::now(),
+            maps: ModelMaps::default(),
+        })
+    }
+
+    /// Points of mapped models are decoded as `maps` configures them.
+    pub fn with_model_maps(mut self, maps: ModelMaps) -> Self {
+        self.maps = maps;
+        self
+    }
+
+    /// Writes one row into the file of the sample's hour. Rows are flushed
+    /// at most once per second.
+    pub fn write(&mut self, sample: &PollSample) -> io::Result<()> {
+        let hour = sample.collected_at_ms / HOUR_MS;
+        if self.current.as_ref().map(|(current, _)| *current) != Some(hour) {
+            self.rotate(hour)?;
+        }
+        if let Some((_, writer)) = self.current.as_mut() {
+            writeln!(writer, "{}", csv_row(sample, &self.maps))?;
+        }
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.last_flush = Instant::now();
+        match self.current.as_mut() {
+            Some((_, writer)) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn rotate(&mut self, hour: u64) -> io::Result<()> {
+        self.flush()?;
+        let path = self.dir.join(file_name(hour));
+        let is_new = !path.exists();
+        let mut writer = BufWriter::new(OpenOptions::new().create(true).append(true).open(&path)?);
+        if is_new {
+            writeln!(writer, "{CSV_HEADER}")?;
+        }
+        self.current = Some((hour, writer));
+        prune(&self.dir, &path, self.retention)
+    }
+}
+
+impl Drop for FileSink {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Archive files in `dir`, oldest first.
+pub fn archive_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX))
+        })
+        .collect();
+    // The UTC timestamp in the name sorts chronologically.
+    files.sort();
+    Ok(files)
+}
+
+/// Deletes the oldest archive files until both limits hold. The file being
+/// written is never removed.
+fn prune(dir: &Path, current: &Path, retention: Retention) -> io::Result<()> {
+    let files = archive_files(dir)?;
+    let mut sizes: Vec<(PathBuf, u64)> = files
+        .into_iter()
+        .map(|path| {
+            let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            (path, size)
+        })
+        .collect();
+    let mut total: u64 = sizes.iter().map(|(_, size)| size).sum();
+
+    while let Some((oldest, size)) = sizes.first().cloned() {
+        let too_many = retention.max_files > 0 && sizes.len() > retention.max_files;
+        let too_big = retention.max_bytes > 0 && total > retention.max_bytes;
+        if !(too_many || too_big) || oldest == current {
+            break;
+        }
+        fs::remove_file(&oldest)?;
+        total = total.saturating_sub(size);
+        sizes.remove(0);
+    }
+    Ok(())
+}
+
+/// Start (Unix ms) of the hour an archive file covers, read from its name.
+pub fn archive_hour_ms(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_str()?;
+    let stamp = name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX)?;
+    parse_utc_hour(stamp)
+}
+
+/// `YYYY-MM-DD` or `YYYY-MM-DDTHH` (UTC) to Unix ms.
+pub fn parse_utc_hour(value: &str) -> Option<u64> {
+    let (date, hour) = match value.split_once('T') {
+        Some((date, hour)) => (date, hour.parse::<u64>().ok().filter(|hour| *hour < 24)?),
+        None => (value, 0),
+    };
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts
+        .next()?
+        .parse()
+        .ok()
+        .filter(|month| (1..=12).contains(month))?;
+    let day: u32 = parts
+        .next()?
+        .parse()
+        .ok()
+        .filter(|day| (1..=31).contains(day))?;
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    Some(days * 24 * HOUR_MS + hour * HOUR_MS)
+}
+
+/// Reads a row written by the sink back into a sample. Window statistics
+/// are not archived, so aggregated rows come back as plain reads.
+pub fn parse_csv_row(line: &str) -> Option<PollSample> {
+    let fields: Vec<&str> = line.splitn(8, ',').collect();
+    let [collected_at_ms, ip, unit_id, model_id, model_name, start, registers, _points] =
+        fields[..]
+    else {
+        return None;
+    };
+    let registers = registers
+        .split_whitespace()
+        .map(|value| value.parse::<u16>().ok())
+        .collect::<Option<Vec<u16>>>()?;
+    Some(PollSample::new(
+        DeviceIdentity {
+            ip: ip.to_string(),
+            unit_id: unit_id.parse().ok()?,
+            base_address: None,
+        },
+        model_id.parse().ok()?,
+        model_name,
+        start.parse().ok()?,
+        registers,
+        collected_at_ms.parse().ok()?,
+    ))
+}
+
+fn file_name(hour: u64) -> String {
+    let date = local_date(hour * HOUR_MS, 0);
+    format!("{FILE_PREFIX}{date}T{:02}{FILE_SUFFIX}", hour % 24)
+}
+
+fn csv_row(sample: &PollSample, maps: &ModelMaps) -> String {
+    let registers: Vec<String> = sample.registers.iter().map(u16::to_string).collect();
+    let points: Vec<String> = match sample.window.as_ref() {
+        Some(window) => window
+            .points
+            .iter()
+            .map(|point| format!("{}={}", point.name, point.avg))
+            .collect(),
+        None => maps
+            .points(sample)
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect(),
+    };
+    format!(
+        "{},{},{},{},{},{},{},{}",
+        sample.collected_at_ms,
+        sample.device.ip,
+        sample.device.unit_id,
+        sample.model_id,
+        sample.model_name,
+        sample.start,
+        registers.join(" "),
+        points.join(";")
+    )
+}
@@ -0,0 +1,304 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use metrics::{counter, gauge};
+use poller_actor::PollSample;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tonic::client::Grpc;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::codegen::tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tonic::{Request, Status};
+use tonic_prost::ProstCodec;
+use tracing::{debug, info, warn};
+
+use self::proto::{Telemetry, TelemetryBatch};
+
+/// The streaming call of the `TelemetryIngest` service, see
+/// `docs/telemetry_ingest.proto`.
+pub const INGEST_PATH: &str = "/sunspec.ingest.v1.TelemetryIngest/Ingest";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Messages of `docs/telemetry_ingest.proto`.
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Point {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(double, tag = "2")]
+        pub value: f64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Telemetry {
+        #[prost(string, tag = "1")]
+        pub device_ip: String,
+        #[prost(uint32, tag = "2")]
+        pub unit_id: u32,
+        #[prost(uint32, tag = "3")]
+        pub model_id: u32,
+        #[prost(string, tag = "4")]
+        pub model_name: String,
+        #[prost(uint64, tag = "5")]
+        pub collected_at_ms: u64,
+        #[prost(uint64, tag = "6")]
+        pub sequence: u64,
+        #[prost(bool, tag = "7")]
+        pub maintenance: bool,
+        /// Empty without a `[site]`.
+        #[prost(string, tag = "8")]
+        pub site_id: String,
+        #[prost(message, repeated, tag = "9")]
+        pub points: Vec<Point>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TelemetryBatch {
+        /// Starts at 1 and grows by one per batch; a batch sent again after
+        /// a reconnect keeps its number.
+        #[prost(uint64, tag = "1")]
+        pub sequence: u64,
+        #[prost(message, repeated, tag = "2")]
+        pub samples: Vec<Telemetry>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct IngestAck {
+        /// Acknowledges every batch up to and including this one.
+        #[prost(uint64, tag = "1")]
+        pub sequence: u64,
+    }
+}
+
+/// The `[grpc_sink]` section. Decoded telemetry is streamed to `endpoint`
+/// when it is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrpcSinkSettings {
+    /// `http://` or `https://` URL of the `TelemetryIngest` service.
+    pub endpoint: Option<String>,
+    /// Samples per batch.
+    pub batch_size: usize,
+    /// Batches sent but not acknowledged yet. While that many are in flight
+    /// no samples are taken from the channel.
+    pub max_in_flight: usize,
+    /// Delay before the first reconnect, doubled per failed attempt.
+    pub retry_backoff_ms: u64,
+    pub retry_max_backoff_ms: u64,
+}
+
+impl Default for GrpcSinkSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            batch_size: 100,
+            max_in_flight: 8,
+            retry_backoff_ms: 500,
+            retry_max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl GrpcSinkSettings {
+    /// The endpoint to stream to, None when no URL is set. `https://` URLs
+    /// are checked against the system's CA certificates.
+    pub fn channel_endpoint(&self) -> Result<Option<Endpoint>, String> {
+        let Some(url) = self.endpoint.as_deref() else {
+            return Ok(None);
+        };
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err("grpc_sink.endpoint must be an http:// or https:// URL".to_string());
+        }
+        let mut endpoint = Endpoint::from_shared(url.to_string())
+            .map_err(|err| format!("grpc_sink.endpoint is invalid: {err}"))?
+            .connect_timeout(CONNECT_TIMEOUT)
+            .http2_keep_alive_interval(KEEP_ALIVE_INTERVAL);
+        if url.starts_with("https://") {
+            endpoint = endpoint
+                .tls_config(ClientTlsConfig::new().with_native_roots())
+                .map_err(|err| format!("grpc_sink.endpoint tls setup failed: {err}"))?;
+        }
+        Ok(Some(endpoint))
+    }
+}
+
+/// The record streamed for `sample`, carrying its decoded `points`.
+pub fn telemetry(sample: &PollSample, points: Vec<(String, f64)>) -> Telemetry {
+    Telemetry {
+        device_ip: sample.device.ip.clone(),
+        unit_id: u32::from(sample.device.unit_id),
+        model_id: u32::from(sample.model_id),
+        model_name: sample.model_name.clone(),
+        collected_at_ms: sample.collected_at_ms,
+        sequence: sample.sequence,
+        maintenance: sample.maintenance,
+        site_id: sample
+            .site
+            .as_ref()
+            .map(|site| site.site_id.clone())
+            .unwrap_or_default(),
+        points: points
+            .into_iter()
+            .map(|(name, value)| proto::Point { name, value })
+            .collect(),
+    }
+}
+
+/// Batches sent on the current or an earlier stream and not acknowledged yet.
+#[derive(Debug, Default)]
+struct Unacked {
+    batches: VecDeque<TelemetryBatch>,
+    next_sequence: u64,
+    /// Batches acknowledged so far.
+    acked: u64,
+}
+
+impl Unacked {
+    fn push(&mut self, samples: Vec<Telemetry>) -> TelemetryBatch {
+        self.next_sequence += 1;
+        let batch = TelemetryBatch {
+            sequence: self.next_sequence,
+            samples,
+        };
+        self.batches.push_back(batch.clone());
+        gauge!("grpc_sink_in_flight").set(self.batches.len() as f64);
+        batch
+    }
+
+    /// Drops the batches up to `sequence`, returning how many samples they held.
+    fn ack(&mut self, sequence: u64) -> usize {
+        let mut samples = 0;
+        while let Some(batch) = self.batches.front() {
+            if batch.sequence > sequence {
+                break;
+            }
+            samples += batch.samples.len();
+            self.batches.pop_front();
+            self.acked += 1;
+        }
+        gauge!("grpc_sink_in_flight").set(self.batches.len() as f64);
+        samples
+    }
+}
+
+/// Streams `samples` to the `TelemetryIngest` service at `endpoint` until
+/// the channel closes and everything sent is acknowledged. Batches not
+/// acknowledged when a stream fails are sent again on the next one, so
+/// delivery is at least once, also after the channel closed. Reconnects back
+/// off exponentially; meanwhile, and while `max_in_flight` batches wait for
+/// their acks, the channel fills up and the sender decides what to drop.
+pub async fn run(
+    endpoint: Endpoint,
+    settings: GrpcSinkSettings,
+    mut samples: mpsc::Receiver<Telemetry>,
+) {
+    let initial_backoff = Duration::from_millis(settings.retry_backoff_ms.max(1));
+    let max_backoff = Duration::from_millis(settings.retry_max_backoff_ms).max(initial_backoff);
+    let mut backoff = initial_backoff;
+    let mut unacked = Unacked::default();
+    let mut closed = false;
+    loop {
+        let acked = unacked.acked;
+        let Err(err) = stream(
+            &endpoint,
+            &settings,
+            &mut samples,
+            &mut unacked,
+            &mut closed,
+        )
+        .await
+        else {
+            break;
+        };
+        warn!(endpoint = %endpoint.uri(), error = %err, unacked = unacked.batches.len(), "grpc sink stream failed");
+        counter!("grpc_sink_error").increment(1);
+        // A stream that delivered something starts the backoff over.
+        if unacked.acked > acked {
+            backoff = initial_backoff;
+        }
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(max_backoff);
+    }
+    info!("grpc sink stopped");
+}
+
+/// One streaming call: sends the unacknowledged batches again, then new
+/// batches as samples arrive. Returns Ok once the channel is closed and every
+/// batch is acknowledged.
+async fn stream(
+    endpoint: &Endpoint,
+    settings: &GrpcSinkSettings,
+    samples: &mut mpsc::Receiver<Telemetry>,
+    unacked: &mut Unacked,
+    closed: &mut bool,
+) -> Result<(), Status> {
+    let channel = endpoint
+        .connect()
+        .await
+        .map_err(|err| Status::unavailable(format!("connect failed: {err}")))?;
+    let mut client = Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .map_err(|err| Status::unavailable(format!("service not ready: {err}")))?;
+
+    let max_in_flight = settings.max_in_flight.max(1);
+    let (batch_tx, batch_rx) = mpsc::channel(max_in_flight);
+    for batch in &unacked.batches {
+        // Never more than max_in_flight, so the channel has room.
+        let _ = batch_tx.try_send(batch.clone());
+    }
+    // After the channel closed only the resent batches remain to be acked.
+    let mut batch_tx = (!*closed).then_some(batch_tx);
+    let mut acks = client
+        .streaming(
+            Request::new(ReceiverStream::new(batch_rx)),
+            PathAndQuery::from_static(INGEST_PATH),
+            ProstCodec::<TelemetryBatch, proto::IngestAck>::default(),
+        )
+        .await?
+        .into_inner();
+    debug!(endpoint = %endpoint.uri(), resent = unacked.batches.len(), "grpc sink stream open");
+
+    loop {
+        let has_room = unacked.batches.len() < max_in_flight;
+        tokio::select! {
+            ack = acks.message() => match ack? {
+                Some(ack) => {
+                    let delivered = unacked.ack(ack.sequence);
+                    counter!("grpc_sink_sent").increment(delivered as u64);
+                }
+                None if *closed && unacked.batches.is_empty() => return Ok(()),
+                None => return Err(Status::unavailable("stream ended by the service")),
+            },
+            sample = samples.recv(), if batch_tx.is_some() && has_room => match sample {
+                Some(first) => {
+                    let mut batch = vec![first];
+                    while batch.len() < settings.batch_size.max(1) {
+                        match samples.try_recv() {
+                            Ok(sample) => batch.push(sample),
+                            Err(_) => break,
+                        }
+                    }
+                    let batch = unacked.push(batch);
+                    if let Some(tx) = batch_tx.as_ref() {
+                        if tx.send(batch).await.is_err() {
+                            return Err(Status::unavailable("request stream closed"));
+                        }
+                    }
+                }
+                None => {
+                    *closed = true;
+                    // Ends the request stream; the service acks the rest and
+                    // ends its stream.
+                    batch_tx = None;
+                    if unacked.batches.is_empty() {
+                        return Ok(());
+                    }
+                }
+            },
+        }
+    }
+}
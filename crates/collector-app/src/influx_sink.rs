@@ -0,0 +1,361 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use metrics::counter;
+use poller_actor::PollSample;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, StatusCode, Url};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+/// Path of the InfluxDB v2 write API, used when the URL has no path.
+pub const V2_WRITE_PATH: &str = "/api/v2/write";
+
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const RETRY_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The `[influx]` section. Decoded points are written as line protocol to
+/// `url` when it is set.
+#[derive(Clone, PartialEq)]
+pub struct InfluxSinkSettings {
+    /// Write endpoint. A bare `http://host:8086` writes to InfluxDB v2's
+    /// `/api/v2/write`; any other path (InfluxDB v1 `/write?db=...`, a
+    /// Telegraf listener) is used as it is.
+    pub url: Option<String>,
+    pub org: Option<String>,
+    pub bucket: Option<String>,
+    /// Sent as `Authorization: Token <token>`.
+    pub token: Option<String>,
+    pub measurement: String,
+    /// Tag key -> sample field: `ip`, `unit_id`, `model_id`, `model_name`,
+    /// `site_id`, `plant` or `label:<name>` for a `[site.labels]` entry.
+    pub tags: BTreeMap<String, String>,
+    /// Lines per write.
+    pub batch_size: usize,
+    /// How long a write waits for more lines before it goes out short.
+    pub flush_interval_ms: u64,
+}
+
+impl Default for InfluxSinkSettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            org: None,
+            bucket: None,
+            token: None,
+            measurement: "sunspec".to_string(),
+            tags: default_tags(),
+            batch_size: 1_000,
+            flush_interval_ms: 1_000,
+        }
+    }
+}
+
+/// Leaves the token out, so configs can be logged.
+impl fmt::Debug for InfluxSinkSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InfluxSinkSettings")
+            .field("url", &self.url)
+            .field("org", &self.org)
+            .field("bucket", &self.bucket)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("measurement", &self.measurement)
+            .field("tags", &self.tags)
+            .field("batch_size", &self.batch_size)
+            .field("flush_interval_ms", &self.flush_interval_ms)
+            .finish()
+    }
+}
+
+pub fn default_tags() -> BTreeMap<String, String> {
+    [
+        ("ip", "ip"),
+        ("unit_id", "unit_id"),
+        ("model", "model_name"),
+        ("site", "site_id"),
+    ]
+    .into_iter()
+    .map(|(key, source)| (key.to_string(), source.to_string()))
+    .collect()
+}
+
+impl InfluxSinkSettings {
+    /// The URL lines are posted to, with `org`, `bucket` and `precision=ms`
+    /// added to its query. None when no URL is set.
+    pub fn write_url(&self) -> Result<Option<Url>, String> {
+        let Some(url) = self.url.as_deref() else {
+            return Ok(None);
+        };
+        let mut url = Url::parse(url).map_err(|err| format!("influx.url is invalid: {err}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("influx.url must be an http:// or https:// URL".to_string());
+        }
+        if url.path() == "/" {
+            url.set_path(V2_WRITE_PATH);
+        }
+        if url.path() == V2_WRITE_PATH && self.bucket.is_none() {
+            return Err("influx.bucket must be set for the InfluxDB v2 write API".to_string());
+        }
+        {
+            let mut query = url.query_pairs_mut();
+            if let Some(org) = &self.org {
+                query.append_pair("org", org);
+            }
+            if let Some(bucket) = &self.bucket {
+                query.append_pair("bucket", bucket);
+            }
+            query.append_pair("precision", "ms");
+        }
+        Ok(Some(url))
+    }
+
+    /// The writer for [`run`], None when no URL is set.
+    pub fn writer(&self) -> Result<Option<InfluxWriter>, String> {
+        let Some(url) = self.write_url()? else {
+            return Ok(None);
+        };
+        let client = Client::builder()
+            .timeout(WRITE_TIMEOUT)
+            .build()
+            .map_err(|err| format!("influx client setup failed: {err}"))?;
+        Ok(Some(InfluxWriter {
+            client,
+            url,
+            token: self.token.clone(),
+        }))
+    }
+}
+
+/// Where a tag takes its value from.
+#[derive(Debug, Clone, PartialEq)]
+enum TagSource {
+    Ip,
+    UnitId,
+    ModelId,
+    ModelName,
+    SiteId,
+    Plant,
+    Label(String),
+}
+
+impl TagSource {
+    fn parse(source: &str) -> Option<Self> {
+        Some(match source {
+            "ip" => Self::Ip,
+            "unit_id" => Self::UnitId,
+            "model_id" => Self::ModelId,
+            "model_name" => Self::ModelName,
+            "site_id" => Self::SiteId,
+            "plant" => Self::Plant,
+            _ => Self::Label(source.strip_prefix("label:")?.to_string()),
+        })
+    }
+
+    fn value(&self, sample: &PollSample) -> Option<String> {
+        let site = sample.site.as_ref();
+        match self {
+            Self::Ip => Some(sample.device.ip.clone()),
+            Self::UnitId => Some(sample.device.unit_id.to_string()),
+            Self::ModelId => Some(sample.model_id.to_string()),
+            Self::ModelName => Some(sample.model_name.clone()),
+            Self::SiteId => site.map(|site| site.site_id.clone()),
+            Self::Plant => site.and_then(|site| site.plant.clone()),
+            Self::Label(name) => site.and_then(|site| site.labels.get(name).cloned()),
+        }
+    }
+}
+
+/// Turns samples into line-protocol lines: one line per sample, tagged as
+/// configured, with a field per decoded point and the collection time in
+/// milliseconds.
+#[derive(Debug, Clone)]
+pub struct LineEncoder {
+    measurement: String,
+    tags: Vec<(String, TagSource)>,
+}
+
+impl LineEncoder {
+    pub fn new(settings: &InfluxSinkSettings) -> Result<Self, String> {
+        if settings.measurement.trim().is_empty() {
+            return Err("influx.measurement must be non-empty".to_string());
+        }
+        let tags = settings
+            .tags
+            .iter()
+            .map(|(key, source)| {
+                if key.trim().is_empty() {
+                    return Err("influx.tags keys must be non-empty".to_string());
+                }
+                let source = TagSource::parse(source).ok_or_else(|| {
+                    format!(
+                        "influx.tags.{key} must be ip, unit_id, model_id, model_name, site_id, plant or label:<name>"
+                    )
+                })?;
+                Ok((escape(key, &[',', '=', ' ']), source))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            measurement: escape(&settings.measurement, &[',', ' ']),
+            tags,
+        })
+    }
+
+    /// The line for `sample`, None when it has no finite point. Tags without
+    /// a value (no `[site]`, a missing label) are left out.
+    pub fn encode(&self, sample: &PollSample, points: &[(String, f64)]) -> Option<String> {
+        let fields: Vec<String> = points
+            .iter()
+            .filter(|(_, value)| value.is_finite())
+            .map(|(name, value)| format!("{}={value}", escape(name, &[',', '=', ' '])))
+            .collect();
+        if fields.is_empty() {
+            return None;
+        }
+        let mut line = self.measurement.clone();
+        for (key, source) in &self.tags {
+            if let Some(value) = source.value(sample).filter(|value| !value.is_empty()) {
+                line.push(',');
+                line.push_str(key);
+                line.push('=');
+                line.push_str(&escape(&value, &[',', '=', ' ']));
+            }
+        }
+        line.push(' ');
+        line.push_str(&fields.join(","));
+        line.push(' ');
+        line.push_str(&sample.collected_at_ms.to_string());
+        Some(line)
+    }
+}
+
+/// Backslash-escapes `special` characters; line breaks cannot be escaped and
+/// become spaces.
+fn escape(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        let c = if c == '\n' || c == '\r' { ' ' } else { c };
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Why a write failed.
+#[derive(Debug)]
+pub enum WriteError {
+    /// The endpoint was unreachable, timed out or asked to retry (429, 5xx).
+    Unavailable(String),
+    /// The endpoint refused the lines; sending them again would not help.
+    Rejected(StatusCode, String),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable(reason) => write!(f, "unavailable: {reason}"),
+            Self::Rejected(status, body) => write!(f, "rejected with {status}: {body}"),
+        }
+    }
+}
+
+/// Posts line-protocol bodies to the write URL.
+#[derive(Debug, Clone)]
+pub struct InfluxWriter {
+    client: Client,
+    url: Url,
+    token: Option<String>,
+}
+
+impl InfluxWriter {
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub async fn write(&self, body: String) -> Result<(), WriteError> {
+        let mut request = self.client.post(self.url.clone()).body(body);
+        if let Some(token) = &self.token {
+            request = request.header(AUTHORIZATION, format!("Token {token}"));
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|err| WriteError::Unavailable(err.to_string()))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            Err(WriteError::Unavailable(format!("{status}: {body}")))
+        } else {
+            Err(WriteError::Rejected(status, body))
+        }
+    }
+}
+
+/// Writes `lines` in batches of up to `batch_size` until the channel
+/// closes. A batch goes out when it is full or `flush_interval_ms` after
+/// its first line. Failed writes are retried with a growing delay; the
+/// channel fills up meanwhile and the sender decides what to drop. Batches
+/// the endpoint rejects are dropped.
+pub async fn run(
+    writer: InfluxWriter,
+    settings: InfluxSinkSettings,
+    mut lines: mpsc::Receiver<String>,
+) {
+    let batch_size = settings.batch_size.max(1);
+    let flush_interval = Duration::from_millis(settings.flush_interval_ms.max(1));
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut closed = false;
+    while !closed {
+        match lines.recv().await {
+            Some(line) => batch.push(line),
+            None => break,
+        }
+        let deadline = Instant::now() + flush_interval;
+        while batch.len() < batch_size {
+            match timeout_at(deadline, lines.recv()).await {
+                Ok(Some(line)) => batch.push(line),
+                Ok(None) => {
+                    closed = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+        write_batch(&writer, &batch).await;
+        batch.clear();
+    }
+    info!("influx sink stopped");
+}
+
+async fn write_batch(writer: &InfluxWriter, batch: &[String]) {
+    let body = batch.join("\n");
+    let mut backoff = RETRY_BACKOFF;
+    loop {
+        match writer.write(body.clone()).await {
+            Ok(()) => {
+                debug!(lines = batch.len(), "influx write done");
+                counter!("influx_sink_written").increment(batch.len() as u64);
+                return;
+            }
+            Err(err @ WriteError::Rejected(..)) => {
+                warn!(url = %writer.url(), error = %err, lines = batch.len(), "influx write rejected, dropping the batch");
+                counter!("influx_sink_error").increment(1);
+                counter!("influx_sink_rejected").increment(batch.len() as u64);
+                return;
+            }
+            Err(err) => {
+                warn!(url = %writer.url(), error = %err, retry_in_ms = backoff.as_millis() as u64, "influx write failed");
+                counter!("influx_sink_error").increment(1);
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(RETRY_MAX_BACKOFF);
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use types::DeviceIdentity;
+
+/// Columns of a CSV inventory; `ip` is required, `unit` is an alias of
+/// `unit_id`.
+const CSV_COLUMNS: &[&str] = &[
+    "ip",
+    "unit_id",
+    "unit",
+    "base_address",
+    "site",
+    "alias",
+    "profile",
+    "address_offset",
+    "one_based_addressing",
+];
+
+/// One static device of the inventory file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InventoryDevice {
+    pub ip: String,
+    #[serde(default = "default_unit_id", alias = "unit")]
+    pub unit_id: u8,
+    #[serde(default)]
+    pub base_address: Option<u16>,
+    #[serde(default)]
+    pub site: Option<String>,
+    /// Name operators know the device by, e.g. `INV-B3-07`.
+    #[serde(default)]
+    pub alias: Option<String>,
+    /// Device profile applied after the device rules, so it wins over them.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Gateway addressing of this device, over the `[modbus]` settings.
+    #[serde(default)]
+    pub address_offset: Option<i32>,
+    #[serde(default)]
+    pub one_based_addressing: Option<bool>,
+}
+
+fn default_unit_id() -> u8 {
+    1
+}
+
+impl InventoryDevice {
+    pub fn identity(&self) -> DeviceIdentity {
+        DeviceIdentity {
+            ip: self.ip.clone(),
+            unit_id: self.unit_id,
+            base_address: self.base_address,
+        }
+    }
+
+    pub fn matches(&self, device: &DeviceIdentity) -> bool {
+        self.ip == device.ip && self.unit_id == device.unit_id
+    }
+
+    pub fn labels(&self) -> DeviceLabels {
+        DeviceLabels {
+            site: self.site.clone(),
+            alias: self.alias.clone(),
+        }
+    }
+}
+
+/// Site and alias of a device, shown next to its poller in `/pollers`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeviceLabels {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+/// Reads the inventory at `path`: a JSON array of devices when the file
+/// ends in `.json`, else CSV with a header row.
+pub fn load_inventory(path: &Path) -> Result<Vec<InventoryDevice>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("read inventory file {}", path.display()))?;
+    let devices = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => parse_json(&content),
+        _ => parse_csv(&content),
+    };
+    devices.with_context(|| format!("parse inventory file {}", path.display()))
+}
+
+pub fn parse_json(content: &str) -> Result<Vec<InventoryDevice>> {
+    let devices: Vec<InventoryDevice> = serde_json::from_str(content)?;
+    for (index, device) in devices.iter().enumerate() {
+        check_device(device).with_context(|| format!("entry {}", index + 1))?;
+    }
+    check_duplicates(
+        devices
+            .iter()
+            .enumerate()
+            .map(|(index, device)| (format!("entry {}", index + 1), device)),
+    )?;
+    Ok(devices)
+}
+
+/// CSV with a header row naming the columns, in any order. Blank lines and
+/// lines starting with `#` are skipped, empty cells are unset. Cells may be
+/// double-quoted to hold commas.
+pub fn parse_csv(content: &str) -> Result<Vec<InventoryDevice>> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let Some((header_line, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let columns: Vec<String> = split_csv_line(header)
+        .with_context(|| format!("line {header_line}"))?
+        .into_iter()
+        .map(|column| column.to_ascii_lowercase())
+        .collect();
+    for column in &columns {
+        if !CSV_COLUMNS.contains(&column.as_str()) {
+            anyhow::bail!("line {header_line}: unknown column '{column}'");
+        }
+    }
+    if !columns.iter().any(|column| column == "ip") {
+        anyhow::bail!("line {header_line}: the header needs an 'ip' column");
+    }
+
+    let mut devices = Vec::new();
+    for (line_no, line) in lines {
+        let cells = split_csv_line(line).with_context(|| format!("line {line_no}"))?;
+        if cells.len() > columns.len() {
+            anyhow::bail!(
+                "line {line_no}: {} cells but {} columns",
+                cells.len(),
+                columns.len()
+            );
+        }
+        let device = csv_device(&columns, &cells).with_context(|| format!("line {line_no}"))?;
+        devices.push((line_no, device));
+    }
+    check_duplicates(
+        devices
+            .iter()
+            .map(|(line_no, device)| (format!("line {line_no}"), device)),
+    )?;
+    Ok(devices.into_iter().map(|(_, device)| device).collect())
+}
+
+fn csv_device(columns: &[String], cells: &[String]) -> Result<InventoryDevice> {
+    let mut device = InventoryDevice {
+        ip: String::new(),
+        unit_id: default_unit_id(),
+        base_address: None,
+        site: None,
+        alias: None,
+        profile: None,
+        address_offset: None,
+        one_based_addressing: None,
+    };
+    for (column, cell) in columns.iter().zip(cells) {
+        if cell.is_empty() {
+            continue;
+        }
+        match column.as_str() {
+            "ip" => device.ip = cell.clone(),
+            "unit_id" | "unit" => {
+                device.unit_id = cell
+                    .parse()
+                    .with_context(|| format!("invalid unit id '{cell}'"))?;
+            }
+            "base_address" => {
+                device.base_address = Some(
+                    cell.parse()
+                        .with_context(|| format!("invalid base address '{cell}'"))?,
+                );
+            }
+            "site" => device.site = Some(cell.clone()),
+            "alias" => device.alias = Some(cell.clone()),
+            "profile" => device.profile = Some(cell.clone()),
+            "address_offset" => {
+                device.address_offset = Some(
+                    cell.parse()
+                        .with_context(|| format!("invalid address offset '{cell}'"))?,
+                );
+            }
+            "one_based_addressing" => {
+                device.one_based_addressing = Some(
+                    cell.parse()
+                        .with_context(|| format!("invalid one_based_addressing '{cell}'"))?,
+                );
+            }
+            _ => {}
+        }
+    }
+    check_device(&device)?;
+    Ok(device)
+}
+
+fn check_device(device: &InventoryDevice) -> Result<()> {
+    if device.ip.trim().is_empty() {
+        anyhow::bail!("ip must be non-empty");
+    }
+    Ok(())
+}
+
+fn check_duplicates<'a>(
+    devices: impl Iterator<Item = (String, &'a InventoryDevice)>,
+) -> Result<()> {
+    let mut seen = HashSet::new();
+    for (place, device) in devices {
+        if !seen.insert((device.ip.as_str(), device.unit_id)) {
+            anyhow::bail!(
+                "{place}: device {}:{} is listed twice",
+                device.ip,
+                device.unit_id
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Splits one CSV line into trimmed cells.
+fn split_csv_line(line: &str) -> Result<Vec<String>> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(ch),
+        }
+    }
+    if quoted {
+        anyhow::bail!("unterminated quote");
+    }
+    cells.push(cell.trim().to_string());
+    Ok(cells)
+}
@@ -0,0 +1,128 @@
+use std::env;
+use std::fmt::{self, Write as _};
+use std::io;
+use std::os::unix::net::UnixDatagram;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+const SYSLOG_IDENTIFIER: &str = "sunspec-collector";
+
+/// Tracing layer that writes events to journald using the native protocol so
+/// fields can be matched with `journalctl FIELD=value`.
+pub struct JournaldLayer {
+    socket: UnixDatagram,
+}
+
+impl JournaldLayer {
+    pub fn connect() -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNALD_SOCKET)?;
+        Ok(Self { socket })
+    }
+}
+
+/// systemd sets `JOURNAL_STREAM` when stdout/stderr are connected to the journal.
+pub fn running_under_systemd() -> bool {
+    env::var_os("JOURNAL_STREAM").is_some()
+}
+
+impl<S: Subscriber> Layer<S> for JournaldLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut payload = Vec::with_capacity(256);
+        put_field(&mut payload, "PRIORITY", priority(metadata.level()));
+        put_field(&mut payload, "SYSLOG_IDENTIFIER", SYSLOG_IDENTIFIER);
+        put_field(&mut payload, "TARGET", metadata.target());
+        put_field(&mut payload, "MESSAGE", &visitor.message);
+        for (name, value) in &visitor.fields {
+            put_field(&mut payload, name, value);
+        }
+
+        // Logging must never take the collector down; drop the entry if journald is gone.
+        let _ = self.socket.send(&payload);
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.fields
+                .push((journal_key(field.name()), value.to_string()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            self.fields
+                .push((journal_key(field.name()), format!("{value:?}")));
+        }
+    }
+}
+
+/// Maps tracing field names onto journal field names. The well-known
+/// collector fields get stable names; everything else is upper-cased.
+fn journal_key(name: &str) -> String {
+    match name {
+        "ip" => "DEVICE_IP".to_string(),
+        "unit_id" => "UNIT_ID".to_string(),
+        "model_id" => "MODEL_ID".to_string(),
+        "error_class" => "ERROR_CLASS".to_string(),
+        "error" => "ERROR".to_string(),
+        other => {
+            let key: String = other
+                .chars()
+                .map(|ch| {
+                    if ch.is_ascii_alphanumeric() {
+                        ch.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            // journald drops client fields that start with '_' or a digit.
+            if key.starts_with(|ch: char| ch == '_' || ch.is_ascii_digit()) {
+                format!("F{key}")
+            } else {
+                key
+            }
+        }
+    }
+}
+
+fn priority(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "3",
+        Level::WARN => "4",
+        Level::INFO => "6",
+        Level::DEBUG | Level::TRACE => "7",
+    }
+}
+
+fn put_field(buf: &mut Vec<u8>, name: &str, value: &str) {
+    buf.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        // Multi-line values use the length-prefixed binary form.
+        buf.push(b'\n');
+        buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        buf.push(b'=');
+    }
+    buf.extend_from_slice(value.as_bytes());
+    buf.push(b'\n');
+}
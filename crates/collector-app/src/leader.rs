@@ -0,0 +1,263 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use metrics::gauge;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Used when neither the config nor the host name gives an instance ID.
+pub const FALLBACK_INSTANCE_ID: &str = "sunspec-collector";
+
+/// The `[leader]` section: active/standby coordination of two collectors
+/// polling the same devices. Both take turns holding a lease file on
+/// storage they share; only the holder polls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderSettings {
+    pub enabled: bool,
+    pub lease_path: String,
+    /// How long a lease holds without being renewed; a standby takes over
+    /// this long after the leader stopped renewing.
+    pub lease_ms: u64,
+    pub renew_interval_ms: u64,
+}
+
+impl Default for LeaderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lease_path: "sunspec-leader.lease".to_string(),
+            lease_ms: 15_000,
+            renew_interval_ms: 5_000,
+        }
+    }
+}
+
+/// The host name, which tells the collectors of a pair apart unless
+/// `instance_id` is configured.
+pub fn default_instance_id() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| FALLBACK_INSTANCE_ID.to_string())
+}
+
+/// Contents of the lease file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaseRecord {
+    pub instance_id: String,
+    pub expires_at_ms: u64,
+}
+
+/// What a look at the lease file found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeaseState {
+    /// The lease is ours and was renewed.
+    Held,
+    /// The lease was free or expired and now names us. It only counts once
+    /// the next look still finds it ours, so of two instances claiming at
+    /// the same time only the one written last becomes leader.
+    Claimed,
+    /// Another instance holds the lease.
+    Taken(LeaseRecord),
+}
+
+/// A lease kept in a file that both instances of a pair can reach. The
+/// file is replaced atomically, so readers never see a partial record;
+/// expiry compares wall clocks, which must be kept in sync (NTP).
+#[derive(Debug, Clone)]
+pub struct FileLease {
+    path: PathBuf,
+    instance_id: String,
+    lease_ms: u64,
+}
+
+impl FileLease {
+    pub fn new(path: impl Into<PathBuf>, instance_id: &str, lease_ms: u64) -> Self {
+        Self {
+            path: path.into(),
+            instance_id: instance_id.to_string(),
+            lease_ms,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Renews the lease if it is ours and claims it if it is free or
+    /// expired.
+    pub fn acquire(&self, now_ms: u64) -> io::Result<LeaseState> {
+        let state = match self.read()? {
+            Some(record) if record.instance_id == self.instance_id => LeaseState::Held,
+            Some(record) if record.expires_at_ms > now_ms => return Ok(LeaseState::Taken(record)),
+            _ => LeaseState::Claimed,
+        };
+        self.write(&LeaseRecord {
+            instance_id: self.instance_id.clone(),
+            expires_at_ms: now_ms + self.lease_ms,
+        })?;
+        Ok(state)
+    }
+
+    /// Gives the lease up so the standby can take over without waiting for
+    /// it to expire. A lease held by another instance is left alone.
+    pub fn release(&self) -> io::Result<()> {
+        match self.read()? {
+            Some(record) if record.instance_id == self.instance_id => {
+                match fs::remove_file(&self.path) {
+                    Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The current record; None when there is no lease file. A file that
+    /// does not parse is treated as an expired lease.
+    pub fn read(&self) -> io::Result<Option<LeaseRecord>> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        match serde_json::from_slice(&bytes) {
+            Ok(record) => Ok(Some(record)),
+            Err(err) => {
+                warn!(path = %self.path.display(), error = %err, "unreadable lease file ignored");
+                Ok(None)
+            }
+        }
+    }
+
+    fn write(&self, record: &LeaseRecord) -> io::Result<()> {
+        if let Some(parent) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", self.instance_id));
+        let tmp = PathBuf::from(tmp);
+        fs::write(&tmp, serde_json::to_vec(record)?)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Decides, one renewal at a time, whether this instance leads.
+#[derive(Debug)]
+pub struct LeaderElection {
+    lease: FileLease,
+    /// Until when our last renewal holds the lease.
+    held_until_ms: Option<u64>,
+}
+
+impl LeaderElection {
+    pub fn new(lease: FileLease) -> Self {
+        Self {
+            lease,
+            held_until_ms: None,
+        }
+    }
+
+    pub fn lease(&self) -> &FileLease {
+        &self.lease
+    }
+
+    /// Renews or claims the lease and returns whether this instance leads.
+    /// When the lease file cannot be reached a leader keeps leading until
+    /// its last renewal expires, the moment the standby may take over.
+    pub fn tick(&mut self, now_ms: u64) -> bool {
+        match self.lease.acquire(now_ms) {
+            Ok(LeaseState::Held) => {
+                self.held_until_ms = Some(now_ms + self.lease.lease_ms);
+            }
+            Ok(LeaseState::Claimed) | Ok(LeaseState::Taken(_)) => self.held_until_ms = None,
+            Err(err) => {
+                warn!(path = %self.lease.path.display(), error = %err, "lease file not reachable");
+                if self.held_until_ms.is_some_and(|until| until <= now_ms) {
+                    self.held_until_ms = None;
+                }
+            }
+        }
+        self.held_until_ms.is_some()
+    }
+}
+
+/// Runs the election until shutdown and publishes on `leader` whether this
+/// instance leads. The lease is released on shutdown.
+pub async fn run(
+    mut election: LeaderElection,
+    renew_interval: Duration,
+    leader: watch::Sender<bool>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let instance = election.lease.instance_id.clone();
+    loop {
+        let leading = election.tick(unix_ms());
+        gauge!("leader_active", "instance" => instance.clone()).set(f64::from(u8::from(leading)));
+        leader.send_if_modified(|current| {
+            if *current == leading {
+                return false;
+            }
+            if leading {
+                info!(instance = %instance, "lease acquired, polling as leader");
+            } else {
+                info!(instance = %instance, "not holding the lease, standing by");
+            }
+            *current = leading;
+            true
+        });
+        tokio::select! {
+            _ = sleep(renew_interval) => {}
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+    if let Err(err) = election.lease.release() {
+        warn!(path = %election.lease.path.display(), error = %err, "lease release failed");
+    }
+    leader.send_replace(false);
+}
+
+/// Holds `pause` while maintenance pauses polling or this instance stands
+/// by. Ends when the election stops.
+pub async fn pause_unless_leading(
+    mut maintenance: watch::Receiver<bool>,
+    mut leader: watch::Receiver<bool>,
+    pause: watch::Sender<bool>,
+) {
+    // Without a maintenance calendar its sender is gone from the start.
+    let mut maintenance_open = true;
+    loop {
+        let paused = *maintenance.borrow_and_update() || !*leader.borrow_and_update();
+        pause.send_if_modified(|current| {
+            let changed = *current != paused;
+            *current = paused;
+            changed
+        });
+        tokio::select! {
+            changed = maintenance.changed(), if maintenance_open => {
+                maintenance_open = changed.is_ok();
+            }
+            changed = leader.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
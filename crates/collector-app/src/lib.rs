@@ -1,3 +1,49 @@
+pub mod aggregate;
+pub mod alarms;
+pub mod alerts;
+pub mod anomaly;
+pub mod boot;
+pub mod bootstrap;
+pub mod broker;
+pub mod capture;
+pub mod clock;
 pub mod config;
+pub mod control;
+pub mod daily;
+pub mod dead_letter;
+pub mod dedup;
+pub mod device_ids;
+pub mod discovered;
+pub mod drill;
+pub mod duplicates;
+pub mod expiry;
+pub mod file_sink;
+pub mod grpc_sink;
+pub mod influx_sink;
+pub mod inventory;
+#[cfg(target_os = "linux")]
+pub mod journald;
+pub mod leader;
+pub mod log_rollup;
+pub mod logging;
+pub mod maintenance;
+pub mod model_map;
+pub mod otel;
+pub mod plant;
+pub mod points;
+pub mod postgres_sink;
+pub mod quarantine;
+pub mod register_dump;
+pub mod reload;
+pub mod resources;
+pub mod restart;
+pub mod replay;
+pub mod routing;
+pub mod rules;
+pub mod simulator;
+pub mod sparkplug;
+pub mod supervisor;
+pub mod timesync;
+pub mod uplink;
 
 pub use config::CollectorConfig;
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Targets whose repeated events are rolled up: the pollers and the Modbus
+/// client log once per failed read, which floods the journal when a device
+/// keeps failing the same way.
+pub const ROLLUP_TARGETS: &[&str] = &["poller_actor", "modbus_client"];
+
+/// Distinct events tracked per window; events beyond it are never suppressed.
+const MAX_TRACKED_EVENTS: usize = 1_024;
+
+/// Events dropped as repeats of an identical event within one window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepeatedEvent {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    /// The event's other fields, `name=value` separated by spaces.
+    pub fields: String,
+    pub repeated: u64,
+}
+
+/// Tracing layer that lets the first of a run of identical events (same
+/// target, level, message and fields) through and drops the rest until the
+/// window ends. [`LogRollup::flush`] reports how many were dropped.
+#[derive(Clone, Default)]
+pub struct LogRollup {
+    inner: Arc<Mutex<RollupState>>,
+}
+
+#[derive(Default)]
+struct RollupState {
+    /// Zero disables the rollup.
+    window_ms: u64,
+    events: HashMap<EventKey, Seen>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct EventKey {
+    level: Level,
+    target: String,
+    message: String,
+    fields: String,
+}
+
+struct Seen {
+    first_at_ms: u64,
+    repeated: u64,
+}
+
+impl LogRollup {
+    pub fn new(window_ms: u64) -> Self {
+        let rollup = Self::default();
+        rollup.set_window_ms(window_ms);
+        rollup
+    }
+
+    /// Changes the window; zero lets every event through.
+    pub fn set_window_ms(&self, window_ms: u64) {
+        let mut state = self.lock();
+        state.window_ms = window_ms;
+        if window_ms == 0 {
+            state.events.clear();
+        }
+    }
+
+    /// Whether an event seen at `now_ms` should be logged, counting it as a
+    /// repeat otherwise.
+    pub fn admit(
+        &self,
+        level: Level,
+        target: &str,
+        message: &str,
+        fields: &str,
+        now_ms: u64,
+    ) -> bool {
+        let mut state = self.lock();
+        let window_ms = state.window_ms;
+        if window_ms == 0 {
+            return true;
+        }
+        let key = EventKey {
+            level,
+            target: target.to_string(),
+            message: message.to_string(),
+            fields: fields.to_string(),
+        };
+        match state.events.get_mut(&key) {
+            Some(seen) if now_ms.saturating_sub(seen.first_at_ms) < window_ms => {
+                seen.repeated += 1;
+                false
+            }
+            // Window over but not flushed yet: log it rather than lose it.
+            Some(_) => true,
+            None => {
+                if state.events.len() < MAX_TRACKED_EVENTS {
+                    state.events.insert(
+                        key,
+                        Seen {
+                            first_at_ms: now_ms,
+                            repeated: 0,
+                        },
+                    );
+                }
+                true
+            }
+        }
+    }
+
+    /// Repeats counted in windows that ended by `now_ms`, forgetting those
+    /// events so their next occurrence is logged again.
+    pub fn flush(&self, now_ms: u64) -> Vec<RepeatedEvent> {
+        let mut state = self.lock();
+        let window_ms = state.window_ms;
+        let mut repeated = Vec::new();
+        state.events.retain(|key, seen| {
+            if now_ms.saturating_sub(seen.first_at_ms) < window_ms {
+                return true;
+            }
+            if seen.repeated > 0 {
+                repeated.push(RepeatedEvent {
+                    level: key.level,
+                    target: key.target.clone(),
+                    message: key.message.clone(),
+                    fields: key.fields.clone(),
+                    repeated: seen.repeated,
+                });
+            }
+            false
+        });
+        repeated.sort_by(|a, b| (&a.target, &a.message).cmp(&(&b.target, &b.message)));
+        repeated
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RollupState> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Whether events of `target` (a crate or one of its modules) are rolled up.
+pub fn rolled_up(target: &str) -> bool {
+    ROLLUP_TARGETS.iter().any(|prefix| {
+        target
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    })
+}
+
+impl<S: Subscriber> Layer<S> for LogRollup {
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let metadata = event.metadata();
+        if !rolled_up(metadata.target()) {
+            return true;
+        }
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        self.admit(
+            *metadata.level(),
+            metadata.target(),
+            &visitor.message,
+            &visitor.fields,
+            unix_ms(),
+        )
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.push_field(field, format_args!("{value}"));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            self.push_field(field, format_args!("{value:?}"));
+        }
+    }
+}
+
+impl FieldVisitor {
+    fn push_field(&mut self, field: &Field, value: fmt::Arguments<'_>) {
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={value}", field.name());
+    }
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use tracing::Subscriber;
+use tracing_subscriber::filter::{filter_fn, FilterExt, LevelFilter, Targets};
+use tracing_subscriber::layer::Filter;
+
+/// How log lines are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines (the default).
+    #[default]
+    Text,
+    /// One JSON object per line, for shippers such as Promtail or Filebeat.
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The `[logging]` section: output format, default level and per-crate
+/// levels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogSettings {
+    /// `text` or `json`; checked by [`LogSettings::format`].
+    pub format: String,
+    /// Level of events no filter matches, e.g. `info`.
+    pub level: String,
+    /// Level per target prefix, e.g. `poller_actor = "debug"`.
+    pub filters: BTreeMap<String, String>,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            format: "text".to_string(),
+            level: "info".to_string(),
+            filters: BTreeMap::new(),
+        }
+    }
+}
+
+impl LogSettings {
+    pub fn format(&self) -> Result<LogFormat, String> {
+        LogFormat::parse(&self.format)
+            .ok_or_else(|| format!("logging.format must be text or json, got '{}'", self.format))
+    }
+
+    /// The filter deciding which events are logged: the level of the
+    /// longest matching filter target, else the default level.
+    pub fn targets(&self) -> Result<Targets, String> {
+        let default = parse_level(&self.level).map_err(|err| format!("logging.level: {err}"))?;
+        let mut targets = Targets::new().with_default(default);
+        for (target, level) in &self.filters {
+            if target.trim().is_empty() {
+                return Err("logging.filters has an empty target".to_string());
+            }
+            let level =
+                parse_level(level).map_err(|err| format!("logging.filters.{target}: {err}"))?;
+            targets = targets.with_target(target.trim(), level);
+        }
+        Ok(targets)
+    }
+}
+
+/// Filter of the log layers: the events `targets` enables. Spans are left to
+/// the trace exporter, so log lines look the same with or without it.
+pub fn log_filter<S: Subscriber>(targets: Targets) -> impl Filter<S> {
+    targets.and(filter_fn(|metadata| metadata.is_event()))
+}
+
+fn parse_level(value: &str) -> Result<LevelFilter, String> {
+    value.trim().parse().map_err(|_| {
+        format!("unknown level '{value}', expected off, error, warn, info, debug or trace")
+    })
+}
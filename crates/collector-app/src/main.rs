@@ -1,286 +1,3301 @@
 use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
+use tokio::net::TcpListener;
 use tokio::sync::{mpsc, watch};
-use tokio::task::JoinSet;
-use tokio::time::{sleep, timeout};
-use tracing::{info, warn};
+use tokio::task::{AbortHandle, JoinHandle, JoinSet};
+use tokio::time::{interval, sleep, sleep_until, timeout_at, Instant, Interval};
+use tracing::{debug, info, warn};
+#[cfg(target_os = "linux")]
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 
-use axum::{routing::get, Router};
+use axum::extract::Path as UrlPath;
+use axum::http::StatusCode;
+use axum::{routing::get, Json, Router};
 use metrics::{counter, gauge, histogram};
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use std::future;
 use std::net::SocketAddr;
 
-use avro_kafka::{KafkaConfig, Publisher};
-use buffer::BufferStore;
+use avro_kafka::{
+    avro_to_json_schema, Encoding, KafkaConfig, PublishError, Publisher, SinkGate, Transactions,
+    DEFAULT_MAX_MESSAGE_BYTES,
+};
+use buffer::{BufferError, BufferStore, BufferedMessage};
+use collector_app::aggregate::WindowAggregator;
+use collector_app::alarms::{AlarmRecord, AlarmTracker, AlarmTransition};
+use collector_app::alerts::{AlertRecord, AlertTracker};
+use collector_app::anomaly::{AnomalyStage, ZScoreDetector};
+use collector_app::boot::{device_class, BootOrder};
+use collector_app::bootstrap::{resolve_brokers, BootstrapWatch};
+use collector_app::broker::{BrokerState, BrokerStatus};
+use collector_app::capture::{self, CaptureFile, CaptureRegistry};
+use collector_app::control::{self, PollerControl};
+use collector_app::daily::{append_csv, local_date, next_local_midnight_ms, DailyAccumulator};
+use collector_app::dead_letter::{encode_samples, DeadLetterReason, DeadLetterRecord};
+use collector_app::dedup::SampleDeduplicator;
+use collector_app::device_ids::DeviceIds;
+use collector_app::discovered::{self, DiscoveredDevice};
+use collector_app::drill::{DrillCounts, DrillReport};
+use collector_app::duplicates::SerialRegistry;
+use collector_app::expiry::ExpiryPolicy;
+use collector_app::file_sink::{FileSink, Retention};
+use collector_app::grpc_sink::{self, proto::Telemetry};
+use collector_app::influx_sink::{self, LineEncoder};
+use collector_app::inventory::{DeviceLabels, InventoryDevice};
+use collector_app::leader::{self, FileLease, LeaderElection};
+use collector_app::log_rollup::{LogRollup, RepeatedEvent};
+use collector_app::logging::{log_filter, LogFormat, LogSettings};
+use collector_app::maintenance::{MaintenanceCalendar, MaintenanceStatus};
+use collector_app::model_map::ModelMaps;
+use collector_app::otel::{span_layer, OtelSettings};
+use collector_app::plant::{next_snapshot_ms, PlantSnapshots, SNAPSHOT_MODELS};
+use collector_app::points::PointDirectory;
+use collector_app::postgres_sink::{self, PostgresSink, SampleRow};
+use collector_app::quarantine::Quarantine;
+use collector_app::register_dump::{self, RegisterDump};
+use collector_app::reload::{config_source, plan_reload, spawn_reload_triggers};
+use collector_app::replay::{read_archive, ReplayPlan, ReplaySource};
+use collector_app::resources::{HealthStatus, ResourceMonitor};
+use collector_app::restart::{Restart, RestartTracker};
+use collector_app::routing::TopicRouter;
+use collector_app::rules::DevicePolicy;
+use collector_app::simulator::{Simulator, SyntheticDevice};
+use collector_app::sparkplug::{metric_name, EdgeNode};
+use collector_app::supervisor::{PollerRegistry, TrafficTracker};
+use collector_app::timesync::{self, TimeSyncCheck};
+use collector_app::uplink::{
+    assign_lanes, live_quotas, split_by_topic, CommitMarker, DrainOrder, WriteThrough,
+};
 use collector_app::CollectorConfig;
-use discovery::discover;
+use discovery::{discover, DiscoveryConfig};
 use modbus_client::{ClientConfig, ModbusClient};
-use poller_actor::{ActorConfig, PollerActor, PollerError, PollSample};
-use sunspec_parser::{parse_models_from_registers_lenient, ModelDefinition};
-use types::DeviceIdentity;
+use poller_actor::{
+    ActorConfig, ClockConfig, PollThrottle, PollerActor, PollerError, PollSample, SampleClock,
+    SampleCounters, ScanClass, TriggeredSample,
+};
+use sunspec_parser::{
+    base_address_candidates, decode_common, decode_storage, has_sunspec_marker,
+    parse_models_from_registers_lenient, CommonModel, ModelCatalog, ModelDefinition,
+};
+use types::{DeviceIdentity, DeviceKey, SerialIdentity, SiteInfo};
 
 const DEFAULT_UPLINK_BACKOFF_MS: u64 = 1_000;
 const DEFAULT_UPLINK_BACKOFF_MAX_MS: u64 = 30_000;
+const CONFIG_WATCH_INTERVAL_MS: u64 = 2_000;
+/// How often quarantined devices are checked for a due probe.
+const QUARANTINE_CHECK_INTERVAL_MS: u64 = 5_000;
+/// Triggered reads waiting for the plant snapshot task.
+const PLANT_READS_CAPACITY: usize = 1_024;
+/// Batches of `buffer.batch_size` samples the recovery drill buffers.
+const DRILL_BATCHES: usize = 3;
+/// Most registers one read of `--dump` asks for, so long models are split
+/// even without `modbus.max_batch_size`.
+const DUMP_MAX_READ_REGISTERS: u16 = 125;
+/// How often write-through checks whether the uplink drained the buffer.
+const WRITE_THROUGH_CHECK_INTERVAL_MS: u64 = 1_000;
+/// How often `buffer_disk_bytes` is refreshed and a due compaction checked.
+const BUFFER_SIZE_INTERVAL_MS: u64 = 60_000;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
-
-    let config_path = parse_config_arg();
-    let config = CollectorConfig::load_with_path(config_path).context("load config failed")?;
+    let config_path = parse_flag("--config");
+    let mut config =
+        CollectorConfig::load_with_path(config_path.clone()).context("load config failed")?;
     config.validate().context("config validation failed")?;
+    let log_rollup = init_tracing(&config.logging, &config.otel)?;
+    log_rollup.set_window_ms(config.log_rollup_window_ms);
+    if let Some(replay) = parse_flag("--replay") {
+        return run_replay(&config, &replay).await;
+    }
+    if let Some(topic) = parse_flag("--drill") {
+        return run_drill(&config, &topic).await;
+    }
+    let catalog = load_catalog(&config)?;
+    if let Some(target) = parse_flag("--dump") {
+        return run_dump(&config, &catalog, &target, parse_flag("--dump-out")).await;
+    }
+    let simulate = has_flag("--simulate");
+    let simulator_handle = if simulate {
+        Some(start_simulator(&mut config).await?)
+    } else {
+        None
+    };
     let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
     let builder = PrometheusBuilder::new();
     let handle = builder
         .install_recorder()
         .context("failed to install metrics recorder")?;
-    let builder = PrometheusBuilder::new();
-    let handle = builder
-        .install_recorder()
-        .context("failed to install metrics recorder")?;
-    let _metrics_handle = tokio::spawn(metrics_task(handle, shutdown_rx.clone(), config.metrics_port));
-
-    let devices = discover(config.discovery.clone())
+    let device_ids = DeviceIds::open(&config.device_ids_path)
+        .with_context(|| format!("load device ids from {} failed", config.device_ids_path))?;
+    let (captures, capture_rx) = CaptureRegistry::new();
+    tokio::spawn(capture::run(CaptureFile::new(&config.capture), capture_rx));
+    let control = PollerControl::new().with_model_maps(ModelMaps::new(config.model_maps.clone()));
+    let registry = PollerRegistry::new()
+        .with_device_ids(device_ids.clone())
+        .with_captures(captures)
+        .with_control(control);
+    let stall_after = Duration::from_millis(config.stall_timeout_ms);
+    let points = PointDirectory::new().with_model_maps(ModelMaps::new(config.model_maps.clone()));
+    let broker = BrokerStatus::new();
+    let health = HealthStatus::new();
+    let alerts = config
+        .alerts_enabled
+        .then(|| Arc::new(Mutex::new(AlertTracker::new(config.alert_rules.clone()))));
+    let buffer = BufferStore::new(&config.buffer_path)
         .await
-        .context("device discovery failed")?;
-    if devices.is_empty() {
-        warn!("no devices discovered");
+        .context("buffer init failed")?;
+    let _metrics_handle = tokio::spawn(metrics_task(
+        handle,
+        registry.clone(),
+        api_router(
+            &registry,
+            points.clone(),
+            broker.clone(),
+            buffer.clone(),
+            alerts.clone(),
+        ),
+        health.clone(),
+        stall_after,
+        shutdown_rx.clone(),
+        config.metrics_port,
+    ));
+
+    let calendar = MaintenanceCalendar::new(
+        &config.maintenance_windows,
+        config.maintenance_utc_offset_minutes,
+    )?;
+    let maintenance = calendar.status(unix_ms());
+    let (pause_tx, pause_rx) = watch::channel(maintenance.pause_polling);
+    let (maintenance_tx, mut maintenance_rx) = watch::channel(maintenance);
+    let maintenance_handle = (!calendar.is_empty()).then(|| {
+        tokio::spawn(maintenance_task(
+            calendar,
+            maintenance_tx,
+            pause_tx,
+            shutdown_rx.clone(),
+        ))
+    });
+    // A standby keeps its pollers paused, on top of maintenance pauses,
+    // until it holds the lease.
+    let (pause_rx, leader_handle) = if config.leader.enabled {
+        let lease = FileLease::new(
+            &config.leader.lease_path,
+            &config.instance_id,
+            config.leader.lease_ms,
+        );
+        info!(instance = %config.instance_id, lease = %config.leader.lease_path, "leader election enabled, polling waits for the lease");
+        let (leader_tx, leader_rx) = watch::channel(false);
+        let (standby_tx, standby_rx) = watch::channel(true);
+        let handle = tokio::spawn(leader::run(
+            LeaderElection::new(lease),
+            Duration::from_millis(config.leader.renew_interval_ms),
+            leader_tx,
+            shutdown_rx.clone(),
+        ));
+        tokio::spawn(leader::pause_unless_leading(
+            pause_rx, leader_rx, standby_tx,
+        ));
+        (standby_rx, Some(handle))
+    } else {
+        (pause_rx, None)
+    };
+
+    // Checked once before the first sample, then in the background.
+    let sample_clock = config.timesync.sample_clock();
+    let timesync_check = config.timesync.check().map_err(anyhow::Error::msg)?;
+    if timesync_check != TimeSyncCheck::None {
+        timesync::check_once(timesync_check, config.timesync.max_offset_ms, &sample_clock).await;
+        tokio::spawn(timesync::run(
+            timesync_check,
+            Duration::from_millis(config.timesync.interval_ms),
+            config.timesync.max_offset_ms,
+            sample_clock.clone(),
+            shutdown_rx.clone(),
+        ));
     }
 
-    let (tx, rx) = mpsc::channel(config.channel_capacity);
-    let publisher = if let Some(brokers) = config.kafka_brokers.clone() {
-        let mut kafka_config = KafkaConfig::default();
-        kafka_config.brokers = brokers;
-        kafka_config.client_id = config
-            .kafka_client_id
-            .clone()
-            .unwrap_or_else(|| "sunspec-collector".to_string());
-        kafka_config.acks = config.kafka_acks.clone().unwrap_or_else(|| "all".to_string());
-        kafka_config.compression = config
-            .kafka_compression
-            .clone()
-            .unwrap_or_else(|| "zstd".to_string());
-        kafka_config.message_timeout_ms = config.kafka_timeout_ms.unwrap_or(5_000);
-        if let Some(enable_idempotence) = config.kafka_enable_idempotence {
-            kafka_config.enable_idempotence = enable_idempotence;
+    let cached = if config.buffer_start_from_inventory {
+        discovered::load(&buffer).await.unwrap_or_else(|err| {
+            warn!(error = %err, "cached inventory not readable, discovering first");
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+    // With a cached inventory, discovery runs in the background and its
+    // devices are reconciled with the pollers once it completes.
+    let (rediscovered_tx, mut rediscovered_rx) = mpsc::channel(1);
+    let devices = if cached.is_empty() {
+        wait_out_maintenance(&mut maintenance_rx, "device discovery").await;
+        let devices = discover(config.discovery.clone())
+            .await
+            .context("device discovery failed")?;
+        if devices.is_empty() {
+            warn!("no devices discovered");
         }
+        devices
+    } else {
+        info!(
+            devices = cached.len(),
+            "polling the cached inventory, rediscovering in the background"
+        );
+        tokio::spawn(rediscover(
+            config.discovery.clone(),
+            maintenance_rx.clone(),
+            rediscovered_tx,
+        ));
+        Vec::new()
+    };
 
-        Publisher::new_kafka(
-            Publisher::default_schema(),
-            config.kafka_topic.clone().unwrap_or_else(|| "sunspec.telemetry".to_string()),
-            kafka_config,
+    let (tx, rx) = mpsc::channel(config.channel_capacity);
+    // Stretches every poller's interval while a resource limit is crossed.
+    let throttle = PollThrottle::default();
+    let resources_handle = tokio::spawn(
+        ResourceMonitor {
+            settings: config.resources.clone(),
+            buffer: buffer.clone(),
+            buffer_path: config.buffer_path.clone(),
+            samples: tx.downgrade(),
+            throttle: throttle.clone(),
+            health,
+        }
+        .run(shutdown_rx.clone()),
+    );
+    let model_maps = ModelMaps::new(config.model_maps.clone());
+    // With aggregation windows configured, samples pass through the windowing
+    // task before they reach the buffer.
+    let (rx, aggregate_handle) = if config.aggregation_windows.is_empty() {
+        (rx, None)
+    } else {
+        let (window_tx, window_rx) = mpsc::channel(config.channel_capacity);
+        let aggregator =
+            WindowAggregator::new(&config.aggregation_windows).with_model_maps(model_maps.clone());
+        let handle = tokio::spawn(aggregate_task(rx, window_tx, aggregator));
+        (window_rx, Some(handle))
+    };
+    let (publisher_tx, publisher_rx) = watch::channel(build_publisher(&config)?);
+    if config.encoding() == Encoding::Json {
+        tokio::spawn(publish_json_schemas(
+            schema_publishers(&publisher_rx.borrow(), &config),
+            config.kafka_schema_topic.clone(),
+        ));
+    }
+    let dedup = config
+        .dedup_enabled
+        .then(|| SampleDeduplicator::new(Duration::from_millis(config.dedup_max_suppression_ms)));
+    let daily = config
+        .daily_enabled
+        .then(|| Arc::new(Mutex::new(DailyAccumulator::new())));
+    let anomaly = config.anomaly_enabled.then(|| {
+        // Only readings from roughly the same poll round are compared.
+        let longest_window_ms = config
+            .aggregation_windows
+            .iter()
+            .map(|window| window.window_ms)
+            .max()
+            .unwrap_or(0);
+        let max_age_ms = config.poller.poll_interval.as_millis() as u64 * 3 + longest_window_ms;
+        let stage = AnomalyStage::new(
+            config.anomaly_groups.clone(),
+            max_age_ms,
+            config.anomaly_min_power_w,
         )
-        .context("kafka publisher init failed")?
+        .with_detector(ZScoreDetector::new(
+            config.anomaly_z_threshold,
+            config.anomaly_min_deficit_pct,
+        ));
+        Arc::new(Mutex::new(stage))
+    });
+    let (sparkplug_tx, sparkplug_handle) = if config.sparkplug_enabled {
+        let (event_tx, event_rx) = mpsc::channel(config.channel_capacity);
+        let node = EdgeNode::new(&config.sparkplug_group_id, &config.sparkplug_edge_node_id)
+            .with_device_ids(device_ids.clone());
+        let handle = tokio::spawn(sparkplug_task(
+            node,
+            event_rx,
+            publisher_rx.clone(),
+            config.sparkplug_topic.clone(),
+        ));
+        (Some(event_tx), Some(handle))
     } else {
-        Publisher::new_mock(Publisher::default_schema(), "sunspec.telemetry")
+        (None, None)
     };
-    let buffer = BufferStore::new(&config.buffer_path)
-        .await
-        .context("buffer init failed")?;
-    let buffer_handle = tokio::spawn(buffer_task(
+    let (alarm_tx, alarm_handle) = if config.alarms_enabled {
+        let (record_tx, record_rx) = mpsc::channel(config.channel_capacity);
+        let handle = tokio::spawn(alarm_task(
+            record_rx,
+            publisher_rx.clone(),
+            config.alarms_topic.clone(),
+        ));
+        (Some(record_tx), Some(handle))
+    } else {
+        (None, None)
+    };
+    let (alert_tx, alert_handle) = if alerts.is_some() {
+        let (record_tx, record_rx) = mpsc::channel(config.channel_capacity);
+        let handle = tokio::spawn(alert_task(
+            record_rx,
+            publisher_rx.clone(),
+            config.alerts_topic.clone(),
+        ));
+        (Some(record_tx), Some(handle))
+    } else {
+        (None, None)
+    };
+    let file_sink = if config.file_sink_enabled {
+        let retention = Retention {
+            max_files: config.file_sink_max_files,
+            max_bytes: config.file_sink_max_bytes,
+        };
+        let sink =
+            FileSink::new(&config.file_sink_dir, retention).context("file sink init failed")?;
+        Some(sink.with_model_maps(model_maps.clone()))
+    } else {
+        None
+    };
+    let (grpc_tx, grpc_handle) = match config
+        .grpc_sink
+        .channel_endpoint()
+        .map_err(anyhow::Error::msg)?
+    {
+        Some(endpoint) => {
+            let (record_tx, record_rx) = mpsc::channel(config.channel_capacity);
+            let handle = tokio::spawn(grpc_sink::run(
+                endpoint,
+                config.grpc_sink.clone(),
+                record_rx,
+            ));
+            (Some(record_tx), Some(handle))
+        }
+        None => (None, None),
+    };
+    let (influx_tx, influx_handle) = match config.influx.writer().map_err(anyhow::Error::msg)? {
+        Some(writer) => {
+            let encoder = LineEncoder::new(&config.influx).map_err(anyhow::Error::msg)?;
+            let (line_tx, line_rx) = mpsc::channel(config.channel_capacity);
+            let handle = tokio::spawn(influx_sink::run(writer, config.influx.clone(), line_rx));
+            (Some((encoder, line_tx)), Some(handle))
+        }
+        None => (None, None),
+    };
+    let postgres_pool = config.postgres.pool().map_err(anyhow::Error::msg)?;
+    let (postgres_queue, postgres_handle) = match postgres_pool {
+        Some(pool) => {
+            let sink = PostgresSink::new(pool, &config.postgres).map_err(anyhow::Error::msg)?;
+            let queue = BufferStore::new(&config.postgres.buffer_path)
+                .await
+                .context("postgres queue init failed")?;
+            let handle = tokio::spawn(postgres_sink::run(
+                sink,
+                queue.clone(),
+                config.postgres.batch_size,
+                shutdown_rx.clone(),
+            ));
+            (Some(queue), Some(handle))
+        }
+        None => (None, None),
+    };
+    let (routes_tx, routes_rx) = watch::channel(config.topic_router());
+    let stages = SampleStages {
+        points,
+        dedup,
+        daily: daily.clone(),
+        anomaly: anomaly.clone(),
+        alarms: alarm_tx.map(|tx| (AlarmTracker::new(), tx)),
+        alerts: alerts.zip(alert_tx),
+        sparkplug: sparkplug_tx.clone(),
+        file_sink,
+        grpc_sink: grpc_tx,
+        influx: influx_tx,
+        postgres: postgres_queue,
+        model_maps,
+        maintenance: maintenance_rx.clone(),
+        routes: routes_rx,
+        site: config.site.clone(),
+        namespace: config.kafka_namespace.clone(),
+        write_through: config.buffer_write_through.then(|| DirectPublish {
+            mode: WriteThrough::new(Duration::from_millis(WRITE_THROUGH_CHECK_INTERVAL_MS)),
+            broker: broker.clone(),
+            batch_size: config.buffer_batch_size.max(1) as usize,
+        }),
+    };
+    let mut buffer_handle = tokio::spawn(buffer_task(
         rx,
         buffer.clone(),
-        publisher.clone(),
-        shutdown_rx.clone(),
+        publisher_rx.clone(),
+        stages,
     ));
-    let uplink_handle = tokio::spawn(uplink_task(
+    let anomaly_handle = anomaly.map(|stage| {
+        tokio::spawn(anomaly_task(
+            stage,
+            Duration::from_millis(config.anomaly_interval_ms),
+            shutdown_rx.clone(),
+        ))
+    });
+    let daily_handle = daily.map(|accumulator| {
+        tokio::spawn(daily_summary_task(
+            accumulator,
+            publisher_rx.clone(),
+            config.daily_topic.clone(),
+            PathBuf::from(&config.daily_csv_path),
+            config.daily_utc_offset_minutes,
+            shutdown_rx.clone(),
+        ))
+    });
+    let (plant_trigger, plant_handle) = config
+        .plant_snapshot_enabled
+        .then(|| {
+            let (trigger, _) = watch::channel(0u64);
+            let trigger = Arc::new(trigger);
+            let (reads_tx, reads_rx) = mpsc::channel(PLANT_READS_CAPACITY);
+            let handle = tokio::spawn(plant_snapshot_task(
+                trigger.clone(),
+                reads_rx,
+                publisher_rx.clone(),
+                config.plant_snapshot_topic.clone(),
+                Duration::from_millis(config.plant_snapshot_interval_ms),
+                Duration::from_millis(config.plant_snapshot_window_ms),
+                shutdown_rx.clone(),
+            ));
+            let plant_trigger = PlantTrigger {
+                trigger,
+                reads: reads_tx,
+            };
+            (plant_trigger, handle)
+        })
+        .unzip();
+    // The uplink is stopped separately so it can flush after the channel is drained.
+    let (uplink_shutdown_tx, uplink_shutdown_rx) = watch::channel(false);
+    let mut uplink_handle = tokio::spawn(uplink_task(
         buffer.clone(),
-        publisher.clone(),
-        shutdown_rx.clone(),
-        config.buffer_batch_size,
+        publisher_rx,
+        uplink_shutdown_rx,
+        broker,
         Duration::from_millis(config.buffer_drain_interval_ms),
+        DrainSettings {
+            batch_size: config.buffer_batch_size,
+            max_in_flight: config.buffer_max_in_flight,
+            expiry: ExpiryPolicy::new(config.buffer_max_age_ms, &config.buffer_topic_max_age),
+            dead_letter_topic: config.buffer_dead_letter_topic.clone(),
+            commit_topic: config.kafka_commit_topic.clone(),
+            order: config.drain_order(),
+            live_window_ms: config.buffer_live_window_ms,
+            backlog_share_pct: config.buffer_backlog_share_pct,
+        },
     ));
 
-    let specs = build_poller_specs(&config, &devices, tx.clone(), shutdown_rx.clone()).await;
-
-    let mut join_set = JoinSet::new();
-    for spec in specs.values() {
-        spawn_poller(spec.clone(), &mut join_set, Duration::from_millis(0));
+    let (poller_config_tx, poller_config_rx) = watch::channel(config.poller.clone());
+    let channels = PollerChannels {
+        sender: tx.clone(),
+        shutdown: shutdown_rx.clone(),
+        config_updates: poller_config_rx.clone(),
+        pause: pause_rx.clone(),
+        sample_clock,
+        throttle,
+    };
+    let mut serials = SerialRegistry::new();
+    let mut quarantine = Quarantine::new(config.quarantine_settings());
+    let mut restarts = RestartTracker::new(config.restart_policy());
+    let mut specs = build_poller_specs(
+        &config,
+        &devices,
+        &channels,
+        &catalog,
+        &mut serials,
+        &mut quarantine,
+        &buffer,
+    )
+    .await;
+    for cached in cached {
+        let device = cached.device.clone();
+        if let Some(spec) = poller_spec(&config, &device, cached, &channels, &mut serials) {
+            specs.insert(device.key(), spec);
+        }
     }
 
+    let mut pollers = Pollers::new(specs, registry.clone(), device_ids.clone(), sparkplug_tx)
+        .with_plant_trigger(plant_trigger);
+    pollers.spawn_all(&config.boot_order());
+    let synthetic_handles: Vec<_> = config
+        .synthetic_devices
+        .iter()
+        .map(|device| {
+            assign_device_id(
+                &device_ids,
+                &DeviceIdentity {
+                    ip: device.name.clone(),
+                    unit_id: device.unit_id,
+                    base_address: None,
+                },
+            );
+            tokio::spawn(synthetic_task(
+                device.clone(),
+                config.base_address,
+                tx.clone(),
+                poller_config_rx.clone(),
+                shutdown_rx.clone(),
+            ))
+        })
+        .collect();
+
+    let log_rollup_handle = tokio::spawn(log_rollup_task(log_rollup, shutdown_rx.clone()));
+    let compaction_handle = tokio::spawn(buffer_compaction_task(
+        buffer.clone(),
+        Duration::from_millis(config.buffer_compact_interval_ms),
+        shutdown_rx.clone(),
+    ));
+    let heartbeat_handle = tokio::spawn(heartbeat_task(
+        registry.clone(),
+        stall_after,
+        Duration::from_millis(config.heartbeat_interval_ms),
+        shutdown_rx.clone(),
+    ));
+
     notify_ready();
-    let watchdog_handle = start_watchdog(shutdown_rx.clone());
+    let watchdog_handle = start_watchdog(shutdown_rx.clone(), registry.clone(), stall_after);
 
-    let mut shutdown_signal = tokio::signal::ctrl_c();
+    let mut reload_rx = spawn_reload_triggers(
+        config_source(config_path.as_deref()),
+        Duration::from_millis(CONFIG_WATCH_INTERVAL_MS),
+        shutdown_rx.clone(),
+    );
+
+    // Devices added by a reload while a maintenance window is open.
+    let mut deferred_devices: Vec<DeviceIdentity> = Vec::new();
+    let mut quarantine_check = interval(Duration::from_millis(QUARANTINE_CHECK_INTERVAL_MS));
+
+    // Addresses the bootstrap servers resolved to when the producer was built.
+    let mut bootstrap = BootstrapWatch::new();
+    let mut bootstrap_refresh = bootstrap_interval(&config);
+
+    let shutdown_signal = shutdown_signal();
+    tokio::pin!(shutdown_signal);
     loop {
         tokio::select! {
             _ = &mut shutdown_signal => {
                 info!("shutdown signal received");
-                let _ = shutdown_tx.send(true);
                 break;
             }
-            maybe_result = join_set.join_next() => {
-                if let Some(result) = maybe_result {
-                    match result {
-                        Ok((id, outcome)) => {
-                            if let Err(err) = outcome {
-                                warn!(device = %id, error = %err, "poller exited with error");
-                            } else {
-                                info!(device = %id, "poller exited cleanly");
-                            }
-                            if let Some(spec) = specs.get(&id) {
-                                spawn_poller(
-                                    spec.clone(),
-                                    &mut join_set,
-                                    Duration::from_millis(config.respawn_delay_ms),
-                                );
-                            }
-                        }
-                        Err(err) => {
-                            warn!(error = %err, "poller task failed");
-                        }
-                    }
+            _ = bootstrap_refresh.tick(), if config.kafka_bootstrap_refresh_ms > 0 => {
+                let Some(brokers) = config.kafka_brokers.clone() else {
+                    continue;
+                };
+                let resolved = match resolve_brokers(&brokers).await {
+                    Ok(resolved) => resolved,
+                    Err(err) => {
+                        warn!(%brokers, error = %err, "kafka bootstrap servers did not resolve, keeping current producer");
+                        continue;
+                    }
+                };
+                if bootstrap.changed(&resolved) {
+                    match build_publisher(&config) {
+                        Ok(publisher) => {
+                            info!(%brokers, "kafka bootstrap addresses changed, publisher rebuilt");
+                            counter!("kafka_bootstrap_refresh").increment(1);
+                            let _ = publisher_tx.send(publisher);
+                        }
+                        Err(err) => {
+                            // Not recorded, so the next refresh retries.
+                            warn!(error = %err, "kafka publisher rebuild failed");
+                            continue;
+                        }
+                    }
+                }
+                bootstrap.record(resolved);
+            }
+            Some(trigger) = reload_rx.recv() => {
+                let next = match CollectorConfig::load_with_path(config_path.clone())
+                    .and_then(|next| next.validate().map(|()| next))
+                    .map(|mut next| {
+                        if simulate {
+                            use_simulator(&mut next);
+                        }
+                        next
+                    })
+                {
+                    Ok(next) => next,
+                    Err(err) => {
+                        warn!(?trigger, error = %err, "config reload rejected, keeping current config");
+                        counter!("config_reload_error").increment(1);
+                        continue;
+                    }
+                };
+                // Every topic and sample stage would have to move at once.
+                if next.kafka_namespace != config.kafka_namespace {
+                    warn!(?trigger, "config reload rejected, kafka.namespace only changes on restart");
+                    counter!("config_reload_error").increment(1);
+                    continue;
+                }
+                let plan = plan_reload(&config, &next);
+                if plan.is_empty() {
+                    info!(?trigger, "config reload: nothing to apply");
+                    continue;
+                }
+
+                if plan.kafka_changed {
+                    let publisher = match build_publisher(&next) {
+                        Ok(publisher) => publisher,
+                        Err(err) => {
+                            // Keep the old broker settings so a later reload retries the change.
+                            warn!(error = %err, "config reload: publisher rebuild failed");
+                            counter!("config_reload_error").increment(1);
+                            continue;
+                        }
+                    };
+                    info!(topic = publisher.topic(), "config reload: publisher rebuilt");
+                    let _ = publisher_tx.send(publisher.clone());
+                    config.kafka_brokers = next.kafka_brokers.clone();
+                    config.kafka_topic = next.kafka_topic.clone();
+                    config.kafka_client_id = next.kafka_client_id.clone();
+                    config.kafka_acks = next.kafka_acks.clone();
+                    config.kafka_compression = next.kafka_compression.clone();
+                    config.kafka_timeout_ms = next.kafka_timeout_ms;
+                    config.kafka_enable_idempotence = next.kafka_enable_idempotence;
+                    config.kafka_max_message_bytes = next.kafka_max_message_bytes;
+                    config.kafka_transactional_id = next.kafka_transactional_id.clone();
+                    config.kafka_security = next.kafka_security.clone();
+                    config.kafka_routes = next.kafka_routes.clone();
+                    let _ = routes_tx.send(config.topic_router());
+                    config.kafka_encoding = next.kafka_encoding.clone();
+                    config.kafka_avro_codec = next.kafka_avro_codec.clone();
+                    config.kafka_schema_topic = next.kafka_schema_topic.clone();
+                    config.kafka_bootstrap_refresh_ms = next.kafka_bootstrap_refresh_ms;
+                    if config.encoding() == Encoding::Json {
+                        tokio::spawn(publish_json_schemas(
+                            schema_publishers(&publisher, &config),
+                            config.kafka_schema_topic.clone(),
+                        ));
+                    }
+                    // The new producer resolved the servers itself; start over.
+                    bootstrap.reset();
+                    bootstrap_refresh = bootstrap_interval(&config);
+                }
+
+                if plan.poller_changed {
+                    info!(
+                        poll_interval_ms = next.poller.poll_interval.as_millis() as u64,
+                        "config reload: poller settings updated"
+                    );
+                    config.poller = next.poller.clone();
+                    let _ = poller_config_tx.send(config.poller.clone());
+                }
+
+                if plan.rules_changed {
+                    // Running pollers keep their profile until they are rediscovered.
+                    info!("config reload: device rules updated, applied to devices added from now on");
+                    config.device_profiles = next.device_profiles.clone();
+                    config.device_rules = next.device_rules.clone();
+                }
+
+                for device in &plan.removed_devices {
+                    info!(ip = %device.ip, unit_id = device.unit_id, "config reload: device removed");
+                    pollers.remove(&device.key());
+                    restarts.forget(&device.key().to_string());
+                    serials.release(device);
+                    quarantine.release(device);
+                }
+                gauge!("devices_quarantined").set(quarantine.len() as f64);
+                // Site, alias and profile of devices added from now on.
+                config.inventory = next.inventory.clone();
+                deferred_devices.retain(|device| !plan.removed_devices.contains(device));
+                if !plan.added_devices.is_empty() && maintenance_rx.borrow().is_active() {
+                    info!(
+                        devices = plan.added_devices.len(),
+                        "config reload: maintenance window open, new devices deferred"
+                    );
+                    deferred_devices.extend(plan.added_devices.iter().cloned());
+                } else if !plan.added_devices.is_empty() {
+                    let added = build_poller_specs(
+                        &config,
+                        &plan.added_devices,
+                        &channels,
+                        &catalog,
+                        &mut serials,
+                        &mut quarantine,
+                        &buffer,
+                    )
+                    .await;
+                    for (id, spec) in added {
+                        let device = spec.identity.clone();
+                        if pollers.insert(id, spec) {
+                            info!(ip = %device.ip, unit_id = device.unit_id, "config reload: device added");
+                        }
+                    }
+                }
+                config.discovery.static_devices = next.discovery.static_devices.clone();
+                counter!("config_reload_applied").increment(1);
+            }
+            Some(devices) = rediscovered_rx.recv() => {
+                let found = build_poller_specs(
+                    &config,
+                    &devices,
+                    &channels,
+                    &catalog,
+                    &mut serials,
+                    &mut quarantine,
+                    &buffer,
+                )
+                .await;
+                for (id, spec) in found {
+                    let device = spec.identity.clone();
+                    let unchanged = pollers.specs.get(&id).map(|current| {
+                        current.identity == spec.identity && current.models == spec.models
+                    });
+                    match unchanged {
+                        Some(true) => {}
+                        Some(false) => {
+                            info!(ip = %device.ip, unit_id = device.unit_id, "rediscovery: models changed, poller restarted");
+                            pollers.remove(&id);
+                            restarts.forget(&id.to_string());
+                            pollers.insert(id, spec);
+                        }
+                        None => {
+                            if pollers.insert(id, spec) {
+                                info!(ip = %device.ip, unit_id = device.unit_id, "rediscovery: device added");
+                            }
+                        }
+                    }
+                }
+                info!(devices = devices.len(), "rediscovery complete");
+            }
+            Ok(()) = maintenance_rx.changed() => {
+                if maintenance_rx.borrow_and_update().is_active() || deferred_devices.is_empty() {
+                    continue;
+                }
+                let devices = std::mem::take(&mut deferred_devices);
+                let added = build_poller_specs(
+                    &config,
+                    &devices,
+                    &channels,
+                    &catalog,
+                    &mut serials,
+                    &mut quarantine,
+                    &buffer,
+                )
+                .await;
+                for (id, spec) in added {
+                    let device = spec.identity.clone();
+                    if pollers.insert(id, spec) {
+                        info!(ip = %device.ip, unit_id = device.unit_id, "maintenance window closed: deferred device added");
+                    }
+                }
+            }
+            _ = quarantine_check.tick(), if !quarantine.is_empty() => {
+                if maintenance_rx.borrow().is_active() {
+                    continue;
+                }
+                let due = quarantine.due(unix_ms());
+                if due.is_empty() {
+                    continue;
+                }
+                let added = build_poller_specs(
+                    &config,
+                    &due,
+                    &channels,
+                    &catalog,
+                    &mut serials,
+                    &mut quarantine,
+                    &buffer,
+                )
+                .await;
+                for (id, spec) in added {
+                    pollers.insert(id, spec);
+                }
+            }
+            maybe_result = pollers.join_set.join_next() => {
+                if let Some(result) = maybe_result {
+                    match result {
+                        Ok((device, outcome)) => {
+                            let key = device.key();
+                            let id = key.to_string();
+                            let unreachable = matches!(outcome, Err(PollerError::Connect(_)));
+                            let failed = outcome.is_err();
+                            if let Err(err) = outcome {
+                                warn!(ip = %device.ip, unit_id = device.unit_id, error = %err, error_class = err.class(), "poller exited with error");
+                                pollers.notify_failed(&key);
+                            } else {
+                                info!(ip = %device.ip, unit_id = device.unit_id, "poller exited cleanly");
+                            }
+                            if !unreachable {
+                                quarantine.clear_strikes(&device);
+                            } else if quarantine.connect_failed(&device, unix_ms()) {
+                                // Rediscovered by a probe once it answers again.
+                                warn!(
+                                    ip = %device.ip,
+                                    unit_id = device.unit_id,
+                                    failures = config.quarantine_after_failures,
+                                    "device not answering, quarantined"
+                                );
+                                counter!("device_quarantined", "ip" => device.ip.clone()).increment(1);
+                                gauge!("devices_quarantined").set(quarantine.len() as f64);
+                                restarts.forget(&id);
+                                pollers.remove(&key);
+                                continue;
+                            }
+                            let made_progress = pollers
+                                .registry
+                                .status(&id)
+                                .is_some_and(|status| status.last_success_ms.is_some());
+                            match restarts.exited(&id, failed, made_progress) {
+                                Restart::After(delay) => {
+                                    debug!(
+                                        ip = %device.ip,
+                                        unit_id = device.unit_id,
+                                        failures = restarts.failures(&id),
+                                        delay_ms = delay.as_millis() as u64,
+                                        "poller respawn scheduled"
+                                    );
+                                    counter!("poller_respawn", "ip" => device.ip.clone()).increment(1);
+                                    pollers.spawn(&key, delay);
+                                }
+                                Restart::BreakerOpen { failures } => {
+                                    // Only a successful quarantine probe brings it back.
+                                    warn!(
+                                        ip = %device.ip,
+                                        unit_id = device.unit_id,
+                                        failures,
+                                        "poller keeps failing, circuit breaker open until the device is rediscovered"
+                                    );
+                                    counter!("poller_circuit_open", "ip" => device.ip.clone()).increment(1);
+                                    quarantine.failed(&device, unix_ms());
+                                    gauge!("devices_quarantined").set(quarantine.len() as f64);
+                                    pollers.remove(&key);
+                                }
+                            }
+                        }
+                        Err(err) if err.is_cancelled() => {}
+                        Err(err) => {
+                            warn!(error = %err, "poller task failed");
+                        }
+                    }
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Ordered shutdown: stop pollers, drain the channel into SQLite, then give
+    // the uplink one bounded chance to flush what is buffered.
+    let deadline = Instant::now() + Duration::from_millis(config.shutdown_timeout_ms);
+    let _ = shutdown_tx.send(true);
+
+    let mut join_set = pollers.join_set;
+    if timeout_at(deadline, async {
+        while let Some(result) = join_set.join_next().await {
+            if let Err(err) = result {
+                warn!(error = %err, "poller task join failed");
+            }
+        }
+    })
+    .await
+    .is_err()
+    {
+        warn!("shutdown deadline reached while stopping pollers, aborting");
+        join_set.abort_all();
+        while join_set.join_next().await.is_some() {}
+    }
+    for mut handle in synthetic_handles {
+        if timeout_at(deadline, &mut handle).await.is_err() {
+            handle.abort();
+        }
+    }
+
+    // Drop every remaining sender so the buffer task sees the channel close.
+    drop(pollers.specs);
+    drop(pollers.sparkplug);
+    drop(channels);
+    drop(poller_config_rx);
+    drop(pause_rx);
+    drop(tx);
+    if timeout_at(deadline, &mut buffer_handle).await.is_err() {
+        warn!("shutdown deadline reached while draining channel into buffer");
+        buffer_handle.abort();
+    }
+    if let Some(handle) = aggregate_handle {
+        handle.abort();
+    }
+    // With every event sender gone the Sparkplug task publishes NDEATH and exits.
+    if let Some(mut handle) = sparkplug_handle {
+        if timeout_at(deadline, &mut handle).await.is_err() {
+            warn!("shutdown deadline reached before sparkplug death certificate");
+            handle.abort();
+        }
+    }
+    // The alarm task exits once it has published what the buffer task sent.
+    if let Some(mut handle) = alarm_handle {
+        if timeout_at(deadline, &mut handle).await.is_err() {
+            warn!("shutdown deadline reached while publishing alarms");
+            handle.abort();
+        }
+    }
+    if let Some(mut handle) = alert_handle {
+        if timeout_at(deadline, &mut handle).await.is_err() {
+            warn!("shutdown deadline reached while publishing alerts");
+            handle.abort();
+        }
+    }
+    // The gRPC sink exits once the service acknowledged what it was sent.
+    if let Some(mut handle) = grpc_handle {
+        if timeout_at(deadline, &mut handle).await.is_err() {
+            warn!("shutdown deadline reached while streaming to the grpc sink");
+            handle.abort();
+        }
+    }
+    // The influx sink exits once it has written the lines it holds.
+    if let Some(mut handle) = influx_handle {
+        if timeout_at(deadline, &mut handle).await.is_err() {
+            warn!("shutdown deadline reached while writing to influx");
+            handle.abort();
+        }
+    }
+    // Rows the postgres sink has not inserted stay in its queue.
+    if let Some(mut handle) = postgres_handle {
+        if timeout_at(deadline, &mut handle).await.is_err() {
+            warn!("shutdown deadline reached while inserting into postgres");
+            handle.abort();
+        }
+    }
+
+    let _ = uplink_shutdown_tx.send(true);
+    if timeout_at(deadline, &mut uplink_handle).await.is_err() {
+        warn!("shutdown deadline reached during final uplink flush, remaining rows stay buffered");
+        uplink_handle.abort();
+    }
+
+    let _ = heartbeat_handle.await;
+    let _ = log_rollup_handle.await;
+    let _ = compaction_handle.await;
+    let _ = resources_handle.await;
+    if let Some(handle) = daily_handle {
+        let _ = handle.await;
+    }
+    if let Some(handle) = plant_handle {
+        let _ = handle.await;
+    }
+    if let Some(handle) = anomaly_handle {
+        let _ = handle.await;
+    }
+    if let Some(handle) = watchdog_handle {
+        let _ = handle.await;
+    }
+    if let Some(handle) = maintenance_handle {
+        let _ = handle.await;
+    }
+    if let Some(handle) = leader_handle {
+        let _ = handle.await;
+    }
+    if let Some(handle) = simulator_handle {
+        handle.abort();
+    }
+    let _ = tokio::task::spawn_blocking(collector_app::otel::shutdown).await;
+    Ok(())
+}
+
+/// Starts the built-in device emulator on localhost and points discovery and
+/// polling at it instead of the configured devices.
+async fn start_simulator(config: &mut CollectorConfig) -> Result<JoinHandle<()>> {
+    let simulator = Simulator::new(
+        config.base_address,
+        &config.simulator_unit_ids,
+        &config.simulator_models,
+    )?;
+    if simulator.map_len() > usize::from(config.discovery_register_count) {
+        anyhow::bail!(
+            "simulated register map has {} registers, more than sunspec.discovery_register_count ({})",
+            simulator.map_len(),
+            config.discovery_register_count
+        );
+    }
+    let listener = TcpListener::bind(("127.0.0.1", config.simulator_port))
+        .await
+        .with_context(|| format!("bind simulator port {}", config.simulator_port))?;
+    use_simulator(config);
+    Ok(tokio::spawn(async move {
+        if let Err(err) = simulator.serve(listener).await {
+            warn!(error = %err, "sunspec simulator stopped");
+        }
+    }))
+}
+
+/// Replaces the device list with the simulated units. Reads are kept within
+/// the Modbus limit of 125 registers the emulator enforces.
+fn use_simulator(config: &mut CollectorConfig) {
+    config.discovery.static_devices = config
+        .simulator_unit_ids
+        .iter()
+        .map(|&unit_id| DeviceIdentity {
+            ip: "127.0.0.1".to_string(),
+            unit_id,
+            base_address: None,
+        })
+        .collect();
+    config.discovery.port = config.simulator_port;
+    config.modbus.port = config.simulator_port;
+    config.modbus.max_batch_size = Some(config.modbus.max_batch_size.unwrap_or(125).min(125));
+}
+
+fn build_publisher(config: &CollectorConfig) -> Result<Publisher> {
+    let Some(brokers) = config.kafka_brokers.clone() else {
+        let publisher = Publisher::new_mock(Publisher::default_schema(), "sunspec.telemetry");
+        return Ok(configure_publisher(publisher, config));
+    };
+
+    let kafka_config = KafkaConfig {
+        brokers,
+        client_id: config
+            .kafka_client_id
+            .clone()
+            .unwrap_or_else(|| "sunspec-collector".to_string()),
+        acks: config.kafka_acks.clone().unwrap_or_else(|| "all".to_string()),
+        compression: config
+            .kafka_compression
+            .clone()
+            .unwrap_or_else(|| "zstd".to_string()),
+        message_timeout_ms: config.kafka_timeout_ms.unwrap_or(5_000),
+        enable_idempotence: config
+            .kafka_enable_idempotence
+            .unwrap_or(KafkaConfig::default().enable_idempotence),
+        max_message_bytes: config
+            .kafka_max_message_bytes
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES),
+        transactional_id: config.kafka_transactional_id.clone(),
+        security: config.kafka_security.clone(),
+    };
+
+    Publisher::new_kafka(
+        Publisher::default_schema(),
+        config.kafka_topic.clone().unwrap_or_else(|| "sunspec.telemetry".to_string()),
+        kafka_config,
+    )
+    .map(|publisher| configure_publisher(publisher, config))
+    .context("kafka publisher init failed")
+}
+
+/// Sets the payload encoding and registers the schemas of the enabled daily
+/// summary, alarm, alert and plant snapshot topics, so their tasks publish
+/// through the telemetry producer.
+fn configure_publisher(publisher: Publisher, config: &CollectorConfig) -> Publisher {
+    let mut publisher = publisher
+        .with_encoding(config.encoding())
+        .with_avro_codec(config.avro_codec());
+    if config.daily_enabled {
+        publisher =
+            publisher.with_topic_schema(&config.daily_topic, Publisher::daily_summary_schema());
+    }
+    if config.alarms_enabled {
+        publisher = publisher.with_topic_schema(&config.alarms_topic, Publisher::alarm_schema());
+    }
+    if config.alerts_enabled {
+        publisher = publisher.with_topic_schema(&config.alerts_topic, Publisher::alert_schema());
+    }
+    if config.plant_snapshot_enabled {
+        publisher = publisher.with_topic_schema(
+            &config.plant_snapshot_topic,
+            Publisher::plant_snapshot_schema(),
+        );
+    }
+    publisher
+}
+
+/// Ticks every `kafka_bootstrap_refresh_ms`, the first time right away so the
+/// addresses the producer starts with are recorded.
+fn bootstrap_interval(config: &CollectorConfig) -> Interval {
+    interval(Duration::from_millis(config.kafka_bootstrap_refresh_ms.max(1)))
+}
+
+/// Publishers of the telemetry topic and of the enabled daily summary,
+/// alarm, alert and plant snapshot topics.
+fn schema_publishers(publisher: &Publisher, config: &CollectorConfig) -> Vec<Publisher> {
+    let mut publishers = vec![publisher.clone()];
+    // Templated routes name their topics only when a sample arrives.
+    for route in config
+        .kafka_routes
+        .iter()
+        .filter(|route| !route.topic.contains('{'))
+    {
+        publishers.push(publisher.for_topic(Publisher::default_schema(), &route.topic));
+    }
+    let topics = [
+        (config.daily_enabled, &config.daily_topic),
+        (config.alarms_enabled, &config.alarms_topic),
+        (config.alerts_enabled, &config.alerts_topic),
+        (config.plant_snapshot_enabled, &config.plant_snapshot_topic),
+    ];
+    for (_, topic) in topics.into_iter().filter(|(enabled, _)| *enabled) {
+        publishers.push(publisher.for_topic(publisher.schema_for(topic).clone(), topic));
+    }
+    publishers
+}
+
+/// Publishes the JSON Schema of each publisher's topic so JSON consumers can
+/// validate what they read.
+async fn publish_json_schemas(publishers: Vec<Publisher>, schema_topic: String) {
+    for publisher in &publishers {
+        match publisher.publish_json_schema(&schema_topic).await {
+            Ok(()) => info!(topic = publisher.topic(), %schema_topic, "json schema published"),
+            Err(err) => {
+                warn!(topic = publisher.topic(), %schema_topic, error = %err, "json schema publish failed");
+                counter!("json_schema_publish_error").increment(1);
+            }
+        }
+    }
+}
+
+/// Input of the Sparkplug task.
+enum SparkplugEvent {
+    /// A poller (re)started; the device needs a new birth certificate.
+    DeviceStarted(DeviceIdentity),
+    DeviceFailed(DeviceIdentity),
+    Data {
+        device: DeviceIdentity,
+        points: Vec<(String, f64)>,
+        collected_at_ms: u64,
+    },
+}
+
+#[derive(Clone)]
+struct PollerSpec {
+    identity: DeviceIdentity,
+    modbus_config: ClientConfig,
+    models: Vec<ModelDefinition>,
+    /// Every discovered model, read once on start when initial snapshots are on.
+    snapshot_models: Option<Vec<ModelDefinition>>,
+    model_intervals: HashMap<u16, Duration>,
+    scan_classes: HashMap<u16, ScanClass>,
+    clock: Option<ClockConfig>,
+    labels: DeviceLabels,
+    /// Class in the boot order.
+    class: Option<String>,
+    config_updates: watch::Receiver<ActorConfig>,
+    pause: watch::Receiver<bool>,
+    sender: mpsc::Sender<PollSample>,
+    shutdown: watch::Receiver<bool>,
+    sample_clock: SampleClock,
+    throttle: PollThrottle,
+}
+
+/// Channels every poller is created with.
+#[derive(Clone)]
+struct PollerChannels {
+    sender: mpsc::Sender<PollSample>,
+    shutdown: watch::Receiver<bool>,
+    config_updates: watch::Receiver<ActorConfig>,
+    pause: watch::Receiver<bool>,
+    /// Shared by every poller, so the time sync check flags all of them.
+    sample_clock: SampleClock,
+    /// Shared by every poller, so the resource monitor slows all of them.
+    throttle: PollThrottle,
+}
+
+async fn build_poller_specs(
+    config: &CollectorConfig,
+    devices: &[DeviceIdentity],
+    channels: &PollerChannels,
+    catalog: &ModelCatalog,
+    serials: &mut SerialRegistry,
+    quarantine: &mut Quarantine,
+    buffer: &BufferStore,
+) -> HashMap<DeviceKey, PollerSpec> {
+    let mut specs = HashMap::new();
+
+    for device in devices {
+        let discovered = discover_models_for_device(config, catalog, device).await;
+        if discovered.is_ok() {
+            if let Some(entry) = quarantine.release(device) {
+                info!(
+                    ip = %device.ip,
+                    unit_id = device.unit_id,
+                    quarantined_ms = unix_ms().saturating_sub(entry.since_ms),
+                    failed_probes = entry.failed_probes,
+                    "quarantined device answers again"
+                );
+                counter!("device_quarantine_released", "ip" => device.ip.clone()).increment(1);
+            }
+        }
+        match discovered {
+            Ok((_, models, _)) if models.is_empty() => {
+                warn!(ip = %device.ip, "no models discovered");
+            }
+            Ok((identity, models, common)) => {
+                let discovered = DiscoveredDevice::new(identity, models, common, unix_ms());
+                if let Err(err) = discovered::record(buffer, &discovered).await {
+                    warn!(ip = %device.ip, unit_id = device.unit_id, error = %err, "inventory not saved");
+                }
+                if let Some(spec) = poller_spec(config, device, discovered, channels, serials) {
+                    specs.insert(device.key(), spec);
+                }
+            }
+            Err(err) => {
+                if quarantine.failed(device, unix_ms()) {
+                    warn!(
+                        ip = %device.ip,
+                        unit_id = device.unit_id,
+                        error = %err,
+                        "model discovery failed, device quarantined"
+                    );
+                    counter!("device_quarantined", "ip" => device.ip.clone()).increment(1);
+                } else {
+                    debug!(
+                        ip = %device.ip,
+                        unit_id = device.unit_id,
+                        error = %err,
+                        "quarantine probe failed"
+                    );
+                }
+            }
+        }
+    }
+    gauge!("devices_quarantined").set(quarantine.len() as f64);
+
+    specs
+}
+
+/// Builds the poller of a discovered device, applying device rules, model
+/// filters and the inventory. None when nothing is left to poll or the unit
+/// mirrors another one.
+fn poller_spec(
+    config: &CollectorConfig,
+    device: &DeviceIdentity,
+    discovered: DiscoveredDevice,
+    channels: &PollerChannels,
+    serials: &mut SerialRegistry,
+) -> Option<PollerSpec> {
+    let DiscoveredDevice {
+        device: identity,
+        models,
+        common,
+        ..
+    } = discovered;
+    let modbus_config = config.modbus_for(device);
+
+    let inventory = config.inventory_device(device);
+    let policy = DevicePolicy::resolve(
+        &config.device_rules,
+        &config.device_profiles,
+        common.as_ref(),
+    )
+    .with_profile(
+        &config.device_profiles,
+        inventory.and_then(|entry| entry.profile.as_deref()),
+    )
+    .with_model_filters(&config.model_filter, &config.device_model_filters, device);
+    let labels = inventory.map(InventoryDevice::labels).unwrap_or_default();
+    if !policy.profiles.is_empty() {
+        info!(
+            ip = %device.ip,
+            site = labels.site.as_deref(),
+            alias = labels.alias.as_deref(),
+            manufacturer = common.as_ref().map(|c| c.manufacturer.as_str()),
+            model = common.as_ref().map(|c| c.model.as_str()),
+            profiles = ?policy.profiles,
+            "device profiles applied"
+        );
+    }
+    let snapshot_models = config.initial_snapshot.then(|| models.clone());
+    let clock = config
+        .clock_settings()
+        .and_then(|settings| settings.resolve(&identity, &models));
+    let model_ids: Vec<u16> = models.iter().map(|model| model.id).collect();
+    let class = policy
+        .class
+        .clone()
+        .or_else(|| device_class(&model_ids).map(str::to_string));
+    let models = policy.select_models(models);
+    if models.is_empty() {
+        warn!(ip = %device.ip, "no models left to poll after device rules and model filters");
+        return None;
+    }
+
+    let serial = common
+        .as_ref()
+        .and_then(|c| SerialIdentity::new(&c.manufacturer, &c.serial_number));
+    if let Some(primary) = serial.as_ref().and_then(|sn| serials.claim(device, sn)) {
+        // Polling both would publish the same device twice.
+        warn!(
+            ip = %device.ip,
+            unit_id = device.unit_id,
+            mirror_of_unit_id = primary.unit_id,
+            serial_number = serial.as_ref().map(|sn| sn.serial_number.as_str()),
+            error_class = "mirrored_unit",
+            "unit reports the serial number of another unit on the same gateway, not polling it"
+        );
+        counter!(
+            "device_misconfiguration",
+            "ip" => device.ip.clone(),
+            "kind" => "mirrored_unit"
+        )
+        .increment(1);
+        return None;
+    }
+
+    Some(PollerSpec {
+        identity,
+        modbus_config,
+        models,
+        snapshot_models,
+        model_intervals: policy.model_intervals,
+        scan_classes: policy.scan_classes,
+        clock,
+        labels,
+        class,
+        config_updates: channels.config_updates.clone(),
+        pause: channels.pause.clone(),
+        sender: channels.sender.clone(),
+        shutdown: channels.shutdown.clone(),
+        sample_clock: channels.sample_clock.clone(),
+        throttle: channels.throttle.clone(),
+    })
+}
+
+/// Gives the device its persistent numeric ID before its first sample, so
+/// Sparkplug aliases can be derived from it. A failed write only costs the
+/// ID's persistence; the device keeps it until the collector restarts.
+fn assign_device_id(ids: &DeviceIds, device: &DeviceIdentity) {
+    let (id, err) = ids.assign(device);
+    if let Some(err) = err {
+        warn!(
+            ip = %device.ip,
+            unit_id = device.unit_id,
+            device_id = id,
+            error = %err,
+            "device id not persisted"
+        );
+    }
+}
+
+/// Running pollers plus what is needed to respawn or retire them, keyed by
+/// IP and unit ID so units behind one gateway get their own poller.
+struct Pollers {
+    specs: HashMap<DeviceKey, PollerSpec>,
+    handles: HashMap<DeviceKey, AbortHandle>,
+    join_set: JoinSet<(DeviceIdentity, Result<(), PollerError>)>,
+    registry: PollerRegistry,
+    device_ids: DeviceIds,
+    /// Sample numbering per device, kept when a poller is removed so the
+    /// sequence continues if the device comes back.
+    counters: HashMap<DeviceKey, Arc<SampleCounters>>,
+    sparkplug: Option<mpsc::Sender<SparkplugEvent>>,
+    plant: Option<PlantTrigger>,
+}
+
+/// Where pollers of inverters and meters get their plant snapshot triggers
+/// and send the reads.
+struct PlantTrigger {
+    trigger: Arc<watch::Sender<u64>>,
+    reads: mpsc::Sender<TriggeredSample>,
+}
+
+impl Pollers {
+    fn new(
+        specs: HashMap<DeviceKey, PollerSpec>,
+        registry: PollerRegistry,
+        device_ids: DeviceIds,
+        sparkplug: Option<mpsc::Sender<SparkplugEvent>>,
+    ) -> Self {
+        Self {
+            specs,
+            handles: HashMap::new(),
+            join_set: JoinSet::new(),
+            registry,
+            device_ids,
+            counters: HashMap::new(),
+            sparkplug,
+            plant: None,
+        }
+    }
+
+    /// Pollers of devices with an inverter or meter model join the plant
+    /// snapshots.
+    fn with_plant_trigger(mut self, plant: Option<PlantTrigger>) -> Self {
+        self.plant = plant;
+        self
+    }
+
+    /// Starts every poller, each class of the boot order one step after
+    /// the class before it.
+    fn spawn_all(&mut self, boot: &BootOrder) {
+        let mut starts: Vec<(Duration, DeviceKey)> = self
+            .specs
+            .iter()
+            .map(|(id, spec)| (boot.delay(spec.class.as_deref()), id.clone()))
+            .collect();
+        starts.sort();
+        if !boot.classes.is_empty() {
+            info!(
+                order = ?boot.classes,
+                step_ms = boot.step.as_millis(),
+                "pollers start in boot order"
+            );
+        }
+        for (delay, id) in starts {
+            if delay > Duration::ZERO {
+                debug!(
+                    poller = %id,
+                    class = self.specs.get(&id).and_then(|spec| spec.class.as_deref()),
+                    delay_ms = delay.as_millis(),
+                    "poller start held back by the boot order"
+                );
+            }
+            self.spawn(&id, delay);
+        }
+    }
+
+    /// Starts (or restarts) the poller for `key` if it still has a spec.
+    fn spawn(&mut self, key: &DeviceKey, delay: Duration) {
+        let Some(spec) = self.specs.get(key).cloned() else {
+            return;
+        };
+        let id = key.to_string();
+        let identity = spec.identity.clone();
+        assign_device_id(&self.device_ids, &identity);
+        let plant = self.plant.as_ref().filter(|_| {
+            spec.models
+                .iter()
+                .any(|model| SNAPSHOT_MODELS.contains(&model.id))
+        });
+        let mut actor = PollerActor::new(
+            spec.identity,
+            spec.modbus_config,
+            spec.models,
+            spec.sender,
+            spec.shutdown,
+            ActorConfig::default(),
+        )
+        .with_config_updates(spec.config_updates)
+        .with_model_intervals(spec.model_intervals)
+        .with_scan_classes(spec.scan_classes)
+        .with_pause(spec.pause)
+        .with_sample_clock(spec.sample_clock)
+        .with_throttle(spec.throttle)
+        .with_counters(self.counters.entry(key.clone()).or_default().clone());
+        if let Some(models) = spec.snapshot_models {
+            actor = actor.with_initial_snapshot(models);
+        }
+        if let Some(clock) = spec.clock {
+            actor = actor.with_clock(clock);
+        }
+        if let Some(captures) = self.registry.captures() {
+            actor = actor.with_capture(captures.tap(&id));
+        }
+        #[cfg(feature = "fault-injection")]
+        if let Some(faults) = injected_faults() {
+            actor = actor.with_faults(faults);
+        }
+        if let Some(control) = self.registry.control() {
+            let (commands, paused) = control.attach(&id);
+            actor = actor.with_commands(commands, paused);
+        }
+        if let Some(plant) = plant {
+            actor = actor.with_triggered_reads(
+                plant.trigger.subscribe(),
+                SNAPSHOT_MODELS.to_vec(),
+                plant.reads.clone(),
+            );
+        }
+        self.registry.register(id.clone(), actor.status());
+        self.registry.label(id, spec.labels);
+        self.notify_sparkplug(SparkplugEvent::DeviceStarted(identity.clone()));
+        let handle = self.join_set.spawn(async move {
+            if delay > Duration::from_millis(0) {
+                sleep(delay).await;
+            }
+            (identity, actor.run().await)
+        });
+        self.handles.insert(key.clone(), handle);
+    }
+
+    /// Adds a poller unless one is already running for `key` (e.g. found by the scan).
+    fn insert(&mut self, key: DeviceKey, spec: PollerSpec) -> bool {
+        if self.specs.contains_key(&key) {
+            return false;
+        }
+        self.specs.insert(key.clone(), spec);
+        self.spawn(&key, Duration::from_millis(0));
+        true
+    }
+
+    /// Reports a failed poller so its device gets a death certificate.
+    fn notify_failed(&self, key: &DeviceKey) {
+        if let Some(spec) = self.specs.get(key) {
+            self.notify_sparkplug(SparkplugEvent::DeviceFailed(spec.identity.clone()));
+        }
+    }
+
+    fn notify_sparkplug(&self, event: SparkplugEvent) {
+        if let Some(sparkplug) = self.sparkplug.as_ref() {
+            if sparkplug.try_send(event).is_err() {
+                counter!("sparkplug_event_dropped").increment(1);
+            }
+        }
+    }
+
+    /// Stops the poller for `key` and forgets its spec so it is not respawned.
+    fn remove(&mut self, key: &DeviceKey) {
+        self.specs.remove(key);
+        if let Some(handle) = self.handles.remove(key) {
+            handle.abort();
+        }
+        self.registry.remove(&key.to_string());
+    }
+}
+
+/// Restores the saved catalog, then loads the model directory over it and
+/// saves the result. With a saved catalog an unreadable model directory is
+/// only a warning; without one it stops the startup as before.
+fn load_catalog(config: &CollectorConfig) -> Result<ModelCatalog> {
+    let catalog = ModelCatalog::new();
+    let saved = config.sunspec_catalog_path.as_deref().map(Path::new);
+    let mut restored = false;
+    if let Some(path) = saved.filter(|path| path.exists()) {
+        match catalog.load(path) {
+            Ok(models) => {
+                info!(path = %path.display(), models, "sunspec model catalog restored");
+                restored = true;
+            }
+            Err(err) => warn!(error = %err, "saved sunspec model catalog ignored"),
+        }
+    }
+    if let Some(dir) = config.sunspec_model_dir.as_deref() {
+        match catalog.load_dir(Path::new(dir)) {
+            Ok(loaded) => info!(dir, models = loaded, "sunspec model files loaded"),
+            Err(err) if restored => {
+                warn!(
+                    dir,
+                    error = %err,
+                    "sunspec model files not loaded, using the saved catalog"
+                );
+            }
+            Err(err) => return Err(err).context("load sunspec model files failed"),
+        }
+    }
+    if let Some(path) = saved {
+        if let Err(err) = catalog.save(path) {
+            warn!(error = %err, "sunspec model catalog not saved");
+        }
+    }
+    Ok(catalog)
+}
+
+/// Finds the base address, then reads the model list and, when present, the
+/// common model that device rules are matched against. The returned identity
+/// carries the base address the device answered on. Models the catalog knows
+/// from model files take their name from there. Devices with a static model
+/// map skip all of this and are polled by their map.
+async fn discover_models_for_device(
+    config: &CollectorConfig,
+    catalog: &ModelCatalog,
+    device: &DeviceIdentity,
+) -> Result<(DeviceIdentity, Vec<ModelDefinition>, Option<CommonModel>)> {
+    let modbus_config = config.modbus_for(device);
+
+    let client = ModbusClient::connect(modbus_config)
+        .await
+        .context("modbus connect failed")?;
+    if let Some(map) = config.model_maps.iter().find(|map| map.matches(device)) {
+        // No model list to read; one register shows the device answers.
+        let first = &map.models[0];
+        client
+            .read_range(device.unit_id, first.start, 1)
+            .await
+            .context("read static model map failed")?;
+        return Ok((device.clone(), map.definitions(), None));
+    }
+    let base_address = detect_base_address(config, device, &client).await?;
+    let registers = client
+        .read_range(device.unit_id, base_address, config.discovery_register_count)
+        .await
+        .context("read sunspec model list failed")?;
+
+    let mut models = parse_models_from_registers_lenient(base_address, &registers)
+        .map_err(|err| anyhow::anyhow!(err))?;
+    for model in &mut models {
+        if let Some(known) = catalog.model(model.id) {
+            model.name = known.name;
+        }
+    }
+    let common = models.iter().find(|model| model.id == 1).and_then(|model| {
+        let offset = usize::from(model.start - base_address);
+        let block = registers.get(offset..offset + usize::from(model.length))?;
+        decode_common(model.id, block)
+    });
+    let identity = DeviceIdentity {
+        base_address: Some(base_address),
+        ..device.clone()
+    };
+    Ok((identity, models, common))
+}
+
+/// First candidate base address holding the `SunS` marker. The device's own
+/// base address (from the static device list) or `sunspec.base_address` is
+/// tried first; the other standard addresses only with detection enabled.
+async fn detect_base_address(
+    config: &CollectorConfig,
+    device: &DeviceIdentity,
+    client: &ModbusClient,
+) -> Result<u16> {
+    let preferred = device.base_address.unwrap_or(config.base_address);
+    let candidates = if config.detect_base_address {
+        base_address_candidates(preferred)
+    } else {
+        vec![preferred]
+    };
+    for &candidate in &candidates {
+        match client.read_range(device.unit_id, candidate, 2).await {
+            Ok(registers) if has_sunspec_marker(&registers) => {
+                if candidate != preferred {
+                    info!(
+                        ip = %device.ip,
+                        unit_id = device.unit_id,
+                        base_address = candidate,
+                        configured = preferred,
+                        "sunspec base address detected"
+                    );
+                }
+                return Ok(candidate);
+            }
+            Ok(_) => {}
+            Err(err) => {
+                debug!(ip = %device.ip, base_address = candidate, error = %err, "base address probe failed");
+            }
+        }
+    }
+    anyhow::bail!("no SunSpec marker at base addresses {candidates:?}")
+}
+
+/// Windows samples of aggregated models and forwards everything else as is.
+/// When the poller channel closes the open (partial) windows are forwarded
+/// too, then the output channel is dropped so the buffer task can finish.
+async fn aggregate_task(
+    mut rx: mpsc::Receiver<PollSample>,
+    tx: mpsc::Sender<PollSample>,
+    mut aggregator: WindowAggregator,
+) {
+    let mut tick = interval(Duration::from_secs(1));
+    loop {
+        let ready = tokio::select! {
+            maybe_sample = rx.recv() => match maybe_sample {
+                Some(sample) => aggregator.push(sample),
+                None => break,
+            },
+            _ = tick.tick() => aggregator.flush_expired(unix_ms()),
+        };
+        for sample in ready {
+            if tx.send(sample).await.is_err() {
+                warn!("buffer channel closed, dropping aggregated sample");
+                return;
+            }
+        }
+    }
+    for sample in aggregator.flush_all() {
+        if tx.send(sample).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Optional per-sample stages run by the buffer task.
+struct SampleStages {
+    /// Latest block per device and model, for `/api/devices/{id}/points` and
+    /// `/api/devices/{id}/latest`.
+    points: PointDirectory,
+    dedup: Option<SampleDeduplicator>,
+    daily: Option<Arc<Mutex<DailyAccumulator>>>,
+    anomaly: Option<Arc<Mutex<AnomalyStage>>>,
+    /// Alarm transitions go to the alarm task through the sender.
+    alarms: Option<(AlarmTracker, mpsc::Sender<AlarmRecord>)>,
+    /// Threshold alerts go to the alert task; the tracker is shared with
+    /// `/api/alerts`.
+    alerts: Option<(Arc<Mutex<AlertTracker>>, mpsc::Sender<AlertRecord>)>,
+    sparkplug: Option<mpsc::Sender<SparkplugEvent>>,
+    file_sink: Option<FileSink>,
+    /// Decoded telemetry for the gRPC sink task.
+    grpc_sink: Option<mpsc::Sender<Telemetry>>,
+    /// Line-protocol lines for the influx sink task.
+    influx: Option<(LineEncoder, mpsc::Sender<String>)>,
+    /// Queue of the postgres sink task.
+    postgres: Option<BufferStore>,
+    /// Point tables of devices polled by a static model map.
+    model_maps: ModelMaps,
+    /// Open maintenance windows; samples collected meanwhile are flagged.
+    maintenance: watch::Receiver<MaintenanceStatus>,
+    /// `[[kafka.routes]]`, replaced on config reload.
+    routes: watch::Receiver<TopicRouter>,
+    /// `[site]`, stamped onto every sample.
+    site: Option<SiteInfo>,
+    /// `kafka.namespace`, stamped onto every sample.
+    namespace: Option<String>,
+    /// `buffer.write_through`: publish without buffering when possible.
+    write_through: Option<DirectPublish>,
+}
+
+/// State of the write-through path of the buffer task.
+struct DirectPublish {
+    mode: WriteThrough,
+    broker: BrokerStatus,
+    /// Samples published together at most.
+    batch_size: usize,
+}
+
+/// Moves samples from the poller channel into SQLite, dropping unchanged
+/// blocks when deduplication is enabled. With write-through the samples
+/// are published right away instead while that works. Runs until every
+/// sender has been dropped, so samples still queued at shutdown are
+/// persisted.
+async fn buffer_task(
+    mut rx: mpsc::Receiver<PollSample>,
+    buffer: BufferStore,
+    publisher: watch::Receiver<Publisher>,
+    mut stages: SampleStages,
+) {
+    let max_batch = stages
+        .write_through
+        .as_ref()
+        .map_or(1, |direct| direct.batch_size.max(1));
+    while let Some(sample) = rx.recv().await {
+        let mut ready = Vec::new();
+        ready.extend(stage_sample(sample, &mut stages, &publisher));
+        // The samples of a poll cycle arrive together; publish them together.
+        while ready.len() < max_batch {
+            let Ok(sample) = rx.try_recv() else {
+                break;
+            };
+            ready.extend(stage_sample(sample, &mut stages, &publisher));
+        }
+        let mut pending = ready.as_slice();
+        if let Some(direct) = stages.write_through.as_mut() {
+            let delivered = publish_direct(direct, &buffer, &publisher, pending).await;
+            pending = &pending[delivered..];
+        }
+        for (topic, sample) in pending {
+            // Store lightweight JSON in buffer instead of Avro
+            match serde_json::to_vec(sample) {
+                Ok(payload) => {
+                    if let Err(err) = buffer.enqueue(topic, &payload).await {
+                        warn!(error = %err, "buffer enqueue failed");
+                        counter!("buffer_enqueue_error").increment(1);
+                    } else {
+                        counter!("buffer_enqueue_success").increment(1);
+                    }
+                }
+                Err(err) => {
+                    warn!(error = %err, "json serialization failed");
+                }
+            }
+        }
+        // Queued whether or not the sample went out directly.
+        if let Some(queue) = stages.postgres.as_ref() {
+            for (_, sample) in &ready {
+                let row = SampleRow::new(sample, decoded_points(sample, &stages.model_maps));
+                postgres_sink::enqueue(queue, &row).await;
+            }
+        }
+    }
+    if let Some(sink) = stages.file_sink.as_mut() {
+        if let Err(err) = sink.flush() {
+            warn!(error = %err, "file sink flush failed");
+        }
+    }
+    info!("buffer channel closed, all in-flight samples persisted");
+}
+
+/// Runs the per-sample stages and returns the sample with the topic it is
+/// routed to, or None when deduplication drops it.
+fn stage_sample(
+    mut sample: PollSample,
+    stages: &mut SampleStages,
+    publisher: &watch::Receiver<Publisher>,
+) -> Option<(String, PollSample)> {
+    sample.maintenance = stages.maintenance.borrow().is_active();
+    sample.site = stages.site.clone();
+    sample.namespace = stages.namespace.clone();
+    stages.points.record(&sample);
+    let storage = decode_storage(sample.model_id, &sample.registers);
+    if let Some(soc) = storage.and_then(|reading| reading.soc_pct) {
+        let device = &sample.device;
+        gauge!("storage_soc_pct", "ip" => device.ip.clone(), "unit_id" => device.unit_id.to_string())
+            .set(soc);
+    }
+    // Daily totals and anomaly checks see every sample, including the ones dedup drops.
+    if let Some(daily) = stages.daily.as_ref() {
+        lock(daily).record(&sample);
+    }
+    // Devices under maintenance are expected to underperform their peers.
+    if let Some(anomaly) = stages.anomaly.as_ref().filter(|_| !sample.maintenance) {
+        lock(anomaly).record(&sample);
+    }
+    if let Some((tracker, alarms)) = stages.alarms.as_mut() {
+        for record in tracker.observe(&sample) {
+            // Like Sparkplug, never hold up buffering; drops are counted.
+            if alarms.try_send(record).is_err() {
+                counter!("alarm_dropped").increment(1);
+            }
+        }
+    }
+    if let Some((tracker, alerts)) = stages.alerts.as_ref() {
+        let points = decoded_points(&sample, &stages.model_maps);
+        let mut tracker = lock(tracker);
+        let records = tracker.observe(&sample, &points);
+        if !records.is_empty() {
+            gauge!("alerts_active").set(tracker.active().len() as f64);
+        }
+        for record in records {
+            if alerts.try_send(record).is_err() {
+                counter!("alert_dropped").increment(1);
+            }
+        }
+    }
+    if let Some(sparkplug) = stages.sparkplug.as_ref() {
+        let points = sparkplug_points(&sample, &stages.model_maps);
+        if !points.is_empty() {
+            let event = SparkplugEvent::Data {
+                device: sample.device.clone(),
+                points,
+                collected_at_ms: sample.collected_at_ms,
+            };
+            // Sparkplug is live data; never hold up buffering for it.
+            if sparkplug.try_send(event).is_err() {
+                counter!("sparkplug_event_dropped").increment(1);
+            }
+        }
+    }
+    if let Some(dedup) = stages.dedup.as_mut() {
+        // Baseline samples are always kept, but still count as published.
+        if !dedup.admit(&sample) && !sample.baseline {
+            counter!("samples_deduplicated").increment(1);
+            return None;
+        }
+    }
+    if let Some(sink) = stages.file_sink.as_mut() {
+        if let Err(err) = sink.write(&sample) {
+            warn!(error = %err, "file sink write failed");
+            counter!("file_sink_error").increment(1);
+        }
+    }
+    if stages.grpc_sink.is_some() || stages.influx.is_some() {
+        let points = decoded_points(&sample, &stages.model_maps);
+        // A slow or unreachable sink must not hold up buffering.
+        if let Some((encoder, influx)) = stages.influx.as_ref() {
+            if let Some(line) = encoder.encode(&sample, &points) {
+                if influx.try_send(line).is_err() {
+                    counter!("influx_sink_dropped").increment(1);
+                }
+            }
+        }
+        if let Some(grpc) = stages.grpc_sink.as_ref() {
+            let record = grpc_sink::telemetry(&sample, points);
+            if grpc.try_send(record).is_err() {
+                counter!("grpc_sink_dropped").increment(1);
+            }
+        }
+    }
+    let topic = stages
+        .routes
+        .borrow()
+        .topic(&sample, publisher.borrow().topic());
+    Some((topic, sample))
+}
+
+/// Publishes `samples` in order while write-through is on and returns how
+/// many were delivered; the rest are left to the buffer. Anything short of
+/// delivering all of them turns write-through off until the uplink has
+/// drained the buffer again, so no sample overtakes a buffered one.
+async fn publish_direct(
+    direct: &mut DirectPublish,
+    buffer: &BufferStore,
+    publisher: &watch::Receiver<Publisher>,
+    samples: &[(String, PollSample)],
+) -> usize {
+    let broker_up = direct.broker.state() == BrokerState::Up;
+    let now = std::time::Instant::now();
+    if direct.mode.should_check(broker_up, now) {
+        match buffer.pending_count().await {
+            Ok(pending) => direct.mode.checked(pending),
+            Err(err) => warn!(error = %err, "buffer count failed"),
+        }
+        if direct.mode.is_direct() {
+            info!("buffer empty and broker up, publishing samples directly");
+        }
+    }
+    if !direct.mode.is_direct() {
+        return 0;
+    }
+    let publisher = publisher.borrow().clone();
+    let mut delivered = 0;
+    while delivered < samples.len() {
+        let topic = &samples[delivered].0;
+        let run: Vec<&PollSample> = samples[delivered..]
+            .iter()
+            .take_while(|(other, _)| other == topic)
+            .map(|(_, sample)| sample)
+            .collect();
+        let encoded = encode_samples(&publisher, &run);
+        if !encoded.rejected.is_empty() {
+            // Buffered, so the uplink dead-letters what cannot be published.
+            break;
+        }
+        let mut failed = false;
+        for payload in encoded.payloads {
+            let start = std::time::Instant::now();
+            if let Err(err) = publisher.publish_bytes(topic, &payload.bytes).await {
+                warn!(error = %err, "direct publish failed, buffering samples");
+                failed = true;
+                break;
+            }
+            histogram!("uplink_publish_latency").record(start.elapsed());
+            delivered += payload.samples.len();
+        }
+        if failed {
+            break;
+        }
+    }
+    if delivered > 0 {
+        counter!("buffer_write_through").increment(delivered as u64);
+        broker_transition(&direct.broker, direct.broker.delivered(unix_ms()));
+    }
+    if delivered < samples.len() {
+        direct.mode.fall_back();
+        counter!("buffer_write_through_fallback").increment(1);
+    }
+    delivered
+}
+
+/// Points of a sample: the window averages of an aggregated sample,
+/// otherwise the decoded points of the read.
+fn decoded_points(sample: &PollSample, maps: &ModelMaps) -> Vec<(String, f64)> {
+    match sample.window.as_ref() {
+        Some(window) => window
+            .points
+            .iter()
+            .map(|point| (point.name.clone(), point.avg))
+            .collect(),
+        None => maps.points(sample),
+    }
+}
+
+/// Sparkplug metrics of a sample, named after its model.
+fn sparkplug_points(sample: &PollSample, maps: &ModelMaps) -> Vec<(String, f64)> {
+    decoded_points(sample, maps)
+        .into_iter()
+        .map(|(name, value)| (metric_name(&sample.model_name, &name), value))
+        .collect()
+}
+
+/// Publishes Sparkplug B messages to `topic`, keyed by their Sparkplug topic.
+/// Sends NBIRTH first and NDEATH once every event sender is gone. After a
+/// failed publish the node is born again, since the host will have seen a
+/// gap in the sequence numbers.
+async fn sparkplug_task(
+    mut node: EdgeNode,
+    mut events: mpsc::Receiver<SparkplugEvent>,
+    publisher: watch::Receiver<Publisher>,
+    topic: String,
+) {
+    let mut needs_birth = true;
+    while let Some(event) = events.recv().await {
+        let now = unix_ms();
+        let mut messages = Vec::new();
+        if needs_birth {
+            messages.push(node.birth(now));
+            needs_birth = false;
+        }
+        match event {
+            SparkplugEvent::DeviceStarted(device) => node.device_started(&device),
+            SparkplugEvent::DeviceFailed(device) => {
+                messages.extend(node.device_death(&device, now))
+            }
+            SparkplugEvent::Data {
+                device,
+                points,
+                collected_at_ms,
+            } => messages.extend(node.device_data(&device, &points, collected_at_ms)),
+        }
+
+        let current = publisher.borrow().clone();
+        for message in messages {
+            if let Err(err) = current
+                .publish_keyed(&topic, &message.topic, &message.payload.encode())
+                .await
+            {
+                warn!(sparkplug_topic = %message.topic, error = %err, "sparkplug publish failed");
+                counter!("sparkplug_publish_error").increment(1);
+                needs_birth = true;
+                break;
+            }
+        }
+    }
+
+    let death = node.death(unix_ms());
+    let current = publisher.borrow().clone();
+    if let Err(err) = current
+        .publish_keyed(&topic, &death.topic, &death.payload.encode())
+        .await
+    {
+        warn!(error = %err, "sparkplug death certificate publish failed");
+    }
+}
+
+/// Blocks `task` while a maintenance window is open.
+/// Discovery of a collector that started polling its cached inventory. The
+/// devices found are handed to the main loop, which starts pollers for new
+/// ones and restarts those whose models changed.
+async fn rediscover(
+    discovery: DiscoveryConfig,
+    mut maintenance: watch::Receiver<MaintenanceStatus>,
+    found: mpsc::Sender<Vec<DeviceIdentity>>,
+) {
+    wait_out_maintenance(&mut maintenance, "device rediscovery").await;
+    match discover(discovery).await {
+        Ok(devices) => {
+            let _ = found.send(devices).await;
+        }
+        Err(err) => {
+            warn!(error = %err, "device rediscovery failed, polling the cached inventory only");
+        }
+    }
+}
+
+async fn wait_out_maintenance(status: &mut watch::Receiver<MaintenanceStatus>, task: &str) {
+    let windows = status.borrow_and_update().windows.clone();
+    if windows.is_empty() {
+        return;
+    }
+    info!(?windows, task, "maintenance window open, deferred until it closes");
+    let _ = status.wait_for(|status| !status.is_active()).await;
+}
+
+/// Re-evaluates the maintenance calendar every second and publishes changes:
+/// the open windows for discovery and sample flagging, the pause flag for pollers.
+async fn maintenance_task(
+    calendar: MaintenanceCalendar,
+    status: watch::Sender<MaintenanceStatus>,
+    pause: watch::Sender<bool>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    gauge!("maintenance_active").set(f64::from(u8::from(status.borrow().is_active())));
+    loop {
+        tokio::select! {
+            _ = sleep(Duration::from_secs(1)) => {
+                let next = calendar.status(unix_ms());
+                if *status.borrow() == next {
+                    continue;
+                }
+                if next.is_active() {
+                    info!(windows = ?next.windows, pause_polling = next.pause_polling, "maintenance window open");
+                } else {
+                    info!("maintenance windows closed");
+                }
+                gauge!("maintenance_active").set(f64::from(u8::from(next.is_active())));
+                pause.send_if_modified(|paused| {
+                    let changed = *paused != next.pause_polling;
+                    *paused = next.pause_polling;
+                    changed
+                });
+                status.send_replace(next);
+            }
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Closes the day at every local midnight: publishes one summary per device
+/// to `topic` and appends them to the CSV at `csv_path`.
+async fn daily_summary_task(
+    accumulator: Arc<Mutex<DailyAccumulator>>,
+    publisher: watch::Receiver<Publisher>,
+    topic: String,
+    csv_path: PathBuf,
+    utc_offset_minutes: i32,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        let now = unix_ms();
+        let midnight = next_local_midnight_ms(now, utc_offset_minutes);
+        tokio::select! {
+            _ = sleep(Duration::from_millis(midnight.saturating_sub(now))) => {
+                let date = local_date(midnight.saturating_sub(1), utc_offset_minutes);
+                let summaries = lock(&accumulator).finish_day(&date);
+                if summaries.is_empty() {
+                    continue;
+                }
+                if let Err(err) = append_csv(&csv_path, &summaries) {
+                    warn!(path = %csv_path.display(), error = %err, "daily summary csv write failed");
+                }
+                let daily_publisher = publisher.borrow().clone();
+                for summary in &summaries {
+                    if let Err(err) = daily_publisher.publish_to(&topic, summary).await {
+                        warn!(ip = %summary.device.ip, error = %err, "daily summary publish failed");
+                        counter!("daily_summary_publish_error").increment(1);
+                    }
+                }
+                info!(%date, devices = summaries.len(), "daily summaries written");
+            }
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Logs and publishes alarm transitions until the buffer task drops its sender.
+async fn alarm_task(
+    mut records: mpsc::Receiver<AlarmRecord>,
+    publisher: watch::Receiver<Publisher>,
+    topic: String,
+) {
+    while let Some(record) = records.recv().await {
+        let transition = match record.transition {
+            AlarmTransition::Raised => {
+                warn!(
+                    ip = %record.device.ip,
+                    unit_id = record.device.unit_id,
+                    point = %record.point,
+                    event = %record.event,
+                    state = ?record.state,
+                    "alarm raised"
+                );
+                "raised"
+            }
+            AlarmTransition::Cleared => {
+                info!(
+                    ip = %record.device.ip,
+                    unit_id = record.device.unit_id,
+                    point = %record.point,
+                    event = %record.event,
+                    "alarm cleared"
+                );
+                "cleared"
+            }
+        };
+        counter!("alarm_transitions", "transition" => transition).increment(1);
+        let alarm_publisher = publisher.borrow().clone();
+        if let Err(err) = alarm_publisher.publish_to(&topic, &record).await {
+            warn!(ip = %record.device.ip, error = %err, "alarm publish failed");
+            counter!("alarm_publish_error").increment(1);
+        }
+    }
+}
+
+/// Logs and publishes threshold alert transitions until the buffer task drops
+/// its sender.
+async fn alert_task(
+    mut records: mpsc::Receiver<AlertRecord>,
+    publisher: watch::Receiver<Publisher>,
+    topic: String,
+) {
+    while let Some(record) = records.recv().await {
+        let transition = match record.transition {
+            AlarmTransition::Raised => {
+                warn!(
+                    ip = %record.device.ip,
+                    unit_id = record.device.unit_id,
+                    rule = %record.rule,
+                    point = %record.point,
+                    value = record.value,
+                    limit = record.limit,
+                    "alert raised"
+                );
+                "raised"
+            }
+            AlarmTransition::Cleared => {
+                info!(
+                    ip = %record.device.ip,
+                    unit_id = record.device.unit_id,
+                    rule = %record.rule,
+                    point = %record.point,
+                    value = record.value,
+                    "alert cleared"
+                );
+                "cleared"
+            }
+        };
+        counter!("alert_transitions", "transition" => transition).increment(1);
+        let alert_publisher = publisher.borrow().clone();
+        if let Err(err) = alert_publisher.publish_to(&topic, &record).await {
+            warn!(ip = %record.device.ip, error = %err, "alert publish failed");
+            counter!("alert_publish_error").increment(1);
+        }
+    }
+}
+
+/// Triggers a plant snapshot at every multiple of `interval`, collects the
+/// reads that come back within `window` and publishes the snapshot to `topic`.
+async fn plant_snapshot_task(
+    trigger: Arc<watch::Sender<u64>>,
+    mut reads: mpsc::Receiver<TriggeredSample>,
+    publisher: watch::Receiver<Publisher>,
+    topic: String,
+    interval: Duration,
+    window: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut snapshots = PlantSnapshots::new(window.as_millis() as u64);
+    let mut snapshot_id = 0u64;
+    let mut close_at: Option<Instant> = None;
+    loop {
+        let wake = close_at.unwrap_or_else(|| {
+            let now = unix_ms();
+            let next = next_snapshot_ms(now, interval.as_millis() as u64);
+            Instant::now() + Duration::from_millis(next - now)
+        });
+        tokio::select! {
+            _ = sleep_until(wake) => {
+                if close_at.take().is_none() {
+                    // Only pollers of inverters and meters hold a receiver.
+                    let expected = trigger.receiver_count() as u32;
+                    if expected > 0 {
+                        snapshot_id += 1;
+                        snapshots.start(snapshot_id, unix_ms(), expected);
+                        trigger.send_replace(snapshot_id);
+                        close_at = Some(Instant::now() + window);
+                    }
+                    continue;
+                }
+                let Some(snapshot) = snapshots.close() else {
+                    continue;
+                };
+                let missing = snapshot.devices_expected.saturating_sub(snapshot.devices_read);
+                gauge!("plant_snapshot_devices_missing").set(f64::from(missing));
+                gauge!("plant_snapshot_spread_ms").set(snapshot.spread_ms as f64);
+                let plant_publisher = publisher.borrow().clone();
+                match plant_publisher.publish_to(&topic, &snapshot).await {
+                    Ok(()) => debug!(
+                        snapshot_id = snapshot.snapshot_id,
+                        devices_read = snapshot.devices_read,
+                        devices_expected = snapshot.devices_expected,
+                        spread_ms = snapshot.spread_ms,
+                        inverter_w = snapshot.inverter_w,
+                        meter_w = snapshot.meter_w,
+                        "plant snapshot published"
+                    ),
+                    Err(err) => {
+                        warn!(snapshot_id = snapshot.snapshot_id, error = %err, "plant snapshot publish failed");
+                        counter!("plant_snapshot_publish_error").increment(1);
+                    }
+                }
+            }
+            Some(read) = reads.recv() => {
+                snapshots.record(read.trigger_id, &read.sample);
+            }
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Runs the anomaly detectors every `interval` and reports underperformers.
+async fn anomaly_task(
+    stage: Arc<Mutex<AnomalyStage>>,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        tokio::select! {
+            _ = sleep(interval) => {
+                let events = lock(&stage).evaluate(unix_ms());
+                for event in events {
+                    warn!(
+                        ip = %event.device.ip,
+                        unit_id = event.device.unit_id,
+                        group = %event.group,
+                        detector = event.detector,
+                        power_w = event.power_w,
+                        peer_mean_w = event.peer_mean_w,
+                        deficit_pct = event.deficit_pct,
+                        z_score = event.z_score,
+                        "device underperforming its group"
+                    );
+                    counter!(
+                        "anomaly_underperformance",
+                        "ip" => event.device.ip.clone(),
+                        "group" => event.group.clone()
+                    )
+                    .increment(1);
+                }
+            }
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+enum DrainOutcome {
+    Empty,
+    Published {
+        batch_size: usize,
+        valid: usize,
+    },
+    /// At least one payload was not delivered; `sent` samples were.
+    /// `payload_only` when every failure was a payload or topic the cluster
+    /// refused, so the cluster itself is fine.
+    Failed {
+        batch_size: usize,
+        sent: usize,
+        payload_only: bool,
+    },
+}
+
+/// How the uplink reads and publishes the buffer.
+struct DrainSettings {
+    batch_size: i64,
+    /// Batches published concurrently, one lane of devices each.
+    max_in_flight: usize,
+    expiry: ExpiryPolicy,
+    dead_letter_topic: Option<String>,
+    /// Where exactly-once drains write their commit markers.
+    commit_topic: String,
+    order: DrainOrder,
+    live_window_ms: u64,
+    backlog_share_pct: u8,
+}
+
+async fn uplink_task(
+    buffer: BufferStore,
+    publisher: watch::Receiver<Publisher>,
+    mut shutdown: watch::Receiver<bool>,
+    broker: BrokerStatus,
+    drain_interval: Duration,
+    settings: DrainSettings,
+) {
+    let mut failure_count: u32 = 0;
+    let mut total_sent: u64 = 0;
+    let mut total_failed: u64 = 0;
+    let initial = publisher.borrow().clone();
+    probe_broker(&initial, &broker).await;
+
+    loop {
+        let delay = uplink_delay(
+            drain_interval,
+            failure_count,
+            Duration::from_millis(DEFAULT_UPLINK_BACKOFF_MS),
+            Duration::from_millis(DEFAULT_UPLINK_BACKOFF_MAX_MS),
+        );
+
+        tokio::select! {
+            _ = sleep(delay) => {
+                // Clone per drain so a publisher rebuilt by a config reload is picked up.
+                let current = publisher.borrow().clone();
+                if broker.state() == BrokerState::Down {
+                    // A drain would only fail the same way; probe, backing
+                    // off, until the cluster answers again.
+                    if !probe_broker(&current, &broker).await {
+                        failure_count = failure_count.saturating_add(1);
+                        continue;
+                    }
+                    failure_count = 0;
+                }
+                let (batch_len, valid_count) = match drain_batch(&buffer, &current, &settings).await {
+                    DrainOutcome::Empty => {
+                        failure_count = 0;
+                        continue;
+                    }
+                    DrainOutcome::Published { batch_size, valid } => {
+                        total_sent = total_sent.saturating_add(valid as u64);
+                        failure_count = 0;
+                        if valid > 0 {
+                            broker_transition(&broker, broker.delivered(unix_ms()));
+                        }
+                        (batch_size, valid)
+                    }
+                    DrainOutcome::Failed { batch_size, sent, payload_only } => {
+                        total_sent = total_sent.saturating_add(sent as u64);
+                        let unsent = batch_size.saturating_sub(sent).max(1);
+                        total_failed = total_failed.saturating_add(unsent as u64);
+                        if payload_only {
+                            // The cluster is fine; backing off would only
+                            // hold up the samples of every other device.
+                            failure_count = 0;
+                            broker_transition(&broker, broker.delivered(unix_ms()));
+                        } else {
+                            failure_count = failure_count.saturating_add(1);
+                            probe_broker(&current, &broker).await;
+                        }
+                        (batch_size, sent)
+                    }
+                };
+
+                let queue_depth = record_queue_depth(&buffer).await;
+                info!(
+                    batch_size = batch_len,
+                    valid_samples = valid_count,
+                    queue_depth = queue_depth.unwrap_or(-1),
+                    total_sent,
+                    total_failed,
+                    failure_count,
+                    next_delay_ms = delay.as_millis(),
+                    "uplink drain complete"
+                );
+            }
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    info!("uplink shutdown requested");
+                    break;
+                }
+            }
+        }
+    }
+
+    // Final flush: keep draining until the buffer is empty or a batch fails.
+    // The caller bounds this phase with the shutdown deadline.
+    let mut flushed: u64 = 0;
+    let publisher = publisher.borrow().clone();
+    loop {
+        match drain_batch(&buffer, &publisher, &settings).await {
+            DrainOutcome::Published { valid, .. } => flushed = flushed.saturating_add(valid as u64),
+            DrainOutcome::Empty => break,
+            DrainOutcome::Failed { sent, .. } => {
+                flushed = flushed.saturating_add(sent as u64);
+                warn!("final uplink flush failed, remaining rows stay buffered");
+                break;
+            }
+        }
+    }
+    let queue_depth = record_queue_depth(&buffer).await;
+    info!(
+        flushed,
+        queue_depth = queue_depth.unwrap_or(-1),
+        "final uplink flush complete"
+    );
+}
+
+/// Republishes archived or previously buffered samples through the
+/// configured publisher, with their original timestamps, then exits. Stops
+/// at the first failed batch; what was published before stays published.
+async fn run_replay(config: &CollectorConfig, arg: &str) -> Result<()> {
+    let plan = ReplayPlan::parse(arg, Path::new(&config.file_sink_dir))?;
+    let publisher = build_publisher(config)?;
+    let router = config.topic_router();
+    let batch_size = config.buffer_batch_size.max(1);
+    let mut replayed = 0usize;
+    let mut skipped = 0usize;
+
+    match &plan.source {
+        ReplaySource::Archive(files) => {
+            if files.is_empty() {
+                warn!(replay = arg, "no archive files to replay");
+            }
+            for file in files {
+                let (samples, unreadable) = read_archive(file)?;
+                skipped += unreadable;
+                let samples: Vec<PollSample> = samples
+                    .into_iter()
+                    .filter(|sample| plan.contains(sample.collected_at_ms))
+                    .map(|sample| with_metadata(sample, config))
+                    .collect();
+                for chunk in samples.chunks(batch_size as usize) {
+                    publish_replayed(&publisher, &router, chunk)
+                        .await
+                        .with_context(|| format!("replay stopped in {}", file.display()))?;
+                }
+                replayed += samples.len();
+                info!(file = %file.display(), samples = samples.len(), unreadable, "archive file replayed");
+            }
+        }
+        ReplaySource::Buffer(path) => {
+            if Path::new(&config.buffer_path) == path.as_path() {
+                anyhow::bail!(
+                    "{} is the live buffer; the uplink publishes its rows",
+                    path.display()
+                );
+            }
+            let store = BufferStore::new(&path.to_string_lossy())
+                .await
+                .context("open replay buffer failed")?;
+            let mut after_id = 0;
+            loop {
+                let batch = store.read_after(after_id, batch_size).await?;
+                let Some(last) = batch.last() else {
+                    break;
+                };
+                after_id = last.id;
+                let mut samples = Vec::with_capacity(batch.len());
+                for message in &batch {
+                    match serde_json::from_slice::<PollSample>(&message.payload) {
+                        Ok(sample) if plan.contains(sample.collected_at_ms) => {
+                            samples.push(with_metadata(sample, config))
+                        }
+                        Ok(_) => {}
+                        Err(_) => skipped += 1,
+                    }
+                }
+                publish_replayed(&publisher, &router, &samples)
+                    .await
+                    .with_context(|| format!("replay stopped at buffer row {after_id}"))?;
+                replayed += samples.len();
+            }
+        }
+    }
+
+    info!(replayed, skipped, topic = publisher.topic(), "replay complete");
+    Ok(())
+}
+
+/// Samples archived or buffered before a site was configured get the
+/// current one. The namespace is always the current one, as replays go to
+/// the current namespace's topics.
+fn with_metadata(mut sample: PollSample, config: &CollectorConfig) -> PollSample {
+    if sample.site.is_none() {
+        sample.site = config.site.clone();
+    }
+    sample.namespace = config.kafka_namespace.clone();
+    sample
+}
+
+/// Replayed samples are routed by the current `[[kafka.routes]]`, not by the
+/// topic they were first buffered for.
+async fn publish_replayed(
+    publisher: &Publisher,
+    router: &TopicRouter,
+    samples: &[PollSample],
+) -> Result<()> {
+    let topics: Vec<String> = samples
+        .iter()
+        .map(|sample| router.topic(sample, publisher.topic()))
+        .collect();
+    let topic_refs: Vec<&str> = topics.iter().map(String::as_str).collect();
+    let indices: Vec<usize> = (0..samples.len()).collect();
+    for (topic, members) in split_by_topic(&indices, &topic_refs) {
+        let batch: Vec<&PollSample> = members.iter().map(|&i| &samples[i]).collect();
+        let records_per_message = if publisher.encoding().batches() {
+            batch.len().max(1)
+        } else {
+            1
+        };
+        for records in batch.chunks(records_per_message) {
+            let payload = publisher
+                .serialize_batch(records)
+                .context("batch serialization failed")?;
+            publisher
+                .publish_bytes(topic, &payload)
+                .await
+                .with_context(|| format!("replay publish to {topic} failed"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Rehearses a broker outage on a scratch buffer next to the live one, then
+/// exits: blocks the sink, buffers simulated samples and checks that none
+/// get out, then releases the sink and checks that the uplink drains every
+/// one of them. Prints a pass/fail report and fails when a check does.
+async fn run_drill(config: &CollectorConfig, topic: &str) -> Result<()> {
+    let topic = &config.namespaced_topic(topic);
+    let mut drill = config.clone();
+    drill.kafka_topic = Some(topic.to_string());
+    // A second producer with the same id would fence off the running collector.
+    drill.kafka_transactional_id = None;
+    drill.validate().context("drill config invalid")?;
+    let gate = SinkGate::new();
+    let publisher = build_publisher(&drill)?.with_gate(gate.clone());
+
+    let count = (config.buffer_batch_size.max(1) as usize).saturating_mul(DRILL_BATCHES);
+    let device = DeviceIdentity {
+        ip: "drill".to_string(),
+        unit_id: 1,
+        base_address: Some(config.base_address),
+    };
+    let mut simulator = Simulator::new(config.base_address, &[device.unit_id], &[103])?;
+    let start_ms = unix_ms().saturating_sub(count as u64 * 1_000);
+    let mut samples = Vec::with_capacity(count);
+    for read in 0..count as u64 {
+        samples.extend(simulator.samples(&device, start_ms + read * 1_000));
+    }
+    samples.truncate(count);
+    for (index, sample) in samples.iter_mut().enumerate() {
+        sample.sequence = index as u64 + 1;
+    }
+
+    let path = format!("{}.drill", config.buffer_path);
+    remove_scratch_buffer(&path);
+    let buffer = BufferStore::new(&path)
+        .await
+        .context("open drill buffer failed")?;
+    let settings = DrainSettings {
+        batch_size: config.buffer_batch_size,
+        max_in_flight: config.buffer_max_in_flight,
+        expiry: ExpiryPolicy::new(0, &[]),
+        dead_letter_topic: None,
+        commit_topic: config.kafka_commit_topic.clone(),
+        order: DrainOrder::OldestFirst,
+        live_window_ms: config.buffer_live_window_ms,
+        backlog_share_pct: config.buffer_backlog_share_pct,
+    };
+    info!(%topic, samples = count, path, "recovery drill started");
+    let counts = drill_phases(&buffer, &publisher, &gate, topic, &samples, &settings).await;
+    drop(buffer);
+    remove_scratch_buffer(&path);
+
+    let report = DrillReport::evaluate(topic, &counts?);
+    println!("{report}");
+    if !report.passed() {
+        anyhow::bail!("recovery drill failed");
+    }
+    Ok(())
+}
+
+/// `--dump <ip[:unit_id]>`: discovers the device's model chain like the
+/// collector does, reads every model once and writes the registers to
+/// `--dump-out` (default `sunspec-dump-<ip>-<unit_id>.json`).
+async fn run_dump(
+    config: &CollectorConfig,
+    catalog: &ModelCatalog,
+    target: &str,
+    out: Option<String>,
+) -> Result<()> {
+    let mut device = register_dump::parse_target(target).map_err(anyhow::Error::msg)?;
+    device.base_address = config
+        .inventory_device(&device)
+        .and_then(|entry| entry.base_address);
+    let (device, models, common) = discover_models_for_device(config, catalog, &device)
+        .await
+        .with_context(|| format!("discovery of {target} failed"))?;
+    let mut modbus_config = config.modbus_for(&device);
+    let batch = modbus_config
+        .max_batch_size
+        .unwrap_or(DUMP_MAX_READ_REGISTERS)
+        .min(DUMP_MAX_READ_REGISTERS);
+    modbus_config.max_batch_size = Some(batch);
+    let client = ModbusClient::connect(modbus_config)
+        .await
+        .context("modbus connect failed")?;
+    let dump = RegisterDump::capture(&client, device, &models, common, unix_ms()).await;
+
+    let path = out.unwrap_or_else(|| register_dump::default_path(&dump.device));
+    let json = serde_json::to_vec_pretty(&dump)?;
+    fs::write(&path, json).with_context(|| format!("write {path} failed"))?;
+    let failed = dump
+        .models
+        .iter()
+        .filter(|model| model.error.is_some())
+        .count();
+    println!(
+        "dumped {} models of {}:{} to {path}",
+        dump.models.len(),
+        dump.device.ip,
+        dump.device.unit_id
+    );
+    if failed > 0 {
+        anyhow::bail!("{failed} models could not be read, see {path}");
+    }
+    Ok(())
+}
+
+async fn drill_phases(
+    buffer: &BufferStore,
+    publisher: &Publisher,
+    gate: &SinkGate,
+    topic: &str,
+    samples: &[PollSample],
+    settings: &DrainSettings,
+) -> Result<DrillCounts> {
+    let mut counts = DrillCounts {
+        generated: samples.len(),
+        ..DrillCounts::default()
+    };
+
+    gate.close();
+    for sample in samples {
+        let payload = serde_json::to_vec(sample).context("encode drill sample")?;
+        buffer
+            .enqueue(topic, &payload)
+            .await
+            .context("buffer drill sample")?;
+    }
+    // A drain while blocked must fail without publishing or losing a row.
+    counts.sent_while_blocked = match drain_batch(buffer, publisher, settings).await {
+        DrainOutcome::Empty => 0,
+        DrainOutcome::Published { valid, .. } => valid,
+        DrainOutcome::Failed { sent, .. } => sent,
+    };
+    counts.buffered = buffer.pending_count().await?;
+
+    gate.open();
+    let start = Instant::now();
+    // Every successful drain deletes at least one row, so this ends.
+    loop {
+        match drain_batch(buffer, publisher, settings).await {
+            DrainOutcome::Empty => break,
+            DrainOutcome::Published { valid, .. } => counts.sent_after_release += valid,
+            DrainOutcome::Failed { sent, .. } => {
+                counts.sent_after_release += sent;
+                counts.drains += 1;
+                break;
+            }
+        }
+        counts.drains += 1;
+    }
+    counts.drain_ms = start.elapsed().as_millis() as u64;
+    counts.left_in_buffer = buffer.pending_count().await?;
+    counts.dead_lettered = buffer.dead_letter_count().await?;
+    Ok(counts)
+}
+
+/// Deletes the drill's SQLite file and its WAL, left over or just used.
+fn remove_scratch_buffer(path: &str) {
+    for file in [
+        path.to_string(),
+        format!("{path}-wal"),
+        format!("{path}-shm"),
+    ] {
+        if let Err(err) = std::fs::remove_file(&file) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                warn!(file, error = %err, "drill buffer not removed");
+            }
+        }
+    }
+}
+
+/// Reads the rows of the next drain in the configured order.
+async fn read_drain_rows(
+    buffer: &BufferStore,
+    settings: &DrainSettings,
+    limit: i64,
+) -> Result<Vec<BufferedMessage>, BufferError> {
+    match settings.order {
+        DrainOrder::OldestFirst => buffer.dequeue_batch(limit).await,
+        DrainOrder::NewestFirst => buffer.dequeue_newest(limit).await,
+        DrainOrder::LivePriority => {
+            let cutoff = (unix_ms().saturating_sub(settings.live_window_ms)) as i64;
+            let (live_quota, backlog_quota) = live_quotas(limit, settings.backlog_share_pct);
+            let mut backlog = buffer.dequeue_before(cutoff, backlog_quota).await?;
+            let live = buffer
+                .dequeue_since(cutoff, limit - backlog.len() as i64)
+                .await?;
+            if backlog.len() as i64 == backlog_quota && (live.len() as i64) < live_quota {
+                // Too little live data to fill its share: the backlog gets the rest.
+                backlog = buffer
+                    .dequeue_before(cutoff, limit - live.len() as i64)
+                    .await?;
+            }
+            backlog.extend(live);
+            Ok(backlog)
+        }
+    }
+}
+
+/// Publishes up to `max_in_flight` batches from the buffer concurrently, one
+/// lane of devices each, and acks what was delivered. Samples past their
+/// topic's max age are acked without being published. With a transactional
+/// producer all lanes go into one Kafka transaction, and their rows are
+/// acked only once it is committed.
+#[tracing::instrument(name = "uplink_drain", skip_all)]
+async fn drain_batch(
+    buffer: &BufferStore,
+    publisher: &Publisher,
+    settings: &DrainSettings,
+) -> DrainOutcome {
+    let DrainSettings {
+        batch_size,
+        max_in_flight,
+        ref expiry,
+        ref dead_letter_topic,
+        ref commit_topic,
+        ..
+    } = *settings;
+    let transactions = publisher.transactions();
+    if let Some(transactions) = transactions {
+        // Its rows are still queued and would be read again below.
+        if let Err(err) = resolve_pending_commit(buffer, transactions, commit_topic).await {
+            warn!(
+                error = format!("{err:#}"),
+                "pending uplink commit unresolved, drain postponed"
+            );
+            counter!("uplink_transaction_error").increment(1);
+            return DrainOutcome::Failed {
+                batch_size: 0,
+                sent: 0,
+                payload_only: false,
+            };
+        }
+    }
+    let limit = batch_size.saturating_mul(max_in_flight.max(1) as i64);
+    let batch = match read_drain_rows(buffer, settings, limit).await {
+        Ok(batch) => batch,
+        Err(err) => {
+            warn!(error = %err, "buffer dequeue failed");
+            return DrainOutcome::Failed {
+                batch_size: 0,
+                sent: 0,
+                payload_only: false,
+            };
+        }
+    };
+
+    if batch.is_empty() {
+        return DrainOutcome::Empty;
+    }
+
+    let mut samples = Vec::with_capacity(batch.len());
+    let mut sample_rows = Vec::with_capacity(batch.len());
+    let mut ids_to_ack = Vec::with_capacity(batch.len());
+    let mut dead_letters = Vec::new();
+    // Expired samples per topic: (count, oldest collected_at_ms).
+    let mut expired: HashMap<&str, (u64, u64)> = HashMap::new();
+    let now = unix_ms();
+
+    for message in &batch {
+        match serde_json::from_slice::<PollSample>(&message.payload) {
+            Ok(sample) if expiry.is_expired(&message.topic, sample.collected_at_ms, now) => {
+                let entry = expired
+                    .entry(message.topic.as_str())
+                    .or_insert((0, sample.collected_at_ms));
+                entry.0 += 1;
+                entry.1 = entry.1.min(sample.collected_at_ms);
+                ids_to_ack.push(message.id);
+            }
+            Ok(sample) => {
+                samples.push(sample);
+                sample_rows.push(message);
+            }
+            Err(err) => {
+                // Corrupt data in buffer: dead-letter it to prevent head-of-line blocking
+                dead_letters.push((message, DeadLetterReason::Decode, err.to_string()));
+            }
+        }
+    }
+
+    // Each row goes to the topic it was routed to when it was buffered.
+    let topics: Vec<&str> = sample_rows.iter().map(|row| row.topic.as_str()).collect();
+    let mut lane_members = Vec::new();
+    let mut lane_payloads = Vec::new();
+    for indices in assign_lanes(&samples, max_in_flight) {
+        let mut members = Vec::new();
+        let mut payloads = Vec::new();
+        for (topic, indices) in split_by_topic(&indices, &topics) {
+            for chunk in indices.chunks(batch_size.max(1) as usize) {
+                let chunk_samples: Vec<&PollSample> = chunk.iter().map(|&i| &samples[i]).collect();
+                let encoded = encode_samples(publisher, &chunk_samples);
+                for (index, err) in encoded.rejected {
+                    let reason = DeadLetterReason::from_publish_error(&err);
+                    dead_letters.push((sample_rows[chunk[index]], reason, err.to_string()));
+                }
+                for payload in encoded.payloads {
+                    let carried: Vec<usize> = payload.samples.iter().map(|&i| chunk[i]).collect();
+                    members.push(carried);
+                    payloads.push((topic.to_string(), payload.bytes));
+                }
+            }
+        }
+        lane_members.push(members);
+        lane_payloads.push(payloads);
+    }
+
+    let mut failed = false;
+    // Set by any failure other than a payload or topic the cluster refused.
+    let mut payload_only = true;
+    let in_transaction = match transactions {
+        Some(transactions) if lane_payloads.iter().any(|payloads| !payloads.is_empty()) => {
+            match transactions.begin().await {
+                Ok(()) => Some(transactions),
+                Err(err) => {
+                    warn!(error = %err, "uplink transaction not started");
+                    counter!("uplink_transaction_error").increment(1);
+                    // Without a transaction nothing may be sent.
+                    lane_payloads.clear();
+                    failed = true;
+                    payload_only = false;
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    // One publish task per lane. A lane publishes its payloads in order and
+    // stops at its first failure, so the rows of a device that stay buffered
+    // are always its newest ones.
+    let mut publishes = JoinSet::new();
+    for (lane, payloads) in lane_payloads.into_iter().enumerate() {
+        let publisher = publisher.clone();
+        let transactions = in_transaction.cloned();
+        publishes.spawn(async move {
+            for (delivered, (topic, payload)) in payloads.iter().enumerate() {
+                let start = std::time::Instant::now();
+                let published = match &transactions {
+                    Some(transactions) => transactions.send(topic, None, payload).await,
+                    None => publisher.publish_bytes(topic, payload).await,
+                };
+                if let Err(err) = published {
+                    return (lane, delivered, Some(err));
+                }
+                histogram!("uplink_publish_latency").record(start.elapsed());
+            }
+            (lane, payloads.len(), None)
+        });
+    }
+
+    let mut sent = 0usize;
+    let mut delivered_ids = Vec::with_capacity(sample_rows.len());
+    while let Some(result) = publishes.join_next().await {
+        let (lane, delivered, error) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                warn!(error = %err, "uplink publish task failed");
+                failed = true;
+                payload_only = false;
+                continue;
+            }
+        };
+        for members in &lane_members[lane][..delivered] {
+            sent += members.len();
+            delivered_ids.extend(members.iter().map(|&i| sample_rows[i].id));
+        }
+        if let Some(err) = error {
+            // The undelivered payloads of this lane are retried with the next drain.
+            let cause = if err.is_fatal() {
+                // Sending it again would be refused again.
+                let reason = DeadLetterReason::from_publish_error(&err);
+                for &index in &lane_members[lane][delivered] {
+                    dead_letters.push((sample_rows[index], reason, err.to_string()));
+                }
+                if err.is_payload_error() {
+                    "payload"
                 } else {
-                    break;
+                    "topic"
                 }
+            } else {
+                payload_only = false;
+                "broker"
+            };
+            warn!(error = %err, lane, cause, "uplink publish batch failed");
+            counter!("uplink_publish_error", "cause" => cause).increment(1);
+            failed = true;
+        }
+    }
+    match in_transaction {
+        // A transaction is all or nothing, so after any failure every row
+        // of this drain is published again.
+        Some(transactions) if failed => {
+            abort_drain(transactions).await;
+            sent = 0;
+        }
+        Some(transactions) => {
+            if let Err(err) = commit_drain(buffer, transactions, commit_topic, &delivered_ids).await
+            {
+                warn!(
+                    error = format!("{err:#}"),
+                    "uplink transaction not committed"
+                );
+                counter!("uplink_transaction_error").increment(1);
+                failed = true;
+                payload_only = false;
+                sent = 0;
             }
         }
+        None => ids_to_ack.extend(delivered_ids),
+    }
+    if sent > 0 {
+        counter!("uplink_messages_sent", "batch_size" => sent.to_string()).increment(sent as u64);
     }
 
-    join_set.abort_all();
-    while let Some(result) = join_set.join_next().await {
-        if let Err(err) = result {
-            warn!(error = %err, "poller task join failed");
+    for (message, reason, error) in dead_letters {
+        if dead_letter(
+            buffer,
+            publisher,
+            dead_letter_topic.as_deref(),
+            message,
+            reason,
+            &error,
+        )
+        .await
+        {
+            ids_to_ack.push(message.id);
         }
     }
 
-    let _ = buffer_handle.await;
-    let _ = uplink_handle.await;
-    if let Some(handle) = watchdog_handle {
-        let _ = handle.await;
+    // Ack processed messages (published, expired and dead-lettered ones)
+    if let Err(err) = buffer.delete_batch(&ids_to_ack).await {
+        // If delete fails the rows are re-published; downstream must tolerate duplicates.
+        warn!(error = %err, "buffer delete failed");
+    } else {
+        for (topic, (count, oldest_ms)) in expired {
+            warn!(
+                topic,
+                dropped = count,
+                oldest_age_ms = now.saturating_sub(oldest_ms),
+                max_age_ms = expiry.max_age_ms(topic),
+                "buffered samples past their max age dropped"
+            );
+            counter!("buffer_expired", "topic" => topic.to_string()).increment(count);
+        }
     }
-    Ok(())
-}
 
-#[derive(Clone)]
-struct PollerSpec {
-    identity: DeviceIdentity,
-    modbus_config: ClientConfig,
-    models: Vec<ModelDefinition>,
-    poller_config: ActorConfig,
-    sender: mpsc::Sender<PollSample>,
-    shutdown: watch::Receiver<bool>,
+    if failed {
+        return DrainOutcome::Failed {
+            batch_size: batch.len(),
+            sent,
+            payload_only,
+        };
+    }
+    DrainOutcome::Published {
+        batch_size: batch.len(),
+        valid: sent,
+    }
 }
 
-async fn build_poller_specs(
-    config: &CollectorConfig,
-    devices: &[DeviceIdentity],
-    sender: mpsc::Sender<PollSample>,
-    shutdown: watch::Receiver<bool>,
-) -> HashMap<String, PollerSpec> {
-    let mut specs = HashMap::new();
-
-    for device in devices {
-        match discover_models_for_device(config, device).await {
-            Ok(models) if models.is_empty() => {
-                warn!(ip = %device.ip, "no models discovered");
-            }
-            Ok(models) => {
-                let mut modbus_config = config.modbus.clone();
-                modbus_config.host = device.ip.clone();
-
-                let spec = PollerSpec {
-                    identity: device.clone(),
-                    modbus_config,
-                    models,
-                    poller_config: config.poller.clone(),
-                    sender: sender.clone(),
-                    shutdown: shutdown.clone(),
-                };
-                specs.insert(device.ip.clone(), spec);
-            }
-            Err(err) => {
-                warn!(ip = %device.ip, error = %err, "model discovery failed");
-            }
+/// Commits the transaction of an exactly-once drain. The rows it carried are
+/// recorded as a pending commit first, and the commit marker joins the
+/// transaction, so a commit interrupted at any point can be settled by
+/// [`resolve_pending_commit`]. The rows are deleted once Kafka confirmed it.
+async fn commit_drain(
+    buffer: &BufferStore,
+    transactions: &Transactions,
+    commit_topic: &str,
+    ids: &[i64],
+) -> Result<()> {
+    let commit = match buffer.prepare_commit(ids).await {
+        Ok(commit) => commit,
+        Err(err) => {
+            abort_drain(transactions).await;
+            return Err(err).context("record pending commit");
+        }
+    };
+    let marker = serde_json::to_vec(&CommitMarker::new(&commit)).context("encode commit marker")?;
+    let committed = match transactions
+        .send(commit_topic, Some(transactions.transactional_id()), &marker)
+        .await
+    {
+        Ok(()) => transactions.commit().await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = committed {
+        // Only an abort makes sure nothing was committed; without it the
+        // commit stays pending until the marker is read back.
+        if transactions.abort().await.is_ok() {
+            buffer
+                .cancel_commit(&commit)
+                .await
+                .context("clear aborted commit")?;
         }
+        return Err(err).context("commit transaction");
     }
+    buffer
+        .complete_commit(&commit)
+        .await
+        .context("ack committed rows")?;
+    debug!(
+        seq = commit.seq,
+        rows = ids.len(),
+        "uplink transaction committed"
+    );
+    Ok(())
+}
 
-    specs
+/// Probes the cluster behind `publisher` and records the result. Returns
+/// whether it answered.
+async fn probe_broker(publisher: &Publisher, broker: &BrokerStatus) -> bool {
+    let result = publisher.probe().await;
+    if let Ok(health) = &result {
+        histogram!("kafka_probe_latency").record(Duration::from_millis(health.latency_ms));
+    }
+    broker_transition(broker, broker.probed(&result, unix_ms()));
+    result.is_ok()
 }
 
-fn spawn_poller(
-    spec: PollerSpec,
-    join_set: &mut JoinSet<(String, Result<(), PollerError>)>,
-    delay: Duration,
-) {
-    let identity = spec.identity.clone();
-    join_set.spawn(async move {
-        if delay > Duration::from_millis(0) {
-            sleep(delay).await;
+/// Logs and counts a change of the cluster's reachability.
+fn broker_transition(broker: &BrokerStatus, previous: Option<BrokerState>) {
+    let Some(previous) = previous else {
+        return;
+    };
+    let report = broker.snapshot();
+    match report.state {
+        BrokerState::Up => {
+            gauge!("kafka_broker_up").set(1.0);
+            if previous == BrokerState::Down {
+                info!(
+                    reconnects = report.reconnects,
+                    "kafka cluster reachable again, uplink resumed"
+                );
+                counter!("kafka_broker_reconnect").increment(1);
+            }
         }
-        let actor = PollerActor::new(
-            spec.identity,
-            spec.modbus_config,
-            spec.models,
-            spec.sender,
-            spec.shutdown,
-            spec.poller_config,
-        );
-        (identity.ip, actor.run().await)
-    });
+        BrokerState::Down => {
+            gauge!("kafka_broker_up").set(0.0);
+            warn!(
+                error = report.error.as_deref().unwrap_or_default(),
+                "kafka cluster unreachable, uplink paused"
+            );
+            counter!("kafka_broker_disconnect").increment(1);
+        }
+        BrokerState::Unknown => {}
+    }
 }
 
-async fn discover_models_for_device(
-    config: &CollectorConfig,
-    device: &DeviceIdentity,
-) -> Result<Vec<ModelDefinition>> {
-    let mut modbus_config = config.modbus.clone();
-    modbus_config.host = device.ip.clone();
+async fn abort_drain(transactions: &Transactions) {
+    if let Err(err) = transactions.abort().await {
+        warn!(error = %err, "uplink transaction abort failed");
+    }
+}
 
-    let client = ModbusClient::connect(modbus_config)
+/// Settles the commit a crash or a failed commit left pending. Reading the
+/// commit topic after the transactional producer was initialized shows
+/// whether the transaction went through: its rows are deleted if so, else
+/// they are published again.
+async fn resolve_pending_commit(
+    buffer: &BufferStore,
+    transactions: &Transactions,
+    commit_topic: &str,
+) -> Result<()> {
+    let Some(commit) = buffer
+        .pending_commit()
         .await
-        .context("modbus connect failed")?;
-    let registers = client
-        .read_range(
-            device.unit_id,
-            config.base_address,
-            config.discovery_register_count,
-        )
+        .context("read pending commit")?
+    else {
+        return Ok(());
+    };
+    let latest = transactions
+        .read_latest(commit_topic, transactions.transactional_id())
         .await
-        .context("read sunspec model list failed")?;
+        .with_context(|| format!("read commit marker from {commit_topic}"))?;
+    let committed = latest
+        .and_then(|payload| serde_json::from_slice::<CommitMarker>(&payload).ok())
+        .is_some_and(|marker| marker.confirms(&commit));
+    if committed {
+        buffer.complete_commit(&commit).await
+    } else {
+        buffer.cancel_commit(&commit).await
+    }
+    .context("settle pending commit")?;
+    info!(
+        seq = commit.seq,
+        rows = commit.ids.len(),
+        committed,
+        "pending uplink commit resolved"
+    );
+    let outcome = if committed { "committed" } else { "resent" };
+    counter!("uplink_commit_resolved", "outcome" => outcome).increment(1);
+    Ok(())
+}
+
+/// Publishes a row that cannot be published as a sample to the dead-letter
+/// topic, or moves it to the buffer's `dead_letter` table without a topic or
+/// when that publish fails. Returns false when both failed and the row stays
+/// queued.
+async fn dead_letter(
+    buffer: &BufferStore,
+    publisher: &Publisher,
+    topic: Option<&str>,
+    message: &BufferedMessage,
+    reason: DeadLetterReason,
+    error: &str,
+) -> bool {
+    warn!(
+        id = message.id,
+        topic = %message.topic,
+        reason = reason.as_str(),
+        error,
+        "unpublishable sample moved to dead letters"
+    );
+    counter!("dead_letter", "reason" => reason.as_str()).increment(1);
+    if let Some(topic) = topic {
+        let record = DeadLetterRecord::new(
+            reason,
+            error,
+            &message.topic,
+            message.id,
+            &message.payload,
+            unix_ms(),
+        );
+        let published = match serde_json::to_vec(&record) {
+            Ok(payload) => publisher.publish_bytes(topic, &payload).await,
+            Err(err) => Err(PublishError::Json(err.to_string())),
+        };
+        match published {
+            Ok(()) => return true,
+            Err(err) => warn!(
+                id = message.id,
+                error = %err,
+                "dead-letter publish failed, keeping the row in the dead_letter table"
+            ),
+        }
+    }
+    match buffer.dead_letter(message, error).await {
+        Ok(()) => true,
+        Err(err) => {
+            warn!(id = message.id, error = %err, "dead-letter write failed, row stays buffered");
+            counter!("dead_letter_error").increment(1);
+            false
+        }
+    }
+}
 
-    parse_models_from_registers_lenient(config.base_address, &registers)
-        .map_err(|err| anyhow::anyhow!(err))
+async fn record_queue_depth(buffer: &BufferStore) -> Option<i64> {
+    match buffer.pending_count().await {
+        Ok(count) => {
+            gauge!("buffer_size").set(count as f64);
+            Some(count)
+        }
+        Err(err) => {
+            warn!(error = %err, "buffer count failed");
+            None
+        }
+    }
 }
 
-async fn buffer_task(
-    mut rx: mpsc::Receiver<PollSample>,
+/// Refreshes `buffer_disk_bytes` and compacts the buffer every
+/// `compact_interval` (never when zero).
+async fn buffer_compaction_task(
     buffer: BufferStore,
-    publisher: Publisher,
+    compact_interval: Duration,
     mut shutdown: watch::Receiver<bool>,
 ) {
+    let mut next_compaction = Instant::now() + compact_interval;
     loop {
+        match buffer.disk_size().await {
+            Ok(bytes) => gauge!("buffer_disk_bytes").set(bytes as f64),
+            Err(err) => warn!(error = %err, "buffer size check failed"),
+        }
         tokio::select! {
-            maybe_sample = rx.recv() => {
-                match maybe_sample {
-                    Some(sample) => {
-                        // Store lightweight JSON in buffer instead of Avro
-                        match serde_json::to_vec(&sample) {
-                            Ok(payload) => {
-                                if let Err(err) = buffer.enqueue(publisher.topic(), &payload).await {
-                                    warn!(error = %err, "buffer enqueue failed");
-                                    counter!("buffer_enqueue_error").increment(1);
-                                } else {
-                                    counter!("buffer_enqueue_success").increment(1);
-                                }
-                            }
-                            Err(err) => {
-                                warn!(error = %err, "json serialization failed");
-                            }
-                        }
+            _ = sleep(Duration::from_millis(BUFFER_SIZE_INTERVAL_MS)) => {
+                if compact_interval.is_zero() || Instant::now() < next_compaction {
+                    continue;
+                }
+                next_compaction = Instant::now() + compact_interval;
+                let started = Instant::now();
+                match buffer.compact().await {
+                    Ok(compaction) if compaction.freed_pages > 0 || compaction.rebuilt => {
+                        info!(
+                            freed_pages = compaction.freed_pages,
+                            rebuilt = compaction.rebuilt,
+                            bytes_before = compaction.bytes_before,
+                            bytes_after = compaction.bytes_after,
+                            elapsed_ms = started.elapsed().as_millis() as u64,
+                            "buffer compacted"
+                        );
+                    }
+                    Ok(_) => debug!("buffer compaction found nothing to free"),
+                    Err(err) => {
+                        warn!(error = %err, "buffer compaction failed");
+                        counter!("buffer_compaction_error").increment(1);
                     }
-                    None => break,
                 }
             }
             _ = shutdown.changed() => {
                 if *shutdown.borrow() {
-                    info!("buffer shutdown requested");
                     break;
                 }
             }
@@ -288,139 +3303,92 @@ async fn buffer_task(
     }
 }
 
-async fn uplink_task(
-    buffer: BufferStore,
-    publisher: Publisher,
+/// Logs one status line per device every interval, including the Modbus
+/// requests, bytes and error classes accumulated since the previous heartbeat.
+/// Feeds the samples of a config-defined synthetic device into the pipeline
+/// at the poll interval, as if a poller had read them.
+async fn synthetic_task(
+    device: SyntheticDevice,
+    base_address: u16,
+    tx: mpsc::Sender<PollSample>,
+    poller_config: watch::Receiver<ActorConfig>,
     mut shutdown: watch::Receiver<bool>,
-    batch_size: i64,
-    drain_interval: Duration,
 ) {
-    let mut failure_count: u32 = 0;
-    let mut total_sent: u64 = 0;
-    let mut total_failed: u64 = 0;
-    
+    let mut simulator = match Simulator::new(base_address, &[device.unit_id], &device.models) {
+        Ok(simulator) => simulator.with_rated_power(device.unit_id, device.rated_w),
+        Err(err) => {
+            warn!(device = %device.name, error = %err, "synthetic device not started");
+            return;
+        }
+    };
+    let identity = DeviceIdentity {
+        ip: device.name.clone(),
+        unit_id: device.unit_id,
+        base_address: Some(base_address),
+    };
+    info!(
+        device = %device.name,
+        unit_id = device.unit_id,
+        models = ?device.models,
+        "synthetic device started"
+    );
+    let counters = SampleCounters::default();
     loop {
-        let delay = uplink_delay(
-            drain_interval,
-            failure_count,
-            Duration::from_millis(DEFAULT_UPLINK_BACKOFF_MS),
-            Duration::from_millis(DEFAULT_UPLINK_BACKOFF_MAX_MS),
-        );
-
+        let cycle_id = counters.next_cycle();
+        for mut sample in simulator.samples(&identity, unix_ms()) {
+            sample.sequence = counters.next_sequence();
+            sample.cycle_id = cycle_id;
+            if tx.send(sample).await.is_err() {
+                return;
+            }
+        }
+        let poll_interval = poller_config.borrow().poll_interval;
         tokio::select! {
-            _ = sleep(delay) => {
-                let batch = match buffer.dequeue_batch(batch_size).await {
-                    Ok(batch) => batch,
-                    Err(err) => {
-                        warn!(error = %err, "buffer dequeue failed");
-                        failure_count = failure_count.saturating_add(1);
-                        total_failed = total_failed.saturating_add(1);
-                        continue;
-                    }
-                };
-
-                if batch.is_empty() {
-                    failure_count = 0;
-                    continue;
-                }
-
-                let mut samples = Vec::with_capacity(batch.len());
-                let mut ids_to_ack = Vec::with_capacity(batch.len());
-                
-                // Deserialization phase
-                for message in &batch {
-                    match serde_json::from_slice::<PollSample>(&message.payload) {
-                        Ok(sample) => {
-                            samples.push(sample);
-                            ids_to_ack.push(message.id);
-                        }
-                        Err(err) => {
-                             // Corrupt data in buffer: log and mark for deletion to prevent head-of-line blocking
-                             warn!(id = message.id, error = %err, "json deserialize failed, discarding");
-                             ids_to_ack.push(message.id);
-                        }
-                    }
-                }
-
-                let valid_count = samples.len();
-                let mut encountered_error = false;
-                
-                if !samples.is_empty() {
-                    // Batch publish
-                    match publisher.serialize_batch(&samples) {
-                        Ok(avro_payload) => {
-                             let start = std::time::Instant::now();
-                             match publisher.publish_bytes(publisher.topic(), &avro_payload).await {
-                                 Ok(()) => {
-                                     // Success! unique batch sent.
-                                     let duration = start.elapsed();
-                                     histogram!("uplink_publish_latency", duration);
-                                     counter!("uplink_messages_sent", "batch_size" => valid_count.to_string()).increment(valid_count as u64);
-                                 }
-                                 Err(err) => {
-                                     warn!(error = %err, "uplink publish batch failed");
-                                     encountered_error = true;
-                                     counter!("uplink_publish_error").increment(1);
-                                     // Reset ids to ack, we must RETRY these valid samples.
-                                     // However, we still want to delete the explicitly corrupt ones (which were not in samples).
-                                     // To do this cleanly: 
-                                     // 1. Separate valid IDs vs corrupt IDs.
-                                     // 2. Only ack valid IDs if publish succeeds.
-                                     // 3. Always ack corrupt IDs.
-                                     // simplified: if publish fails, we just don't ack *anything* this cycle. 
-                                     // Corrupt messages will stay and be warned about again. (Suboptimal but safe).
-                                     // Actually, let's just fail the whole batch for now.
-                                 }
-                             }
-                        }
-                        Err(err) => {
-                            warn!(error = %err, "avro batch serialization failed");
-                            encountered_error = true;
-                        }
-                    }
+            _ = sleep(poll_interval) => {}
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    return;
                 }
+            }
+        }
+    }
+}
 
-                if encountered_error {
-                    failure_count = failure_count.saturating_add(1);
-                    total_failed = total_failed.saturating_add(batch.len() as u64);
-                } else {
-                    // Ack processed messages (valid + corrupt ones we filtered out)
-                    if !ids_to_ack.is_empty() {
-                        if let Err(err) = buffer.delete_batch(&ids_to_ack).await {
-                            warn!(error = %err, "buffer delete failed");
-                             // If delete fails, we will re-process them. Idempotency handling needed downstream or just accept duples.
-                        }
-                    }
-                    
-                    total_sent = total_sent.saturating_add(valid_count as u64);
-                    failure_count = 0;
+async fn heartbeat_task(
+    registry: PollerRegistry,
+    stall_after: Duration,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut tracker = TrafficTracker::new();
+    loop {
+        tokio::select! {
+            _ = sleep(interval) => {
+                let reports = registry.snapshot(unix_ms(), stall_after);
+                let traffic = tracker.interval(&reports);
+                for (report, (_, delta)) in reports.iter().zip(traffic) {
+                    let (ip, unit_id) = report.id.rsplit_once(':').unwrap_or((&report.id, ""));
+                    info!(
+                        ip,
+                        unit_id,
+                        device_id = report.device_id,
+                        state = ?report.status.state,
+                        stalled = report.stalled,
+                        consecutive_errors = report.status.consecutive_errors,
+                        models_quarantined = report.status.models.iter().filter(|model| model.quarantined()).count(),
+                        requests = delta.requests,
+                        bytes_sent = delta.bytes_sent,
+                        bytes_received = delta.bytes_received,
+                        errors = delta.error_count(),
+                        error_classes = ?delta.errors,
+                        clock_drift_ms = report.status.clock_drift_ms,
+                        interval_ms = interval.as_millis(),
+                        "device heartbeat"
+                    );
                 }
-
-                let queue_depth = match buffer.pending_count().await {
-                    Ok(count) => {
-                        gauge!("buffer_size", count as f64);
-                        Some(count)
-                    }
-                    Err(err) => {
-                        warn!(error = %err, "buffer count failed");
-                        None
-                    }
-                };
-
-                info!(
-                    batch_size = batch.len(),
-                    valid_samples = valid_count,
-                    queue_depth = queue_depth.unwrap_or(-1),
-                    total_sent,
-                    total_failed,
-                    failure_count,
-                    next_delay_ms = delay.as_millis(),
-                    "uplink drain complete"
-                );
             }
             _ = shutdown.changed() => {
                 if *shutdown.borrow() {
-                    info!("uplink shutdown requested");
                     break;
                 }
             }
@@ -428,8 +3396,93 @@ async fn uplink_task(
     }
 }
 
-async fn metrics_task(handle: PrometheusHandle, mut shutdown: watch::Receiver<bool>, port: u16) {
-    let app = Router::new().route("/metrics", get(move || future::ready(handle.render())));
+/// The admin API next to the metrics: point maps, latest values, broker
+/// state, the discovered inventory, and active alerts, captures and device
+/// control when enabled.
+fn api_router(
+    registry: &PollerRegistry,
+    points: PointDirectory,
+    broker: BrokerStatus,
+    buffer: BufferStore,
+    alerts: Option<Arc<Mutex<AlertTracker>>>,
+) -> Router {
+    let mut app = Router::new()
+        .route("/api/devices/:id/points", {
+            let points = points.clone();
+            get(move |UrlPath(id): UrlPath<String>| {
+                future::ready(points.device(&id).map(Json).ok_or(StatusCode::NOT_FOUND))
+            })
+        })
+        .route(
+            "/api/devices/:id/latest",
+            get(move |UrlPath(id): UrlPath<String>| {
+                future::ready(points.latest(&id).map(Json).ok_or(StatusCode::NOT_FOUND))
+            }),
+        )
+        .route(
+            "/api/broker",
+            get(move || future::ready(Json(broker.snapshot()))),
+        )
+        .merge(discovered::router(buffer));
+    if let Some(alerts) = alerts {
+        app = app.route(
+            "/api/alerts",
+            get(move || future::ready(Json(lock(&alerts).active()))),
+        );
+    }
+    if let Some(captures) = registry.captures().cloned() {
+        app = app.merge(capture::router(captures));
+    }
+    if let Some(control) = registry.control().cloned() {
+        app = app.merge(control::router(control));
+    }
+    app
+}
+
+async fn metrics_task(
+    handle: PrometheusHandle,
+    registry: PollerRegistry,
+    api: Router,
+    health: HealthStatus,
+    stall_after: Duration,
+    mut shutdown: watch::Receiver<bool>,
+    port: u16,
+) {
+    let app = Router::new()
+        .route("/metrics", get(move || future::ready(handle.render())))
+        .route(
+            "/pollers",
+            get(move || future::ready(Json(registry.snapshot(unix_ms(), stall_after)))),
+        )
+        .route(
+            "/health",
+            get(move || future::ready(Json(health.snapshot()))),
+        )
+        .route(
+            "/api/schema/telemetry",
+            get(|| future::ready(Json(avro_to_json_schema(&Publisher::default_schema())))),
+        )
+        .route(
+            "/api/schema/daily",
+            get(|| future::ready(Json(avro_to_json_schema(&Publisher::daily_summary_schema())))),
+        )
+        .route(
+            "/api/schema/alarms",
+            get(|| future::ready(Json(avro_to_json_schema(&Publisher::alarm_schema())))),
+        )
+        .route(
+            "/api/schema/alerts",
+            get(|| future::ready(Json(avro_to_json_schema(&Publisher::alert_schema())))),
+        )
+        .route(
+            "/api/schema/plant",
+            get(|| {
+                future::ready(Json(avro_to_json_schema(
+                    &Publisher::plant_snapshot_schema(),
+                )))
+            }),
+        )
+        .merge(api);
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     info!(%addr, "metrics server listening");
 
@@ -462,7 +3515,7 @@ fn uplink_delay(
     }
 
     let shift = failures.saturating_sub(1).min(31);
-    let factor = 1u64 << shift;
+    let factor = 1u32 << shift;
     let candidate = backoff_base.saturating_mul(factor);
     let backoff = if candidate > backoff_max {
         backoff_max
@@ -476,19 +3529,179 @@ fn uplink_delay(
     }
 }
 
-fn parse_config_arg() -> Option<String> {
+/// Resolves on Ctrl-C, or SIGTERM (what systemd sends on stop) on unix.
+#[cfg(unix)]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = sigterm.recv() => {}
+            }
+        }
+        Err(err) => {
+            warn!(error = %err, "failed to install SIGTERM handler");
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+fn unix_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// The fault injector of a soak run, shared by every poller; None unless a
+/// `SUNSPEC_FAULT_*_RATE` is set.
+#[cfg(feature = "fault-injection")]
+fn injected_faults() -> Option<modbus_client::FaultInjector> {
+    static FAULTS: std::sync::OnceLock<Option<modbus_client::FaultInjector>> =
+        std::sync::OnceLock::new();
+    FAULTS
+        .get_or_init(|| {
+            let rates = modbus_client::FaultRates::from_env()?;
+            warn!(?rates, "injecting modbus faults into every poller");
+            Some(modbus_client::FaultInjector::new(rates))
+        })
+        .clone()
+}
+
+/// Whether the value-less `flag` was passed.
+fn has_flag(flag: &str) -> bool {
+    env::args().skip(1).any(|arg| arg == flag)
+}
+
+/// Value of `--flag value` or `--flag=value`.
+fn parse_flag(flag: &str) -> Option<String> {
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
-        if arg == "--config" {
+        if arg == flag {
             return args.next();
         }
-        if let Some(path) = arg.strip_prefix("--config=") {
-            return Some(path.to_string());
+        if let Some(value) = arg
+            .strip_prefix(flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value.to_string());
         }
     }
     None
 }
 
+/// Installs the log layers and, when configured, the span exporter. The
+/// returned rollup starts disabled until the config sets its window. JSON
+/// output goes to stdout even under systemd, for shippers that read it from
+/// there.
+#[cfg(target_os = "linux")]
+fn init_tracing(settings: &LogSettings, otel: &OtelSettings) -> Result<LogRollup> {
+    use collector_app::journald::{running_under_systemd, JournaldLayer};
+
+    let format = settings.format().map_err(anyhow::Error::msg)?;
+    let targets = settings.targets().map_err(anyhow::Error::msg)?;
+    let journald = if format == LogFormat::Text && running_under_systemd() {
+        match JournaldLayer::connect() {
+            Ok(layer) => Some(layer),
+            Err(err) => {
+                eprintln!("journald unavailable, falling back to stdout logging: {err}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // Under systemd stdout already lands in the journal; avoid duplicate entries.
+    let text =
+        (format == LogFormat::Text && journald.is_none()).then(tracing_subscriber::fmt::layer);
+    let json = (format == LogFormat::Json).then(|| tracing_subscriber::fmt::layer().json());
+
+    let rollup = LogRollup::new(0);
+    let logs = rollup
+        .clone()
+        .and_then(journald)
+        .and_then(text)
+        .and_then(json)
+        .with_filter(log_filter(targets));
+    tracing_subscriber::registry()
+        .with(logs)
+        .with(span_layer(otel)?)
+        .init();
+    Ok(rollup)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn init_tracing(settings: &LogSettings, otel: &OtelSettings) -> Result<LogRollup> {
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
+
+    let format = settings.format().map_err(anyhow::Error::msg)?;
+    let targets = settings.targets().map_err(anyhow::Error::msg)?;
+    let text = (format == LogFormat::Text).then(tracing_subscriber::fmt::layer);
+    let json = (format == LogFormat::Json).then(|| tracing_subscriber::fmt::layer().json());
+
+    let rollup = LogRollup::new(0);
+    let logs = rollup
+        .clone()
+        .and_then(text)
+        .and_then(json)
+        .with_filter(log_filter(targets));
+    tracing_subscriber::registry()
+        .with(logs)
+        .with(span_layer(otel)?)
+        .init();
+    Ok(rollup)
+}
+
+/// Logs how often each rolled-up event was suppressed once its window ends.
+/// Checks every second so summaries follow their window closely.
+async fn log_rollup_task(rollup: LogRollup, mut shutdown: watch::Receiver<bool>) {
+    loop {
+        let stopping = tokio::select! {
+            _ = sleep(Duration::from_secs(1)) => false,
+            result = shutdown.changed() => result.is_err() || *shutdown.borrow(),
+        };
+        // On shutdown report every pending repeat, whatever its window.
+        let now_ms = if stopping { u64::MAX } else { unix_ms() };
+        for event in rollup.flush(now_ms) {
+            log_repeated(&event);
+        }
+        if stopping {
+            break;
+        }
+    }
+}
+
+fn log_repeated(event: &RepeatedEvent) {
+    let RepeatedEvent {
+        level,
+        target,
+        message,
+        fields,
+        repeated,
+    } = event;
+    match *level {
+        tracing::Level::ERROR => {
+            tracing::error!(source = %target, original = %message, fields = %fields, repeated, "log message repeated")
+        }
+        tracing::Level::WARN => {
+            warn!(source = %target, original = %message, fields = %fields, repeated, "log message repeated")
+        }
+        tracing::Level::INFO => {
+            info!(source = %target, original = %message, fields = %fields, repeated, "log message repeated")
+        }
+        _ => {
+            debug!(source = %target, original = %message, fields = %fields, repeated, "log message repeated")
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn notify_ready() {
     if let Err(err) = sd_notify::notify(true, &[sd_notify::NotifyState::Ready]) {
@@ -502,12 +3715,20 @@ fn notify_ready() {}
 #[cfg(target_os = "linux")]
 fn start_watchdog(
     mut shutdown: watch::Receiver<bool>,
+    registry: PollerRegistry,
+    stall_after: Duration,
 ) -> Option<tokio::task::JoinHandle<()>> {
     let interval = watchdog_interval()?;
     Some(tokio::spawn(async move {
         loop {
             tokio::select! {
                 _ = sleep(interval) => {
+                    // Withhold the keep-alive when every poller is stalled so systemd restarts us.
+                    let stalled = registry.stalled(unix_ms(), stall_after);
+                    if !registry.is_empty() && stalled.len() == registry.len() {
+                        warn!(stalled = stalled.len(), "all pollers stalled, skipping watchdog notify");
+                        continue;
+                    }
                     if let Err(err) = sd_notify::notify(false, &[sd_notify::NotifyState::Watchdog]) {
                         warn!(error = %err, "systemd watchdog notify failed");
                     }
@@ -523,7 +3744,11 @@ fn start_watchdog(
 }
 
 #[cfg(not(target_os = "linux"))]
-fn start_watchdog(_shutdown: watch::Receiver<bool>) -> Option<tokio::task::JoinHandle<()>> {
+fn start_watchdog(
+    _shutdown: watch::Receiver<bool>,
+    _registry: PollerRegistry,
+    _stall_after: Duration,
+) -> Option<tokio::task::JoinHandle<()>> {
     None
 }
 
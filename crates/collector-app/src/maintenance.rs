@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+
+use crate::daily::civil_from_days;
+
+const MINUTE_MS: i64 = 60_000;
+const DAY_MS: i64 = 86_400_000;
+/// Longest supported window; keeps the look-back for window starts bounded.
+pub const MAX_WINDOW_MS: u64 = 7 * DAY_MS as u64;
+
+/// Recurring maintenance period: starts at every minute `schedule` matches
+/// and lasts `duration_ms`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintenanceWindow {
+    pub name: String,
+    /// Cron expression (`minute hour day-of-month month day-of-week`).
+    pub schedule: String,
+    pub duration_ms: u64,
+    /// Also stop polling, not only discovery, while the window is open.
+    pub pause_polling: bool,
+}
+
+/// Parsed five-field cron expression. Fields take `*`, values, ranges
+/// (`1-5`), steps (`*/15`, `0-30/10`) and lists of those. Day-of-week 0 and
+/// 7 are Sunday. As in cron, when both day fields are restricted a day
+/// matching either of them matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            anyhow::bail!(
+                "expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            );
+        };
+        let mut days_of_week = parse_field(day_of_week, 0, 7).context("day-of-week")?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).context("minute")?,
+            hours: parse_field(hour, 0, 23).context("hour")?,
+            days_of_month: parse_field(day_of_month, 1, 31).context("day-of-month")?,
+            months: parse_field(month, 1, 12).context("month")?,
+            days_of_week,
+            day_of_month_any: day_of_month == "*",
+            day_of_week_any: day_of_week == "*",
+        })
+    }
+
+    /// Whether the minute containing `local_ms` (ms since the epoch in local
+    /// time) matches.
+    pub fn matches(&self, local_ms: i64) -> bool {
+        let days = local_ms.div_euclid(DAY_MS);
+        let minute_of_day = local_ms.rem_euclid(DAY_MS) / MINUTE_MS;
+        let (_, month, day) = civil_from_days(days);
+        // 1970-01-01 was a Thursday.
+        let weekday = (days + 4).rem_euclid(7);
+
+        let day_of_month = bit(self.days_of_month, i64::from(day));
+        let day_of_week = bit(self.days_of_week, weekday);
+        let day_matches = match (self.day_of_month_any, self.day_of_week_any) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+        bit(self.minutes, minute_of_day % 60)
+            && bit(self.hours, minute_of_day / 60)
+            && bit(self.months, i64::from(month))
+            && day_matches
+    }
+}
+
+/// Which windows are open at one instant.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MaintenanceStatus {
+    /// Names of the open windows.
+    pub windows: Vec<String>,
+    /// At least one open window pauses polling.
+    pub pause_polling: bool,
+}
+
+impl MaintenanceStatus {
+    pub fn is_active(&self) -> bool {
+        !self.windows.is_empty()
+    }
+}
+
+/// Configured maintenance windows evaluated in one local time zone.
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceCalendar {
+    windows: Vec<(MaintenanceWindow, CronSchedule)>,
+    utc_offset_minutes: i32,
+}
+
+impl MaintenanceCalendar {
+    pub fn new(windows: &[MaintenanceWindow], utc_offset_minutes: i32) -> Result<Self> {
+        let windows = windows
+            .iter()
+            .map(|window| {
+                let schedule = CronSchedule::parse(&window.schedule).with_context(|| {
+                    format!("maintenance window '{}': invalid schedule", window.name)
+                })?;
+                Ok((window.clone(), schedule))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            windows,
+            utc_offset_minutes,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Windows open at `now_ms`: those that started at a matching minute
+    /// less than `duration_ms` ago.
+    pub fn status(&self, now_ms: u64) -> MaintenanceStatus {
+        let local_ms = now_ms as i64 + i64::from(self.utc_offset_minutes) * MINUTE_MS;
+        let current_minute = local_ms.div_euclid(MINUTE_MS) * MINUTE_MS;
+        let mut status = MaintenanceStatus::default();
+        for (window, schedule) in &self.windows {
+            let duration_ms = window.duration_ms.min(MAX_WINDOW_MS) as i64;
+            let mut start = current_minute;
+            let mut open = false;
+            while start + duration_ms > local_ms {
+                if schedule.matches(start) {
+                    open = true;
+                    break;
+                }
+                start -= MINUTE_MS;
+            }
+            if open {
+                status.windows.push(window.name.clone());
+                status.pause_polling |= window.pause_polling;
+            }
+        }
+        status
+    }
+}
+
+fn bit(mask: u64, value: i64) -> bool {
+    (0..64).contains(&value) && mask & (1 << value) != 0
+}
+
+/// Bit mask of the values one cron field selects.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .with_context(|| format!("invalid step '{step}'"))?;
+                if step == 0 {
+                    anyhow::bail!("step must be >= 1");
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (first, last) = if range == "*" {
+            (min, max)
+        } else if let Some((first, last)) = range.split_once('-') {
+            (parse_value(first, min, max)?, parse_value(last, min, max)?)
+        } else {
+            let value = parse_value(range, min, max)?;
+            // `5/15` runs from 5 to the end of the range.
+            (value, if part.contains('/') { max } else { value })
+        };
+        if first > last {
+            anyhow::bail!("range '{range}' is reversed");
+        }
+        for value in (first..=last).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32> {
+    let parsed: u32 = value
+        .parse()
+        .with_context(|| format!("invalid value '{value}'"))?;
+    if !(min..=max).contains(&parsed) {
+        anyhow::bail!("value {parsed} outside {min}-{max}");
+    }
+    Ok(parsed)
+}
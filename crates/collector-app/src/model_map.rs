@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use poller_actor::PollSample;
+use serde::Deserialize;
+use sunspec_parser::{decode_points, ModelDefinition};
+use types::DeviceIdentity;
+
+/// Encoding of a configured point; 32-bit values are big-endian, high
+/// register first, as in SunSpec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PointType {
+    Uint16,
+    Int16,
+    /// Also accepted as `acc32`, the SunSpec name of energy counters.
+    #[serde(alias = "acc32")]
+    Uint32,
+    Int32,
+    Float32,
+}
+
+impl PointType {
+    /// Registers the point occupies.
+    pub fn size(self) -> u16 {
+        match self {
+            Self::Uint16 | Self::Int16 => 1,
+            Self::Uint32 | Self::Int32 | Self::Float32 => 2,
+        }
+    }
+
+    fn decode(self, registers: &[u16]) -> Option<f64> {
+        let word = |index: usize| registers.get(index).copied();
+        let long = || Some((u32::from(word(0)?) << 16) | u32::from(word(1)?));
+        match self {
+            Self::Uint16 => word(0).map(f64::from),
+            Self::Int16 => word(0).map(|raw| f64::from(raw as i16)),
+            Self::Uint32 => long().map(f64::from),
+            Self::Int32 => long().map(|raw| f64::from(raw as i32)),
+            Self::Float32 => long()
+                .map(|raw| f64::from(f32::from_bits(raw)))
+                .filter(|value| value.is_finite()),
+        }
+    }
+}
+
+/// A point of a [`StaticModel`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticPoint {
+    pub name: String,
+    /// Offset from the model's start address.
+    pub offset: u16,
+    pub point_type: PointType,
+    /// Power of ten the raw value is multiplied with.
+    pub scale: i16,
+}
+
+/// A register block polled as one model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticModel {
+    pub id: u16,
+    pub name: String,
+    pub start: u16,
+    /// Registers read from `start`.
+    pub length: u16,
+    /// Decoded into the sample's points; without them a model with a
+    /// SunSpec ID is decoded like the SunSpec model.
+    pub points: Vec<StaticPoint>,
+}
+
+/// Register layout of a device without the SunSpec marker. Its models are
+/// polled as configured instead of discovered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticModelMap {
+    pub ip: String,
+    pub unit_id: u8,
+    pub models: Vec<StaticModel>,
+}
+
+impl StaticModelMap {
+    pub fn matches(&self, device: &DeviceIdentity) -> bool {
+        self.ip == device.ip && self.unit_id == device.unit_id
+    }
+
+    pub fn identity(&self) -> DeviceIdentity {
+        DeviceIdentity {
+            ip: self.ip.clone(),
+            unit_id: self.unit_id,
+            base_address: None,
+        }
+    }
+
+    /// The models handed to the poller.
+    pub fn definitions(&self) -> Vec<ModelDefinition> {
+        self.models
+            .iter()
+            .map(|model| ModelDefinition {
+                id: model.id,
+                name: model.name.clone(),
+                start: model.start,
+                length: model.length,
+            })
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let device = format!("model_maps entry {}:{}", self.ip, self.unit_id);
+        if self.ip.trim().is_empty() {
+            return Err("model_maps entries need an ip".to_string());
+        }
+        if self.models.is_empty() {
+            return Err(format!("{device} has no models"));
+        }
+        let mut blocks = HashSet::new();
+        for model in &self.models {
+            if model.name.trim().is_empty() {
+                return Err(format!("{device}: model {} needs a name", model.id));
+            }
+            if model.length == 0 {
+                return Err(format!("{device}: model {} has length 0", model.id));
+            }
+            if u32::from(model.start) + u32::from(model.length) > 0x1_0000 {
+                return Err(format!(
+                    "{device}: model {} runs past register 65535",
+                    model.id
+                ));
+            }
+            if !blocks.insert((model.id, model.start)) {
+                return Err(format!(
+                    "{device}: model {} at {} is listed twice",
+                    model.id, model.start
+                ));
+            }
+            let mut names = HashSet::new();
+            for point in &model.points {
+                if point.name.trim().is_empty() {
+                    return Err(format!(
+                        "{device}: model {} has a point without a name",
+                        model.id
+                    ));
+                }
+                if !names.insert(point.name.as_str()) {
+                    return Err(format!(
+                        "{device}: model {} lists point {} twice",
+                        model.id, point.name
+                    ));
+                }
+                if u32::from(point.offset) + u32::from(point.point_type.size())
+                    > u32::from(model.length)
+                {
+                    return Err(format!(
+                        "{device}: point {} lies outside model {}",
+                        point.name, model.id
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The configured model maps, cheap to clone into every stage that decodes
+/// samples.
+#[derive(Debug, Clone, Default)]
+pub struct ModelMaps {
+    maps: Arc<Vec<StaticModelMap>>,
+}
+
+impl ModelMaps {
+    pub fn new(maps: Vec<StaticModelMap>) -> Self {
+        Self {
+            maps: Arc::new(maps),
+        }
+    }
+
+    pub fn find(&self, device: &DeviceIdentity) -> Option<&StaticModelMap> {
+        self.maps.iter().find(|map| map.matches(device))
+    }
+
+    /// Decoded points of `sample`: the configured points when the block
+    /// belongs to a mapped model that has them, else the SunSpec points of
+    /// its model ID.
+    pub fn points(&self, sample: &PollSample) -> Vec<(String, f64)> {
+        let configured = self
+            .find(&sample.device)
+            .and_then(|map| {
+                map.models
+                    .iter()
+                    .find(|model| model.id == sample.model_id && model.start == sample.start)
+            })
+            .filter(|model| !model.points.is_empty());
+        match configured {
+            Some(model) => model
+                .points
+                .iter()
+                .filter_map(|point| {
+                    let offset = usize::from(point.offset);
+                    let raw = point.point_type.decode(sample.registers.get(offset..)?)?;
+                    Some((point.name.clone(), raw * 10f64.powi(i32::from(point.scale))))
+                })
+                .collect(),
+            None => decode_points(sample.model_id, &sample.registers)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Crates whose spans are exported: poll cycles, Modbus reads, the buffer
+/// and Kafka publishes.
+pub const TRACED_CRATES: &[&str] = &[
+    "collector_app",
+    "poller_actor",
+    "modbus_client",
+    "buffer",
+    "avro_kafka",
+];
+
+/// The `[otel]` section. Spans are exported over OTLP/gRPC when `endpoint`
+/// is set and the collector is built with the `otel` feature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtelSettings {
+    /// OTLP/gRPC endpoint, e.g. `http://localhost:4317`.
+    pub endpoint: Option<String>,
+    /// `service.name` of the exported spans.
+    pub service_name: String,
+    /// Fraction of traces exported, between 0 and 1.
+    pub sample_ratio: f64,
+}
+
+impl Default for OtelSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            service_name: "sunspec-collector".to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+static PROVIDER: std::sync::OnceLock<opentelemetry_sdk::trace::SdkTracerProvider> =
+    std::sync::OnceLock::new();
+
+/// The layer exporting spans of [`TRACED_CRATES`] to `settings.endpoint`,
+/// independent of the log level; None without an endpoint.
+#[cfg(feature = "otel")]
+pub fn span_layer<S>(settings: &OtelSettings) -> anyhow::Result<Option<impl Layer<S>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    use anyhow::Context;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use opentelemetry_sdk::Resource;
+    use tracing_subscriber::filter::{LevelFilter, Targets};
+
+    let Some(endpoint) = settings.endpoint.as_deref() else {
+        return Ok(None);
+    };
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .context("build otlp span exporter")?;
+    let provider = SdkTracerProvider::builder()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            settings.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(settings.service_name.clone())
+                .build(),
+        )
+        .with_batch_exporter(exporter)
+        .build();
+    let tracer = provider.tracer("sunspec-collector");
+    let _ = PROVIDER.set(provider);
+
+    // Only our own crates: the exporter's gRPC stack logs through tracing too.
+    let traced = Targets::new().with_targets(
+        TRACED_CRATES
+            .iter()
+            .map(|target| (*target, LevelFilter::INFO)),
+    );
+    Ok(Some(
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(traced),
+    ))
+}
+
+/// Without the `otel` feature there is nothing to export; a configured
+/// endpoint is reported on stderr since logging is not set up yet.
+#[cfg(not(feature = "otel"))]
+pub fn span_layer<S>(settings: &OtelSettings) -> anyhow::Result<Option<impl Layer<S>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    if settings.endpoint.is_some() {
+        eprintln!("otel.endpoint is set but the collector was built without the otel feature, spans are not exported");
+    }
+    Ok(None::<tracing_subscriber::layer::Identity>)
+}
+
+/// Exports the spans still queued and stops the exporter. Blocks, so call it
+/// from a blocking task.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    if let Some(provider) = PROVIDER.get() {
+        if let Err(err) = provider.shutdown() {
+            tracing::warn!(error = %err, "otel span exporter shutdown failed");
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeSet;
+
+use poller_actor::PollSample;
+use serde::Serialize;
+use sunspec_parser::{decode_inverter, decode_meter_power, is_inverter_model};
+use types::DeviceIdentity;
+
+/// Models read for a plant snapshot: the integer inverters and the integer
+/// and float meters.
+pub const SNAPSHOT_MODELS: &[u16] = &[101, 102, 103, 201, 202, 203, 204, 211, 212, 213, 214];
+
+/// AC power of one inverter or meter at the time of a snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlantReading {
+    pub device: DeviceIdentity,
+    pub model_id: u16,
+    pub collected_at_ms: u64,
+    pub power_w: f64,
+}
+
+/// AC power of every inverter and meter of the plant, read within one short
+/// window so the meter can be checked against the sum of the inverters.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlantSnapshot {
+    pub snapshot_id: u64,
+    pub triggered_at_ms: u64,
+    /// Time between the first and the last reading.
+    pub spread_ms: u64,
+    /// Sum over the inverters.
+    pub inverter_w: f64,
+    /// Sum over the meters, signed as they report it; None without meters.
+    pub meter_w: Option<f64>,
+    /// Pollers asked to read. More than `devices_read` means the sums miss
+    /// devices that were offline, busy or too slow.
+    pub devices_expected: u32,
+    pub devices_read: u32,
+    /// Reads that arrived after the window and were left out.
+    pub late: u32,
+    pub inverters: Vec<PlantReading>,
+    pub meters: Vec<PlantReading>,
+}
+
+#[derive(Debug)]
+struct OpenSnapshot {
+    id: u64,
+    triggered_at_ms: u64,
+    devices_expected: u32,
+    inverters: Vec<PlantReading>,
+    meters: Vec<PlantReading>,
+    late: u32,
+}
+
+/// Collects the triggered reads of one snapshot at a time.
+#[derive(Debug)]
+pub struct PlantSnapshots {
+    window_ms: u64,
+    open: Option<OpenSnapshot>,
+}
+
+impl PlantSnapshots {
+    pub fn new(window_ms: u64) -> Self {
+        Self {
+            window_ms,
+            open: None,
+        }
+    }
+
+    /// Opens snapshot `id`. A snapshot still open is closed and returned.
+    pub fn start(
+        &mut self,
+        id: u64,
+        triggered_at_ms: u64,
+        devices_expected: u32,
+    ) -> Option<PlantSnapshot> {
+        let previous = self.close();
+        self.open = Some(OpenSnapshot {
+            id,
+            triggered_at_ms,
+            devices_expected,
+            inverters: Vec::new(),
+            meters: Vec::new(),
+            late: 0,
+        });
+        previous
+    }
+
+    /// Adds a read made for snapshot `trigger_id`. Returns false when it was
+    /// left out: read for another snapshot or after the window, or a model
+    /// without AC power.
+    pub fn record(&mut self, trigger_id: u64, sample: &PollSample) -> bool {
+        let Some(open) = self.open.as_mut().filter(|open| open.id == trigger_id) else {
+            return false;
+        };
+        let age_ms = sample.collected_at_ms.saturating_sub(open.triggered_at_ms);
+        if age_ms > self.window_ms {
+            open.late += 1;
+            return false;
+        }
+        let inverter = is_inverter_model(sample.model_id);
+        let power_w = if inverter {
+            decode_inverter(sample.model_id, &sample.registers).and_then(|reading| reading.power_w)
+        } else {
+            decode_meter_power(sample.model_id, &sample.registers)
+        };
+        let Some(power_w) = power_w else {
+            return false;
+        };
+        let reading = PlantReading {
+            device: sample.device.clone(),
+            model_id: sample.model_id,
+            collected_at_ms: sample.collected_at_ms,
+            power_w,
+        };
+        if inverter {
+            open.inverters.push(reading);
+        } else {
+            open.meters.push(reading);
+        }
+        true
+    }
+
+    /// Closes the open snapshot.
+    pub fn close(&mut self) -> Option<PlantSnapshot> {
+        let open = self.open.take()?;
+        let readings = open.inverters.iter().chain(&open.meters);
+        let first = readings.clone().map(|r| r.collected_at_ms).min();
+        let last = readings.clone().map(|r| r.collected_at_ms).max();
+        let devices_read = readings
+            .map(|reading| (reading.device.ip.as_str(), reading.device.unit_id))
+            .collect::<BTreeSet<_>>()
+            .len() as u32;
+        Some(PlantSnapshot {
+            snapshot_id: open.id,
+            triggered_at_ms: open.triggered_at_ms,
+            spread_ms: match (first, last) {
+                (Some(first), Some(last)) => last - first,
+                _ => 0,
+            },
+            inverter_w: open.inverters.iter().map(|r| r.power_w).sum(),
+            meter_w: (!open.meters.is_empty()).then(|| open.meters.iter().map(|r| r.power_w).sum()),
+            devices_expected: open.devices_expected,
+            devices_read,
+            late: open.late,
+            inverters: open.inverters,
+            meters: open.meters,
+        })
+    }
+}
+
+/// Start of the next snapshot after `now_ms`, aligned to multiples of
+/// `interval_ms` since the epoch so every snapshot lands on the same second.
+pub fn next_snapshot_ms(now_ms: u64, interval_ms: u64) -> u64 {
+    let interval_ms = interval_ms.max(1);
+    (now_ms / interval_ms + 1) * interval_ms
+}
@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use poller_actor::PollSample;
+use serde::Serialize;
+use sunspec_parser::{decode_typed_points, point_layout, PointLayout};
+use types::{DeviceIdentity, PointValue};
+
+use crate::model_map::ModelMaps;
+
+/// Point map of one device, built from the last block read of each model.
+#[derive(Debug, Clone, Serialize)]
+pub struct DevicePoints {
+    pub id: String,
+    pub device: DeviceIdentity,
+    pub models: Vec<ModelPoints>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelPoints {
+    pub model_id: u16,
+    pub model_name: String,
+    pub start: u16,
+    /// When the block the layout was taken from was read.
+    pub collected_at_ms: u64,
+    /// False for models without a point table; `points` is empty then.
+    pub decoded: bool,
+    pub points: Vec<PointLayout>,
+}
+
+/// Latest decoded values of one device, per model.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceLatest {
+    pub id: String,
+    pub device: DeviceIdentity,
+    pub models: Vec<ModelLatest>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelLatest {
+    pub model_id: u16,
+    pub model_name: String,
+    pub start: u16,
+    /// When the values were read.
+    pub collected_at_ms: u64,
+    /// Scaled points by name, as configured for mapped models.
+    pub values: BTreeMap<String, f64>,
+    /// String, enum and bit field points of SunSpec models.
+    pub typed: BTreeMap<&'static str, PointValue>,
+}
+
+/// Last block of every model per device, kept so the admin API can describe
+/// the register map each device actually implements and serve its latest
+/// values.
+#[derive(Clone, Default)]
+pub struct PointDirectory {
+    devices: Arc<Mutex<HashMap<String, BTreeMap<u16, LastBlock>>>>,
+    model_maps: ModelMaps,
+}
+
+struct LastBlock {
+    device: DeviceIdentity,
+    model_name: String,
+    start: u16,
+    registers: Vec<u16>,
+    /// Decoded when the block is recorded, so configured points need no
+    /// lookup on every request.
+    values: Vec<(String, f64)>,
+    collected_at_ms: u64,
+}
+
+impl PointDirectory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode blocks of mapped devices with their configured points.
+    pub fn with_model_maps(mut self, model_maps: ModelMaps) -> Self {
+        self.model_maps = model_maps;
+        self
+    }
+
+    /// Keeps `sample` as the latest block of its model. Aggregated samples
+    /// carry the registers of their last read, so they qualify too, and their
+    /// values are that read's rather than the window's averages.
+    pub fn record(&self, sample: &PollSample) {
+        let values = self.model_maps.points(sample);
+        self.lock()
+            .entry(device_id(&sample.device))
+            .or_default()
+            .insert(
+                sample.model_id,
+                LastBlock {
+                    device: sample.device.clone(),
+                    model_name: sample.model_name.clone(),
+                    start: sample.start,
+                    registers: sample.registers.clone(),
+                    values,
+                    collected_at_ms: sample.collected_at_ms,
+                },
+            );
+    }
+
+    /// Point map of the device with `id` (`ip:unit_id`), models in ID order.
+    pub fn device(&self, id: &str) -> Option<DevicePoints> {
+        let devices = self.lock();
+        let models = devices.get(id)?;
+        let device = models.values().next()?.device.clone();
+        Some(DevicePoints {
+            id: id.to_string(),
+            device,
+            models: models
+                .iter()
+                .map(|(&model_id, block)| {
+                    let points = point_layout(model_id, block.start, &block.registers);
+                    ModelPoints {
+                        model_id,
+                        model_name: block.model_name.clone(),
+                        start: block.start,
+                        collected_at_ms: block.collected_at_ms,
+                        decoded: points.is_some(),
+                        points: points.unwrap_or_default(),
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    /// Latest values of the device with `id` (`ip:unit_id`), models in ID
+    /// order.
+    pub fn latest(&self, id: &str) -> Option<DeviceLatest> {
+        let devices = self.lock();
+        let models = devices.get(id)?;
+        let device = models.values().next()?.device.clone();
+        Some(DeviceLatest {
+            id: id.to_string(),
+            device,
+            models: models
+                .iter()
+                .map(|(&model_id, block)| ModelLatest {
+                    model_id,
+                    model_name: block.model_name.clone(),
+                    start: block.start,
+                    collected_at_ms: block.collected_at_ms,
+                    values: block.values.iter().cloned().collect(),
+                    typed: decode_typed_points(model_id, &block.registers)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, BTreeMap<u16, LastBlock>>> {
+        self.devices.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Same `ip:unit_id` form the poller registry uses.
+pub fn device_id(device: &DeviceIdentity) -> String {
+    device.key().to_string()
+}
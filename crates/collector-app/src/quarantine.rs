@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use types::DeviceIdentity;
+
+/// Probe schedule for devices that do not answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuarantineSettings {
+    /// Delay before the first probe; it doubles after every failed probe.
+    pub initial_probe_ms: u64,
+    pub max_probe_ms: u64,
+    /// Connect failures in a row after which a poller is quarantined instead
+    /// of respawned.
+    pub after_failures: u32,
+}
+
+impl QuarantineSettings {
+    /// Delay before the next probe after `failed_probes` failed ones.
+    pub fn probe_delay_ms(&self, failed_probes: u32) -> u64 {
+        let factor = 1u64 << failed_probes.min(63);
+        self.initial_probe_ms
+            .saturating_mul(factor)
+            .min(self.max_probe_ms)
+    }
+}
+
+/// A device taken out of the respawn loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuarantinedDevice {
+    pub device: DeviceIdentity,
+    pub since_ms: u64,
+    pub next_probe_ms: u64,
+    /// Failed probes since the device was quarantined.
+    pub failed_probes: u32,
+}
+
+/// Devices that did not answer, keyed by `ip:unit_id`, and the connect
+/// failures of running pollers that may land them here. Quarantined devices
+/// are only contacted when their probe is due, so a dead host costs one
+/// connect timeout per probe instead of one per respawn.
+#[derive(Debug)]
+pub struct Quarantine {
+    settings: QuarantineSettings,
+    strikes: HashMap<String, u32>,
+    devices: HashMap<String, QuarantinedDevice>,
+}
+
+impl Quarantine {
+    pub fn new(settings: QuarantineSettings) -> Self {
+        Self {
+            settings,
+            strikes: HashMap::new(),
+            devices: HashMap::new(),
+        }
+    }
+
+    /// Counts a poller exit on a failed connect. Returns true when this
+    /// puts the device in quarantine.
+    pub fn connect_failed(&mut self, device: &DeviceIdentity, now_ms: u64) -> bool {
+        let strikes = self.strikes.entry(key(device)).or_default();
+        *strikes += 1;
+        if *strikes < self.settings.after_failures {
+            return false;
+        }
+        self.failed(device, now_ms)
+    }
+
+    /// Forgets the connect failures of a poller that got through.
+    pub fn clear_strikes(&mut self, device: &DeviceIdentity) {
+        self.strikes.remove(&key(device));
+    }
+
+    /// Records that `device` did not answer: quarantines it, or schedules
+    /// the next probe further out when it already is. Returns true when the
+    /// device was not quarantined before.
+    pub fn failed(&mut self, device: &DeviceIdentity, now_ms: u64) -> bool {
+        let key = key(device);
+        self.strikes.remove(&key);
+        match self.devices.get_mut(&key) {
+            Some(entry) => {
+                entry.failed_probes = entry.failed_probes.saturating_add(1);
+                entry.next_probe_ms = now_ms + self.settings.probe_delay_ms(entry.failed_probes);
+                false
+            }
+            None => {
+                let entry = QuarantinedDevice {
+                    device: device.clone(),
+                    since_ms: now_ms,
+                    next_probe_ms: now_ms + self.settings.probe_delay_ms(0),
+                    failed_probes: 0,
+                };
+                self.devices.insert(key, entry);
+                true
+            }
+        }
+    }
+
+    /// Quarantined devices whose probe is due.
+    pub fn due(&self, now_ms: u64) -> Vec<DeviceIdentity> {
+        let mut due: Vec<_> = self
+            .devices
+            .values()
+            .filter(|entry| entry.next_probe_ms <= now_ms)
+            .map(|entry| entry.device.clone())
+            .collect();
+        due.sort_by(|a, b| (&a.ip, a.unit_id).cmp(&(&b.ip, b.unit_id)));
+        due
+    }
+
+    /// Takes `device` out of quarantine because it answered. Returns its
+    /// entry when it was quarantined.
+    pub fn release(&mut self, device: &DeviceIdentity) -> Option<QuarantinedDevice> {
+        let key = key(device);
+        self.strikes.remove(&key);
+        self.devices.remove(&key)
+    }
+
+    pub fn contains(&self, device: &DeviceIdentity) -> bool {
+        self.devices.contains_key(&key(device))
+    }
+
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+}
+
+fn key(device: &DeviceIdentity) -> String {
+    device.key().to_string()
+}
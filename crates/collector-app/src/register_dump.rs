@@ -0,0 +1,133 @@
+use std::fmt::Write as _;
+
+use modbus_client::ModbusClient;
+use serde::{Deserialize, Serialize};
+use sunspec_parser::{CommonModel, ModelDefinition};
+use tracing::warn;
+use types::DeviceIdentity;
+
+/// Every register of a device's model chain as read at one moment, written
+/// by `--dump`. Used to build regression fixtures (the blocks load straight
+/// into the mock server) and to show vendors what their device returns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegisterDump {
+    /// Carries the base address the device answered on.
+    pub device: DeviceIdentity,
+    pub captured_at_ms: u64,
+    /// Identity strings of the common model, when the device has one.
+    pub common: Option<CommonModel>,
+    pub models: Vec<ModelDump>,
+}
+
+/// The registers of one model, header included.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelDump {
+    pub model_id: u16,
+    pub model_name: String,
+    pub start: u16,
+    /// Registers of the model, its ID and length included.
+    pub length: u16,
+    /// Empty when the read failed.
+    pub registers: Vec<u16>,
+    /// The registers as hex words, e.g. `0067 0032 0000`.
+    pub hex: String,
+    /// Why the model could not be read.
+    pub error: Option<String>,
+}
+
+impl RegisterDump {
+    /// Reads every model in `models` with `client`. A model that fails to
+    /// read is kept with its error, so one bad block does not hide the rest.
+    pub async fn capture(
+        client: &ModbusClient,
+        device: DeviceIdentity,
+        models: &[ModelDefinition],
+        common: Option<CommonModel>,
+        captured_at_ms: u64,
+    ) -> Self {
+        let mut dumps = Vec::with_capacity(models.len());
+        for model in models {
+            let (registers, error) = match client
+                .read_range(device.unit_id, model.start, model.length)
+                .await
+            {
+                Ok(registers) => (registers, None),
+                Err(err) => {
+                    warn!(
+                        ip = %device.ip,
+                        unit_id = device.unit_id,
+                        model_id = model.id,
+                        error = %err,
+                        "model read failed, dumped without registers"
+                    );
+                    (Vec::new(), Some(err.to_string()))
+                }
+            };
+            dumps.push(ModelDump {
+                model_id: model.id,
+                model_name: model.name.clone(),
+                start: model.start,
+                length: model.length,
+                hex: to_hex(&registers),
+                registers,
+                error,
+            });
+        }
+        Self {
+            device,
+            captured_at_ms,
+            common,
+            models: dumps,
+        }
+    }
+
+    /// The blocks read in full, in chain order, as `MockServer::load_sunspec`
+    /// takes them.
+    pub fn blocks(&self) -> Vec<Vec<u16>> {
+        self.models
+            .iter()
+            .filter(|model| model.registers.len() == usize::from(model.length))
+            .map(|model| model.registers.clone())
+            .collect()
+    }
+}
+
+/// Registers as space-separated four-digit hex words.
+pub fn to_hex(registers: &[u16]) -> String {
+    let mut hex = String::with_capacity(registers.len() * 5);
+    for (index, register) in registers.iter().enumerate() {
+        if index > 0 {
+            hex.push(' ');
+        }
+        let _ = write!(hex, "{register:04x}");
+    }
+    hex
+}
+
+/// The device named on the command line: `ip` or `ip:unit_id`, unit 1 when
+/// left out.
+pub fn parse_target(value: &str) -> Result<DeviceIdentity, String> {
+    let value = value.trim();
+    let (ip, unit_id) = match value.rsplit_once(':') {
+        Some((ip, unit)) => {
+            let unit_id = unit
+                .parse::<u8>()
+                .map_err(|_| format!("invalid unit ID '{unit}' in '{value}'"))?;
+            (ip, unit_id)
+        }
+        None => (value, 1),
+    };
+    if ip.is_empty() {
+        return Err(format!("no device address in '{value}'"));
+    }
+    Ok(DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id,
+        base_address: None,
+    })
+}
+
+/// Default file name of a dump: `sunspec-dump-<ip>-<unit_id>.json`.
+pub fn default_path(device: &DeviceIdentity) -> String {
+    format!("sunspec-dump-{}-{}.json", device.ip, device.unit_id)
+}
@@ -0,0 +1,192 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::{mpsc, watch};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use types::DeviceIdentity;
+
+use crate::CollectorConfig;
+
+/// Why a configuration reload was requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadTrigger {
+    Signal,
+    FileChanged,
+}
+
+/// What differs between the running and the reloaded configuration.
+#[derive(Debug, Default, PartialEq)]
+pub struct ReloadPlan {
+    pub poller_changed: bool,
+    pub kafka_changed: bool,
+    /// Device profiles or rules differ; they apply to devices added afterwards.
+    pub rules_changed: bool,
+    pub added_devices: Vec<DeviceIdentity>,
+    pub removed_devices: Vec<DeviceIdentity>,
+}
+
+impl ReloadPlan {
+    pub fn is_empty(&self) -> bool {
+        *self == ReloadPlan::default()
+    }
+}
+
+/// Settings that are applied in place. Everything else (discovery subnet,
+/// buffer path, metrics port, ...) still needs a restart.
+pub fn plan_reload(current: &CollectorConfig, next: &CollectorConfig) -> ReloadPlan {
+    let added_devices = next
+        .discovery
+        .static_devices
+        .iter()
+        .filter(|device| !current.discovery.static_devices.contains(device))
+        .cloned()
+        .collect();
+    let removed_devices = current
+        .discovery
+        .static_devices
+        .iter()
+        .filter(|device| !next.discovery.static_devices.contains(device))
+        .cloned()
+        .collect();
+
+    ReloadPlan {
+        poller_changed: current.poller != next.poller,
+        kafka_changed: kafka_changed(current, next),
+        rules_changed: current.device_profiles != next.device_profiles
+            || current.device_rules != next.device_rules,
+        added_devices,
+        removed_devices,
+    }
+}
+
+fn kafka_changed(current: &CollectorConfig, next: &CollectorConfig) -> bool {
+    current.kafka_brokers != next.kafka_brokers
+        || current.kafka_topic != next.kafka_topic
+        || current.kafka_client_id != next.kafka_client_id
+        || current.kafka_acks != next.kafka_acks
+        || current.kafka_compression != next.kafka_compression
+        || current.kafka_timeout_ms != next.kafka_timeout_ms
+        || current.kafka_enable_idempotence != next.kafka_enable_idempotence
+        || current.kafka_max_message_bytes != next.kafka_max_message_bytes
+        || current.kafka_encoding != next.kafka_encoding
+        || current.kafka_avro_codec != next.kafka_avro_codec
+        || current.kafka_schema_topic != next.kafka_schema_topic
+        || current.kafka_bootstrap_refresh_ms != next.kafka_bootstrap_refresh_ms
+        || current.kafka_transactional_id != next.kafka_transactional_id
+        || current.kafka_security != next.kafka_security
+        || current.kafka_routes != next.kafka_routes
+}
+
+/// Resolves the config file the collector was started with, if any.
+pub fn config_source(cli_path: Option<&str>) -> Option<PathBuf> {
+    cli_path
+        .map(PathBuf::from)
+        .or_else(|| env::var("SUNSPEC_CONFIG").ok().map(PathBuf::from))
+}
+
+/// Detects config file modification by polling its mtime.
+#[derive(Debug)]
+pub struct FileWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+}
+
+impl FileWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let last_modified = modified(&path);
+        Self {
+            path,
+            last_modified,
+        }
+    }
+
+    /// True once per modification; a file that disappears is not reported
+    /// until it comes back.
+    pub fn changed(&mut self) -> bool {
+        let current = modified(&self.path);
+        if current.is_some() && current != self.last_modified {
+            self.last_modified = current;
+            return true;
+        }
+        false
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Emits a [`ReloadTrigger`] on SIGHUP (unix) and whenever the config file at
+/// `path` changes.
+pub fn spawn_reload_triggers(
+    path: Option<PathBuf>,
+    poll_interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) -> mpsc::Receiver<ReloadTrigger> {
+    let (tx, rx) = mpsc::channel(4);
+    tokio::spawn(async move {
+        let mut watcher = path.map(FileWatcher::new);
+        let mut hangup = hangup_signal();
+        loop {
+            tokio::select! {
+                _ = next_hangup(&mut hangup) => {
+                    info!("SIGHUP received, reloading configuration");
+                    let _ = tx.try_send(ReloadTrigger::Signal);
+                }
+                _ = sleep(poll_interval), if watcher.is_some() => {
+                    if watcher.as_mut().map(FileWatcher::changed).unwrap_or(false) {
+                        info!("config file changed, reloading configuration");
+                        let _ = tx.try_send(ReloadTrigger::FileChanged);
+                    }
+                }
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(unix)]
+type Hangup = Option<tokio::signal::unix::Signal>;
+#[cfg(not(unix))]
+type Hangup = ();
+
+#[cfg(unix)]
+fn hangup_signal() -> Hangup {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::hangup()) {
+        Ok(signal) => Some(signal),
+        Err(err) => {
+            warn!(error = %err, "failed to install SIGHUP handler");
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn hangup_signal() -> Hangup {}
+
+#[cfg(unix)]
+async fn next_hangup(hangup: &mut Hangup) {
+    match hangup {
+        Some(signal) => {
+            signal.recv().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(not(unix))]
+async fn next_hangup(_hangup: &mut Hangup) {
+    std::future::pending::<()>().await
+}
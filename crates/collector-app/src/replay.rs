@@ -0,0 +1,113 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use poller_actor::PollSample;
+
+use crate::file_sink::{archive_files, archive_hour_ms, parse_csv_row, parse_utc_hour, CSV_HEADER};
+
+const HOUR_MS: u64 = 3_600_000;
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// Where `--replay` reads samples from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplaySource {
+    /// File-sink CSV files, oldest first.
+    Archive(Vec<PathBuf>),
+    /// SQLite buffer left behind by an earlier run.
+    Buffer(PathBuf),
+}
+
+/// Parsed `--replay <path|range>` argument.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayPlan {
+    pub source: ReplaySource,
+    /// Only samples collected in `[from_ms, to_ms)` are replayed.
+    pub from_ms: u64,
+    pub to_ms: u64,
+}
+
+impl ReplayPlan {
+    /// `arg` is an archive file, a directory of archive files, a SQLite
+    /// buffer, or a UTC range `FROM..TO` (`YYYY-MM-DD[THH]`, end exclusive
+    /// and optional) over the archive files in `archive_dir`.
+    pub fn parse(arg: &str, archive_dir: &Path) -> Result<Self> {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            let files = archive_files(path)
+                .with_context(|| format!("list archive files in {}", path.display()))?;
+            return Ok(Self::everything(ReplaySource::Archive(files)));
+        }
+        if path.is_file() {
+            let source = if is_sqlite(path)? {
+                ReplaySource::Buffer(path.to_path_buf())
+            } else {
+                ReplaySource::Archive(vec![path.to_path_buf()])
+            };
+            return Ok(Self::everything(source));
+        }
+
+        let Some((from, to)) = arg.split_once("..") else {
+            anyhow::bail!("--replay {arg}: not a file, a directory or a FROM..TO range");
+        };
+        let from_ms = parse_utc_hour(from)
+            .with_context(|| format!("--replay {arg}: invalid range start '{from}'"))?;
+        let to_ms = if to.is_empty() {
+            u64::MAX
+        } else {
+            parse_utc_hour(to)
+                .with_context(|| format!("--replay {arg}: invalid range end '{to}'"))?
+        };
+        if from_ms >= to_ms {
+            anyhow::bail!("--replay {arg}: range end must be after its start");
+        }
+        let files = archive_files(archive_dir)
+            .with_context(|| format!("list archive files in {}", archive_dir.display()))?
+            .into_iter()
+            .filter(|file| {
+                archive_hour_ms(file).is_some_and(|hour| hour < to_ms && hour + HOUR_MS > from_ms)
+            })
+            .collect();
+        Ok(Self {
+            source: ReplaySource::Archive(files),
+            from_ms,
+            to_ms,
+        })
+    }
+
+    fn everything(source: ReplaySource) -> Self {
+        Self {
+            source,
+            from_ms: 0,
+            to_ms: u64::MAX,
+        }
+    }
+
+    pub fn contains(&self, collected_at_ms: u64) -> bool {
+        (self.from_ms..self.to_ms).contains(&collected_at_ms)
+    }
+}
+
+/// Samples of one archive file, plus the number of rows that could not be read.
+pub fn read_archive(path: &Path) -> Result<(Vec<PollSample>, usize)> {
+    let content = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut samples = Vec::new();
+    let mut skipped = 0;
+    for line in content.lines() {
+        if line.is_empty() || line == CSV_HEADER {
+            continue;
+        }
+        match parse_csv_row(line) {
+            Some(sample) => samples.push(sample),
+            None => skipped += 1,
+        }
+    }
+    Ok((samples, skipped))
+}
+
+fn is_sqlite(path: &Path) -> Result<bool> {
+    let mut header = [0u8; 16];
+    let mut file = File::open(path).with_context(|| format!("open {}", path.display()))?;
+    Ok(file.read_exact(&mut header).is_ok() && &header == SQLITE_MAGIC)
+}
@@ -0,0 +1,351 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use buffer::BufferStore;
+use metrics::{counter, gauge};
+use poller_actor::{PollSample, PollThrottle};
+use serde::Serialize;
+use tokio::sync::{mpsc, watch};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// The `[resources]` section: limits that keep the collector running on
+/// small gateways. A limit of 0 is not checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceSettings {
+    pub interval_ms: u64,
+    /// Free space on the buffer's file system below which the oldest
+    /// buffered rows are evicted.
+    pub min_free_disk_bytes: u64,
+    /// Buffer database size (file and WAL) above which the oldest buffered
+    /// rows are evicted.
+    pub max_buffer_bytes: u64,
+    /// Resident memory of the process above which polling is throttled.
+    pub max_rss_bytes: u64,
+    /// Fill of the sample channel, in percent of its capacity, above which
+    /// polling is throttled.
+    pub max_channel_fill_pct: u8,
+    /// Poll intervals are multiplied by this while any limit is crossed; 1
+    /// never throttles.
+    pub throttle_factor: u32,
+    /// Rows evicted per check while the disk or buffer limit is crossed; 0
+    /// never evicts.
+    pub evict_rows: u64,
+}
+
+impl Default for ResourceSettings {
+    fn default() -> Self {
+        Self {
+            interval_ms: 30_000,
+            min_free_disk_bytes: 0,
+            max_buffer_bytes: 0,
+            max_rss_bytes: 0,
+            max_channel_fill_pct: 0,
+            throttle_factor: 2,
+            evict_rows: 1_000,
+        }
+    }
+}
+
+impl ResourceSettings {
+    /// The limits `usage` crosses, in the order of [`Pressure`].
+    pub fn pressures(&self, usage: &ResourceUsage) -> Vec<Pressure> {
+        let below = |value: Option<u64>, limit: u64| limit > 0 && value.is_some_and(|v| v < limit);
+        let above = |value: Option<u64>, limit: u64| limit > 0 && value.is_some_and(|v| v > limit);
+        let mut pressures = Vec::new();
+        if below(usage.free_disk_bytes, self.min_free_disk_bytes) {
+            pressures.push(Pressure::Disk);
+        }
+        if above(usage.buffer_bytes, self.max_buffer_bytes) {
+            pressures.push(Pressure::Buffer);
+        }
+        if above(usage.rss_bytes, self.max_rss_bytes) {
+            pressures.push(Pressure::Memory);
+        }
+        if self.max_channel_fill_pct > 0
+            && usage.channel_capacity > 0
+            && usage.channel_depth * 100
+                > usage.channel_capacity * usize::from(self.max_channel_fill_pct)
+        {
+            pressures.push(Pressure::Channel);
+        }
+        pressures
+    }
+}
+
+/// A crossed limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pressure {
+    /// Free disk space below `min_free_disk_bytes`.
+    Disk,
+    /// Buffer database above `max_buffer_bytes`.
+    Buffer,
+    /// Resident memory above `max_rss_bytes`.
+    Memory,
+    /// Sample channel fuller than `max_channel_fill_pct`.
+    Channel,
+}
+
+impl Pressure {
+    pub const ALL: [Self; 4] = [Self::Disk, Self::Buffer, Self::Memory, Self::Channel];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Disk => "disk",
+            Self::Buffer => "buffer",
+            Self::Memory => "memory",
+            Self::Channel => "channel",
+        }
+    }
+
+    /// Whether evicting buffered rows relieves it.
+    pub fn evicts(self) -> bool {
+        matches!(self, Self::Disk | Self::Buffer)
+    }
+}
+
+/// What one check measured; None where a value could not be read.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ResourceUsage {
+    pub free_disk_bytes: Option<u64>,
+    pub buffer_bytes: Option<u64>,
+    pub rss_bytes: Option<u64>,
+    /// Samples waiting in the channel from the pollers.
+    pub channel_depth: usize,
+    pub channel_capacity: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    #[default]
+    Ok,
+    /// A resource limit is crossed; the collector keeps running but polls
+    /// slower or drops buffered rows.
+    Degraded,
+}
+
+/// Resource state as served by `/health`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    pub status: HealthState,
+    /// When the current status was entered.
+    pub since_ms: Option<u64>,
+    pub pressures: Vec<Pressure>,
+    pub usage: ResourceUsage,
+    pub throttle_factor: u32,
+    /// Buffered rows evicted since start.
+    pub evicted_rows: u64,
+}
+
+/// Shared between the resource monitor and the metrics server.
+#[derive(Debug, Clone, Default)]
+pub struct HealthStatus {
+    report: Arc<Mutex<HealthReport>>,
+}
+
+impl HealthStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> HealthReport {
+        self.lock().clone()
+    }
+
+    /// Records a check. Returns the previous status when this one differs.
+    pub fn update(
+        &self,
+        usage: ResourceUsage,
+        pressures: Vec<Pressure>,
+        throttle_factor: u32,
+        evicted_rows: u64,
+        now_ms: u64,
+    ) -> Option<HealthState> {
+        let mut report = self.lock();
+        let status = if pressures.is_empty() {
+            HealthState::Ok
+        } else {
+            HealthState::Degraded
+        };
+        let previous = report.status;
+        report.usage = usage;
+        report.pressures = pressures;
+        report.throttle_factor = throttle_factor;
+        report.evicted_rows += evicted_rows;
+        if report.since_ms.is_none() || previous != status {
+            report.status = status;
+            report.since_ms = Some(now_ms);
+        }
+        (previous != status).then_some(previous)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HealthReport> {
+        self.report
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Parses the `VmRSS` line of `/proc/self/status`, in bytes.
+pub fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let mut fields = line["VmRSS:".len()..].split_whitespace();
+    let value = fields.next()?.parse::<u64>().ok()?;
+    match fields.next() {
+        Some("kB") | None => Some(value * 1_024),
+        _ => None,
+    }
+}
+
+/// Resident memory of this process; None where `/proc` is not available.
+pub fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_rss(&status)
+}
+
+/// Space available to the collector on the file system holding `path`.
+#[cfg(target_os = "linux")]
+// The block counts are narrower than u64 on 32-bit gateways.
+#[allow(clippy::useless_conversion)]
+pub fn free_disk_bytes(path: &Path) -> Option<u64> {
+    let stat = nix::sys::statvfs::statvfs(path).ok()?;
+    Some(u64::from(stat.blocks_available()) * u64::from(stat.fragment_size()))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn free_disk_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// The directory the buffer database lives in, for the free space check.
+pub fn buffer_dir(buffer_path: &str) -> &Path {
+    let path = Path::new(buffer_path.strip_prefix("sqlite://").unwrap_or(buffer_path));
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Everything the monitor watches and acts on.
+pub struct ResourceMonitor {
+    pub settings: ResourceSettings,
+    pub buffer: BufferStore,
+    pub buffer_path: String,
+    /// Weak, so the monitor does not keep the sample channel open at
+    /// shutdown.
+    pub samples: mpsc::WeakSender<PollSample>,
+    pub throttle: PollThrottle,
+    pub health: HealthStatus,
+}
+
+impl ResourceMonitor {
+    async fn usage(&self) -> ResourceUsage {
+        let (channel_depth, channel_capacity) = self.samples.upgrade().map_or((0, 0), |tx| {
+            (tx.max_capacity() - tx.capacity(), tx.max_capacity())
+        });
+        ResourceUsage {
+            free_disk_bytes: free_disk_bytes(buffer_dir(&self.buffer_path)),
+            buffer_bytes: self.buffer.disk_size().await.ok(),
+            rss_bytes: rss_bytes(),
+            channel_depth,
+            channel_capacity,
+        }
+    }
+
+    /// Measures once, records the gauges and applies the throttle and
+    /// eviction the crossed limits call for.
+    pub async fn check(&self, now_ms: u64) {
+        let usage = self.usage().await;
+        if let Some(bytes) = usage.free_disk_bytes {
+            gauge!("disk_free_bytes").set(bytes as f64);
+        }
+        if let Some(bytes) = usage.buffer_bytes {
+            gauge!("buffer_disk_bytes").set(bytes as f64);
+        }
+        if let Some(bytes) = usage.rss_bytes {
+            gauge!("process_rss_bytes").set(bytes as f64);
+        }
+        gauge!("sample_channel_depth").set(usage.channel_depth as f64);
+
+        let pressures = self.settings.pressures(&usage);
+        for pressure in Pressure::ALL {
+            let crossed = f64::from(u8::from(pressures.contains(&pressure)));
+            gauge!("resource_pressure", "resource" => pressure.as_str()).set(crossed);
+        }
+
+        let factor = if pressures.is_empty() {
+            1
+        } else {
+            self.settings.throttle_factor
+        };
+        if factor != self.throttle.factor() {
+            info!(factor, "poll interval throttle changed");
+        }
+        self.throttle.set_factor(factor);
+        gauge!("poll_throttle_factor").set(f64::from(self.throttle.factor()));
+
+        let mut evicted = 0;
+        if self.settings.evict_rows > 0 && pressures.iter().any(|pressure| pressure.evicts()) {
+            let limit = i64::try_from(self.settings.evict_rows).unwrap_or(i64::MAX);
+            match self.buffer.evict_oldest(limit).await {
+                Ok(rows) => {
+                    evicted = rows;
+                    if rows > 0 {
+                        warn!(
+                            rows,
+                            free_disk_bytes = ?usage.free_disk_bytes,
+                            buffer_bytes = ?usage.buffer_bytes,
+                            "buffer over its resource limit, oldest rows evicted"
+                        );
+                        counter!("buffer_evicted").increment(rows);
+                        // Deleted rows only give space back once compacted.
+                        if let Err(err) = self.buffer.compact().await {
+                            warn!(error = %err, "buffer compaction after eviction failed");
+                            counter!("buffer_compaction_error").increment(1);
+                        }
+                    }
+                }
+                Err(err) => warn!(error = %err, "buffer eviction failed"),
+            }
+        }
+
+        let names: Vec<&str> = pressures.iter().map(|pressure| pressure.as_str()).collect();
+        match self
+            .health
+            .update(usage, pressures, factor, evicted, now_ms)
+        {
+            Some(HealthState::Ok) => warn!(
+                pressures = ?names,
+                "resource limit crossed, collector degraded"
+            ),
+            Some(HealthState::Degraded) => info!("resources back within limits"),
+            None => {}
+        }
+    }
+
+    /// Repeats [`Self::check`] every `interval_ms` until shutdown.
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        let interval = Duration::from_millis(self.settings.interval_ms);
+        loop {
+            self.check(unix_ms()).await;
+            tokio::select! {
+                _ = sleep(interval) => {}
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Respawn schedule of failed pollers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestartPolicy {
+    /// Delay before the first respawn; it doubles with every further failure
+    /// in a row.
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Failures in a row after which the circuit breaker opens and the
+    /// poller is left to rediscovery; 0 never opens it.
+    pub breaker_failures: u32,
+}
+
+impl RestartPolicy {
+    /// Delay before the respawn after `failures` failures in a row.
+    pub fn delay_ms(&self, failures: u32) -> u64 {
+        let factor = 1u64 << failures.saturating_sub(1).min(63);
+        self.initial_delay_ms
+            .saturating_mul(factor)
+            .min(self.max_delay_ms)
+    }
+}
+
+/// What to do with a poller that exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Restart {
+    /// Respawn it after this delay.
+    After(Duration),
+    /// Stop respawning it until rediscovery finds the device again.
+    BreakerOpen { failures: u32 },
+}
+
+/// Failures in a row of each poller, keyed by poller id. A run that
+/// completed a clean cycle starts the count over, so a device that fails
+/// now and then keeps the short delay.
+#[derive(Debug)]
+pub struct RestartTracker {
+    policy: RestartPolicy,
+    failures: HashMap<String, u32>,
+}
+
+impl RestartTracker {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Records the exit of poller `id` and decides on its restart. `failed`
+    /// is an exit with an error, `made_progress` a run with a clean cycle.
+    pub fn exited(&mut self, id: &str, failed: bool, made_progress: bool) -> Restart {
+        if !failed || made_progress {
+            self.failures.remove(id);
+        }
+        if !failed {
+            return Restart::After(Duration::from_millis(self.policy.initial_delay_ms));
+        }
+        let failures = self.failures.entry(id.to_string()).or_default();
+        *failures = failures.saturating_add(1);
+        let failures = *failures;
+        if self.policy.breaker_failures > 0 && failures >= self.policy.breaker_failures {
+            self.failures.remove(id);
+            return Restart::BreakerOpen { failures };
+        }
+        Restart::After(Duration::from_millis(self.policy.delay_ms(failures)))
+    }
+
+    /// Failures in a row recorded for `id`.
+    pub fn failures(&self, id: &str) -> u32 {
+        self.failures.get(id).copied().unwrap_or(0)
+    }
+
+    /// Drops the count of a poller that was removed or quarantined.
+    pub fn forget(&mut self, id: &str) {
+        self.failures.remove(id);
+    }
+}
@@ -0,0 +1,115 @@
+use poller_actor::PollSample;
+
+use crate::rules::ModelRange;
+
+/// Placeholders a topic template may use.
+pub const TOPIC_PLACEHOLDERS: &[&str] = &["model_name", "model_id", "device_ip", "unit_id"];
+
+/// Sends the samples it matches to the topic its template renders. A route
+/// without conditions matches every sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicRoute {
+    /// Models the route applies to; empty matches every model.
+    pub models: Vec<ModelRange>,
+    pub ip: Option<String>,
+    pub unit_id: Option<u8>,
+    /// Topic name with placeholders, e.g. `sunspec.{model_name}`.
+    pub topic: String,
+}
+
+impl TopicRoute {
+    pub fn matches(&self, sample: &PollSample) -> bool {
+        (self.models.is_empty()
+            || self
+                .models
+                .iter()
+                .any(|range| range.contains(sample.model_id)))
+            && self.ip.as_ref().is_none_or(|ip| *ip == sample.device.ip)
+            && self
+                .unit_id
+                .is_none_or(|unit_id| unit_id == sample.device.unit_id)
+    }
+}
+
+/// Routes in order of the config; the first that matches picks the topic.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicRouter {
+    routes: Vec<TopicRoute>,
+}
+
+impl TopicRouter {
+    pub fn new(routes: Vec<TopicRoute>) -> Self {
+        Self { routes }
+    }
+
+    /// Topic of the first route that matches `sample`, else `default`.
+    pub fn topic(&self, sample: &PollSample, default: &str) -> String {
+        self.routes
+            .iter()
+            .find(|route| route.matches(sample))
+            .map(|route| render_topic(&route.topic, sample))
+            .unwrap_or_else(|| default.to_string())
+    }
+}
+
+/// Fills the placeholders of `template` from `sample`. Characters a topic
+/// name may not contain are replaced by `_` in the filled-in values.
+/// Unknown placeholders are kept as they are; [`check_template`] rejects
+/// them at config load.
+pub fn render_topic(template: &str, sample: &PollSample) -> String {
+    let mut topic = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        topic.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        let name = &rest[open + 1..open + close];
+        match placeholder(name, sample) {
+            Some(value) => topic.extend(value.chars().map(|ch| {
+                if ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-') {
+                    ch
+                } else {
+                    '_'
+                }
+            })),
+            None => topic.push_str(&rest[open..=open + close]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    topic.push_str(rest);
+    topic
+}
+
+fn placeholder(name: &str, sample: &PollSample) -> Option<String> {
+    match name {
+        "model_name" => Some(sample.model_name.clone()),
+        "model_id" => Some(sample.model_id.to_string()),
+        "device_ip" => Some(sample.device.ip.clone()),
+        "unit_id" => Some(sample.device.unit_id.to_string()),
+        _ => None,
+    }
+}
+
+/// Checks that every `{...}` of `template` is closed and a known
+/// placeholder.
+pub fn check_template(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(open) = rest.find(['{', '}']) {
+        if rest[open..].starts_with('}') {
+            return Err(format!("unmatched '}}' in '{template}'"));
+        }
+        let Some(close) = rest[open..].find('}') else {
+            return Err(format!("unclosed '{{' in '{template}'"));
+        };
+        let name = &rest[open + 1..open + close];
+        if !TOPIC_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "unknown placeholder '{{{name}}}' in '{template}', expected one of {}",
+                TOPIC_PLACEHOLDERS.join(", ")
+            ));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    Ok(())
+}
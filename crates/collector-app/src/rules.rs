@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use poller_actor::ScanClass;
+use sunspec_parser::{point_span, CommonModel, ModelDefinition};
+use types::DeviceIdentity;
+
+/// How often one model is read, when it should not be read every cycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInterval {
+    pub model_id: u16,
+    pub interval_ms: u64,
+}
+
+/// Points of a model read every cycle, while the rest of its block is only
+/// read once per `full_interval_ms`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanClassConfig {
+    pub model_id: u16,
+    /// SunSpec point names, e.g. `W`, `Hz` and `St` of model 103.
+    pub points: Vec<String>,
+    pub full_interval_ms: u64,
+}
+
+impl ScanClassConfig {
+    /// The registers to read every cycle: the named points and the scale
+    /// factors they need.
+    pub fn scan_class(&self) -> Result<ScanClass, sunspec_parser::ParserError> {
+        let (offset, length) = point_span(self.model_id, &self.points)?;
+        Ok(ScanClass {
+            offset,
+            length,
+            full_interval: Duration::from_millis(self.full_interval_ms),
+        })
+    }
+}
+
+/// Inclusive range of model IDs; a single ID is a range of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelRange {
+    pub first: u16,
+    pub last: u16,
+}
+
+impl ModelRange {
+    pub fn single(model_id: u16) -> Self {
+        Self {
+            first: model_id,
+            last: model_id,
+        }
+    }
+
+    /// `103` or `64001-65535`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let range = match value.split_once('-') {
+            Some((first, last)) => Self {
+                first: first.trim().parse().ok()?,
+                last: last.trim().parse().ok()?,
+            },
+            None => Self::single(value.parse().ok()?),
+        };
+        (range.first <= range.last).then_some(range)
+    }
+
+    pub fn contains(&self, model_id: u16) -> bool {
+        (self.first..=self.last).contains(&model_id)
+    }
+}
+
+/// Include/exclude lists of model IDs, checked before any model is polled.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModelFilter {
+    /// Poll only models in these ranges; empty allows every model.
+    pub include: Vec<ModelRange>,
+    pub exclude: Vec<ModelRange>,
+}
+
+impl ModelFilter {
+    pub fn allows(&self, model_id: u16) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|range| range.contains(model_id)))
+            && !self.exclude.iter().any(|range| range.contains(model_id))
+    }
+}
+
+/// Model filter for the units behind one IP, or for one unit when
+/// `unit_id` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceModelFilter {
+    pub ip: String,
+    pub unit_id: Option<u8>,
+    pub filter: ModelFilter,
+}
+
+impl DeviceModelFilter {
+    pub fn matches(&self, device: &DeviceIdentity) -> bool {
+        self.ip == device.ip && self.unit_id.is_none_or(|unit_id| unit_id == device.unit_id)
+    }
+}
+
+/// Named set of polling settings applied to every device a rule matches.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DeviceProfile {
+    pub name: String,
+    /// Poll only these models; empty keeps every discovered model.
+    pub only_models: Vec<u16>,
+    pub skip_models: Vec<u16>,
+    pub model_intervals: Vec<ModelInterval>,
+    pub scan_classes: Vec<ScanClassConfig>,
+    /// Class the device starts with in the boot order, instead of the one
+    /// its models give it.
+    pub class: Option<String>,
+}
+
+/// Applies `profile` to devices whose common model matches every condition
+/// that is set. A rule without conditions matches every device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceRule {
+    /// Exact manufacturer (`Mn`), ignoring case and surrounding blanks.
+    pub manufacturer: Option<String>,
+    /// Substring of the model (`Md`), ignoring case.
+    pub model_contains: Option<String>,
+    pub profile: String,
+}
+
+impl DeviceRule {
+    /// Devices whose common model could not be read only match rules
+    /// without conditions.
+    pub fn matches(&self, common: Option<&CommonModel>) -> bool {
+        let manufacturer_ok = match (&self.manufacturer, common) {
+            (None, _) => true,
+            (Some(expected), Some(common)) => {
+                common.manufacturer.eq_ignore_ascii_case(expected.trim())
+            }
+            (Some(_), None) => false,
+        };
+        let model_ok = match (&self.model_contains, common) {
+            (None, _) => true,
+            (Some(needle), Some(common)) => common
+                .model
+                .to_ascii_lowercase()
+                .contains(&needle.to_ascii_lowercase()),
+            (Some(_), None) => false,
+        };
+        manufacturer_ok && model_ok
+    }
+}
+
+/// Polling settings of one device after all matching rules were applied.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DevicePolicy {
+    /// Names of the applied profiles, in rule order.
+    pub profiles: Vec<String>,
+    pub only_models: Vec<u16>,
+    pub skip_models: Vec<u16>,
+    pub model_intervals: HashMap<u16, Duration>,
+    pub scan_classes: HashMap<u16, ScanClass>,
+    /// Global and per-device model filters; a model must pass all of them.
+    pub model_filters: Vec<ModelFilter>,
+    /// Boot order class set by the last profile that names one.
+    pub class: Option<String>,
+}
+
+impl DevicePolicy {
+    /// Matching rules apply in order; a later profile's `only_models` replaces
+    /// an earlier one, skips accumulate, and intervals and scan classes
+    /// override per model.
+    pub fn resolve(
+        rules: &[DeviceRule],
+        profiles: &[DeviceProfile],
+        common: Option<&CommonModel>,
+    ) -> Self {
+        let mut policy = Self::default();
+        for rule in rules.iter().filter(|rule| rule.matches(common)) {
+            if let Some(profile) = profiles.iter().find(|p| p.name == rule.profile) {
+                policy.apply(profile);
+            }
+        }
+        policy
+    }
+
+    /// Applies the profile named for this device (in the inventory) after
+    /// the rules, so it overrides them.
+    pub fn with_profile(mut self, profiles: &[DeviceProfile], name: Option<&str>) -> Self {
+        if let Some(profile) = name.and_then(|name| profiles.iter().find(|p| p.name == name)) {
+            self.apply(profile);
+        }
+        self
+    }
+
+    fn apply(&mut self, profile: &DeviceProfile) {
+        self.profiles.push(profile.name.clone());
+        if !profile.only_models.is_empty() {
+            self.only_models = profile.only_models.clone();
+        }
+        self.skip_models.extend(&profile.skip_models);
+        if profile.class.is_some() {
+            self.class = profile.class.clone();
+        }
+        for interval in &profile.model_intervals {
+            self.model_intervals.insert(
+                interval.model_id,
+                Duration::from_millis(interval.interval_ms),
+            );
+        }
+        // Point names are checked when the config is loaded.
+        for config in &profile.scan_classes {
+            if let Ok(class) = config.scan_class() {
+                self.scan_classes.insert(config.model_id, class);
+            }
+        }
+    }
+
+    /// Adds the global filter and the filters configured for `device`.
+    pub fn with_model_filters(
+        mut self,
+        global: &ModelFilter,
+        devices: &[DeviceModelFilter],
+        device: &DeviceIdentity,
+    ) -> Self {
+        self.model_filters.push(global.clone());
+        self.model_filters.extend(
+            devices
+                .iter()
+                .filter(|filter| filter.matches(device))
+                .map(|filter| filter.filter.clone()),
+        );
+        self
+    }
+
+    /// The discovered models this device should poll.
+    pub fn select_models(&self, models: Vec<ModelDefinition>) -> Vec<ModelDefinition> {
+        models
+            .into_iter()
+            .filter(|model| self.only_models.is_empty() || self.only_models.contains(&model.id))
+            .filter(|model| !self.skip_models.contains(&model.id))
+            .filter(|model| {
+                self.model_filters
+                    .iter()
+                    .all(|filter| filter.allows(model.id))
+            })
+            .collect()
+    }
+}
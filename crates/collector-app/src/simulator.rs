@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::Result;
+use poller_actor::PollSample;
+use sunspec_parser::parse_models_from_registers;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info};
+use types::DeviceIdentity;
+
+const SUNSPEC_ID: [u16; 2] = [0x5375, 0x6e53];
+const END_MODEL: [u16; 2] = [0xFFFF, 0];
+const COMMON_LEN: u16 = 66;
+const INVERTER_LEN: u16 = 50;
+const METER_LEN: u16 = 105;
+const NOT_IMPLEMENTED_U16: u16 = 0xFFFF;
+const NOT_IMPLEMENTED_I16: u16 = 0x8000;
+/// One simulated "day": the power curve repeats every ten minutes so demos
+/// show movement without waiting for the sun.
+const CYCLE_MS: f64 = 600_000.0;
+const MAX_READ_REGISTERS: u16 = 125;
+
+const READ_HOLDING_REGISTERS: u8 = 0x03;
+const READ_INPUT_REGISTERS: u8 = 0x04;
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+const ILLEGAL_DATA_VALUE: u8 = 0x03;
+const GATEWAY_TARGET_FAILED: u8 = 0x0B;
+
+/// Models the simulator can emulate besides the common model, which every
+/// unit always has.
+pub const SIMULATED_MODELS: &[u16] = &[101, 102, 103, 201, 202, 203, 204];
+/// Highest rated power the integer models can report with a scale factor of 0.
+pub const MAX_RATED_W: f64 = 30_000.0;
+
+/// Config-defined device that produces simulated samples in-process, without
+/// Modbus, to check the pipeline behind the pollers on a new gateway.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticDevice {
+    /// Published in place of the IP address.
+    pub name: String,
+    pub unit_id: u8,
+    pub models: Vec<u16>,
+    pub rated_w: f64,
+}
+
+/// In-process SunSpec device: one register map per unit ID, starting at
+/// `base_address` with the `SunS` marker, the common model, `models` in
+/// order and the end marker. Inverters produce a synthetic power curve with
+/// a matching lifetime energy counter; meters report the inverter's power as
+/// export.
+#[derive(Debug)]
+pub struct Simulator {
+    base_address: u16,
+    models: Vec<u16>,
+    units: BTreeMap<u8, SimulatedUnit>,
+}
+
+#[derive(Debug)]
+struct SimulatedUnit {
+    rated_w: f64,
+    phase: f64,
+    energy_wh: f64,
+    last_read_ms: Option<u64>,
+}
+
+impl Simulator {
+    pub fn new(base_address: u16, unit_ids: &[u8], models: &[u16]) -> Result<Self> {
+        if unit_ids.is_empty() {
+            anyhow::bail!("simulator needs at least one unit ID");
+        }
+        if let Some(model) = models
+            .iter()
+            .find(|model| !SIMULATED_MODELS.contains(model))
+        {
+            anyhow::bail!(
+                "simulator cannot emulate model {model} (supported: {SIMULATED_MODELS:?})"
+            );
+        }
+        let units = unit_ids
+            .iter()
+            .enumerate()
+            .map(|(index, &unit_id)| {
+                let unit = SimulatedUnit {
+                    rated_w: 5_000.0 + 1_000.0 * (index % 4) as f64,
+                    phase: 0.1 * index as f64,
+                    energy_wh: 1_000_000.0 * (index + 1) as f64,
+                    last_read_ms: None,
+                };
+                (unit_id, unit)
+            })
+            .collect();
+        let simulator = Self {
+            base_address,
+            models: models.to_vec(),
+            units,
+        };
+        if u32::from(base_address) + simulator.map_len() as u32 > u32::from(u16::MAX) + 1 {
+            anyhow::bail!("simulated register map does not fit above base address {base_address}");
+        }
+        Ok(simulator)
+    }
+
+    /// Sets the rated power of `unit_id`, which scales its power curve.
+    pub fn with_rated_power(mut self, unit_id: u8, rated_w: f64) -> Self {
+        if let Some(unit) = self.units.get_mut(&unit_id) {
+            unit.rated_w = rated_w.clamp(0.0, MAX_RATED_W);
+        }
+        self
+    }
+
+    pub fn unit_ids(&self) -> Vec<u8> {
+        self.units.keys().copied().collect()
+    }
+
+    /// Number of registers from the `SunS` marker through the end marker.
+    pub fn map_len(&self) -> usize {
+        let models: usize = self
+            .models
+            .iter()
+            .map(|&model| usize::from(model_len(model)) + 2)
+            .sum();
+        SUNSPEC_ID.len() + usize::from(COMMON_LEN) + 2 + models + END_MODEL.len()
+    }
+
+    /// Register map of `unit_id` as read at `now_ms`. Advances the unit's
+    /// energy counter by the power produced since the previous read.
+    pub fn registers(&mut self, unit_id: u8, now_ms: u64) -> Option<Vec<u16>> {
+        let map_len = self.map_len();
+        let unit = self.units.get_mut(&unit_id)?;
+        let power_w = unit.power_w(now_ms);
+        if let Some(last_ms) = unit.last_read_ms {
+            let elapsed_h = now_ms.saturating_sub(last_ms) as f64 / 3_600_000.0;
+            unit.energy_wh += power_w * elapsed_h;
+        }
+        unit.last_read_ms = Some(now_ms);
+
+        let mut map = Vec::with_capacity(map_len);
+        map.extend_from_slice(&SUNSPEC_ID);
+        map.extend(common_block(unit_id, &self.models));
+        for &model in &self.models {
+            match model {
+                101..=103 => map.extend(inverter_block(model, power_w, unit.energy_wh)),
+                _ => map.extend(meter_block(model, -power_w)),
+            }
+        }
+        map.extend_from_slice(&END_MODEL);
+        Some(map)
+    }
+
+    /// One sample per model of `device.unit_id` as read at `now_ms`, like a
+    /// poller would send them.
+    pub fn samples(&mut self, device: &DeviceIdentity, now_ms: u64) -> Vec<PollSample> {
+        let base_address = self.base_address;
+        let Some(registers) = self.registers(device.unit_id, now_ms) else {
+            return Vec::new();
+        };
+        let models = parse_models_from_registers(base_address, &registers).unwrap_or_default();
+        models
+            .into_iter()
+            .map(|model| {
+                let offset = usize::from(model.start - base_address);
+                let block = registers[offset..offset + usize::from(model.length)].to_vec();
+                PollSample::new(device.clone(), model.id, model.name, model.start, block, now_ms)
+            })
+            .collect()
+    }
+
+    /// `count` registers of `unit_id` from `address`, or the Modbus exception
+    /// code to answer with.
+    fn read(&mut self, unit_id: u8, address: u16, count: u16, now_ms: u64) -> Result<Vec<u16>, u8> {
+        if count == 0 || count > MAX_READ_REGISTERS {
+            return Err(ILLEGAL_DATA_VALUE);
+        }
+        let base_address = self.base_address;
+        let map = self
+            .registers(unit_id, now_ms)
+            .ok_or(GATEWAY_TARGET_FAILED)?;
+        let start = address
+            .checked_sub(base_address)
+            .map(usize::from)
+            .ok_or(ILLEGAL_DATA_ADDRESS)?;
+        // Like most devices, addresses past the end marker read as zero.
+        Ok((start..start + usize::from(count))
+            .map(|index| map.get(index).copied().unwrap_or(0))
+            .collect())
+    }
+
+    /// Answers Modbus TCP read requests on `listener` until the task is
+    /// aborted. Reads of unknown unit IDs get a gateway exception, reads of
+    /// more than 125 registers an illegal data value exception.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        info!(
+            addr = %listener.local_addr()?,
+            units = ?self.unit_ids(),
+            models = ?self.models,
+            "sunspec simulator listening"
+        );
+        let simulator = Arc::new(Mutex::new(self));
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            debug!(%peer, "simulator connection accepted");
+            tokio::spawn(serve_connection(stream, simulator.clone()));
+        }
+    }
+}
+
+impl SimulatedUnit {
+    fn power_w(&self, now_ms: u64) -> f64 {
+        let angle = TAU * ((now_ms as f64 % CYCLE_MS) / CYCLE_MS + self.phase);
+        (self.rated_w * (0.55 + 0.45 * angle.sin())).round()
+    }
+}
+
+async fn serve_connection(mut stream: TcpStream, simulator: Arc<Mutex<Simulator>>) {
+    let mut header = [0u8; 7];
+    loop {
+        if stream.read_exact(&mut header).await.is_err() {
+            return;
+        }
+        let length = usize::from(u16::from_be_bytes([header[4], header[5]]));
+        if length < 2 {
+            return;
+        }
+        let mut pdu = vec![0u8; length - 1];
+        if stream.read_exact(&mut pdu).await.is_err() {
+            return;
+        }
+
+        let unit_id = header[6];
+        let function = pdu[0];
+        let reply = match (function, pdu.get(1..5)) {
+            (READ_HOLDING_REGISTERS | READ_INPUT_REGISTERS, Some(request)) => {
+                let address = u16::from_be_bytes([request[0], request[1]]);
+                let count = u16::from_be_bytes([request[2], request[3]]);
+                simulator
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .read(unit_id, address, count, unix_ms())
+            }
+            (READ_HOLDING_REGISTERS | READ_INPUT_REGISTERS, None) => Err(ILLEGAL_DATA_VALUE),
+            _ => Err(ILLEGAL_FUNCTION),
+        };
+        let body = match reply {
+            Ok(registers) => {
+                let mut body = vec![function, (registers.len() * 2) as u8];
+                body.extend(registers.iter().flat_map(|register| register.to_be_bytes()));
+                body
+            }
+            Err(exception) => vec![function | 0x80, exception],
+        };
+
+        let mut frame = Vec::with_capacity(7 + body.len());
+        frame.extend_from_slice(&header[..4]);
+        frame.extend_from_slice(&(body.len() as u16 + 1).to_be_bytes());
+        frame.push(unit_id);
+        frame.extend(body);
+        if stream.write_all(&frame).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn model_len(model: u16) -> u16 {
+    match model {
+        101..=103 => INVERTER_LEN,
+        _ => METER_LEN,
+    }
+}
+
+fn common_block(unit_id: u8, models: &[u16]) -> Vec<u16> {
+    let model_name = match models.first() {
+        Some(model) => format!("SIM-{model}"),
+        None => "SIM".to_string(),
+    };
+    let mut block = vec![1, COMMON_LEN];
+    block.extend(string_registers("SunSpec Simulator", 16));
+    block.extend(string_registers(&model_name, 16));
+    block.extend(string_registers("", 8));
+    block.extend(string_registers(env!("CARGO_PKG_VERSION"), 8));
+    block.extend(string_registers(&format!("SIM{unit_id:05}"), 16));
+    // Device address and padding.
+    block.extend([u16::from(unit_id), NOT_IMPLEMENTED_U16]);
+    block
+}
+
+/// Integer inverter block (models 101-103). Single- and split-phase models
+/// leave the unused phases unimplemented.
+fn inverter_block(model: u16, power_w: f64, energy_wh: f64) -> Vec<u16> {
+    let phases = usize::from(model - 100);
+    let mut block = vec![NOT_IMPLEMENTED_U16; usize::from(INVERTER_LEN) + 2];
+    block[0] = model;
+    block[1] = INVERTER_LEN;
+
+    let phase_a = power_w / 230.0 / phases as f64;
+    block[2] = (power_w / 230.0 * 100.0).round() as u16;
+    for phase in 0..phases {
+        block[3 + phase] = (phase_a * 100.0).round() as u16;
+        block[10 + phase] = 2_300;
+    }
+    block[6] = scale(-2);
+    block[13] = scale(-1);
+    block[14] = power_w as i16 as u16;
+    block[15] = scale(0);
+    block[16] = 5_000;
+    block[17] = scale(-2);
+    block[18] = power_w as i16 as u16;
+    block[19] = scale(0);
+    block[20] = 0;
+    block[21] = scale(0);
+    block[22] = 1_000;
+    block[23] = scale(-1);
+    let energy_wh = energy_wh as u32;
+    block[24] = (energy_wh >> 16) as u16;
+    block[25] = energy_wh as u16;
+    block[26] = scale(0);
+    let dc_w = power_w * 1.03;
+    block[27] = (dc_w / 600.0 * 100.0).round() as u16;
+    block[28] = scale(-2);
+    block[29] = 600;
+    block[30] = scale(0);
+    block[31] = dc_w.round() as i16 as u16;
+    block[32] = scale(0);
+    block[33] = 400;
+    block[34] = 450;
+    block[35] = NOT_IMPLEMENTED_I16;
+    block[36] = NOT_IMPLEMENTED_I16;
+    block[37] = scale(-1);
+    // MPPT while producing, sleeping otherwise.
+    block[38] = if power_w > 0.0 { 4 } else { 2 };
+    block[39] = 0;
+    block[40..44].fill(0);
+    block
+}
+
+/// Integer meter block (models 201-204): current, voltage, frequency and
+/// real power; everything else is left unimplemented.
+fn meter_block(model: u16, power_w: f64) -> Vec<u16> {
+    let mut block = vec![NOT_IMPLEMENTED_I16; usize::from(METER_LEN) + 2];
+    block[0] = model;
+    block[1] = METER_LEN;
+    block[2] = (power_w.abs() / 230.0 * 100.0).round() as u16;
+    block[6] = scale(-2);
+    block[7] = 2_300;
+    block[15] = scale(-1);
+    block[16] = 5_000;
+    block[17] = scale(-2);
+    block[18] = power_w as i16 as u16;
+    block[22] = scale(0);
+    block
+}
+
+fn scale(factor: i16) -> u16 {
+    factor as u16
+}
+
+/// `value` as NUL-padded big-endian byte pairs over `registers` registers.
+fn string_registers(value: &str, registers: usize) -> Vec<u16> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.resize(registers * 2, 0);
+    bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect()
+}
+
+fn unix_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,383 @@
+use std::collections::{BTreeMap, HashMap};
+
+use types::DeviceIdentity;
+
+use crate::device_ids::DeviceIds;
+
+const NAMESPACE: &str = "spBv1.0";
+const BD_SEQ: &str = "bdSeq";
+const REBIRTH: &str = "Node Control/Rebirth";
+
+// Sparkplug B `DataType` values.
+const DATATYPE_UINT64: u32 = 8;
+const DATATYPE_DOUBLE: u32 = 10;
+const DATATYPE_BOOLEAN: u32 = 11;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    UInt64(u64),
+    Double(f64),
+    Boolean(bool),
+}
+
+impl MetricValue {
+    fn datatype(&self) -> u32 {
+        match self {
+            MetricValue::UInt64(_) => DATATYPE_UINT64,
+            MetricValue::Double(_) => DATATYPE_DOUBLE,
+            MetricValue::Boolean(_) => DATATYPE_BOOLEAN,
+        }
+    }
+}
+
+/// One Sparkplug metric. Births carry name and alias, data messages only the alias.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub name: Option<String>,
+    pub alias: Option<u64>,
+    pub timestamp: u64,
+    pub value: MetricValue,
+}
+
+/// Sparkplug B payload (the subset of `org.eclipse.tahu.protobuf.Payload` we emit).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payload {
+    pub timestamp: u64,
+    pub metrics: Vec<Metric>,
+    /// Absent only in NDEATH.
+    pub seq: Option<u64>,
+}
+
+impl Payload {
+    /// Protobuf wire encoding.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        put_key(&mut buf, 1, WIRE_VARINT);
+        put_varint(&mut buf, self.timestamp);
+        for metric in &self.metrics {
+            let encoded = metric.encode();
+            put_key(&mut buf, 2, WIRE_LEN);
+            put_varint(&mut buf, encoded.len() as u64);
+            buf.extend_from_slice(&encoded);
+        }
+        if let Some(seq) = self.seq {
+            put_key(&mut buf, 3, WIRE_VARINT);
+            put_varint(&mut buf, seq);
+        }
+        buf
+    }
+}
+
+impl Metric {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        if let Some(name) = &self.name {
+            put_key(&mut buf, 1, WIRE_LEN);
+            put_varint(&mut buf, name.len() as u64);
+            buf.extend_from_slice(name.as_bytes());
+        }
+        if let Some(alias) = self.alias {
+            put_key(&mut buf, 2, WIRE_VARINT);
+            put_varint(&mut buf, alias);
+        }
+        put_key(&mut buf, 3, WIRE_VARINT);
+        put_varint(&mut buf, self.timestamp);
+        put_key(&mut buf, 4, WIRE_VARINT);
+        put_varint(&mut buf, u64::from(self.value.datatype()));
+        match self.value {
+            MetricValue::UInt64(value) => {
+                put_key(&mut buf, 11, WIRE_VARINT);
+                put_varint(&mut buf, value);
+            }
+            MetricValue::Double(value) => {
+                put_key(&mut buf, 13, WIRE_FIXED64);
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            MetricValue::Boolean(value) => {
+                put_key(&mut buf, 14, WIRE_VARINT);
+                put_varint(&mut buf, u64::from(value));
+            }
+        }
+        buf
+    }
+}
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+
+fn put_key(buf: &mut Vec<u8>, field: u32, wire_type: u8) {
+    put_varint(buf, u64::from((field << 3) | u32::from(wire_type)));
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// A payload and the Sparkplug topic it belongs to
+/// (`spBv1.0/<group>/<type>/<edge node>[/<device>]`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparkplugMessage {
+    pub topic: String,
+    pub payload: Payload,
+}
+
+#[derive(Debug, Default)]
+struct DeviceState {
+    born: bool,
+    /// Last value of every metric the device reported, by metric name.
+    metrics: BTreeMap<String, f64>,
+    /// Next metric index within the device's alias range.
+    next_alias: u64,
+}
+
+/// Sparkplug edge node state: sequence numbers, metric aliases and which
+/// devices have a valid birth certificate. The collector is the edge node,
+/// every polled Modbus device a Sparkplug device.
+#[derive(Debug)]
+pub struct EdgeNode {
+    group_id: String,
+    edge_node_id: String,
+    seq: u64,
+    bd_seq: u64,
+    next_alias: u64,
+    aliases: HashMap<(String, String), u64>,
+    devices: HashMap<String, DeviceState>,
+    device_ids: Option<DeviceIds>,
+}
+
+impl EdgeNode {
+    pub fn new(group_id: impl Into<String>, edge_node_id: impl Into<String>) -> Self {
+        Self {
+            group_id: group_id.into(),
+            edge_node_id: edge_node_id.into(),
+            seq: 0,
+            bd_seq: 0,
+            next_alias: 0,
+            aliases: HashMap::new(),
+            devices: HashMap::new(),
+            device_ids: None,
+        }
+    }
+
+    /// Derives metric aliases from the persistent device IDs: the device ID
+    /// in the upper 32 bits, the metric's index within the device below, so
+    /// an alias keeps pointing at the same device after a restart.
+    pub fn with_device_ids(mut self, ids: DeviceIds) -> Self {
+        self.device_ids = Some(ids);
+        self
+    }
+
+    /// NBIRTH. Starts a new session: sequence numbers restart at 0 and every
+    /// device has to be born again.
+    pub fn birth(&mut self, now_ms: u64) -> SparkplugMessage {
+        self.seq = 0;
+        for device in self.devices.values_mut() {
+            device.born = false;
+        }
+        let metrics = vec![
+            Metric {
+                name: Some(BD_SEQ.to_string()),
+                alias: None,
+                timestamp: now_ms,
+                value: MetricValue::UInt64(self.bd_seq),
+            },
+            Metric {
+                name: Some(REBIRTH.to_string()),
+                alias: None,
+                timestamp: now_ms,
+                value: MetricValue::Boolean(false),
+            },
+        ];
+        let payload = Payload {
+            timestamp: now_ms,
+            metrics,
+            seq: Some(self.next_seq()),
+        };
+        SparkplugMessage {
+            topic: self.topic("NBIRTH", None),
+            payload,
+        }
+    }
+
+    /// NDEATH for the current session. The next birth uses a new `bdSeq`.
+    pub fn death(&mut self, now_ms: u64) -> SparkplugMessage {
+        let payload = Payload {
+            timestamp: now_ms,
+            metrics: vec![Metric {
+                name: Some(BD_SEQ.to_string()),
+                alias: None,
+                timestamp: now_ms,
+                value: MetricValue::UInt64(self.bd_seq),
+            }],
+            seq: None,
+        };
+        self.bd_seq = (self.bd_seq + 1) % 256;
+        SparkplugMessage {
+            topic: self.topic("NDEATH", None),
+            payload,
+        }
+    }
+
+    /// Forgets the device's birth, so its next data is preceded by a DBIRTH
+    /// (called when its poller starts).
+    pub fn device_started(&mut self, device: &DeviceIdentity) {
+        self.devices.entry(device_id(device)).or_default().born = false;
+    }
+
+    /// DDATA for the given decoded points, or a DBIRTH carrying every known
+    /// metric of the device when it is not born yet or reports a metric its
+    /// birth did not declare. None for an empty point list.
+    pub fn device_data(
+        &mut self,
+        device: &DeviceIdentity,
+        points: &[(String, f64)],
+        now_ms: u64,
+    ) -> Option<SparkplugMessage> {
+        if points.is_empty() {
+            return None;
+        }
+        let id = device_id(device);
+        let state = self.devices.entry(id.clone()).or_default();
+        let rebirth = !state.born
+            || points
+                .iter()
+                .any(|(name, _)| !state.metrics.contains_key(name));
+        for (name, value) in points {
+            state.metrics.insert(name.clone(), *value);
+        }
+
+        if rebirth {
+            state.born = true;
+            let values: Vec<(String, f64)> = state
+                .metrics
+                .iter()
+                .map(|(name, value)| (name.clone(), *value))
+                .collect();
+            let metrics = values
+                .into_iter()
+                .map(|(name, value)| Metric {
+                    alias: Some(self.alias(device, &name)),
+                    name: Some(name),
+                    timestamp: now_ms,
+                    value: MetricValue::Double(value),
+                })
+                .collect();
+            let payload = Payload {
+                timestamp: now_ms,
+                metrics,
+                seq: Some(self.next_seq()),
+            };
+            return Some(SparkplugMessage {
+                topic: self.topic("DBIRTH", Some(&id)),
+                payload,
+            });
+        }
+
+        let metrics = points
+            .iter()
+            .map(|(name, value)| Metric {
+                name: None,
+                alias: Some(self.alias(device, name)),
+                timestamp: now_ms,
+                value: MetricValue::Double(*value),
+            })
+            .collect();
+        let payload = Payload {
+            timestamp: now_ms,
+            metrics,
+            seq: Some(self.next_seq()),
+        };
+        Some(SparkplugMessage {
+            topic: self.topic("DDATA", Some(&id)),
+            payload,
+        })
+    }
+
+    /// DDEATH, sent when the device's poller fails. None when the device was
+    /// never born.
+    pub fn device_death(
+        &mut self,
+        device: &DeviceIdentity,
+        now_ms: u64,
+    ) -> Option<SparkplugMessage> {
+        let id = device_id(device);
+        let state = self.devices.get_mut(&id)?;
+        if !state.born {
+            return None;
+        }
+        state.born = false;
+        let payload = Payload {
+            timestamp: now_ms,
+            metrics: Vec::new(),
+            seq: Some(self.next_seq()),
+        };
+        Some(SparkplugMessage {
+            topic: self.topic("DDEATH", Some(&id)),
+            payload,
+        })
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        let seq = self.seq;
+        self.seq = (self.seq + 1) % 256;
+        seq
+    }
+
+    /// Aliases are unique across all devices of the node and stable for the
+    /// life of the process. Devices without an assigned ID take theirs from a
+    /// node-wide counter, which stays below the first device ID range.
+    fn alias(&mut self, device: &DeviceIdentity, metric: &str) -> u64 {
+        let id = device_id(device);
+        let key = (id.clone(), metric.to_string());
+        if let Some(alias) = self.aliases.get(&key) {
+            return *alias;
+        }
+        let number = self
+            .device_ids
+            .as_ref()
+            .and_then(|ids| ids.get(&DeviceIds::key(device)));
+        let alias = match number {
+            Some(number) => {
+                let state = self.devices.entry(id).or_default();
+                let index = state.next_alias;
+                state.next_alias += 1;
+                (u64::from(number) << 32) | index
+            }
+            None => {
+                let alias = self.next_alias;
+                self.next_alias += 1;
+                alias
+            }
+        };
+        self.aliases.insert(key, alias);
+        alias
+    }
+
+    fn topic(&self, message_type: &str, device_id: Option<&str>) -> String {
+        match device_id {
+            Some(device_id) => format!(
+                "{NAMESPACE}/{}/{message_type}/{}/{device_id}",
+                self.group_id, self.edge_node_id
+            ),
+            None => format!(
+                "{NAMESPACE}/{}/{message_type}/{}",
+                self.group_id, self.edge_node_id
+            ),
+        }
+    }
+}
+
+/// Sparkplug device ID of a Modbus device, `<ip>-<unit id>`.
+pub fn device_id(device: &DeviceIdentity) -> String {
+    format!("{}-{}", device.ip, device.unit_id)
+}
+
+/// Metric name of a decoded SunSpec point, `<model name>/<point>`.
+pub fn metric_name(model_name: &str, point: &str) -> String {
+    format!("{model_name}/{point}")
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use modbus_client::ClientStats;
+use poller_actor::{PollerState, PollerStatus};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::capture::CaptureRegistry;
+use crate::control::PollerControl;
+use crate::device_ids::DeviceIds;
+use crate::inventory::DeviceLabels;
+
+/// Aggregates the status channels of every spawned poller so the admin API
+/// and the systemd watchdog can see stalled devices.
+#[derive(Clone, Default)]
+pub struct PollerRegistry {
+    pollers: Arc<Mutex<HashMap<String, watch::Receiver<PollerStatus>>>>,
+    device_ids: Option<DeviceIds>,
+    labels: Arc<Mutex<HashMap<String, DeviceLabels>>>,
+    captures: Option<CaptureRegistry>,
+    control: Option<PollerControl>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PollerReport {
+    pub id: String,
+    /// Short numeric ID of the device, see [`DeviceIds`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<u32>,
+    /// Site and alias from the inventory file.
+    #[serde(flatten)]
+    pub labels: DeviceLabels,
+    #[serde(flatten)]
+    pub status: PollerStatus,
+    pub stalled: bool,
+    /// Modbus capture is switched on, see [`CaptureRegistry`].
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub capturing: bool,
+}
+
+impl PollerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports carry the device IDs assigned in `ids`.
+    pub fn with_device_ids(mut self, ids: DeviceIds) -> Self {
+        self.device_ids = Some(ids);
+        self
+    }
+
+    /// Pollers get their Modbus capture tap from `captures`, and reports
+    /// show whether it is on.
+    pub fn with_captures(mut self, captures: CaptureRegistry) -> Self {
+        self.captures = Some(captures);
+        self
+    }
+
+    pub fn captures(&self) -> Option<&CaptureRegistry> {
+        self.captures.as_ref()
+    }
+
+    /// Pollers get their command channel from `control`.
+    pub fn with_control(mut self, control: PollerControl) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    pub fn control(&self) -> Option<&PollerControl> {
+        self.control.as_ref()
+    }
+
+    /// Registers (or replaces, after a respawn) the status channel for a poller.
+    pub fn register(&self, id: impl Into<String>, status: watch::Receiver<PollerStatus>) {
+        self.lock().insert(id.into(), status);
+    }
+
+    /// Sets the site and alias reported for a poller.
+    pub fn label(&self, id: impl Into<String>, labels: DeviceLabels) {
+        self.lock_labels().insert(id.into(), labels);
+    }
+
+    pub fn remove(&self, id: &str) {
+        self.lock().remove(id);
+        self.lock_labels().remove(id);
+        if let Some(control) = self.control.as_ref() {
+            control.remove(id);
+        }
+    }
+
+    /// Latest status of the poller registered as `id`.
+    pub fn status(&self, id: &str) -> Option<PollerStatus> {
+        self.lock().get(id).map(|rx| rx.borrow().clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Current status of every registered poller, sorted by id.
+    pub fn snapshot(&self, now_ms: u64, stall_after: Duration) -> Vec<PollerReport> {
+        let labels = self.lock_labels().clone();
+        let mut reports: Vec<PollerReport> = self
+            .lock()
+            .iter()
+            .map(|(id, rx)| {
+                let status = rx.borrow().clone();
+                let stalled = is_stalled(&status, now_ms, stall_after);
+                PollerReport {
+                    id: id.clone(),
+                    device_id: self.device_ids.as_ref().and_then(|ids| ids.get(id)),
+                    labels: labels.get(id).cloned().unwrap_or_default(),
+                    status,
+                    stalled,
+                    capturing: self
+                        .captures
+                        .as_ref()
+                        .and_then(|captures| captures.is_enabled(id))
+                        .unwrap_or(false),
+                }
+            })
+            .collect();
+        reports.sort_by(|a, b| a.id.cmp(&b.id));
+        reports
+    }
+
+    /// Ids of active pollers that have not completed a clean cycle within `stall_after`.
+    pub fn stalled(&self, now_ms: u64, stall_after: Duration) -> Vec<String> {
+        self.snapshot(now_ms, stall_after)
+            .into_iter()
+            .filter(|report| report.stalled)
+            .map(|report| report.id)
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, watch::Receiver<PollerStatus>>> {
+        self.pollers.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_labels(&self) -> std::sync::MutexGuard<'_, HashMap<String, DeviceLabels>> {
+        self.labels.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A poller is stalled when it is still supposed to be polling but its last
+/// successful cycle (or its start, if it never succeeded) is older than `stall_after`.
+/// Paused pollers are not stalled, and a resumed one gets `stall_after` to recover.
+pub fn is_stalled(status: &PollerStatus, now_ms: u64, stall_after: Duration) -> bool {
+    if !matches!(status.state, PollerState::Connecting | PollerState::Running) {
+        return false;
+    }
+    let reference = status
+        .last_success_ms
+        .unwrap_or(status.started_at_ms)
+        .max(status.resumed_at_ms.unwrap_or(0));
+    now_ms.saturating_sub(reference) > stall_after.as_millis() as u64
+}
+
+/// Turns the cumulative per-poller traffic counters into per-heartbeat deltas.
+#[derive(Debug, Default)]
+pub struct TrafficTracker {
+    previous: HashMap<String, (u64, ClientStats)>,
+}
+
+impl TrafficTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Traffic per poller since the previous call. Pollers that were respawned
+    /// in between report their new totals.
+    pub fn interval(&mut self, reports: &[PollerReport]) -> Vec<(String, ClientStats)> {
+        reports
+            .iter()
+            .map(|report| {
+                let total = &report.status.traffic;
+                let started_at = report.status.started_at_ms;
+                let delta = match self.previous.get(&report.id) {
+                    Some((previous_start, previous)) if *previous_start == started_at => {
+                        total.since(previous)
+                    }
+                    _ => total.clone(),
+                };
+                self.previous
+                    .insert(report.id.clone(), (started_at, total.clone()));
+                (report.id.clone(), delta)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,218 @@
+use std::io;
+use std::time::Duration;
+
+use metrics::{counter, gauge};
+use poller_actor::SampleClock;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::{sleep, timeout};
+use tracing::{info, warn};
+
+/// How long a `chronyc` or `timedatectl` call may take.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the host clock's synchronization state is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeSyncCheck {
+    /// No check (the default); samples are never flagged.
+    #[default]
+    None,
+    /// `chronyc -c tracking`, which also gives the offset from NTP time.
+    Chrony,
+    /// `timedatectl show`, i.e. systemd-timesyncd or whatever NTP client
+    /// systemd knows about; gives no offset.
+    Timedatectl,
+}
+
+impl TimeSyncCheck {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "chrony" => Some(Self::Chrony),
+            "timedatectl" => Some(Self::Timedatectl),
+            _ => None,
+        }
+    }
+}
+
+/// The `[timesync]` section: checking that the host clock is synchronized
+/// and how samples are timestamped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSyncSettings {
+    /// `none`, `chrony` or `timedatectl`; checked by
+    /// [`TimeSyncSettings::check`].
+    pub check: String,
+    pub interval_ms: u64,
+    /// Offset from NTP time above which a clock counts as unsynchronized,
+    /// for checks that report one.
+    pub max_offset_ms: u64,
+    /// Take `collected_at_ms` from the monotonic clock, anchored to the
+    /// wall clock at startup and at every check that finds it synchronized.
+    pub monotonic_timestamps: bool,
+}
+
+impl Default for TimeSyncSettings {
+    fn default() -> Self {
+        Self {
+            check: "none".to_string(),
+            interval_ms: 60_000,
+            max_offset_ms: 1_000,
+            monotonic_timestamps: false,
+        }
+    }
+}
+
+impl TimeSyncSettings {
+    pub fn check(&self) -> Result<TimeSyncCheck, String> {
+        TimeSyncCheck::parse(&self.check).ok_or_else(|| {
+            format!(
+                "timesync.check must be none, chrony or timedatectl, got '{}'",
+                self.check
+            )
+        })
+    }
+
+    /// The clock every poller timestamps its samples with.
+    pub fn sample_clock(&self) -> SampleClock {
+        if self.monotonic_timestamps {
+            SampleClock::anchored()
+        } else {
+            SampleClock::default()
+        }
+    }
+}
+
+/// What one check found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncStatus {
+    /// The NTP client considers the clock synchronized.
+    pub synchronized: bool,
+    /// Offset of the clock from NTP time in seconds, when known.
+    pub offset_seconds: Option<f64>,
+}
+
+impl SyncStatus {
+    /// Synchronized and no further than `max_offset_ms` off.
+    pub fn is_trusted(&self, max_offset_ms: u64) -> bool {
+        self.synchronized
+            && self
+                .offset_seconds
+                .is_none_or(|offset| offset.abs() * 1_000.0 <= max_offset_ms as f64)
+    }
+}
+
+/// Parses `chronyc -c tracking`: comma-separated, the system time offset in
+/// seconds fifth and the leap status last. None when the output is not in
+/// that shape.
+pub fn parse_chrony_tracking(output: &str) -> Option<SyncStatus> {
+    let fields: Vec<&str> = output.trim().split(',').collect();
+    if fields.len() < 14 {
+        return None;
+    }
+    let offset_seconds = fields[4].trim().parse::<f64>().ok()?;
+    let leap_status = fields[fields.len() - 1].trim();
+    Some(SyncStatus {
+        synchronized: !leap_status.eq_ignore_ascii_case("not synchronised"),
+        offset_seconds: Some(offset_seconds),
+    })
+}
+
+/// Parses `timedatectl show -p NTPSynchronized --value`, i.e. `yes` or `no`.
+pub fn parse_timedatectl(output: &str) -> Option<SyncStatus> {
+    let synchronized = match output.trim() {
+        "yes" => true,
+        "no" => false,
+        _ => return None,
+    };
+    Some(SyncStatus {
+        synchronized,
+        offset_seconds: None,
+    })
+}
+
+/// Runs the check's command and parses what it prints. None for
+/// [`TimeSyncCheck::None`].
+pub async fn query(check: TimeSyncCheck) -> io::Result<Option<SyncStatus>> {
+    let (program, args): (&str, &[&str]) = match check {
+        TimeSyncCheck::None => return Ok(None),
+        TimeSyncCheck::Chrony => ("chronyc", &["-c", "tracking"]),
+        TimeSyncCheck::Timedatectl => {
+            ("timedatectl", &["show", "-p", "NTPSynchronized", "--value"])
+        }
+    };
+    let output = timeout(QUERY_TIMEOUT, Command::new(program).args(args).output())
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, format!("{program} timed out")))??;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{program} exited with {}",
+            output.status
+        )));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let status = match check {
+        TimeSyncCheck::Chrony => parse_chrony_tracking(&stdout),
+        _ => parse_timedatectl(&stdout),
+    };
+    status.map(Some).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected {program} output: {}", stdout.trim()),
+        )
+    })
+}
+
+/// Checks the host clock once and flags `clock` while it is not trusted.
+/// A trusted clock re-anchors monotonic timestamps; when the check fails
+/// the flag is left as it was.
+pub async fn check_once(check: TimeSyncCheck, max_offset_ms: u64, clock: &SampleClock) {
+    let status = match query(check).await {
+        Ok(Some(status)) => status,
+        Ok(None) => return,
+        Err(err) => {
+            counter!("timesync_check_errors").increment(1);
+            warn!(error = %err, "host clock synchronization check failed");
+            return;
+        }
+    };
+    let trusted = status.is_trusted(max_offset_ms);
+    gauge!("host_clock_synchronized").set(f64::from(u8::from(trusted)));
+    if let Some(offset) = status.offset_seconds {
+        gauge!("host_clock_offset_seconds").set(offset);
+    }
+    if trusted {
+        clock.anchor();
+        if clock.is_unsynchronized() {
+            info!(offset_seconds = ?status.offset_seconds, "host clock synchronized, samples no longer flagged");
+        }
+    } else if !clock.is_unsynchronized() {
+        warn!(
+            synchronized = status.synchronized,
+            offset_seconds = ?status.offset_seconds,
+            max_offset_ms,
+            "host clock not synchronized, samples are flagged clock_unsynchronized"
+        );
+    }
+    clock.set_unsynchronized(!trusted);
+}
+
+/// Repeats [`check_once`] every `interval` until shutdown.
+pub async fn run(
+    check: TimeSyncCheck,
+    interval: Duration,
+    max_offset_ms: u64,
+    clock: SampleClock,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        tokio::select! {
+            _ = sleep(interval) => {}
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
+        }
+        check_once(check, max_offset_ms, &clock).await;
+    }
+}
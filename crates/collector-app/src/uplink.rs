@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use buffer::PendingCommit;
+use poller_actor::PollSample;
+use serde::{Deserialize, Serialize};
+
+/// Splits `samples` into at most `lanes` groups that can be published
+/// concurrently. All samples of a device share a lane and keep their order,
+/// so two payloads of the same device are never in flight at once. Devices
+/// are dealt to lanes in order of first appearance. Empty lanes are left out.
+pub fn assign_lanes(samples: &[PollSample], lanes: usize) -> Vec<Vec<usize>> {
+    let lanes = lanes.max(1);
+    let mut by_device: HashMap<(&str, u8), usize> = HashMap::new();
+    let mut assigned = vec![Vec::new(); lanes];
+    for (index, sample) in samples.iter().enumerate() {
+        let next = by_device.len() % lanes;
+        let lane = *by_device
+            .entry((sample.device.ip.as_str(), sample.device.unit_id))
+            .or_insert(next);
+        assigned[lane].push(index);
+    }
+    assigned.retain(|lane| !lane.is_empty());
+    assigned
+}
+
+/// Splits the indices of one lane by the topic their rows were buffered
+/// for, in order of first appearance. Samples keep their order within a
+/// topic; each group is published to its own topic.
+pub fn split_by_topic<'a>(indices: &[usize], topics: &[&'a str]) -> Vec<(&'a str, Vec<usize>)> {
+    let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+    for &index in indices {
+        let topic = topics[index];
+        match groups.iter_mut().find(|(group, _)| *group == topic) {
+            Some((_, members)) => members.push(index),
+            None => groups.push((topic, vec![index])),
+        }
+    }
+    groups
+}
+
+/// Published to the commit topic inside the transaction of every
+/// exactly-once drain, keyed by the transactional id. After a crash it tells
+/// whether the commit left pending in the buffer went through.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitMarker {
+    pub seq: i64,
+    pub prepared_at_ms: i64,
+    pub rows: usize,
+}
+
+impl CommitMarker {
+    pub fn new(commit: &PendingCommit) -> Self {
+        Self {
+            seq: commit.seq,
+            prepared_at_ms: commit.prepared_at_ms,
+            rows: commit.ids.len(),
+        }
+    }
+
+    /// Whether this marker was written for `commit`. The preparation time is
+    /// compared too, so a marker left by an earlier buffer file with the same
+    /// sequence number does not count.
+    pub fn confirms(&self, commit: &PendingCommit) -> bool {
+        self.seq == commit.seq && self.prepared_at_ms == commit.prepared_at_ms
+    }
+}
+
+/// Whether the buffer task publishes samples itself instead of buffering
+/// them. That is only safe while the buffer is empty, or a sample would
+/// overtake older ones of its device still waiting for the uplink. So it
+/// starts out buffering, and a failed publish goes back to buffering until
+/// a check finds the backlog drained.
+#[derive(Debug)]
+pub struct WriteThrough {
+    direct: bool,
+    check_interval: Duration,
+    last_check: Option<Instant>,
+}
+
+impl WriteThrough {
+    /// `check_interval` limits how often the buffer is counted while
+    /// samples are being buffered.
+    pub fn new(check_interval: Duration) -> Self {
+        Self {
+            direct: false,
+            check_interval,
+            last_check: None,
+        }
+    }
+
+    /// Whether samples are published directly.
+    pub fn is_direct(&self) -> bool {
+        self.direct
+    }
+
+    /// Whether to count the buffer now: while buffering with the broker up,
+    /// at most once per check interval. With the broker down, direct
+    /// publishing stops right away.
+    pub fn should_check(&mut self, broker_up: bool, now: Instant) -> bool {
+        if !broker_up {
+            self.direct = false;
+            return false;
+        }
+        if self.direct
+            || self
+                .last_check
+                .is_some_and(|last| now.duration_since(last) < self.check_interval)
+        {
+            return false;
+        }
+        self.last_check = Some(now);
+        true
+    }
+
+    /// Records the rows the buffer held at a check; an empty buffer turns
+    /// direct publishing on.
+    pub fn checked(&mut self, pending: i64) {
+        self.direct = pending == 0;
+    }
+
+    /// A direct publish failed, so samples are buffered again.
+    pub fn fall_back(&mut self) {
+        self.direct = false;
+    }
+}
+
+/// The order in which the uplink drains the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DrainOrder {
+    /// Strictly in buffered order (the default).
+    #[default]
+    OldestFirst,
+    /// The most recent rows first, so live data goes out before the backlog
+    /// of an outage. Rows within a batch stay in buffered order.
+    NewestFirst,
+    /// Rows buffered within the live window first, with a share of every
+    /// batch reserved for the oldest backlog rows so it keeps draining.
+    LivePriority,
+}
+
+impl DrainOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "oldest_first" => Some(Self::OldestFirst),
+            "newest_first" => Some(Self::NewestFirst),
+            "live_priority" => Some(Self::LivePriority),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a drain of `limit` rows into (live, backlog) quotas for
+/// [`DrainOrder::LivePriority`]: the backlog gets `backlog_share_pct`
+/// percent, rounded up, and at least one row.
+pub fn live_quotas(limit: i64, backlog_share_pct: u8) -> (i64, i64) {
+    let limit = limit.max(1);
+    let backlog = (limit * i64::from(backlog_share_pct.min(100)) + 99) / 100;
+    let backlog = backlog.clamp(1, limit);
+    (limit - backlog, backlog)
+}
@@ -0,0 +1,99 @@
+use collector_app::aggregate::{window_point, ModelWindow, WindowAggregator};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+fn inverter_sample(model_id: u16, power_w: i16, collected_at_ms: u64) -> PollSample {
+    let mut registers = vec![0u16; 52];
+    registers[0] = model_id;
+    registers[1] = 50;
+    registers[14] = power_w as u16;
+    PollSample::new(
+        DeviceIdentity {
+            ip: "10.0.0.2".to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        model_id,
+        "inverter",
+        40_002,
+        registers,
+        collected_at_ms,
+    )
+}
+
+fn aggregator() -> WindowAggregator {
+    WindowAggregator::new(&[ModelWindow {
+        model_id: 103,
+        window_ms: 60_000,
+    }])
+}
+
+#[test]
+fn unconfigured_models_pass_through() {
+    let mut aggregator = aggregator();
+
+    let ready = aggregator.push(inverter_sample(101, 500, 1_000));
+    assert_eq!(ready.len(), 1);
+    assert!(ready[0].window.is_none());
+    assert!(aggregator.flush_all().is_empty());
+}
+
+#[test]
+fn baseline_samples_bypass_windows() {
+    let mut aggregator = aggregator();
+
+    let mut baseline = inverter_sample(103, 500, 1_000);
+    baseline.baseline = true;
+    let ready = aggregator.push(baseline);
+    assert_eq!(ready.len(), 1);
+    assert!(ready[0].baseline && ready[0].window.is_none());
+    assert!(aggregator.flush_all().is_empty());
+}
+
+#[test]
+fn window_closes_when_the_next_one_starts() {
+    let mut aggregator = aggregator();
+
+    assert!(aggregator.push(inverter_sample(103, 1_000, 0)).is_empty());
+    assert!(aggregator
+        .push(inverter_sample(103, 3_000, 20_000))
+        .is_empty());
+    assert!(aggregator
+        .push(inverter_sample(103, 2_000, 40_000))
+        .is_empty());
+    let ready = aggregator.push(inverter_sample(103, 500, 60_000));
+
+    assert_eq!(ready.len(), 1);
+    let window = ready[0].window.as_ref().expect("aggregated sample");
+    assert_eq!((window.start_ms, window.end_ms), (0, 60_000));
+    assert_eq!(window.samples, 3);
+    assert_eq!(ready[0].collected_at_ms, 40_000);
+
+    let power = window_point(&ready[0], "W").expect("W stats");
+    assert_eq!(power.min, 1_000.0);
+    assert_eq!(power.avg, 2_000.0);
+    assert_eq!(power.max, 3_000.0);
+}
+
+#[test]
+fn expired_windows_flush_after_grace() {
+    let mut aggregator = aggregator();
+    aggregator.push(inverter_sample(103, 1_000, 10_000));
+
+    assert!(aggregator.flush_expired(61_000).is_empty());
+    let ready = aggregator.flush_expired(62_000);
+    assert_eq!(ready.len(), 1);
+    assert_eq!(ready[0].window.as_ref().map(|w| w.samples), Some(1));
+    assert!(aggregator.flush_expired(120_000).is_empty());
+}
+
+#[test]
+fn flush_all_emits_partial_windows() {
+    let mut aggregator = aggregator();
+    aggregator.push(inverter_sample(103, 1_000, 10_000));
+    aggregator.push(inverter_sample(103, 1_500, 20_000));
+
+    let ready = aggregator.flush_all();
+    assert_eq!(ready.len(), 1);
+    assert_eq!(ready[0].window.as_ref().map(|w| w.samples), Some(2));
+}
@@ -0,0 +1,107 @@
+use avro_kafka::{Encoding, Publisher};
+use collector_app::alarms::{AlarmRecord, AlarmTracker, AlarmTransition, FAULT_EVENT};
+use poller_actor::PollSample;
+use sunspec_parser::INVERTER_STATE_FAULT;
+use types::DeviceIdentity;
+
+const MPPT: u16 = 4;
+const GROUND_FAULT: u16 = 1;
+const OVER_TEMP: u16 = 1 << 7;
+
+fn inverter_sample(state: u16, evt1: u16, collected_at_ms: u64) -> PollSample {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[38] = state;
+    registers[41] = evt1;
+    PollSample {
+        device: DeviceIdentity {
+            ip: "192.168.1.20".to_string(),
+            unit_id: 1,
+            base_address: Some(40_000),
+        },
+        model_id: 103,
+        model_name: "three_phase_inverter".to_string(),
+        start: 40_070,
+        registers,
+        collected_at_ms,
+        window: None,
+        maintenance: false,
+        baseline: false,
+        sequence: 0,
+        cycle_id: 0,
+        site: None,
+        monotonic_ms: 0,
+        clock_unsynchronized: false,
+        namespace: None,
+    }
+}
+
+fn transitions(records: &[AlarmRecord]) -> Vec<(&str, &str, AlarmTransition)> {
+    records
+        .iter()
+        .map(|record| {
+            (
+                record.point.as_str(),
+                record.event.as_str(),
+                record.transition,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn fault_states_and_events_are_raised_and_cleared() {
+    let mut tracker = AlarmTracker::new();
+
+    // Alarms standing at the first sample are raised.
+    let records = tracker.observe(&inverter_sample(MPPT, OVER_TEMP, 0));
+    assert_eq!(
+        transitions(&records),
+        vec![("Evt1", "OVER_TEMP", AlarmTransition::Raised)]
+    );
+    assert_eq!(records[0].state.as_deref(), Some("MPPT"));
+    assert!(tracker
+        .observe(&inverter_sample(MPPT, OVER_TEMP, 1_000))
+        .is_empty());
+
+    let records = tracker.observe(&inverter_sample(INVERTER_STATE_FAULT, GROUND_FAULT, 2_000));
+    assert_eq!(
+        transitions(&records),
+        vec![
+            ("Evt1", "GROUND_FAULT", AlarmTransition::Raised),
+            ("St", FAULT_EVENT, AlarmTransition::Raised),
+            ("Evt1", "OVER_TEMP", AlarmTransition::Cleared),
+        ]
+    );
+    assert!(records.iter().all(|record| record.collected_at_ms == 2_000));
+
+    let records = tracker.observe(&inverter_sample(MPPT, 0, 3_000));
+    assert_eq!(
+        transitions(&records),
+        vec![
+            ("Evt1", "GROUND_FAULT", AlarmTransition::Cleared),
+            ("St", FAULT_EVENT, AlarmTransition::Cleared),
+        ]
+    );
+
+    // Models without state points are ignored.
+    let mut meter = inverter_sample(MPPT, GROUND_FAULT, 4_000);
+    meter.model_id = 203;
+    assert!(tracker.observe(&meter).is_empty());
+}
+
+#[test]
+fn alarm_records_match_the_alarm_schema() {
+    let mut tracker = AlarmTracker::new();
+    let records = tracker.observe(&inverter_sample(INVERTER_STATE_FAULT, GROUND_FAULT, 0));
+    assert_eq!(records.len(), 2);
+
+    let publisher = Publisher::new_mock(Publisher::alarm_schema(), "sunspec.alarms");
+    assert!(!publisher.serialize(&records[0]).expect("avro").is_empty());
+    let json = publisher.with_encoding(Encoding::Json);
+    let payload = json.serialize(&records[1]).expect("json");
+    let record: serde_json::Value = serde_json::from_slice(&payload).expect("json record");
+    assert_eq!(record["transition"], "raised");
+    assert_eq!(record["state"], "FAULT");
+}
@@ -0,0 +1,169 @@
+use avro_kafka::{Encoding, Publisher};
+use collector_app::alarms::AlarmTransition;
+use collector_app::alerts::{AlertCondition, AlertRecord, AlertRule, AlertTracker};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+fn sample(unit_id: u8, collected_at_ms: u64) -> PollSample {
+    PollSample {
+        device: DeviceIdentity {
+            ip: "192.168.1.20".to_string(),
+            unit_id,
+            base_address: Some(40_000),
+        },
+        model_id: 103,
+        model_name: "three_phase_inverter".to_string(),
+        start: 40_070,
+        registers: vec![103, 50],
+        collected_at_ms,
+        window: None,
+        maintenance: false,
+        baseline: false,
+        sequence: 0,
+        cycle_id: 0,
+        site: None,
+        monotonic_ms: 0,
+        clock_unsynchronized: false,
+        namespace: None,
+    }
+}
+
+fn rule(name: &str, point: &str, condition: AlertCondition, deadband: f64) -> AlertRule {
+    AlertRule {
+        name: name.to_string(),
+        point: point.to_string(),
+        model_id: None,
+        condition,
+        deadband,
+    }
+}
+
+fn point(name: &str, value: f64) -> Vec<(String, f64)> {
+    vec![(name.to_string(), value)]
+}
+
+fn transitions(records: &[AlertRecord]) -> Vec<(&str, AlarmTransition)> {
+    records
+        .iter()
+        .map(|record| (record.rule.as_str(), record.transition))
+        .collect()
+}
+
+#[test]
+fn thresholds_raise_once_and_clear_past_the_deadband() {
+    let mut tracker = AlertTracker::new(vec![
+        rule("dc_high", "DCV", AlertCondition::Above(950.0), 10.0),
+        rule("dc_low", "DCV", AlertCondition::Below(200.0), 0.0),
+    ]);
+
+    // Over the limit at the first sample: raised right away.
+    let records = tracker.observe(&sample(1, 0), &point("DCV", 960.0));
+    assert_eq!(
+        transitions(&records),
+        vec![("dc_high", AlarmTransition::Raised)]
+    );
+    assert_eq!(records[0].value, 960.0);
+    assert_eq!(records[0].limit, 950.0);
+    // Back under the limit but within the deadband: still raised.
+    assert!(tracker
+        .observe(&sample(1, 1_000), &point("DCV", 945.0))
+        .is_empty());
+    assert_eq!(tracker.active().len(), 1);
+    let records = tracker.observe(&sample(1, 2_000), &point("DCV", 940.0));
+    assert_eq!(
+        transitions(&records),
+        vec![("dc_high", AlarmTransition::Cleared)]
+    );
+
+    let records = tracker.observe(&sample(1, 3_000), &point("DCV", 150.0));
+    assert_eq!(
+        transitions(&records),
+        vec![("dc_low", AlarmTransition::Raised)]
+    );
+    // Other devices are tracked on their own; samples without the point are ignored.
+    assert!(tracker
+        .observe(&sample(2, 3_000), &point("DCV", 500.0))
+        .is_empty());
+    assert!(tracker
+        .observe(&sample(1, 4_000), &point("W", 0.0))
+        .is_empty());
+    let active = tracker.active();
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].device.unit_id, 1);
+}
+
+#[test]
+fn rate_rules_compare_consecutive_samples() {
+    let mut cabinet = rule(
+        "cabinet_heating",
+        "TmpCab",
+        AlertCondition::RisingPerMin(2.0),
+        0.5,
+    );
+    cabinet.model_id = Some(103);
+    let mut tracker = AlertTracker::new(vec![
+        cabinet,
+        rule(
+            "power_drop",
+            "W",
+            AlertCondition::FallingPerMin(1_000.0),
+            0.0,
+        ),
+    ]);
+
+    // The first sample only sets the baseline.
+    assert!(tracker
+        .observe(&sample(1, 0), &point("TmpCab", 40.0))
+        .is_empty());
+    // 1.5 °C in 30 s is 3 °C/min.
+    let records = tracker.observe(&sample(1, 30_000), &point("TmpCab", 41.5));
+    assert_eq!(
+        transitions(&records),
+        vec![("cabinet_heating", AlarmTransition::Raised)]
+    );
+    assert_eq!(records[0].value, 3.0);
+    // 1.6 °C/min is within the deadband, 1 °C/min is not.
+    assert!(tracker
+        .observe(&sample(1, 90_000), &point("TmpCab", 43.1))
+        .is_empty());
+    let records = tracker.observe(&sample(1, 150_000), &point("TmpCab", 44.1));
+    assert_eq!(
+        transitions(&records),
+        vec![("cabinet_heating", AlarmTransition::Cleared)]
+    );
+
+    tracker.observe(&sample(1, 0), &point("W", 5_000.0));
+    let records = tracker.observe(&sample(1, 60_000), &point("W", 3_000.0));
+    assert_eq!(
+        transitions(&records),
+        vec![("power_drop", AlarmTransition::Raised)]
+    );
+    assert_eq!(records[0].value, -2_000.0);
+
+    // The model filter leaves out blocks of other models.
+    let mut meter = sample(1, 0);
+    meter.model_id = 203;
+    tracker.observe(&meter, &point("TmpCab", 20.0));
+    meter.collected_at_ms = 60_000;
+    assert!(tracker.observe(&meter, &point("TmpCab", 40.0)).is_empty());
+}
+
+#[test]
+fn alert_records_match_the_alert_schema() {
+    let mut tracker = AlertTracker::new(vec![rule(
+        "dc_high",
+        "DCV",
+        AlertCondition::Above(950.0),
+        0.0,
+    )]);
+    let records = tracker.observe(&sample(1, 0), &point("DCV", 960.0));
+
+    let publisher = Publisher::new_mock(Publisher::alert_schema(), "sunspec.alerts");
+    assert!(!publisher.serialize(&records[0]).expect("avro").is_empty());
+    let json = publisher.with_encoding(Encoding::Json);
+    let payload = json.serialize(&records[0]).expect("json");
+    let record: serde_json::Value = serde_json::from_slice(&payload).expect("json record");
+    assert_eq!(record["transition"], "raised");
+    assert_eq!(record["rule"], "dc_high");
+    assert_eq!(record["limit"], 950.0);
+}
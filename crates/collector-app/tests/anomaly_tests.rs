@@ -0,0 +1,108 @@
+use collector_app::anomaly::{
+    AnomalyDetector, AnomalyStage, DeviceGroup, PowerReading, ZScoreDetector,
+};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+fn device(ip: &str) -> DeviceIdentity {
+    DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id: 1,
+        base_address: None,
+    }
+}
+
+fn reading(ip: &str, power_w: f64) -> PowerReading {
+    PowerReading {
+        device: device(ip),
+        power_w,
+    }
+}
+
+fn inverter_sample(ip: &str, power_w: i16, at_ms: u64) -> PollSample {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[14] = power_w as u16;
+    PollSample::new(
+        device(ip),
+        103,
+        "three_phase_inverter",
+        40_070,
+        registers,
+        at_ms,
+    )
+}
+
+#[test]
+fn zscore_flags_device_far_below_peers() {
+    let mut detector = ZScoreDetector::new(2.0, 20.0);
+    let readings = vec![
+        reading("10.0.0.2", 5_000.0),
+        reading("10.0.0.3", 5_100.0),
+        reading("10.0.0.4", 4_900.0),
+        reading("10.0.0.5", 3_500.0),
+    ];
+
+    let events = detector.evaluate("roof", &readings);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].device.ip, "10.0.0.5");
+    assert_eq!(events[0].group, "roof");
+    assert_eq!(events[0].detector, "zscore");
+    assert_eq!(events[0].peer_mean_w, 5_000.0);
+    assert!((events[0].deficit_pct - 30.0).abs() < 1e-9);
+}
+
+#[test]
+fn zscore_ignores_small_deficits_and_small_groups() {
+    let mut detector = ZScoreDetector::new(2.0, 20.0);
+    let close = vec![
+        reading("10.0.0.2", 5_000.0),
+        reading("10.0.0.3", 5_000.0),
+        reading("10.0.0.4", 4_500.0),
+    ];
+    assert!(detector.evaluate("roof", &close).is_empty());
+
+    let pair = vec![reading("10.0.0.2", 5_000.0), reading("10.0.0.3", 1_000.0)];
+    assert!(detector.evaluate("roof", &pair).is_empty());
+}
+
+#[test]
+fn stage_evaluates_configured_groups_with_fresh_readings() {
+    let groups = vec![DeviceGroup {
+        name: "east".to_string(),
+        devices: vec![
+            "10.0.0.2".to_string(),
+            "10.0.0.3".to_string(),
+            "10.0.0.4".to_string(),
+        ],
+    }];
+    let mut stage =
+        AnomalyStage::new(groups, 3_000, 100.0).with_detector(ZScoreDetector::new(2.0, 20.0));
+
+    stage.record(&inverter_sample("10.0.0.2", 5_000, 10_000));
+    stage.record(&inverter_sample("10.0.0.3", 5_000, 10_000));
+    stage.record(&inverter_sample("10.0.0.4", 2_000, 10_000));
+    // Not in the group.
+    stage.record(&inverter_sample("10.0.0.9", 0, 10_000));
+
+    let events = stage.evaluate(11_000);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].device.ip, "10.0.0.4");
+    assert_eq!(events[0].group, "east");
+
+    // Stale readings are dropped before comparing.
+    assert!(stage.evaluate(20_000).is_empty());
+}
+
+#[test]
+fn stage_skips_groups_below_min_power() {
+    let mut stage =
+        AnomalyStage::new(Vec::new(), 3_000, 100.0).with_detector(ZScoreDetector::new(2.0, 20.0));
+
+    stage.record(&inverter_sample("10.0.0.2", 40, 0));
+    stage.record(&inverter_sample("10.0.0.3", 40, 0));
+    stage.record(&inverter_sample("10.0.0.4", 0, 0));
+
+    assert!(stage.evaluate(1_000).is_empty());
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use collector_app::boot::{device_class, BootOrder};
+
+#[test]
+fn class_follows_the_models() {
+    assert_eq!(device_class(&[1, 103, 160]), Some("inverter"));
+    assert_eq!(device_class(&[1, 203]), Some("meter"));
+    // A meter built into an inverter still starts with the meters.
+    assert_eq!(device_class(&[1, 103, 213]), Some("meter"));
+    assert_eq!(device_class(&[1, 802]), Some("storage"));
+    assert_eq!(device_class(&[1, 307]), None);
+}
+
+#[test]
+fn listed_classes_start_one_step_apart() {
+    let boot = BootOrder {
+        classes: vec!["meter".to_string(), "inverter".to_string()],
+        step: Duration::from_secs(5),
+    };
+    assert_eq!(boot.delay(Some("meter")), Duration::ZERO);
+    assert_eq!(boot.delay(Some("Inverter")), Duration::from_secs(5));
+    assert_eq!(boot.delay(Some("storage")), Duration::from_secs(10));
+    assert_eq!(boot.delay(None), Duration::from_secs(10));
+}
+
+#[test]
+fn without_an_order_everything_starts_at_once() {
+    let boot = BootOrder {
+        classes: Vec::new(),
+        step: Duration::from_secs(5),
+    };
+    assert_eq!(boot.delay(Some("meter")), Duration::ZERO);
+    assert_eq!(boot.delay(None), Duration::ZERO);
+}
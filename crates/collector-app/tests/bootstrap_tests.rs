@@ -0,0 +1,42 @@
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+
+use collector_app::bootstrap::{resolve_brokers, BootstrapWatch};
+
+fn addrs(list: &[&str]) -> BTreeSet<SocketAddr> {
+    list.iter()
+        .map(|addr| addr.parse().expect("socket address"))
+        .collect()
+}
+
+#[tokio::test]
+async fn broker_lists_resolve_like_librdkafka_reads_them() {
+    let resolved = resolve_brokers("10.0.0.1:9093, PLAINTEXT://10.0.0.2,[::1]:9094")
+        .await
+        .expect("resolve");
+    assert_eq!(
+        resolved,
+        addrs(&["10.0.0.1:9093", "10.0.0.2:9092", "[::1]:9094"])
+    );
+
+    assert!(resolve_brokers("kafka.invalid:9092").await.is_err());
+}
+
+#[test]
+fn only_a_different_resolution_is_a_change() {
+    let mut watch = BootstrapWatch::new();
+    let first = addrs(&["10.0.0.1:9092"]);
+    assert!(!watch.changed(&first));
+    watch.record(first.clone());
+    assert!(!watch.changed(&first));
+
+    let moved = addrs(&["10.0.1.1:9092"]);
+    assert!(watch.changed(&moved));
+    // Still a change until the rebuilt producer's addresses are recorded.
+    assert!(watch.changed(&moved));
+    watch.record(moved.clone());
+    assert!(!watch.changed(&moved));
+
+    watch.reset();
+    assert!(!watch.changed(&first));
+}
@@ -0,0 +1,67 @@
+use avro_kafka::{BrokerHealth, PublishError, Publisher};
+use collector_app::broker::{BrokerState, BrokerStatus};
+
+fn healthy() -> Result<BrokerHealth, PublishError> {
+    Ok(BrokerHealth {
+        brokers: 3,
+        latency_ms: 12,
+    })
+}
+
+fn unreachable() -> Result<BrokerHealth, PublishError> {
+    Err(PublishError::Probe("all brokers down".to_string()))
+}
+
+#[test]
+fn reconnects_are_counted_when_the_cluster_comes_back() {
+    let broker = BrokerStatus::new();
+    assert_eq!(broker.state(), BrokerState::Unknown);
+
+    assert_eq!(broker.probed(&healthy(), 1_000), Some(BrokerState::Unknown));
+    assert_eq!(broker.probed(&healthy(), 2_000), None);
+    assert_eq!(broker.probed(&unreachable(), 3_000), Some(BrokerState::Up));
+    let down = broker.snapshot();
+    assert_eq!(down.state, BrokerState::Down);
+    assert_eq!(down.since_ms, Some(3_000));
+    assert_eq!(down.last_probe_latency_ms, None);
+    assert!(down.error.is_some());
+    assert_eq!(down.reconnects, 0);
+
+    assert_eq!(broker.probed(&unreachable(), 4_000), None);
+    assert_eq!(broker.delivered(5_000), Some(BrokerState::Down));
+    let up = broker.snapshot();
+    assert_eq!(up.state, BrokerState::Up);
+    assert_eq!(up.since_ms, Some(5_000));
+    assert_eq!(up.last_probe_ms, Some(4_000));
+    assert_eq!(up.error, None);
+    assert_eq!(up.reconnects, 1);
+}
+
+#[test]
+fn report_serializes_the_state_in_snake_case() {
+    let broker = BrokerStatus::new();
+    broker.probed(&healthy(), 1_000);
+    let json = serde_json::to_value(broker.snapshot()).expect("json");
+    assert_eq!(json["state"], "up");
+    assert_eq!(json["brokers"], 3);
+    assert_eq!(json["last_probe_latency_ms"], 12);
+}
+
+#[test]
+fn only_refused_payloads_count_as_payload_errors() {
+    assert!(PublishError::Oversized {
+        bytes: 2_000_000,
+        max: 1_000_000
+    }
+    .is_payload_error());
+    assert!(PublishError::Encode("missing field".to_string()).is_payload_error());
+    assert!(!PublishError::Probe("timed out".to_string()).is_payload_error());
+    assert!(!PublishError::Transaction("fenced".to_string()).is_payload_error());
+}
+
+#[tokio::test]
+async fn mock_publisher_probes_healthy() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "sunspec.telemetry");
+    let health = publisher.probe().await.expect("probe");
+    assert_eq!(health.brokers, 0);
+}
@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use collector_app::capture::{
+    self, rotated_path, CaptureFile, CaptureRecord, CaptureRegistry, CaptureSettings, CAPTURE_FILE,
+};
+use collector_app::supervisor::PollerRegistry;
+use modbus_client::Exchange;
+use poller_actor::PollerStatus;
+use reqwest::{Client, StatusCode};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+fn temp_dir(prefix: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    let pid = std::process::id();
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    path.push(format!("{prefix}-{pid}-{ts}"));
+    path
+}
+
+fn exchange(transaction_id: u16) -> Exchange {
+    Exchange {
+        at_ms: 5_000,
+        host: "10.0.0.7".to_string(),
+        port: 502,
+        unit_id: 3,
+        transaction_id,
+        function: 3,
+        start: 40_000,
+        count: 1,
+        request: vec![0, 1, 0, 0, 0, 6, 3, 3, 0x9c, 0x40, 0, 1],
+        response: Some(vec![0, 1, 0, 0, 0, 3, 3, 0x83, 2]),
+        exception: Some(2),
+        error: Some("Modbus function 3: Illegal data address".to_string()),
+        latency_us: 1_250,
+    }
+}
+
+#[test]
+fn records_show_frames_as_hex() {
+    let record = CaptureRecord::from(&exchange(1));
+    assert_eq!(record.device, "10.0.0.7:3");
+    assert_eq!(record.request, "00 01 00 00 00 06 03 03 9c 40 00 01");
+    assert_eq!(
+        record.response.as_deref(),
+        Some("00 01 00 00 00 03 03 83 02")
+    );
+    assert_eq!(record.exception, Some(2));
+}
+
+#[test]
+fn taps_are_switched_per_device() {
+    let (captures, _rx) = CaptureRegistry::new();
+    assert!(!captures.set_enabled("10.0.0.7:3", true));
+    assert_eq!(captures.is_enabled("10.0.0.7:3"), None);
+
+    let tap = captures.tap("10.0.0.7:3");
+    let other = captures.tap("10.0.0.8:1");
+    assert!(captures.set_enabled("10.0.0.7:3", true));
+    assert!(tap.is_enabled());
+    assert!(!other.is_enabled());
+    // A restarted poller gets the same, still enabled, tap.
+    assert!(captures.tap("10.0.0.7:3").is_enabled());
+
+    let registry = PollerRegistry::new().with_captures(captures.clone());
+    let (_status_tx, status_rx) = watch::channel(PollerStatus::default());
+    registry.register("10.0.0.7:3", status_rx.clone());
+    registry.register("10.0.0.8:1", status_rx);
+    let reports = registry.snapshot(0, Duration::from_secs(60));
+    let capturing: Vec<bool> = reports.iter().map(|report| report.capturing).collect();
+    assert_eq!(capturing, vec![true, false]);
+}
+
+#[tokio::test]
+async fn admin_api_switches_capture() {
+    let (captures, _rx) = CaptureRegistry::new();
+    let tap = captures.tap("10.0.0.7:3");
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let app = capture::router(captures);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    let client = Client::new();
+    let url = |id: &str| format!("http://{addr}/api/devices/{id}/capture");
+
+    let response = client.put(url("10.0.0.9:1")).send().await.expect("put");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = client.put(url("10.0.0.7:3")).send().await.expect("put");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(tap.is_enabled());
+    let body = client
+        .get(url("10.0.0.7:3"))
+        .send()
+        .await
+        .expect("get")
+        .text()
+        .await
+        .expect("body");
+    let state: serde_json::Value = serde_json::from_str(&body).expect("json");
+    assert_eq!(state["enabled"], true);
+
+    let response = client
+        .delete(url("10.0.0.7:3"))
+        .send()
+        .await
+        .expect("delete");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(!tap.is_enabled());
+}
+
+#[test]
+fn capture_files_are_rotated() {
+    let dir = temp_dir("capture_rotation");
+    let line_bytes = serde_json::to_vec(&CaptureRecord::from(&exchange(0)))
+        .expect("json")
+        .len() as u64
+        + 1;
+    let mut file = CaptureFile::new(&CaptureSettings {
+        dir: dir.to_string_lossy().into_owned(),
+        max_file_bytes: 2 * line_bytes,
+        max_files: 2,
+    });
+    for transaction_id in 0..7 {
+        file.write(&CaptureRecord::from(&exchange(transaction_id)))
+            .expect("write");
+    }
+    file.flush().expect("flush");
+
+    let ids = |path: PathBuf| -> Vec<u64> {
+        fs::read_to_string(path)
+            .expect("read")
+            .lines()
+            .map(|line| {
+                let record: serde_json::Value = serde_json::from_str(line).expect("record");
+                record["transaction_id"].as_u64().expect("id")
+            })
+            .collect()
+    };
+    assert_eq!(ids(dir.join(CAPTURE_FILE)), vec![6]);
+    assert_eq!(ids(rotated_path(&dir, 1)), vec![4, 5]);
+    assert_eq!(ids(rotated_path(&dir, 2)), vec![2, 3]);
+    assert!(!rotated_path(&dir, 3).exists());
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn captured_exchanges_are_written() {
+    let dir = temp_dir("capture_run");
+    let (captures, rx) = CaptureRegistry::new();
+    let tap = captures.tap("10.0.0.7:3");
+    assert!(tap.record(exchange(1)));
+    assert!(tap.record(exchange(2)));
+    drop((captures, tap));
+
+    let file = CaptureFile::new(&CaptureSettings {
+        dir: dir.to_string_lossy().into_owned(),
+        ..CaptureSettings::default()
+    });
+    timeout(Duration::from_secs(5), capture::run(file, rx))
+        .await
+        .expect("writer finished");
+
+    let written = fs::read_to_string(dir.join(CAPTURE_FILE)).expect("read");
+    assert_eq!(written.lines().count(), 2);
+    assert!(written.contains(r#""device":"10.0.0.7:3""#));
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use collector_app::clock::{ClockSettings, DeviceClock};
+use poller_actor::{clock_drift_ms, next_aligned_ms, ClockEpoch};
+use sunspec_parser::ModelDefinition;
+use types::DeviceIdentity;
+
+fn device(ip: &str, unit_id: u8) -> DeviceIdentity {
+    DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id,
+        base_address: Some(40_000),
+    }
+}
+
+fn model(id: u16, start: u16, length: u16) -> ModelDefinition {
+    ModelDefinition {
+        id,
+        name: format!("model_{id}"),
+        start,
+        length,
+    }
+}
+
+fn settings(devices: Vec<DeviceClock>) -> ClockSettings {
+    ClockSettings {
+        interval_ms: 60_000,
+        set_threshold_ms: 5_000,
+        writes_enabled: true,
+        devices,
+    }
+}
+
+#[test]
+fn clock_epochs_round_trip_register_pairs() {
+    // 2026-10-16 00:00:00 UTC.
+    let unix_ms = 1_792_108_800_000;
+    let registers = ClockEpoch::Sunspec.encode(unix_ms + 999).expect("in range");
+    assert_eq!(ClockEpoch::Sunspec.decode(&registers), Some(unix_ms));
+    let registers = ClockEpoch::Unix.encode(unix_ms).expect("in range");
+    assert_eq!(
+        registers,
+        [(1_792_108_800u32 >> 16) as u16, 1_792_108_800u32 as u16]
+    );
+    assert_eq!(ClockEpoch::Unix.decode(&registers), Some(unix_ms));
+
+    assert_eq!(ClockEpoch::Sunspec.decode(&[0xFFFF, 0xFFFF]), None);
+    assert_eq!(ClockEpoch::Sunspec.decode(&[1]), None);
+    assert_eq!(ClockEpoch::Sunspec.encode(1_000), None);
+
+    assert_eq!(clock_drift_ms(10_000, 12_500), -2_500);
+    assert_eq!(clock_drift_ms(12_500, 10_000), 2_500);
+}
+
+#[test]
+fn clock_defaults_to_read_only_tms_of_model_122() {
+    let models = vec![model(1, 40_002, 68), model(122, 40_070, 46)];
+    let clock = settings(Vec::new())
+        .resolve(&device("10.0.0.2", 1), &models)
+        .expect("model 122 clock");
+    assert_eq!(clock.address, 40_111);
+    assert_eq!(clock.epoch, ClockEpoch::Sunspec);
+    assert_eq!(clock.interval, Duration::from_secs(60));
+    assert_eq!(clock.set_threshold, None);
+
+    assert!(settings(Vec::new())
+        .resolve(&device("10.0.0.2", 1), &models[..1])
+        .is_none());
+}
+
+#[test]
+fn configured_clock_is_set_only_when_writes_are_enabled() {
+    let configured = DeviceClock {
+        ip: "10.0.0.2".to_string(),
+        unit_id: Some(3),
+        address: 41_000,
+        epoch: ClockEpoch::Unix,
+        writable: true,
+    };
+    let mut settings = settings(vec![configured]);
+
+    let clock = settings
+        .resolve(&device("10.0.0.2", 3), &[])
+        .expect("configured clock");
+    assert_eq!(clock.address, 41_000);
+    assert_eq!(clock.epoch, ClockEpoch::Unix);
+    assert_eq!(clock.set_threshold, Some(Duration::from_secs(5)));
+    assert!(settings.resolve(&device("10.0.0.2", 1), &[]).is_none());
+
+    settings.writes_enabled = false;
+    let clock = settings
+        .resolve(&device("10.0.0.2", 3), &[])
+        .expect("configured clock");
+    assert_eq!(clock.set_threshold, None);
+
+    settings.writes_enabled = true;
+    settings.set_threshold_ms = 0;
+    let clock = settings
+        .resolve(&device("10.0.0.2", 3), &[])
+        .expect("configured clock");
+    assert_eq!(clock.set_threshold, None);
+}
+
+#[test]
+fn aligned_cycles_start_on_interval_multiples() {
+    assert_eq!(next_aligned_ms(1_792_108_803_250, 5_000), 1_792_108_805_000);
+    assert_eq!(next_aligned_ms(1_792_108_805_000, 5_000), 1_792_108_810_000);
+    assert_eq!(
+        next_aligned_ms(1_792_108_859_999, 60_000),
+        1_792_108_860_000
+    );
+    assert_eq!(next_aligned_ms(1_250, 1_000), 2_000);
+}
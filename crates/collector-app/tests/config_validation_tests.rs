@@ -2,7 +2,14 @@ use std::env;
 use std::path::PathBuf;
 use std::sync::Mutex;
 
+use avro_kafka::{AvroCodec, Encoding, KafkaSecurity};
+use collector_app::alerts::AlertCondition;
+use collector_app::model_map::PointType;
+use collector_app::simulator::SyntheticDevice;
+use collector_app::timesync::TimeSyncCheck;
+use collector_app::uplink::DrainOrder;
 use collector_app::CollectorConfig;
+use types::SiteInfo;
 
 static ENV_LOCK: Mutex<()> = Mutex::new(());
 
@@ -13,6 +20,13 @@ fn toml_config_validates() {
 
     let config = CollectorConfig::load().expect("load config");
     config.validate().expect("validate config");
+    assert_eq!(config.timesync.check(), Ok(TimeSyncCheck::Timedatectl));
+    assert_eq!(config.timesync.interval_ms, 300_000);
+    assert!(config.timesync.monotonic_timestamps);
+    assert_eq!(config.resources.interval_ms, 60_000);
+    assert_eq!(config.resources.min_free_disk_bytes, 100 * 1024 * 1024);
+    assert_eq!(config.resources.max_buffer_bytes, 256 * 1024 * 1024);
+    assert_eq!(config.resources.throttle_factor, 4);
 
     env::remove_var("SUNSPEC_CONFIG");
 }
@@ -39,6 +53,921 @@ fn invalid_config_fails_validation() {
     env::remove_var("SUNSPEC_CONFIG");
 }
 
+#[test]
+fn device_rules_reference_known_profiles() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.device_rules.len(), 2);
+    assert_eq!(config.device_profiles.len(), 2);
+    config.device_rules[0].profile = "missing".to_string();
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn scan_classes_name_known_points() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+
+    let mut config = CollectorConfig::load().expect("load config");
+    let sma = config
+        .device_profiles
+        .iter_mut()
+        .find(|profile| profile.name == "sma")
+        .expect("sma profile");
+    assert_eq!(sma.scan_classes[0].points, vec!["W", "Hz", "St"]);
+    sma.scan_classes[0].points.push("Nope".to_string());
+    let err = config.validate().expect_err("unknown point");
+    assert_eq!(
+        err.to_string(),
+        "profiles.sma: scan class: model 103 has no point Nope"
+    );
+
+    let mut config = CollectorConfig::load().expect("load config");
+    let sma = config
+        .device_profiles
+        .iter_mut()
+        .find(|profile| profile.name == "sma")
+        .expect("sma profile");
+    sma.scan_classes[0].full_interval_ms = 0;
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn alert_rules_need_one_condition() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert!(config.alerts_enabled);
+    assert_eq!(config.alerts_topic, "sunspec.alerts");
+    let conditions: Vec<AlertCondition> = config
+        .alert_rules
+        .iter()
+        .map(|rule| rule.condition)
+        .collect();
+    assert_eq!(
+        conditions,
+        vec![
+            AlertCondition::Above(950.0),
+            AlertCondition::RisingPerMin(2.0)
+        ]
+    );
+    assert_eq!(config.alert_rules[1].model_id, Some(103));
+    config.alert_rules[1].name = "dc_overvoltage".to_string();
+    let err = config.validate().expect_err("duplicate rule");
+    assert_eq!(err.to_string(), "alerts.rules lists dc_overvoltage twice");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    config.alert_rules[0].deadband = -1.0;
+    assert!(config.validate().is_err());
+
+    let path = env::temp_dir().join(format!("sunspec_alerts_{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+[[alerts.rules]]
+name = "dc"
+point = "DCV"
+above = 950.0
+below = 100.0
+"#,
+    )
+    .expect("write config");
+    env::set_var("SUNSPEC_CONFIG", &path);
+    let err = CollectorConfig::load().expect_err("two conditions");
+    assert!(format!("{err:#}").contains("needs exactly one of above, below"));
+    std::fs::remove_file(&path).ok();
+
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn model_filters_load_ids_and_ranges() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+
+    let config = CollectorConfig::load().expect("load config");
+    assert!(!config.model_filter.allows(64_110));
+    assert!(config.model_filter.allows(1));
+    let device = &config.device_model_filters[0];
+    assert_eq!(device.unit_id, Some(1));
+    assert!(!device.filter.allows(1));
+    assert!(!device.filter.allows(161));
+    assert!(device.filter.allows(103));
+
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn maintenance_schedules_must_parse() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.maintenance_windows.len(), 1);
+    assert!(config.maintenance_windows[0].pause_polling);
+    assert_eq!(config.maintenance_utc_offset_minutes, 60);
+    config.maintenance_windows[0].schedule = "0 25 * * *".to_string();
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn simulator_models_must_be_supported() {
+    let mut config = CollectorConfig {
+        simulator_models: vec![103, 203],
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.simulator_models.push(64_001);
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn synthetic_devices_are_validated() {
+    let device = SyntheticDevice {
+        name: "synthetic-1".to_string(),
+        unit_id: 1,
+        models: vec![103],
+        rated_w: 5_000.0,
+    };
+    let mut config = CollectorConfig {
+        synthetic_devices: vec![device.clone()],
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+
+    config.synthetic_devices.push(device.clone());
+    assert!(config.validate().is_err());
+    config.synthetic_devices[1].unit_id = 2;
+    assert!(config.validate().is_ok());
+    config.synthetic_devices[1].rated_w = 0.0;
+    assert!(config.validate().is_err());
+    config.synthetic_devices[1].rated_w = 5_000.0;
+    config.synthetic_devices[1].name = " ".to_string();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn kafka_encoding_is_validated() {
+    let mut config = CollectorConfig {
+        kafka_encoding: Some("JSON".to_string()),
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    assert_eq!(config.encoding(), Encoding::Json);
+
+    config.kafka_schema_topic = "bad topic".to_string();
+    assert!(config.validate().is_err());
+    config.kafka_encoding = Some("avro".to_string());
+    // The schema topic is unused with Avro.
+    assert!(config.validate().is_ok());
+
+    config.kafka_encoding = Some("xml".to_string());
+    assert!(config.validate().is_err());
+    config.kafka_encoding = Some("avro_single".to_string());
+    assert!(config.validate().is_ok());
+    assert_eq!(config.encoding(), Encoding::AvroSingle);
+}
+
+#[test]
+fn avro_codec_is_validated() {
+    let mut config = CollectorConfig::default();
+    assert_eq!(config.avro_codec(), AvroCodec::Deflate);
+
+    config.kafka_avro_codec = Some("ZSTD".to_string());
+    assert!(config.validate().is_ok());
+    assert_eq!(config.avro_codec(), AvroCodec::Zstd);
+
+    config.kafka_avro_codec = Some("snappy".to_string());
+    assert!(config
+        .validate()
+        .unwrap_err()
+        .to_string()
+        .contains("kafka.avro_codec"));
+}
+
+#[test]
+fn plant_snapshot_window_must_fit_the_interval() {
+    let mut config = CollectorConfig {
+        plant_snapshot_enabled: true,
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.plant_snapshot_window_ms = config.plant_snapshot_interval_ms;
+    assert!(config.validate().is_err());
+    config.plant_snapshot_window_ms = 0;
+    assert!(config.validate().is_err());
+    config.plant_snapshot_enabled = false;
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn dead_letter_topic_and_message_size_are_checked() {
+    let mut config = CollectorConfig {
+        buffer_dead_letter_topic: Some("sunspec.dead-letter".to_string()),
+        kafka_max_message_bytes: Some(1_000_000),
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.kafka_max_message_bytes = Some(999);
+    assert!(config.validate().is_err());
+    config.kafka_max_message_bytes = None;
+    config.buffer_dead_letter_topic = Some("dead letters".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn transactional_id_needs_idempotent_acks_all() {
+    let mut config = CollectorConfig {
+        kafka_transactional_id: Some("collector-plant-a".to_string()),
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.kafka_acks = Some("1".to_string());
+    assert!(config.validate().is_err());
+    config.kafka_acks = Some("all".to_string());
+    config.kafka_enable_idempotence = Some(false);
+    assert!(config.validate().is_err());
+    config.kafka_enable_idempotence = None;
+    config.kafka_commit_topic = "uplink commits".to_string();
+    assert!(config.validate().is_err());
+    config.kafka_transactional_id = Some(" ".to_string());
+    config.kafka_commit_topic = "sunspec.uplink-commits".to_string();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn boot_order_lists_each_class_once() {
+    let mut config = CollectorConfig {
+        boot_order: vec!["meter".to_string(), "inverter".to_string()],
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.boot_order.push("Meter".to_string());
+    assert!(config.validate().is_err());
+    config.boot_order.pop();
+    config.boot_step_ms = 0;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn aligned_polling_has_no_jitter() {
+    let mut config = CollectorConfig::default();
+    config.poller.align_to_clock = true;
+    assert!(config.validate().is_ok());
+    config.poller.jitter_ms = 100;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn kafka_sasl_needs_a_mechanism_and_credentials() {
+    let sasl = KafkaSecurity {
+        protocol: Some("SASL_SSL".to_string()),
+        sasl_mechanism: Some("SCRAM-SHA-512".to_string()),
+        sasl_username: Some("collector".to_string()),
+        sasl_password: Some("secret".to_string()),
+        ..KafkaSecurity::default()
+    };
+    let mut config = CollectorConfig {
+        kafka_security: sasl.clone(),
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.kafka_security.sasl_password = None;
+    assert!(config.validate().is_err());
+    config.kafka_security = KafkaSecurity {
+        sasl_mechanism: Some("GSSAPI".to_string()),
+        ..sasl.clone()
+    };
+    assert!(config.validate().is_err());
+    config.kafka_security = KafkaSecurity {
+        protocol: Some("ssl".to_string()),
+        ..sasl
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn kafka_ssl_files_need_an_ssl_protocol() {
+    let mut config = CollectorConfig {
+        kafka_security: KafkaSecurity {
+            protocol: Some("ssl".to_string()),
+            ssl_ca_location: Some("/etc/ssl/kafka-ca.pem".to_string()),
+            ssl_certificate_location: Some("/etc/ssl/collector.pem".to_string()),
+            ssl_key_location: Some("/etc/ssl/collector.key".to_string()),
+            ..KafkaSecurity::default()
+        },
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.kafka_security.ssl_key_location = None;
+    assert!(config.validate().is_err());
+    config.kafka_security.ssl_certificate_location = None;
+    assert!(config.validate().is_ok());
+    config.kafka_security.protocol = Some("sasl_plaintext".to_string());
+    assert!(config.validate().is_err());
+    config.kafka_security.protocol = Some("tls".to_string());
+    config.kafka_security.ssl_ca_location = None;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn kafka_routes_load_and_render_valid_topics() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+
+    let config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.kafka_routes.len(), 2);
+    assert_eq!(config.kafka_routes[0].topic, "sunspec.meter.{device_ip}");
+    assert_eq!(config.kafka_routes[1].models.len(), 3);
+
+    let mut config = CollectorConfig {
+        kafka_routes: config.kafka_routes,
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.kafka_routes[0].topic = "sunspec.{serial}".to_string();
+    assert!(config.validate().is_err());
+    config.kafka_routes[0].topic = "sunspec/{model_name}".to_string();
+    assert!(config.validate().is_err());
+    config.kafka_routes[0].topic = "{model_name".to_string();
+    assert!(config.validate().is_err());
+    config.kafka_routes[0].topic = "site.{device_ip}.telemetry".to_string();
+    config.kafka_routes[0].ip = Some("meter-1".to_string());
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn site_loads_from_file_and_env() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+    env::set_var("SUNSPEC_SITE_LABELS", "operator=acme, feeder=3");
+
+    let config = CollectorConfig::load().expect("load config");
+    let site = config.site.clone().expect("site");
+    assert_eq!(site.site_id, "za-wc-017");
+    assert_eq!(site.plant.as_deref(), Some("Darling Solar"));
+    assert_eq!(site.latitude, Some(-33.38));
+    assert_eq!(site.labels.len(), 2);
+    assert_eq!(site.labels["feeder"], "3");
+    assert!(config.validate().is_ok());
+
+    env::remove_var("SUNSPEC_SITE_LABELS");
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn site_needs_an_id_and_valid_coordinates() {
+    let site = SiteInfo {
+        site_id: "za-wc-017".to_string(),
+        latitude: Some(-33.38),
+        longitude: Some(18.38),
+        ..SiteInfo::default()
+    };
+    let mut config = CollectorConfig {
+        site: Some(site.clone()),
+        ..CollectorConfig::default()
+    };
+    assert!(config.validate().is_ok());
+    config.site = Some(SiteInfo {
+        site_id: " ".to_string(),
+        ..site.clone()
+    });
+    assert!(config.validate().is_err());
+    config.site = Some(SiteInfo {
+        longitude: None,
+        ..site.clone()
+    });
+    assert!(config.validate().is_err());
+    config.site = Some(SiteInfo {
+        latitude: Some(133.38),
+        ..site.clone()
+    });
+    assert!(config.validate().is_err());
+    let mut labels = site.labels.clone();
+    labels.insert("feeder".to_string(), String::new());
+    config.site = Some(SiteInfo { labels, ..site });
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn inventory_devices_join_the_static_devices() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+    env::set_var("SUNSPEC_INVENTORY_PATH", fixture_path("inventory.csv"));
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.discovery.static_devices.len(), 3);
+    let meter = config
+        .inventory_device(&config.discovery.static_devices[2])
+        .expect("inventory entry");
+    assert_eq!(meter.alias.as_deref(), Some("Meter, feeder 3"));
+    config.validate().expect("validate config");
+    config.inventory[0].profile = Some("missing".to_string());
+    assert!(config.validate().is_err());
+
+    // A device may not be listed in both places.
+    env::set_var("SUNSPEC_STATIC_DEVICES", "10.20.3.11:2");
+    assert!(CollectorConfig::load().is_err());
+
+    env::remove_var("SUNSPEC_STATIC_DEVICES");
+    env::remove_var("SUNSPEC_INVENTORY_PATH");
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn logging_loads_from_file_and_env() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+    env::set_var("SUNSPEC_LOG_FILTERS", "poller_actor=debug, rdkafka=warn");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.logging.format, "json");
+    assert_eq!(config.logging.level, "info");
+    assert_eq!(config.logging.filters.len(), 2);
+    assert_eq!(config.logging.filters["rdkafka"], "warn");
+    assert!(config.validate().is_ok());
+    config.logging.format = "xml".to_string();
+    assert!(config.validate().is_err());
+    config.logging.format = "text".to_string();
+    config
+        .logging
+        .filters
+        .insert("modbus_client".to_string(), "chatty".to_string());
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_LOG_FILTERS");
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn otel_settings_are_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_OTEL_ENDPOINT", "http://otel-collector:4317");
+    env::set_var("SUNSPEC_OTEL_SAMPLE_RATIO", "0.25");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(
+        config.otel.endpoint.as_deref(),
+        Some("http://otel-collector:4317")
+    );
+    assert_eq!(config.otel.service_name, "sunspec-collector");
+    assert_eq!(config.otel.sample_ratio, 0.25);
+    assert!(config.validate().is_ok());
+    config.otel.endpoint = Some("otel-collector:4317".to_string());
+    assert!(config.validate().is_err());
+    config.otel.endpoint = None;
+    config.otel.sample_ratio = 1.5;
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_OTEL_SAMPLE_RATIO");
+    env::remove_var("SUNSPEC_OTEL_ENDPOINT");
+}
+
+#[test]
+fn durations_accept_milliseconds_and_humantime_strings() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    let path = env::temp_dir().join(format!("sunspec_durations_{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+shutdown_timeout = "15s"
+
+[poller]
+poll_interval = "5m"
+request_timeout_ms = "1s 500ms"
+jitter_ms = 250
+
+[modbus]
+timeout = "2s"
+
+[buffer]
+drain_interval_ms = "500ms"
+
+[[aggregation.models]]
+model_id = 103
+window = "1m"
+"#,
+    )
+    .expect("write config");
+    env::set_var("SUNSPEC_CONFIG", &path);
+    env::set_var("SUNSPEC_STALL_TIMEOUT_MS", "90s");
+    env::set_var("SUNSPEC_HEARTBEAT_INTERVAL_MS", "30000");
+
+    let config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.shutdown_timeout_ms, 15_000);
+    assert_eq!(config.poller.poll_interval.as_millis(), 300_000);
+    assert_eq!(config.poller.request_timeout.as_millis(), 1_500);
+    assert_eq!(config.poller.jitter_ms, 250);
+    assert_eq!(config.modbus.timeout_ms, 2_000);
+    assert_eq!(config.buffer_drain_interval_ms, 500);
+    assert_eq!(config.aggregation_windows[0].window_ms, 60_000);
+    assert_eq!(config.stall_timeout_ms, 90_000);
+    assert_eq!(config.heartbeat_interval_ms, 30_000);
+
+    env::remove_var("SUNSPEC_STALL_TIMEOUT_MS");
+    env::remove_var("SUNSPEC_HEARTBEAT_INTERVAL_MS");
+    for bad in ["\"soon\"", "\"1500us\""] {
+        std::fs::write(&path, format!("[poller]\npoll_interval = {bad}\n")).expect("write config");
+        assert!(CollectorConfig::load().is_err(), "{bad} should not parse");
+    }
+    env::remove_var("SUNSPEC_CONFIG");
+    std::fs::remove_file(&path).expect("remove config");
+}
+
+#[test]
+fn respawn_backoff_loads_from_file_and_env() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    let path = env::temp_dir().join(format!("sunspec_respawn_{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        "[poller]\nrespawn_delay = \"2s\"\nrespawn_max_delay = \"5m\"\ncircuit_breaker_failures = 4\n",
+    )
+    .expect("write config");
+    env::set_var("SUNSPEC_CONFIG", &path);
+    env::set_var("SUNSPEC_CIRCUIT_BREAKER_FAILURES", "0");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    let policy = config.restart_policy();
+    assert_eq!(policy.initial_delay_ms, 2_000);
+    assert_eq!(policy.max_delay_ms, 300_000);
+    assert_eq!(policy.breaker_failures, 0);
+    assert!(config.validate().is_ok());
+    config.respawn_max_delay_ms = 1_000;
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_CIRCUIT_BREAKER_FAILURES");
+    env::remove_var("SUNSPEC_CONFIG");
+    std::fs::remove_file(&path).expect("remove config");
+}
+
+#[test]
+fn model_maps_load_and_join_the_static_devices() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    let path = env::temp_dir().join(format!("sunspec_model_maps_{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+[[model_maps]]
+ip = "10.0.0.9"
+
+[[model_maps.models]]
+id = 64900
+start = 1000
+length = 4
+points = [
+  { name = "power_w", offset = 0, type = "int32", scale = -1 },
+  { name = "energy_wh", offset = 2, type = "acc32" },
+]
+"#,
+    )
+    .expect("write config");
+    env::set_var("SUNSPEC_CONFIG", &path);
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert!(config.validate().is_ok());
+    let map = &config.model_maps[0];
+    assert_eq!(map.unit_id, 1);
+    assert_eq!(map.models[0].name, "model_64900");
+    assert_eq!(map.models[0].points[1].point_type, PointType::Uint32);
+    assert_eq!(map.models[0].points[1].scale, 0);
+    assert!(config
+        .discovery
+        .static_devices
+        .iter()
+        .any(|device| map.matches(device)));
+
+    let duplicate = config.model_maps[0].clone();
+    config.model_maps.push(duplicate);
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_CONFIG");
+    std::fs::remove_file(&path).expect("remove config");
+}
+
+#[test]
+fn static_devices_accept_host_names() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_STATIC_DEVICES", "gateway-3.local:2,192.168.1.20");
+    env::set_var("SUNSPEC_MODBUS_DNS_TTL_MS", "30s");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.discovery.static_devices[0].ip, "gateway-3.local");
+    assert_eq!(config.discovery.static_devices[0].unit_id, 2);
+    assert_eq!(config.modbus.dns_ttl_ms, 30_000);
+    assert!(config.validate().is_ok());
+    config.discovery.static_devices[1].ip = "gateway 3".to_string();
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_MODBUS_DNS_TTL_MS");
+    env::remove_var("SUNSPEC_STATIC_DEVICES");
+}
+
+#[test]
+fn buffer_compaction_interval_is_checked() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_BUFFER_COMPACT_INTERVAL_MS", "6h");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.buffer_compact_interval_ms, 21_600_000);
+    assert!(config.validate().is_ok());
+    config.buffer_compact_interval_ms = 0;
+    assert!(config.validate().is_ok());
+    config.buffer_compact_interval_ms = 5_000;
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_BUFFER_COMPACT_INTERVAL_MS");
+}
+
+#[test]
+fn write_through_excludes_transactions() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_BUFFER_WRITE_THROUGH", "true");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert!(config.buffer_write_through);
+    assert!(config.validate().is_ok());
+    config.kafka_enable_idempotence = Some(true);
+    config.kafka_acks = Some("all".to_string());
+    config.kafka_transactional_id = Some("collector-a".to_string());
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_BUFFER_WRITE_THROUGH");
+}
+
+#[test]
+fn grpc_sink_settings_are_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_GRPC_SINK_ENDPOINT", "http://ingest.local:50051");
+    env::set_var("SUNSPEC_GRPC_SINK_RETRY_MAX_BACKOFF_MS", "1m");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(
+        config.grpc_sink.endpoint.as_deref(),
+        Some("http://ingest.local:50051")
+    );
+    assert_eq!(config.grpc_sink.retry_max_backoff_ms, 60_000);
+    assert!(config.validate().is_ok());
+    config.grpc_sink.endpoint = Some("ingest.local:50051".to_string());
+    assert!(config.validate().is_err());
+    config.grpc_sink.endpoint = None;
+    config.grpc_sink.batch_size = 0;
+    assert!(config.validate().is_err());
+    config.grpc_sink.batch_size = 100;
+    config.grpc_sink.retry_max_backoff_ms = config.grpc_sink.retry_backoff_ms - 1;
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_GRPC_SINK_RETRY_MAX_BACKOFF_MS");
+    env::remove_var("SUNSPEC_GRPC_SINK_ENDPOINT");
+}
+
+#[test]
+fn influx_settings_are_loaded_and_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_INFLUX_URL", "http://influx.local:8086");
+    env::set_var("SUNSPEC_INFLUX_BUCKET", "telemetry");
+    env::set_var("SUNSPEC_INFLUX_TAGS", "device=ip, feeder=label:feeder");
+    env::set_var("SUNSPEC_INFLUX_FLUSH_INTERVAL_MS", "5s");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.influx.bucket.as_deref(), Some("telemetry"));
+    assert_eq!(config.influx.tags["feeder"], "label:feeder");
+    assert_eq!(config.influx.flush_interval_ms, 5_000);
+    assert!(config.validate().is_ok());
+    config
+        .influx
+        .tags
+        .insert("serial".to_string(), "serial_number".to_string());
+    assert!(config.validate().is_err());
+    config.influx.tags.remove("serial");
+    config.influx.bucket = None;
+    assert!(config.validate().is_err());
+    config.influx.url = None;
+    config.influx.batch_size = 0;
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_INFLUX_FLUSH_INTERVAL_MS");
+    env::remove_var("SUNSPEC_INFLUX_TAGS");
+    env::remove_var("SUNSPEC_INFLUX_BUCKET");
+    env::remove_var("SUNSPEC_INFLUX_URL");
+}
+
+#[test]
+fn postgres_settings_are_loaded_and_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var(
+        "SUNSPEC_POSTGRES_DSN",
+        "postgres://collector@db.local/telemetry",
+    );
+    env::set_var("SUNSPEC_POSTGRES_ON_CONFLICT", "update");
+    env::set_var("SUNSPEC_POSTGRES_CREATE_TABLE", "false");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.postgres.on_conflict, "update");
+    assert!(!config.postgres.create_table);
+    assert!(config.validate().is_ok());
+    config.postgres.buffer_path = config.buffer_path.clone();
+    assert!(config.validate().is_err());
+    config.postgres.buffer_path = "postgres-queue.sqlite".to_string();
+    config.postgres.batch_size = 10_000;
+    assert!(config.validate().is_err());
+    config.postgres.batch_size = 500;
+    config.postgres.table = "samples;".to_string();
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_POSTGRES_CREATE_TABLE");
+    env::remove_var("SUNSPEC_POSTGRES_ON_CONFLICT");
+    env::remove_var("SUNSPEC_POSTGRES_DSN");
+}
+
+#[test]
+fn capture_settings_are_loaded_and_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CAPTURE_DIR", "/var/lib/sunspec/captures");
+    env::set_var("SUNSPEC_CAPTURE_MAX_FILE_BYTES", "1048576");
+    env::set_var("SUNSPEC_CAPTURE_MAX_FILES", "0");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.capture.dir, "/var/lib/sunspec/captures");
+    assert_eq!(config.capture.max_file_bytes, 1_048_576);
+    assert_eq!(config.capture.max_files, 0);
+    assert!(config.validate().is_ok());
+    config.capture.max_file_bytes = 0;
+    assert!(config.validate().is_err());
+    config.capture.max_file_bytes = 1_048_576;
+    config.capture.dir = " ".to_string();
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_CAPTURE_MAX_FILES");
+    env::remove_var("SUNSPEC_CAPTURE_MAX_FILE_BYTES");
+    env::remove_var("SUNSPEC_CAPTURE_DIR");
+}
+
+#[test]
+fn leader_settings_are_loaded_and_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_INSTANCE_ID", "edge-b");
+    env::set_var("SUNSPEC_LEADER_ENABLED", "true");
+    env::set_var("SUNSPEC_LEADER_LEASE_PATH", "/mnt/shared/sunspec.lease");
+    env::set_var("SUNSPEC_LEADER_LEASE_MS", "9000");
+    env::set_var("SUNSPEC_LEADER_RENEW_INTERVAL_MS", "3000");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.instance_id, "edge-b");
+    assert!(config.leader.enabled);
+    assert_eq!(config.leader.lease_path, "/mnt/shared/sunspec.lease");
+    assert_eq!(config.leader.lease_ms, 9_000);
+    assert_eq!(config.leader.renew_interval_ms, 3_000);
+    assert!(config.validate().is_ok());
+    config.leader.lease_ms = 5_000;
+    assert!(config.validate().is_err());
+    config.leader.lease_ms = 9_000;
+    config.instance_id = " ".to_string();
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_LEADER_RENEW_INTERVAL_MS");
+    env::remove_var("SUNSPEC_LEADER_LEASE_MS");
+    env::remove_var("SUNSPEC_LEADER_LEASE_PATH");
+    env::remove_var("SUNSPEC_LEADER_ENABLED");
+    env::remove_var("SUNSPEC_INSTANCE_ID");
+}
+
+#[test]
+fn timesync_settings_are_loaded_and_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_TIMESYNC_CHECK", "chrony");
+    env::set_var("SUNSPEC_TIMESYNC_INTERVAL_MS", "30000");
+    env::set_var("SUNSPEC_TIMESYNC_MAX_OFFSET_MS", "250");
+    env::set_var("SUNSPEC_TIMESYNC_MONOTONIC_TIMESTAMPS", "true");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.timesync.check(), Ok(TimeSyncCheck::Chrony));
+    assert_eq!(config.timesync.interval_ms, 30_000);
+    assert_eq!(config.timesync.max_offset_ms, 250);
+    assert!(config.timesync.monotonic_timestamps);
+    assert!(config.timesync.sample_clock().is_anchored());
+    assert!(config.validate().is_ok());
+    config.timesync.interval_ms = 0;
+    assert!(config.validate().is_err());
+    config.timesync.interval_ms = 30_000;
+    config.timesync.check = "ntpd".to_string();
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_TIMESYNC_MONOTONIC_TIMESTAMPS");
+    env::remove_var("SUNSPEC_TIMESYNC_MAX_OFFSET_MS");
+    env::remove_var("SUNSPEC_TIMESYNC_INTERVAL_MS");
+    env::remove_var("SUNSPEC_TIMESYNC_CHECK");
+}
+
+#[test]
+fn resource_limits_are_loaded_and_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_RESOURCES_MAX_RSS_BYTES", "134217728");
+    env::set_var("SUNSPEC_RESOURCES_MAX_CHANNEL_FILL_PCT", "80");
+    env::set_var("SUNSPEC_RESOURCES_EVICT_ROWS", "500");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.resources.max_rss_bytes, 128 * 1024 * 1024);
+    assert_eq!(config.resources.max_channel_fill_pct, 80);
+    assert_eq!(config.resources.evict_rows, 500);
+    // Limits that are not set stay off.
+    assert_eq!(config.resources.min_free_disk_bytes, 0);
+    assert!(config.validate().is_ok());
+    config.resources.max_channel_fill_pct = 101;
+    assert!(config.validate().is_err());
+    config.resources.max_channel_fill_pct = 80;
+    config.resources.throttle_factor = 0;
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_RESOURCES_EVICT_ROWS");
+    env::remove_var("SUNSPEC_RESOURCES_MAX_CHANNEL_FILL_PCT");
+    env::remove_var("SUNSPEC_RESOURCES_MAX_RSS_BYTES");
+}
+
+#[test]
+fn kafka_namespace_prefixes_every_topic() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_CONFIG", fixture_path("config-valid.toml"));
+    env::set_var("SUNSPEC_KAFKA_NAMESPACE", "acme-site17");
+    env::set_var("SUNSPEC_BUFFER_DEAD_LETTER_TOPIC", "sunspec.dead");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(
+        config.kafka_topic.as_deref(),
+        Some("acme-site17.sunspec.telemetry")
+    );
+    assert_eq!(config.alarms_topic, "acme-site17.sunspec.alarms");
+    assert_eq!(config.kafka_schema_topic, "acme-site17.sunspec.schemas");
+    assert_eq!(
+        config.buffer_dead_letter_topic.as_deref(),
+        Some("acme-site17.sunspec.dead")
+    );
+    assert!(config
+        .kafka_routes
+        .iter()
+        .all(|route| route.topic.starts_with("acme-site17.")));
+    assert_eq!(config.namespaced_topic("drill"), "acme-site17.drill");
+    assert!(config.validate().is_ok());
+    config.kafka_namespace = Some("acme.site17".to_string());
+    assert!(config.validate().is_err());
+    config.kafka_namespace = Some("-acme".to_string());
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_BUFFER_DEAD_LETTER_TOPIC");
+    env::remove_var("SUNSPEC_KAFKA_NAMESPACE");
+    env::remove_var("SUNSPEC_CONFIG");
+}
+
+#[test]
+fn drain_order_settings_are_loaded_and_validated() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    env::set_var("SUNSPEC_BUFFER_DRAIN_ORDER", "live-priority");
+    env::set_var("SUNSPEC_BUFFER_LIVE_WINDOW_MS", "60000");
+    env::set_var("SUNSPEC_BUFFER_BACKLOG_SHARE_PCT", "10");
+
+    let mut config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.drain_order(), DrainOrder::LivePriority);
+    assert_eq!(config.buffer_live_window_ms, 60_000);
+    assert_eq!(config.buffer_backlog_share_pct, 10);
+    assert!(config.validate().is_ok());
+    config.buffer_backlog_share_pct = 100;
+    assert!(config.validate().is_err());
+    config.buffer_backlog_share_pct = 10;
+    config.buffer_live_window_ms = 0;
+    assert!(config.validate().is_err());
+    config.buffer_drain_order = Some("newest_first".to_string());
+    // The live window only matters to live_priority.
+    assert!(config.validate().is_ok());
+    config.buffer_drain_order = Some("random".to_string());
+    assert!(config.validate().is_err());
+
+    env::remove_var("SUNSPEC_BUFFER_BACKLOG_SHARE_PCT");
+    env::remove_var("SUNSPEC_BUFFER_LIVE_WINDOW_MS");
+    env::remove_var("SUNSPEC_BUFFER_DRAIN_ORDER");
+    let config = CollectorConfig::load().expect("load config");
+    assert_eq!(config.drain_order(), DrainOrder::OldestFirst);
+}
+
+#[test]
+fn start_from_inventory_is_loaded() {
+    let _guard = ENV_LOCK.lock().expect("env lock");
+    assert!(!CollectorConfig::default().buffer_start_from_inventory);
+    env::set_var("SUNSPEC_BUFFER_START_FROM_INVENTORY", "true");
+    let config = CollectorConfig::load().expect("load config");
+    assert!(config.buffer_start_from_inventory);
+    env::remove_var("SUNSPEC_BUFFER_START_FROM_INVENTORY");
+}
+
 fn fixture_path(name: &str) -> String {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("tests");
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use collector_app::control::{self, ControlError, PollerControl};
+use collector_app::simulator::Simulator;
+use modbus_client::ClientConfig;
+use poller_actor::{ActorConfig, CommandError, PollerActor, PollerState, PollerStatus};
+use reqwest::{Client, StatusCode};
+use sunspec_parser::parse_models_from_registers;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use types::DeviceIdentity;
+
+const BASE_ADDRESS: u16 = 40_000;
+const ID: &str = "127.0.0.1:1";
+
+/// A simulated inverter polled once a minute, controlled through `control`.
+struct Poller {
+    status: watch::Receiver<PollerStatus>,
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+    server: JoinHandle<()>,
+}
+
+async fn start(control: &PollerControl) -> Poller {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1], &[103]).expect("simulator");
+    let registers = simulator.registers(1, 0).expect("unit 1");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let server = tokio::spawn(async move {
+        let _ = simulator.serve(listener).await;
+    });
+
+    let (tx, mut rx) = mpsc::channel(16);
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let (commands, paused) = control.attach(ID);
+    let actor = PollerActor::new(
+        DeviceIdentity {
+            ip: "127.0.0.1".to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        ClientConfig {
+            port,
+            ..ClientConfig::default()
+        },
+        models,
+        tx,
+        shutdown_rx,
+        ActorConfig {
+            poll_interval: Duration::from_secs(60),
+            ..ActorConfig::default()
+        },
+    )
+    .with_commands(commands, paused);
+    let status = actor.status();
+    let handle = tokio::spawn(async move {
+        actor.run().await.expect("poller");
+    });
+    // Samples of the cycles are not looked at.
+    tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    Poller {
+        status,
+        shutdown,
+        handle,
+        server,
+    }
+}
+
+impl Poller {
+    async fn wait_for(&mut self, state: PollerState) {
+        timeout(
+            Duration::from_secs(5),
+            self.status.wait_for(|status| status.state == state),
+        )
+        .await
+        .expect("state reached")
+        .expect("status");
+    }
+
+    async fn stop(self) {
+        self.shutdown.send(true).expect("shutdown");
+        self.handle.await.expect("join");
+        self.server.abort();
+    }
+}
+
+#[tokio::test]
+async fn reads_are_served_between_cycles() {
+    let control = PollerControl::new();
+    let mut poller = start(&control).await;
+    poller.wait_for(PollerState::Running).await;
+
+    let sample = control.read(ID, 103).await.expect("read");
+    assert_eq!(sample.model_id, 103);
+    // Model 103 with its ID and length registers.
+    assert_eq!(sample.registers.len(), 52);
+    assert_eq!(sample.sequence, 0);
+
+    let err = control.read(ID, 160).await.expect_err("not polled");
+    assert!(matches!(
+        err,
+        ControlError::Command(CommandError::UnknownModel(160))
+    ));
+    assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    assert!(matches!(
+        control.read("10.0.0.9:1", 103).await,
+        Err(ControlError::UnknownPoller(_))
+    ));
+    poller.stop().await;
+}
+
+#[tokio::test]
+async fn pauses_hold_until_resumed_and_survive_restarts() {
+    let control = PollerControl::new();
+    let mut poller = start(&control).await;
+    poller.wait_for(PollerState::Running).await;
+
+    control.set_paused(ID, true).expect("pause");
+    poller.wait_for(PollerState::Paused).await;
+    assert!(poller.status.borrow().paused_by_command);
+    let err = control.read(ID, 103).await.expect_err("paused");
+    assert_eq!(err.status(), StatusCode::CONFLICT);
+    poller.stop().await;
+
+    // The next actor of the device starts paused.
+    let mut poller = start(&control).await;
+    poller.wait_for(PollerState::Paused).await;
+    assert_eq!(control.is_paused(ID), Some(true));
+
+    control.set_paused(ID, false).expect("resume");
+    poller.wait_for(PollerState::Running).await;
+    assert!(!poller.status.borrow().paused_by_command);
+    assert!(control.read(ID, 103).await.is_ok());
+    poller.stop().await;
+}
+
+#[tokio::test]
+async fn admin_api_controls_pollers() {
+    let control = PollerControl::new();
+    let mut poller = start(&control).await;
+    poller.wait_for(PollerState::Running).await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let app = control::router(control.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    let client = Client::new();
+    let url = |path: &str| format!("http://{addr}/api/devices/{ID}/{path}");
+
+    let response = client
+        .post(url("models/103/read"))
+        .send()
+        .await
+        .expect("read");
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value =
+        serde_json::from_str(&response.text().await.expect("body")).expect("json");
+    assert_eq!(body["model_id"], 103);
+    assert_eq!(body["registers"].as_array().map(Vec::len), Some(52));
+    assert!(body["points"]
+        .as_array()
+        .expect("points")
+        .iter()
+        .any(|point| point["name"] == "W"));
+
+    let response = client.post(url("pause")).send().await.expect("pause");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    poller.wait_for(PollerState::Paused).await;
+    let response = client.post(url("resume")).send().await.expect("resume");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    poller.wait_for(PollerState::Running).await;
+
+    let response = client
+        .post(format!("http://{addr}/api/devices/10.0.0.9:1/pause"))
+        .send()
+        .await
+        .expect("pause");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    poller.stop().await;
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use collector_app::daily::{
+    append_csv, local_date, next_local_midnight_ms, DailyAccumulator, CSV_HEADER,
+};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+/// Model 103 block (ID, length, 50 points) with W, WH and St filled in.
+fn inverter_block(power_w: i16, energy_wh: u32, state: u16) -> Vec<u16> {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[14] = power_w as u16;
+    registers[15] = 0;
+    registers[24] = (energy_wh >> 16) as u16;
+    registers[25] = energy_wh as u16;
+    registers[26] = 0;
+    registers[38] = state;
+    registers
+}
+
+fn sample(ip: &str, at_ms: u64, power_w: i16, energy_wh: u32, state: u16) -> PollSample {
+    PollSample::new(
+        DeviceIdentity {
+            ip: ip.to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        103,
+        "three_phase_inverter",
+        40_070,
+        inverter_block(power_w, energy_wh, state),
+        at_ms,
+    )
+}
+
+#[test]
+fn day_totals_energy_uptime_and_faults() {
+    let mut daily = DailyAccumulator::new();
+    daily.record(&sample("10.0.0.2", 0, 1_000, 50_000, 4));
+    daily.record(&sample("10.0.0.2", 60_000, 3_000, 50_040, 4));
+    daily.record(&sample("10.0.0.2", 120_000, 0, 50_050, 7));
+    daily.record(&sample("10.0.0.2", 240_000, 0, 50_050, 7));
+    // Gap longer than five minutes does not count as uptime.
+    daily.record(&sample("10.0.0.2", 1_000_000, 2_000, 50_100, 4));
+
+    let summaries = daily.finish_day("2026-10-15");
+    assert_eq!(summaries.len(), 1);
+    let summary = &summaries[0];
+    assert_eq!(summary.date, "2026-10-15");
+    assert_eq!(summary.energy_wh, 100.0);
+    assert_eq!(summary.max_power_w, 3_000.0);
+    assert_eq!(summary.uptime_minutes, 4);
+    assert_eq!(summary.fault_minutes, 2);
+    assert_eq!(summary.samples, 5);
+}
+
+#[test]
+fn energy_counts_rollovers_but_not_resets() {
+    let mut daily = DailyAccumulator::new();
+    daily.record(&sample("10.0.0.2", 0, 1_000, u32::MAX - 9, 4));
+    // The acc32 counter wraps past u32::MAX.
+    daily.record(&sample("10.0.0.2", 60_000, 1_000, 20, 4));
+    // Far from the top, a decrease is a reset and loses the interval.
+    daily.record(&sample("10.0.0.2", 120_000, 1_000, 5, 4));
+    daily.record(&sample("10.0.0.2", 180_000, 1_000, 35, 4));
+
+    let summaries = daily.finish_day("2026-10-15");
+    assert_eq!(summaries[0].energy_wh, 60.0);
+}
+
+#[test]
+fn finishing_a_day_resets_totals_but_keeps_last_reading() {
+    let mut daily = DailyAccumulator::new();
+    daily.record(&sample("10.0.0.2", 0, 1_000, 10_000, 4));
+    daily.record(&sample("10.0.0.3", 0, 1_000, 10_000, 4));
+    assert_eq!(daily.finish_day("2026-10-15").len(), 2);
+
+    daily.record(&sample("10.0.0.2", 60_000, 1_000, 10_020, 4));
+    let summaries = daily.finish_day("2026-10-16");
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].device.ip, "10.0.0.2");
+    assert_eq!(summaries[0].energy_wh, 20.0);
+    assert_eq!(summaries[0].uptime_minutes, 1);
+}
+
+#[test]
+fn non_inverter_models_are_ignored() {
+    let mut daily = DailyAccumulator::new();
+    let mut other = sample("10.0.0.2", 0, 1_000, 10_000, 4);
+    other.model_id = 160;
+    daily.record(&other);
+    assert!(daily.finish_day("2026-10-15").is_empty());
+}
+
+#[test]
+fn float_inverters_count_the_increase_of_wh() {
+    let float = |at_ms: u64, energy_wh: f32| {
+        let mut registers = vec![0u16; 62];
+        registers[0] = 113;
+        registers[1] = 60;
+        for (offset, value) in [(22, 1_500.0), (32, energy_wh)] {
+            let bits = f32::to_bits(value);
+            registers[offset] = (bits >> 16) as u16;
+            registers[offset + 1] = bits as u16;
+        }
+        registers[48] = 4;
+        let mut sample = sample("10.0.0.4", at_ms, 0, 0, 4);
+        sample.model_id = 113;
+        sample.registers = registers;
+        sample
+    };
+    let mut daily = DailyAccumulator::new();
+    daily.record(&float(0, 1_000.0));
+    daily.record(&float(60_000, 1_025.0));
+    // A drop is a reset and loses the interval.
+    daily.record(&float(120_000, 10.0));
+    daily.record(&float(180_000, 15.0));
+
+    let summaries = daily.finish_day("2026-10-15");
+    assert_eq!(summaries[0].energy_wh, 30.0);
+    assert_eq!(summaries[0].max_power_w, 1_500.0);
+}
+
+#[test]
+fn local_midnight_honours_utc_offset() {
+    // 2026-10-13T22:30:00Z
+    let now = 1_791_930_600_000;
+    assert_eq!(local_date(now, 0), "2026-10-13");
+    assert_eq!(local_date(now, 120), "2026-10-14");
+    assert_eq!(next_local_midnight_ms(now, 0), 1_791_936_000_000);
+    assert_eq!(next_local_midnight_ms(now, 120), 1_792_015_200_000);
+    assert_eq!(next_local_midnight_ms(now, -300), 1_791_954_000_000);
+}
+
+#[test]
+fn csv_header_is_written_once() {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("sunspec_daily_{nanos}.csv"));
+
+    let mut daily = DailyAccumulator::new();
+    daily.record(&sample("10.0.0.2", 0, 1_000, 10_000, 4));
+    let summaries = daily.finish_day("2026-10-15");
+    append_csv(&path, &summaries).expect("first append");
+    append_csv(&path, &summaries).expect("second append");
+
+    let content = fs::read_to_string(&path).expect("read csv");
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], CSV_HEADER);
+    assert_eq!(lines[1], "2026-10-15,10.0.0.2,1,0.0,1000.0,0,0,1");
+    fs::remove_file(&path).expect("remove csv");
+}
@@ -0,0 +1,113 @@
+use avro_kafka::{Encoding, PublishError, Publisher};
+use collector_app::dead_letter::{encode_samples, DeadLetterReason, DeadLetterRecord};
+use poller_actor::PollSample;
+use serde::Serialize;
+use types::DeviceIdentity;
+
+const NOW_MS: u64 = 1_791_930_600_000;
+
+/// A sample with `len` registers that deflate cannot shrink much.
+fn sample(len: usize, seed: u32) -> PollSample {
+    let device = DeviceIdentity {
+        ip: "10.0.0.2".to_string(),
+        unit_id: 1,
+        base_address: None,
+    };
+    let mut state = 0x2545_f491u32 ^ seed;
+    let registers = (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u16
+        })
+        .collect();
+    PollSample::new(
+        device,
+        103,
+        "three_phase_inverter",
+        40_070,
+        registers,
+        NOW_MS,
+    )
+}
+
+fn encoded_len<T: Serialize>(publisher: &Publisher, samples: &[T]) -> usize {
+    publisher.serialize_batch(samples).expect("encode").len()
+}
+
+#[test]
+fn batch_within_the_limit_is_one_payload() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "sunspec.telemetry");
+    let samples = vec![sample(50, 1), sample(50, 2)];
+    let encoded = encode_samples(&publisher, &samples);
+    assert_eq!(encoded.payloads.len(), 1);
+    assert!(encoded.rejected.is_empty());
+}
+
+#[test]
+fn oversized_sample_is_rejected_and_the_rest_still_batched() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "sunspec.telemetry");
+    let samples = vec![sample(50, 3), sample(4_000, 4), sample(50, 5)];
+    let small = [&samples[0], &samples[2]];
+    let max = encoded_len(&publisher, &small);
+    assert!(encoded_len(&publisher, &samples[1..2]) > max);
+
+    let encoded = encode_samples(&publisher.with_max_message_bytes(max), &samples);
+    assert_eq!(encoded.payloads.len(), 1);
+    assert_eq!(encoded.payloads[0].samples, vec![0, 2]);
+    assert_eq!(encoded.rejected.len(), 1);
+    let (index, err) = &encoded.rejected[0];
+    assert_eq!(*index, 1);
+    assert!(matches!(err, PublishError::Oversized { .. }));
+    assert_eq!(
+        DeadLetterReason::from_publish_error(err),
+        DeadLetterReason::Oversized
+    );
+}
+
+#[test]
+fn samples_are_sent_one_by_one_when_the_batch_does_not_fit() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "sunspec.telemetry");
+    let samples = vec![sample(500, 6), sample(500, 7), sample(500, 8)];
+    let max = samples
+        .chunks(1)
+        .map(|single| encoded_len(&publisher, single))
+        .max()
+        .expect("samples");
+    assert!(encoded_len(&publisher, &samples) > max);
+
+    let encoded = encode_samples(&publisher.with_max_message_bytes(max), &samples);
+    let members: Vec<_> = encoded.payloads.iter().map(|p| p.samples.clone()).collect();
+    assert_eq!(members, vec![vec![0], vec![1], vec![2]]);
+    assert!(encoded.rejected.is_empty());
+}
+
+#[test]
+fn single_object_encoding_sends_one_sample_per_payload() {
+    let publisher = Publisher::new_mock(Publisher::default_schema(), "sunspec.telemetry")
+        .with_encoding(Encoding::AvroSingle);
+    let samples = vec![sample(50, 9), sample(50, 10)];
+    let encoded = encode_samples(&publisher, &samples);
+    let members: Vec<_> = encoded.payloads.iter().map(|p| p.samples.clone()).collect();
+    assert_eq!(members, vec![vec![0], vec![1]]);
+    assert!(encoded.rejected.is_empty());
+}
+
+#[test]
+fn dead_letter_record_keeps_the_payload_and_error() {
+    let record = DeadLetterRecord::new(
+        DeadLetterReason::Decode,
+        "expected value at line 1 column 1",
+        "sunspec.telemetry",
+        42,
+        b"{broken",
+        NOW_MS,
+    );
+    let json = serde_json::to_value(&record).expect("json");
+    assert_eq!(json["reason"], "decode");
+    assert_eq!(json["source_topic"], "sunspec.telemetry");
+    assert_eq!(json["buffer_id"], 42);
+    assert_eq!(json["payload"], "{broken");
+    assert_eq!(json["failed_at_ms"], NOW_MS);
+}
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use collector_app::dedup::SampleDeduplicator;
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+fn sample(ip: &str, model_id: u16, registers: Vec<u16>, collected_at_ms: u64) -> PollSample {
+    PollSample::new(
+        DeviceIdentity {
+            ip: ip.to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        model_id,
+        "model",
+        40_002,
+        registers,
+        collected_at_ms,
+    )
+}
+
+#[test]
+fn unchanged_block_is_suppressed_until_max_age() {
+    let mut dedup = SampleDeduplicator::new(Duration::from_secs(10));
+
+    assert!(dedup.admit(&sample("10.0.0.2", 103, vec![1, 2], 0)));
+    assert!(!dedup.admit(&sample("10.0.0.2", 103, vec![1, 2], 1_000)));
+    assert!(!dedup.admit(&sample("10.0.0.2", 103, vec![1, 2], 9_999)));
+    assert!(dedup.admit(&sample("10.0.0.2", 103, vec![1, 2], 10_000)));
+    assert!(!dedup.admit(&sample("10.0.0.2", 103, vec![1, 2], 11_000)));
+}
+
+#[test]
+fn changed_block_is_published() {
+    let mut dedup = SampleDeduplicator::new(Duration::from_secs(10));
+
+    assert!(dedup.admit(&sample("10.0.0.2", 103, vec![1, 2], 0)));
+    assert!(dedup.admit(&sample("10.0.0.2", 103, vec![1, 3], 1_000)));
+    assert!(!dedup.admit(&sample("10.0.0.2", 103, vec![1, 3], 2_000)));
+}
+
+#[test]
+fn blocks_are_tracked_per_device_and_model() {
+    let mut dedup = SampleDeduplicator::new(Duration::from_secs(10));
+
+    assert!(dedup.admit(&sample("10.0.0.2", 103, vec![1, 2], 0)));
+    assert!(dedup.admit(&sample("10.0.0.3", 103, vec![1, 2], 0)));
+    assert!(dedup.admit(&sample("10.0.0.2", 160, vec![1, 2], 0)));
+    assert_eq!(dedup.len(), 3);
+}
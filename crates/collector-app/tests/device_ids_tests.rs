@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use collector_app::device_ids::DeviceIds;
+use collector_app::sparkplug::EdgeNode;
+use types::DeviceIdentity;
+
+fn temp_path(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("sunspec_{name}_{nanos}.json"))
+}
+
+fn device(ip: &str, unit_id: u8) -> DeviceIdentity {
+    DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id,
+        base_address: None,
+    }
+}
+
+#[test]
+fn ids_survive_a_restart_and_are_not_reused() {
+    let path = temp_path("device_ids");
+    let ids = DeviceIds::open(&path).expect("open");
+    assert_eq!(ids.assign(&device("10.0.0.2", 1)).0, 1);
+    assert_eq!(ids.assign(&device("10.0.0.2", 2)).0, 2);
+    assert_eq!(ids.assign(&device("10.0.0.2", 1)).0, 1);
+
+    let reopened = DeviceIds::open(&path).expect("reopen");
+    assert_eq!(reopened.get("10.0.0.2:1"), Some(1));
+    assert_eq!(reopened.get("10.0.0.2:2"), Some(2));
+    assert_eq!(reopened.assign(&device("10.0.0.3", 1)).0, 3);
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn corrupt_file_is_rejected() {
+    let path = temp_path("device_ids_corrupt");
+    fs::write(&path, "{\"next\": ").expect("write");
+    assert!(DeviceIds::open(&path).is_err());
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn unwritable_file_keeps_the_id_for_this_run() {
+    let path = temp_path("device_ids_missing_dir").join("ids.json");
+    let ids = DeviceIds::open(&path).expect("open");
+    let (id, err) = ids.assign(&device("10.0.0.2", 1));
+    assert_eq!(id, 1);
+    assert!(err.is_some());
+    assert_eq!(ids.get("10.0.0.2:1"), Some(1));
+}
+
+#[test]
+fn sparkplug_aliases_carry_the_device_id() {
+    let path = temp_path("device_ids_sparkplug");
+    let ids = DeviceIds::open(&path).expect("open");
+    ids.assign(&device("10.0.0.2", 1));
+    ids.assign(&device("10.0.0.3", 1));
+    let mut node = EdgeNode::new("site", "edge").with_device_ids(ids);
+    node.birth(1_000);
+
+    let points = vec![
+        ("inverter/W".to_string(), 1.0),
+        ("inverter/Hz".to_string(), 50.0),
+    ];
+    let birth = node
+        .device_data(&device("10.0.0.3", 1), &points, 1_000)
+        .expect("birth");
+    let aliases: Vec<_> = birth.payload.metrics.iter().map(|m| m.alias).collect();
+    assert_eq!(aliases, vec![Some(2 << 32), Some((2 << 32) | 1)]);
+
+    let unknown = node
+        .device_data(&device("10.0.0.9", 1), &points, 1_000)
+        .expect("birth");
+    let aliases: Vec<_> = unknown.payload.metrics.iter().map(|m| m.alias).collect();
+    assert_eq!(aliases, vec![Some(0), Some(1)]);
+
+    let _ = fs::remove_file(path);
+}
@@ -0,0 +1,130 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use buffer::{BufferStore, InventoryEntry};
+use collector_app::discovered::{self, DiscoveredDevice};
+use reqwest::Client;
+use sunspec_parser::{CommonModel, ModelDefinition};
+use tokio::net::TcpListener;
+use types::DeviceIdentity;
+
+fn temp_db_path(prefix: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    let pid = std::process::id();
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    path.push(format!("{prefix}-{pid}-{ts}.sqlite"));
+    path
+}
+
+fn cleanup_db(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+}
+
+fn inverter(ip: &str, seen_ms: u64) -> DiscoveredDevice {
+    let model = |id: u16, name: &str, start: u16, length: u16| ModelDefinition {
+        id,
+        name: name.to_string(),
+        start,
+        length,
+    };
+    DiscoveredDevice::new(
+        DeviceIdentity {
+            ip: ip.to_string(),
+            unit_id: 1,
+            base_address: Some(40_000),
+        },
+        vec![
+            model(1, "common", 40_002, 68),
+            model(103, "three_phase_inverter", 40_070, 52),
+        ],
+        Some(CommonModel {
+            manufacturer: "Fronius".to_string(),
+            model: "Symo 10.0-3-M".to_string(),
+            serial_number: "SN-4711".to_string(),
+            ..CommonModel::default()
+        }),
+        seen_ms,
+    )
+}
+
+#[tokio::test]
+async fn rediscovery_updates_the_inventory_but_keeps_first_seen() {
+    let path = temp_db_path("discovered_inventory");
+    let buffer = BufferStore::new(path.to_str().expect("path"))
+        .await
+        .expect("init");
+
+    discovered::record(&buffer, &inverter("10.0.0.7", 1_000))
+        .await
+        .expect("record");
+    discovered::record(&buffer, &inverter("10.0.0.5", 2_000))
+        .await
+        .expect("record");
+    let mut changed = inverter("10.0.0.7", 9_000);
+    changed.models.truncate(1);
+    changed.common = None;
+    discovered::record(&buffer, &changed).await.expect("record");
+
+    let devices = discovered::load(&buffer).await.expect("load");
+    assert_eq!(devices.len(), 2);
+    assert_eq!(devices[0], inverter("10.0.0.5", 2_000));
+    assert_eq!(devices[1].models, changed.models);
+    assert_eq!(devices[1].common, None);
+    assert_eq!(devices[1].first_seen_ms, 1_000);
+    assert_eq!(devices[1].last_seen_ms, 9_000);
+
+    // A row the collector cannot decode is left out rather than failing.
+    buffer
+        .save_inventory(&InventoryEntry {
+            ip: "10.0.0.9".to_string(),
+            unit_id: 2,
+            base_address: None,
+            models: "not json".to_string(),
+            identity: None,
+            first_seen_ms: 3_000,
+            last_seen_ms: 3_000,
+        })
+        .await
+        .expect("save");
+    assert_eq!(discovered::load(&buffer).await.expect("load").len(), 2);
+
+    drop(buffer);
+    cleanup_db(&path);
+}
+
+#[tokio::test]
+async fn admin_api_lists_the_inventory() {
+    let path = temp_db_path("discovered_api");
+    let buffer = BufferStore::new(path.to_str().expect("path"))
+        .await
+        .expect("init");
+    discovered::record(&buffer, &inverter("10.0.0.7", 1_000))
+        .await
+        .expect("record");
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let app = discovered::router(buffer.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let body = Client::new()
+        .get(format!("http://{addr}/api/inventory"))
+        .send()
+        .await
+        .expect("get")
+        .text()
+        .await
+        .expect("body");
+    let devices: Vec<DiscoveredDevice> = serde_json::from_str(&body).expect("json");
+    assert_eq!(devices, vec![inverter("10.0.0.7", 1_000)]);
+    let json: serde_json::Value = serde_json::from_str(&body).expect("json");
+    assert_eq!(json[0]["common"]["serial_number"], "SN-4711");
+    assert_eq!(json[0]["models"][1]["id"], 103);
+
+    drop(buffer);
+    cleanup_db(&path);
+}
@@ -0,0 +1,64 @@
+use collector_app::drill::{DrillCounts, DrillReport};
+
+fn clean_run() -> DrillCounts {
+    DrillCounts {
+        generated: 300,
+        sent_while_blocked: 0,
+        buffered: 300,
+        sent_after_release: 300,
+        left_in_buffer: 0,
+        dead_lettered: 0,
+        drains: 3,
+        drain_ms: 420,
+    }
+}
+
+#[test]
+fn complete_drain_passes() {
+    let report = DrillReport::evaluate("sunspec.drill", &clean_run());
+    assert!(report.passed());
+    let text = report.to_string();
+    assert!(text.starts_with("recovery drill on topic sunspec.drill"));
+    assert!(text.contains("PASS  buffered during outage: 300 of 300 samples buffered"));
+    assert!(text.ends_with("result: PASS"));
+}
+
+#[test]
+fn rows_left_or_lost_fail_the_drill() {
+    let stalled = DrillCounts {
+        sent_after_release: 100,
+        left_in_buffer: 200,
+        ..clean_run()
+    };
+    let report = DrillReport::evaluate("sunspec.drill", &stalled);
+    assert!(!report.passed());
+    let failed: Vec<&str> = report
+        .checks
+        .iter()
+        .filter(|check| !check.passed)
+        .map(|check| check.name)
+        .collect();
+    assert_eq!(failed, vec!["drained after release", "no loss"]);
+    assert!(report.to_string().ends_with("result: FAIL"));
+
+    let dead_lettered = DrillCounts {
+        sent_after_release: 299,
+        dead_lettered: 1,
+        ..clean_run()
+    };
+    assert!(!DrillReport::evaluate("sunspec.drill", &dead_lettered).passed());
+}
+
+#[test]
+fn publishing_through_a_blocked_sink_fails_the_drill() {
+    let leaked = DrillCounts {
+        sent_while_blocked: 100,
+        buffered: 200,
+        sent_after_release: 200,
+        ..clean_run()
+    };
+    let report = DrillReport::evaluate("sunspec.drill", &leaked);
+    assert!(!report.checks[0].passed);
+    assert!(!report.checks[1].passed);
+    assert!(!report.passed());
+}
@@ -0,0 +1,55 @@
+use collector_app::duplicates::SerialRegistry;
+use types::{DeviceIdentity, SerialIdentity};
+
+fn unit(ip: &str, unit_id: u8) -> DeviceIdentity {
+    DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id,
+        base_address: None,
+    }
+}
+
+fn serial(manufacturer: &str, serial_number: &str) -> SerialIdentity {
+    SerialIdentity::new(manufacturer, serial_number).expect("serial number")
+}
+
+#[test]
+fn second_unit_with_same_serial_on_gateway_is_a_mirror() {
+    let mut serials = SerialRegistry::new();
+    assert_eq!(
+        serials.claim(&unit("10.0.0.2", 1), &serial("SMA", "SN-1")),
+        None
+    );
+    assert_eq!(
+        serials.claim(&unit("10.0.0.2", 2), &serial(" SMA", " SN-1 ")),
+        Some(unit("10.0.0.2", 1))
+    );
+    // Claiming again for the same unit (after a rebuild) is not a conflict.
+    assert_eq!(
+        serials.claim(&unit("10.0.0.2", 1), &serial("SMA", "SN-1")),
+        None
+    );
+    // The same serial behind another gateway is left alone.
+    assert_eq!(
+        serials.claim(&unit("10.0.0.3", 1), &serial("SMA", "SN-1")),
+        None
+    );
+    // So is the same serial of another manufacturer.
+    assert_eq!(
+        serials.claim(&unit("10.0.0.2", 5), &serial("Fronius", "SN-1")),
+        None
+    );
+    // Devices without a serial number are never matched.
+    assert_eq!(SerialIdentity::new("SMA", " "), None);
+}
+
+#[test]
+fn released_unit_frees_its_serial() {
+    let mut serials = SerialRegistry::new();
+    serials.claim(&unit("10.0.0.2", 1), &serial("SMA", "SN-1"));
+    serials.release(&unit("10.0.0.2", 1));
+    assert_eq!(
+        serials.claim(&unit("10.0.0.2", 2), &serial("SMA", "SN-1")),
+        None
+    );
+}
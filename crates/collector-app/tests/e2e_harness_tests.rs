@@ -18,6 +18,7 @@ async fn e2e_harness_serializes_and_buffers() {
         DeviceIdentity {
             ip: "127.0.0.1".to_string(),
             unit_id: 1,
+            base_address: None,
         },
         103,
         "three_phase_inverter",
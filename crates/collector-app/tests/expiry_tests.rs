@@ -0,0 +1,36 @@
+use collector_app::expiry::{ExpiryPolicy, TopicMaxAge};
+
+const DAY_MS: u64 = 86_400_000;
+const NOW_MS: u64 = 1_791_930_600_000;
+
+#[test]
+fn topic_limit_overrides_default() {
+    let policy = ExpiryPolicy::new(
+        7 * DAY_MS,
+        &[
+            TopicMaxAge {
+                topic: "sunspec.fast".to_string(),
+                max_age_ms: DAY_MS,
+            },
+            TopicMaxAge {
+                topic: "sunspec.audit".to_string(),
+                max_age_ms: 0,
+            },
+        ],
+    );
+
+    assert!(!policy.is_expired("sunspec.telemetry", NOW_MS - 7 * DAY_MS, NOW_MS));
+    assert!(policy.is_expired("sunspec.telemetry", NOW_MS - 7 * DAY_MS - 1, NOW_MS));
+    assert!(policy.is_expired("sunspec.fast", NOW_MS - 2 * DAY_MS, NOW_MS));
+    // A zero limit keeps the topic's samples forever.
+    assert_eq!(policy.max_age_ms("sunspec.audit"), None);
+    assert!(!policy.is_expired("sunspec.audit", 0, NOW_MS));
+}
+
+#[test]
+fn default_policy_keeps_everything() {
+    let policy = ExpiryPolicy::default();
+    assert!(!policy.is_expired("sunspec.telemetry", 0, NOW_MS));
+    // Clock skew: samples from the future are never expired.
+    assert!(!ExpiryPolicy::new(1, &[]).is_expired("t", NOW_MS + 10, NOW_MS));
+}
@@ -0,0 +1,166 @@
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use modbus_client::{
+    ClientConfig, ClientError, Fault, FaultCounts, FaultInjector, FaultRates, ModbusClient,
+};
+use modbus_mock::{model_block, MockServer};
+use poller_actor::{ActorConfig, PollerActor, PollerError};
+use sunspec_parser::parse_models_from_registers;
+use tokio::sync::{mpsc, watch};
+use tokio::time::timeout;
+use types::DeviceIdentity;
+
+const BASE_ADDRESS: u16 = 40_000;
+
+fn inverter() -> MockServer {
+    let server = MockServer::start().expect("mock server");
+    server.load_sunspec(1, BASE_ADDRESS, &[model_block(1, 66), model_block(103, 50)]);
+    server
+}
+
+async fn client(server: &MockServer, rates: FaultRates) -> (ModbusClient, FaultInjector) {
+    let faults = FaultInjector::new(rates);
+    let client = ModbusClient::connect(ClientConfig {
+        port: server.port(),
+        timeout_ms: 50,
+        retry_count: 2,
+        retry_backoff_ms: 1,
+        ..ClientConfig::default()
+    })
+    .await
+    .expect("connect")
+    .with_faults(faults.clone());
+    (client, faults)
+}
+
+#[test]
+fn faults_follow_their_rates_and_seed() {
+    let rates = FaultRates {
+        timeout: 0.2,
+        truncated: 0.1,
+        exception: 0.1,
+        seed: 7,
+        ..FaultRates::default()
+    };
+    let faults = FaultInjector::new(rates.clone());
+    let drawn: Vec<Option<Fault>> = (0..10_000).map(|_| faults.next_fault()).collect();
+    let counts = faults.counts();
+    assert!((1_800..2_200).contains(&counts.timeouts), "{counts:?}");
+    assert!((800..1_200).contains(&counts.truncated), "{counts:?}");
+    assert!((800..1_200).contains(&counts.exceptions), "{counts:?}");
+    assert!(drawn.contains(&Some(Fault::Exception(0x06))));
+
+    // The same seed repeats the sequence.
+    let again = FaultInjector::new(rates);
+    assert!(drawn.iter().all(|fault| *fault == again.next_fault()));
+
+    let none = FaultInjector::new(FaultRates::default());
+    assert!((0..1_000).all(|_| none.next_fault().is_none()));
+}
+
+#[tokio::test]
+async fn injected_faults_fail_reads_before_they_are_sent() {
+    let server = inverter();
+    let (truncated, faults) = client(
+        &server,
+        FaultRates {
+            truncated: 1.0,
+            ..FaultRates::default()
+        },
+    )
+    .await;
+    let err = truncated
+        .read_range(1, BASE_ADDRESS, 2)
+        .await
+        .expect_err("truncated");
+    assert!(matches!(&err, ClientError::Modbus(io) if io.kind() == ErrorKind::UnexpectedEof));
+    // The first attempt and both retries.
+    assert_eq!(faults.counts().truncated, 3);
+
+    let (exception, _) = client(
+        &server,
+        FaultRates {
+            exception: 1.0,
+            exception_code: 0x04,
+            ..FaultRates::default()
+        },
+    )
+    .await;
+    let err = exception
+        .read_range(1, BASE_ADDRESS, 2)
+        .await
+        .expect_err("exception");
+    assert_eq!(
+        err.to_string(),
+        "modbus transport error: Modbus function 3: Server device failure"
+    );
+
+    let (timeouts, faults) = client(
+        &server,
+        FaultRates {
+            timeout: 1.0,
+            ..FaultRates::default()
+        },
+    )
+    .await;
+    let err = timeouts
+        .read_range(1, BASE_ADDRESS, 2)
+        .await
+        .expect_err("timeout");
+    assert!(matches!(err, ClientError::Timeout { timeout_ms: 50 }));
+    assert_eq!(
+        faults.counts(),
+        FaultCounts {
+            timeouts: 3,
+            ..FaultCounts::default()
+        }
+    );
+    assert_eq!(timeouts.take_stats().error_count(), 3);
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn pollers_give_up_under_sustained_failure() {
+    let server = inverter();
+    let registers = server.registers(1, BASE_ADDRESS, 124).expect("registers");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    let faults = FaultInjector::new(FaultRates {
+        exception: 1.0,
+        ..FaultRates::default()
+    });
+    let (tx, mut rx) = mpsc::channel(16);
+    let (_shutdown, shutdown_rx) = watch::channel(false);
+    let actor = PollerActor::new(
+        DeviceIdentity {
+            ip: "127.0.0.1".to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        ClientConfig {
+            port: server.port(),
+            retry_count: 0,
+            ..ClientConfig::default()
+        },
+        models,
+        tx,
+        shutdown_rx,
+        ActorConfig {
+            poll_interval: Duration::from_millis(10),
+            ..ActorConfig::default()
+        },
+    )
+    .with_faults(faults.clone());
+    let status = actor.status();
+
+    let exit = timeout(Duration::from_secs(10), actor.run())
+        .await
+        .expect("poller exit");
+    assert!(matches!(exit, Err(PollerError::TooManyErrors(10))));
+    assert_eq!(status.borrow().consecutive_errors, 10);
+    // Two models per cycle.
+    assert_eq!(faults.counts().exceptions, 20);
+    assert!(rx.try_recv().is_err());
+    assert!(server.requests().is_empty());
+    assert_eq!(server.connections(), 1);
+}
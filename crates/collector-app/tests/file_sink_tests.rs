@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use collector_app::file_sink::{archive_files, FileSink, Retention, CSV_HEADER};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+// 2026-10-13 22:30:00 UTC
+const AT_MS: u64 = 1_791_930_600_000;
+const HOUR_MS: u64 = 3_600_000;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("sunspec_{name}_{nanos}"))
+}
+
+fn sample(collected_at_ms: u64) -> PollSample {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[14] = 1_500;
+    PollSample::new(
+        DeviceIdentity {
+            ip: "10.0.0.2".to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        103,
+        "three_phase_inverter",
+        40_070,
+        registers,
+        collected_at_ms,
+    )
+}
+
+#[test]
+fn rows_go_to_the_file_of_their_hour() {
+    let dir = temp_dir("file_sink_rows");
+    let mut sink = FileSink::new(
+        &dir,
+        Retention {
+            max_files: 0,
+            max_bytes: 0,
+        },
+    )
+    .expect("sink");
+
+    sink.write(&sample(AT_MS)).expect("write");
+    sink.write(&sample(AT_MS + 1_000)).expect("write");
+    sink.write(&sample(AT_MS + HOUR_MS)).expect("write");
+    sink.flush().expect("flush");
+
+    let files = archive_files(&dir).expect("list");
+    let names: Vec<String> = files
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["telemetry-2026-10-13T22.csv", "telemetry-2026-10-13T23.csv"]
+    );
+
+    let content = fs::read_to_string(&files[0]).expect("read");
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], CSV_HEADER);
+    assert!(
+        lines[1].starts_with("1791930600000,10.0.0.2,1,103,three_phase_inverter,40070,103 50 0")
+    );
+    assert!(lines[1].contains("W=1500"));
+    fs::remove_dir_all(&dir).expect("cleanup");
+}
+
+#[test]
+fn rotation_prunes_oldest_files() {
+    let dir = temp_dir("file_sink_retention");
+    let mut sink = FileSink::new(
+        &dir,
+        Retention {
+            max_files: 2,
+            max_bytes: 0,
+        },
+    )
+    .expect("sink");
+
+    for hour in 0..4 {
+        sink.write(&sample(AT_MS + hour * HOUR_MS)).expect("write");
+    }
+    sink.flush().expect("flush");
+
+    let names: Vec<String> = archive_files(&dir)
+        .expect("list")
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["telemetry-2026-10-14T00.csv", "telemetry-2026-10-14T01.csv"]
+    );
+    fs::remove_dir_all(&dir).expect("cleanup");
+}
@@ -0,0 +1,228 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use collector_app::grpc_sink::proto::{IngestAck, Telemetry, TelemetryBatch};
+use collector_app::grpc_sink::{self, GrpcSinkSettings, INGEST_PATH};
+use poller_actor::PollSample;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::codegen::tokio_stream::wrappers::ReceiverStream;
+use tonic::codegen::{http, Body, BoxFuture, Service, StdError};
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+use tonic_prost::ProstCodec;
+use types::{DeviceIdentity, SiteInfo};
+
+/// A `TelemetryIngest` service that records every batch it reads.
+#[derive(Clone, Default)]
+struct Ingest {
+    batches: Arc<Mutex<Vec<TelemetryBatch>>>,
+    /// Streams left to fail after their first batch, which is not acked.
+    failures: Arc<AtomicUsize>,
+}
+
+impl Ingest {
+    fn batches(&self) -> Vec<TelemetryBatch> {
+        self.batches.lock().expect("batches").clone()
+    }
+}
+
+impl Service<Request<Streaming<TelemetryBatch>>> for Ingest {
+    type Response = Response<ReceiverStream<Result<IngestAck, Status>>>;
+    type Error = Status;
+    type Future = BoxFuture<Self::Response, Status>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Streaming<TelemetryBatch>>) -> Self::Future {
+        let ingest = self.clone();
+        let mut batches = request.into_inner();
+        let (ack_tx, ack_rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            while let Ok(Some(batch)) = batches.message().await {
+                let sequence = batch.sequence;
+                ingest.batches.lock().expect("batches").push(batch);
+                let fail = ingest
+                    .failures
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                        left.checked_sub(1)
+                    })
+                    .is_ok();
+                if fail {
+                    let _ = ack_tx.send(Err(Status::unavailable("restarting"))).await;
+                    break;
+                }
+                if ack_tx.send(Ok(IngestAck { sequence })).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Box::pin(async move { Ok(Response::new(ReceiverStream::new(ack_rx))) })
+    }
+}
+
+/// Hands HTTP/2 requests to [`Ingest`], as generated server code would.
+#[derive(Clone)]
+struct IngestServer(Ingest);
+
+impl<B> Service<http::Request<B>> for IngestServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        assert_eq!(request.uri().path(), INGEST_PATH);
+        let ingest = self.0.clone();
+        Box::pin(async move {
+            let codec = ProstCodec::<IngestAck, TelemetryBatch>::default();
+            Ok(tonic::server::Grpc::new(codec)
+                .streaming(ingest, request)
+                .await)
+        })
+    }
+}
+
+async fn serve(ingest: Ingest) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    tokio::spawn(
+        Server::builder().serve_with_incoming(IngestServer(ingest), TcpIncoming::from(listener)),
+    );
+    addr
+}
+
+fn settings(addr: SocketAddr, batch_size: usize) -> GrpcSinkSettings {
+    GrpcSinkSettings {
+        endpoint: Some(format!("http://{addr}")),
+        batch_size,
+        retry_backoff_ms: 10,
+        ..GrpcSinkSettings::default()
+    }
+}
+
+fn record(sequence: u64) -> Telemetry {
+    let mut sample = PollSample::new(
+        DeviceIdentity {
+            ip: "10.0.0.7".to_string(),
+            unit_id: 3,
+            base_address: None,
+        },
+        103,
+        "three_phase_inverter",
+        40_070,
+        vec![0; 4],
+        1_000 * sequence,
+    );
+    sample.sequence = sequence;
+    grpc_sink::telemetry(&sample, vec![("W".to_string(), 1_500.0)])
+}
+
+/// Streams `records` and waits for the sink to finish.
+async fn stream_all(settings: GrpcSinkSettings, records: Vec<Telemetry>) {
+    let (tx, rx) = mpsc::channel(records.len());
+    for record in records {
+        tx.send(record).await.expect("send");
+    }
+    drop(tx);
+    let endpoint = settings
+        .channel_endpoint()
+        .expect("valid endpoint")
+        .expect("endpoint set");
+    timeout(
+        Duration::from_secs(10),
+        grpc_sink::run(endpoint, settings, rx),
+    )
+    .await
+    .expect("sink finished");
+}
+
+fn sample_sequences(batch: &TelemetryBatch) -> Vec<u64> {
+    batch.samples.iter().map(|sample| sample.sequence).collect()
+}
+
+#[test]
+fn samples_carry_their_decoded_points() {
+    let mut sample = PollSample::new(
+        DeviceIdentity {
+            ip: "10.0.0.7".to_string(),
+            unit_id: 3,
+            base_address: None,
+        },
+        103,
+        "three_phase_inverter",
+        40_070,
+        vec![0; 4],
+        5_000,
+    );
+    sample.site = Some(SiteInfo {
+        site_id: "za-wc-017".to_string(),
+        ..SiteInfo::default()
+    });
+    let record = grpc_sink::telemetry(&sample, vec![("W".to_string(), 1_500.0)]);
+    assert_eq!(record.device_ip, "10.0.0.7");
+    assert_eq!((record.unit_id, record.model_id), (3, 103));
+    assert_eq!(record.collected_at_ms, 5_000);
+    assert_eq!(record.site_id, "za-wc-017");
+    assert_eq!(record.points.len(), 1);
+    assert_eq!(record.points[0].name, "W");
+    assert_eq!(record.points[0].value, 1_500.0);
+}
+
+#[tokio::test]
+async fn samples_are_streamed_in_acknowledged_batches() {
+    let ingest = Ingest::default();
+    let addr = serve(ingest.clone()).await;
+
+    stream_all(settings(addr, 2), (1..=5).map(record).collect()).await;
+
+    let batches = ingest.batches();
+    let sequences: Vec<u64> = batches.iter().map(|batch| batch.sequence).collect();
+    assert_eq!(sequences, vec![1, 2, 3]);
+    let samples: Vec<Vec<u64>> = batches.iter().map(sample_sequences).collect();
+    assert_eq!(samples, vec![vec![1, 2], vec![3, 4], vec![5]]);
+}
+
+#[tokio::test]
+async fn unacknowledged_batches_are_sent_again_after_a_reconnect() {
+    let ingest = Ingest::default();
+    ingest.failures.store(1, Ordering::SeqCst);
+    let addr = serve(ingest.clone()).await;
+
+    stream_all(settings(addr, 2), (1..=5).map(record).collect()).await;
+
+    let batches = ingest.batches();
+    // The failed stream took batch 1 without acking it.
+    assert_eq!(batches[0].sequence, 1);
+    let resent = batches.iter().filter(|batch| batch.sequence == 1).count();
+    assert_eq!(resent, 2);
+    let mut delivered: Vec<u64> = batches.iter().flat_map(sample_sequences).collect();
+    delivered.sort_unstable();
+    delivered.dedup();
+    assert_eq!(delivered, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn endpoints_must_be_urls() {
+    let mut settings = GrpcSinkSettings::default();
+    assert!(matches!(settings.channel_endpoint(), Ok(None)));
+    settings.endpoint = Some("ingest.local:50051".to_string());
+    assert!(settings.channel_endpoint().is_err());
+    settings.endpoint = Some("http://ingest.local:50051".to_string());
+    assert!(matches!(settings.channel_endpoint(), Ok(Some(_))));
+}
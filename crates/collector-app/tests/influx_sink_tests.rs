@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use collector_app::influx_sink::{self, InfluxSinkSettings, LineEncoder};
+use poller_actor::PollSample;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use types::{DeviceIdentity, SiteInfo};
+
+/// One request the fake InfluxDB received.
+#[derive(Debug, Clone)]
+struct Write {
+    query: HashMap<String, String>,
+    authorization: Option<String>,
+    body: String,
+}
+
+/// Records writes and answers with the queued statuses, then 204.
+#[derive(Clone, Default)]
+struct Influx {
+    writes: Arc<Mutex<Vec<Write>>>,
+    statuses: Arc<Mutex<Vec<StatusCode>>>,
+    requests: Arc<AtomicUsize>,
+}
+
+async fn write(
+    State(influx): State<Influx>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: String,
+) -> StatusCode {
+    influx.requests.fetch_add(1, Ordering::SeqCst);
+    influx.writes.lock().expect("writes").push(Write {
+        query,
+        authorization: headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        body,
+    });
+    let mut statuses = influx.statuses.lock().expect("statuses");
+    if statuses.is_empty() {
+        StatusCode::NO_CONTENT
+    } else {
+        statuses.remove(0)
+    }
+}
+
+async fn serve(influx: Influx) -> SocketAddr {
+    let app = Router::new()
+        .route("/api/v2/write", post(write))
+        .with_state(influx);
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    addr
+}
+
+fn settings(addr: SocketAddr) -> InfluxSinkSettings {
+    InfluxSinkSettings {
+        url: Some(format!("http://{addr}")),
+        org: Some("solar".to_string()),
+        bucket: Some("telemetry".to_string()),
+        token: Some("secret-token".to_string()),
+        batch_size: 2,
+        flush_interval_ms: 50,
+        ..InfluxSinkSettings::default()
+    }
+}
+
+fn sample(collected_at_ms: u64) -> PollSample {
+    PollSample::new(
+        DeviceIdentity {
+            ip: "10.0.0.7".to_string(),
+            unit_id: 3,
+            base_address: None,
+        },
+        103,
+        "three_phase_inverter",
+        40_070,
+        vec![0; 4],
+        collected_at_ms,
+    )
+}
+
+/// Writes `lines` and waits for the sink to finish.
+async fn write_all(settings: InfluxSinkSettings, lines: Vec<String>) {
+    let (tx, rx) = mpsc::channel(lines.len());
+    for line in lines {
+        tx.send(line).await.expect("send");
+    }
+    drop(tx);
+    let writer = settings.writer().expect("valid settings").expect("url set");
+    timeout(
+        Duration::from_secs(10),
+        influx_sink::run(writer, settings, rx),
+    )
+    .await
+    .expect("sink finished");
+}
+
+#[test]
+fn samples_become_tagged_lines() {
+    let encoder = LineEncoder::new(&InfluxSinkSettings::default()).expect("encoder");
+    let mut sample = sample(5_000);
+    let points = vec![
+        ("W".to_string(), 1_500.0),
+        ("Hz".to_string(), 50.02),
+        ("PF".to_string(), f64::NAN),
+    ];
+    assert_eq!(
+        encoder.encode(&sample, &points).as_deref(),
+        Some("sunspec,ip=10.0.0.7,model=three_phase_inverter,unit_id=3 W=1500,Hz=50.02 5000")
+    );
+
+    sample.site = Some(SiteInfo {
+        site_id: "za-wc-017".to_string(),
+        ..SiteInfo::default()
+    });
+    let line = encoder.encode(&sample, &points).expect("line");
+    assert!(line.contains(",site=za-wc-017,unit_id=3 "));
+
+    // Nothing to write without a finite point.
+    assert!(encoder
+        .encode(&sample, &[("PF".to_string(), f64::INFINITY)])
+        .is_none());
+}
+
+#[test]
+fn tags_follow_the_mapping_and_are_escaped() {
+    let settings = InfluxSinkSettings {
+        measurement: "pv plant".to_string(),
+        tags: BTreeMap::from([
+            ("device".to_string(), "ip".to_string()),
+            ("feeder".to_string(), "label:feeder".to_string()),
+            ("plant".to_string(), "plant".to_string()),
+        ]),
+        ..InfluxSinkSettings::default()
+    };
+    let encoder = LineEncoder::new(&settings).expect("encoder");
+    let mut sample = sample(5_000);
+    sample.site = Some(SiteInfo {
+        site_id: "za-wc-017".to_string(),
+        plant: Some("North, roof".to_string()),
+        labels: BTreeMap::from([("feeder".to_string(), "3=a".to_string())]),
+        ..SiteInfo::default()
+    });
+
+    let line = encoder
+        .encode(&sample, &[("W phase".to_string(), 1.0)])
+        .expect("line");
+    assert_eq!(
+        line,
+        r"pv\ plant,device=10.0.0.7,feeder=3\=a,plant=North\,\ roof W\ phase=1 5000"
+    );
+
+    let unknown = InfluxSinkSettings {
+        tags: BTreeMap::from([("serial".to_string(), "serial_number".to_string())]),
+        ..InfluxSinkSettings::default()
+    };
+    assert!(LineEncoder::new(&unknown)
+        .unwrap_err()
+        .contains("influx.tags.serial"));
+}
+
+#[test]
+fn urls_without_a_path_use_the_v2_write_api() {
+    let mut settings = InfluxSinkSettings {
+        url: Some("http://influx.local:8086".to_string()),
+        org: Some("solar".to_string()),
+        bucket: Some("telemetry".to_string()),
+        ..InfluxSinkSettings::default()
+    };
+    let url = settings.write_url().expect("valid").expect("set");
+    assert_eq!(
+        url.as_str(),
+        "http://influx.local:8086/api/v2/write?org=solar&bucket=telemetry&precision=ms"
+    );
+
+    // InfluxDB v1 and other endpoints keep their path and query.
+    settings.url = Some("http://influx.local:8086/write?db=sunspec".to_string());
+    settings.org = None;
+    settings.bucket = None;
+    let url = settings.write_url().expect("valid").expect("set");
+    assert_eq!(
+        url.as_str(),
+        "http://influx.local:8086/write?db=sunspec&precision=ms"
+    );
+
+    // The v2 API needs a bucket.
+    settings.url = Some("http://influx.local:8086".to_string());
+    assert!(settings.write_url().is_err());
+    settings.url = Some("influx.local:8086".to_string());
+    assert!(settings.write_url().is_err());
+}
+
+#[tokio::test]
+async fn lines_are_written_in_batches() {
+    let influx = Influx::default();
+    let addr = serve(influx.clone()).await;
+    let lines: Vec<String> = (1..=3).map(|n| format!("sunspec W={n} {n}")).collect();
+
+    write_all(settings(addr), lines).await;
+
+    let writes = influx.writes.lock().expect("writes").clone();
+    let bodies: Vec<&str> = writes.iter().map(|write| write.body.as_str()).collect();
+    assert_eq!(
+        bodies,
+        vec!["sunspec W=1 1\nsunspec W=2 2", "sunspec W=3 3"]
+    );
+    let write = &writes[0];
+    assert_eq!(write.query["org"], "solar");
+    assert_eq!(write.query["bucket"], "telemetry");
+    assert_eq!(write.query["precision"], "ms");
+    assert_eq!(write.authorization.as_deref(), Some("Token secret-token"));
+}
+
+#[tokio::test]
+async fn unavailable_endpoints_are_retried_and_rejected_batches_dropped() {
+    let influx = Influx::default();
+    influx.statuses.lock().expect("statuses").extend([
+        StatusCode::SERVICE_UNAVAILABLE,
+        StatusCode::NO_CONTENT,
+        StatusCode::BAD_REQUEST,
+    ]);
+    let addr = serve(influx.clone()).await;
+    let lines: Vec<String> = (1..=4).map(|n| format!("sunspec W={n} {n}")).collect();
+
+    write_all(settings(addr), lines).await;
+
+    let writes = influx.writes.lock().expect("writes").clone();
+    let bodies: Vec<&str> = writes.iter().map(|write| write.body.as_str()).collect();
+    // The first batch is sent again after the 503; the second is rejected
+    // once and not retried.
+    assert_eq!(
+        bodies,
+        vec![
+            "sunspec W=1 1\nsunspec W=2 2",
+            "sunspec W=1 1\nsunspec W=2 2",
+            "sunspec W=3 3\nsunspec W=4 4",
+        ]
+    );
+    assert_eq!(influx.requests.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn the_token_is_not_logged() {
+    let settings = InfluxSinkSettings {
+        token: Some("secret-token".to_string()),
+        ..InfluxSinkSettings::default()
+    };
+    let debug = format!("{settings:?}");
+    assert!(!debug.contains("secret-token"));
+    assert!(debug.contains("<redacted>"));
+}
@@ -0,0 +1,102 @@
+use collector_app::inventory::{parse_csv, parse_json, DeviceLabels};
+use collector_app::CollectorConfig;
+
+#[test]
+fn csv_rows_become_devices() {
+    let devices = parse_csv(
+        "# plant A\n\
+         IP,Unit,Site,Alias,Profile\n\
+         \n\
+         10.0.0.2,3,north,INV-01,sma\n\
+         10.0.0.3,,north,\"Meter, feeder 1\",\n",
+    )
+    .expect("parse");
+    assert_eq!(devices.len(), 2);
+    assert_eq!(devices[0].ip, "10.0.0.2");
+    assert_eq!(devices[0].unit_id, 3);
+    assert_eq!(devices[0].profile.as_deref(), Some("sma"));
+    assert_eq!(devices[1].unit_id, 1);
+    assert_eq!(devices[1].profile, None);
+    assert_eq!(
+        devices[1].labels(),
+        DeviceLabels {
+            site: Some("north".to_string()),
+            alias: Some("Meter, feeder 1".to_string()),
+        }
+    );
+    assert_eq!(devices[1].identity().base_address, None);
+}
+
+#[test]
+fn csv_columns_can_come_in_any_order() {
+    let devices = parse_csv("alias,base_address,ip\nINV-07,50000,10.0.0.7\n").expect("parse");
+    assert_eq!(devices[0].ip, "10.0.0.7");
+    assert_eq!(devices[0].base_address, Some(50_000));
+    assert_eq!(devices[0].alias.as_deref(), Some("INV-07"));
+}
+
+#[test]
+fn gateway_addressing_overrides_the_modbus_settings() {
+    let devices = parse_csv(
+        "ip,unit,address_offset,one_based_addressing\n\
+         10.0.0.8,1,-1000,true\n\
+         10.0.0.8,2,,\n",
+    )
+    .expect("parse");
+    assert_eq!(devices[0].address_offset, Some(-1_000));
+    assert_eq!(devices[0].one_based_addressing, Some(true));
+
+    let mut config = CollectorConfig::default();
+    config.modbus.address_offset = 5;
+    config.inventory = devices;
+    let shifted = config.modbus_for(&config.inventory[0].identity());
+    assert_eq!(shifted.host, "10.0.0.8");
+    assert_eq!(shifted.address_offset, -1_000);
+    assert!(shifted.one_based_addressing);
+    let plain = config.modbus_for(&config.inventory[1].identity());
+    assert_eq!(plain.address_offset, 5);
+    assert!(!plain.one_based_addressing);
+
+    let err = parse_csv("ip,one_based_addressing\n10.0.0.8,yes\n").expect_err("bad flag");
+    assert!(format!("{err:#}").contains("line 2: invalid one_based_addressing 'yes'"));
+}
+
+#[test]
+fn csv_errors_name_the_line() {
+    let err = parse_csv("ip,unit,zone\n10.0.0.2,1,a\n").expect_err("unknown column");
+    assert!(format!("{err:#}").contains("line 1: unknown column 'zone'"));
+
+    let err = parse_csv("unit\n1\n").expect_err("no ip column");
+    assert!(format!("{err:#}").contains("needs an 'ip' column"));
+
+    let err = parse_csv("ip,unit\n10.0.0.2,1\n10.0.0.2,300\n").expect_err("bad unit");
+    assert!(format!("{err:#}").contains("line 3: invalid unit id '300'"));
+
+    let err = parse_csv("ip,unit\n10.0.0.2,1\n10.0.0.3,1\n10.0.0.2,\n").expect_err("duplicate");
+    assert!(format!("{err:#}").contains("line 4: device 10.0.0.2:1 is listed twice"));
+
+    let err = parse_csv("ip,unit\n,1\n").expect_err("empty ip");
+    assert!(format!("{err:#}").contains("line 2: ip must be non-empty"));
+}
+
+#[test]
+fn json_inventory_accepts_the_same_fields() {
+    let devices = parse_json(
+        r#"[
+            {"ip": "10.0.0.2", "unit": 2, "site": "south", "alias": "INV-02"},
+            {"ip": "10.0.0.3", "unit_id": 4, "profile": "storage"},
+            {"ip": "10.0.0.4"}
+        ]"#,
+    )
+    .expect("parse");
+    assert_eq!(devices.len(), 3);
+    assert_eq!(devices[0].unit_id, 2);
+    assert_eq!(devices[0].site.as_deref(), Some("south"));
+    assert_eq!(devices[1].unit_id, 4);
+    assert_eq!(devices[2].unit_id, 1);
+
+    assert!(parse_json(r#"[{"ip": "10.0.0.2", "zone": "a"}]"#).is_err());
+    let err = parse_json(r#"[{"ip": "10.0.0.2"}, {"ip": "10.0.0.2", "unit": 1}]"#)
+        .expect_err("duplicate");
+    assert!(format!("{err:#}").contains("entry 2: device 10.0.0.2:1 is listed twice"));
+}
@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use collector_app::leader::{self, FileLease, LeaderElection, LeaseRecord, LeaseState};
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+const LEASE_MS: u64 = 15_000;
+
+fn temp_dir(prefix: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    let pid = std::process::id();
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    path.push(format!("{prefix}-{pid}-{ts}"));
+    path
+}
+
+#[test]
+fn one_instance_holds_the_lease_until_it_expires() {
+    let dir = temp_dir("leader_lease");
+    let path = dir.join("leader.lease");
+    let a = FileLease::new(&path, "edge-a", LEASE_MS);
+    let b = FileLease::new(&path, "edge-b", LEASE_MS);
+
+    assert_eq!(a.acquire(1_000).expect("claim"), LeaseState::Claimed);
+    let held = LeaseRecord {
+        instance_id: "edge-a".to_string(),
+        expires_at_ms: 16_000,
+    };
+    assert_eq!(
+        b.acquire(2_000).expect("look"),
+        LeaseState::Taken(held.clone())
+    );
+    assert_eq!(a.acquire(5_000).expect("renew"), LeaseState::Held);
+    assert_eq!(
+        a.read().expect("read").map(|record| record.expires_at_ms),
+        Some(20_000)
+    );
+
+    // a stops renewing; b claims the lease once it expired.
+    assert!(matches!(b.acquire(19_999), Ok(LeaseState::Taken(_))));
+    assert_eq!(b.acquire(20_000).expect("claim"), LeaseState::Claimed);
+    assert!(matches!(a.acquire(20_001), Ok(LeaseState::Taken(_))));
+
+    // Releasing someone else's lease leaves it alone.
+    a.release().expect("release");
+    assert!(path.exists());
+    b.release().expect("release");
+    assert!(!path.exists());
+    assert_eq!(a.acquire(21_000).expect("claim"), LeaseState::Claimed);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn election_leads_after_a_confirmed_claim() {
+    let dir = temp_dir("leader_election");
+    let path = dir.join("leader.lease");
+    let mut a = LeaderElection::new(FileLease::new(&path, "edge-a", LEASE_MS));
+    let mut b = LeaderElection::new(FileLease::new(&path, "edge-b", LEASE_MS));
+
+    assert!(!a.tick(1_000));
+    assert!(!b.tick(1_000));
+    assert!(a.tick(6_000));
+    assert!(!b.tick(6_000));
+
+    // a loses the lease file: it keeps leading until its last renewal
+    // expires, when b may take over.
+    fs::remove_file(&path).expect("remove");
+    fs::create_dir(&path).expect("block");
+    assert!(a.tick(11_000));
+    assert!(!a.tick(21_000));
+    fs::remove_dir(&path).expect("unblock");
+    assert!(!b.tick(21_000));
+    assert!(b.tick(26_000));
+    assert!(!a.tick(26_000));
+    let _ = fs::remove_dir_all(dir);
+}
+
+async fn expect(pause: &mut watch::Receiver<bool>, paused: bool) {
+    timeout(
+        Duration::from_secs(5),
+        pause.wait_for(|value| *value == paused),
+    )
+    .await
+    .expect("pause flag")
+    .expect("pause sender");
+}
+
+#[tokio::test]
+async fn standby_and_maintenance_both_pause_polling() {
+    let (maintenance_tx, maintenance_rx) = watch::channel(false);
+    let (leader_tx, leader_rx) = watch::channel(false);
+    let (pause_tx, mut pause_rx) = watch::channel(true);
+    let handle = tokio::spawn(leader::pause_unless_leading(
+        maintenance_rx,
+        leader_rx,
+        pause_tx,
+    ));
+    leader_tx.send_replace(true);
+    expect(&mut pause_rx, false).await;
+    maintenance_tx.send_replace(true);
+    expect(&mut pause_rx, true).await;
+    maintenance_tx.send_replace(false);
+    expect(&mut pause_rx, false).await;
+
+    // Without a maintenance calendar only the election counts.
+    drop(maintenance_tx);
+    leader_tx.send_replace(false);
+    expect(&mut pause_rx, true).await;
+    leader_tx.send_replace(true);
+    expect(&mut pause_rx, false).await;
+    drop(leader_tx);
+    timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("finished")
+        .expect("join");
+}
@@ -0,0 +1,60 @@
+use collector_app::log_rollup::{rolled_up, LogRollup};
+use tracing::Level;
+
+const WINDOW_MS: u64 = 60_000;
+
+#[test]
+fn repeats_within_the_window_are_summarized_once_it_ends() {
+    let rollup = LogRollup::new(WINDOW_MS);
+    let admit = |fields: &str, now_ms: u64| {
+        rollup.admit(
+            Level::WARN,
+            "poller_actor",
+            "modbus read failed",
+            fields,
+            now_ms,
+        )
+    };
+
+    assert!(admit("model_id=103", 0));
+    assert!(!admit("model_id=103", 1_000));
+    assert!(!admit("model_id=103", 2_000));
+    // A different field value is a different event.
+    assert!(admit("model_id=160", 2_000));
+    assert!(rollup.flush(30_000).is_empty());
+
+    let repeated = rollup.flush(WINDOW_MS);
+    assert_eq!(repeated.len(), 1);
+    assert_eq!(repeated[0].level, Level::WARN);
+    assert_eq!(repeated[0].message, "modbus read failed");
+    assert_eq!(repeated[0].fields, "model_id=103");
+    assert_eq!(repeated[0].repeated, 2);
+
+    // Forgotten after the flush, so the next occurrence is logged again.
+    assert!(admit("model_id=103", 61_000));
+    assert!(admit("model_id=160", 62_000));
+}
+
+#[test]
+fn zero_window_logs_everything() {
+    let rollup = LogRollup::new(WINDOW_MS);
+    rollup.set_window_ms(0);
+    for now_ms in 0..3 {
+        assert!(rollup.admit(
+            Level::WARN,
+            "modbus_client",
+            "modbus read timeout",
+            "",
+            now_ms
+        ));
+    }
+    assert!(rollup.flush(u64::MAX).is_empty());
+}
+
+#[test]
+fn only_poller_and_client_targets_are_rolled_up() {
+    assert!(rolled_up("poller_actor"));
+    assert!(rolled_up("modbus_client::transport"));
+    assert!(!rolled_up("modbus_client_ext"));
+    assert!(!rolled_up("collector_app"));
+}
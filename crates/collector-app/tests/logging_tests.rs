@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use collector_app::logging::{LogFormat, LogSettings};
+use tracing::Level;
+
+#[test]
+fn formats_parse_case_insensitively() {
+    assert_eq!(LogFormat::parse("text"), Some(LogFormat::Text));
+    assert_eq!(LogFormat::parse(" JSON "), Some(LogFormat::Json));
+    assert_eq!(LogFormat::parse("logfmt"), None);
+    assert_eq!(LogSettings::default().format(), Ok(LogFormat::Text));
+}
+
+#[test]
+fn crate_filters_override_the_default_level() {
+    let settings = LogSettings {
+        level: "warn".to_string(),
+        filters: BTreeMap::from([
+            ("poller_actor".to_string(), "debug".to_string()),
+            ("modbus_client".to_string(), "off".to_string()),
+        ]),
+        ..LogSettings::default()
+    };
+    let targets = settings.targets().expect("valid filters");
+    assert!(targets.would_enable("poller_actor::actor", &Level::DEBUG));
+    assert!(!targets.would_enable("poller_actor", &Level::TRACE));
+    assert!(!targets.would_enable("modbus_client", &Level::ERROR));
+    assert!(targets.would_enable("collector_app", &Level::WARN));
+    assert!(!targets.would_enable("collector_app", &Level::INFO));
+}
+
+#[test]
+fn unknown_levels_and_empty_targets_are_rejected() {
+    let verbose = LogSettings {
+        level: "verbose".to_string(),
+        ..LogSettings::default()
+    };
+    assert!(verbose.targets().unwrap_err().contains("logging.level"));
+
+    let filters = |target: &str, level: &str| LogSettings {
+        filters: BTreeMap::from([(target.to_string(), level.to_string())]),
+        ..LogSettings::default()
+    };
+    assert!(filters("rdkafka", "loud")
+        .targets()
+        .unwrap_err()
+        .contains("logging.filters.rdkafka"));
+    assert!(filters(" ", "debug").targets().is_err());
+}
@@ -0,0 +1,78 @@
+use collector_app::maintenance::{CronSchedule, MaintenanceCalendar, MaintenanceWindow};
+
+// Sunday 2026-10-11 02:00:00 UTC
+const SUNDAY_2AM_MS: i64 = 1_791_684_000_000;
+const MINUTE_MS: i64 = 60_000;
+const HOUR_MS: i64 = 3_600_000;
+
+fn window(name: &str, schedule: &str, duration_ms: u64, pause_polling: bool) -> MaintenanceWindow {
+    MaintenanceWindow {
+        name: name.to_string(),
+        schedule: schedule.to_string(),
+        duration_ms,
+        pause_polling,
+    }
+}
+
+#[test]
+fn cron_fields_accept_lists_ranges_and_steps() {
+    let schedule = CronSchedule::parse("*/15 1-3,22 * * 7").expect("parse");
+    assert!(schedule.matches(SUNDAY_2AM_MS));
+    assert!(schedule.matches(SUNDAY_2AM_MS + 45 * MINUTE_MS + 59_999));
+    assert!(!schedule.matches(SUNDAY_2AM_MS + 10 * MINUTE_MS));
+    assert!(!schedule.matches(SUNDAY_2AM_MS + 2 * HOUR_MS));
+    // Monday
+    assert!(!schedule.matches(SUNDAY_2AM_MS + 24 * HOUR_MS));
+
+    assert!(CronSchedule::parse("0 2 * *").is_err());
+    assert!(CronSchedule::parse("60 2 * * *").is_err());
+    assert!(CronSchedule::parse("0 5-2 * * *").is_err());
+    assert!(CronSchedule::parse("*/0 * * * *").is_err());
+}
+
+#[test]
+fn restricted_day_fields_match_either_day() {
+    // The 11th of the month or any Tuesday.
+    let schedule = CronSchedule::parse("0 2 11 * 2").expect("parse");
+    assert!(schedule.matches(SUNDAY_2AM_MS));
+    assert!(schedule.matches(SUNDAY_2AM_MS + 2 * 24 * HOUR_MS));
+    assert!(!schedule.matches(SUNDAY_2AM_MS + 24 * HOUR_MS));
+}
+
+#[test]
+fn window_stays_open_for_its_duration() {
+    let calendar = MaintenanceCalendar::new(
+        &[
+            window("substation", "0 2 * * 0", 2 * HOUR_MS as u64, true),
+            window("scan-quiet", "30 3 * * *", HOUR_MS as u64, false),
+        ],
+        0,
+    )
+    .expect("calendar");
+
+    let before = calendar.status((SUNDAY_2AM_MS - 1) as u64);
+    assert!(!before.is_active());
+
+    let open = calendar.status((SUNDAY_2AM_MS + 90 * MINUTE_MS) as u64);
+    assert_eq!(open.windows, vec!["substation", "scan-quiet"]);
+    assert!(open.pause_polling);
+
+    let after = calendar.status((SUNDAY_2AM_MS + 2 * HOUR_MS) as u64);
+    assert_eq!(after.windows, vec!["scan-quiet"]);
+    assert!(!after.pause_polling);
+}
+
+#[test]
+fn schedules_use_the_configured_utc_offset() {
+    // 02:00 at UTC+01:00 is 01:00 UTC.
+    let calendar =
+        MaintenanceCalendar::new(&[window("local", "0 2 * * *", MINUTE_MS as u64, false)], 60)
+            .expect("calendar");
+    assert!(calendar
+        .status((SUNDAY_2AM_MS - HOUR_MS) as u64)
+        .is_active());
+    assert!(!calendar.status(SUNDAY_2AM_MS as u64).is_active());
+
+    let invalid = MaintenanceCalendar::new(&[window("bad", "0 2 32 * *", 1, false)], 0);
+    assert!(invalid.is_err());
+}
@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use collector_app::quarantine::{Quarantine, QuarantineSettings};
+use collector_app::restart::{Restart, RestartPolicy, RestartTracker};
+use discovery::{discover_subnet, DiscoveryConfig};
+use modbus_client::{ClientConfig, ClientError, ModbusClient};
+use modbus_mock::{
+    model_block, Action, Fault, MockServer, ILLEGAL_DATA_ADDRESS, SERVER_DEVICE_FAILURE,
+};
+use poller_actor::{ActorConfig, PollSample, PollerActor, PollerError, ScanClass};
+use sunspec_parser::{parse_models_from_registers, ModelDefinition};
+use tokio::sync::{mpsc, watch};
+use tokio::time::timeout;
+use types::DeviceIdentity;
+
+const BASE_ADDRESS: u16 = 40_000;
+/// Registers of model 103, header included, after the marker and model 1.
+const INVERTER: std::ops::Range<u16> = 40_070..40_122;
+
+/// A mock inverter with the common model and model 103 on unit 1.
+fn inverter() -> (MockServer, Vec<ModelDefinition>) {
+    let server = MockServer::start().expect("mock server");
+    let mut inverter = model_block(103, 50);
+    // W and W_SF.
+    inverter[14] = 4_200;
+    server.load_sunspec(1, BASE_ADDRESS, &[model_block(1, 66), inverter]);
+    let registers = server.registers(1, BASE_ADDRESS, 124).expect("registers");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    (server, models)
+}
+
+fn client_config(server: &MockServer) -> ClientConfig {
+    ClientConfig {
+        port: server.port(),
+        timeout_ms: 500,
+        retry_count: 0,
+        retry_backoff_ms: 1,
+        ..ClientConfig::default()
+    }
+}
+
+fn device() -> DeviceIdentity {
+    DeviceIdentity {
+        ip: "127.0.0.1".to_string(),
+        unit_id: 1,
+        base_address: None,
+    }
+}
+
+fn actor(
+    server: &MockServer,
+    models: &[ModelDefinition],
+    config: ActorConfig,
+) -> (PollerActor, mpsc::Receiver<PollSample>, watch::Sender<bool>) {
+    let (tx, rx) = mpsc::channel(64);
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let actor = PollerActor::new(
+        device(),
+        client_config(server),
+        models.to_vec(),
+        tx,
+        shutdown_rx,
+        ActorConfig {
+            poll_interval: Duration::from_millis(20),
+            request_timeout: Duration::from_millis(500),
+            ..config
+        },
+    );
+    (actor, rx, shutdown)
+}
+
+async fn next_sample(rx: &mut mpsc::Receiver<PollSample>, model_id: u16) -> PollSample {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let sample = rx.recv().await.expect("sample channel");
+            if sample.model_id == model_id {
+                return sample;
+            }
+        }
+    })
+    .await
+    .expect("sample in time")
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[tokio::test]
+async fn reads_are_retried_past_exceptions_but_not_past_timeouts() {
+    let (server, _) = inverter();
+    let client = ModbusClient::connect(ClientConfig {
+        retry_count: 2,
+        timeout_ms: 100,
+        ..client_config(&server)
+    })
+    .await
+    .expect("connect");
+
+    server.inject(
+        Fault::new(Action::Exception(SERVER_DEVICE_FAILURE))
+            .addresses(INVERTER)
+            .times(2),
+    );
+    let registers = client.read_range(1, 40_070, 52).await.expect("read");
+    assert_eq!(registers[14], 4_200);
+    assert_eq!(server.requests().len(), 3);
+
+    server.inject(Fault::new(Action::Exception(SERVER_DEVICE_FAILURE)).times(3));
+    assert!(matches!(
+        client.read_range(1, 40_070, 52).await,
+        Err(ClientError::Modbus(_))
+    ));
+    assert_eq!(server.requests().len(), 6);
+    // Unmapped registers are refused like a real device would.
+    assert!(client.read_range(1, 40_124, 2).await.is_err());
+    assert_eq!(server.requests().len(), 9);
+
+    server.inject(Fault::new(Action::Delay(Duration::from_millis(250))));
+    let client = ModbusClient::connect(ClientConfig {
+        timeout_ms: 100,
+        ..client_config(&server)
+    })
+    .await
+    .expect("connect");
+    let err = client.read_range(1, 40_070, 52).await.expect_err("timeout");
+    assert!(matches!(err, ClientError::Timeout { timeout_ms: 100 }));
+}
+
+#[tokio::test]
+async fn gateway_offsets_shift_every_address_sent() {
+    // The gateway maps the device 1 000 registers up and counts from 1.
+    let server = MockServer::start().expect("mock server");
+    let mut inverter = model_block(103, 50);
+    inverter[14] = 4_200;
+    server.load_sunspec(1, BASE_ADDRESS + 1_001, &[model_block(1, 66), inverter]);
+    let client = ModbusClient::connect(ClientConfig {
+        address_offset: 1_000,
+        one_based_addressing: true,
+        ..client_config(&server)
+    })
+    .await
+    .expect("connect");
+
+    let registers = client
+        .read_range(1, BASE_ADDRESS, 124)
+        .await
+        .expect("read model list");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    assert_eq!(models[1].start, INVERTER.start);
+    let block = client
+        .read_range(1, INVERTER.start, 52)
+        .await
+        .expect("read");
+    assert_eq!(block[14], 4_200);
+    client
+        .write_registers(1, INVERTER.start + 14, &[4_300])
+        .await
+        .expect("write");
+    assert_eq!(
+        server.registers(1, INVERTER.start + 1_015, 1),
+        Some(vec![4_300])
+    );
+    assert!(server
+        .requests()
+        .iter()
+        .all(|request| request.address > BASE_ADDRESS + 1_000));
+
+    let config = ClientConfig {
+        address_offset: -41_000,
+        ..ClientConfig::default()
+    };
+    assert!(matches!(
+        config.wire_address(BASE_ADDRESS),
+        Err(ClientError::AddressOverflow)
+    ));
+}
+
+#[tokio::test]
+async fn pollers_reconnect_after_a_disconnect_mid_reply() {
+    let (server, models) = inverter();
+    server.inject(
+        Fault::new(Action::DisconnectMidReply)
+            .addresses(INVERTER)
+            .times(1),
+    );
+    let (poller, mut rx, _shutdown) = actor(&server, &models, ActorConfig::default());
+    let run = tokio::spawn(poller.run());
+    // The poller keeps failing on the broken connection until it gives up.
+    let exit = timeout(Duration::from_secs(10), run)
+        .await
+        .expect("poller exit")
+        .expect("join");
+    assert!(matches!(exit, Err(PollerError::TooManyErrors(_))));
+    assert_eq!(server.connections(), 1);
+
+    let mut restarts = RestartTracker::new(RestartPolicy {
+        initial_delay_ms: 10,
+        max_delay_ms: 100,
+        breaker_failures: 3,
+    });
+    let Restart::After(delay) = restarts.exited("127.0.0.1:1", true, false) else {
+        panic!("breaker opened after one failure");
+    };
+    tokio::time::sleep(delay).await;
+
+    let (poller, mut next_rx, shutdown) = actor(&server, &models, ActorConfig::default());
+    let run = tokio::spawn(poller.run());
+    let sample = next_sample(&mut next_rx, 103).await;
+    assert_eq!(sample.registers[14], 4_200);
+    assert_eq!(server.connections(), 2);
+    shutdown.send(true).expect("shutdown");
+    run.await.expect("join").expect("clean stop");
+    // The first poller's common model read went through before the cut.
+    assert!(rx.try_recv().is_ok());
+}
+
+#[tokio::test]
+async fn failing_models_are_quarantined_while_the_rest_is_polled() {
+    let (server, models) = inverter();
+    server.inject(Fault::new(Action::Exception(ILLEGAL_DATA_ADDRESS)).addresses(INVERTER));
+    let (poller, _rx, shutdown) = actor(
+        &server,
+        &models,
+        ActorConfig {
+            model_quarantine_after: 2,
+            model_probe_interval: Duration::from_secs(60),
+            ..ActorConfig::default()
+        },
+    );
+    let mut status = poller.status();
+    let run = tokio::spawn(poller.run());
+
+    timeout(
+        Duration::from_secs(5),
+        status.wait_for(|status| {
+            status
+                .models
+                .iter()
+                .any(|model| model.model_id == 103 && model.quarantined())
+        }),
+    )
+    .await
+    .expect("quarantined in time")
+    .expect("status");
+    let failed = server.requests().len();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    // Only the common model is read until the probe is due.
+    let requests = server.requests();
+    assert!(requests.len() > failed);
+    assert!(requests[failed..]
+        .iter()
+        .all(|request| request.address < INVERTER.start));
+    shutdown.send(true).expect("shutdown");
+    run.await.expect("join").expect("clean stop");
+}
+
+#[tokio::test]
+async fn scan_classes_read_fast_points_between_full_blocks() {
+    let (server, models) = inverter();
+    // W through St, as resolved for W, Hz and St.
+    let scan_classes = |full_interval| {
+        HashMap::from([(
+            103,
+            ScanClass {
+                offset: 14,
+                length: 25,
+                full_interval,
+            },
+        )])
+    };
+    let (poller, mut rx, shutdown) = actor(&server, &models, ActorConfig::default());
+    let run = tokio::spawn(
+        poller
+            .with_scan_classes(scan_classes(Duration::from_secs(3_600)))
+            .run(),
+    );
+    let full = next_sample(&mut rx, 103).await;
+    assert_eq!(full.registers.len(), 52);
+    // AphA is outside the fast span, W inside.
+    server.set_registers(1, 40_072, &[7]);
+    server.set_registers(1, 40_084, &[4_300]);
+    let fast = loop {
+        let sample = next_sample(&mut rx, 103).await;
+        if sample.registers[14] == 4_300 {
+            break sample;
+        }
+    };
+    assert_eq!(fast.start, INVERTER.start);
+    assert_eq!(fast.registers.len(), 52);
+    assert_eq!(fast.registers[2], full.registers[2]);
+    shutdown.send(true).expect("shutdown");
+    run.await.expect("join").expect("clean stop");
+    let reads: Vec<(u16, u16)> = server
+        .requests()
+        .iter()
+        .filter(|request| request.address >= INVERTER.start)
+        .map(|request| (request.address, request.count))
+        .collect();
+    assert_eq!(reads[0], (40_070, 52));
+    assert!(reads.len() > 1);
+    assert!(reads[1..].iter().all(|read| *read == (40_084, 25)));
+
+    // The whole block is read again once the full interval is up.
+    let (poller, mut rx, shutdown) = actor(&server, &models, ActorConfig::default());
+    let run = tokio::spawn(
+        poller
+            .with_scan_classes(scan_classes(Duration::from_millis(100)))
+            .run(),
+    );
+    let before = server.requests().len();
+    next_sample(&mut rx, 103).await;
+    server.set_registers(1, 40_072, &[8]);
+    loop {
+        if next_sample(&mut rx, 103).await.registers[2] == 8 {
+            break;
+        }
+    }
+    shutdown.send(true).expect("shutdown");
+    run.await.expect("join").expect("clean stop");
+    let requests = server.requests();
+    let full_reads = requests[before..]
+        .iter()
+        .filter(|request| request.address == 40_070)
+        .count();
+    let fast_reads = requests[before..]
+        .iter()
+        .filter(|request| request.address == 40_084)
+        .count();
+    assert!(full_reads >= 2);
+    assert!(fast_reads >= 1);
+}
+
+#[tokio::test]
+async fn unreachable_devices_are_quarantined_until_they_answer() {
+    let (server, models) = inverter();
+    server.set_online(false).expect("offline");
+    let mut quarantine = Quarantine::new(QuarantineSettings {
+        initial_probe_ms: 1_000,
+        max_probe_ms: 60_000,
+        after_failures: 2,
+    });
+
+    for attempt in 1..=2 {
+        let (poller, _rx, _shutdown) = actor(&server, &models, ActorConfig::default());
+        let exit = timeout(Duration::from_secs(5), poller.run())
+            .await
+            .expect("connect failure in time");
+        assert!(matches!(exit, Err(PollerError::Connect(_))));
+        let quarantined = quarantine.connect_failed(&device(), unix_ms());
+        assert_eq!(quarantined, attempt == 2);
+    }
+    assert!(quarantine.contains(&device()));
+    let due_ms = unix_ms() + 1_000;
+    assert_eq!(quarantine.due(due_ms), vec![device()]);
+    assert!(ModbusClient::connect(client_config(&server)).await.is_err());
+    quarantine.failed(&device(), due_ms);
+    assert!(quarantine.due(due_ms + 1_000).is_empty());
+
+    // Back online: the next probe gets through and the device is polled again.
+    server.set_online(true).expect("online");
+    let probe = ModbusClient::connect(client_config(&server))
+        .await
+        .expect("probe connect");
+    assert!(probe.read_range(1, BASE_ADDRESS, 2).await.is_ok());
+    assert!(quarantine.release(&device()).is_some());
+    let (poller, mut rx, shutdown) = actor(&server, &models, ActorConfig::default());
+    let run = tokio::spawn(poller.run());
+    next_sample(&mut rx, 103).await;
+    shutdown.send(true).expect("shutdown");
+    run.await.expect("join").expect("clean stop");
+}
+
+#[tokio::test]
+async fn discovery_finds_the_server_only_while_it_listens() {
+    let server = MockServer::start().expect("mock server");
+    let config = DiscoveryConfig {
+        subnet: "127.0.0.1/32".to_string(),
+        port: server.port(),
+        per_host_timeout_ms: 1_000,
+        unit_ids: vec![1, 2],
+        ..DiscoveryConfig::default()
+    };
+
+    let devices = discover_subnet(config.clone()).await.expect("discovery");
+    let units: Vec<u8> = devices.iter().map(|device| device.unit_id).collect();
+    assert_eq!(units, vec![1, 2]);
+    assert!(devices.iter().all(|device| device.ip == "127.0.0.1"));
+
+    server.set_online(false).expect("offline");
+    assert!(discover_subnet(config.clone())
+        .await
+        .expect("discovery")
+        .is_empty());
+    server.set_online(true).expect("online");
+    assert_eq!(discover_subnet(config).await.expect("discovery").len(), 2);
+    assert_eq!(server.connections(), 2);
+}
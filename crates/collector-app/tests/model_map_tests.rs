@@ -0,0 +1,129 @@
+use collector_app::model_map::{ModelMaps, PointType, StaticModel, StaticModelMap, StaticPoint};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+fn point(name: &str, offset: u16, point_type: PointType, scale: i16) -> StaticPoint {
+    StaticPoint {
+        name: name.to_string(),
+        offset,
+        point_type,
+        scale,
+    }
+}
+
+fn meter_map() -> StaticModelMap {
+    StaticModelMap {
+        ip: "10.0.0.9".to_string(),
+        unit_id: 2,
+        models: vec![
+            StaticModel {
+                id: 64_900,
+                name: "vendor_meter".to_string(),
+                start: 1_000,
+                length: 6,
+                points: vec![
+                    point("power_w", 0, PointType::Int32, -1),
+                    point("frequency_hz", 2, PointType::Float32, 0),
+                    point("voltage_v", 4, PointType::Uint16, 0),
+                ],
+            },
+            StaticModel {
+                id: 103,
+                name: "inverter".to_string(),
+                start: 2_000,
+                length: 52,
+                points: Vec::new(),
+            },
+        ],
+    }
+}
+
+fn sample(model_id: u16, start: u16, registers: Vec<u16>) -> PollSample {
+    PollSample::new(
+        DeviceIdentity {
+            ip: "10.0.0.9".to_string(),
+            unit_id: 2,
+            base_address: None,
+        },
+        model_id,
+        "model",
+        start,
+        registers,
+        1_000,
+    )
+}
+
+#[test]
+fn definitions_follow_the_map() {
+    let map = meter_map();
+    map.validate().expect("valid map");
+    let definitions = map.definitions();
+    assert_eq!(definitions.len(), 2);
+    assert_eq!(definitions[0].id, 64_900);
+    assert_eq!(definitions[0].name, "vendor_meter");
+    assert_eq!(definitions[0].start, 1_000);
+    assert_eq!(definitions[0].length, 6);
+    assert!(map.matches(&map.identity()));
+}
+
+#[test]
+fn configured_points_are_decoded_and_scaled() {
+    let maps = ModelMaps::new(vec![meter_map()]);
+    let power = (-12_345i32) as u32;
+    let frequency = 50.0f32.to_bits();
+    let registers = vec![
+        (power >> 16) as u16,
+        power as u16,
+        (frequency >> 16) as u16,
+        frequency as u16,
+        231,
+        0,
+    ];
+
+    let points = maps.points(&sample(64_900, 1_000, registers));
+    assert_eq!(
+        points,
+        vec![
+            ("power_w".to_string(), -1_234.5),
+            ("frequency_hz".to_string(), 50.0),
+            ("voltage_v".to_string(), 231.0),
+        ]
+    );
+}
+
+#[test]
+fn models_without_points_decode_as_sunspec() {
+    let maps = ModelMaps::new(vec![meter_map()]);
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[14] = 1_500;
+
+    let points = maps.points(&sample(103, 2_000, registers.clone()));
+    assert!(points.contains(&("W".to_string(), 1_500.0)));
+    // Unmapped devices decode the same way.
+    assert_eq!(
+        ModelMaps::default().points(&sample(103, 2_000, registers)),
+        points
+    );
+}
+
+#[test]
+fn invalid_maps_are_rejected() {
+    let mut empty = meter_map();
+    empty.models.clear();
+    assert!(empty.validate().is_err());
+
+    let mut outside = meter_map();
+    outside.models[0].points[2].offset = 5;
+    outside.models[0].points[2].point_type = PointType::Uint32;
+    assert!(outside.validate().unwrap_err().contains("voltage_v"));
+
+    let mut twice = meter_map();
+    twice.models[0].points[1].name = "power_w".to_string();
+    assert!(twice.validate().is_err());
+
+    let mut overflow = meter_map();
+    overflow.models[1].start = 65_500;
+    assert!(overflow.validate().is_err());
+}
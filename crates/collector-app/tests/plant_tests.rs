@@ -0,0 +1,107 @@
+use avro_kafka::{validate_json, Encoding, Publisher};
+use collector_app::plant::{next_snapshot_ms, PlantSnapshots};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+const TRIGGERED_AT_MS: u64 = 1_791_930_600_000;
+
+fn device(ip: &str) -> DeviceIdentity {
+    DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id: 1,
+        base_address: None,
+    }
+}
+
+fn inverter_sample(ip: &str, power_w: i16, at_ms: u64) -> PollSample {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[14] = power_w as u16;
+    PollSample::new(
+        device(ip),
+        103,
+        "three_phase_inverter",
+        40_070,
+        registers,
+        at_ms,
+    )
+}
+
+fn meter_sample(ip: &str, power_w: i16, at_ms: u64) -> PollSample {
+    let mut registers = vec![0u16; 107];
+    registers[0] = 203;
+    registers[1] = 105;
+    registers[18] = power_w as u16;
+    PollSample::new(device(ip), 203, "meter", 40_122, registers, at_ms)
+}
+
+#[test]
+fn snapshot_sums_inverters_and_meters_within_the_window() {
+    let mut snapshots = PlantSnapshots::new(2_000);
+    assert!(snapshots.start(7, TRIGGERED_AT_MS, 4).is_none());
+    assert!(snapshots.record(7, &inverter_sample("10.0.0.2", 4_000, TRIGGERED_AT_MS + 40)));
+    assert!(snapshots.record(7, &inverter_sample("10.0.0.3", 3_500, TRIGGERED_AT_MS + 90)));
+    assert!(snapshots.record(7, &meter_sample("10.0.0.9", -7_400, TRIGGERED_AT_MS + 25)));
+    // Too late, and a read answering an earlier snapshot.
+    assert!(!snapshots.record(
+        7,
+        &inverter_sample("10.0.0.4", 3_000, TRIGGERED_AT_MS + 2_500)
+    ));
+    assert!(!snapshots.record(6, &inverter_sample("10.0.0.4", 3_000, TRIGGERED_AT_MS + 50)));
+
+    let snapshot = snapshots.close().expect("snapshot");
+    assert_eq!(snapshot.snapshot_id, 7);
+    assert_eq!(snapshot.inverter_w, 7_500.0);
+    assert_eq!(snapshot.meter_w, Some(-7_400.0));
+    assert_eq!(snapshot.spread_ms, 65);
+    assert_eq!(snapshot.devices_expected, 4);
+    assert_eq!(snapshot.devices_read, 3);
+    assert_eq!(snapshot.late, 1);
+    assert_eq!(snapshot.inverters.len(), 2);
+    assert!(snapshots.close().is_none());
+}
+
+#[test]
+fn snapshot_without_meters_has_no_meter_power() {
+    let mut snapshots = PlantSnapshots::new(2_000);
+    snapshots.start(1, TRIGGERED_AT_MS, 1);
+    snapshots.record(1, &inverter_sample("10.0.0.2", 4_000, TRIGGERED_AT_MS));
+    let snapshot = snapshots
+        .start(2, TRIGGERED_AT_MS + 60_000, 1)
+        .expect("closed");
+    assert_eq!(snapshot.meter_w, None);
+    assert_eq!(snapshot.spread_ms, 0);
+}
+
+#[test]
+fn snapshots_align_to_the_interval() {
+    assert_eq!(
+        next_snapshot_ms(TRIGGERED_AT_MS, 60_000),
+        TRIGGERED_AT_MS + 60_000
+    );
+    assert_eq!(
+        next_snapshot_ms(TRIGGERED_AT_MS + 1, 60_000),
+        TRIGGERED_AT_MS + 60_000
+    );
+    assert_eq!(
+        next_snapshot_ms(TRIGGERED_AT_MS - 1, 60_000),
+        TRIGGERED_AT_MS
+    );
+}
+
+#[test]
+fn plant_snapshots_match_the_plant_schema() {
+    let mut snapshots = PlantSnapshots::new(2_000);
+    snapshots.start(1, TRIGGERED_AT_MS, 2);
+    snapshots.record(1, &inverter_sample("10.0.0.2", 4_000, TRIGGERED_AT_MS + 10));
+    snapshots.record(1, &meter_sample("10.0.0.9", -3_900, TRIGGERED_AT_MS + 20));
+    let snapshot = snapshots.close().expect("snapshot");
+
+    let publisher = Publisher::new_mock(Publisher::plant_snapshot_schema(), "sunspec.plant");
+    assert!(!publisher.serialize(&snapshot).expect("avro").is_empty());
+    let record = serde_json::to_value(&snapshot).expect("json");
+    assert_eq!(validate_json(&publisher.json_schema(), &record), Ok(()));
+    let json = publisher.with_encoding(Encoding::Json);
+    assert!(!json.serialize(&snapshot).expect("json").is_empty());
+}
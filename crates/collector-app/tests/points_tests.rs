@@ -0,0 +1,100 @@
+use collector_app::points::{device_id, PointDirectory};
+use collector_app::simulator::Simulator;
+use sunspec_parser::decode_points;
+use types::{DeviceIdentity, PointValue};
+
+const BASE_ADDRESS: u16 = 40_000;
+
+#[test]
+fn directory_describes_the_last_block_of_each_model() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1], &[101, 203]).expect("simulator");
+    let device = DeviceIdentity {
+        ip: "192.168.1.20".to_string(),
+        unit_id: 1,
+        base_address: Some(BASE_ADDRESS),
+    };
+    let directory = PointDirectory::new();
+    for now_ms in [0, 1_000] {
+        for sample in simulator.samples(&device, now_ms) {
+            directory.record(&sample);
+        }
+    }
+
+    let id = device_id(&device);
+    assert_eq!(id, "192.168.1.20:1");
+    let points = directory.device(&id).expect("device points");
+    assert_eq!(points.device, device);
+    let ids: Vec<u16> = points.models.iter().map(|model| model.model_id).collect();
+    assert_eq!(ids, vec![1, 101, 203]);
+    assert!(points
+        .models
+        .iter()
+        .all(|model| model.collected_at_ms == 1_000));
+
+    let common = &points.models[0];
+    let serial = common
+        .points
+        .iter()
+        .find(|point| point.name == "SN")
+        .expect("SN");
+    assert_eq!(serial.address, BASE_ADDRESS + 2 + 50);
+    assert_eq!(serial.point_type, "string");
+
+    let inverter = &points.models[1];
+    assert!(inverter.decoded);
+    let names: Vec<&str> = inverter.points.iter().map(|point| point.name).collect();
+    assert!(names.contains(&"W"));
+    assert!(!names.contains(&"AphB"));
+
+    let meter = &points.models[2];
+    assert!(meter.decoded);
+    assert!(meter.points.iter().any(|point| point.name == "W"));
+
+    assert!(directory.device("192.168.1.20:2").is_none());
+}
+
+#[test]
+fn latest_values_come_from_the_last_sample_of_each_model() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1], &[101]).expect("simulator");
+    let device = DeviceIdentity {
+        ip: "192.168.1.20".to_string(),
+        unit_id: 1,
+        base_address: Some(BASE_ADDRESS),
+    };
+    let directory = PointDirectory::new();
+    let mut last = Vec::new();
+    for now_ms in [0, 60_000] {
+        last = simulator.samples(&device, now_ms);
+        for sample in &last {
+            directory.record(sample);
+        }
+    }
+
+    let latest = directory.latest("192.168.1.20:1").expect("latest values");
+    assert_eq!(latest.device, device);
+    let inverter = latest
+        .models
+        .iter()
+        .find(|model| model.model_id == 101)
+        .expect("inverter");
+    assert_eq!(inverter.collected_at_ms, 60_000);
+    let sample = last
+        .iter()
+        .find(|sample| sample.model_id == 101)
+        .expect("inverter sample");
+    let power = decode_points(101, &sample.registers)
+        .expect("decoded")
+        .into_iter()
+        .find(|(name, _)| *name == "W")
+        .expect("W");
+    assert_eq!(inverter.values.get("W"), Some(&power.1));
+
+    let common = &latest.models[0];
+    assert!(common.values.is_empty());
+    assert!(matches!(
+        common.typed.get("SN"),
+        Some(PointValue::String(_))
+    ));
+
+    assert!(directory.latest("192.168.1.20:2").is_none());
+}
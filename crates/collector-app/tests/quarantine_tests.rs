@@ -0,0 +1,68 @@
+use collector_app::quarantine::{Quarantine, QuarantineSettings};
+use types::DeviceIdentity;
+
+const MINUTE_MS: u64 = 60_000;
+const HOUR_MS: u64 = 3_600_000;
+const NOW_MS: u64 = 1_791_930_600_000;
+
+fn settings() -> QuarantineSettings {
+    QuarantineSettings {
+        initial_probe_ms: MINUTE_MS,
+        max_probe_ms: HOUR_MS,
+        after_failures: 3,
+    }
+}
+
+fn device(ip: &str) -> DeviceIdentity {
+    DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id: 1,
+        base_address: None,
+    }
+}
+
+#[test]
+fn probe_delay_doubles_up_to_the_maximum() {
+    let settings = settings();
+    assert_eq!(settings.probe_delay_ms(0), MINUTE_MS);
+    assert_eq!(settings.probe_delay_ms(1), 2 * MINUTE_MS);
+    assert_eq!(settings.probe_delay_ms(5), 32 * MINUTE_MS);
+    assert_eq!(settings.probe_delay_ms(6), HOUR_MS);
+    assert_eq!(settings.probe_delay_ms(u32::MAX), HOUR_MS);
+}
+
+#[test]
+fn failed_device_is_probed_at_growing_intervals() {
+    let mut quarantine = Quarantine::new(settings());
+    let dead = device("10.0.0.9");
+    assert!(quarantine.failed(&dead, NOW_MS));
+    assert!(quarantine.due(NOW_MS + MINUTE_MS - 1).is_empty());
+    assert_eq!(quarantine.due(NOW_MS + MINUTE_MS), vec![dead.clone()]);
+
+    let probed_at = NOW_MS + MINUTE_MS;
+    assert!(!quarantine.failed(&dead, probed_at));
+    assert!(quarantine.due(probed_at + MINUTE_MS).is_empty());
+    assert_eq!(quarantine.due(probed_at + 2 * MINUTE_MS).len(), 1);
+
+    let entry = quarantine.release(&dead).expect("quarantined");
+    assert_eq!(entry.since_ms, NOW_MS);
+    assert_eq!(entry.failed_probes, 1);
+    assert!(quarantine.is_empty());
+    assert!(quarantine.release(&dead).is_none());
+}
+
+#[test]
+fn pollers_are_quarantined_after_repeated_connect_failures() {
+    let mut quarantine = Quarantine::new(settings());
+    let flaky = device("10.0.0.2");
+    assert!(!quarantine.connect_failed(&flaky, NOW_MS));
+    assert!(!quarantine.connect_failed(&flaky, NOW_MS));
+    // A poller that got through starts counting again.
+    quarantine.clear_strikes(&flaky);
+    assert!(!quarantine.connect_failed(&flaky, NOW_MS));
+    assert!(!quarantine.connect_failed(&flaky, NOW_MS));
+    assert!(!quarantine.contains(&flaky));
+    assert!(quarantine.connect_failed(&flaky, NOW_MS));
+    assert!(quarantine.contains(&flaky));
+    assert_eq!(quarantine.len(), 1);
+}
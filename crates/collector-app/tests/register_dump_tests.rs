@@ -0,0 +1,94 @@
+use collector_app::register_dump::{parse_target, to_hex, RegisterDump};
+use modbus_client::{ClientConfig, ModbusClient};
+use modbus_mock::{model_block, MockServer};
+use sunspec_parser::{parse_models_from_registers, ModelDefinition};
+use types::DeviceIdentity;
+
+const BASE_ADDRESS: u16 = 40_000;
+
+fn client_config(server: &MockServer) -> ClientConfig {
+    ClientConfig {
+        port: server.port(),
+        timeout_ms: 500,
+        retry_count: 0,
+        retry_backoff_ms: 1,
+        ..ClientConfig::default()
+    }
+}
+
+fn device() -> DeviceIdentity {
+    DeviceIdentity {
+        ip: "127.0.0.1".to_string(),
+        unit_id: 1,
+        base_address: Some(BASE_ADDRESS),
+    }
+}
+
+#[tokio::test]
+async fn dumps_load_back_into_the_mock_server() {
+    let server = MockServer::start().expect("mock server");
+    let mut inverter = model_block(103, 50);
+    inverter[14] = 4_200;
+    server.load_sunspec(1, BASE_ADDRESS, &[model_block(1, 66), inverter.clone()]);
+    let registers = server.registers(1, BASE_ADDRESS, 124).expect("registers");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    let client = ModbusClient::connect(client_config(&server))
+        .await
+        .expect("connect");
+
+    let dump = RegisterDump::capture(&client, device(), &models, None, 1_000).await;
+    assert_eq!(dump.models.len(), 2);
+    let block = &dump.models[1];
+    assert_eq!(
+        (block.model_id, block.start, block.length),
+        (103, 40_070, 52)
+    );
+    assert_eq!(block.registers, inverter);
+    assert!(block.hex.starts_with("0067 0032 "));
+    assert_eq!(block.error, None);
+
+    // The dump rebuilds the device for regression tests.
+    let json = serde_json::to_string(&dump).expect("json");
+    let dump: RegisterDump = serde_json::from_str(&json).expect("dump");
+    let replica = MockServer::start().expect("mock server");
+    replica.load_sunspec(1, BASE_ADDRESS, &dump.blocks());
+    assert_eq!(replica.registers(1, BASE_ADDRESS, 124), Some(registers));
+}
+
+#[tokio::test]
+async fn unreadable_models_are_kept_with_their_error() {
+    let server = MockServer::start().expect("mock server");
+    server.load_sunspec(1, BASE_ADDRESS, &[model_block(1, 66)]);
+    let mut models = parse_models_from_registers(
+        BASE_ADDRESS,
+        &server.registers(1, BASE_ADDRESS, 72).expect("registers"),
+    )
+    .expect("model list");
+    // Announced by the device but never mapped.
+    models.push(ModelDefinition::new(160, 41_000, 50));
+    let client = ModbusClient::connect(client_config(&server))
+        .await
+        .expect("connect");
+
+    let dump = RegisterDump::capture(&client, device(), &models, None, 1_000).await;
+    assert_eq!(dump.models[0].error, None);
+    assert!(dump.models[1].registers.is_empty());
+    assert!(dump.models[1].error.is_some());
+    assert_eq!(dump.blocks().len(), 1);
+}
+
+#[test]
+fn targets_default_to_unit_one() {
+    assert_eq!(
+        parse_target("192.168.1.20"),
+        Ok(DeviceIdentity {
+            ip: "192.168.1.20".to_string(),
+            unit_id: 1,
+            base_address: None,
+        })
+    );
+    assert_eq!(parse_target("inverter-7:3").expect("target").unit_id, 3);
+    assert!(parse_target("192.168.1.20:300").is_err());
+    assert!(parse_target(":1").is_err());
+    assert_eq!(to_hex(&[0x5375, 0x6e53, 1]), "5375 6e53 0001");
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use collector_app::reload::{plan_reload, FileWatcher};
+use collector_app::CollectorConfig;
+use types::DeviceIdentity;
+
+fn device(ip: &str) -> DeviceIdentity {
+    DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id: 1,
+        base_address: None,
+    }
+}
+
+#[test]
+fn identical_configs_plan_nothing() {
+    let config = CollectorConfig::default();
+    assert!(plan_reload(&config, &config.clone()).is_empty());
+}
+
+#[test]
+fn plan_reports_poller_kafka_and_device_changes() {
+    let mut current = CollectorConfig::default();
+    current.discovery.static_devices = vec![device("10.0.0.2"), device("10.0.0.3")];
+
+    let mut next = current.clone();
+    next.poller.poll_interval = Duration::from_millis(250);
+    next.kafka_brokers = Some("localhost:9092".to_string());
+    next.discovery.static_devices = vec![device("10.0.0.3"), device("10.0.0.4")];
+
+    let plan = plan_reload(&current, &next);
+    assert!(plan.poller_changed);
+    assert!(plan.kafka_changed);
+    assert_eq!(plan.added_devices, vec![device("10.0.0.4")]);
+    assert_eq!(plan.removed_devices, vec![device("10.0.0.2")]);
+}
+
+#[test]
+fn restart_only_settings_are_ignored() {
+    let current = CollectorConfig::default();
+    let mut next = current.clone();
+    next.metrics_port = 9191;
+    next.buffer_path = "/tmp/other.sqlite".to_string();
+
+    assert!(plan_reload(&current, &next).is_empty());
+}
+
+#[test]
+fn file_watcher_reports_each_modification_once() {
+    let path = temp_path("file_watcher");
+    fs::write(&path, "a = 1").expect("write config");
+    let mut watcher = FileWatcher::new(&path);
+    assert!(!watcher.changed());
+
+    let later = SystemTime::now() + Duration::from_secs(5);
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(later))
+        .expect("touch config");
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+
+    fs::remove_file(&path).expect("remove config");
+    assert!(!watcher.changed());
+}
+
+fn temp_path(label: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("sunspec_{label}_{nanos}.toml"))
+}
@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use collector_app::file_sink::{FileSink, Retention};
+use collector_app::replay::{read_archive, ReplayPlan, ReplaySource};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+// 2026-10-13 22:30:00 UTC
+const AT_MS: u64 = 1_791_930_600_000;
+const HOUR_MS: u64 = 3_600_000;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("sunspec_{name}_{nanos}"))
+}
+
+fn sample(collected_at_ms: u64) -> PollSample {
+    PollSample::new(
+        DeviceIdentity {
+            ip: "10.0.0.2".to_string(),
+            unit_id: 3,
+            base_address: None,
+        },
+        103,
+        "three_phase_inverter",
+        40_070,
+        vec![103, 50, 1_500],
+        collected_at_ms,
+    )
+}
+
+fn archive(dir: &PathBuf, hours: u64) {
+    let mut sink = FileSink::new(
+        dir,
+        Retention {
+            max_files: 0,
+            max_bytes: 0,
+        },
+    )
+    .expect("sink");
+    for hour in 0..hours {
+        sink.write(&sample(AT_MS + hour * HOUR_MS)).expect("write");
+    }
+    sink.flush().expect("flush");
+}
+
+#[test]
+fn archived_rows_read_back_with_original_timestamps() {
+    let dir = temp_dir("replay_rows");
+    archive(&dir, 1);
+
+    let plan = ReplayPlan::parse(dir.to_str().expect("path"), &dir).expect("plan");
+    let ReplaySource::Archive(files) = &plan.source else {
+        panic!("expected archive source");
+    };
+    assert_eq!(files.len(), 1);
+
+    fs::write(
+        &files[0],
+        fs::read_to_string(&files[0]).expect("read") + "garbage\n",
+    )
+    .expect("append");
+    let (samples, skipped) = read_archive(&files[0]).expect("read archive");
+    assert_eq!(skipped, 1);
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].collected_at_ms, AT_MS);
+    assert_eq!(samples[0].device.unit_id, 3);
+    assert_eq!(samples[0].model_name, "three_phase_inverter");
+    assert_eq!(samples[0].registers, vec![103, 50, 1_500]);
+    fs::remove_dir_all(&dir).expect("cleanup");
+}
+
+#[test]
+fn range_selects_archive_hours() {
+    let dir = temp_dir("replay_range");
+    archive(&dir, 4);
+
+    let plan = ReplayPlan::parse("2026-10-13T23..2026-10-14T01", &dir).expect("plan");
+    let ReplaySource::Archive(files) = &plan.source else {
+        panic!("expected archive source");
+    };
+    let names: Vec<String> = files
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["telemetry-2026-10-13T23.csv", "telemetry-2026-10-14T00.csv"]
+    );
+    assert!(plan.contains(AT_MS + HOUR_MS));
+    assert!(!plan.contains(AT_MS + 3 * HOUR_MS));
+
+    let open_ended = ReplayPlan::parse("2026-10-14..", &dir).expect("plan");
+    assert!(open_ended.contains(u64::MAX - 1));
+    assert!(ReplayPlan::parse("2026-10-14..2026-10-13", &dir).is_err());
+    assert!(ReplayPlan::parse("not-a-path", &dir).is_err());
+    fs::remove_dir_all(&dir).expect("cleanup");
+}
@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::time::Duration;
+
+use collector_app::resources::{
+    buffer_dir, parse_vm_rss, HealthState, HealthStatus, Pressure, ResourceSettings, ResourceUsage,
+};
+use poller_actor::PollThrottle;
+
+const MIB: u64 = 1024 * 1024;
+
+fn usage() -> ResourceUsage {
+    ResourceUsage {
+        free_disk_bytes: Some(500 * MIB),
+        buffer_bytes: Some(40 * MIB),
+        rss_bytes: Some(60 * MIB),
+        channel_depth: 10,
+        channel_capacity: 100,
+    }
+}
+
+#[test]
+fn limits_of_zero_are_not_checked() {
+    let settings = ResourceSettings::default();
+    let mut starved = usage();
+    starved.free_disk_bytes = Some(0);
+    starved.channel_depth = 100;
+    assert!(settings.pressures(&starved).is_empty());
+}
+
+#[test]
+fn crossed_limits_are_reported_in_order() {
+    let settings = ResourceSettings {
+        min_free_disk_bytes: 100 * MIB,
+        max_buffer_bytes: 64 * MIB,
+        max_rss_bytes: 128 * MIB,
+        max_channel_fill_pct: 80,
+        ..ResourceSettings::default()
+    };
+    assert!(settings.pressures(&usage()).is_empty());
+
+    let mut usage = usage();
+    usage.free_disk_bytes = Some(50 * MIB);
+    usage.buffer_bytes = Some(80 * MIB);
+    usage.rss_bytes = Some(200 * MIB);
+    usage.channel_depth = 81;
+    let pressures = settings.pressures(&usage);
+    assert_eq!(
+        pressures,
+        vec![
+            Pressure::Disk,
+            Pressure::Buffer,
+            Pressure::Memory,
+            Pressure::Channel
+        ]
+    );
+    let evicting: Vec<_> = pressures.into_iter().filter(|p| p.evicts()).collect();
+    assert_eq!(evicting, vec![Pressure::Disk, Pressure::Buffer]);
+
+    // Values that could not be read never cross a limit.
+    usage.free_disk_bytes = None;
+    usage.rss_bytes = None;
+    assert_eq!(
+        settings.pressures(&usage),
+        vec![Pressure::Buffer, Pressure::Channel]
+    );
+}
+
+#[test]
+fn health_turns_degraded_and_back() {
+    let health = HealthStatus::new();
+    assert_eq!(health.update(usage(), Vec::new(), 1, 0, 1_000), None);
+    assert_eq!(health.snapshot().since_ms, Some(1_000));
+
+    assert_eq!(
+        health.update(usage(), vec![Pressure::Disk], 2, 1_000, 2_000),
+        Some(HealthState::Ok)
+    );
+    assert_eq!(
+        health.update(usage(), vec![Pressure::Disk], 2, 500, 3_000),
+        None
+    );
+    let report = health.snapshot();
+    assert_eq!(report.status, HealthState::Degraded);
+    assert_eq!(report.since_ms, Some(2_000));
+    assert_eq!(report.throttle_factor, 2);
+    assert_eq!(report.evicted_rows, 1_500);
+
+    assert_eq!(
+        health.update(usage(), Vec::new(), 1, 0, 4_000),
+        Some(HealthState::Degraded)
+    );
+    let report = health.snapshot();
+    assert_eq!(report.status, HealthState::Ok);
+    assert!(report.pressures.is_empty());
+    let json = serde_json::to_value(&report).expect("json");
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["evicted_rows"], 1_500);
+}
+
+#[test]
+fn vm_rss_is_read_in_bytes() {
+    let status =
+        "Name:\tsunspec-collector\nVmPeak:\t  120000 kB\nVmRSS:\t   51200 kB\nThreads:\t8\n";
+    assert_eq!(parse_vm_rss(status), Some(50 * MIB));
+    assert_eq!(parse_vm_rss("Name:\tkthreadd\n"), None);
+}
+
+#[test]
+fn free_space_is_checked_where_the_buffer_lives() {
+    assert_eq!(
+        buffer_dir("/var/lib/sunspec-collector/buffer.sqlite"),
+        Path::new("/var/lib/sunspec-collector")
+    );
+    assert_eq!(buffer_dir("sunspec-buffer.sqlite"), Path::new("."));
+}
+
+#[test]
+fn throttle_stretches_the_poll_interval() {
+    let throttle = PollThrottle::default();
+    let shared = throttle.clone();
+    assert_eq!(
+        shared.interval(Duration::from_secs(5)),
+        Duration::from_secs(5)
+    );
+    throttle.set_factor(3);
+    assert_eq!(
+        shared.interval(Duration::from_secs(5)),
+        Duration::from_secs(15)
+    );
+    // A factor below 1 would poll faster than configured.
+    throttle.set_factor(0);
+    assert_eq!(shared.factor(), 1);
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use collector_app::restart::{Restart, RestartPolicy, RestartTracker};
+
+const SECOND_MS: u64 = 1_000;
+const MINUTE_MS: u64 = 60_000;
+const ID: &str = "10.0.0.9:1";
+
+fn policy(breaker_failures: u32) -> RestartPolicy {
+    RestartPolicy {
+        initial_delay_ms: SECOND_MS,
+        max_delay_ms: MINUTE_MS,
+        breaker_failures,
+    }
+}
+
+fn after_ms(ms: u64) -> Restart {
+    Restart::After(Duration::from_millis(ms))
+}
+
+#[test]
+fn delay_doubles_up_to_the_maximum() {
+    let policy = policy(0);
+    assert_eq!(policy.delay_ms(1), SECOND_MS);
+    assert_eq!(policy.delay_ms(2), 2 * SECOND_MS);
+    assert_eq!(policy.delay_ms(6), 32 * SECOND_MS);
+    assert_eq!(policy.delay_ms(7), MINUTE_MS);
+    assert_eq!(policy.delay_ms(u32::MAX), MINUTE_MS);
+}
+
+#[test]
+fn failures_in_a_row_back_off() {
+    let mut restarts = RestartTracker::new(policy(0));
+    assert_eq!(restarts.exited(ID, true, false), after_ms(SECOND_MS));
+    assert_eq!(restarts.exited(ID, true, false), after_ms(2 * SECOND_MS));
+    assert_eq!(restarts.exited(ID, true, false), after_ms(4 * SECOND_MS));
+    assert_eq!(restarts.failures(ID), 3);
+    // Other pollers keep their own count.
+    assert_eq!(
+        restarts.exited("10.0.0.10:1", true, false),
+        after_ms(SECOND_MS)
+    );
+}
+
+#[test]
+fn a_clean_cycle_or_exit_starts_the_count_over() {
+    let mut restarts = RestartTracker::new(policy(0));
+    restarts.exited(ID, true, false);
+    restarts.exited(ID, true, false);
+    assert_eq!(restarts.exited(ID, true, true), after_ms(SECOND_MS));
+    assert_eq!(restarts.failures(ID), 1);
+
+    restarts.exited(ID, true, false);
+    assert_eq!(restarts.exited(ID, false, false), after_ms(SECOND_MS));
+    assert_eq!(restarts.failures(ID), 0);
+}
+
+#[test]
+fn breaker_opens_after_the_configured_failures() {
+    let mut restarts = RestartTracker::new(policy(3));
+    restarts.exited(ID, true, false);
+    restarts.exited(ID, true, false);
+    assert_eq!(
+        restarts.exited(ID, true, false),
+        Restart::BreakerOpen { failures: 3 }
+    );
+    // A device rediscovered later starts with the short delay again.
+    assert_eq!(restarts.failures(ID), 0);
+    assert_eq!(restarts.exited(ID, true, false), after_ms(SECOND_MS));
+
+    restarts.forget(ID);
+    assert_eq!(restarts.failures(ID), 0);
+}
+
+#[test]
+fn breaker_of_zero_never_opens() {
+    let mut restarts = RestartTracker::new(policy(0));
+    for _ in 0..100 {
+        assert!(matches!(
+            restarts.exited(ID, true, false),
+            Restart::After(_)
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+use collector_app::routing::{check_template, render_topic, TopicRoute, TopicRouter};
+use collector_app::rules::ModelRange;
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+fn sample(ip: &str, unit_id: u8, model_id: u16, model_name: &str) -> PollSample {
+    let device = DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id,
+        base_address: None,
+    };
+    PollSample::new(device, model_id, model_name, 40_070, vec![0; 4], 0)
+}
+
+fn route(models: &[(u16, u16)], topic: &str) -> TopicRoute {
+    TopicRoute {
+        models: models
+            .iter()
+            .map(|&(first, last)| ModelRange { first, last })
+            .collect(),
+        ip: None,
+        unit_id: None,
+        topic: topic.to_string(),
+    }
+}
+
+#[test]
+fn placeholders_are_filled_from_the_sample() {
+    let meter = sample("192.168.1.30", 2, 203, "wye_meter");
+    assert_eq!(
+        render_topic("sunspec.{model_name}", &meter),
+        "sunspec.wye_meter"
+    );
+    assert_eq!(
+        render_topic("site.{device_ip}.{unit_id}.m{model_id}", &meter),
+        "site.192.168.1.30.2.m203"
+    );
+}
+
+#[test]
+fn characters_a_topic_cannot_hold_are_replaced() {
+    let device = sample("192.168.1.30", 1, 64_001, "vendor ext/1");
+    assert_eq!(
+        render_topic("sunspec.{model_name}", &device),
+        "sunspec.vendor_ext_1"
+    );
+}
+
+#[test]
+fn first_matching_route_wins() {
+    let router = TopicRouter::new(vec![
+        route(&[(201, 204)], "sunspec.meter"),
+        route(&[(101, 103), (111, 113)], "sunspec.inverter"),
+        route(&[], "sunspec.other.{model_id}"),
+    ]);
+    let meter = sample("192.168.1.30", 1, 203, "wye_meter");
+    let inverter = sample("192.168.1.20", 1, 113, "three_phase_inverter_float");
+    let battery = sample("192.168.1.40", 1, 802, "battery");
+    assert_eq!(router.topic(&meter, "sunspec.telemetry"), "sunspec.meter");
+    assert_eq!(
+        router.topic(&inverter, "sunspec.telemetry"),
+        "sunspec.inverter"
+    );
+    assert_eq!(
+        router.topic(&battery, "sunspec.telemetry"),
+        "sunspec.other.802"
+    );
+}
+
+#[test]
+fn unmatched_samples_keep_the_default_topic() {
+    let site = TopicRoute {
+        ip: Some("192.168.1.20".to_string()),
+        unit_id: Some(1),
+        ..route(&[], "site.{device_ip}.telemetry")
+    };
+    let router = TopicRouter::new(vec![site]);
+    let matched = sample("192.168.1.20", 1, 103, "three_phase_inverter");
+    let other_unit = sample("192.168.1.20", 2, 103, "three_phase_inverter");
+    assert_eq!(
+        router.topic(&matched, "sunspec.telemetry"),
+        "site.192.168.1.20.telemetry"
+    );
+    assert_eq!(
+        router.topic(&other_unit, "sunspec.telemetry"),
+        "sunspec.telemetry"
+    );
+    assert_eq!(
+        TopicRouter::default().topic(&matched, "sunspec.telemetry"),
+        "sunspec.telemetry"
+    );
+}
+
+#[test]
+fn templates_only_use_known_placeholders() {
+    assert!(check_template("sunspec.telemetry").is_ok());
+    assert!(check_template("site.{device_ip}.{unit_id}.{model_name}").is_ok());
+    assert!(check_template("sunspec.{serial}").is_err());
+    assert!(check_template("sunspec.{model_name").is_err());
+    assert!(check_template("sunspec.model_name}").is_err());
+}
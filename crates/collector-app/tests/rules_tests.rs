@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use collector_app::rules::{
+    DeviceModelFilter, DevicePolicy, DeviceProfile, DeviceRule, ModelFilter, ModelInterval,
+    ModelRange, ScanClassConfig,
+};
+use sunspec_parser::{CommonModel, ModelDefinition};
+use types::DeviceIdentity;
+
+fn common(manufacturer: &str, model: &str) -> CommonModel {
+    CommonModel {
+        manufacturer: manufacturer.to_string(),
+        model: model.to_string(),
+        ..CommonModel::default()
+    }
+}
+
+fn model(id: u16, start: u16) -> ModelDefinition {
+    ModelDefinition {
+        id,
+        name: format!("model_{id}"),
+        start,
+        length: 10,
+    }
+}
+
+fn fleet() -> (Vec<DeviceRule>, Vec<DeviceProfile>) {
+    let rules = vec![
+        DeviceRule {
+            manufacturer: Some("SMA".to_string()),
+            model_contains: None,
+            profile: "sma".to_string(),
+        },
+        DeviceRule {
+            manufacturer: None,
+            model_contains: Some("storage".to_string()),
+            profile: "storage".to_string(),
+        },
+    ];
+    let profiles = vec![
+        DeviceProfile {
+            name: "sma".to_string(),
+            skip_models: vec![160],
+            ..DeviceProfile::default()
+        },
+        DeviceProfile {
+            name: "storage".to_string(),
+            model_intervals: vec![ModelInterval {
+                model_id: 802,
+                interval_ms: 5_000,
+            }],
+            ..DeviceProfile::default()
+        },
+    ];
+    (rules, profiles)
+}
+
+#[test]
+fn rules_match_on_manufacturer_and_model() {
+    let (rules, _) = fleet();
+
+    assert!(rules[0].matches(Some(&common("sma", "Sunny Boy 5.0"))));
+    assert!(!rules[0].matches(Some(&common("SMA Solar", "Sunny Boy 5.0"))));
+    assert!(rules[1].matches(Some(&common("SMA", "Sunny Boy Storage 5.0"))));
+    assert!(!rules[1].matches(Some(&common("Fronius", "Symo 10.0"))));
+    assert!(!rules[0].matches(None));
+}
+
+#[test]
+fn matching_profiles_are_combined_in_rule_order() {
+    let (rules, profiles) = fleet();
+
+    let policy = DevicePolicy::resolve(
+        &rules,
+        &profiles,
+        Some(&common("SMA", "Sunny Boy Storage 5.0")),
+    );
+    assert_eq!(policy.profiles, vec!["sma", "storage"]);
+    assert_eq!(
+        policy.model_intervals.get(&802),
+        Some(&Duration::from_secs(5))
+    );
+
+    let models = policy.select_models(vec![
+        model(1, 40_002),
+        model(160, 40_070),
+        model(802, 40_120),
+    ]);
+    let ids: Vec<u16> = models.iter().map(|model| model.id).collect();
+    assert_eq!(ids, vec![1, 802]);
+}
+
+#[test]
+fn inventory_profile_applies_after_the_rules() {
+    let (rules, profiles) = fleet();
+
+    let policy = DevicePolicy::resolve(&rules, &profiles, Some(&common("SMA", "Sunny Boy 5.0")))
+        .with_profile(&profiles, Some("storage"));
+    assert_eq!(policy.profiles, vec!["sma", "storage"]);
+    assert_eq!(
+        policy.model_intervals.get(&802),
+        Some(&Duration::from_secs(5))
+    );
+
+    let policy = DevicePolicy::resolve(&rules, &profiles, None).with_profile(&profiles, None);
+    assert!(policy.profiles.is_empty());
+}
+
+#[test]
+fn last_profile_naming_a_class_sets_it() {
+    let (rules, mut profiles) = fleet();
+    profiles.push(DeviceProfile {
+        name: "grid_point".to_string(),
+        class: Some("meter".to_string()),
+        ..DeviceProfile::default()
+    });
+
+    let policy = DevicePolicy::resolve(&rules, &profiles, Some(&common("SMA", "Sunny Boy 5.0")));
+    assert_eq!(policy.class, None);
+    let policy = policy.with_profile(&profiles, Some("grid_point"));
+    assert_eq!(policy.class.as_deref(), Some("meter"));
+    let policy = policy.with_profile(&profiles, Some("storage"));
+    assert_eq!(policy.class.as_deref(), Some("meter"));
+}
+
+#[test]
+fn scan_classes_resolve_to_register_spans() {
+    let (rules, mut profiles) = fleet();
+    profiles[0].scan_classes = vec![ScanClassConfig {
+        model_id: 103,
+        points: vec!["W".to_string(), "Hz".to_string(), "St".to_string()],
+        full_interval_ms: 60_000,
+    }];
+
+    let policy = DevicePolicy::resolve(&rules, &profiles, Some(&common("SMA", "Sunny Boy 5.0")));
+    let class = policy.scan_classes.get(&103).expect("scan class");
+    assert_eq!((class.offset, class.length), (14, 25));
+    assert_eq!(class.full_interval, Duration::from_secs(60));
+    let policy = DevicePolicy::resolve(&rules, &profiles, Some(&common("Fronius", "Symo 10.0")));
+    assert!(policy.scan_classes.is_empty());
+}
+
+#[test]
+fn unmatched_device_keeps_all_models() {
+    let (rules, profiles) = fleet();
+
+    let policy = DevicePolicy::resolve(&rules, &profiles, Some(&common("Fronius", "Symo 10.0")));
+    assert!(policy.profiles.is_empty());
+    assert_eq!(
+        policy
+            .select_models(vec![model(1, 40_002), model(160, 40_070)])
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn only_models_restricts_polling() {
+    let rules = vec![DeviceRule {
+        manufacturer: None,
+        model_contains: None,
+        profile: "minimal".to_string(),
+    }];
+    let profiles = vec![DeviceProfile {
+        name: "minimal".to_string(),
+        only_models: vec![103],
+        ..DeviceProfile::default()
+    }];
+
+    let policy = DevicePolicy::resolve(&rules, &profiles, None);
+    let models = policy.select_models(vec![model(1, 40_002), model(103, 40_070)]);
+    assert_eq!(models.len(), 1);
+    assert_eq!(models[0].id, 103);
+}
+
+#[test]
+fn model_filters_apply_globally_and_per_device() {
+    let global = ModelFilter {
+        include: Vec::new(),
+        exclude: vec![ModelRange::parse("64001-65535").expect("range")],
+    };
+    let devices = vec![DeviceModelFilter {
+        ip: "192.168.1.20".to_string(),
+        unit_id: Some(2),
+        filter: ModelFilter {
+            include: Vec::new(),
+            exclude: vec![ModelRange::single(1)],
+        },
+    }];
+    let discovered = || vec![model(1, 40_002), model(103, 40_070), model(64_110, 40_122)];
+    let device = |unit_id: u8| DeviceIdentity {
+        ip: "192.168.1.20".to_string(),
+        unit_id,
+        base_address: None,
+    };
+    let polled = |unit_id: u8| -> Vec<u16> {
+        DevicePolicy::default()
+            .with_model_filters(&global, &devices, &device(unit_id))
+            .select_models(discovered())
+            .iter()
+            .map(|model| model.id)
+            .collect()
+    };
+
+    assert_eq!(polled(1), vec![1, 103]);
+    assert_eq!(polled(2), vec![103]);
+
+    assert_eq!(ModelRange::parse(" 160 "), Some(ModelRange::single(160)));
+    assert_eq!(ModelRange::parse("200-100"), None);
+    assert_eq!(ModelRange::parse("abc"), None);
+}
@@ -0,0 +1,421 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use collector_app::simulator::Simulator;
+use modbus_client::{ClientConfig, ModbusClient};
+use poller_actor::{group_reads, ActorConfig, PollerActor, SampleCounters};
+use sunspec_parser::{
+    decode_common, decode_inverter, decode_points, parse_models_from_registers, ModelDefinition,
+};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch};
+use types::DeviceIdentity;
+
+const BASE_ADDRESS: u16 = 40_000;
+const HOUR_MS: u64 = 3_600_000;
+
+#[test]
+fn register_map_parses_as_sunspec() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1, 2], &[101, 203]).expect("simulator");
+    let registers = simulator.registers(2, 0).expect("unit 2");
+    assert_eq!(registers.len(), simulator.map_len());
+
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    let ids: Vec<u16> = models.iter().map(|model| model.id).collect();
+    assert_eq!(ids, vec![1, 101, 203]);
+
+    let block = |id: u16| {
+        let model = models.iter().find(|model| model.id == id).expect("model");
+        let offset = usize::from(model.start - BASE_ADDRESS);
+        &registers[offset..offset + usize::from(model.length)]
+    };
+    let common = decode_common(1, block(1)).expect("common model");
+    assert_eq!(common.manufacturer, "SunSpec Simulator");
+    assert_eq!(common.serial_number, "SIM00002");
+
+    let points = decode_points(101, block(101)).expect("inverter points");
+    let point = |name: &str| {
+        points
+            .iter()
+            .find(|(point, _)| *point == name)
+            .map(|(_, v)| *v)
+    };
+    assert!(point("W").is_some_and(|w| w > 0.0));
+    assert_eq!(point("Hz"), Some(50.0));
+    // Single phase: the other phases are not implemented.
+    assert!(point("AphA").is_some());
+    assert_eq!(point("AphB"), None);
+
+    assert!(simulator.registers(3, 0).is_none());
+    assert!(Simulator::new(BASE_ADDRESS, &[1], &[160]).is_err());
+    assert!(Simulator::new(BASE_ADDRESS, &[], &[103]).is_err());
+}
+
+#[test]
+fn energy_counter_follows_power() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1], &[103]).expect("simulator");
+    let read = |simulator: &mut Simulator, now_ms: u64| {
+        let registers = simulator.registers(1, now_ms).expect("unit 1");
+        // SunS marker and common model come first.
+        decode_inverter(103, &registers[70..122]).expect("inverter")
+    };
+
+    let first = read(&mut simulator, 0);
+    let second = read(&mut simulator, HOUR_MS);
+    let power_w = second.power_w.expect("power");
+    let produced_wh = second.energy_wh.expect("energy") - first.energy_wh.expect("energy");
+    assert!(
+        (produced_wh - power_w).abs() <= 1.0,
+        "{produced_wh} Wh for {power_w} W"
+    );
+    assert_eq!(second.state, Some(4));
+}
+
+#[test]
+fn synthetic_samples_cover_every_model() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[4], &[103, 203])
+        .expect("simulator")
+        .with_rated_power(4, 12_000.0);
+    let device = DeviceIdentity {
+        ip: "synthetic-west".to_string(),
+        unit_id: 4,
+        base_address: Some(BASE_ADDRESS),
+    };
+    let samples = simulator.samples(&device, HOUR_MS);
+    let ids: Vec<u16> = samples.iter().map(|sample| sample.model_id).collect();
+    assert_eq!(ids, vec![1, 103, 203]);
+    assert!(samples
+        .iter()
+        .all(|sample| sample.device == device && sample.collected_at_ms == HOUR_MS));
+
+    let inverter = decode_inverter(103, &samples[1].registers).expect("inverter");
+    assert!(inverter.power_w.is_some_and(|w| w > 5_000.0 && w <= 12_000.0));
+
+    let other = DeviceIdentity {
+        unit_id: 5,
+        ..device
+    };
+    assert!(simulator.samples(&other, HOUR_MS).is_empty());
+}
+
+#[tokio::test]
+async fn serves_modbus_tcp_reads() {
+    let simulator = Simulator::new(BASE_ADDRESS, &[1], &[103]).expect("simulator");
+    let map_len = simulator.map_len() as u16;
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let server = tokio::spawn(simulator.serve(listener));
+
+    let client = ModbusClient::connect(ClientConfig {
+        port,
+        max_batch_size: Some(100),
+        retry_count: 0,
+        ..ClientConfig::default()
+    })
+    .await
+    .expect("connect");
+    let registers = client
+        .read_range(1, BASE_ADDRESS, map_len + 10)
+        .await
+        .expect("read");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    assert_eq!(models.len(), 2);
+    assert!(registers[usize::from(map_len)..]
+        .iter()
+        .all(|&value| value == 0));
+
+    assert!(client.read_range(9, BASE_ADDRESS, 2).await.is_err());
+    assert!(client.read_range(1, BASE_ADDRESS - 1, 2).await.is_err());
+    server.abort();
+}
+
+#[tokio::test]
+async fn poller_reads_initial_snapshot_first() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1], &[103, 203]).expect("simulator");
+    let registers = simulator.registers(1, 0).expect("unit 1");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let server = tokio::spawn(simulator.serve(listener));
+
+    let (tx, mut rx) = mpsc::channel(16);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let counters = Arc::new(SampleCounters::default());
+    let polled = models
+        .iter()
+        .filter(|model| model.id == 103)
+        .cloned()
+        .collect();
+    let actor = PollerActor::new(
+        DeviceIdentity {
+            ip: "127.0.0.1".to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        ClientConfig {
+            port,
+            ..ClientConfig::default()
+        },
+        polled,
+        tx,
+        shutdown_rx,
+        ActorConfig {
+            poll_interval: Duration::from_millis(20),
+            ..ActorConfig::default()
+        },
+    )
+    .with_initial_snapshot(models)
+    .with_counters(counters.clone());
+    let poller = tokio::spawn(actor.run());
+
+    let mut received = Vec::new();
+    while received.len() < 4 {
+        let sample = rx.recv().await.expect("sample");
+        received.push((
+            sample.model_id,
+            sample.baseline,
+            sample.sequence,
+            sample.cycle_id,
+        ));
+    }
+    assert_eq!(
+        received,
+        vec![
+            (1, true, 1, 1),
+            (103, true, 2, 1),
+            (203, true, 3, 1),
+            (103, false, 4, 2),
+        ]
+    );
+
+    shutdown_tx.send(true).expect("shutdown");
+    poller.await.expect("join").expect("poller");
+    server.abort();
+    // A respawned poller sharing the counters continues the numbering.
+    assert!(counters.next_sequence() > 4);
+}
+
+#[test]
+fn adjacent_models_share_a_read_up_to_the_batch_size() {
+    let model = |id: u16, start: u16, length: u16| ModelDefinition {
+        id,
+        name: format!("model_{id}"),
+        start,
+        length,
+    };
+    let models = [
+        model(203, 40_122, 107),
+        model(1, 40_002, 68),
+        model(103, 40_070, 52),
+        model(160, 40_300, 20),
+    ];
+    let spans = |max_registers: Option<u16>| -> Vec<(u16, u16, Vec<u16>)> {
+        group_reads(models.iter().collect(), max_registers)
+            .into_iter()
+            .map(|group| {
+                let ids = group.models.iter().map(|model| model.id).collect();
+                (group.start, group.length, ids)
+            })
+            .collect()
+    };
+
+    // One read per model, in the order given, unless coalescing.
+    assert_eq!(
+        spans(None),
+        vec![
+            (40_122, 107, vec![203]),
+            (40_002, 68, vec![1]),
+            (40_070, 52, vec![103]),
+            (40_300, 20, vec![160]),
+        ]
+    );
+    assert_eq!(
+        spans(Some(125)),
+        vec![
+            (40_002, 120, vec![1, 103]),
+            (40_122, 107, vec![203]),
+            (40_300, 20, vec![160]),
+        ]
+    );
+    assert_eq!(spans(Some(250))[0], (40_002, 227, vec![1, 103, 203]));
+    // A model longer than the batch size is read alone and chunked by the client.
+    assert_eq!(spans(Some(64))[0], (40_002, 68, vec![1]));
+}
+
+#[tokio::test]
+async fn coalesced_reads_publish_every_model() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1], &[103, 203]).expect("simulator");
+    let registers = simulator.registers(1, 0).expect("unit 1");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let server = tokio::spawn(simulator.serve(listener));
+
+    let (tx, mut rx) = mpsc::channel(16);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let actor = PollerActor::new(
+        DeviceIdentity {
+            ip: "127.0.0.1".to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        ClientConfig {
+            port,
+            ..ClientConfig::default()
+        },
+        models.clone(),
+        tx,
+        shutdown_rx,
+        ActorConfig {
+            poll_interval: Duration::from_millis(20),
+            coalesce_reads: true,
+            ..ActorConfig::default()
+        },
+    );
+    let status = actor.status();
+    let poller = tokio::spawn(actor.run());
+
+    for model in &models {
+        let sample = rx.recv().await.expect("sample");
+        assert_eq!(sample.model_id, model.id);
+        assert_eq!(sample.start, model.start);
+        assert_eq!(sample.registers.len(), usize::from(model.length));
+        assert_eq!(sample.registers[0], model.id);
+        assert_eq!(sample.registers[1], model.length - 2);
+    }
+    // The next cycle's first sample is sent after the first cycle is recorded.
+    rx.recv().await.expect("sample");
+    // Models 1 and 103 fit in one read, 203 needs its own.
+    assert_eq!(status.borrow().last_cycle_traffic.requests, 2);
+
+    shutdown_tx.send(true).expect("shutdown");
+    poller.await.expect("join").expect("poller");
+    server.abort();
+}
+
+#[tokio::test]
+async fn failing_model_is_quarantined_without_failing_the_poller() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1], &[103]).expect("simulator");
+    let registers = simulator.registers(1, 0).expect("unit 1");
+    let mut models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    // Below the base address, which the simulator refuses to read.
+    models.push(ModelDefinition {
+        id: 160,
+        name: "mppt".to_string(),
+        start: BASE_ADDRESS - 100,
+        length: 20,
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let server = tokio::spawn(simulator.serve(listener));
+
+    let (tx, mut rx) = mpsc::channel(16);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let actor = PollerActor::new(
+        DeviceIdentity {
+            ip: "127.0.0.1".to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        ClientConfig {
+            port,
+            retry_count: 0,
+            ..ClientConfig::default()
+        },
+        models,
+        tx,
+        shutdown_rx,
+        ActorConfig {
+            poll_interval: Duration::from_millis(20),
+            model_quarantine_after: 2,
+            model_probe_interval: Duration::from_secs(3_600),
+            ..ActorConfig::default()
+        },
+    );
+    let status = actor.status();
+    let poller = tokio::spawn(actor.run());
+
+    // The other models keep publishing every cycle.
+    loop {
+        let sample = rx.recv().await.expect("sample");
+        assert_ne!(sample.model_id, 160);
+        if sample.cycle_id >= 5 {
+            break;
+        }
+    }
+    {
+        let status = status.borrow();
+        let failing = status
+            .models
+            .iter()
+            .find(|model| model.model_id == 160)
+            .expect("model 160 health");
+        assert!(failing.quarantined());
+        assert_eq!(failing.consecutive_errors, 2);
+        assert_eq!(failing.last_success_ms, None);
+        assert!(status
+            .models
+            .iter()
+            .filter(|model| model.model_id != 160)
+            .all(|model| !model.quarantined() && model.last_success_ms.is_some()));
+        // Cycles without the quarantined model count as clean again.
+        assert!(status.last_success_ms.is_some());
+        assert_eq!(status.consecutive_errors, 0);
+    }
+
+    shutdown_tx.send(true).expect("shutdown");
+    poller.await.expect("join").expect("poller");
+    server.abort();
+}
+
+#[tokio::test]
+async fn poller_serves_triggered_reads_between_cycles() {
+    let mut simulator = Simulator::new(BASE_ADDRESS, &[1], &[103, 203]).expect("simulator");
+    let registers = simulator.registers(1, 0).expect("unit 1");
+    let models = parse_models_from_registers(BASE_ADDRESS, &registers).expect("model list");
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let server = tokio::spawn(simulator.serve(listener));
+
+    let (tx, mut rx) = mpsc::channel(16);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let (trigger_tx, trigger_rx) = watch::channel(0u64);
+    let (reads_tx, mut reads_rx) = mpsc::channel(16);
+    let actor = PollerActor::new(
+        DeviceIdentity {
+            ip: "127.0.0.1".to_string(),
+            unit_id: 1,
+            base_address: None,
+        },
+        ClientConfig {
+            port,
+            ..ClientConfig::default()
+        },
+        models,
+        tx,
+        shutdown_rx,
+        ActorConfig {
+            poll_interval: Duration::from_secs(60),
+            ..ActorConfig::default()
+        },
+    )
+    .with_triggered_reads(trigger_rx, vec![103, 203], reads_tx);
+    let poller = tokio::spawn(actor.run());
+
+    // Wait for the first cycle, then trigger while the poller sleeps.
+    for _ in 0..3 {
+        rx.recv().await.expect("sample");
+    }
+    trigger_tx.send(5).expect("trigger");
+    let mut triggered = Vec::new();
+    while triggered.len() < 2 {
+        let read = reads_rx.recv().await.expect("triggered read");
+        triggered.push((read.trigger_id, read.sample.model_id, read.sample.sequence));
+    }
+    assert_eq!(triggered, vec![(5, 103, 0), (5, 203, 0)]);
+    assert!(rx.try_recv().is_err());
+
+    shutdown_tx.send(true).expect("shutdown");
+    poller.await.expect("join").expect("poller");
+    server.abort();
+}
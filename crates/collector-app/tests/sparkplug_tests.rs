@@ -0,0 +1,122 @@
+use collector_app::sparkplug::{EdgeNode, Metric, MetricValue, Payload};
+use types::DeviceIdentity;
+
+fn device() -> DeviceIdentity {
+    DeviceIdentity {
+        ip: "10.0.0.2".to_string(),
+        unit_id: 1,
+        base_address: None,
+    }
+}
+
+fn points(values: &[(&str, f64)]) -> Vec<(String, f64)> {
+    values
+        .iter()
+        .map(|(name, value)| (name.to_string(), *value))
+        .collect()
+}
+
+#[test]
+fn payload_encodes_protobuf_wire_format() {
+    let payload = Payload {
+        timestamp: 1,
+        metrics: vec![Metric {
+            name: None,
+            alias: Some(3),
+            timestamp: 1,
+            value: MetricValue::Boolean(true),
+        }],
+        seq: Some(5),
+    };
+    assert_eq!(
+        payload.encode(),
+        vec![0x08, 0x01, 0x12, 0x08, 0x10, 0x03, 0x18, 0x01, 0x20, 0x0B, 0x70, 0x01, 0x18, 0x05]
+    );
+
+    let payload = Payload {
+        timestamp: 300,
+        metrics: vec![Metric {
+            name: Some("W".to_string()),
+            alias: None,
+            timestamp: 300,
+            value: MetricValue::Double(1.5),
+        }],
+        seq: None,
+    };
+    let mut expected = vec![0x08, 0xAC, 0x02, 0x12, 0x11, 0x0A, 0x01, b'W'];
+    expected.extend([0x18, 0xAC, 0x02, 0x20, 0x0A, 0x69]);
+    expected.extend(1.5f64.to_le_bytes());
+    assert_eq!(payload.encode(), expected);
+}
+
+#[test]
+fn device_is_born_before_data_and_aliases_are_reused() {
+    let mut node = EdgeNode::new("site", "edge");
+
+    let birth = node.birth(1_000);
+    assert_eq!(birth.topic, "spBv1.0/site/NBIRTH/edge");
+    assert_eq!(birth.payload.seq, Some(0));
+
+    let first = node
+        .device_data(&device(), &points(&[("inverter/W", 500.0)]), 1_000)
+        .expect("message");
+    assert_eq!(first.topic, "spBv1.0/site/DBIRTH/edge/10.0.0.2-1");
+    assert_eq!(first.payload.seq, Some(1));
+    assert_eq!(first.payload.metrics[0].name.as_deref(), Some("inverter/W"));
+    let alias = first.payload.metrics[0].alias;
+
+    let second = node
+        .device_data(&device(), &points(&[("inverter/W", 600.0)]), 2_000)
+        .expect("message");
+    assert_eq!(second.topic, "spBv1.0/site/DDATA/edge/10.0.0.2-1");
+    assert_eq!(second.payload.seq, Some(2));
+    assert_eq!(second.payload.metrics[0].name, None);
+    assert_eq!(second.payload.metrics[0].alias, alias);
+    assert_eq!(second.payload.metrics[0].value, MetricValue::Double(600.0));
+}
+
+#[test]
+fn new_metric_triggers_rebirth_with_all_metrics() {
+    let mut node = EdgeNode::new("site", "edge");
+    node.birth(0);
+    node.device_data(&device(), &points(&[("inverter/W", 500.0)]), 0);
+
+    let rebirth = node
+        .device_data(&device(), &points(&[("inverter/Hz", 50.0)]), 1_000)
+        .expect("message");
+    assert_eq!(rebirth.topic, "spBv1.0/site/DBIRTH/edge/10.0.0.2-1");
+    assert_eq!(rebirth.payload.metrics.len(), 2);
+}
+
+#[test]
+fn death_certificates() {
+    let mut node = EdgeNode::new("site", "edge");
+    node.birth(0);
+    assert!(node.device_death(&device(), 0).is_none());
+
+    node.device_data(&device(), &points(&[("inverter/W", 500.0)]), 0);
+    let device_death = node.device_death(&device(), 1_000).expect("born device");
+    assert_eq!(device_death.topic, "spBv1.0/site/DDEATH/edge/10.0.0.2-1");
+    assert_eq!(device_death.payload.seq, Some(2));
+
+    let death = node.death(2_000);
+    assert_eq!(death.topic, "spBv1.0/site/NDEATH/edge");
+    assert_eq!(death.payload.seq, None);
+    assert_eq!(death.payload.metrics[0].value, MetricValue::UInt64(0));
+
+    let birth = node.birth(3_000);
+    assert_eq!(birth.payload.metrics[0].value, MetricValue::UInt64(1));
+}
+
+#[test]
+fn sequence_numbers_wrap_at_256() {
+    let mut node = EdgeNode::new("site", "edge");
+    node.birth(0);
+    let mut last = None;
+    for at_ms in 0..256 {
+        last = node
+            .device_data(&device(), &points(&[("inverter/W", 1.0)]), at_ms)
+            .and_then(|message| message.payload.seq);
+    }
+    assert_eq!(last, Some(0));
+}
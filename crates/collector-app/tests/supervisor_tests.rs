@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use collector_app::supervisor::{is_stalled, PollerRegistry, TrafficTracker};
+use modbus_client::ClientStats;
+use poller_actor::{PollerState, PollerStatus};
+use tokio::sync::watch;
+
+#[test]
+fn running_poller_without_recent_success_is_stalled() {
+    let status = PollerStatus {
+        state: PollerState::Running,
+        started_at_ms: 1_000,
+        last_success_ms: Some(2_000),
+        ..PollerStatus::default()
+    };
+
+    assert!(!is_stalled(&status, 11_000, Duration::from_secs(10)));
+    assert!(is_stalled(&status, 12_001, Duration::from_secs(10)));
+}
+
+#[test]
+fn stopped_poller_is_not_stalled() {
+    let status = PollerStatus {
+        state: PollerState::Failed,
+        started_at_ms: 0,
+        ..PollerStatus::default()
+    };
+
+    assert!(!is_stalled(&status, 1_000_000, Duration::from_secs(1)));
+}
+
+#[test]
+fn paused_poller_is_not_stalled_and_gets_time_after_resume() {
+    let mut status = PollerStatus {
+        state: PollerState::Paused,
+        started_at_ms: 0,
+        last_success_ms: Some(1_000),
+        ..PollerStatus::default()
+    };
+    assert!(!is_stalled(&status, 100_000, Duration::from_secs(10)));
+
+    status.state = PollerState::Connecting;
+    status.resumed_at_ms = Some(100_000);
+    assert!(!is_stalled(&status, 105_000, Duration::from_secs(10)));
+    assert!(is_stalled(&status, 110_001, Duration::from_secs(10)));
+}
+
+#[test]
+fn registry_reports_latest_status() {
+    let registry = PollerRegistry::new();
+    let (tx_a, rx_a) = watch::channel(PollerStatus {
+        state: PollerState::Running,
+        started_at_ms: 0,
+        ..PollerStatus::default()
+    });
+    let (_tx_b, rx_b) = watch::channel(PollerStatus {
+        state: PollerState::Running,
+        started_at_ms: 0,
+        last_success_ms: Some(9_500),
+        ..PollerStatus::default()
+    });
+    registry.register("10.0.0.2", rx_a);
+    registry.register("10.0.0.3", rx_b);
+
+    let stall_after = Duration::from_secs(1);
+    assert_eq!(registry.stalled(10_000, stall_after), vec!["10.0.0.2"]);
+
+    tx_a.send_modify(|status| status.last_success_ms = Some(9_900));
+    assert!(registry.stalled(10_000, stall_after).is_empty());
+
+    let snapshot = registry.snapshot(10_000, stall_after);
+    assert_eq!(snapshot.len(), 2);
+    assert_eq!(snapshot[0].id, "10.0.0.2");
+    assert_eq!(snapshot[0].status.last_success_ms, Some(9_900));
+}
+
+#[test]
+fn traffic_tracker_reports_interval_deltas() {
+    let registry = PollerRegistry::new();
+    let (tx, rx) = watch::channel(PollerStatus {
+        state: PollerState::Running,
+        started_at_ms: 100,
+        ..PollerStatus::default()
+    });
+    registry.register("10.0.0.2", rx);
+    let mut tracker = TrafficTracker::new();
+
+    tx.send_modify(|status| {
+        status.traffic.requests = 10;
+        status.traffic.bytes_sent = 120;
+    });
+    let first = tracker.interval(&registry.snapshot(0, Duration::from_secs(60)));
+    assert_eq!(first[0].1.requests, 10);
+
+    tx.send_modify(|status| {
+        status.traffic.requests = 14;
+        status.traffic.bytes_sent = 168;
+        status.traffic.errors.insert("timeout".to_string(), 1);
+    });
+    let second = tracker.interval(&registry.snapshot(0, Duration::from_secs(60)));
+    assert_eq!(second[0].1.requests, 4);
+    assert_eq!(second[0].1.bytes_sent, 48);
+    assert_eq!(second[0].1.error_count(), 1);
+
+    // A respawned poller starts its counters from zero.
+    let (_tx, rx) = watch::channel(PollerStatus {
+        state: PollerState::Running,
+        started_at_ms: 200,
+        traffic: ClientStats {
+            requests: 20,
+            ..ClientStats::default()
+        },
+        ..PollerStatus::default()
+    });
+    registry.register("10.0.0.2", rx);
+    let third = tracker.interval(&registry.snapshot(0, Duration::from_secs(60)));
+    assert_eq!(third[0].1.requests, 20);
+}
@@ -0,0 +1,78 @@
+use collector_app::timesync::{parse_chrony_tracking, parse_timedatectl, SyncStatus};
+use poller_actor::{PollSample, SampleClock};
+use types::DeviceIdentity;
+
+const CHRONY_SYNCED: &str = "A29FC87B,ntp1.example.net,3,1760000000.123456789,-0.000012345,0.000001234,0.000020000,-12.345,0.001,0.050,0.012345,0.001234,64.5,Normal\n";
+const CHRONY_UNSYNCED: &str =
+    "00000000,,0,0.000000000,0.000000000,0.000000000,0.000000000,0.000,0.000,0.000,1.000000000,1.000000000,0.0,Not synchronised\n";
+
+#[test]
+fn chrony_tracking_gives_leap_status_and_offset() {
+    let status = parse_chrony_tracking(CHRONY_SYNCED).expect("tracking");
+    assert!(status.synchronized);
+    assert_eq!(status.offset_seconds, Some(-0.000012345));
+    assert!(status.is_trusted(1_000));
+
+    let status = parse_chrony_tracking(CHRONY_UNSYNCED).expect("tracking");
+    assert!(!status.synchronized);
+    assert!(!status.is_trusted(1_000));
+
+    assert_eq!(parse_chrony_tracking("506 Cannot talk to daemon"), None);
+}
+
+#[test]
+fn large_offsets_are_not_trusted() {
+    let status = SyncStatus {
+        synchronized: true,
+        offset_seconds: Some(-2.5),
+    };
+    assert!(!status.is_trusted(1_000));
+    assert!(status.is_trusted(3_000));
+}
+
+#[test]
+fn timedatectl_reports_yes_or_no() {
+    assert_eq!(
+        parse_timedatectl("yes\n"),
+        Some(SyncStatus {
+            synchronized: true,
+            offset_seconds: None,
+        })
+    );
+    assert!(!parse_timedatectl("no\n").expect("status").synchronized);
+    assert_eq!(parse_timedatectl(""), None);
+}
+
+#[test]
+fn sample_clock_stamps_wall_and_monotonic_time() {
+    let clock = SampleClock::anchored();
+    let first = clock.now();
+    let shared = clock.clone();
+    shared.set_unsynchronized(true);
+    let second = clock.now();
+    assert!(!first.clock_unsynchronized);
+    assert!(second.clock_unsynchronized);
+    assert!(second.monotonic_ms >= first.monotonic_ms);
+    assert!(second.collected_at_ms >= first.collected_at_ms);
+    // Anchored time only moves with the monotonic clock.
+    assert_eq!(
+        second.collected_at_ms - first.collected_at_ms,
+        second.monotonic_ms - first.monotonic_ms
+    );
+
+    let device = DeviceIdentity {
+        ip: "192.168.1.20".to_string(),
+        unit_id: 1,
+        base_address: Some(40_000),
+    };
+    let sample = clock.stamp(PollSample::new(
+        device,
+        103,
+        "three_phase_inverter".to_string(),
+        40_070,
+        vec![103, 50],
+        0,
+    ));
+    assert!(sample.collected_at_ms >= second.collected_at_ms);
+    assert!(sample.clock_unsynchronized);
+}
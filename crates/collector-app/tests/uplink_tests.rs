@@ -0,0 +1,150 @@
+use std::time::{Duration, Instant};
+
+use buffer::PendingCommit;
+use collector_app::uplink::{
+    assign_lanes, live_quotas, split_by_topic, CommitMarker, DrainOrder, WriteThrough,
+};
+use poller_actor::PollSample;
+use types::DeviceIdentity;
+
+fn sample(ip: &str, unit_id: u8) -> PollSample {
+    let device = DeviceIdentity {
+        ip: ip.to_string(),
+        unit_id,
+        base_address: None,
+    };
+    PollSample::new(device, 103, "three_phase_inverter", 40_070, vec![0; 4], 0)
+}
+
+#[test]
+fn devices_keep_their_lane_and_order() {
+    let samples = vec![
+        sample("10.0.0.2", 1),
+        sample("10.0.0.3", 1),
+        sample("10.0.0.2", 2),
+        sample("10.0.0.2", 1),
+        sample("10.0.0.4", 1),
+        sample("10.0.0.3", 1),
+    ];
+    assert_eq!(
+        assign_lanes(&samples, 2),
+        vec![vec![0, 2, 3], vec![1, 4, 5]]
+    );
+    assert_eq!(
+        assign_lanes(&samples, 3),
+        vec![vec![0, 3, 4], vec![1, 5], vec![2]]
+    );
+}
+
+#[test]
+fn unused_lanes_are_left_out() {
+    let samples = vec![sample("10.0.0.2", 1), sample("10.0.0.2", 1)];
+    assert_eq!(assign_lanes(&samples, 4), vec![vec![0, 1]]);
+    assert_eq!(assign_lanes(&samples, 0), vec![vec![0, 1]]);
+    assert!(assign_lanes(&[], 4).is_empty());
+}
+
+#[test]
+fn lanes_split_by_topic_in_order_of_first_appearance() {
+    let topics = [
+        "sunspec.meter",
+        "sunspec.inverter",
+        "sunspec.meter",
+        "sunspec.storage",
+    ];
+    assert_eq!(
+        split_by_topic(&[0, 1, 2, 3], &topics),
+        vec![
+            ("sunspec.meter", vec![0, 2]),
+            ("sunspec.inverter", vec![1]),
+            ("sunspec.storage", vec![3]),
+        ]
+    );
+    assert_eq!(
+        split_by_topic(&[1, 3], &topics),
+        vec![("sunspec.inverter", vec![1]), ("sunspec.storage", vec![3])]
+    );
+}
+
+#[test]
+fn commit_marker_confirms_only_its_own_commit() {
+    let commit = PendingCommit {
+        seq: 7,
+        ids: vec![11, 12, 14],
+        prepared_at_ms: 1_791_930_600_000,
+    };
+    let marker = CommitMarker::new(&commit);
+    assert_eq!(marker.rows, 3);
+    let json = serde_json::to_vec(&marker).expect("json");
+    let read_back: CommitMarker = serde_json::from_slice(&json).expect("parse");
+    assert!(read_back.confirms(&commit));
+
+    let next = PendingCommit {
+        seq: 8,
+        ..commit.clone()
+    };
+    assert!(!marker.confirms(&next));
+    // Same sequence from an earlier buffer file.
+    let recreated = PendingCommit {
+        prepared_at_ms: 1_791_930_000_000,
+        ..commit
+    };
+    assert!(!marker.confirms(&recreated));
+}
+
+#[test]
+fn write_through_waits_for_an_empty_buffer() {
+    let mut mode = WriteThrough::new(Duration::from_secs(1));
+    let start = Instant::now();
+    assert!(!mode.is_direct());
+    // No checks while the broker is not known to be up.
+    assert!(!mode.should_check(false, start));
+
+    assert!(mode.should_check(true, start));
+    mode.checked(12);
+    assert!(!mode.is_direct());
+    assert!(!mode.should_check(true, start + Duration::from_millis(500)));
+
+    assert!(mode.should_check(true, start + Duration::from_secs(1)));
+    mode.checked(0);
+    assert!(mode.is_direct());
+    assert!(!mode.should_check(true, start + Duration::from_secs(5)));
+}
+
+#[test]
+fn write_through_falls_back_on_failure_or_broker_loss() {
+    let mut mode = WriteThrough::new(Duration::from_secs(1));
+    let start = Instant::now();
+    assert!(mode.should_check(true, start));
+    mode.checked(0);
+    mode.fall_back();
+    assert!(!mode.is_direct());
+
+    assert!(mode.should_check(true, start + Duration::from_secs(1)));
+    mode.checked(0);
+    assert!(!mode.should_check(false, start + Duration::from_secs(2)));
+    assert!(!mode.is_direct());
+}
+
+#[test]
+fn drain_orders_parse_and_live_drains_keep_a_backlog_share() {
+    assert_eq!(
+        DrainOrder::parse("oldest_first"),
+        Some(DrainOrder::OldestFirst)
+    );
+    assert_eq!(
+        DrainOrder::parse(" Newest-First "),
+        Some(DrainOrder::NewestFirst)
+    );
+    assert_eq!(
+        DrainOrder::parse("live_priority"),
+        Some(DrainOrder::LivePriority)
+    );
+    assert_eq!(DrainOrder::parse("lifo"), None);
+
+    assert_eq!(live_quotas(100, 20), (80, 20));
+    assert_eq!(live_quotas(10, 15), (8, 2));
+    // The backlog never stalls completely.
+    assert_eq!(live_quotas(100, 0), (99, 1));
+    assert_eq!(live_quotas(1, 50), (0, 1));
+}
@@ -105,6 +105,7 @@ pub async fn discover_subnet(
                         found.push(DeviceIdentity {
                             ip: ip.to_string(),
                             unit_id: uid,
+                            base_address: None,
                         });
                     }
                     Some(found)
@@ -0,0 +1,184 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::mpsc;
+
+use crate::{ClientConfig, ClientError};
+
+/// Function code of a holding register read.
+pub const READ_HOLDING_REGISTERS: u8 = 0x03;
+/// Function code of a multiple register write.
+pub const WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+
+/// Exception codes by the text tokio-modbus reports them with.
+const EXCEPTIONS: [(u8, &str); 9] = [
+    (0x01, "Illegal function"),
+    (0x02, "Illegal data address"),
+    (0x03, "Illegal data value"),
+    (0x04, "Server device failure"),
+    (0x05, "Acknowledge"),
+    (0x06, "Server device busy"),
+    (0x08, "Memory parity error"),
+    (0x0A, "Gateway path unavailable"),
+    (0x0B, "Gateway target device failed to respond"),
+];
+
+/// One request and its response as a client with capture on saw them.
+///
+/// tokio-modbus does not expose the raw frames, so both are rebuilt from the
+/// request and the decoded response. Transaction IDs follow the connection's
+/// own numbering, so the frames match what went over the wire byte for byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    /// When the request was sent (Unix ms).
+    pub at_ms: u64,
+    pub host: String,
+    pub port: u16,
+    pub unit_id: u8,
+    pub transaction_id: u16,
+    pub function: u8,
+    pub start: u16,
+    pub count: u16,
+    /// Modbus/TCP ADU (MBAP header and PDU) of the request.
+    pub request: Vec<u8>,
+    /// ADU of the response; None when none arrived or it could not be decoded.
+    pub response: Option<Vec<u8>>,
+    /// Exception code the device answered with.
+    pub exception: Option<u8>,
+    pub error: Option<String>,
+    pub latency_us: u64,
+}
+
+/// The request of an exchange.
+pub(crate) enum Call<'a> {
+    Read { start: u16, count: u16 },
+    Write { start: u16, values: &'a [u16] },
+}
+
+impl Exchange {
+    /// `reply` holds the registers read (nothing for writes) or the error
+    /// the request failed with.
+    pub(crate) fn new(
+        config: &ClientConfig,
+        unit_id: u8,
+        transaction_id: u16,
+        call: Call<'_>,
+        reply: Result<&[u16], &ClientError>,
+        latency: Duration,
+    ) -> Self {
+        let (function, start, count, request_pdu) = match call {
+            Call::Read { start, count } => {
+                let mut pdu = vec![READ_HOLDING_REGISTERS];
+                pdu.extend_from_slice(&start.to_be_bytes());
+                pdu.extend_from_slice(&count.to_be_bytes());
+                (READ_HOLDING_REGISTERS, start, count, pdu)
+            }
+            Call::Write { start, values } => {
+                let count = values.len() as u16;
+                let mut pdu = vec![WRITE_MULTIPLE_REGISTERS];
+                pdu.extend_from_slice(&start.to_be_bytes());
+                pdu.extend_from_slice(&count.to_be_bytes());
+                pdu.push((2 * values.len()) as u8);
+                pdu.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+                (WRITE_MULTIPLE_REGISTERS, start, count, pdu)
+            }
+        };
+        let exception = reply.err().and_then(exception_code);
+        let response_pdu = match (reply, exception) {
+            (Ok(values), _) if function == READ_HOLDING_REGISTERS => {
+                let mut pdu = vec![function, (2 * values.len()) as u8];
+                pdu.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+                Some(pdu)
+            }
+            (Ok(_), _) => Some(request_pdu[..5].to_vec()),
+            (Err(_), Some(code)) => Some(vec![function | 0x80, code]),
+            (Err(_), None) => None,
+        };
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Self {
+            at_ms: now_ms.saturating_sub(latency.as_millis() as u64),
+            host: config.host.clone(),
+            port: config.port,
+            unit_id,
+            transaction_id,
+            function,
+            start,
+            count,
+            request: adu(transaction_id, unit_id, &request_pdu),
+            response: response_pdu.map(|pdu| adu(transaction_id, unit_id, &pdu)),
+            exception,
+            error: reply.err().map(ToString::to_string),
+            latency_us: latency.as_micros() as u64,
+        }
+    }
+}
+
+/// MBAP header (transaction ID, protocol 0, length, unit ID) followed by `pdu`.
+fn adu(transaction_id: u16, unit_id: u8, pdu: &[u8]) -> Vec<u8> {
+    let mut adu = Vec::with_capacity(7 + pdu.len());
+    adu.extend_from_slice(&transaction_id.to_be_bytes());
+    adu.extend_from_slice(&[0, 0]);
+    adu.extend_from_slice(&(pdu.len() as u16 + 1).to_be_bytes());
+    adu.push(unit_id);
+    adu.extend_from_slice(pdu);
+    adu
+}
+
+/// Text tokio-modbus reports exception `code` with.
+pub(crate) fn exception_text(code: u8) -> Option<&'static str> {
+    EXCEPTIONS
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, text)| *text)
+}
+
+/// tokio-modbus reports exception responses as I/O errors reading
+/// "Modbus function <code>: <exception>"; maps them back to the code.
+fn exception_code(err: &ClientError) -> Option<u8> {
+    let ClientError::Modbus(err) = err else {
+        return None;
+    };
+    let message = err.to_string();
+    let (_, exception) = message.strip_prefix("Modbus function ")?.split_once(": ")?;
+    EXCEPTIONS
+        .iter()
+        .find(|(_, text)| *text == exception)
+        .map(|(code, _)| *code)
+}
+
+/// Switchable capture of a client's exchanges, shared by the clients of one
+/// device across reconnects. Starts switched off; exchanges go to the
+/// channel while it is on and are dropped when the channel is full, so a
+/// slow reader never holds up polling.
+#[derive(Debug, Clone)]
+pub struct CaptureTap {
+    enabled: Arc<AtomicBool>,
+    sender: mpsc::Sender<Exchange>,
+}
+
+impl CaptureTap {
+    pub fn new(sender: mpsc::Sender<Exchange>) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(false)),
+            sender,
+        }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Sends `exchange` unless the channel is full or closed; returns
+    /// whether it was sent.
+    pub fn record(&self, exchange: Exchange) -> bool {
+        self.sender.try_send(exchange).is_ok()
+    }
+}
@@ -0,0 +1,163 @@
+use std::env;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Exception code injected unless configured otherwise: server device busy.
+const DEFAULT_EXCEPTION_CODE: u8 = 0x06;
+
+/// Probabilities, between 0 and 1, of the faults injected into each read
+/// attempt. At most one fault is injected per attempt; the rates add up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultRates {
+    /// The read waits out the request timeout and fails with a timeout.
+    pub timeout: f64,
+    /// The read fails as if the response broke off.
+    pub truncated: f64,
+    /// The read fails with `exception_code`.
+    pub exception: f64,
+    pub exception_code: u8,
+    /// Seed of the fault sequence, so a failing run can be repeated.
+    pub seed: u64,
+}
+
+impl Default for FaultRates {
+    fn default() -> Self {
+        Self {
+            timeout: 0.0,
+            truncated: 0.0,
+            exception: 0.0,
+            exception_code: DEFAULT_EXCEPTION_CODE,
+            seed: 0,
+        }
+    }
+}
+
+impl FaultRates {
+    /// Rates from `SUNSPEC_FAULT_TIMEOUT_RATE`, `SUNSPEC_FAULT_TRUNCATED_RATE`,
+    /// `SUNSPEC_FAULT_EXCEPTION_RATE`, `SUNSPEC_FAULT_EXCEPTION_CODE` and
+    /// `SUNSPEC_FAULT_SEED`; None when no rate is set above 0.
+    pub fn from_env() -> Option<Self> {
+        fn parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+        }
+        let defaults = Self::default();
+        let rates = Self {
+            timeout: parse("SUNSPEC_FAULT_TIMEOUT_RATE").unwrap_or(defaults.timeout),
+            truncated: parse("SUNSPEC_FAULT_TRUNCATED_RATE").unwrap_or(defaults.truncated),
+            exception: parse("SUNSPEC_FAULT_EXCEPTION_RATE").unwrap_or(defaults.exception),
+            exception_code: parse("SUNSPEC_FAULT_EXCEPTION_CODE")
+                .unwrap_or(defaults.exception_code),
+            seed: parse("SUNSPEC_FAULT_SEED").unwrap_or(defaults.seed),
+        };
+        (rates.timeout > 0.0 || rates.truncated > 0.0 || rates.exception > 0.0).then_some(rates)
+    }
+}
+
+/// A fault picked for one read attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Timeout,
+    Truncated,
+    Exception(u8),
+}
+
+impl Fault {
+    /// The I/O error tokio-modbus reports for the fault; None for a timeout.
+    pub(crate) fn io_error(self, function: u8) -> Option<io::Error> {
+        match self {
+            Fault::Timeout => None,
+            Fault::Truncated => Some(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "injected fault: truncated response",
+            )),
+            Fault::Exception(code) => Some(io::Error::other(format!(
+                "Modbus function {function}: {}",
+                crate::capture::exception_text(code).unwrap_or("Unknown exception")
+            ))),
+        }
+    }
+}
+
+/// Faults injected so far, by kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultCounts {
+    pub timeouts: u64,
+    pub truncated: u64,
+    pub exceptions: u64,
+}
+
+#[derive(Debug)]
+struct InjectorState {
+    rng: u64,
+    counts: FaultCounts,
+}
+
+/// Injects faults into the reads of the clients it is handed to (see
+/// [`crate::ModbusClient::with_faults`]). Clones share one fault sequence
+/// and one set of counts.
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    rates: FaultRates,
+    state: Arc<Mutex<InjectorState>>,
+}
+
+impl FaultInjector {
+    pub fn new(rates: FaultRates) -> Self {
+        let state = InjectorState {
+            rng: rates.seed,
+            counts: FaultCounts::default(),
+        };
+        Self {
+            rates,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub fn rates(&self) -> &FaultRates {
+        &self.rates
+    }
+
+    pub fn counts(&self) -> FaultCounts {
+        self.lock().counts
+    }
+
+    /// The fault for the next read attempt, if any.
+    pub fn next_fault(&self) -> Option<Fault> {
+        let mut state = self.lock();
+        let roll = next_unit(&mut state.rng);
+        let mut threshold = self.rates.timeout;
+        if roll < threshold {
+            state.counts.timeouts += 1;
+            return Some(Fault::Timeout);
+        }
+        threshold += self.rates.truncated;
+        if roll < threshold {
+            state.counts.truncated += 1;
+            return Some(Fault::Truncated);
+        }
+        threshold += self.rates.exception;
+        if roll < threshold {
+            state.counts.exceptions += 1;
+            return Some(Fault::Exception(self.rates.exception_code));
+        }
+        None
+    }
+
+    fn lock(&self) -> MutexGuard<'_, InjectorState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Next value in [0, 1) of a splitmix64 sequence.
+fn next_unit(state: &mut u64) -> f64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -1,16 +1,30 @@
 #![allow(dead_code)]
 
 use std::cmp::min;
+use std::collections::BTreeMap;
+use std::future::Future;
 use std::net::SocketAddr;
-use std::time::Duration;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, Instant};
 
 use thiserror::Error;
 use tokio::sync::Mutex;
 use tokio::time::{sleep, timeout};
 use tokio_modbus::client::tcp;
 use tokio_modbus::client::Context;
-use tokio_modbus::prelude::{Reader, Slave, SlaveContext};
-use tracing::{debug, warn};
+use tokio_modbus::prelude::{Reader, Slave, SlaveContext, Writer};
+use tracing::{debug, instrument, warn};
+
+mod capture;
+#[cfg(feature = "fault-injection")]
+mod faults;
+mod resolve;
+
+use capture::Call;
+pub use capture::{CaptureTap, Exchange, READ_HOLDING_REGISTERS, WRITE_MULTIPLE_REGISTERS};
+#[cfg(feature = "fault-injection")]
+pub use faults::{Fault, FaultCounts, FaultInjector, FaultRates};
 
 /// Configuration options for connecting and polling a Modbus TCP device.
 #[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
@@ -30,6 +44,16 @@ pub struct ClientConfig {
     pub retry_max_backoff_ms: u64,
     /// Optional delay between split reads to placate slower devices.
     pub inter_read_delay_ms: Option<u64>,
+    /// How long the address a host name resolved to is reused by later
+    /// connects, in milliseconds; 0 resolves on every connect. A failed
+    /// request expires it early.
+    pub dns_ttl_ms: u64,
+    /// Added to every register address sent, for gateways that shift the
+    /// register map of the devices behind them.
+    pub address_offset: i32,
+    /// The gateway counts registers from 1 and subtracts 1 from every
+    /// address it forwards, so 1 is added on top of `address_offset`.
+    pub one_based_addressing: bool,
 }
 
 impl Default for ClientConfig {
@@ -43,10 +67,22 @@ impl Default for ClientConfig {
             retry_backoff_ms: 100,
             retry_max_backoff_ms: 2_000,
             inter_read_delay_ms: None,
+            dns_ttl_ms: 300_000,
+            address_offset: 0,
+            one_based_addressing: false,
         }
     }
 }
 
+impl ClientConfig {
+    /// The address sent on the wire for register `address` of the device's
+    /// map, after `address_offset` and `one_based_addressing`.
+    pub fn wire_address(&self, address: u16) -> Result<u16, ClientError> {
+        let shift = i64::from(self.address_offset) + i64::from(self.one_based_addressing);
+        u16::try_from(i64::from(address) + shift).map_err(|_| ClientError::AddressOverflow)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ClientError {
     #[error("invalid socket address {0}:{1}")]
@@ -59,26 +95,316 @@ pub enum ClientError {
     Timeout { timeout_ms: u64 },
     #[error("register address overflow")]
     AddressOverflow,
+    #[error("pre-connect hook failed: {0}")]
+    PreConnect(String),
+    #[error("could not resolve {host}:{port}: {reason}")]
+    Resolve {
+        host: String,
+        port: u16,
+        reason: String,
+    },
+}
+
+impl ClientError {
+    /// Short, stable classification used for structured log fields and metrics labels.
+    pub fn class(&self) -> &'static str {
+        match self {
+            ClientError::InvalidAddress(..) => "invalid_address",
+            ClientError::Modbus(_) => "modbus",
+            ClientError::Io(_) => "io",
+            ClientError::Timeout { .. } => "timeout",
+            ClientError::AddressOverflow => "address_overflow",
+            ClientError::PreConnect(_) => "pre_connect",
+            ClientError::Resolve { .. } => "dns",
+        }
+    }
+}
+
+/// MBAP header (7 bytes) + function code, start address and quantity.
+const READ_REQUEST_BYTES: u64 = 12;
+/// MBAP header (7 bytes) + function code and byte count, before register data.
+const READ_RESPONSE_OVERHEAD_BYTES: u64 = 9;
+/// MBAP header (7 bytes) + function code, start address, quantity and byte
+/// count, before register data.
+const WRITE_REQUEST_OVERHEAD_BYTES: u64 = 13;
+/// MBAP header (7 bytes) + function code, start address and quantity.
+const WRITE_RESPONSE_BYTES: u64 = 12;
+
+/// Request and wire-byte counters for one client. Byte counts are Modbus/TCP
+/// ADU sizes; TCP/IP framing is not included.
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    pub requests: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Failed requests keyed by [`ClientError::class`].
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl ClientStats {
+    pub fn merge(&mut self, other: &ClientStats) {
+        self.requests = self.requests.saturating_add(other.requests);
+        self.bytes_sent = self.bytes_sent.saturating_add(other.bytes_sent);
+        self.bytes_received = self.bytes_received.saturating_add(other.bytes_received);
+        for (class, count) in &other.errors {
+            let entry = self.errors.entry(class.clone()).or_default();
+            *entry = entry.saturating_add(*count);
+        }
+    }
+
+    /// Counters accumulated since `earlier`. If the counters went backwards
+    /// (the source was restarted), the current totals are returned.
+    pub fn since(&self, earlier: &ClientStats) -> ClientStats {
+        if self.requests < earlier.requests {
+            return self.clone();
+        }
+        ClientStats {
+            requests: self.requests - earlier.requests,
+            bytes_sent: self.bytes_sent.saturating_sub(earlier.bytes_sent),
+            bytes_received: self.bytes_received.saturating_sub(earlier.bytes_received),
+            errors: self
+                .errors
+                .iter()
+                .map(|(class, count)| {
+                    let before = earlier.errors.get(class).copied().unwrap_or(0);
+                    (class.clone(), count.saturating_sub(before))
+                })
+                .filter(|(_, count)| *count > 0)
+                .collect(),
+        }
+    }
+
+    pub fn error_count(&self) -> u64 {
+        self.errors.values().sum()
+    }
+}
+
+/// Future returned by [`PreConnect::prepare`].
+pub type PreConnectFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<SocketAddr, ClientError>> + Send + 'a>>;
+
+/// Vendor-specific session setup run before every Modbus TCP connect, for
+/// devices that only accept Modbus after an HTTP login or that expose it
+/// through a tunnel.
+pub trait PreConnect: Send + Sync {
+    /// Establishes the session for the device of `config` at `addr` and
+    /// returns the address to open the Modbus TCP connection to: `addr`
+    /// itself, or the local end of a tunnel. The hook bounds its own time;
+    /// the request timeout does not apply to it.
+    fn prepare<'a>(&'a self, config: &'a ClientConfig, addr: SocketAddr) -> PreConnectFuture<'a>;
 }
 
 #[derive(Debug)]
 pub struct ModbusClient {
     config: ClientConfig,
     context: Mutex<Context>,
+    stats: std::sync::Mutex<ClientStats>,
+    /// Transaction ID of the next request, numbered like tokio-modbus does
+    /// for the connection so captured frames carry the IDs that were sent.
+    transaction_id: AtomicU16,
+    capture: Option<CaptureTap>,
+    #[cfg(feature = "fault-injection")]
+    faults: Option<FaultInjector>,
 }
 
 impl ModbusClient {
     pub async fn connect(config: ClientConfig) -> Result<Self, ClientError> {
-        let addr = format!("{}:{}", config.host, config.port)
-            .parse::<SocketAddr>()
-            .map_err(|_| ClientError::InvalidAddress(config.host.clone(), config.port))?;
-        let context = tcp::connect(addr).await?;
+        Self::connect_with(config, None).await
+    }
+
+    /// Connects like [`Self::connect`], running `pre_connect` first.
+    pub async fn connect_with(
+        config: ClientConfig,
+        pre_connect: Option<&dyn PreConnect>,
+    ) -> Result<Self, ClientError> {
+        if config.host.trim().is_empty() {
+            return Err(ClientError::InvalidAddress(
+                config.host.clone(),
+                config.port,
+            ));
+        }
+        let mut addr = resolve::resolve(
+            &config.host,
+            config.port,
+            config.dns_ttl_ms,
+            config.timeout_ms,
+        )
+        .await?;
+        if let Some(hook) = pre_connect {
+            addr = hook.prepare(&config, addr).await?;
+            debug!(host = %config.host, %addr, "pre-connect hook done");
+        }
+        let context = match tcp::connect(addr).await {
+            Ok(context) => context,
+            Err(err) => {
+                resolve::expire(&config.host, config.port);
+                return Err(err.into());
+            }
+        };
         Ok(Self {
             config,
             context: Mutex::new(context),
+            stats: std::sync::Mutex::new(ClientStats::default()),
+            transaction_id: AtomicU16::new(0),
+            capture: None,
+            #[cfg(feature = "fault-injection")]
+            faults: None,
         })
     }
 
+    /// Sends every request and its response to `capture` while it is
+    /// switched on.
+    pub fn with_capture(mut self, capture: CaptureTap) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Fails read attempts at the rates of `faults` instead of sending them,
+    /// to exercise error handling and backoff under sustained failure.
+    #[cfg(feature = "fault-injection")]
+    pub fn with_faults(mut self, faults: FaultInjector) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
+    /// The error of an injected fault for the next read attempt, after
+    /// waiting out the timeout for an injected timeout.
+    #[cfg(feature = "fault-injection")]
+    async fn injected_fault(&self, unit_id: u8, start: u16, count: u16) -> Option<ClientError> {
+        let fault = self.faults.as_ref()?.next_fault()?;
+        warn!(unit_id, start, count, ?fault, "injecting modbus fault");
+        match fault.io_error(READ_HOLDING_REGISTERS) {
+            Some(err) => Some(ClientError::Modbus(err)),
+            None => {
+                sleep(Duration::from_millis(self.config.timeout_ms)).await;
+                Some(ClientError::Timeout {
+                    timeout_ms: self.config.timeout_ms,
+                })
+            }
+        }
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    async fn injected_fault(&self, _unit_id: u8, _start: u16, _count: u16) -> Option<ClientError> {
+        None
+    }
+
+    /// Returns the counters accumulated since the previous call and resets them.
+    pub fn take_stats(&self) -> ClientStats {
+        std::mem::take(&mut *self.lock_stats())
+    }
+
+    fn lock_stats(&self) -> std::sync::MutexGuard<'_, ClientStats> {
+        self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn next_transaction_id(&self) -> u16 {
+        self.transaction_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn capture_exchange(
+        &self,
+        unit_id: u8,
+        transaction_id: u16,
+        call: Call<'_>,
+        reply: Result<&[u16], &ClientError>,
+        sent_at: Instant,
+    ) {
+        let Some(capture) = self.capture.as_ref().filter(|capture| capture.is_enabled()) else {
+            return;
+        };
+        let exchange = Exchange::new(
+            &self.config,
+            unit_id,
+            transaction_id,
+            call,
+            reply,
+            sent_at.elapsed(),
+        );
+        if !capture.record(exchange) {
+            debug!(unit_id, transaction_id, "modbus capture full, dropped");
+        }
+    }
+
+    fn record_attempt(&self, count: u16, error: Option<&ClientError>) {
+        let mut stats = self.lock_stats();
+        stats.requests += 1;
+        stats.bytes_sent += READ_REQUEST_BYTES;
+        match error {
+            None => {
+                stats.bytes_received += READ_RESPONSE_OVERHEAD_BYTES + 2 * u64::from(count);
+            }
+            Some(err) => {
+                *stats.errors.entry(err.class().to_string()).or_default() += 1;
+                resolve::expire(&self.config.host, self.config.port);
+            }
+        }
+    }
+
+    /// Writes `values` to consecutive holding registers starting at `start`
+    /// (function code 16) in one request. Writes are not retried, since a
+    /// request that timed out may still have been applied.
+    pub async fn write_registers(
+        &self,
+        unit_id: u8,
+        start: u16,
+        values: &[u16],
+    ) -> Result<(), ClientError> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        let start = self.config.wire_address(start)?;
+        let mut ctx = self.context.lock().await;
+        ctx.set_slave(Slave(unit_id));
+        let transaction_id = self.next_transaction_id();
+        let sent_at = Instant::now();
+        let request = ctx.write_multiple_registers(start, values);
+        let error = match timeout(Duration::from_millis(self.config.timeout_ms), request).await {
+            Ok(Ok(())) => {
+                debug!(unit_id, start, count = values.len(), "modbus write ok");
+                None
+            }
+            Ok(Err(err)) => {
+                warn!(unit_id, start, error = %err, error_class = "modbus", "modbus write error");
+                Some(ClientError::Modbus(err))
+            }
+            Err(_) => {
+                warn!(
+                    unit_id,
+                    start,
+                    error_class = "timeout",
+                    "modbus write timeout"
+                );
+                Some(ClientError::Timeout {
+                    timeout_ms: self.config.timeout_ms,
+                })
+            }
+        };
+        self.capture_exchange(
+            unit_id,
+            transaction_id,
+            Call::Write { start, values },
+            error.as_ref().map_or(Ok(&[]), Err),
+            sent_at,
+        );
+
+        let mut stats = self.lock_stats();
+        stats.requests += 1;
+        stats.bytes_sent += WRITE_REQUEST_OVERHEAD_BYTES + 2 * values.len() as u64;
+        match error {
+            None => {
+                stats.bytes_received += WRITE_RESPONSE_BYTES;
+                Ok(())
+            }
+            Some(err) => {
+                *stats.errors.entry(err.class().to_string()).or_default() += 1;
+                resolve::expire(&self.config.host, self.config.port);
+                Err(err)
+            }
+        }
+    }
+
     pub async fn read_range(&self, unit_id: u8, start: u16, count: u16) -> Result<Vec<u16>, ClientError> {
         if count == 0 {
             return Ok(Vec::new());
@@ -98,6 +424,7 @@ impl ModbusClient {
             let chunk = min(remaining, batch_size);
             let chunk_start = u16::try_from(u32::from(start) + u32::from(offset))
                 .map_err(|_| ClientError::AddressOverflow)?;
+            let chunk_start = self.config.wire_address(chunk_start)?;
             let values = self
                 .read_chunk(&mut ctx, unit_id, chunk_start, chunk)
                 .await?;
@@ -115,6 +442,7 @@ impl ModbusClient {
         Ok(out)
     }
 
+    #[instrument(name = "modbus_read", skip(self, ctx))]
     async fn read_chunk(
         &self,
         ctx: &mut Context,
@@ -124,32 +452,59 @@ impl ModbusClient {
     ) -> Result<Vec<u16>, ClientError> {
         ctx.set_slave(Slave(unit_id));
         let mut attempts = 0usize;
-        let mut last_error = None;
 
         loop {
-            let request = ctx.read_holding_registers(start, count);
-            let result = timeout(Duration::from_millis(self.config.timeout_ms), request).await;
-            match result {
-                Ok(Ok(values)) => {
-                    debug!(unit_id, start, count, "modbus read ok");
-                    return Ok(values);
+            let transaction_id = self.next_transaction_id();
+            let sent_at = Instant::now();
+            let error = match self.injected_fault(unit_id, start, count).await {
+                Some(error) => error,
+                None => {
+                    let request = ctx.read_holding_registers(start, count);
+                    let result =
+                        timeout(Duration::from_millis(self.config.timeout_ms), request).await;
+                    match result {
+                        Ok(Ok(values)) => {
+                            debug!(unit_id, start, count, "modbus read ok");
+                            self.record_attempt(count, None);
+                            self.capture_exchange(
+                                unit_id,
+                                transaction_id,
+                                Call::Read { start, count },
+                                Ok(&values),
+                                sent_at,
+                            );
+                            return Ok(values);
+                        }
+                        Ok(Err(err)) => {
+                            warn!(unit_id, start, count, error = %err, error_class = "modbus", "modbus read error");
+                            ClientError::Modbus(err)
+                        }
+                        Err(_) => {
+                            warn!(
+                                unit_id,
+                                start,
+                                count,
+                                error_class = "timeout",
+                                "modbus read timeout"
+                            );
+                            ClientError::Timeout {
+                                timeout_ms: self.config.timeout_ms,
+                            }
+                        }
+                    }
                 }
-                Ok(Err(err)) => {
-                    warn!(unit_id, start, count, error = %err, "modbus read error");
-                    last_error = Some(ClientError::Modbus(err));
-                }
-                Err(_) => {
-                    warn!(unit_id, start, count, "modbus read timeout");
-                    last_error = Some(ClientError::Timeout {
-                        timeout_ms: self.config.timeout_ms,
-                    });
-                }
-            }
+            };
+            self.record_attempt(count, Some(&error));
+            self.capture_exchange(
+                unit_id,
+                transaction_id,
+                Call::Read { start, count },
+                Err(&error),
+                sent_at,
+            );
 
             if attempts >= self.config.retry_count {
-                return Err(last_error.unwrap_or(ClientError::Timeout {
-                    timeout_ms: self.config.timeout_ms,
-                }));
+                return Err(error);
             }
 
             let delay_ms = self.retry_delay_ms(attempts);
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use tokio::net::lookup_host;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+use crate::ClientError;
+
+/// Address a host name resolved to last.
+#[derive(Debug, Clone, Copy)]
+struct Resolved {
+    addr: SocketAddr,
+    at: Instant,
+    /// Set when a connection to `addr` failed; the next connect resolves the
+    /// name again, whatever the TTL.
+    expired: bool,
+}
+
+/// Shared by every client, so pollers, discovery and probes of one device
+/// agree on its address.
+static CACHE: OnceLock<Mutex<HashMap<(String, u16), Resolved>>> = OnceLock::new();
+
+fn cache() -> MutexGuard<'static, HashMap<(String, u16), Resolved>> {
+    CACHE
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn ip_addr(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// The address to connect to for `host:port`. IP addresses are used as they
+/// are. A host name is resolved and its address reused for `ttl_ms` (0
+/// resolves on every connect). When the lookup fails the last address is
+/// used, so a flaky mDNS responder does not take a reachable device down.
+pub(crate) async fn resolve(
+    host: &str,
+    port: u16,
+    ttl_ms: u64,
+    timeout_ms: u64,
+) -> Result<SocketAddr, ClientError> {
+    if let Some(ip) = ip_addr(host) {
+        return Ok(SocketAddr::new(ip, port));
+    }
+    let key = (host.to_string(), port);
+    let previous = cache().get(&key).copied();
+    if let Some(entry) = previous {
+        if !entry.expired && entry.at.elapsed() < Duration::from_millis(ttl_ms) {
+            return Ok(entry.addr);
+        }
+    }
+
+    let lookup = timeout(Duration::from_millis(timeout_ms), lookup_host((host, port))).await;
+    let reason = match lookup {
+        Ok(Ok(addrs)) => match prefer_ipv4(addrs) {
+            Some(addr) => {
+                match previous {
+                    Some(entry) if entry.addr != addr => {
+                        info!(host, from = %entry.addr, to = %addr, "device address changed");
+                    }
+                    Some(_) => {}
+                    None => debug!(host, %addr, "device host name resolved"),
+                }
+                cache().insert(
+                    key,
+                    Resolved {
+                        addr,
+                        at: Instant::now(),
+                        expired: false,
+                    },
+                );
+                return Ok(addr);
+            }
+            None => "no addresses".to_string(),
+        },
+        Ok(Err(err)) => err.to_string(),
+        Err(_) => format!("lookup timed out after {timeout_ms}ms"),
+    };
+    match previous {
+        Some(entry) => {
+            warn!(host, addr = %entry.addr, error = %reason, "host name lookup failed, using the last address");
+            Ok(entry.addr)
+        }
+        None => Err(ClientError::Resolve {
+            host: host.to_string(),
+            port,
+            reason,
+        }),
+    }
+}
+
+/// Most gateways only listen on IPv4, while resolvers often list IPv6
+/// first.
+fn prefer_ipv4(addrs: impl Iterator<Item = SocketAddr>) -> Option<SocketAddr> {
+    let addrs: Vec<SocketAddr> = addrs.collect();
+    addrs
+        .iter()
+        .find(|addr| addr.is_ipv4())
+        .or_else(|| addrs.first())
+        .copied()
+}
+
+/// Makes the next connect to `host:port` resolve the name again.
+pub(crate) fn expire(host: &str, port: u16) {
+    if let Some(entry) = cache().get_mut(&(host.to_string(), port)) {
+        entry.expired = true;
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::{Arc, Mutex};
+
+use modbus_client::{
+    CaptureTap, ClientConfig, ModbusClient, READ_HOLDING_REGISTERS, WRITE_MULTIPLE_REGISTERS,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+/// Frames a fake device received and sent, in order.
+type Wire = Arc<Mutex<Vec<(Vec<u8>, Vec<u8>)>>>;
+
+/// Answers reads with registers 0x1234, 0x5678 (or exception 2 for reads at
+/// address 9999) and acknowledges writes, recording every frame.
+async fn serve() -> (u16, Wire) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let wire = Wire::default();
+    let recorded = wire.clone();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.expect("accept");
+        loop {
+            let mut request = vec![0u8; 7];
+            if stream.read_exact(&mut request).await.is_err() {
+                return;
+            }
+            let length = u16::from_be_bytes([request[4], request[5]]) as usize;
+            let mut pdu = vec![0u8; length - 1];
+            stream.read_exact(&mut pdu).await.expect("pdu");
+            request.extend_from_slice(&pdu);
+
+            let start = u16::from_be_bytes([pdu[1], pdu[2]]);
+            let reply = match pdu[0] {
+                0x03 if start == 9_999 => vec![0x83, 0x02],
+                0x03 => vec![0x03, 4, 0x12, 0x34, 0x56, 0x78],
+                _ => pdu[..5].to_vec(),
+            };
+            let mut response = request[..4].to_vec();
+            response.extend_from_slice(&(reply.len() as u16 + 1).to_be_bytes());
+            response.push(request[6]);
+            response.extend_from_slice(&reply);
+            stream.write_all(&response).await.expect("respond");
+            recorded.lock().expect("wire").push((request, response));
+        }
+    });
+    (port, wire)
+}
+
+async fn client(port: u16) -> ModbusClient {
+    ModbusClient::connect(ClientConfig {
+        port,
+        retry_count: 0,
+        ..ClientConfig::default()
+    })
+    .await
+    .expect("connect")
+}
+
+#[tokio::test]
+async fn captured_frames_match_the_wire() {
+    let (port, wire) = serve().await;
+    let (tx, mut rx) = mpsc::channel(16);
+    let tap = CaptureTap::new(tx);
+    tap.set_enabled(true);
+    let client = client(port).await.with_capture(tap);
+
+    assert_eq!(
+        client.read_range(3, 40_000, 2).await.expect("read"),
+        vec![0x1234, 0x5678]
+    );
+    client
+        .write_registers(3, 40_100, &[7, 8])
+        .await
+        .expect("write");
+    let err = client.read_range(3, 9_999, 2).await.expect_err("exception");
+    assert_eq!(err.class(), "modbus");
+
+    let wire = wire.lock().expect("wire").clone();
+    assert_eq!(wire.len(), 3);
+    let exchanges: Vec<_> = (0..3).map(|_| rx.try_recv().expect("exchange")).collect();
+    for ((request, response), exchange) in wire.iter().zip(&exchanges) {
+        assert_eq!(&exchange.request, request);
+        assert_eq!(exchange.response.as_ref(), Some(response));
+        assert_eq!(exchange.unit_id, 3);
+        assert_eq!(exchange.port, port);
+    }
+    assert_eq!(
+        exchanges
+            .iter()
+            .map(|exchange| exchange.transaction_id)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+
+    let read = &exchanges[0];
+    assert_eq!(read.function, READ_HOLDING_REGISTERS);
+    assert_eq!((read.start, read.count), (40_000, 2));
+    assert_eq!((read.exception, read.error.as_deref()), (None, None));
+    let write = &exchanges[1];
+    assert_eq!(write.function, WRITE_MULTIPLE_REGISTERS);
+    assert_eq!((write.start, write.count), (40_100, 2));
+    let failed = &exchanges[2];
+    assert_eq!(failed.exception, Some(0x02));
+    assert!(failed
+        .error
+        .as_deref()
+        .is_some_and(|error| error.contains("Illegal data address")));
+}
+
+#[tokio::test]
+async fn nothing_is_captured_while_switched_off() {
+    let (port, wire) = serve().await;
+    let (tx, mut rx) = mpsc::channel(16);
+    let tap = CaptureTap::new(tx);
+    let client = client(port).await.with_capture(tap.clone());
+
+    client.read_range(1, 40_000, 2).await.expect("read");
+    assert!(rx.try_recv().is_err());
+
+    tap.set_enabled(true);
+    client.read_range(1, 40_000, 2).await.expect("read");
+    let exchange = rx.try_recv().expect("exchange");
+    // The first read still used up a transaction ID.
+    assert_eq!(exchange.transaction_id, 1);
+    assert_eq!(
+        exchange.response.as_ref(),
+        wire.lock()
+            .expect("wire")
+            .last()
+            .map(|(_, response)| response)
+    );
+}
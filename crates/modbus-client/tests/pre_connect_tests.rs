@@ -0,0 +1,62 @@
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use modbus_client::{ClientConfig, ClientError, ModbusClient, PreConnect, PreConnectFuture};
+use tokio::net::TcpListener;
+
+/// Sends the connection to a local listener, as a tunnel would.
+struct Tunnel {
+    local: SocketAddr,
+    device: Mutex<Option<SocketAddr>>,
+}
+
+impl PreConnect for Tunnel {
+    fn prepare<'a>(&'a self, _config: &'a ClientConfig, addr: SocketAddr) -> PreConnectFuture<'a> {
+        Box::pin(async move {
+            *self.device.lock().expect("lock") = Some(addr);
+            Ok(self.local)
+        })
+    }
+}
+
+struct RejectedLogin;
+
+impl PreConnect for RejectedLogin {
+    fn prepare<'a>(&'a self, _config: &'a ClientConfig, _addr: SocketAddr) -> PreConnectFuture<'a> {
+        Box::pin(async { Err(ClientError::PreConnect("login rejected (401)".to_string())) })
+    }
+}
+
+fn config() -> ClientConfig {
+    ClientConfig {
+        host: "192.0.2.10".to_string(),
+        port: 502,
+        ..ClientConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn hook_chooses_the_address_to_connect_to() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let tunnel = Tunnel {
+        local: listener.local_addr().expect("addr"),
+        device: Mutex::new(None),
+    };
+
+    let client = ModbusClient::connect_with(config(), Some(&tunnel)).await;
+    assert!(client.is_ok());
+    listener.accept().await.expect("accept");
+    assert_eq!(
+        *tunnel.device.lock().expect("lock"),
+        Some("192.0.2.10:502".parse().expect("addr"))
+    );
+}
+
+#[tokio::test]
+async fn failing_hook_fails_the_connect() {
+    let err = ModbusClient::connect_with(config(), Some(&RejectedLogin))
+        .await
+        .expect_err("hook failed");
+    assert_eq!(err.class(), "pre_connect");
+    assert!(err.to_string().contains("login rejected"));
+}
@@ -0,0 +1,32 @@
+use modbus_client::{ClientConfig, ModbusClient};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn host_names_are_resolved() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let config = ClientConfig {
+        host: "localhost".to_string(),
+        port: listener.local_addr().expect("addr").port(),
+        ..ClientConfig::default()
+    };
+
+    // The second connect reuses the cached address.
+    for _ in 0..2 {
+        let client = ModbusClient::connect(config.clone()).await;
+        assert!(client.is_ok());
+        listener.accept().await.expect("accept");
+    }
+}
+
+#[tokio::test]
+async fn unknown_host_names_fail_the_connect() {
+    let err = ModbusClient::connect(ClientConfig {
+        host: "gateway.invalid".to_string(),
+        timeout_ms: 500,
+        ..ClientConfig::default()
+    })
+    .await
+    .expect_err("not resolvable");
+    assert_eq!(err.class(), "dns");
+    assert!(err.to_string().contains("gateway.invalid:502"));
+}
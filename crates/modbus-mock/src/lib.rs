@@ -0,0 +1,403 @@
+//! Scriptable Modbus/TCP server for tests: register maps per unit ID and
+//! faults injected per request (delays, exceptions, disconnects), so retry,
+//! reconnect, quarantine and discovery behaviour can be exercised without a
+//! real device or `diagslave`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+use tokio::sync::watch;
+use tokio::time::sleep;
+use tracing::debug;
+
+pub const READ_HOLDING_REGISTERS: u8 = 3;
+pub const READ_INPUT_REGISTERS: u8 = 4;
+pub const WRITE_MULTIPLE_REGISTERS: u8 = 16;
+
+pub const ILLEGAL_FUNCTION: u8 = 1;
+pub const ILLEGAL_DATA_ADDRESS: u8 = 2;
+pub const ILLEGAL_DATA_VALUE: u8 = 3;
+pub const SERVER_DEVICE_FAILURE: u8 = 4;
+pub const GATEWAY_TARGET_FAILED: u8 = 0x0b;
+
+/// Registers a single read may ask for.
+const MAX_READ_REGISTERS: u16 = 125;
+/// "SunS" at the base address of a SunSpec device.
+const SUNSPEC_MARKER: [u16; 2] = [0x5375, 0x6e53];
+const END_MODEL_ID: u16 = 0xffff;
+
+/// What the server does with a request a [`Fault`] applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Answer as usual, after this long.
+    Delay(Duration),
+    /// Answer with this exception code.
+    Exception(u8),
+    /// Close the connection without answering.
+    Disconnect,
+    /// Send the first half of the answer, then close the connection.
+    DisconnectMidReply,
+}
+
+/// An [`Action`] for the requests of one unit and address range, all of
+/// them unless limited with [`Fault::times`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    action: Action,
+    unit_id: Option<u8>,
+    addresses: Option<Range<u16>>,
+    remaining: Option<u32>,
+}
+
+impl Fault {
+    pub fn new(action: Action) -> Self {
+        Self {
+            action,
+            unit_id: None,
+            addresses: None,
+            remaining: None,
+        }
+    }
+
+    /// Only requests to `unit_id`.
+    pub fn unit(mut self, unit_id: u8) -> Self {
+        self.unit_id = Some(unit_id);
+        self
+    }
+
+    /// Only requests touching a register in `addresses`.
+    pub fn addresses(mut self, addresses: Range<u16>) -> Self {
+        self.addresses = Some(addresses);
+        self
+    }
+
+    /// Only the next `count` requests it applies to.
+    pub fn times(mut self, count: u32) -> Self {
+        self.remaining = Some(count);
+        self
+    }
+
+    fn applies_to(&self, request: &Request) -> bool {
+        let end = u32::from(request.address) + u32::from(request.count);
+        self.unit_id
+            .is_none_or(|unit_id| unit_id == request.unit_id)
+            && self.addresses.as_ref().is_none_or(|addresses| {
+                u32::from(addresses.start) < end && request.address < addresses.end
+            })
+    }
+}
+
+/// A request as the server received it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Request {
+    pub unit_id: u8,
+    pub function: u8,
+    pub address: u16,
+    pub count: u16,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    units: HashMap<u8, BTreeMap<u16, u16>>,
+    faults: Vec<Fault>,
+    requests: Vec<Request>,
+    connections: usize,
+}
+
+impl State {
+    /// Records `request`, consumes the first fault that applies to it and
+    /// returns that fault's action besides the answer body.
+    fn answer(&mut self, request: Request, data: &[u8]) -> (Option<Action>, Vec<u8>) {
+        self.requests.push(request);
+        let action = self.take_fault(&request);
+        let reply = match action {
+            Some(Action::Exception(code)) => Err(code),
+            _ => self.execute(&request, data),
+        };
+        let body = match reply {
+            Ok(body) => body,
+            Err(code) => vec![request.function | 0x80, code],
+        };
+        (action, body)
+    }
+
+    fn take_fault(&mut self, request: &Request) -> Option<Action> {
+        let index = self
+            .faults
+            .iter()
+            .position(|fault| fault.applies_to(request))?;
+        let fault = &mut self.faults[index];
+        let action = fault.action;
+        if let Some(remaining) = fault.remaining.as_mut() {
+            *remaining -= 1;
+            if *remaining == 0 {
+                self.faults.remove(index);
+            }
+        }
+        Some(action)
+    }
+
+    fn execute(&mut self, request: &Request, data: &[u8]) -> Result<Vec<u8>, u8> {
+        let registers = self
+            .units
+            .get_mut(&request.unit_id)
+            .ok_or(GATEWAY_TARGET_FAILED)?;
+        match request.function {
+            READ_HOLDING_REGISTERS | READ_INPUT_REGISTERS => {
+                if request.count == 0 || request.count > MAX_READ_REGISTERS {
+                    return Err(ILLEGAL_DATA_VALUE);
+                }
+                let values =
+                    read(registers, request.address, request.count).ok_or(ILLEGAL_DATA_ADDRESS)?;
+                let mut body = vec![request.function, (values.len() * 2) as u8];
+                body.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+                Ok(body)
+            }
+            WRITE_MULTIPLE_REGISTERS => {
+                // Byte count, then the values.
+                let values = data.get(1..).unwrap_or_default();
+                if request.count == 0 || values.len() != usize::from(request.count) * 2 {
+                    return Err(ILLEGAL_DATA_VALUE);
+                }
+                read(registers, request.address, request.count).ok_or(ILLEGAL_DATA_ADDRESS)?;
+                for (offset, value) in values.chunks_exact(2).enumerate() {
+                    registers.insert(
+                        request.address + offset as u16,
+                        u16::from_be_bytes([value[0], value[1]]),
+                    );
+                }
+                let mut body = vec![request.function];
+                body.extend(request.address.to_be_bytes());
+                body.extend(request.count.to_be_bytes());
+                Ok(body)
+            }
+            _ => Err(ILLEGAL_FUNCTION),
+        }
+    }
+}
+
+/// Values of `count` registers from `address`; None if one of them is not
+/// mapped.
+fn read(registers: &BTreeMap<u16, u16>, address: u16, count: u16) -> Option<Vec<u16>> {
+    (0..count)
+        .map(|offset| {
+            let address = address.checked_add(offset)?;
+            registers.get(&address).copied()
+        })
+        .collect()
+}
+
+/// A Modbus/TCP server on a local port, answering from register maps set
+/// per unit ID. Registers that were never set answer with an illegal data
+/// address exception, unknown unit IDs with a gateway exception. The server
+/// stops when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    online: watch::Sender<bool>,
+}
+
+impl MockServer {
+    /// Starts a server on a free port of 127.0.0.1.
+    pub fn start() -> io::Result<Self> {
+        let listener = listen("127.0.0.1:0".parse().expect("loopback address"))?;
+        let server = Self {
+            addr: listener.local_addr()?,
+            state: Arc::default(),
+            online: watch::channel(true).0,
+        };
+        server.accept(listener);
+        Ok(server)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// Maps `values` to the registers of `unit_id` from `address` on.
+    pub fn set_registers(&self, unit_id: u8, address: u16, values: &[u16]) {
+        let mut state = self.lock();
+        let registers = state.units.entry(unit_id).or_default();
+        for (offset, value) in values.iter().enumerate() {
+            registers.insert(address + offset as u16, *value);
+        }
+    }
+
+    /// Current values of `count` registers of `unit_id`; None if one of
+    /// them is not mapped.
+    pub fn registers(&self, unit_id: u8, address: u16, count: u16) -> Option<Vec<u16>> {
+        read(self.lock().units.get(&unit_id)?, address, count)
+    }
+
+    /// Maps a SunSpec device to `unit_id`: the marker at `base_address`,
+    /// then `blocks` (each with its model ID and length registers, see
+    /// [`model_block`]) and the end marker.
+    pub fn load_sunspec(&self, unit_id: u8, base_address: u16, blocks: &[Vec<u16>]) {
+        let mut registers = SUNSPEC_MARKER.to_vec();
+        for block in blocks {
+            registers.extend(block);
+        }
+        registers.extend([END_MODEL_ID, 0]);
+        self.set_registers(unit_id, base_address, &registers);
+    }
+
+    /// Applies `fault` to the requests it matches. Faults apply in the order
+    /// they were injected; a request gets the first that matches.
+    pub fn inject(&self, fault: Fault) {
+        self.lock().faults.push(fault);
+    }
+
+    pub fn clear_faults(&self) {
+        self.lock().faults.clear();
+    }
+
+    /// Every request received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.lock().requests.clone()
+    }
+
+    /// Connections accepted so far.
+    pub fn connections(&self) -> usize {
+        self.lock().connections
+    }
+
+    /// Taking the server offline closes its connections and the port, so
+    /// connects are refused; back online it listens on the same port again.
+    pub fn set_online(&self, online: bool) -> io::Result<()> {
+        if *self.online.borrow() == online {
+            return Ok(());
+        }
+        if online {
+            let listener = listen(self.addr)?;
+            self.online.send_replace(true);
+            self.accept(listener);
+        } else {
+            self.online.send_replace(false);
+        }
+        Ok(())
+    }
+
+    fn accept(&self, listener: TcpListener) {
+        let state = self.state.clone();
+        let mut online = self.online.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(_) => return,
+                    },
+                    _ = online.wait_for(|online| !*online) => return,
+                };
+                lock(&state).connections += 1;
+                tokio::spawn(serve_connection(stream, state.clone(), online.clone()));
+            }
+        });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.online.send_replace(false);
+    }
+}
+
+/// A model block of `model_id` with `length` zeroed registers after its ID
+/// and length.
+pub fn model_block(model_id: u16, length: u16) -> Vec<u16> {
+    let mut block = vec![0; usize::from(length) + 2];
+    block[0] = model_id;
+    block[1] = length;
+    block
+}
+
+/// A listener that can take the port over again right after it was
+/// closed, while connections to it linger in TIME_WAIT.
+fn listen(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = TcpSocket::new_v4()?;
+    socket.set_reuseaddr(true)?;
+    socket.bind(addr)?;
+    socket.listen(128)
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+async fn serve_connection(
+    mut stream: TcpStream,
+    state: Arc<Mutex<State>>,
+    mut online: watch::Receiver<bool>,
+) {
+    tokio::select! {
+        _ = answer_requests(&mut stream, &state) => {}
+        _ = online.wait_for(|online| !*online) => {}
+    }
+}
+
+async fn answer_requests(stream: &mut TcpStream, state: &Mutex<State>) {
+    let mut header = [0u8; 7];
+    loop {
+        if stream.read_exact(&mut header).await.is_err() {
+            return;
+        }
+        let length = usize::from(u16::from_be_bytes([header[4], header[5]]));
+        if length < 2 {
+            return;
+        }
+        let mut pdu = vec![0u8; length - 1];
+        if stream.read_exact(&mut pdu).await.is_err() {
+            return;
+        }
+        let field = |index: usize| {
+            pdu.get(index..index + 2)
+                .map_or(0, |bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        };
+        let request = Request {
+            unit_id: header[6],
+            function: pdu[0],
+            address: field(1),
+            count: field(3),
+        };
+        let (action, body) = lock(state).answer(request, pdu.get(5..).unwrap_or_default());
+
+        let mut frame = Vec::with_capacity(7 + body.len());
+        frame.extend_from_slice(&header[..4]);
+        frame.extend_from_slice(&(body.len() as u16 + 1).to_be_bytes());
+        frame.push(request.unit_id);
+        frame.extend(body);
+        match action {
+            Some(Action::Delay(delay)) => sleep(delay).await,
+            Some(Action::Disconnect) => {
+                debug!(?request, "mock server disconnects instead of answering");
+                return;
+            }
+            Some(Action::DisconnectMidReply) => {
+                debug!(?request, "mock server disconnects mid-reply");
+                let _ = stream.write_all(&frame[..frame.len() / 2]).await;
+                return;
+            }
+            Some(Action::Exception(_)) | None => {}
+        }
+        if stream.write_all(&frame).await.is_err() {
+            return;
+        }
+    }
+}
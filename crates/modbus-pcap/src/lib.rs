@@ -0,0 +1,371 @@
+#![allow(dead_code)]
+
+//! Offline reconstruction of Modbus/TCP register reads from pcap captures.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use serde::Serialize;
+use sunspec_parser::{parse_models_from_registers_lenient, ModelDefinition};
+use thiserror::Error;
+use tracing::debug;
+
+pub const DEFAULT_MODBUS_PORT: u16 = 502;
+
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+
+const FN_READ_HOLDING: u8 = 0x03;
+const FN_READ_INPUT: u8 = 0x04;
+const SUNSPEC_ID0: u16 = 0x5375;
+const SUNSPEC_ID1: u16 = 0x6e53;
+
+#[derive(Debug, Error)]
+pub enum PcapError {
+    #[error("file too short for pcap header")]
+    Truncated,
+    #[error("unsupported pcap magic {0:#010x} (pcapng is not supported)")]
+    BadMagic(u32),
+    #[error("unsupported link type {0}")]
+    UnsupportedLinkType(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct PcapPacket {
+    pub ts_us: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub link_type: u32,
+    pub packets: Vec<PcapPacket>,
+}
+
+/// One request/response pair for function 3 or 4, or an exception response.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterRead {
+    pub ts_us: u64,
+    pub client: String,
+    pub server: String,
+    pub unit_id: u8,
+    pub function: u8,
+    pub start: u16,
+    pub count: u16,
+    pub registers: Vec<u16>,
+    pub exception: Option<u8>,
+    pub latency_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSpan {
+    pub id: u16,
+    pub name: String,
+    pub start: u16,
+    pub length: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedRead {
+    #[serde(flatten)]
+    pub read: RegisterRead,
+    /// SunSpec models overlapping the read, when the device model map is known.
+    pub models: Vec<ModelSpan>,
+}
+
+/// Parses a classic libpcap file (micro- or nanosecond, either byte order).
+pub fn read_pcap(bytes: &[u8]) -> Result<Capture, PcapError> {
+    if bytes.len() < 24 {
+        return Err(PcapError::Truncated);
+    }
+    let magic = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let (little_endian, nanos) = match magic {
+        0xa1b2_c3d4 => (true, false),
+        0xa1b2_3c4d => (true, true),
+        0xd4c3_b2a1 => (false, false),
+        0x4d3c_b2a1 => (false, true),
+        other => return Err(PcapError::BadMagic(other)),
+    };
+    let read_u32 = |offset: usize| {
+        let raw = [
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ];
+        if little_endian {
+            u32::from_le_bytes(raw)
+        } else {
+            u32::from_be_bytes(raw)
+        }
+    };
+
+    let link_type = read_u32(20);
+    let mut packets = Vec::new();
+    let mut offset = 24usize;
+    while offset + 16 <= bytes.len() {
+        let ts_sec = u64::from(read_u32(offset));
+        let ts_frac = u64::from(read_u32(offset + 4));
+        let incl_len = read_u32(offset + 8) as usize;
+        let data_start = offset + 16;
+        let Some(data_end) = data_start.checked_add(incl_len) else {
+            break;
+        };
+        if data_end > bytes.len() {
+            debug!(offset, "truncated final packet");
+            break;
+        }
+        let ts_us = ts_sec * 1_000_000 + if nanos { ts_frac / 1_000 } else { ts_frac };
+        packets.push(PcapPacket {
+            ts_us,
+            data: bytes[data_start..data_end].to_vec(),
+        });
+        offset = data_end;
+    }
+
+    Ok(Capture { link_type, packets })
+}
+
+struct TcpSegment<'a> {
+    src: (Ipv4Addr, u16),
+    dst: (Ipv4Addr, u16),
+    payload: &'a [u8],
+}
+
+fn parse_frame(link_type: u32, data: &[u8]) -> Option<TcpSegment<'_>> {
+    let ip = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16::from_be_bytes([*data.get(12)?, *data.get(13)?]);
+            let mut offset = 14;
+            // Skip 802.1Q / QinQ tags.
+            while ethertype == 0x8100 || ethertype == 0x88a8 {
+                ethertype = u16::from_be_bytes([*data.get(offset + 2)?, *data.get(offset + 3)?]);
+                offset += 4;
+            }
+            if ethertype != 0x0800 {
+                return None;
+            }
+            data.get(offset..)?
+        }
+        LINKTYPE_LINUX_SLL => {
+            if u16::from_be_bytes([*data.get(14)?, *data.get(15)?]) != 0x0800 {
+                return None;
+            }
+            data.get(16..)?
+        }
+        LINKTYPE_RAW => data,
+        _ => return None,
+    };
+
+    if ip.len() < 20 || ip[0] >> 4 != 4 || ip[9] != 6 {
+        return None;
+    }
+    let ihl = usize::from(ip[0] & 0x0f) * 4;
+    let total_len = usize::from(u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]));
+    let src_ip = Ipv4Addr::new(ip[12], ip[13], ip[14], ip[15]);
+    let dst_ip = Ipv4Addr::new(ip[16], ip[17], ip[18], ip[19]);
+    // Ethernet padding may follow the IP datagram.
+    let tcp = ip.get(ihl..total_len.min(ip.len()))?;
+    let src_port = u16::from_be_bytes([*tcp.first()?, *tcp.get(1)?]);
+    let dst_port = u16::from_be_bytes([*tcp.get(2)?, *tcp.get(3)?]);
+    let data_offset = usize::from(tcp.get(12)? >> 4) * 4;
+
+    Some(TcpSegment {
+        src: (src_ip, src_port),
+        dst: (dst_ip, dst_port),
+        payload: tcp.get(data_offset..)?,
+    })
+}
+
+struct PendingRequest {
+    ts_us: u64,
+    unit_id: u8,
+    function: u8,
+    start: u16,
+    count: u16,
+}
+
+/// Pairs Modbus requests with responses by connection and transaction id.
+///
+/// Segments are assumed to carry whole ADUs, which holds for the short
+/// request/response exchanges Modbus/TCP pollers produce in practice.
+pub struct ReadReconstructor {
+    port: u16,
+    pending: HashMap<(String, String, u16), PendingRequest>,
+}
+
+impl ReadReconstructor {
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, link_type: u32, packet: &PcapPacket) -> Vec<RegisterRead> {
+        let Some(segment) = parse_frame(link_type, &packet.data) else {
+            return Vec::new();
+        };
+        let to_server = segment.dst.1 == self.port;
+        if !to_server && segment.src.1 != self.port {
+            return Vec::new();
+        }
+        let (client, server) = if to_server {
+            (segment.src, segment.dst)
+        } else {
+            (segment.dst, segment.src)
+        };
+        let client = format!("{}:{}", client.0, client.1);
+        let server = server.0.to_string();
+
+        let mut reads = Vec::new();
+        let mut rest = segment.payload;
+        while rest.len() >= 8 {
+            let transaction = u16::from_be_bytes([rest[0], rest[1]]);
+            let length = usize::from(u16::from_be_bytes([rest[4], rest[5]]));
+            if rest[2] != 0 || rest[3] != 0 || length < 2 || rest.len() < 6 + length {
+                break;
+            }
+            let unit_id = rest[6];
+            let pdu = &rest[7..6 + length];
+            let key = (client.clone(), server.clone(), transaction);
+
+            if to_server {
+                if let [function @ (FN_READ_HOLDING | FN_READ_INPUT), s0, s1, c0, c1, ..] = *pdu {
+                    self.pending.insert(
+                        key,
+                        PendingRequest {
+                            ts_us: packet.ts_us,
+                            unit_id,
+                            function,
+                            start: u16::from_be_bytes([s0, s1]),
+                            count: u16::from_be_bytes([c0, c1]),
+                        },
+                    );
+                }
+            } else if let Some(request) = self.pending.remove(&key) {
+                reads.push(complete_read(
+                    &request,
+                    pdu,
+                    packet.ts_us,
+                    client.clone(),
+                    server.clone(),
+                ));
+            }
+
+            rest = &rest[6 + length..];
+        }
+
+        reads
+    }
+}
+
+fn complete_read(
+    request: &PendingRequest,
+    pdu: &[u8],
+    ts_us: u64,
+    client: String,
+    server: String,
+) -> RegisterRead {
+    let exception = match pdu {
+        [function, code, ..] if *function == request.function | 0x80 => Some(*code),
+        _ => None,
+    };
+    let registers = match (exception, pdu) {
+        (None, [_, byte_count, data @ ..]) => data
+            .chunks_exact(2)
+            .take(usize::from(*byte_count) / 2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    RegisterRead {
+        ts_us,
+        client,
+        server,
+        unit_id: request.unit_id,
+        function: request.function,
+        start: request.start,
+        count: request.count,
+        registers,
+        exception,
+        latency_us: ts_us.saturating_sub(request.ts_us),
+    }
+}
+
+/// Extracts every completed register read from a capture.
+pub fn reconstruct_reads(capture: &Capture, port: u16) -> Vec<RegisterRead> {
+    let mut reconstructor = ReadReconstructor::new(port);
+    capture
+        .packets
+        .iter()
+        .flat_map(|packet| reconstructor.push(capture.link_type, packet))
+        .collect()
+}
+
+/// Annotates reads with the SunSpec models they cover. Model maps are learned
+/// from any read (ours or a third party's) that starts at `base_address` and
+/// carries the SunSpec sentinel.
+pub fn decode_reads(reads: Vec<RegisterRead>, base_address: u16) -> Vec<DecodedRead> {
+    let mut maps: HashMap<(String, u8), Vec<ModelDefinition>> = HashMap::new();
+    for read in &reads {
+        if read.start == base_address
+            && read.registers.len() >= 2
+            && read.registers[0] == SUNSPEC_ID0
+            && read.registers[1] == SUNSPEC_ID1
+        {
+            if let Ok(models) = parse_models_from_registers_lenient(base_address, &read.registers) {
+                if !models.is_empty() {
+                    maps.insert((read.server.clone(), read.unit_id), models);
+                }
+            }
+        }
+    }
+
+    reads
+        .into_iter()
+        .map(|read| {
+            let read_end = u32::from(read.start) + u32::from(read.count);
+            let models = maps
+                .get(&(read.server.clone(), read.unit_id))
+                .map(|models| {
+                    models
+                        .iter()
+                        .filter(|model| {
+                            let model_end = u32::from(model.start) + u32::from(model.length);
+                            u32::from(model.start) < read_end && u32::from(read.start) < model_end
+                        })
+                        .map(|model| ModelSpan {
+                            id: model.id,
+                            name: model.name.clone(),
+                            start: model.start,
+                            length: model.length,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            DecodedRead { read, models }
+        })
+        .collect()
+}
+
+/// Convenience wrapper: parse pcap bytes, reconstruct reads and decode them.
+pub fn decode_capture(
+    bytes: &[u8],
+    port: u16,
+    base_address: u16,
+) -> Result<Vec<DecodedRead>, PcapError> {
+    let capture = read_pcap(bytes)?;
+    if !matches!(
+        capture.link_type,
+        LINKTYPE_ETHERNET | LINKTYPE_RAW | LINKTYPE_LINUX_SLL
+    ) {
+        return Err(PcapError::UnsupportedLinkType(capture.link_type));
+    }
+    Ok(decode_reads(
+        reconstruct_reads(&capture, port),
+        base_address,
+    ))
+}
@@ -0,0 +1,74 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use modbus_pcap::{decode_capture, DEFAULT_MODBUS_PORT};
+
+const DEFAULT_BASE_ADDRESS: u16 = 40_000;
+
+const USAGE: &str = "usage: modbus-pcap-decode <capture.pcap> [--port 502] [--base-address 40000]";
+
+/// Decodes a Modbus/TCP capture into a JSON-lines timeline of register reads.
+fn main() -> ExitCode {
+    let mut path = None;
+    let mut port = DEFAULT_MODBUS_PORT;
+    let mut base_address = DEFAULT_BASE_ADDRESS;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => port = value,
+                None => return usage_error("--port expects a number"),
+            },
+            "--base-address" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => base_address = value,
+                None => return usage_error("--base-address expects a number"),
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            other if path.is_none() => path = Some(other.to_string()),
+            other => return usage_error(&format!("unexpected argument {other}")),
+        }
+    }
+
+    let Some(path) = path else {
+        return usage_error("missing capture path");
+    };
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("read {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let decoded = match decode_capture(&bytes, port, base_address) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            eprintln!("decode {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for read in &decoded {
+        match serde_json::to_string(read) {
+            Ok(line) => {
+                if writeln!(out, "{line}").is_err() {
+                    return ExitCode::FAILURE;
+                }
+            }
+            Err(err) => eprintln!("encode read: {err}"),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("{message}\n{USAGE}");
+    ExitCode::from(2)
+}
@@ -0,0 +1,141 @@
+use modbus_pcap::{decode_capture, read_pcap, reconstruct_reads, PcapError};
+
+const CLIENT: [u8; 4] = [192, 168, 1, 5];
+const SERVER: [u8; 4] = [192, 168, 1, 20];
+
+#[test]
+fn reconstructs_read_and_annotates_models() {
+    let packets = vec![
+        // Model list read at the base address.
+        (
+            1_000,
+            frame(CLIENT, 40_100, SERVER, 502, &request(1, 1, 40_000, 8)),
+        ),
+        (
+            1_250,
+            frame(
+                SERVER,
+                502,
+                CLIENT,
+                40_100,
+                &response(1, 1, &[0x5375, 0x6e53, 1, 2, 0, 0, 103, 0]),
+            ),
+        ),
+        // Third-party poller reading into the common model.
+        (
+            2_000,
+            frame(CLIENT, 40_200, SERVER, 502, &request(7, 1, 40_004, 2)),
+        ),
+        (
+            2_400,
+            frame(
+                SERVER,
+                502,
+                CLIENT,
+                40_200,
+                &response(7, 1, &[0xAAAA, 0xBBBB]),
+            ),
+        ),
+    ];
+
+    let decoded = decode_capture(&pcap(&packets), 502, 40_000).expect("decode");
+    assert_eq!(decoded.len(), 2);
+
+    let second = &decoded[1];
+    assert_eq!(second.read.client, "192.168.1.5:40200");
+    assert_eq!(second.read.server, "192.168.1.20");
+    assert_eq!(second.read.start, 40_004);
+    assert_eq!(second.read.registers, vec![0xAAAA, 0xBBBB]);
+    assert_eq!(second.read.latency_us, 400);
+    assert_eq!(second.models.len(), 1);
+    assert_eq!(second.models[0].name, "common");
+}
+
+#[test]
+fn exception_response_is_reported() {
+    let packets = vec![
+        (
+            10,
+            frame(CLIENT, 40_300, SERVER, 502, &request(3, 2, 40_000, 2)),
+        ),
+        (
+            20,
+            frame(
+                SERVER,
+                502,
+                CLIENT,
+                40_300,
+                &[0, 3, 0, 0, 0, 3, 2, 0x83, 0x02],
+            ),
+        ),
+    ];
+
+    let capture = read_pcap(&pcap(&packets)).expect("pcap");
+    let reads = reconstruct_reads(&capture, 502);
+    assert_eq!(reads.len(), 1);
+    assert_eq!(reads[0].exception, Some(0x02));
+    assert!(reads[0].registers.is_empty());
+}
+
+#[test]
+fn rejects_non_pcap_input() {
+    let err = read_pcap(&[0u8; 32]).expect_err("bad magic");
+    assert!(matches!(err, PcapError::BadMagic(0)));
+}
+
+fn request(transaction: u16, unit_id: u8, start: u16, count: u16) -> Vec<u8> {
+    let mut adu = transaction.to_be_bytes().to_vec();
+    adu.extend_from_slice(&[0, 0, 0, 6, unit_id, 0x03]);
+    adu.extend_from_slice(&start.to_be_bytes());
+    adu.extend_from_slice(&count.to_be_bytes());
+    adu
+}
+
+fn response(transaction: u16, unit_id: u8, registers: &[u16]) -> Vec<u8> {
+    let byte_count = (registers.len() * 2) as u8;
+    let mut adu = transaction.to_be_bytes().to_vec();
+    adu.extend_from_slice(&[0, 0]);
+    adu.extend_from_slice(&(u16::from(byte_count) + 3).to_be_bytes());
+    adu.extend_from_slice(&[unit_id, 0x03, byte_count]);
+    for value in registers {
+        adu.extend_from_slice(&value.to_be_bytes());
+    }
+    adu
+}
+
+fn frame(src: [u8; 4], src_port: u16, dst: [u8; 4], dst_port: u16, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0u8; 12];
+    frame.extend_from_slice(&[0x08, 0x00]);
+
+    let total_len = (20 + 20 + payload.len()) as u16;
+    frame.extend_from_slice(&[0x45, 0]);
+    frame.extend_from_slice(&total_len.to_be_bytes());
+    frame.extend_from_slice(&[0, 0, 0, 0, 64, 6, 0, 0]);
+    frame.extend_from_slice(&src);
+    frame.extend_from_slice(&dst);
+
+    frame.extend_from_slice(&src_port.to_be_bytes());
+    frame.extend_from_slice(&dst_port.to_be_bytes());
+    frame.extend_from_slice(&[0; 8]);
+    frame.extend_from_slice(&[0x50, 0x18, 0xff, 0xff, 0, 0, 0, 0]);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn pcap(packets: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&65_535u32.to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+    for (ts_us, data) in packets {
+        out.extend_from_slice(&((ts_us / 1_000_000) as u32).to_le_bytes());
+        out.extend_from_slice(&((ts_us % 1_000_000) as u32).to_le_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+    }
+    out
+}
@@ -1,23 +1,39 @@
 #![allow(dead_code)]
 
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use thiserror::Error;
-use tokio::sync::{mpsc, watch};
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::time::sleep;
-use tracing::{info, warn};
+use tracing::{debug, field, info, info_span, warn, Instrument};
 
-use modbus_client::{ClientConfig, ClientError, ModbusClient};
-use metrics::counter;
+use modbus_client::{
+    CaptureTap, ClientConfig, ClientError, ClientStats, ModbusClient, PreConnect,
+};
+use metrics::{counter, gauge};
 use serde::{Deserialize, Serialize};
 use sunspec_parser::ModelDefinition;
-use types::DeviceIdentity;
+use types::{DeviceIdentity, SiteInfo};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ActorConfig {
     pub poll_interval: Duration,
     pub request_timeout: Duration,
     pub jitter_ms: u64,
+    /// Start cycles on wall-clock multiples of `poll_interval` (every :00,
+    /// :05, ... for 5 s) instead of one interval after the previous cycle,
+    /// so devices are read at the same moments.
+    pub align_to_clock: bool,
+    /// Read adjacent models with one request, up to the client's
+    /// `max_batch_size` registers, and split the response per model.
+    pub coalesce_reads: bool,
+    /// Failed reads in a row after which a model is quarantined: skipped,
+    /// except for a probe every `model_probe_interval`. 0 never quarantines.
+    pub model_quarantine_after: u32,
+    pub model_probe_interval: Duration,
 }
 
 impl Default for ActorConfig {
@@ -26,6 +42,10 @@ impl Default for ActorConfig {
             poll_interval: Duration::from_secs(1),
             request_timeout: Duration::from_secs(1),
             jitter_ms: 0,
+            align_to_clock: false,
+            coalesce_reads: false,
+            model_quarantine_after: 5,
+            model_probe_interval: Duration::from_secs(300),
         }
     }
 }
@@ -38,6 +58,246 @@ pub enum PollerError {
     TooManyErrors(u32),
 }
 
+impl PollerError {
+    pub fn class(&self) -> &'static str {
+        match self {
+            PollerError::Connect(err) => err.class(),
+            PollerError::TooManyErrors(_) => "too_many_errors",
+        }
+    }
+}
+
+/// Runtime control of one actor, sent through the channel given to
+/// [`PollerActor::with_commands`]. Commands are served between cycles.
+#[derive(Debug)]
+pub enum PollerCommand {
+    /// Close the connection and stop polling until [`PollerCommand::Resume`].
+    Pause,
+    Resume,
+    /// Read one model right away and answer with the sample. The read is not
+    /// telemetry: the sample is not sent to the sample channel.
+    Read {
+        model_id: u16,
+        reply: oneshot::Sender<Result<PollSample, CommandError>>,
+    },
+}
+
+/// Why a [`PollerCommand::Read`] was not answered with a sample.
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("polling is paused")]
+    Paused,
+    #[error("model {0} is not polled on this device")]
+    UnknownModel(u16),
+    #[error("read failed: {0}")]
+    Read(#[from] ClientError),
+}
+
+/// Lifecycle stage reported through [`PollerStatus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PollerState {
+    #[default]
+    Connecting,
+    Running,
+    /// Suspended by a maintenance window, a standby collector or a pause
+    /// command; reconnects when resumed.
+    Paused,
+    Stopped,
+    Failed,
+}
+
+/// Health snapshot published by a running actor after every poll cycle.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PollerStatus {
+    pub state: PollerState,
+    pub started_at_ms: u64,
+    pub consecutive_errors: u32,
+    /// Completion time of the last cycle in which every model read succeeded.
+    pub last_success_ms: Option<u64>,
+    /// When polling last resumed after a pause; stall checks start from here.
+    #[serde(default)]
+    pub resumed_at_ms: Option<u64>,
+    /// Paused by [`PollerCommand::Pause`] until resumed by command,
+    /// independent of maintenance windows.
+    #[serde(default)]
+    pub paused_by_command: bool,
+    pub last_cycle_ms: Option<u64>,
+    pub last_cycle_timeouts: u64,
+    pub total_timeouts: u64,
+    pub cycles: u64,
+    /// Modbus requests, wire bytes and error classes during the last cycle.
+    pub last_cycle_traffic: ClientStats,
+    /// Cumulative traffic since this actor started.
+    pub traffic: ClientStats,
+    /// Device clock minus collector clock at the last clock read, in ms.
+    #[serde(default)]
+    pub clock_drift_ms: Option<i64>,
+    /// When the collector last wrote its time to the device clock.
+    #[serde(default)]
+    pub clock_set_at_ms: Option<u64>,
+    /// Read health of every model read so far, by start address.
+    #[serde(default)]
+    pub models: Vec<ModelHealth>,
+}
+
+/// Read health of one model of a device.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelHealth {
+    pub model_id: u16,
+    pub start: u16,
+    pub consecutive_errors: u32,
+    /// Failed reads since the actor started.
+    pub errors: u64,
+    pub last_success_ms: Option<u64>,
+    /// Set while the model is quarantined: when it is probed next.
+    #[serde(default)]
+    pub next_probe_ms: Option<u64>,
+}
+
+impl ModelHealth {
+    pub fn quarantined(&self) -> bool {
+        self.next_probe_ms.is_some()
+    }
+}
+
+/// Per-model read outcomes of a poller, keyed by start address. A model
+/// that keeps failing is quarantined so it neither spoils every cycle nor
+/// costs a failed request each time.
+#[derive(Debug, Default)]
+struct ModelTracker {
+    models: BTreeMap<u16, ModelHealth>,
+}
+
+impl ModelTracker {
+    fn entry(&mut self, model: &ModelDefinition) -> &mut ModelHealth {
+        self.models
+            .entry(model.start)
+            .or_insert_with(|| ModelHealth {
+                model_id: model.id,
+                start: model.start,
+                ..ModelHealth::default()
+            })
+    }
+
+    /// Whether `model` is read this cycle: unless quarantined with its probe
+    /// not yet due.
+    fn due(&self, model: &ModelDefinition, now_ms: u64) -> bool {
+        self.models
+            .get(&model.start)
+            .and_then(|health| health.next_probe_ms)
+            .is_none_or(|next_probe_ms| next_probe_ms <= now_ms)
+    }
+
+    /// Records a read of `model`. Returns true when it leaves quarantine.
+    fn succeeded(&mut self, model: &ModelDefinition, now_ms: u64) -> bool {
+        let health = self.entry(model);
+        health.consecutive_errors = 0;
+        health.last_success_ms = Some(now_ms);
+        health.next_probe_ms.take().is_some()
+    }
+
+    /// Records a failed read of `model`. Returns true when it was a probe of
+    /// a quarantined model, which is rescheduled.
+    fn failed(&mut self, model: &ModelDefinition, now_ms: u64, probe_interval: Duration) -> bool {
+        let health = self.entry(model);
+        health.consecutive_errors = health.consecutive_errors.saturating_add(1);
+        health.errors = health.errors.saturating_add(1);
+        match health.next_probe_ms.as_mut() {
+            Some(next_probe_ms) => {
+                *next_probe_ms = now_ms + probe_interval.as_millis() as u64;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Quarantines the models with `after` failures in a row and returns
+    /// them.
+    fn quarantine(&mut self, after: u32, next_probe_ms: u64) -> Vec<ModelHealth> {
+        if after == 0 {
+            return Vec::new();
+        }
+        self.models
+            .values_mut()
+            .filter(|health| !health.quarantined() && health.consecutive_errors >= after)
+            .map(|health| {
+                health.next_probe_ms = Some(next_probe_ms);
+                health.clone()
+            })
+            .collect()
+    }
+
+    fn snapshot(&self) -> Vec<ModelHealth> {
+        self.models.values().cloned().collect()
+    }
+}
+
+/// Epoch of a device clock held as uint32 seconds in two registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockEpoch {
+    /// Seconds since 2000-01-01 00:00 UTC, as the SunSpec `Tms` point counts.
+    #[default]
+    Sunspec,
+    /// Seconds since 1970-01-01 00:00 UTC.
+    Unix,
+}
+
+/// 2000-01-01 00:00 UTC in Unix milliseconds.
+const SUNSPEC_EPOCH_MS: u64 = 946_684_800_000;
+
+impl ClockEpoch {
+    fn offset_ms(self) -> u64 {
+        match self {
+            ClockEpoch::Sunspec => SUNSPEC_EPOCH_MS,
+            ClockEpoch::Unix => 0,
+        }
+    }
+
+    /// Unix milliseconds of a clock register pair; None when the device
+    /// marks the time as not implemented (`0xFFFFFFFF`).
+    pub fn decode(self, registers: &[u16]) -> Option<u64> {
+        let [high, low] = registers.get(..2)? else {
+            return None;
+        };
+        match (u32::from(*high) << 16) | u32::from(*low) {
+            u32::MAX => None,
+            seconds => Some(u64::from(seconds) * 1_000 + self.offset_ms()),
+        }
+    }
+
+    /// Register pair holding `unix_ms`, rounded down to the second; None when
+    /// the time is out of the epoch's range.
+    pub fn encode(self, unix_ms: u64) -> Option<[u16; 2]> {
+        let seconds = u32::try_from(unix_ms.checked_sub(self.offset_ms())? / 1_000).ok()?;
+        Some([(seconds >> 16) as u16, seconds as u16])
+    }
+}
+
+/// Where and how often a poller reads the device clock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClockConfig {
+    /// Address of the two clock registers.
+    pub address: u16,
+    pub epoch: ClockEpoch,
+    pub interval: Duration,
+    /// Write the collector's time to the clock when the drift is larger than
+    /// this; None only reports the drift.
+    pub set_threshold: Option<Duration>,
+}
+
+/// Points of a model that change fast enough to read every cycle: the
+/// registers `offset..offset + length`, counted from the model ID. The whole
+/// block is read once per `full_interval`; in between only the span is read
+/// and laid over the last full block, so samples still carry every point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanClass {
+    pub offset: u16,
+    pub length: u16,
+    pub full_interval: Duration,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PollSample {
     pub device: DeviceIdentity,
@@ -46,6 +306,262 @@ pub struct PollSample {
     pub start: u16,
     pub registers: Vec<u16>,
     pub collected_at_ms: u64,
+    /// Set when the sample summarises an aggregation window; `registers` then
+    /// hold the last read of the window.
+    #[serde(default)]
+    pub window: Option<SampleWindow>,
+    /// Collected while a maintenance window was active.
+    #[serde(default)]
+    pub maintenance: bool,
+    /// Part of the full snapshot read when the poller started, published so
+    /// consumers have every model before selective polling begins.
+    #[serde(default)]
+    pub baseline: bool,
+    /// Per-device number of the sample, starting at 1 and incremented for
+    /// every read, so consumers can tell lost or reordered samples from a
+    /// wall-clock step in `collected_at_ms`. 0 when unknown (replayed rows).
+    #[serde(default)]
+    pub sequence: u64,
+    /// Per-device number of the poll cycle the read belongs to; samples of
+    /// one cycle share it. 0 when unknown.
+    #[serde(default)]
+    pub cycle_id: u64,
+    /// Site of the collector, from its `[site]` config; stamped when the
+    /// sample is buffered.
+    #[serde(default)]
+    pub site: Option<SiteInfo>,
+    /// Milliseconds since the collector started on the monotonic clock, so
+    /// consumers can order and space samples across wall-clock steps. 0 when
+    /// unknown (replayed rows).
+    #[serde(default)]
+    pub monotonic_ms: u64,
+    /// Collected while the host clock was known not to be synchronized, so
+    /// `collected_at_ms` may be off.
+    #[serde(default)]
+    pub clock_unsynchronized: bool,
+    /// Tenant namespace of the collector (`kafka.namespace`).
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+/// Aggregation window a [`PollSample`] stands for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleWindow {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Number of reads folded into the window.
+    pub samples: u64,
+    pub points: Vec<PointStats>,
+}
+
+/// Min/avg/max of one decoded SunSpec point over a window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointStats {
+    pub name: String,
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+impl PollSample {
+    pub fn new(
+        device: DeviceIdentity,
+        model_id: u16,
+        model_name: impl Into<String>,
+        start: u16,
+        registers: Vec<u16>,
+        collected_at_ms: u64,
+    ) -> Self {
+        Self {
+            device,
+            model_id,
+            model_name: model_name.into(),
+            start,
+            registers,
+            collected_at_ms,
+            window: None,
+            maintenance: false,
+            baseline: false,
+            sequence: 0,
+            cycle_id: 0,
+            site: None,
+            monotonic_ms: 0,
+            clock_unsynchronized: false,
+            namespace: None,
+        }
+    }
+}
+
+/// When a sample was read, as [`SampleClock::now`] gives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleStamp {
+    pub collected_at_ms: u64,
+    pub monotonic_ms: u64,
+    pub clock_unsynchronized: bool,
+}
+
+#[derive(Debug)]
+struct SampleClockState {
+    /// Wall time minus monotonic time at the last anchor; only used when
+    /// anchored.
+    anchor_ms: AtomicU64,
+    anchored: bool,
+    unsynchronized: AtomicBool,
+}
+
+/// Timestamps samples. Clones share their state, so one clock handed to
+/// every poller flags all of them when the host clock is found
+/// unsynchronized. An anchored clock takes wall time from the monotonic
+/// clock, so steps of the system clock between anchors do not show up in
+/// `collected_at_ms`.
+#[derive(Debug, Clone)]
+pub struct SampleClock {
+    state: Arc<SampleClockState>,
+}
+
+impl Default for SampleClock {
+    /// Wall-clock timestamps.
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl SampleClock {
+    /// A clock anchored to the current wall time.
+    pub fn anchored() -> Self {
+        Self::new(true)
+    }
+
+    fn new(anchored: bool) -> Self {
+        let clock = Self {
+            state: Arc::new(SampleClockState {
+                anchor_ms: AtomicU64::new(0),
+                anchored,
+                unsynchronized: AtomicBool::new(false),
+            }),
+        };
+        clock.anchor();
+        clock
+    }
+
+    pub fn is_anchored(&self) -> bool {
+        self.state.anchored
+    }
+
+    /// Takes the current wall time as the reference of an anchored clock,
+    /// e.g. once the host clock is synchronized again.
+    pub fn anchor(&self) {
+        let anchor_ms = unix_ms().saturating_sub(monotonic_ms());
+        self.state.anchor_ms.store(anchor_ms, Ordering::Relaxed);
+    }
+
+    pub fn set_unsynchronized(&self, unsynchronized: bool) {
+        self.state
+            .unsynchronized
+            .store(unsynchronized, Ordering::Relaxed);
+    }
+
+    pub fn is_unsynchronized(&self) -> bool {
+        self.state.unsynchronized.load(Ordering::Relaxed)
+    }
+
+    pub fn now(&self) -> SampleStamp {
+        let monotonic_ms = monotonic_ms();
+        let collected_at_ms = if self.state.anchored {
+            self.state.anchor_ms.load(Ordering::Relaxed) + monotonic_ms
+        } else {
+            unix_ms()
+        };
+        SampleStamp {
+            collected_at_ms,
+            monotonic_ms,
+            clock_unsynchronized: self.is_unsynchronized(),
+        }
+    }
+
+    /// `sample` as read now.
+    pub fn stamp(&self, mut sample: PollSample) -> PollSample {
+        let stamp = self.now();
+        sample.collected_at_ms = stamp.collected_at_ms;
+        sample.monotonic_ms = stamp.monotonic_ms;
+        sample.clock_unsynchronized = stamp.clock_unsynchronized;
+        sample
+    }
+}
+
+/// Stretches the poll interval of every poller sharing it, e.g. while the
+/// host runs short of disk space or memory. Clones share their state; a
+/// factor of 1 polls at the configured rate.
+#[derive(Debug, Clone)]
+pub struct PollThrottle {
+    factor: Arc<AtomicU32>,
+}
+
+impl Default for PollThrottle {
+    fn default() -> Self {
+        Self {
+            factor: Arc::new(AtomicU32::new(1)),
+        }
+    }
+}
+
+impl PollThrottle {
+    pub fn set_factor(&self, factor: u32) {
+        self.factor.store(factor.max(1), Ordering::Relaxed);
+    }
+
+    pub fn factor(&self) -> u32 {
+        self.factor.load(Ordering::Relaxed)
+    }
+
+    /// `interval` stretched by the current factor.
+    pub fn interval(&self, interval: Duration) -> Duration {
+        interval.saturating_mul(self.factor())
+    }
+}
+
+/// Milliseconds on the monotonic clock since it was first read in this
+/// process.
+fn monotonic_ms() -> u64 {
+    static STARTED: OnceLock<Instant> = OnceLock::new();
+    STARTED.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+/// Sample and cycle numbering of one device. Shared between the actors
+/// polling the device over its lifetime, so a respawned poller continues
+/// where the previous one stopped.
+#[derive(Debug, Default)]
+pub struct SampleCounters {
+    sequence: AtomicU64,
+    cycle: AtomicU64,
+}
+
+impl SampleCounters {
+    /// Sequence number for the next sample; the first is 1.
+    pub fn next_sequence(&self) -> u64 {
+        self.sequence.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// ID of the next poll cycle; the first is 1.
+    pub fn next_cycle(&self) -> u64 {
+        self.cycle.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// A read made outside the poll cycle because the collector asked for it,
+/// with the ID of the request it answers.
+#[derive(Debug)]
+pub struct TriggeredSample {
+    pub trigger_id: u64,
+    pub sample: PollSample,
+}
+
+/// Out-of-cycle reads: whenever `trigger` changes, the models in `model_ids`
+/// are read right away and sent to `sender`.
+struct TriggeredReads {
+    trigger: watch::Receiver<u64>,
+    model_ids: Vec<u16>,
+    sender: mpsc::Sender<TriggeredSample>,
 }
 
 pub struct PollerActor {
@@ -55,9 +571,37 @@ pub struct PollerActor {
     sender: mpsc::Sender<PollSample>,
     shutdown: watch::Receiver<bool>,
     config: ActorConfig,
+    config_updates: Option<watch::Receiver<ActorConfig>>,
+    model_intervals: HashMap<u16, Duration>,
+    scan_classes: HashMap<u16, ScanClass>,
+    /// Models of the start-up snapshot, cleared once it has been read.
+    snapshot_models: Option<Vec<ModelDefinition>>,
+    pause: watch::Receiver<bool>,
+    clock: Option<ClockConfig>,
+    status: watch::Sender<PollerStatus>,
+    counters: Arc<SampleCounters>,
+    timestamps: SampleClock,
+    throttle: PollThrottle,
+    triggered_reads: Option<TriggeredReads>,
+    pre_connect: Option<Arc<dyn PreConnect>>,
+    capture: Option<CaptureTap>,
+    #[cfg(feature = "fault-injection")]
+    faults: Option<modbus_client::FaultInjector>,
+    commands: Option<mpsc::Receiver<PollerCommand>>,
+    /// Paused by command.
+    held: bool,
+    model_health: ModelTracker,
+}
+
+/// Why the poll loop returned without an error.
+enum LoopExit {
+    Shutdown,
+    Paused,
 }
 
 const MAX_CONSECUTIVE_ERRORS: u32 = 10;
+/// Most registers one Modbus read may return.
+const MAX_READ_REGISTERS: u16 = 125;
 
 impl PollerActor {
     pub fn new(
@@ -68,6 +612,10 @@ impl PollerActor {
         shutdown: watch::Receiver<bool>,
         config: ActorConfig,
     ) -> Self {
+        let (status, _) = watch::channel(PollerStatus {
+            started_at_ms: unix_ms(),
+            ..PollerStatus::default()
+        });
         Self {
             identity,
             modbus_config,
@@ -75,93 +623,557 @@ impl PollerActor {
             sender,
             shutdown,
             config,
+            config_updates: None,
+            model_intervals: HashMap::new(),
+            scan_classes: HashMap::new(),
+            snapshot_models: None,
+            // Never paused unless a pause channel is attached.
+            pause: watch::channel(false).1,
+            clock: None,
+            status,
+            counters: Arc::default(),
+            timestamps: SampleClock::default(),
+            throttle: PollThrottle::default(),
+            triggered_reads: None,
+            pre_connect: None,
+            capture: None,
+            #[cfg(feature = "fault-injection")]
+            faults: None,
+            commands: None,
+            held: false,
+            model_health: ModelTracker::default(),
         }
     }
 
+    /// Follow configuration changes published on `updates`. Poll interval and
+    /// jitter apply from the next cycle; the request timeout on the next connect.
+    pub fn with_config_updates(mut self, mut updates: watch::Receiver<ActorConfig>) -> Self {
+        self.config = updates.borrow_and_update().clone();
+        self.config_updates = Some(updates);
+        self
+    }
+
+    /// Read the given models (by model ID) at most once per interval instead
+    /// of every cycle. Intervals shorter than the poll interval have no effect.
+    pub fn with_model_intervals(mut self, intervals: HashMap<u16, Duration>) -> Self {
+        self.model_intervals = intervals;
+        self
+    }
+
+    /// Read only the fast points of the given models (by model ID) most
+    /// cycles, and their whole block once per [`ScanClass::full_interval`].
+    /// Spans that do not fit in a model's block are ignored for it.
+    pub fn with_scan_classes(mut self, classes: HashMap<u16, ScanClass>) -> Self {
+        self.scan_classes = classes;
+        self
+    }
+
+    /// Read `models` once as the first cycle after connecting and flag those
+    /// samples as [`PollSample::baseline`]. Usually every model the device
+    /// exposes, including ones polled rarely or not at all; regular polling
+    /// starts one interval later.
+    pub fn with_initial_snapshot(mut self, models: Vec<ModelDefinition>) -> Self {
+        self.snapshot_models = Some(models);
+        self
+    }
+
+    /// Suspend polling while `pause` holds `true`. The Modbus connection is
+    /// closed for the pause and opened again once it is lifted.
+    pub fn with_pause(mut self, pause: watch::Receiver<bool>) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Read the device clock once per [`ClockConfig::interval`] and report
+    /// its drift from the collector clock in [`PollerStatus::clock_drift_ms`].
+    pub fn with_clock(mut self, clock: ClockConfig) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Number samples and cycles with `counters` instead of starting at 1,
+    /// typically the counters of the device's previous actor.
+    pub fn with_counters(mut self, counters: Arc<SampleCounters>) -> Self {
+        self.counters = counters;
+        self
+    }
+
+    /// Timestamp samples with `clock`, typically one shared by every poller.
+    pub fn with_sample_clock(mut self, clock: SampleClock) -> Self {
+        self.timestamps = clock;
+        self
+    }
+
+    /// Wait between cycles as long as `throttle` says, typically one shared
+    /// by every poller.
+    pub fn with_throttle(mut self, throttle: PollThrottle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Read the models in `model_ids` as soon as `trigger` changes, also
+    /// between cycles, and send them with the new trigger value to `sender`.
+    /// Triggered reads are not telemetry: they do not go to the sample
+    /// channel and do not count against the cycle. A trigger arriving during
+    /// a cycle is served when the cycle ends.
+    pub fn with_triggered_reads(
+        mut self,
+        mut trigger: watch::Receiver<u64>,
+        model_ids: Vec<u16>,
+        sender: mpsc::Sender<TriggeredSample>,
+    ) -> Self {
+        trigger.mark_unchanged();
+        self.triggered_reads = Some(TriggeredReads {
+            trigger,
+            model_ids,
+            sender,
+        });
+        self
+    }
+
+    /// Run `hook` before every connect, e.g. to log in to a device that only
+    /// accepts Modbus after an HTTP session was established. A failing hook
+    /// fails the connect.
+    pub fn with_pre_connect(mut self, hook: Arc<dyn PreConnect>) -> Self {
+        self.pre_connect = Some(hook);
+        self
+    }
+
+    /// Hand every Modbus exchange to `capture` while it is switched on. The
+    /// tap outlives reconnects, so switching it stays in effect.
+    pub fn with_capture(mut self, capture: CaptureTap) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Inject `faults` into the reads of every connection, to exercise error
+    /// handling, backoff and restarts under sustained failure.
+    #[cfg(feature = "fault-injection")]
+    pub fn with_faults(mut self, faults: modbus_client::FaultInjector) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
+    /// Serve the commands sent on `commands`. With `paused` the actor starts
+    /// paused as if it had received [`PollerCommand::Pause`], e.g. because
+    /// the device's previous actor was.
+    pub fn with_commands(mut self, commands: mpsc::Receiver<PollerCommand>, paused: bool) -> Self {
+        self.commands = Some(commands);
+        self.held = paused;
+        self.status
+            .send_modify(|current| current.paused_by_command = paused);
+        self
+    }
+
+    /// Subscribe to health updates. Receivers stay valid after the actor exits
+    /// and keep the final status.
+    pub fn status(&self) -> watch::Receiver<PollerStatus> {
+        self.status.subscribe()
+    }
+
     pub async fn run(mut self) -> Result<(), PollerError> {
+        let status = self.status.clone();
+        let result = loop {
+            if !self.wait_while_paused().await {
+                break Ok(());
+            }
+            match self.poll_loop().await {
+                Ok(LoopExit::Paused) => continue,
+                Ok(LoopExit::Shutdown) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+        };
+        status.send_modify(|current| {
+            current.state = if result.is_ok() {
+                PollerState::Stopped
+            } else {
+                PollerState::Failed
+            };
+        });
+        result
+    }
+
+    /// Waits until the pause is lifted. Returns `false` on shutdown.
+    async fn wait_while_paused(&mut self) -> bool {
+        if !*self.pause.borrow_and_update() && !self.held {
+            return true;
+        }
+        if self.held {
+            info!(ip = %self.identity.ip, "polling paused by command");
+        } else {
+            info!(ip = %self.identity.ip, "polling paused for maintenance or standby");
+        }
+        self.status
+            .send_modify(|current| current.state = PollerState::Paused);
+        loop {
+            if *self.shutdown.borrow() {
+                return false;
+            }
+            tokio::select! {
+                changed = self.pause.changed() => {
+                    if changed.is_err() {
+                        // Without a sender the window can no longer close.
+                        self.pause = watch::channel(false).1;
+                    }
+                }
+                Some(command) = next_command(self.commands.as_mut()) => {
+                    self.serve_command(command, None).await;
+                }
+                Ok(()) = self.shutdown.changed() => {}
+            }
+            if !*self.pause.borrow_and_update() && !self.held {
+                break;
+            }
+        }
+        info!(ip = %self.identity.ip, "polling resumed");
+        self.status.send_modify(|current| {
+            current.state = PollerState::Connecting;
+            current.resumed_at_ms = Some(unix_ms());
+        });
+        true
+    }
+
+    async fn poll_loop(&mut self) -> Result<LoopExit, PollerError> {
         let mut modbus_config = self.modbus_config.clone();
         modbus_config.timeout_ms = self.config.request_timeout.as_millis() as u64;
-        let client = ModbusClient::connect(modbus_config).await?;
+        let mut client =
+            ModbusClient::connect_with(modbus_config, self.pre_connect.as_deref()).await?;
+        if let Some(capture) = self.capture.clone() {
+            client = client.with_capture(capture);
+        }
+        #[cfg(feature = "fault-injection")]
+        if let Some(faults) = self.faults.clone() {
+            client = client.with_faults(faults);
+        }
+        self.status
+            .send_modify(|current| current.state = PollerState::Running);
         let mut iteration = 0u64;
         let mut consecutive_errors = 0u32;
+        // Last read of each model with its own interval, keyed by start address.
+        let mut last_reads: HashMap<u16, Instant> = HashMap::new();
+        // Last full block of each model with a scan class, keyed by start
+        // address, and when it was read.
+        let mut scan_blocks: HashMap<u16, (Vec<u16>, Instant)> = HashMap::new();
+        let mut last_clock_read: Option<Instant> = None;
 
         loop {
             if *self.shutdown.borrow() {
                 info!(ip = %self.identity.ip, "poller shutdown requested");
-                break;
+                return Ok(LoopExit::Shutdown);
+            }
+            if *self.pause.borrow() || self.held {
+                return Ok(LoopExit::Paused);
             }
 
             let cycle_start = Instant::now();
             let mut timeout_count = 0u64;
             let mut cycle_had_error = false;
+            let snapshot = self.snapshot_models.take();
+            let baseline = snapshot.is_some();
+            let models = snapshot.as_ref().unwrap_or(&self.models);
+            let cycle_id = self.counters.next_cycle();
+            // Parent of the Modbus reads of this cycle, closed once it is logged.
+            let cycle_span = info_span!(
+                "poll_cycle",
+                ip = %self.identity.ip,
+                unit_id = self.identity.unit_id,
+                cycle_id,
+                models = models.len(),
+                timeouts = field::Empty,
+                failed = field::Empty,
+            );
 
-            for model in &self.models {
-                if model.length == 0 {
+            // Fast spans read instead of their whole model, and the models
+            // they belong to, keyed by the span's start address.
+            let mut fast_reads = Vec::new();
+            let mut fast_parents: HashMap<u16, &ModelDefinition> = HashMap::new();
+            let mut due = Vec::with_capacity(models.len());
+            let mut cycle_had_read = false;
+            let now_ms = unix_ms();
+            for model in models {
+                if model.length == 0 || !self.model_health.due(model, now_ms) {
                     continue;
                 }
+                if baseline {
+                    // Counts as the first read of models with their own interval.
+                    last_reads.insert(model.start, Instant::now());
+                } else if let Some(interval) = self.model_intervals.get(&model.id) {
+                    let due = last_reads
+                        .get(&model.start)
+                        .is_none_or(|last| last.elapsed() >= *interval);
+                    if !due {
+                        continue;
+                    }
+                    last_reads.insert(model.start, Instant::now());
+                }
+                if let Some(class) = self.scan_classes.get(&model.id) {
+                    let fits = u32::from(class.offset) + u32::from(class.length)
+                        <= u32::from(model.length);
+                    let full_due = baseline
+                        || scan_blocks
+                            .get(&model.start)
+                            .is_none_or(|(_, read_at)| read_at.elapsed() >= class.full_interval);
+                    if fits && class.offset > 0 && !full_due {
+                        let start = model.start + class.offset;
+                        fast_reads.push(ModelDefinition {
+                            id: model.id,
+                            name: model.name.clone(),
+                            start,
+                            length: class.length,
+                        });
+                        fast_parents.insert(start, model);
+                        continue;
+                    }
+                }
+                due.push(model);
+            }
+            due.extend(&fast_reads);
 
-                match client
-                    .read_range(self.identity.unit_id, model.start, model.length)
-                    .await
-                {
-                    Ok(registers) => {
-                        // Reset error counter on successful read (at least partial success keeps us alive)
-                        if consecutive_errors > 0 {
-                             info!(ip = %self.identity.ip, "connection recovered");
-                             consecutive_errors = 0;
+            let max_registers = self.config.coalesce_reads.then(|| {
+                self.modbus_config
+                    .max_batch_size
+                    .unwrap_or(MAX_READ_REGISTERS)
+            });
+            for group in group_reads(due, max_registers) {
+                let mut reads = Vec::with_capacity(1);
+                let merged = client
+                    .read_range(self.identity.unit_id, group.start, group.length)
+                    .instrument(cycle_span.clone())
+                    .await;
+                match merged {
+                    // The device may refuse a range spanning several models;
+                    // their own ranges are known to be readable.
+                    Err(err @ ClientError::Modbus(_)) if group.models.len() > 1 => {
+                        debug!(
+                            ip = %self.identity.ip,
+                            unit_id = self.identity.unit_id,
+                            start = group.start,
+                            length = group.length,
+                            error = %err,
+                            "merged read refused, reading the models one by one"
+                        );
+                        for model in group.models {
+                            let result = client
+                                .read_range(self.identity.unit_id, model.start, model.length)
+                                .instrument(cycle_span.clone())
+                                .await;
+                            reads.push((vec![model], result));
                         }
+                    }
+                    result => reads.push((group.models, result)),
+                }
+
+                for (read_models, result) in reads {
+                    match result {
+                        Ok(registers) => {
+                            // Reset error counter on successful read (at least partial success keeps us alive)
+                            if consecutive_errors > 0 {
+                                info!(ip = %self.identity.ip, "connection recovered");
+                                consecutive_errors = 0;
+                            }
 
-                        let sample = PollSample {
-                            device: self.identity.clone(),
-                            model_id: model.id,
-                            model_name: model.name.clone(),
-                            start: model.start,
-                            registers,
-                            collected_at_ms: unix_ms(),
-                        };
-
-                        if let Err(err) = self.sender.send(sample).await {
-                             warn!(
+                            cycle_had_read = true;
+                            let mut offset = 0usize;
+                            for model in read_models {
+                                let end = offset + model.length as usize;
+                                let Some(registers) = registers.get(offset..end) else {
+                                    break;
+                                };
+                                offset = end;
+                                let (model, registers) = match fast_parents.get(&model.start) {
+                                    Some(&parent) => {
+                                        let Some((block, _)) = scan_blocks.get_mut(&parent.start)
+                                        else {
+                                            continue;
+                                        };
+                                        let at = usize::from(model.start - parent.start);
+                                        let Some(span) = block.get_mut(at..at + registers.len())
+                                        else {
+                                            continue;
+                                        };
+                                        span.copy_from_slice(registers);
+                                        (parent, block.clone())
+                                    }
+                                    None => {
+                                        if self.scan_classes.contains_key(&model.id) {
+                                            scan_blocks.insert(
+                                                model.start,
+                                                (registers.to_vec(), Instant::now()),
+                                            );
+                                        }
+                                        (model, registers.to_vec())
+                                    }
+                                };
+                                if self.model_health.succeeded(model, unix_ms()) {
+                                    info!(
+                                        ip = %self.identity.ip,
+                                        unit_id = self.identity.unit_id,
+                                        model_id = model.id,
+                                        "quarantined model answers again"
+                                    );
+                                }
+                                let stamp = self.timestamps.now();
+                                let sample = PollSample {
+                                    device: self.identity.clone(),
+                                    model_id: model.id,
+                                    model_name: model.name.clone(),
+                                    start: model.start,
+                                    registers,
+                                    collected_at_ms: stamp.collected_at_ms,
+                                    window: None,
+                                    maintenance: false,
+                                    baseline,
+                                    sequence: self.counters.next_sequence(),
+                                    cycle_id,
+                                    site: None,
+                                    monotonic_ms: stamp.monotonic_ms,
+                                    clock_unsynchronized: stamp.clock_unsynchronized,
+                                    namespace: None,
+                                };
+                                self.send_sample(sample).await;
+                            }
+                        }
+                        Err(err) => {
+                            let now_ms = unix_ms();
+                            let probe_interval = self.config.model_probe_interval;
+                            let mut probes = 0;
+                            for model in &read_models {
+                                let model = fast_parents.get(&model.start).unwrap_or(model);
+                                if self.model_health.failed(model, now_ms, probe_interval) {
+                                    probes += 1;
+                                }
+                            }
+                            if matches!(err, ClientError::Timeout { .. }) {
+                                timeout_count += 1;
+                            }
+                            if probes == read_models.len() {
+                                // A failed probe leaves the model quarantined.
+                                debug!(
+                                    ip = %self.identity.ip,
+                                    unit_id = self.identity.unit_id,
+                                    model_id = read_models[0].id,
+                                    error = %err,
+                                    error_class = err.class(),
+                                    "quarantined model probe failed"
+                                );
+                                continue;
+                            }
+                            cycle_had_error = true;
+                            warn!(
                                 ip = %self.identity.ip,
                                 unit_id = self.identity.unit_id,
-                                model_id = model.id,
+                                model_id = read_models[0].id,
+                                merged_models = read_models.len(),
                                 error = %err,
-                                "telemetry channel send failed"
+                                error_class = err.class(),
+                                "modbus read failed"
                             );
-                            counter!("poller_error", "ip" => self.identity.ip.clone(), "type" => "channel").increment(1);
-                        } else {
-                            counter!("poller_success", "ip" => self.identity.ip.clone()).increment(1);
+                            counter!("poller_error", "ip" => self.identity.ip.clone(), "type" => "modbus").increment(1);
                         }
                     }
-                    Err(err) => {
-                        cycle_had_error = true;
-                        if matches!(err, ClientError::Timeout { .. }) {
-                            timeout_count += 1;
-                        }
-                        warn!(
-                            ip = %self.identity.ip,
-                            unit_id = self.identity.unit_id,
-                            model_id = model.id,
-                            error = %err,
-                            "modbus read failed"
-                        );
-                        counter!("poller_error", "ip" => self.identity.ip.clone(), "type" => "modbus").increment(1);
-                    }
+                }
+            }
+
+            // Only while the device answers other reads: a device that does
+            // not answer at all is a poller failure, not a model one.
+            if cycle_had_read {
+                let now_ms = unix_ms();
+                let next_probe_ms = now_ms + self.config.model_probe_interval.as_millis() as u64;
+                let quarantined = self
+                    .model_health
+                    .quarantine(self.config.model_quarantine_after, next_probe_ms);
+                for health in quarantined {
+                    warn!(
+                        ip = %self.identity.ip,
+                        unit_id = self.identity.unit_id,
+                        model_id = health.model_id,
+                        failures = health.consecutive_errors,
+                        probe_interval_ms = self.config.model_probe_interval.as_millis() as u64,
+                        "model keeps failing, quarantined"
+                    );
+                    counter!(
+                        "model_quarantined",
+                        "ip" => self.identity.ip.clone(),
+                        "model_id" => health.model_id.to_string()
+                    )
+                    .increment(1);
+                }
+            }
+
+            if let Some(clock) = self.clock.as_ref() {
+                if last_clock_read.is_none_or(|last| last.elapsed() >= clock.interval) {
+                    last_clock_read = Some(Instant::now());
+                    self.check_clock(&client, clock)
+                        .instrument(cycle_span.clone())
+                        .await;
                 }
             }
 
             if cycle_had_error {
                 consecutive_errors += 1;
-                if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
-                    warn!(ip = %self.identity.ip, errors = consecutive_errors, "max errors exceeded, exiting");
-                    return Err(PollerError::TooManyErrors(consecutive_errors));
+            }
+            if baseline {
+                info!(
+                    ip = %self.identity.ip,
+                    unit_id = self.identity.unit_id,
+                    models = models.len(),
+                    complete = !cycle_had_error,
+                    "initial snapshot read"
+                );
+            }
+
+            let cycle_traffic = client.take_stats();
+            let ip = self.identity.ip.clone();
+            counter!("modbus_requests", "ip" => ip.clone()).increment(cycle_traffic.requests);
+            counter!("modbus_bytes_sent", "ip" => ip.clone()).increment(cycle_traffic.bytes_sent);
+            counter!("modbus_bytes_received", "ip" => ip).increment(cycle_traffic.bytes_received);
+
+            let cycle_end_ms = unix_ms();
+            self.status.send_modify(|current| {
+                current.traffic.merge(&cycle_traffic);
+                current.last_cycle_traffic = cycle_traffic;
+                current.consecutive_errors = consecutive_errors;
+                if !cycle_had_error {
+                    current.last_success_ms = Some(cycle_end_ms);
+                }
+                current.last_cycle_ms = Some(cycle_end_ms);
+                current.last_cycle_timeouts = timeout_count;
+                current.total_timeouts = current.total_timeouts.saturating_add(timeout_count);
+                current.cycles = current.cycles.saturating_add(1);
+                current.models = self.model_health.snapshot();
+            });
+
+            if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
+                warn!(
+                    ip = %self.identity.ip,
+                    errors = consecutive_errors,
+                    error_class = "too_many_errors",
+                    "max errors exceeded, exiting"
+                );
+                return Err(PollerError::TooManyErrors(consecutive_errors));
+            }
+
+            if let Some(updates) = self.config_updates.as_mut() {
+                if updates.has_changed().unwrap_or(false) {
+                    self.config = updates.borrow_and_update().clone();
+                    info!(ip = %self.identity.ip, "poller config updated");
                 }
             }
 
             iteration = iteration.wrapping_add(1);
             let elapsed = cycle_start.elapsed();
             let lag = elapsed.saturating_sub(self.config.poll_interval);
-            let delay = jittered_delay(self.config.poll_interval, self.config.jitter_ms, iteration);
+            // Aligned cycles aim at a wall-clock instant; the wait below is
+            // extended if the timer fires before the clock gets there.
+            let poll_interval = self.throttle.interval(self.config.poll_interval);
+            let (delay, aligned_at_ms) = if self.config.align_to_clock {
+                let now_ms = unix_ms();
+                let at_ms = next_aligned_ms(now_ms, poll_interval.as_millis() as u64);
+                (Duration::from_millis(at_ms - now_ms), Some(at_ms))
+            } else {
+                let delay = jittered_delay(poll_interval, self.config.jitter_ms, iteration);
+                (delay, None)
+            };
             info!(
                 ip = %self.identity.ip,
                 unit_id = self.identity.unit_id,
@@ -172,22 +1184,321 @@ impl PollerActor {
                 delay_ms = delay.as_millis(),
                 "poll cycle complete"
             );
+            cycle_span.record("timeouts", timeout_count);
+            cycle_span.record("failed", cycle_had_error);
+            drop(cycle_span);
 
-            tokio::select! {
-                _ = sleep(delay) => {},
-                _ = self.shutdown.changed() => {
-                    if *self.shutdown.borrow() {
-                        info!(ip = %self.identity.ip, "poller shutdown requested");
+            let wake = sleep(delay);
+            tokio::pin!(wake);
+            loop {
+                tokio::select! {
+                    _ = &mut wake => {
+                        let now_ms = unix_ms();
+                        match aligned_at_ms {
+                            Some(at_ms) if now_ms < at_ms => {
+                                let rest = Duration::from_millis(at_ms - now_ms);
+                                wake.as_mut().reset(tokio::time::Instant::now() + rest);
+                            }
+                            _ => break,
+                        }
+                    }
+                    _ = self.shutdown.changed() => {
+                        if *self.shutdown.borrow() {
+                            info!(ip = %self.identity.ip, "poller shutdown requested");
+                            return Ok(LoopExit::Shutdown);
+                        }
                         break;
                     }
+                    Ok(()) = self.pause.changed() => break,
+                    Some(trigger_id) = next_trigger(self.triggered_reads.as_mut()) => {
+                        self.read_triggered(&client, trigger_id).await;
+                    }
+                    Some(command) = next_command(self.commands.as_mut()) => {
+                        if self.serve_command(command, Some(&client)).await {
+                            break;
+                        }
+                    }
                 }
             }
         }
+    }
+}
 
-        Ok(())
+impl PollerActor {
+    /// Sends a sample of a poll cycle on to the buffer.
+    async fn send_sample(&self, sample: PollSample) {
+        let model_id = sample.model_id;
+        if let Err(err) = self.sender.send(sample).await {
+            warn!(
+                ip = %self.identity.ip,
+                unit_id = self.identity.unit_id,
+                model_id,
+                error = %err,
+                error_class = "channel",
+                "telemetry channel send failed"
+            );
+            counter!("poller_error", "ip" => self.identity.ip.clone(), "type" => "channel")
+                .increment(1);
+        } else {
+            counter!("poller_success", "ip" => self.identity.ip.clone()).increment(1);
+        }
     }
 }
 
+/// Models read with one request.
+#[derive(Debug, Clone)]
+pub struct ReadGroup<'a> {
+    pub start: u16,
+    pub length: u16,
+    pub models: Vec<&'a ModelDefinition>,
+}
+
+impl<'a> ReadGroup<'a> {
+    fn single(model: &'a ModelDefinition) -> Self {
+        Self {
+            start: model.start,
+            length: model.length,
+            models: vec![model],
+        }
+    }
+}
+
+/// Splits the models due in a cycle into reads. Without `max_registers`
+/// every model is read on its own, in the given order; with it, models that
+/// follow each other in register space share a read as long as they fit
+/// in `max_registers` together.
+pub fn group_reads(
+    mut models: Vec<&ModelDefinition>,
+    max_registers: Option<u16>,
+) -> Vec<ReadGroup<'_>> {
+    let Some(max_registers) = max_registers else {
+        return models.into_iter().map(ReadGroup::single).collect();
+    };
+    models.sort_by_key(|model| model.start);
+    let mut groups: Vec<ReadGroup<'_>> = Vec::new();
+    for model in models {
+        if let Some(group) = groups.last_mut() {
+            let adjacent =
+                u32::from(group.start) + u32::from(group.length) == u32::from(model.start);
+            let fits =
+                u32::from(group.length) + u32::from(model.length) <= u32::from(max_registers);
+            if adjacent && fits {
+                group.length += model.length;
+                group.models.push(model);
+                continue;
+            }
+        }
+        groups.push(ReadGroup::single(model));
+    }
+    groups
+}
+
+/// Resolves with the next trigger value; pending forever without triggered
+/// reads, and None once the trigger's sender is gone.
+async fn next_trigger(reads: Option<&mut TriggeredReads>) -> Option<u64> {
+    let Some(reads) = reads else {
+        return std::future::pending().await;
+    };
+    reads.trigger.changed().await.ok()?;
+    let trigger_id = *reads.trigger.borrow_and_update();
+    Some(trigger_id)
+}
+
+/// Resolves with the next command; pending forever without a command
+/// channel, and None once every sender is gone.
+async fn next_command(
+    commands: Option<&mut mpsc::Receiver<PollerCommand>>,
+) -> Option<PollerCommand> {
+    let Some(commands) = commands else {
+        return std::future::pending().await;
+    };
+    commands.recv().await
+}
+
+impl PollerActor {
+    /// Serves one command; `client` is None while paused. Returns whether
+    /// the pause changed, so the poll loop checks it before the next cycle.
+    async fn serve_command(
+        &mut self,
+        command: PollerCommand,
+        client: Option<&ModbusClient>,
+    ) -> bool {
+        let held = match command {
+            PollerCommand::Pause => true,
+            PollerCommand::Resume => false,
+            PollerCommand::Read { model_id, reply } => {
+                let result = match client {
+                    Some(client) => self.read_model(client, model_id).await,
+                    None => Err(CommandError::Paused),
+                };
+                let _ = reply.send(result);
+                return false;
+            }
+        };
+        if held == self.held {
+            return false;
+        }
+        self.held = held;
+        info!(ip = %self.identity.ip, unit_id = self.identity.unit_id, paused = held, "poller pause changed by command");
+        self.status
+            .send_modify(|current| current.paused_by_command = held);
+        true
+    }
+
+    /// Reads `model_id` outside the cycle. Failures do not count against the
+    /// poll cycle or the model's health.
+    async fn read_model(
+        &self,
+        client: &ModbusClient,
+        model_id: u16,
+    ) -> Result<PollSample, CommandError> {
+        let model = self
+            .models
+            .iter()
+            .find(|model| model.id == model_id && model.length > 0)
+            .ok_or(CommandError::UnknownModel(model_id))?;
+        let registers = client
+            .read_range(self.identity.unit_id, model.start, model.length)
+            .await
+            .map_err(|err| {
+                warn!(
+                    ip = %self.identity.ip,
+                    unit_id = self.identity.unit_id,
+                    model_id,
+                    error = %err,
+                    error_class = err.class(),
+                    "on-demand read failed"
+                );
+                err
+            })?;
+        Ok(self.timestamps.stamp(PollSample::new(
+            self.identity.clone(),
+            model.id,
+            model.name.clone(),
+            model.start,
+            registers,
+            0,
+        )))
+    }
+}
+
+impl PollerActor {
+    /// Serves one trigger: reads the triggered models and sends them on.
+    /// Failures are logged and do not count against the poll cycle.
+    async fn read_triggered(&self, client: &ModbusClient, trigger_id: u64) {
+        let Some(reads) = self.triggered_reads.as_ref() else {
+            return;
+        };
+        let models = self
+            .models
+            .iter()
+            .filter(|model| model.length > 0 && reads.model_ids.contains(&model.id));
+        for model in models {
+            let registers = match client
+                .read_range(self.identity.unit_id, model.start, model.length)
+                .await
+            {
+                Ok(registers) => registers,
+                Err(err) => {
+                    warn!(
+                        ip = %self.identity.ip,
+                        unit_id = self.identity.unit_id,
+                        model_id = model.id,
+                        trigger_id,
+                        error = %err,
+                        error_class = err.class(),
+                        "triggered read failed"
+                    );
+                    counter!("poller_error", "ip" => self.identity.ip.clone(), "type" => "triggered").increment(1);
+                    continue;
+                }
+            };
+            let sample = self.timestamps.stamp(PollSample::new(
+                self.identity.clone(),
+                model.id,
+                model.name.clone(),
+                model.start,
+                registers,
+                0,
+            ));
+            if reads
+                .sender
+                .send(TriggeredSample { trigger_id, sample })
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+
+    /// Reads the device clock, publishes its drift and, past the threshold,
+    /// sets it to the collector's time. Failures are logged and do not count
+    /// against the poll cycle.
+    async fn check_clock(&self, client: &ModbusClient, clock: &ClockConfig) {
+        let ip = self.identity.ip.clone();
+        let unit_id = self.identity.unit_id;
+        let requested_ms = unix_ms();
+        let registers = match client.read_range(unit_id, clock.address, 2).await {
+            Ok(registers) => registers,
+            Err(err) => {
+                warn!(ip = %ip, unit_id, error = %err, error_class = err.class(), "device clock read failed");
+                return;
+            }
+        };
+        // The device answered somewhere between request and response.
+        let collector_ms = requested_ms + unix_ms().saturating_sub(requested_ms) / 2;
+        let Some(device_ms) = clock.epoch.decode(&registers) else {
+            warn!(ip = %ip, unit_id, address = clock.address, "device clock not implemented");
+            return;
+        };
+        let drift_ms = clock_drift_ms(device_ms, collector_ms);
+        gauge!("device_clock_drift_seconds", "ip" => ip.clone(), "unit_id" => unit_id.to_string())
+            .set(drift_ms as f64 / 1_000.0);
+        self.status
+            .send_modify(|current| current.clock_drift_ms = Some(drift_ms));
+
+        let Some(threshold) = clock.set_threshold else {
+            return;
+        };
+        if drift_ms.unsigned_abs() <= threshold.as_millis() as u64 {
+            return;
+        }
+        let now_ms = unix_ms();
+        let Some(values) = clock.epoch.encode(now_ms) else {
+            return;
+        };
+        match client
+            .write_registers(unit_id, clock.address, &values)
+            .await
+        {
+            Ok(()) => {
+                info!(ip = %ip, unit_id, drift_ms, "device clock set to collector time");
+                counter!("device_clock_set", "ip" => ip).increment(1);
+                self.status
+                    .send_modify(|current| current.clock_set_at_ms = Some(now_ms));
+            }
+            Err(err) => {
+                warn!(ip = %ip, unit_id, drift_ms, error = %err, error_class = err.class(), "device clock write failed");
+                counter!("poller_error", "ip" => ip, "type" => "clock_write").increment(1);
+            }
+        }
+    }
+}
+
+/// Device clock minus collector clock; positive when the device is ahead.
+pub fn clock_drift_ms(device_ms: u64, collector_ms: u64) -> i64 {
+    (device_ms as i128 - collector_ms as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Start of the next aligned cycle: the first multiple of `interval_ms`
+/// since the Unix epoch after `now_ms`. Boundaries a slow cycle ran past
+/// are skipped.
+pub fn next_aligned_ms(now_ms: u64, interval_ms: u64) -> u64 {
+    let interval_ms = interval_ms.max(1);
+    (now_ms / interval_ms + 1).saturating_mul(interval_ms)
+}
+
 fn jittered_delay(base: Duration, jitter_ms: u64, iteration: u64) -> Duration {
     if jitter_ms == 0 {
         return base;
@@ -0,0 +1,234 @@
+//! SunSpec access for programs that embed it instead of running the
+//! collector: find a device's model chain, read whole models or single
+//! points by name, and write points.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), sunspec_device::DeviceError> {
+//! use modbus_client::ClientConfig;
+//! use sunspec_device::SunspecDevice;
+//!
+//! let config = ClientConfig {
+//!     host: "192.168.1.20".to_string(),
+//!     ..ClientConfig::default()
+//! };
+//! let mut device = SunspecDevice::connect(config, 1).await?;
+//! device.discover_models().await?;
+//! let power = device.read_point(103, "W").await?;
+//! device.write_point(124, "WChaMax", 5_000.0).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use modbus_client::{ClientConfig, ClientError, ModbusClient};
+use sunspec_parser::{
+    base_address_candidates, decode_points, decode_typed_points, encode_point, has_sunspec_marker,
+    point_span, ModelDefinition, ParserError,
+};
+use thiserror::Error;
+use tracing::debug;
+use types::PointValue;
+
+/// Base address tried first unless [`SunspecDevice::with_base_address`]
+/// names another.
+pub const DEFAULT_BASE_ADDRESS: u16 = 40_000;
+/// Most registers one Modbus read may return.
+const MAX_READ_REGISTERS: u16 = 125;
+const END_MODEL_ID: u16 = 0xFFFF;
+
+#[derive(Debug, Error)]
+pub enum DeviceError {
+    #[error(transparent)]
+    Client(#[from] ClientError),
+    #[error(transparent)]
+    Parser(#[from] ParserError),
+    #[error("no SunSpec marker at base addresses {0:?}")]
+    NoMarker(Vec<u16>),
+    #[error("model {0} is not on this device")]
+    UnknownModel(u16),
+    #[error("point {name} of model {model_id} is not implemented by the device")]
+    NotImplemented { model_id: u16, name: String },
+}
+
+/// The registers of one model, as read from the device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelBlock {
+    pub model: ModelDefinition,
+    /// Registers starting at the model ID.
+    pub registers: Vec<u16>,
+}
+
+impl ModelBlock {
+    /// Scaled points by SunSpec name, leaving out points the device does not
+    /// implement. Empty for models without a point table.
+    pub fn points(&self) -> Vec<(&'static str, f64)> {
+        decode_points(self.model.id, &self.registers).unwrap_or_default()
+    }
+
+    /// String, enum and bit field points by SunSpec name.
+    pub fn typed_points(&self) -> Vec<(&'static str, PointValue)> {
+        decode_typed_points(self.model.id, &self.registers).unwrap_or_default()
+    }
+}
+
+/// Value of one point read with [`SunspecDevice::read_point`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PointReading {
+    /// A numeric point with its scale factor applied.
+    Scaled(f64),
+    /// A string, enum or bit field point.
+    Typed(PointValue),
+}
+
+/// One SunSpec unit behind a Modbus/TCP connection.
+#[derive(Debug)]
+pub struct SunspecDevice {
+    client: ModbusClient,
+    unit_id: u8,
+    base_address: u16,
+    models: Vec<ModelDefinition>,
+}
+
+impl SunspecDevice {
+    /// Connects to `config.host`. Without `config.max_batch_size`, reads
+    /// are split at the Modbus limit of 125 registers.
+    pub async fn connect(mut config: ClientConfig, unit_id: u8) -> Result<Self, DeviceError> {
+        config.max_batch_size.get_or_insert(MAX_READ_REGISTERS);
+        Ok(Self {
+            client: ModbusClient::connect(config).await?,
+            unit_id,
+            base_address: DEFAULT_BASE_ADDRESS,
+            models: Vec::new(),
+        })
+    }
+
+    /// Try `base_address` first when looking for the model chain.
+    pub fn with_base_address(mut self, base_address: u16) -> Self {
+        self.base_address = base_address;
+        self
+    }
+
+    /// The client, e.g. for reads outside the SunSpec map.
+    pub fn client(&self) -> &ModbusClient {
+        &self.client
+    }
+
+    /// The model chain found by the last [`Self::discover_models`]; empty
+    /// before that.
+    pub fn models(&self) -> &[ModelDefinition] {
+        &self.models
+    }
+
+    /// Finds the base address among the standard candidates and walks the
+    /// model headers up to the end marker. Reading and writing models needs
+    /// the chain, so this comes first.
+    pub async fn discover_models(&mut self) -> Result<&[ModelDefinition], DeviceError> {
+        let base_address = self.find_base_address().await?;
+        let mut models = Vec::new();
+        let mut address = base_address
+            .checked_add(2)
+            .ok_or(ParserError::LengthOverflow)?;
+        loop {
+            let header = self.client.read_range(self.unit_id, address, 2).await?;
+            let [model_id, length] = header[..] else {
+                return Err(ParserError::UnexpectedEnd.into());
+            };
+            if model_id == END_MODEL_ID {
+                break;
+            }
+            let length = length.checked_add(2).ok_or(ParserError::LengthOverflow)?;
+            models.push(ModelDefinition::new(model_id, address, length));
+            address = address
+                .checked_add(length)
+                .ok_or(ParserError::LengthOverflow)?;
+        }
+        debug!(
+            unit_id = self.unit_id,
+            base_address,
+            models = models.len(),
+            "sunspec models discovered"
+        );
+        self.base_address = base_address;
+        self.models = models;
+        Ok(&self.models)
+    }
+
+    /// Reads the first model with `model_id`, header included.
+    pub async fn read_model(&self, model_id: u16) -> Result<ModelBlock, DeviceError> {
+        let model = self
+            .models
+            .iter()
+            .find(|model| model.id == model_id)
+            .ok_or(DeviceError::UnknownModel(model_id))?;
+        let registers = self
+            .client
+            .read_range(self.unit_id, model.start, model.length)
+            .await?;
+        Ok(ModelBlock {
+            model: model.clone(),
+            registers,
+        })
+    }
+
+    /// Reads point `name` (e.g. `W` or `St`) of the first model with
+    /// `model_id`. The whole model is read, so the point is scaled with the
+    /// factor the device reports next to it.
+    pub async fn read_point(&self, model_id: u16, name: &str) -> Result<PointReading, DeviceError> {
+        // Fails for names the model does not have.
+        point_span(model_id, &[name.to_string()])?;
+        let block = self.read_model(model_id).await?;
+        if let Some((_, value)) = block.points().into_iter().find(|(point, _)| *point == name) {
+            return Ok(PointReading::Scaled(value));
+        }
+        block
+            .typed_points()
+            .into_iter()
+            .find(|(point, _)| *point == name)
+            .map(|(_, value)| PointReading::Typed(value))
+            .ok_or_else(|| DeviceError::NotImplemented {
+                model_id,
+                name: name.to_string(),
+            })
+    }
+
+    /// Writes `value` to point `name` of the first model with `model_id`:
+    /// scaled with the factor the device currently reports, or the enum value
+    /// for enum points. Like every write, it is not retried.
+    pub async fn write_point(
+        &self,
+        model_id: u16,
+        name: &str,
+        value: f64,
+    ) -> Result<(), DeviceError> {
+        let block = self.read_model(model_id).await?;
+        let (offset, values) = encode_point(model_id, &block.registers, name, value)?;
+        if usize::from(offset) + values.len() > block.registers.len() {
+            return Err(DeviceError::NotImplemented {
+                model_id,
+                name: name.to_string(),
+            });
+        }
+        let address = block
+            .model
+            .start
+            .checked_add(offset)
+            .ok_or(ParserError::LengthOverflow)?;
+        self.client
+            .write_registers(self.unit_id, address, &values)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_base_address(&self) -> Result<u16, DeviceError> {
+        let candidates = base_address_candidates(self.base_address);
+        for &candidate in &candidates {
+            match self.client.read_range(self.unit_id, candidate, 2).await {
+                Ok(registers) if has_sunspec_marker(&registers) => return Ok(candidate),
+                Ok(_) => {}
+                Err(err) => {
+                    debug!(base_address = candidate, error = %err, "base address probe failed");
+                }
+            }
+        }
+        Err(DeviceError::NoMarker(candidates))
+    }
+}
@@ -0,0 +1,125 @@
+use modbus_client::ClientConfig;
+use modbus_mock::{model_block, MockServer, WRITE_MULTIPLE_REGISTERS};
+use sunspec_device::{DeviceError, PointReading, SunspecDevice};
+use sunspec_parser::ParserError;
+use types::PointValue;
+
+/// A device that counts registers from 1, so the chain is found at 40 001.
+const BASE_ADDRESS: u16 = 40_001;
+/// Model 124 after the marker, model 1 and model 103.
+const STORAGE: u16 = 40_123;
+
+fn storage_inverter() -> MockServer {
+    let server = MockServer::start().expect("mock server");
+    let mut inverter = model_block(103, 50);
+    // W = 4 200 * 10^-1, St = MPPT.
+    inverter[14] = 4_200;
+    inverter[15] = (-1i16) as u16;
+    inverter[38] = 4;
+    let mut storage = model_block(124, 24);
+    // WChaMax_SF = 1; InWRte is not implemented.
+    storage[18] = 1;
+    storage[25] = 0x8000;
+    server.load_sunspec(1, BASE_ADDRESS, &[model_block(1, 66), inverter, storage]);
+    server
+}
+
+async fn device(server: &MockServer) -> SunspecDevice {
+    let config = ClientConfig {
+        port: server.port(),
+        timeout_ms: 500,
+        retry_count: 0,
+        ..ClientConfig::default()
+    };
+    SunspecDevice::connect(config, 1).await.expect("connect")
+}
+
+#[tokio::test]
+async fn models_are_discovered_and_points_read_by_name() {
+    let server = storage_inverter();
+    let mut device = device(&server).await;
+    assert!(matches!(
+        device.read_model(103).await,
+        Err(DeviceError::UnknownModel(103))
+    ));
+
+    let models = device.discover_models().await.expect("discover");
+    let chain: Vec<(u16, u16, u16)> = models
+        .iter()
+        .map(|model| (model.id, model.start, model.length))
+        .collect();
+    assert_eq!(
+        chain,
+        vec![(1, 40_003, 68), (103, 40_071, 52), (124, STORAGE, 26)]
+    );
+
+    let block = device.read_model(103).await.expect("read model");
+    assert_eq!(block.registers.len(), 52);
+    assert_eq!(
+        device.read_point(103, "W").await.expect("W"),
+        PointReading::Scaled(420.0)
+    );
+    assert!(matches!(
+        device.read_point(103, "St").await.expect("St"),
+        PointReading::Typed(PointValue::Enum16 { value: 4, .. })
+    ));
+    assert!(matches!(
+        device.read_point(124, "InWRte").await,
+        Err(DeviceError::NotImplemented { model_id: 124, .. })
+    ));
+    assert!(matches!(
+        device.read_point(103, "Nope").await,
+        Err(DeviceError::Parser(ParserError::UnknownPoint {
+            model_id: 103,
+            ..
+        }))
+    ));
+}
+
+#[tokio::test]
+async fn points_are_written_with_the_device_scale_factor() {
+    let server = storage_inverter();
+    let mut device = device(&server).await;
+    device.discover_models().await.expect("discover");
+
+    device
+        .write_point(124, "WChaMax", 5_000.0)
+        .await
+        .expect("write WChaMax");
+    assert_eq!(server.registers(1, STORAGE + 2, 1), Some(vec![500]));
+    assert_eq!(
+        device.read_point(124, "WChaMax").await.expect("WChaMax"),
+        PointReading::Scaled(5_000.0)
+    );
+    device
+        .write_point(124, "ChaGriSet", 1.0)
+        .await
+        .expect("write ChaGriSet");
+    assert_eq!(server.registers(1, STORAGE + 17, 1), Some(vec![1]));
+    let writes = server
+        .requests()
+        .iter()
+        .filter(|request| request.function == WRITE_MULTIPLE_REGISTERS)
+        .count();
+    assert_eq!(writes, 2);
+
+    assert!(matches!(
+        device.write_point(124, "WChaMax", -10.0).await,
+        Err(DeviceError::Parser(ParserError::OutOfRange { .. }))
+    ));
+    assert!(matches!(
+        device.write_point(103, "WH", 1.0).await,
+        Err(DeviceError::Parser(ParserError::NotWritable { .. }))
+    ));
+}
+
+#[tokio::test]
+async fn devices_without_a_marker_are_refused() {
+    let server = MockServer::start().expect("mock server");
+    server.set_registers(1, 40_000, &[0, 0]);
+    let mut device = device(&server).await;
+    assert!(matches!(
+        device.discover_models().await,
+        Err(DeviceError::NoMarker(candidates)) if candidates[0] == 40_000
+    ));
+}
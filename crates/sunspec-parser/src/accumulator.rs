@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Width of a SunSpec accumulator point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccumulatorKind {
+    Acc32,
+    Acc64,
+}
+
+impl AccumulatorKind {
+    /// Largest count before the accumulator wraps around to zero.
+    pub fn max(self) -> u64 {
+        match self {
+            AccumulatorKind::Acc32 => u64::from(u32::MAX),
+            AccumulatorKind::Acc64 => u64::MAX,
+        }
+    }
+}
+
+/// Raw count and scale factor of an `acc32` or `acc64` accumulator (energy
+/// totals of inverters, meters and DER models). A count of 0 means "not
+/// accumulated" and is never decoded into an accumulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Accumulator {
+    pub kind: AccumulatorKind,
+    pub count: u64,
+    pub scale: i16,
+}
+
+impl Accumulator {
+    /// The accumulated value in the point's units.
+    pub fn value(self) -> f64 {
+        self.count as f64 * 10f64.powi(i32::from(self.scale))
+    }
+
+    /// Increase since `previous` in the point's units. Accumulators wrap
+    /// around past their maximum, so a count in the lowest quarter of the
+    /// range after one in the highest quarter is a rollover. Any other
+    /// decrease is a counter reset, and a changed width or scale factor
+    /// makes the counts incomparable: both yield None.
+    pub fn delta_since(self, previous: Accumulator) -> Option<f64> {
+        if self.kind != previous.kind || self.scale != previous.scale {
+            return None;
+        }
+        let max = self.kind.max();
+        let quarter = max / 4;
+        let rolled_over = previous.count > max - quarter && self.count < quarter;
+        if self.count < previous.count && !rolled_over {
+            return None;
+        }
+        let delta = if rolled_over {
+            (max - previous.count) + self.count + 1
+        } else {
+            self.count - previous.count
+        };
+        Some(delta as f64 * 10f64.powi(i32::from(self.scale)))
+    }
+}
+
+/// What [`AccumulatorTracker::update`] makes of one reading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccumulatorReading {
+    /// Increase since the previous reading of the same key; 0 for the first
+    /// reading and after a reset.
+    pub interval: f64,
+    /// Value that never decreases: the first reading plus every increase
+    /// since, carried across rollovers and resets.
+    pub total: f64,
+    /// The counter went down without rolling over, or changed its scale.
+    pub reset: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tracked {
+    last: Accumulator,
+    total: f64,
+}
+
+/// Turns accumulator readings into interval and monotonic values, one
+/// counter per key (e.g. device and point).
+#[derive(Debug)]
+pub struct AccumulatorTracker<K> {
+    counters: HashMap<K, Tracked>,
+}
+
+impl<K> Default for AccumulatorTracker<K> {
+    fn default() -> Self {
+        Self {
+            counters: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> AccumulatorTracker<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, key: K, reading: Accumulator) -> AccumulatorReading {
+        let Some(tracked) = self.counters.get_mut(&key) else {
+            let total = reading.value();
+            self.counters.insert(
+                key,
+                Tracked {
+                    last: reading,
+                    total,
+                },
+            );
+            return AccumulatorReading {
+                interval: 0.0,
+                total,
+                reset: false,
+            };
+        };
+        let delta = reading.delta_since(tracked.last);
+        let interval = delta.unwrap_or_default();
+        tracked.last = reading;
+        tracked.total += interval;
+        AccumulatorReading {
+            interval,
+            total: tracked.total,
+            reset: delta.is_none(),
+        }
+    }
+
+    /// Forgets the counter of `key`; its next reading starts over.
+    pub fn remove(&mut self, key: &K) {
+        self.counters.remove(key);
+    }
+
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ModelDefinition, ModelSpec, ParserError};
+
+/// Layout version of the persisted catalog. Files of another version are
+/// rejected instead of guessed at.
+pub const CATALOG_FILE_VERSION: u32 = 1;
+
+/// What a persisted catalog holds: model definitions and point layouts,
+/// both sorted by model ID so equal catalogs produce equal files.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CatalogContents {
+    pub(crate) models: Vec<ModelDefinition>,
+    pub(crate) specs: Vec<ModelSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CatalogFile {
+    version: u32,
+    /// FNV-1a of `contents` as serialized by this layout version.
+    checksum: String,
+    contents: CatalogContents,
+}
+
+pub(crate) fn encode(contents: CatalogContents) -> Result<String, ParserError> {
+    let checksum = checksum(&serde_json::to_string(&contents)?);
+    let file = CatalogFile {
+        version: CATALOG_FILE_VERSION,
+        checksum,
+        contents,
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+/// Parses a persisted catalog, checking its version and checksum.
+pub(crate) fn decode(path: &str, data: &str) -> Result<CatalogContents, ParserError> {
+    #[derive(Deserialize)]
+    struct Header {
+        version: u32,
+    }
+    let header: Header = serde_json::from_str(data)?;
+    if header.version != CATALOG_FILE_VERSION {
+        return Err(ParserError::CatalogVersion {
+            path: path.to_string(),
+            version: header.version,
+        });
+    }
+    let file: CatalogFile = serde_json::from_str(data)?;
+    if checksum(&serde_json::to_string(&file.contents)?) != file.checksum {
+        return Err(ParserError::CatalogChecksum {
+            path: path.to_string(),
+        });
+    }
+    Ok(file.contents)
+}
+
+/// Stable across builds, unlike the std hasher used for the in-memory cache.
+fn checksum(data: &str) -> String {
+    let hash = data.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
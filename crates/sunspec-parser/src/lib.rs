@@ -1,16 +1,29 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
 use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use quick_xml::events::Event;
 use quick_xml::Reader;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::warn;
-use types::PointValue;
+use types::{PointFlag, PointValue};
 
-#[derive(Debug, Clone)]
+mod accumulator;
+mod catalog_file;
+mod model_json;
+
+pub use accumulator::{Accumulator, AccumulatorKind, AccumulatorReading, AccumulatorTracker};
+pub use catalog_file::CATALOG_FILE_VERSION;
+pub use model_json::{
+    parse_model_file, GroupCount, GroupSpec, ModelSpec, PointDef, ScaleFactorRef, SymbolDef,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ModelDefinition {
     pub id: u16,
     pub name: String,
@@ -20,6 +33,19 @@ pub struct ModelDefinition {
     pub length: u16,
 }
 
+impl ModelDefinition {
+    /// A model at `start` spanning `length` registers, its header included.
+    /// Models this crate knows get their name.
+    pub fn new(id: u16, start: u16, length: u16) -> Self {
+        Self {
+            id,
+            name: model_name(id),
+            start,
+            length,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ParserError {
     #[error("missing SunSpec sentinel at base address")]
@@ -34,45 +60,298 @@ pub enum ParserError {
     Xml(#[from] quick_xml::Error),
     #[error("invalid attribute value for {0}")]
     InvalidAttribute(String),
+    #[error("read {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("model catalog {path} has unsupported version {version}")]
+    CatalogVersion { path: String, version: u32 },
+    #[error("model catalog {path} does not match its checksum")]
+    CatalogChecksum { path: String },
+    #[error("model {model_id} has no point {name}")]
+    UnknownPoint { model_id: u16, name: String },
+    #[error("point {name} of model {model_id} cannot be written")]
+    NotWritable { model_id: u16, name: String },
+    #[error("{value} does not fit point {name}")]
+    OutOfRange { name: String, value: f64 },
 }
 
 const SUNSPEC_ID0: u16 = 0x5375;
 const SUNSPEC_ID1: u16 = 0x6e53;
 const SUNSPEC_END_ID: u16 = 0xFFFF;
+/// Base addresses the SunSpec specification allows for the `SunS` marker.
+pub const STANDARD_BASE_ADDRESSES: &[u16] = &[40_000, 50_000, 0];
+/// Parsed documents kept per kind (JSON, XML) before the oldest is evicted.
+pub const DEFAULT_DOCUMENT_LIMIT: usize = 64;
 
-#[derive(Default)]
+/// Model documents parsed so far, cached by content. Clones are cheap and
+/// share one cache, so every poller and decoder can hold the same catalog.
+/// The lock is only taken for lookups and inserts, never while parsing, which
+/// keeps the calls short enough for async tasks.
+#[derive(Debug, Clone, Default)]
 pub struct ModelCatalog {
-    json_cache: HashMap<u64, Vec<ModelDefinition>>,
-    xml_cache: HashMap<u64, Vec<ModelDefinition>>,
+    inner: Arc<RwLock<CatalogCache>>,
+}
+
+#[derive(Debug)]
+struct CatalogCache {
+    json: DocumentCache,
+    xml: DocumentCache,
+    /// Documents kept per kind; 0 keeps none.
+    document_limit: usize,
+    /// Definitions of every parsed document by model ID; later documents win.
+    /// Unlike the documents they are never evicted, only removed explicitly.
+    models: HashMap<u16, ModelDefinition>,
+    /// Full point layouts from official model files.
+    specs: HashMap<u16, Arc<ModelSpec>>,
+}
+
+impl Default for CatalogCache {
+    fn default() -> Self {
+        Self {
+            json: DocumentCache::default(),
+            xml: DocumentCache::default(),
+            document_limit: DEFAULT_DOCUMENT_LIMIT,
+            models: HashMap::new(),
+            specs: HashMap::new(),
+        }
+    }
+}
+
+/// Parsed documents by content fingerprint, evicted oldest first.
+#[derive(Debug, Default)]
+struct DocumentCache {
+    entries: HashMap<u64, Arc<[ModelDefinition]>>,
+    order: VecDeque<u64>,
+}
+
+impl DocumentCache {
+    fn get(&self, key: u64) -> Option<Arc<[ModelDefinition]>> {
+        self.entries.get(&key).cloned()
+    }
+
+    fn insert(&mut self, key: u64, models: Arc<[ModelDefinition]>, limit: usize) {
+        if self.entries.insert(key, models).is_none() {
+            self.order.push_back(key);
+        }
+        self.truncate(limit);
+    }
+
+    fn truncate(&mut self, limit: usize) {
+        while self.order.len() > limit {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DocumentKind {
+    Json,
+    Xml,
+}
+
+impl CatalogCache {
+    fn documents(&self, kind: DocumentKind) -> &DocumentCache {
+        match kind {
+            DocumentKind::Json => &self.json,
+            DocumentKind::Xml => &self.xml,
+        }
+    }
+
+    fn documents_mut(&mut self, kind: DocumentKind) -> &mut DocumentCache {
+        match kind {
+            DocumentKind::Json => &mut self.json,
+            DocumentKind::Xml => &mut self.xml,
+        }
+    }
 }
 
 impl ModelCatalog {
-    pub fn parse_json(&mut self, data: &str) -> Result<Vec<ModelDefinition>, ParserError> {
-        let key = fingerprint(data);
-        if let Some(models) = self.json_cache.get(&key) {
-            return Ok(models.clone());
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps at most `limit` parsed documents per kind (default
+    /// [`DEFAULT_DOCUMENT_LIMIT`]). Model definitions already taken from
+    /// evicted documents stay known.
+    pub fn with_document_limit(self, limit: usize) -> Self {
+        {
+            let mut cache = self.write();
+            cache.document_limit = limit;
+            cache.json.truncate(limit);
+            cache.xml.truncate(limit);
         }
-        let models = parse_models_from_json(data)?;
-        self.json_cache.insert(key, models.clone());
-        Ok(models)
+        self
     }
 
-    pub fn parse_xml(&mut self, data: &str) -> Result<Vec<ModelDefinition>, ParserError> {
-        let key = fingerprint(data);
-        if let Some(models) = self.xml_cache.get(&key) {
-            return Ok(models.clone());
+    pub fn parse_json(&self, data: &str) -> Result<Vec<ModelDefinition>, ParserError> {
+        self.parse_cached(data, DocumentKind::Json, parse_models_from_json)
+    }
+
+    pub fn parse_xml(&self, data: &str) -> Result<Vec<ModelDefinition>, ParserError> {
+        self.parse_cached(data, DocumentKind::Xml, parse_models_from_xml)
+    }
+
+    /// Definition of `model_id` from any document parsed so far.
+    pub fn model(&self, model_id: u16) -> Option<ModelDefinition> {
+        self.read().models.get(&model_id).cloned()
+    }
+
+    /// Point layout of `model_id`, if an official model file defined it.
+    pub fn spec(&self, model_id: u16) -> Option<Arc<ModelSpec>> {
+        self.read().specs.get(&model_id).cloned()
+    }
+
+    /// Loads every official model file (`*.json`) in `dir`, in file name
+    /// order, and returns how many were loaded. Files that do not parse are
+    /// logged and skipped so one bad file does not hide the others.
+    pub fn load_dir(&self, dir: &Path) -> Result<usize, ParserError> {
+        let io_error = |source| ParserError::Io {
+            path: dir.display().to_string(),
+            source,
+        };
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .map_err(io_error)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut loaded = 0;
+        for path in paths {
+            let spec = fs::read_to_string(&path)
+                .map_err(|source| ParserError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+                .and_then(|data| parse_model_file(&data));
+            match spec {
+                Ok(spec) => {
+                    self.insert_spec(spec);
+                    loaded += 1;
+                }
+                Err(err) => warn!(path = %path.display(), error = %err, "skipping model file"),
+            }
         }
-        let models = parse_models_from_xml(data)?;
-        self.xml_cache.insert(key, models.clone());
-        Ok(models)
+        Ok(loaded)
+    }
+
+    fn insert_spec(&self, spec: ModelSpec) {
+        let mut cache = self.write();
+        cache.models.insert(spec.id, spec.definition());
+        cache.specs.insert(spec.id, Arc::new(spec));
+    }
+
+    /// Forgets the definition and point layout of `model_id`, e.g. before
+    /// loading a corrected model file. Returns whether it was known.
+    pub fn remove(&self, model_id: u16) -> bool {
+        let mut cache = self.write();
+        let spec = cache.specs.remove(&model_id).is_some();
+        cache.models.remove(&model_id).is_some() || spec
+    }
+
+    /// Drops every cached document; the model definitions stay known.
+    pub fn clear_documents(&self) {
+        let mut cache = self.write();
+        cache.json.clear();
+        cache.xml.clear();
+    }
+
+    /// Writes every known model definition and point layout to `path`, with
+    /// the file version and a checksum, and returns how many models were
+    /// written. The file is replaced atomically.
+    pub fn save(&self, path: &Path) -> Result<usize, ParserError> {
+        let contents = {
+            let cache = self.read();
+            let mut models: Vec<_> = cache.models.values().cloned().collect();
+            models.sort_by_key(|model| model.id);
+            let mut specs: Vec<_> = cache.specs.values().map(|spec| (**spec).clone()).collect();
+            specs.sort_by_key(|spec| spec.id);
+            catalog_file::CatalogContents { models, specs }
+        };
+        let count = contents.models.len();
+        let data = catalog_file::encode(contents)?;
+        let io_error = |source| ParserError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, data).map_err(io_error)?;
+        fs::rename(&temp, path).map_err(io_error)?;
+        Ok(count)
+    }
+
+    /// Adds the models of a catalog written by [`ModelCatalog::save`] and
+    /// returns how many were loaded. A file of another version or with a
+    /// wrong checksum is rejected as a whole.
+    pub fn load(&self, path: &Path) -> Result<usize, ParserError> {
+        let display = path.display().to_string();
+        let data = fs::read_to_string(path).map_err(|source| ParserError::Io {
+            path: display.clone(),
+            source,
+        })?;
+        let contents = catalog_file::decode(&display, &data)?;
+        let count = contents.models.len();
+        let mut cache = self.write();
+        for model in contents.models {
+            cache.models.insert(model.id, model);
+        }
+        for spec in contents.specs {
+            cache.specs.insert(spec.id, Arc::new(spec));
+        }
+        Ok(count)
     }
 
     pub fn json_cache_len(&self) -> usize {
-        self.json_cache.len()
+        self.read().json.len()
     }
 
     pub fn xml_cache_len(&self) -> usize {
-        self.xml_cache.len()
+        self.read().xml.len()
+    }
+
+    fn parse_cached(
+        &self,
+        data: &str,
+        kind: DocumentKind,
+        parse: fn(&str) -> Result<Vec<ModelDefinition>, ParserError>,
+    ) -> Result<Vec<ModelDefinition>, ParserError> {
+        let key = fingerprint(data);
+        let cached = self.read().documents(kind).get(key);
+        if let Some(models) = cached {
+            return Ok(models.to_vec());
+        }
+        // Two callers racing on a new document both parse it; the results are equal.
+        let models = parse(data)?;
+        let mut cache = self.write();
+        for model in &models {
+            cache.models.insert(model.id, model.clone());
+        }
+        let limit = cache.document_limit;
+        cache
+            .documents_mut(kind)
+            .insert(key, models.as_slice().into(), limit);
+        Ok(models)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, CatalogCache> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, CatalogCache> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
@@ -89,7 +368,12 @@ struct JsonRoot {
     models: Vec<JsonModel>,
 }
 
+/// Parses a model list, or a single official model file (see [`ModelSpec`]).
 pub fn parse_models_from_json(data: &str) -> Result<Vec<ModelDefinition>, ParserError> {
+    let value: serde_json::Value = serde_json::from_str(data)?;
+    if model_json::is_model_file(&value) {
+        return Ok(vec![parse_model_file(data)?.definition()]);
+    }
     if let Ok(models) = serde_json::from_str::<Vec<JsonModel>>(data) {
         return Ok(models
             .into_iter()
@@ -124,7 +408,9 @@ pub fn parse_models_from_xml(data: &str) -> Result<Vec<ModelDefinition>, ParserE
 
     loop {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref event)) if event.name().as_ref() == b"model" => {
+            Ok(Event::Start(ref event)) | Ok(Event::Empty(ref event))
+                if event.name().as_ref() == b"model" =>
+            {
                 let mut id = None;
                 let mut name = None;
                 let mut length = None;
@@ -136,21 +422,18 @@ pub fn parse_models_from_xml(data: &str) -> Result<Vec<ModelDefinition>, ParserE
 
                     match key {
                         b"id" => {
-                            id = Some(
-                                value
-                                    .parse::<u16>()
-                                    .map_err(|_| ParserError::InvalidAttribute("id".to_string()))?,
-                            );
+                            id =
+                                Some(value.parse::<u16>().map_err(|_| {
+                                    ParserError::InvalidAttribute("id".to_string())
+                                })?);
                         }
                         b"name" => {
                             name = Some(value);
                         }
                         b"len" | b"length" => {
-                            length = Some(
-                                value
-                                    .parse::<u16>()
-                                    .map_err(|_| ParserError::InvalidAttribute("length".to_string()))?,
-                            );
+                            length = Some(value.parse::<u16>().map_err(|_| {
+                                ParserError::InvalidAttribute("length".to_string())
+                            })?);
                         }
                         _ => {}
                     }
@@ -179,6 +462,30 @@ pub fn parse_models_from_xml(data: &str) -> Result<Vec<ModelDefinition>, ParserE
     Ok(models)
 }
 
+/// Whether `registers` start with the `SunS` marker.
+pub fn has_sunspec_marker(registers: &[u16]) -> bool {
+    registers.starts_with(&[SUNSPEC_ID0, SUNSPEC_ID1])
+}
+
+/// Base addresses to probe, in order: `preferred`, the standard addresses,
+/// then each of those shifted by one for devices that count registers from 1
+/// (or from -1). Duplicates are left out.
+pub fn base_address_candidates(preferred: u16) -> Vec<u16> {
+    let mut candidates = vec![preferred];
+    candidates.extend_from_slice(STANDARD_BASE_ADDRESSES);
+    for &base in [preferred].iter().chain(STANDARD_BASE_ADDRESSES) {
+        candidates.extend(base.checked_add(1));
+        candidates.extend(base.checked_sub(1));
+    }
+    let mut seen = Vec::with_capacity(candidates.len());
+    candidates.retain(|base| {
+        let new = !seen.contains(base);
+        seen.push(*base);
+        new
+    });
+    candidates
+}
+
 pub fn parse_models_from_registers(
     base_address: u16,
     registers: &[u16],
@@ -290,28 +597,1571 @@ pub fn parse_models_from_registers_lenient(
 }
 
 /// SunSpec marks absent values with sentinel patterns (e.g., 0x8000 for i16). Returns None when the raw value is a sentinel.
+/// Strings, enums and bit fields are not scaled and also return None.
+/// Decoders take the factor from [`ScaleFactors`] rather than the caller.
 pub fn apply_scale(raw: PointValue, scale_factor: i16) -> Option<f64> {
     match raw {
+        PointValue::String(_) | PointValue::Enum16 { .. } | PointValue::Bitfield32 { .. } => None,
         PointValue::I16(v) if v == i16::MIN => None,
         PointValue::U16(v) if v == u16::MAX => None,
         PointValue::I32(v) if v == i32::MIN => None,
         PointValue::U32(v) if v == u32::MAX => None,
+        PointValue::U64(v) if v == u64::MAX => None,
         PointValue::F32(v) if v.is_nan() => None,
         PointValue::I16(v) => Some((v as f64) * 10f64.powi(scale_factor as i32)),
         PointValue::U16(v) => Some((v as f64) * 10f64.powi(scale_factor as i32)),
         PointValue::I32(v) => Some((v as f64) * 10f64.powi(scale_factor as i32)),
         PointValue::U32(v) => Some((v as f64) * 10f64.powi(scale_factor as i32)),
+        PointValue::U64(v) => Some((v as f64) * 10f64.powi(scale_factor as i32)),
         PointValue::F32(v) => Some((v as f64) * 10f64.powi(scale_factor as i32)),
     }
 }
 
+/// Operating state (`St`) reported by the inverter models.
+pub const INVERTER_STATE_FAULT: u16 = 7;
+/// Inverter state (`InvSt`) reported by the DER measurement model 701.
+pub const DER_STATE_FAULT: u16 = 6;
+/// Battery state (`State`) reported by the battery model 802.
+pub const BATTERY_STATE_FAULT: u16 = 99;
+
+// Offsets into an integer inverter block (models 101-103), counted from the
+// model ID register.
+const INV_W: usize = 14;
+const INV_WH: usize = 24;
+const INV_ST: usize = 38;
+// The same points in a float inverter block (models 111-113).
+const FLOAT_INV_W: usize = 22;
+const FLOAT_INV_WH: usize = 32;
+const FLOAT_INV_ST: usize = 48;
+/// Total real power and its scale factor in the integer meter models 201-204.
+const METER_W: usize = 18;
+const METER_W_SF: usize = 22;
+/// Total real power in the float meter models 211-214.
+const FLOAT_METER_W: usize = 28;
+
+/// SunSpec only defines scale factors from -10 to 10; anything else,
+/// including the 0x8000 sentinel, means the factor is not implemented.
+const MAX_SCALE_FACTOR: i16 = 10;
+
+/// A `sunssf` point: the power of ten the points referencing it by name are
+/// scaled with.
+struct ScaleFactorSpec {
+    name: &'static str,
+    offset: usize,
+}
+
+const fn sunssf(name: &'static str, offset: usize) -> ScaleFactorSpec {
+    ScaleFactorSpec { name, offset }
+}
+
+/// Scale factor points of the integer inverter models 101-103.
+const INVERTER_SCALE_FACTORS: &[ScaleFactorSpec] = &[
+    sunssf("A_SF", 6),
+    sunssf("V_SF", 13),
+    sunssf("W_SF", 15),
+    sunssf("Hz_SF", 17),
+    sunssf("VA_SF", 19),
+    sunssf("VAr_SF", 21),
+    sunssf("PF_SF", 23),
+    sunssf("WH_SF", 26),
+    sunssf("DCA_SF", 28),
+    sunssf("DCV_SF", 30),
+    sunssf("DCW_SF", 32),
+    sunssf("Tmp_SF", 37),
+];
+
+/// The scale factors of one model block, resolved once so every point is
+/// scaled with the factor the device reported next to it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScaleFactors {
+    factors: Vec<ScaleFactor>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ScaleFactor {
+    name: &'static str,
+    offset: usize,
+    /// None when the device does not implement the factor.
+    value: Option<i16>,
+}
+
+impl ScaleFactors {
+    fn resolve(specs: &[ScaleFactorSpec], registers: &[u16]) -> Self {
+        let factors = specs
+            .iter()
+            .map(|spec| ScaleFactor {
+                name: spec.name,
+                offset: spec.offset,
+                value: registers
+                    .get(spec.offset)
+                    .map(|&raw| raw as i16)
+                    .filter(|value| (-MAX_SCALE_FACTOR..=MAX_SCALE_FACTOR).contains(value)),
+            })
+            .collect();
+        Self { factors }
+    }
+
+    /// Value of the scale factor point `name` (e.g. `W_SF`); None when the
+    /// model has no such point or the device does not implement it.
+    pub fn get(&self, name: &str) -> Option<i16> {
+        self.find(name)?.value
+    }
+
+    /// `raw` scaled by the factor `name`. None when either the value or the
+    /// factor is a sentinel, so a missing factor never yields a wrong value.
+    pub fn apply(&self, raw: PointValue, name: &str) -> Option<f64> {
+        apply_scale(raw, self.get(name)?)
+    }
+
+    fn find(&self, name: &str) -> Option<&ScaleFactor> {
+        self.factors.iter().find(|factor| factor.name == name)
+    }
+}
+
+fn scale_factor_specs(model_id: u16, registers: &[u16]) -> Option<&'static [ScaleFactorSpec]> {
+    scaled_model(model_id, registers).map(|model| model.scale_factors)
+}
+
+/// Resolves every scale factor point of a model block (registers starting at
+/// the model ID). Returns None for models without a point table.
+pub fn resolve_scale_factors(model_id: u16, registers: &[u16]) -> Option<ScaleFactors> {
+    scale_factor_specs(model_id, registers).map(|specs| ScaleFactors::resolve(specs, registers))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PointKind {
+    U16,
+    I16,
+    U32,
+    Acc32,
+    /// Energy totals of the 7xx models.
+    Acc64,
+    /// Points of the float models (211-214); not scaled.
+    F32,
+}
+
+impl PointKind {
+    fn type_name(self) -> &'static str {
+        match self {
+            PointKind::U16 => "uint16",
+            PointKind::I16 => "int16",
+            PointKind::U32 => "uint32",
+            PointKind::Acc32 => "acc32",
+            PointKind::Acc64 => "acc64",
+            PointKind::F32 => "float32",
+        }
+    }
+
+    fn size(self) -> u16 {
+        match self {
+            PointKind::U16 | PointKind::I16 => 1,
+            PointKind::U32 | PointKind::Acc32 | PointKind::F32 => 2,
+            PointKind::Acc64 => 4,
+        }
+    }
+}
+
+struct PointSpec {
+    name: &'static str,
+    offset: usize,
+    kind: PointKind,
+    /// Name of the `sunssf` point the value is scaled with; empty for
+    /// float points.
+    scale_factor: &'static str,
+    units: &'static str,
+}
+
+const fn point(
+    name: &'static str,
+    offset: usize,
+    kind: PointKind,
+    scale_factor: &'static str,
+    units: &'static str,
+) -> PointSpec {
+    PointSpec {
+        name,
+        offset,
+        kind,
+        scale_factor,
+        units,
+    }
+}
+
+/// Scaled measurement points of the integer inverter models 101-103.
+const INVERTER_POINTS: &[PointSpec] = &[
+    point("A", 2, PointKind::U16, "A_SF", "A"),
+    point("AphA", 3, PointKind::U16, "A_SF", "A"),
+    point("AphB", 4, PointKind::U16, "A_SF", "A"),
+    point("AphC", 5, PointKind::U16, "A_SF", "A"),
+    point("PhVphA", 10, PointKind::U16, "V_SF", "V"),
+    point("PhVphB", 11, PointKind::U16, "V_SF", "V"),
+    point("PhVphC", 12, PointKind::U16, "V_SF", "V"),
+    point("W", INV_W, PointKind::I16, "W_SF", "W"),
+    point("Hz", 16, PointKind::U16, "Hz_SF", "Hz"),
+    point("VA", 18, PointKind::I16, "VA_SF", "VA"),
+    point("VAr", 20, PointKind::I16, "VAr_SF", "var"),
+    point("PF", 22, PointKind::I16, "PF_SF", "Pct"),
+    point("WH", INV_WH, PointKind::Acc32, "WH_SF", "Wh"),
+    point("DCA", 27, PointKind::U16, "DCA_SF", "A"),
+    point("DCV", 29, PointKind::U16, "DCV_SF", "V"),
+    point("DCW", 31, PointKind::I16, "DCW_SF", "W"),
+    point("TmpCab", 33, PointKind::I16, "Tmp_SF", "C"),
+    point("TmpSnk", 34, PointKind::I16, "Tmp_SF", "C"),
+];
+
+/// Scaled points of one model and the scale factors they reference.
+struct ScaledModel {
+    /// Registers a block needs, counted from the model ID, to be decoded.
+    min_len: usize,
+    scale_factors: &'static [ScaleFactorSpec],
+    points: &'static [PointSpec],
+}
+
+const INVERTER_MODEL: ScaledModel = ScaledModel {
+    min_len: INV_ST + 1,
+    scale_factors: INVERTER_SCALE_FACTORS,
+    points: INVERTER_POINTS,
+};
+
+/// Models 111-113 (float inverters): the points of [`INVERTER_MODEL`] as
+/// float32 values, which need no scale factors.
+const FLOAT_INVERTER_MODEL: ScaledModel = ScaledModel {
+    min_len: FLOAT_INV_ST + 1,
+    scale_factors: &[],
+    points: &[
+        point("A", 2, PointKind::F32, "", "A"),
+        point("AphA", 4, PointKind::F32, "", "A"),
+        point("AphB", 6, PointKind::F32, "", "A"),
+        point("AphC", 8, PointKind::F32, "", "A"),
+        point("PhVphA", 16, PointKind::F32, "", "V"),
+        point("PhVphB", 18, PointKind::F32, "", "V"),
+        point("PhVphC", 20, PointKind::F32, "", "V"),
+        point("W", FLOAT_INV_W, PointKind::F32, "", "W"),
+        point("Hz", 24, PointKind::F32, "", "Hz"),
+        point("VA", 26, PointKind::F32, "", "VA"),
+        point("VAr", 28, PointKind::F32, "", "var"),
+        point("PF", 30, PointKind::F32, "", "Pct"),
+        point("WH", FLOAT_INV_WH, PointKind::F32, "", "Wh"),
+        point("DCA", 34, PointKind::F32, "", "A"),
+        point("DCV", 36, PointKind::F32, "", "V"),
+        point("DCW", 38, PointKind::F32, "", "W"),
+        point("TmpCab", 40, PointKind::F32, "", "C"),
+        point("TmpSnk", 42, PointKind::F32, "", "C"),
+    ],
+};
+
+/// Model 701 (DER AC measurement): totals and per-phase values.
+const DER_MEASUREMENT_MODEL: ScaledModel = ScaledModel {
+    min_len: 123,
+    scale_factors: &[
+        sunssf("A_SF", 113),
+        sunssf("V_SF", 114),
+        sunssf("Hz_SF", 115),
+        sunssf("W_SF", 116),
+        sunssf("PF_SF", 117),
+        sunssf("VA_SF", 118),
+        sunssf("Var_SF", 119),
+        sunssf("TotWh_SF", 120),
+        sunssf("TotVarh_SF", 121),
+        sunssf("Tmp_SF", 122),
+    ],
+    points: &[
+        point("W", 10, PointKind::I16, "W_SF", "W"),
+        point("VA", 11, PointKind::I16, "VA_SF", "VA"),
+        point("Var", 12, PointKind::I16, "Var_SF", "var"),
+        point("PF", 13, PointKind::I16, "PF_SF", ""),
+        point("A", 14, PointKind::I16, "A_SF", "A"),
+        point("LLV", 15, PointKind::U16, "V_SF", "V"),
+        point("LNV", 16, PointKind::U16, "V_SF", "V"),
+        point("Hz", 17, PointKind::U32, "Hz_SF", "Hz"),
+        point("TotWhInj", 19, PointKind::Acc64, "TotWh_SF", "Wh"),
+        point("TotWhAbs", 23, PointKind::Acc64, "TotWh_SF", "Wh"),
+        point("TotVarhInj", 27, PointKind::Acc64, "TotVarh_SF", "varh"),
+        point("TotVarhAbs", 31, PointKind::Acc64, "TotVarh_SF", "varh"),
+        point("TmpAmb", 35, PointKind::I16, "Tmp_SF", "C"),
+        point("TmpCab", 36, PointKind::I16, "Tmp_SF", "C"),
+        point("TmpSnk", 37, PointKind::I16, "Tmp_SF", "C"),
+        point("TmpTrns", 38, PointKind::I16, "Tmp_SF", "C"),
+        point("TmpSw", 39, PointKind::I16, "Tmp_SF", "C"),
+        point("TmpOt", 40, PointKind::I16, "Tmp_SF", "C"),
+        point("WL1", 41, PointKind::I16, "W_SF", "W"),
+        point("VAL1", 42, PointKind::I16, "VA_SF", "VA"),
+        point("VarL1", 43, PointKind::I16, "Var_SF", "var"),
+        point("PFL1", 44, PointKind::I16, "PF_SF", ""),
+        point("AL1", 45, PointKind::I16, "A_SF", "A"),
+        point("VL1L2", 46, PointKind::U16, "V_SF", "V"),
+        point("VL1", 47, PointKind::U16, "V_SF", "V"),
+        point("WL2", 64, PointKind::I16, "W_SF", "W"),
+        point("VAL2", 65, PointKind::I16, "VA_SF", "VA"),
+        point("VarL2", 66, PointKind::I16, "Var_SF", "var"),
+        point("PFL2", 67, PointKind::I16, "PF_SF", ""),
+        point("AL2", 68, PointKind::I16, "A_SF", "A"),
+        point("VL2L3", 69, PointKind::U16, "V_SF", "V"),
+        point("VL2", 70, PointKind::U16, "V_SF", "V"),
+        point("WL3", 87, PointKind::I16, "W_SF", "W"),
+        point("VAL3", 88, PointKind::I16, "VA_SF", "VA"),
+        point("VarL3", 89, PointKind::I16, "Var_SF", "var"),
+        point("PFL3", 90, PointKind::I16, "PF_SF", ""),
+        point("AL3", 91, PointKind::I16, "A_SF", "A"),
+        point("VL3L1", 92, PointKind::U16, "V_SF", "V"),
+        point("VL3", 93, PointKind::U16, "V_SF", "V"),
+    ],
+};
+
+/// Model 702 (DER capacity): nameplate ratings and configured limits.
+const DER_CAPACITY_MODEL: ScaledModel = ScaledModel {
+    min_len: 51,
+    scale_factors: &[
+        sunssf("W_SF", 44),
+        sunssf("PF_SF", 45),
+        sunssf("VA_SF", 46),
+        sunssf("Var_SF", 47),
+        sunssf("V_SF", 48),
+        sunssf("A_SF", 49),
+        sunssf("S_SF", 50),
+    ],
+    points: &[
+        point("WMaxRtg", 2, PointKind::U16, "W_SF", "W"),
+        point("WOvrExtRtg", 3, PointKind::U16, "W_SF", "W"),
+        point("WOvrExtRtgPF", 4, PointKind::U16, "PF_SF", ""),
+        point("WUndExtRtg", 5, PointKind::U16, "W_SF", "W"),
+        point("WUndExtRtgPF", 6, PointKind::U16, "PF_SF", ""),
+        point("VAMaxRtg", 7, PointKind::U16, "VA_SF", "VA"),
+        point("VarMaxInjRtg", 8, PointKind::U16, "Var_SF", "var"),
+        point("VarMaxAbsRtg", 9, PointKind::U16, "Var_SF", "var"),
+        point("WChaRteMaxRtg", 10, PointKind::U16, "W_SF", "W"),
+        point("WDisChaRteMaxRtg", 11, PointKind::U16, "W_SF", "W"),
+        point("VAChaRteMaxRtg", 12, PointKind::U16, "VA_SF", "VA"),
+        point("VADisChaRteMaxRtg", 13, PointKind::U16, "VA_SF", "VA"),
+        point("VNomRtg", 14, PointKind::U16, "V_SF", "V"),
+        point("VMaxRtg", 15, PointKind::U16, "V_SF", "V"),
+        point("VMinRtg", 16, PointKind::U16, "V_SF", "V"),
+        point("AMaxRtg", 17, PointKind::U16, "A_SF", "A"),
+        point("PFOvrExtRtg", 18, PointKind::U16, "PF_SF", ""),
+        point("PFUndExtRtg", 19, PointKind::U16, "PF_SF", ""),
+        point("ReactSusceptRtg", 20, PointKind::U16, "S_SF", "S"),
+        point("WMax", 26, PointKind::U16, "W_SF", "W"),
+        point("WMaxOvrExt", 27, PointKind::U16, "W_SF", "W"),
+        point("WOvrExtPF", 28, PointKind::U16, "PF_SF", ""),
+        point("WMaxUndExt", 29, PointKind::U16, "W_SF", "W"),
+        point("WUndExtPF", 30, PointKind::U16, "PF_SF", ""),
+        point("VAMax", 31, PointKind::U16, "VA_SF", "VA"),
+        point("AMax", 32, PointKind::U16, "A_SF", "A"),
+        point("VNom", 33, PointKind::U16, "V_SF", "V"),
+        point("VRefOfs", 34, PointKind::I16, "V_SF", "V"),
+        point("VMax", 35, PointKind::U16, "V_SF", "V"),
+        point("VMin", 36, PointKind::U16, "V_SF", "V"),
+        point("VarMaxInj", 37, PointKind::U16, "Var_SF", "var"),
+        point("VarMaxAbs", 38, PointKind::U16, "Var_SF", "var"),
+        point("WChaRteMax", 39, PointKind::U16, "W_SF", "W"),
+        point("WDisChaRteMax", 40, PointKind::U16, "W_SF", "W"),
+        point("VAChaRteMax", 41, PointKind::U16, "VA_SF", "VA"),
+        point("VADisChaRteMax", 42, PointKind::U16, "VA_SF", "VA"),
+    ],
+};
+
+/// Model 713 (DER storage capacity).
+const DER_STORAGE_MODEL: ScaledModel = ScaledModel {
+    min_len: 9,
+    scale_factors: &[sunssf("WH_SF", 7), sunssf("Pct_SF", 8)],
+    points: &[
+        point("WHRtg", 2, PointKind::U16, "WH_SF", "Wh"),
+        point("WHAvail", 3, PointKind::U16, "WH_SF", "Wh"),
+        point("SoC", 4, PointKind::U16, "Pct_SF", "Pct"),
+        point("SoH", 5, PointKind::U16, "Pct_SF", "Pct"),
+    ],
+};
+
+/// Model 714 (DER DC measurement): totals over all DC ports. The repeating
+/// per-port groups are not decoded.
+const DER_DC_MODEL: ScaledModel = ScaledModel {
+    min_len: 20,
+    scale_factors: &[
+        sunssf("DCA_SF", 15),
+        sunssf("DCV_SF", 16),
+        sunssf("DCW_SF", 17),
+        sunssf("DCWH_SF", 18),
+        sunssf("Tmp_SF", 19),
+    ],
+    points: &[
+        point("DCA", 5, PointKind::I16, "DCA_SF", "A"),
+        point("DCW", 6, PointKind::I16, "DCW_SF", "W"),
+        point("DCWhInj", 7, PointKind::Acc64, "DCWH_SF", "Wh"),
+        point("DCWhAbs", 11, PointKind::Acc64, "DCWH_SF", "Wh"),
+    ],
+};
+
+/// Model 124 (basic storage controls): charge limits, state of charge
+/// (`ChaState`) and charge/discharge rates.
+const BASIC_STORAGE_MODEL: ScaledModel = ScaledModel {
+    min_len: 26,
+    scale_factors: &[
+        sunssf("WChaMax_SF", 18),
+        sunssf("WChaDisChaGra_SF", 19),
+        sunssf("VAChaMax_SF", 20),
+        sunssf("MinRsvPct_SF", 21),
+        sunssf("ChaState_SF", 22),
+        sunssf("StorAval_SF", 23),
+        sunssf("InBatV_SF", 24),
+        sunssf("InOutWRte_SF", 25),
+    ],
+    points: &[
+        point("WChaMax", 2, PointKind::U16, "WChaMax_SF", "W"),
+        point("WChaGra", 3, PointKind::U16, "WChaDisChaGra_SF", "Pct"),
+        point("WDisChaGra", 4, PointKind::U16, "WChaDisChaGra_SF", "Pct"),
+        point("VAChaMax", 6, PointKind::U16, "VAChaMax_SF", "VA"),
+        point("MinRsvPct", 7, PointKind::U16, "MinRsvPct_SF", "Pct"),
+        point("ChaState", 8, PointKind::U16, "ChaState_SF", "Pct"),
+        point("StorAval", 9, PointKind::U16, "StorAval_SF", "Ah"),
+        point("InBatV", 10, PointKind::U16, "InBatV_SF", "V"),
+        point("OutWRte", 12, PointKind::I16, "InOutWRte_SF", "Pct"),
+        point("InWRte", 13, PointKind::I16, "InOutWRte_SF", "Pct"),
+    ],
+};
+
+/// Model 802 (battery base): ratings, state of charge and health, limits,
+/// and the cell voltage extremes. The string/module locators of the cell
+/// extremes and the cycle count are unscaled and not decoded.
+const BATTERY_MODEL: ScaledModel = ScaledModel {
+    min_len: 64,
+    scale_factors: &[
+        sunssf("AHRtg_SF", 52),
+        sunssf("WHRtg_SF", 53),
+        sunssf("WChaDisChaMax_SF", 54),
+        sunssf("DisChaRte_SF", 55),
+        sunssf("SoC_SF", 56),
+        sunssf("DoD_SF", 57),
+        sunssf("SoH_SF", 58),
+        sunssf("V_SF", 59),
+        sunssf("CellV_SF", 60),
+        sunssf("A_SF", 61),
+        sunssf("AMax_SF", 62),
+        sunssf("W_SF", 63),
+    ],
+    points: &[
+        point("AHRtg", 2, PointKind::U16, "AHRtg_SF", "Ah"),
+        point("WHRtg", 3, PointKind::U16, "WHRtg_SF", "Wh"),
+        point("WChaRteMax", 4, PointKind::U16, "WChaDisChaMax_SF", "W"),
+        point("WDisChaRteMax", 5, PointKind::U16, "WChaDisChaMax_SF", "W"),
+        point("DisChaRte", 6, PointKind::U16, "DisChaRte_SF", "Pct"),
+        point("SoCMax", 7, PointKind::U16, "SoC_SF", "Pct"),
+        point("SoCMin", 8, PointKind::U16, "SoC_SF", "Pct"),
+        point("SocRsvMax", 9, PointKind::U16, "SoC_SF", "Pct"),
+        point("SoCRsvMin", 10, PointKind::U16, "SoC_SF", "Pct"),
+        point("SoC", 11, PointKind::U16, "SoC_SF", "Pct"),
+        point("DoD", 12, PointKind::U16, "DoD_SF", "Pct"),
+        point("SoH", 13, PointKind::U16, "SoH_SF", "Pct"),
+        point("V", 34, PointKind::U16, "V_SF", "V"),
+        point("VMax", 35, PointKind::U16, "V_SF", "V"),
+        point("VMin", 36, PointKind::U16, "V_SF", "V"),
+        point("CellVMax", 37, PointKind::U16, "CellV_SF", "V"),
+        point("CellVMin", 40, PointKind::U16, "CellV_SF", "V"),
+        point("CellVAvg", 43, PointKind::U16, "CellV_SF", "V"),
+        point("A", 44, PointKind::I16, "A_SF", "A"),
+        point("AChaMax", 45, PointKind::U16, "AMax_SF", "A"),
+        point("ADisChaMax", 46, PointKind::U16, "AMax_SF", "A"),
+        point("W", 47, PointKind::I16, "W_SF", "W"),
+    ],
+};
+
+/// Model 803 (lithium-ion battery bank): module temperature, string voltage
+/// and string current extremes over the bank. The repeating per-string
+/// groups are not decoded.
+const LITHIUM_ION_BANK_MODEL: ScaledModel = ScaledModel {
+    min_len: 28,
+    scale_factors: &[
+        sunssf("CellV_SF", 22),
+        sunssf("ModTmp_SF", 23),
+        sunssf("A_SF", 24),
+        sunssf("SoH_SF", 25),
+        sunssf("SoC_SF", 26),
+        sunssf("V_SF", 27),
+    ],
+    points: &[
+        point("ModTmpMax", 4, PointKind::I16, "ModTmp_SF", "C"),
+        point("ModTmpMin", 7, PointKind::I16, "ModTmp_SF", "C"),
+        point("ModTmpAvg", 10, PointKind::I16, "ModTmp_SF", "C"),
+        point("StrVMax", 11, PointKind::U16, "V_SF", "V"),
+        point("StrVMin", 13, PointKind::U16, "V_SF", "V"),
+        point("StrVAvg", 15, PointKind::U16, "V_SF", "V"),
+        point("StrAMax", 16, PointKind::I16, "A_SF", "A"),
+        point("StrAMin", 18, PointKind::I16, "A_SF", "A"),
+        point("StrAAvg", 20, PointKind::I16, "A_SF", "A"),
+    ],
+};
+
+/// Models 201-204 (integer AC meters): totals and per-phase current,
+/// voltage, power, power factor and energy. Single- and split-phase meters
+/// leave the unused phases not implemented.
+const METER_MODEL: ScaledModel = ScaledModel {
+    min_len: 105,
+    scale_factors: &[
+        sunssf("A_SF", 6),
+        sunssf("V_SF", 15),
+        sunssf("Hz_SF", 17),
+        sunssf("W_SF", METER_W_SF),
+        sunssf("VA_SF", 27),
+        sunssf("VAR_SF", 32),
+        sunssf("PF_SF", 37),
+        sunssf("TotWh_SF", 54),
+        sunssf("TotVAh_SF", 71),
+        sunssf("TotVArh_SF", 104),
+    ],
+    points: &[
+        point("A", 2, PointKind::I16, "A_SF", "A"),
+        point("AphA", 3, PointKind::I16, "A_SF", "A"),
+        point("AphB", 4, PointKind::I16, "A_SF", "A"),
+        point("AphC", 5, PointKind::I16, "A_SF", "A"),
+        point("PhV", 7, PointKind::I16, "V_SF", "V"),
+        point("PhVphA", 8, PointKind::I16, "V_SF", "V"),
+        point("PhVphB", 9, PointKind::I16, "V_SF", "V"),
+        point("PhVphC", 10, PointKind::I16, "V_SF", "V"),
+        point("PPV", 11, PointKind::I16, "V_SF", "V"),
+        point("PPVphAB", 12, PointKind::I16, "V_SF", "V"),
+        point("PPVphBC", 13, PointKind::I16, "V_SF", "V"),
+        point("PPVphCA", 14, PointKind::I16, "V_SF", "V"),
+        point("Hz", 16, PointKind::I16, "Hz_SF", "Hz"),
+        point("W", METER_W, PointKind::I16, "W_SF", "W"),
+        point("WphA", 19, PointKind::I16, "W_SF", "W"),
+        point("WphB", 20, PointKind::I16, "W_SF", "W"),
+        point("WphC", 21, PointKind::I16, "W_SF", "W"),
+        point("VA", 23, PointKind::I16, "VA_SF", "VA"),
+        point("VAphA", 24, PointKind::I16, "VA_SF", "VA"),
+        point("VAphB", 25, PointKind::I16, "VA_SF", "VA"),
+        point("VAphC", 26, PointKind::I16, "VA_SF", "VA"),
+        point("VAR", 28, PointKind::I16, "VAR_SF", "var"),
+        point("VARphA", 29, PointKind::I16, "VAR_SF", "var"),
+        point("VARphB", 30, PointKind::I16, "VAR_SF", "var"),
+        point("VARphC", 31, PointKind::I16, "VAR_SF", "var"),
+        point("PF", 33, PointKind::I16, "PF_SF", "Pct"),
+        point("PFphA", 34, PointKind::I16, "PF_SF", "Pct"),
+        point("PFphB", 35, PointKind::I16, "PF_SF", "Pct"),
+        point("PFphC", 36, PointKind::I16, "PF_SF", "Pct"),
+        point("TotWhExp", 38, PointKind::Acc32, "TotWh_SF", "Wh"),
+        point("TotWhExpPhA", 40, PointKind::Acc32, "TotWh_SF", "Wh"),
+        point("TotWhExpPhB", 42, PointKind::Acc32, "TotWh_SF", "Wh"),
+        point("TotWhExpPhC", 44, PointKind::Acc32, "TotWh_SF", "Wh"),
+        point("TotWhImp", 46, PointKind::Acc32, "TotWh_SF", "Wh"),
+        point("TotWhImpPhA", 48, PointKind::Acc32, "TotWh_SF", "Wh"),
+        point("TotWhImpPhB", 50, PointKind::Acc32, "TotWh_SF", "Wh"),
+        point("TotWhImpPhC", 52, PointKind::Acc32, "TotWh_SF", "Wh"),
+        point("TotVAhExp", 55, PointKind::Acc32, "TotVAh_SF", "VAh"),
+        point("TotVAhImp", 63, PointKind::Acc32, "TotVAh_SF", "VAh"),
+        point("TotVArhImpQ1", 72, PointKind::Acc32, "TotVArh_SF", "varh"),
+        point("TotVArhImpQ2", 80, PointKind::Acc32, "TotVArh_SF", "varh"),
+        point("TotVArhExpQ3", 88, PointKind::Acc32, "TotVArh_SF", "varh"),
+        point("TotVArhExpQ4", 96, PointKind::Acc32, "TotVArh_SF", "varh"),
+    ],
+};
+
+/// Models 211-214 (float AC meters): the points of [`METER_MODEL`] as
+/// float32 values, which need no scale factors.
+const FLOAT_METER_MODEL: ScaledModel = ScaledModel {
+    min_len: 118,
+    scale_factors: &[],
+    points: &[
+        point("A", 2, PointKind::F32, "", "A"),
+        point("AphA", 4, PointKind::F32, "", "A"),
+        point("AphB", 6, PointKind::F32, "", "A"),
+        point("AphC", 8, PointKind::F32, "", "A"),
+        point("PhV", 10, PointKind::F32, "", "V"),
+        point("PhVphA", 12, PointKind::F32, "", "V"),
+        point("PhVphB", 14, PointKind::F32, "", "V"),
+        point("PhVphC", 16, PointKind::F32, "", "V"),
+        point("PPV", 18, PointKind::F32, "", "V"),
+        point("PPVphAB", 20, PointKind::F32, "", "V"),
+        point("PPVphBC", 22, PointKind::F32, "", "V"),
+        point("PPVphCA", 24, PointKind::F32, "", "V"),
+        point("Hz", 26, PointKind::F32, "", "Hz"),
+        point("W", FLOAT_METER_W, PointKind::F32, "", "W"),
+        point("WphA", 30, PointKind::F32, "", "W"),
+        point("WphB", 32, PointKind::F32, "", "W"),
+        point("WphC", 34, PointKind::F32, "", "W"),
+        point("VA", 36, PointKind::F32, "", "VA"),
+        point("VAphA", 38, PointKind::F32, "", "VA"),
+        point("VAphB", 40, PointKind::F32, "", "VA"),
+        point("VAphC", 42, PointKind::F32, "", "VA"),
+        point("VAR", 44, PointKind::F32, "", "var"),
+        point("VARphA", 46, PointKind::F32, "", "var"),
+        point("VARphB", 48, PointKind::F32, "", "var"),
+        point("VARphC", 50, PointKind::F32, "", "var"),
+        point("PF", 52, PointKind::F32, "", "Pct"),
+        point("PFphA", 54, PointKind::F32, "", "Pct"),
+        point("PFphB", 56, PointKind::F32, "", "Pct"),
+        point("PFphC", 58, PointKind::F32, "", "Pct"),
+        point("TotWhExp", 60, PointKind::F32, "", "Wh"),
+        point("TotWhExpPhA", 62, PointKind::F32, "", "Wh"),
+        point("TotWhExpPhB", 64, PointKind::F32, "", "Wh"),
+        point("TotWhExpPhC", 66, PointKind::F32, "", "Wh"),
+        point("TotWhImp", 68, PointKind::F32, "", "Wh"),
+        point("TotWhImpPhA", 70, PointKind::F32, "", "Wh"),
+        point("TotWhImpPhB", 72, PointKind::F32, "", "Wh"),
+        point("TotWhImpPhC", 74, PointKind::F32, "", "Wh"),
+        point("TotVAhExp", 76, PointKind::F32, "", "VAh"),
+        point("TotVAhImp", 84, PointKind::F32, "", "VAh"),
+        point("TotVArhImpQ1", 92, PointKind::F32, "", "varh"),
+        point("TotVArhImpQ2", 100, PointKind::F32, "", "varh"),
+        point("TotVArhExpQ3", 108, PointKind::F32, "", "varh"),
+        point("TotVArhExpQ4", 116, PointKind::F32, "", "varh"),
+    ],
+};
+
+/// Scaled point table of a model block, if this crate has one and the block
+/// is long enough to hold it.
+fn scaled_model(model_id: u16, registers: &[u16]) -> Option<&'static ScaledModel> {
+    scaled_table(model_id).filter(|model| registers.len() >= model.min_len)
+}
+
+fn scaled_table(model_id: u16) -> Option<&'static ScaledModel> {
+    Some(match model_id {
+        101..=103 => &INVERTER_MODEL,
+        111..=113 => &FLOAT_INVERTER_MODEL,
+        201..=204 => &METER_MODEL,
+        211..=214 => &FLOAT_METER_MODEL,
+        DER_MEASUREMENT => &DER_MEASUREMENT_MODEL,
+        702 => &DER_CAPACITY_MODEL,
+        DER_STORAGE => &DER_STORAGE_MODEL,
+        714 => &DER_DC_MODEL,
+        BASIC_STORAGE => &BASIC_STORAGE_MODEL,
+        BATTERY => &BATTERY_MODEL,
+        803 => &LITHIUM_ION_BANK_MODEL,
+        _ => return None,
+    })
+}
+
+/// Unscaled points: strings, enums with their symbols and named bit fields.
+#[derive(Debug, Clone, Copy)]
+enum TypedKind {
+    /// Size in registers.
+    String(u16),
+    Enum16(&'static [(u16, &'static str)]),
+    /// Bit number and flag name.
+    Bitfield32(&'static [(u32, &'static str)]),
+}
+
+impl TypedKind {
+    fn type_name(self) -> &'static str {
+        match self {
+            TypedKind::String(_) => "string",
+            TypedKind::Enum16(_) => "enum16",
+            TypedKind::Bitfield32(_) => "bitfield32",
+        }
+    }
+
+    fn size(self) -> u16 {
+        match self {
+            TypedKind::String(size) => size,
+            TypedKind::Enum16(_) => 1,
+            TypedKind::Bitfield32(_) => 2,
+        }
+    }
+}
+
+struct TypedPointSpec {
+    name: &'static str,
+    offset: usize,
+    kind: TypedKind,
+}
+
+const fn typed(name: &'static str, offset: usize, kind: TypedKind) -> TypedPointSpec {
+    TypedPointSpec { name, offset, kind }
+}
+
+/// String points of the common model.
+const COMMON_POINTS: &[TypedPointSpec] = &[
+    typed("Mn", 2, TypedKind::String(16)),
+    typed("Md", 18, TypedKind::String(16)),
+    typed("Opt", 34, TypedKind::String(8)),
+    typed("Vr", 42, TypedKind::String(8)),
+    typed("SN", 50, TypedKind::String(16)),
+];
+
+/// Symbols of the inverter operating state (`St`).
+const INVERTER_STATES: &[(u16, &str)] = &[
+    (1, "OFF"),
+    (2, "SLEEPING"),
+    (3, "STARTING"),
+    (4, "MPPT"),
+    (5, "THROTTLED"),
+    (6, "SHUTTING_DOWN"),
+    (INVERTER_STATE_FAULT, "FAULT"),
+    (8, "STANDBY"),
+];
+
+/// Flags of the inverter event field `Evt1`.
+const INVERTER_EVENTS: &[(u32, &str)] = &[
+    (0, "GROUND_FAULT"),
+    (1, "DC_OVER_VOLT"),
+    (2, "AC_DISCONNECT"),
+    (3, "DC_DISCONNECT"),
+    (4, "GRID_DISCONNECT"),
+    (5, "CABINET_OPEN"),
+    (6, "MANUAL_SHUTDOWN"),
+    (7, "OVER_TEMP"),
+    (8, "OVER_FREQUENCY"),
+    (9, "UNDER_FREQUENCY"),
+    (10, "AC_OVER_VOLT"),
+    (11, "AC_UNDER_VOLT"),
+    (12, "BLOWN_STRING_FUSE"),
+    (13, "UNDER_TEMP"),
+    (14, "MEMORY_LOSS"),
+    (15, "HW_TEST_FAILURE"),
+];
+
+/// State and event points of the integer inverter models 101-103. The
+/// vendor state has no standard symbols and every `Evt2` bit is reserved.
+const INVERTER_TYPED_POINTS: &[TypedPointSpec] = &[
+    typed("St", INV_ST, TypedKind::Enum16(INVERTER_STATES)),
+    typed("StVnd", 39, TypedKind::Enum16(&[])),
+    typed("Evt1", 40, TypedKind::Bitfield32(INVERTER_EVENTS)),
+    typed("Evt2", 42, TypedKind::Bitfield32(&[])),
+];
+
+/// State and event points of the float inverter models 111-113.
+const FLOAT_INVERTER_TYPED_POINTS: &[TypedPointSpec] = &[
+    typed("St", FLOAT_INV_ST, TypedKind::Enum16(INVERTER_STATES)),
+    typed("StVnd", 49, TypedKind::Enum16(&[])),
+    typed("Evt1", 50, TypedKind::Bitfield32(INVERTER_EVENTS)),
+    typed("Evt2", 52, TypedKind::Bitfield32(&[])),
+];
+
+/// Model 701 (DER AC measurement).
+const DER_MEASUREMENT: u16 = 701;
+/// Model 713 (DER storage capacity).
+const DER_STORAGE: u16 = 713;
+/// Model 124 (basic storage controls).
+const BASIC_STORAGE: u16 = 124;
+/// Model 802 (battery base).
+const BATTERY: u16 = 802;
+const DER_ALRM: usize = 6;
+
+/// Symbols of the DER inverter state (`InvSt`).
+const DER_STATES: &[(u16, &str)] = &[
+    (0, "OFF"),
+    (1, "SLEEPING"),
+    (2, "STARTING"),
+    (3, "RUNNING"),
+    (4, "THROTTLED"),
+    (5, "SHUTTING_DOWN"),
+    (DER_STATE_FAULT, "FAULT"),
+    (7, "STANDBY"),
+];
+
+/// Flags of the DER alarm field `Alrm`: the inverter events plus a
+/// manufacturer alarm.
+const DER_ALARMS: &[(u32, &str)] = &[
+    (0, "GROUND_FAULT"),
+    (1, "DC_OVER_VOLT"),
+    (2, "AC_DISCONNECT"),
+    (3, "DC_DISCONNECT"),
+    (4, "GRID_DISCONNECT"),
+    (5, "CABINET_OPEN"),
+    (6, "MANUAL_SHUTDOWN"),
+    (7, "OVER_TEMP"),
+    (8, "OVER_FREQUENCY"),
+    (9, "UNDER_FREQUENCY"),
+    (10, "AC_OVER_VOLT"),
+    (11, "AC_UNDER_VOLT"),
+    (12, "BLOWN_STRING_FUSE"),
+    (13, "UNDER_TEMP"),
+    (14, "MEMORY_LOSS"),
+    (15, "HW_TEST_FAILURE"),
+    (16, "MANUFACTURER_ALRM"),
+];
+
+/// State and alarm points of model 701.
+const DER_TYPED_POINTS: &[TypedPointSpec] = &[
+    typed(
+        "ACType",
+        2,
+        TypedKind::Enum16(&[(0, "SINGLE_PHASE"), (1, "SPLIT_PHASE"), (2, "THREE_PHASE")]),
+    ),
+    typed("St", 3, TypedKind::Enum16(&[(0, "OFF"), (1, "ON")])),
+    typed("InvSt", 4, TypedKind::Enum16(DER_STATES)),
+    typed(
+        "ConnSt",
+        5,
+        TypedKind::Enum16(&[(0, "DISCONNECTED"), (1, "CONNECTED")]),
+    ),
+    typed("Alrm", DER_ALRM, TypedKind::Bitfield32(DER_ALARMS)),
+];
+
+/// Storage status of model 713.
+const DER_STORAGE_TYPED_POINTS: &[TypedPointSpec] = &[typed(
+    "Sta",
+    6,
+    TypedKind::Enum16(&[(0, "OK"), (1, "WARNING"), (2, "ERROR")]),
+)];
+
+/// Symbols of the storage charge status (`ChaSt`) of models 124 and 802.
+const CHARGE_STATES: &[(u16, &str)] = &[
+    (1, "OFF"),
+    (2, "EMPTY"),
+    (3, "DISCHARGING"),
+    (4, "CHARGING"),
+    (5, "FULL"),
+    (6, "HOLDING"),
+    (7, "TESTING"),
+];
+
+/// Charge status and charge source of model 124.
+const BASIC_STORAGE_TYPED_POINTS: &[TypedPointSpec] = &[
+    typed("ChaSt", 11, TypedKind::Enum16(CHARGE_STATES)),
+    typed(
+        "ChaGriSet",
+        17,
+        TypedKind::Enum16(&[(0, "PV"), (1, "GRID")]),
+    ),
+];
+
+/// Flags of the battery alarm field `Evt1`.
+const BATTERY_EVENTS: &[(u32, &str)] = &[
+    (0, "COMMUNICATION_ERROR"),
+    (1, "OVER_TEMP_ALARM"),
+    (2, "OVER_TEMP_WARNING"),
+    (3, "UNDER_TEMP_ALARM"),
+    (4, "UNDER_TEMP_WARNING"),
+    (5, "OVER_CHARGE_CURRENT_ALARM"),
+    (6, "OVER_CHARGE_CURRENT_WARNING"),
+    (7, "OVER_DISCHARGE_CURRENT_ALARM"),
+    (8, "OVER_DISCHARGE_CURRENT_WARNING"),
+    (9, "OVER_VOLT_ALARM"),
+    (10, "OVER_VOLT_WARNING"),
+    (11, "UNDER_VOLT_ALARM"),
+    (12, "UNDER_VOLT_WARNING"),
+    (13, "UNDER_SOC_MIN_ALARM"),
+    (14, "UNDER_SOC_MIN_WARNING"),
+    (15, "OVER_SOC_MAX_ALARM"),
+    (16, "OVER_SOC_MAX_WARNING"),
+    (17, "VOLTAGE_IMBALANCE_WARNING"),
+    (18, "TEMPERATURE_IMBALANCE_ALARM"),
+    (19, "TEMPERATURE_IMBALANCE_WARNING"),
+    (20, "CONTACTOR_ERROR"),
+    (21, "FAN_ERROR"),
+    (22, "GROUND_FAULT"),
+    (23, "OPEN_DOOR_ERROR"),
+    (24, "CURRENT_IMBALANCE_WARNING"),
+    (25, "OTHER_ALARM"),
+    (26, "OTHER_WARNING"),
+    (28, "CONFIGURATION_ALARM"),
+    (29, "CONFIGURATION_WARNING"),
+];
+
+/// Charge status, battery type, state and alarms of model 802. `Evt2` is
+/// reserved.
+const BATTERY_TYPED_POINTS: &[TypedPointSpec] = &[
+    typed("ChaSt", 16, TypedKind::Enum16(CHARGE_STATES)),
+    typed(
+        "LocRemCtl",
+        17,
+        TypedKind::Enum16(&[(0, "REMOTE"), (1, "LOCAL")]),
+    ),
+    typed(
+        "Typ",
+        21,
+        TypedKind::Enum16(&[
+            (0, "NOT_APPLICABLE_UNKNOWN"),
+            (1, "LEAD_ACID"),
+            (2, "NICKEL_METAL_HYDRATE"),
+            (3, "NICKEL_CADMIUM"),
+            (4, "LITHIUM_ION"),
+            (5, "CARBON_ZINC"),
+            (6, "ZINC_CHLORIDE"),
+            (7, "ALKALINE"),
+            (8, "RECHARGEABLE_ALKALINE"),
+            (9, "SODIUM_SULFUR"),
+            (10, "FLOW"),
+            (99, "OTHER"),
+        ]),
+    ),
+    typed(
+        "State",
+        22,
+        TypedKind::Enum16(&[
+            (1, "DISCONNECTED"),
+            (2, "INITIALIZING"),
+            (3, "CONNECTED"),
+            (4, "STANDBY"),
+            (5, "SOC_PROTECTION"),
+            (6, "SUSPENDING"),
+            (BATTERY_STATE_FAULT, "FAULT"),
+        ]),
+    ),
+    typed("StateVnd", 23, TypedKind::Enum16(&[])),
+    typed("Evt1", 26, TypedKind::Bitfield32(BATTERY_EVENTS)),
+    typed("Evt2", 28, TypedKind::Bitfield32(&[])),
+];
+
+/// Flags of the meter event field `Evt`. Bits 16-31 are vendor defined.
+const METER_EVENTS: &[(u32, &str)] = &[
+    (2, "POWER_FAILURE"),
+    (3, "UNDER_VOLTAGE"),
+    (4, "LOW_PF"),
+    (5, "OVER_CURRENT"),
+    (6, "OVER_VOLTAGE"),
+    (7, "MISSING_SENSOR"),
+];
+
+/// Events of the integer meter models 201-204.
+const METER_TYPED_POINTS: &[TypedPointSpec] =
+    &[typed("Evt", 105, TypedKind::Bitfield32(METER_EVENTS))];
+
+/// Events of the float meter models 211-214.
+const FLOAT_METER_TYPED_POINTS: &[TypedPointSpec] =
+    &[typed("Evt", 124, TypedKind::Bitfield32(METER_EVENTS))];
+
+/// Decodes a string, enum or bit field point; None when the device marks it
+/// as not implemented (all NUL, `0xFFFF`, `0xFFFFFFFF`).
+fn read_typed_point(registers: &[u16], spec: &TypedPointSpec) -> Option<PointValue> {
+    match spec.kind {
+        TypedKind::String(size) => {
+            let value = read_string(registers.get(spec.offset..spec.offset + usize::from(size))?);
+            (!value.is_empty()).then_some(PointValue::String(value))
+        }
+        TypedKind::Enum16(symbols) => match *registers.get(spec.offset)? {
+            u16::MAX => None,
+            value => Some(PointValue::Enum16 {
+                value,
+                symbol: symbols
+                    .iter()
+                    .find(|(symbol_value, _)| *symbol_value == value)
+                    .map(|(_, symbol)| symbol.to_string()),
+            }),
+        },
+        TypedKind::Bitfield32(bits) => {
+            let high = *registers.get(spec.offset)?;
+            let low = *registers.get(spec.offset + 1)?;
+            match (u32::from(high) << 16) | u32::from(low) {
+                u32::MAX => None,
+                value => Some(PointValue::Bitfield32 {
+                    value,
+                    flags: bits
+                        .iter()
+                        .map(|&(bit, name)| PointFlag {
+                            name: name.to_string(),
+                            set: value & (1 << bit) != 0,
+                        })
+                        .collect(),
+                }),
+            }
+        }
+    }
+}
+
+fn typed_points(model_id: u16, registers: &[u16]) -> Option<&'static [TypedPointSpec]> {
+    if model_id == 1 {
+        decode_common(model_id, registers)?;
+        Some(COMMON_POINTS)
+    } else if is_inverter_block(model_id, registers) {
+        typed_table(model_id)
+    } else if model_id == DER_MEASUREMENT && registers.len() > DER_ALRM + 1 {
+        Some(DER_TYPED_POINTS)
+    } else if model_id == DER_STORAGE && registers.len() > 6 {
+        Some(DER_STORAGE_TYPED_POINTS)
+    } else if (201..=204).contains(&model_id) && registers.len() > 106 {
+        Some(METER_TYPED_POINTS)
+    } else if (211..=214).contains(&model_id) && registers.len() > 125 {
+        Some(FLOAT_METER_TYPED_POINTS)
+    } else if model_id == BASIC_STORAGE && registers.len() > 17 {
+        Some(BASIC_STORAGE_TYPED_POINTS)
+    } else if model_id == BATTERY && registers.len() > 29 {
+        Some(BATTERY_TYPED_POINTS)
+    } else {
+        None
+    }
+}
+
+/// Typed point table of a model, whatever the length of its blocks. The
+/// common model is left out: its strings are read with [`decode_common`].
+fn typed_table(model_id: u16) -> Option<&'static [TypedPointSpec]> {
+    Some(match model_id {
+        101..=103 => INVERTER_TYPED_POINTS,
+        111..=113 => FLOAT_INVERTER_TYPED_POINTS,
+        DER_MEASUREMENT => DER_TYPED_POINTS,
+        DER_STORAGE => DER_STORAGE_TYPED_POINTS,
+        201..=204 => METER_TYPED_POINTS,
+        211..=214 => FLOAT_METER_TYPED_POINTS,
+        BASIC_STORAGE => BASIC_STORAGE_TYPED_POINTS,
+        BATTERY => BATTERY_TYPED_POINTS,
+        _ => return None,
+    })
+}
+
+fn read_point(registers: &[u16], spec: &PointSpec, factors: &ScaleFactors) -> Option<f64> {
+    let raw = match spec.kind {
+        PointKind::U16 => PointValue::U16(*registers.get(spec.offset)?),
+        PointKind::I16 => PointValue::I16(*registers.get(spec.offset)? as i16),
+        PointKind::U32 => PointValue::U32(read_u32(registers, spec.offset)?),
+        // Accumulators use 0 for "not accumulated".
+        PointKind::Acc32 => match read_u32(registers, spec.offset)? {
+            0 => return None,
+            value => PointValue::U32(value),
+        },
+        PointKind::Acc64 => match read_u64(registers, spec.offset)? {
+            0 => return None,
+            value => PointValue::U64(value),
+        },
+        // NaN marks a float point as not implemented.
+        PointKind::F32 => {
+            let value = f32::from_bits(read_u32(registers, spec.offset)?);
+            return apply_scale(PointValue::F32(value), 0);
+        }
+    };
+    factors.apply(raw, spec.scale_factor)
+}
+
+fn read_u32(registers: &[u16], offset: usize) -> Option<u32> {
+    let high = *registers.get(offset)?;
+    let low = *registers.get(offset + 1)?;
+    Some((u32::from(high) << 16) | u32::from(low))
+}
+
+fn read_u64(registers: &[u16], offset: usize) -> Option<u64> {
+    let words = registers.get(offset..offset + 4)?;
+    Some(
+        words
+            .iter()
+            .fold(0u64, |value, &word| (value << 16) | u64::from(word)),
+    )
+}
+
+/// Offset of the operating state `St` in an integer or float inverter block.
+fn inverter_state_offset(model_id: u16) -> Option<usize> {
+    match model_id {
+        101..=103 => Some(INV_ST),
+        111..=113 => Some(FLOAT_INV_ST),
+        _ => None,
+    }
+}
+
+fn is_inverter_block(model_id: u16, registers: &[u16]) -> bool {
+    inverter_state_offset(model_id).is_some_and(|offset| registers.len() > offset)
+}
+
+/// Whether `model_id` is one of the inverter models, integer (101-103) or
+/// float (111-113).
+pub fn is_inverter_model(model_id: u16) -> bool {
+    inverter_state_offset(model_id).is_some()
+}
+
+/// The handful of inverter points needed for energy accounting.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct InverterReading {
+    /// AC power in W.
+    pub power_w: Option<f64>,
+    /// Lifetime AC energy in Wh.
+    pub energy_wh: Option<f64>,
+    /// Operating state (`St`): 1 off, 2 sleeping, 4 MPPT, 7 fault, ...
+    pub state: Option<u16>,
+}
+
+/// Decodes power, lifetime energy and state from a model 101-103 or 111-113
+/// block (registers starting at the model ID). Returns None for other models
+/// or a block too short to hold the points; individual sentinels, including
+/// NaN in the float models, decode to None.
+pub fn decode_inverter(model_id: u16, registers: &[u16]) -> Option<InverterReading> {
+    let state_offset = inverter_state_offset(model_id)?;
+    let model = scaled_model(model_id, registers)?;
+    let factors = ScaleFactors::resolve(model.scale_factors, registers);
+    let read = |name: &str| {
+        let spec = model.points.iter().find(|spec| spec.name == name)?;
+        read_point(registers, spec, &factors)
+    };
+    let power_w = read("W");
+    let energy_wh = read("WH");
+    let state = match registers[state_offset] {
+        u16::MAX => None,
+        value => Some(value),
+    };
+
+    Some(InverterReading {
+        power_w,
+        energy_wh,
+        state,
+    })
+}
+
+/// Total real power in W from a model 201-204 or 211-214 block (registers
+/// starting at the model ID), signed as the meter reports it. Returns None
+/// for other models, a block too short to hold `W`, or a sentinel.
+pub fn decode_meter_power(model_id: u16, registers: &[u16]) -> Option<f64> {
+    match model_id {
+        201..=204 if registers.len() > METER_W_SF => {
+            let factors = ScaleFactors::resolve(&[sunssf("W_SF", METER_W_SF)], registers);
+            read_point(
+                registers,
+                &point("W", METER_W, PointKind::I16, "W_SF", "W"),
+                &factors,
+            )
+        }
+        211..=214 => read_point(
+            registers,
+            &point("W", FLOAT_METER_W, PointKind::F32, "", "W"),
+            &ScaleFactors::default(),
+        ),
+        _ => None,
+    }
+}
+
+/// The accumulator point `name` (`acc32` or `acc64`) of a model block
+/// (registers starting at the model ID), e.g. `WH` of an inverter,
+/// `TotWhImp` of a meter or `TotWhInj` of model 701. None when the model has
+/// no such point, the device does not accumulate it (count 0) or does not
+/// implement its scale factor.
+pub fn decode_accumulator(model_id: u16, registers: &[u16], name: &str) -> Option<Accumulator> {
+    let model = scaled_model(model_id, registers)?;
+    let spec = model.points.iter().find(|spec| spec.name == name)?;
+    let (kind, count) = match spec.kind {
+        PointKind::Acc32 => (
+            AccumulatorKind::Acc32,
+            u64::from(read_u32(registers, spec.offset)?),
+        ),
+        PointKind::Acc64 => (AccumulatorKind::Acc64, read_u64(registers, spec.offset)?),
+        _ => return None,
+    };
+    if count == 0 {
+        return None;
+    }
+    let factors = ScaleFactors::resolve(model.scale_factors, registers);
+    Some(Accumulator {
+        kind,
+        count,
+        scale: factors.get(spec.scale_factor)?,
+    })
+}
+
+/// State of charge and power limits of a storage block.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StorageReading {
+    /// State of charge in percent of the usable capacity.
+    pub soc_pct: Option<f64>,
+    /// Charge status (`ChaSt`): 1 off, 2 empty, 3 discharging, 4 charging,
+    /// 5 full, 6 holding, 7 testing.
+    pub charge_state: Option<u16>,
+    /// Maximum charge power in W.
+    pub max_charge_w: Option<f64>,
+    /// Maximum discharge power in W.
+    pub max_discharge_w: Option<f64>,
+}
+
+/// Decodes state of charge, charge status and power limits from a storage
+/// block (registers starting at the model ID): basic storage 124, DER
+/// storage capacity 713 or battery 802. Returns None for other models or a
+/// block too short to hold the points; individual sentinels decode to None.
+pub fn decode_storage(model_id: u16, registers: &[u16]) -> Option<StorageReading> {
+    let (soc, max_charge, max_discharge) = match model_id {
+        BASIC_STORAGE => ("ChaState", Some("WChaMax"), None),
+        DER_STORAGE => ("SoC", None, None),
+        BATTERY => ("SoC", Some("WChaRteMax"), Some("WDisChaRteMax")),
+        _ => return None,
+    };
+    let points = decode_points(model_id, registers)?;
+    let value = |name: Option<&str>| {
+        points
+            .iter()
+            .find(|(point, _)| Some(*point) == name)
+            .map(|(_, value)| *value)
+    };
+    let charge_state = decode_typed_points(model_id, registers)
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|(name, value)| match value {
+            PointValue::Enum16 { value, .. } if name == "ChaSt" => Some(value),
+            _ => None,
+        });
+
+    Some(StorageReading {
+        soc_pct: value(Some(soc)),
+        charge_state,
+        max_charge_w: value(max_charge),
+        max_discharge_w: value(max_discharge),
+    })
+}
+
+/// Every scaled point of a model block this crate knows how to decode, by
+/// SunSpec point name. Points holding a sentinel are left out. Returns None
+/// for models without a point table.
+pub fn decode_points(model_id: u16, registers: &[u16]) -> Option<Vec<(&'static str, f64)>> {
+    let model = scaled_model(model_id, registers)?;
+    let factors = ScaleFactors::resolve(model.scale_factors, registers);
+    Some(
+        model
+            .points
+            .iter()
+            .filter_map(|spec| {
+                read_point(registers, spec, &factors).map(|value| (spec.name, value))
+            })
+            .collect(),
+    )
+}
+
+/// Where one point of a model block sits and how it is encoded, with the
+/// scale factor the device reported in the block it was taken from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PointLayout {
+    pub name: &'static str,
+    /// Register address of the point's first register.
+    pub address: u16,
+    /// SunSpec point type: `uint16`, `int16`, `uint32`, `acc32`, `acc64`,
+    /// `float32`, `string`, `enum16` or `bitfield32`.
+    #[serde(rename = "type")]
+    pub point_type: &'static str,
+    /// Number of registers the point occupies.
+    pub size: u16,
+    /// Power of ten applied to the raw value; None for unscaled points.
+    pub scale: Option<i16>,
+    /// Name of the `sunssf` point holding `scale`.
+    pub scale_factor: Option<&'static str>,
+    pub scale_address: Option<u16>,
+    /// SunSpec units; empty for strings, enums and bit fields.
+    pub units: &'static str,
+}
+
+/// Point map of a model block starting at register `start` (registers
+/// starting at the model ID). Only points the device implements are listed,
+/// so the result reflects its firmware. Returns None for models without a
+/// point table.
+pub fn point_layout(model_id: u16, start: u16, registers: &[u16]) -> Option<Vec<PointLayout>> {
+    let address = |offset: usize| start.saturating_add(offset as u16);
+    let typed_specs = typed_points(model_id, registers);
+    let model = scaled_model(model_id, registers);
+    if typed_specs.is_none() && model.is_none() {
+        return None;
+    }
+    let typed = typed_specs
+        .unwrap_or_default()
+        .iter()
+        .filter(|spec| read_typed_point(registers, spec).is_some())
+        .map(|spec| PointLayout {
+            name: spec.name,
+            address: address(spec.offset),
+            point_type: spec.kind.type_name(),
+            size: spec.kind.size(),
+            scale: None,
+            scale_factor: None,
+            scale_address: None,
+            units: "",
+        });
+    let factors = resolve_scale_factors(model_id, registers).unwrap_or_default();
+    let scaled = model
+        .map(|model| model.points)
+        .unwrap_or_default()
+        .iter()
+        .filter(|spec| read_point(registers, spec, &factors).is_some())
+        .filter_map(|spec| {
+            // Float points are not scaled.
+            let factor = match spec.scale_factor {
+                "" => None,
+                name => Some(factors.find(name)?),
+            };
+            Some(PointLayout {
+                name: spec.name,
+                address: address(spec.offset),
+                point_type: spec.kind.type_name(),
+                size: spec.kind.size(),
+                scale: factor.and_then(|factor| factor.value),
+                scale_factor: factor.map(|factor| factor.name),
+                scale_address: factor.map(|factor| address(factor.offset)),
+                units: spec.units,
+            })
+        });
+    let mut points: Vec<PointLayout> = scaled.chain(typed).collect();
+    points.sort_by_key(|point| point.address);
+    Some(points)
+}
+
+/// Registers of a model block, as offsets from the model ID, that hold the
+/// named points and the scale factors they are scaled with. Reading only
+/// `offset..offset + length` is enough to decode those points; no names give
+/// an empty span.
+pub fn point_span(model_id: u16, names: &[String]) -> Result<(u16, u16), ParserError> {
+    let scaled = scaled_table(model_id);
+    let typed = typed_table(model_id).unwrap_or_default();
+    let mut span: Option<(usize, usize)> = None;
+    let mut cover = |offset: usize, size: u16| {
+        let end = offset + usize::from(size);
+        span = Some(match span {
+            Some((first, last)) => (first.min(offset), last.max(end)),
+            None => (offset, end),
+        });
+    };
+    for name in names {
+        let unknown = || ParserError::UnknownPoint {
+            model_id,
+            name: name.clone(),
+        };
+        if let Some(spec) = typed.iter().find(|spec| spec.name == name) {
+            cover(spec.offset, spec.kind.size());
+            continue;
+        }
+        let model = scaled.ok_or_else(unknown)?;
+        let spec = model
+            .points
+            .iter()
+            .find(|spec| spec.name == name)
+            .ok_or_else(unknown)?;
+        cover(spec.offset, spec.kind.size());
+        if let Some(factor) = model
+            .scale_factors
+            .iter()
+            .find(|factor| factor.name == spec.scale_factor)
+        {
+            cover(factor.offset, 1);
+        }
+    }
+    let (first, end) = span.unwrap_or_default();
+    Ok((first as u16, (end - first) as u16))
+}
+
+/// Registers to write to set point `name` of a model block to `value`, and
+/// their offset from the model ID. Scaled points are scaled with the factor
+/// the device reports in `registers` (the block as last read); enum points
+/// take the enum value. Accumulators, strings, bit fields and points whose
+/// scale factor the device does not implement cannot be written.
+pub fn encode_point(
+    model_id: u16,
+    registers: &[u16],
+    name: &str,
+    value: f64,
+) -> Result<(u16, Vec<u16>), ParserError> {
+    let not_writable = || ParserError::NotWritable {
+        model_id,
+        name: name.to_string(),
+    };
+    let out_of_range = || ParserError::OutOfRange {
+        name: name.to_string(),
+        value,
+    };
+    if let Some(spec) = typed_table(model_id)
+        .unwrap_or_default()
+        .iter()
+        .find(|spec| spec.name == name)
+    {
+        let TypedKind::Enum16(_) = spec.kind else {
+            return Err(not_writable());
+        };
+        // 0xFFFF marks the point as not implemented.
+        if value.fract() != 0.0 || !(0.0..f64::from(u16::MAX)).contains(&value) {
+            return Err(out_of_range());
+        }
+        return Ok((spec.offset as u16, vec![value as u16]));
+    }
+    let model = scaled_table(model_id).ok_or_else(|| ParserError::UnknownPoint {
+        model_id,
+        name: name.to_string(),
+    })?;
+    let spec = model
+        .points
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| ParserError::UnknownPoint {
+            model_id,
+            name: name.to_string(),
+        })?;
+    let raw = match spec.scale_factor {
+        "" => value,
+        factor => {
+            let factor = ScaleFactors::resolve(model.scale_factors, registers)
+                .get(factor)
+                .ok_or_else(not_writable)?;
+            (value / 10f64.powi(i32::from(factor))).round()
+        }
+    };
+    let in_range = |min: f64, max: f64| (min..=max).contains(&raw);
+    // The sentinel of each type is left out of its range.
+    let words = match spec.kind {
+        PointKind::U16 if in_range(0.0, f64::from(u16::MAX - 1)) => vec![raw as u16],
+        PointKind::I16 if in_range(f64::from(i16::MIN + 1), f64::from(i16::MAX)) => {
+            vec![raw as i16 as u16]
+        }
+        PointKind::U32 if in_range(0.0, f64::from(u32::MAX - 1)) => {
+            let raw = raw as u32;
+            vec![(raw >> 16) as u16, raw as u16]
+        }
+        PointKind::F32 if raw.is_finite() && in_range(f64::from(f32::MIN), f64::from(f32::MAX)) => {
+            let bits = (raw as f32).to_bits();
+            vec![(bits >> 16) as u16, bits as u16]
+        }
+        PointKind::Acc32 | PointKind::Acc64 => return Err(not_writable()),
+        _ => return Err(out_of_range()),
+    };
+    Ok((spec.offset as u16, words))
+}
+
+/// String, enum and bit field points of a model block (registers starting at
+/// the model ID), by SunSpec point name. Points the device does not implement
+/// are left out. Returns None for models without a point table.
+pub fn decode_typed_points(
+    model_id: u16,
+    registers: &[u16],
+) -> Option<Vec<(&'static str, PointValue)>> {
+    Some(
+        typed_points(model_id, registers)?
+            .iter()
+            .filter_map(|spec| read_typed_point(registers, spec).map(|value| (spec.name, value)))
+            .collect(),
+    )
+}
+
+/// Operating state and active events of an inverter or battery block.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeviceStatus {
+    /// Name of the state point: `St` for models 101-103, `InvSt` for 701,
+    /// `State` for 802.
+    pub state_point: &'static str,
+    pub state: Option<u16>,
+    pub state_symbol: Option<String>,
+    /// Whether the state is the model's fault state.
+    pub fault: bool,
+    /// Set event bits, in point and bit order.
+    pub events: Vec<DeviceEvent>,
+}
+
+/// One set bit of an event point.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeviceEvent {
+    /// Event point: `Evt1`, `Evt2` or `Alrm`.
+    pub point: &'static str,
+    /// Flag name, or `BIT_<n>` for bits the model does not name.
+    pub name: String,
+}
+
+/// Where a model keeps its state and events.
+struct StatusPoints {
+    state: &'static str,
+    fault_state: u16,
+    events: &'static [&'static str],
+}
+
+const INVERTER_STATUS: StatusPoints = StatusPoints {
+    state: "St",
+    fault_state: INVERTER_STATE_FAULT,
+    events: &["Evt1", "Evt2"],
+};
+
+const DER_STATUS: StatusPoints = StatusPoints {
+    state: "InvSt",
+    fault_state: DER_STATE_FAULT,
+    events: &["Alrm"],
+};
+
+const BATTERY_STATUS: StatusPoints = StatusPoints {
+    state: "State",
+    fault_state: BATTERY_STATE_FAULT,
+    events: &["Evt1", "Evt2"],
+};
+
+/// Decodes the operating state and event bits of an inverter or battery
+/// block (models 101-103, 111-113, 701 and 802, registers starting at the model ID).
+/// Points the device does not implement decode to no state and no events.
+/// Returns None for other models.
+pub fn decode_status(model_id: u16, registers: &[u16]) -> Option<DeviceStatus> {
+    let status = match model_id {
+        101..=103 | 111..=113 => &INVERTER_STATUS,
+        DER_MEASUREMENT => &DER_STATUS,
+        BATTERY => &BATTERY_STATUS,
+        _ => return None,
+    };
+    let specs = typed_points(model_id, registers)?;
+    let spec = |name: &str| specs.iter().find(|spec| spec.name == name);
+
+    let (state, state_symbol) =
+        match spec(status.state).and_then(|spec| read_typed_point(registers, spec)) {
+            Some(PointValue::Enum16 { value, symbol }) => (Some(value), symbol),
+            _ => (None, None),
+        };
+    let mut events = Vec::new();
+    for spec in status.events.iter().filter_map(|name| spec(name)) {
+        let (TypedKind::Bitfield32(bits), Some(PointValue::Bitfield32 { value, .. })) =
+            (spec.kind, read_typed_point(registers, spec))
+        else {
+            continue;
+        };
+        events.extend((0..32).filter(|bit| value & (1 << bit) != 0).map(|bit| {
+            let name = bits
+                .iter()
+                .find(|(flag_bit, _)| *flag_bit == bit)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| format!("BIT_{bit}"));
+            DeviceEvent {
+                point: spec.name,
+                name,
+            }
+        }));
+    }
+
+    Some(DeviceStatus {
+        state_point: status.state,
+        state,
+        state_symbol,
+        fault: state == Some(status.fault_state),
+        events,
+    })
+}
+
+/// Identity strings of the common model (model 1).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CommonModel {
+    pub manufacturer: String,
+    pub model: String,
+    pub options: String,
+    pub version: String,
+    pub serial_number: String,
+}
+
+/// Decodes a common model block (registers starting at the model ID).
+/// Returns None for other models or a block too short to hold `SN`.
+pub fn decode_common(model_id: u16, registers: &[u16]) -> Option<CommonModel> {
+    if model_id != 1 {
+        return None;
+    }
+    // Mn, Md, Opt, Vr and SN follow the two header registers back to back.
+    Some(CommonModel {
+        manufacturer: read_string(registers.get(2..18)?),
+        model: read_string(registers.get(18..34)?),
+        options: read_string(registers.get(34..42)?),
+        version: read_string(registers.get(42..50)?),
+        serial_number: read_string(registers.get(50..66)?),
+    })
+}
+
+/// SunSpec strings are big-endian byte pairs padded with NUL (some vendors
+/// pad with spaces).
+fn read_string(registers: &[u16]) -> String {
+    let bytes: Vec<u8> = registers.iter().flat_map(|reg| reg.to_be_bytes()).collect();
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
 fn model_name(model_id: u16) -> String {
     match model_id {
         1 => "common".to_string(),
         101 => "inverter".to_string(),
         103 => "three_phase_inverter".to_string(),
+        111 => "float_inverter".to_string(),
+        112 => "float_split_phase_inverter".to_string(),
+        113 => "float_three_phase_inverter".to_string(),
         160 => "mppt".to_string(),
+        124 => "storage".to_string(),
         201 => "meter".to_string(),
+        701 => "der_ac_measurement".to_string(),
+        702 => "der_capacity".to_string(),
+        703 => "der_enter_service".to_string(),
+        704 => "der_ac_controls".to_string(),
+        705 => "der_volt_var".to_string(),
+        706 => "der_volt_watt".to_string(),
+        707 => "der_trip_lv".to_string(),
+        708 => "der_trip_hv".to_string(),
+        709 => "der_trip_lf".to_string(),
+        710 => "der_trip_hf".to_string(),
+        711 => "der_freq_droop".to_string(),
+        712 => "der_watt_var".to_string(),
+        713 => "der_storage_capacity".to_string(),
+        714 => "der_dc_measurement".to_string(),
+        802 => "battery".to_string(),
+        803 => "lithium_ion_bank".to_string(),
+        804 => "lithium_ion_string".to_string(),
+        805 => "lithium_ion_module".to_string(),
         _ => format!("model_{model_id}"),
     }
 }
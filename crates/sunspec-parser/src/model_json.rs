@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ModelDefinition, ParserError};
+
+/// One model as defined by a file of the official SunSpec models repository
+/// (`models/json/model_NNN.json`): a top-level group of points with nested
+/// and repeating groups.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSpec {
+    pub id: u16,
+    /// Top-level group, starting with the `ID` and `L` points.
+    pub group: GroupSpec,
+}
+
+/// How often a group repeats.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GroupCount {
+    Fixed(u16),
+    /// Given by the value of a point of the enclosing group.
+    Point(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupSpec {
+    pub name: String,
+    pub count: GroupCount,
+    pub points: Vec<PointDef>,
+    pub groups: Vec<GroupSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointDef {
+    pub name: String,
+    /// SunSpec type name (`uint16`, `acc32`, `enum16`, `string`, `sunssf`, ...).
+    pub point_type: String,
+    /// Size in registers.
+    pub size: u16,
+    /// Offset from the start of the group instance; for the top-level group,
+    /// from the model ID register.
+    pub offset: u16,
+    pub scale_factor: Option<ScaleFactorRef>,
+    pub units: Option<String>,
+    pub symbols: Vec<SymbolDef>,
+}
+
+/// Scale factor of a point: a `sunssf` point or a fixed exponent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ScaleFactorRef {
+    Point(String),
+    Fixed(i16),
+}
+
+/// Named value of an enum point, or named bit of a bitfield point.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolDef {
+    pub name: String,
+    pub value: i64,
+}
+
+impl ModelSpec {
+    pub fn name(&self) -> &str {
+        &self.group.name
+    }
+
+    /// Top-level point by name.
+    pub fn point(&self, name: &str) -> Option<&PointDef> {
+        self.group.points.iter().find(|point| point.name == name)
+    }
+
+    /// Registers of the fixed part of the model, header included. Groups
+    /// repeated by a point value follow it; their number is only known from
+    /// the model length the device reports.
+    pub fn fixed_length(&self) -> u16 {
+        self.group.fixed_length()
+    }
+
+    pub fn definition(&self) -> ModelDefinition {
+        ModelDefinition {
+            id: self.id,
+            name: self.group.name.clone(),
+            start: 0,
+            length: self.fixed_length(),
+        }
+    }
+}
+
+impl GroupSpec {
+    /// Registers of one instance: its points plus nested groups with a fixed
+    /// count.
+    pub fn fixed_length(&self) -> u16 {
+        let points = self
+            .points
+            .iter()
+            .fold(0u16, |total, point| total.saturating_add(point.size));
+        self.groups
+            .iter()
+            .filter_map(|group| match group.count {
+                GroupCount::Fixed(count) => Some(group.fixed_length().saturating_mul(count)),
+                GroupCount::Point(_) => None,
+            })
+            .fold(points, u16::saturating_add)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawModelFile {
+    id: u16,
+    group: RawGroup,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawGroup {
+    name: String,
+    count: Option<RawCount>,
+    #[serde(default)]
+    points: Vec<RawPoint>,
+    #[serde(default)]
+    groups: Vec<RawGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawCount {
+    Fixed(u16),
+    Point(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPoint {
+    name: String,
+    #[serde(rename = "type")]
+    point_type: String,
+    size: Option<u16>,
+    sf: Option<RawScaleFactor>,
+    units: Option<String>,
+    #[serde(default)]
+    symbols: Vec<RawSymbol>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawScaleFactor {
+    Fixed(i16),
+    Point(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSymbol {
+    name: String,
+    value: i64,
+}
+
+/// Whether a JSON document looks like an official model file rather than a
+/// model list.
+pub(crate) fn is_model_file(value: &serde_json::Value) -> bool {
+    value.get("group").is_some() && value.get("id").is_some()
+}
+
+/// Parses one official model file.
+pub fn parse_model_file(data: &str) -> Result<ModelSpec, ParserError> {
+    let raw: RawModelFile = serde_json::from_str(data)?;
+    let group = convert_group(raw.group)?;
+    Ok(ModelSpec { id: raw.id, group })
+}
+
+fn convert_group(raw: RawGroup) -> Result<GroupSpec, ParserError> {
+    let mut offset = 0u16;
+    let mut points = Vec::with_capacity(raw.points.len());
+    for point in raw.points {
+        let size = match point.size {
+            Some(size) => size,
+            None => type_size(&point.point_type).ok_or_else(|| {
+                ParserError::InvalidAttribute(format!("size of point {}", point.name))
+            })?,
+        };
+        points.push(PointDef {
+            name: point.name,
+            point_type: point.point_type,
+            size,
+            offset,
+            scale_factor: point.sf.map(|sf| match sf {
+                RawScaleFactor::Fixed(value) => ScaleFactorRef::Fixed(value),
+                RawScaleFactor::Point(name) => ScaleFactorRef::Point(name),
+            }),
+            units: point.units,
+            symbols: point
+                .symbols
+                .into_iter()
+                .map(|symbol| SymbolDef {
+                    name: symbol.name,
+                    value: symbol.value,
+                })
+                .collect(),
+        });
+        offset = offset
+            .checked_add(size)
+            .ok_or(ParserError::LengthOverflow)?;
+    }
+    let count = match raw.count {
+        None => GroupCount::Fixed(1),
+        Some(RawCount::Fixed(count)) => GroupCount::Fixed(count),
+        // Some files quote fixed counts.
+        Some(RawCount::Point(value)) => match value.parse() {
+            Ok(count) => GroupCount::Fixed(count),
+            Err(_) => GroupCount::Point(value),
+        },
+    };
+    Ok(GroupSpec {
+        name: raw.name,
+        count,
+        points,
+        groups: raw
+            .groups
+            .into_iter()
+            .map(convert_group)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Size of fixed-size point types, for files that leave `size` out.
+fn type_size(point_type: &str) -> Option<u16> {
+    match point_type {
+        "int16" | "uint16" | "acc16" | "enum16" | "bitfield16" | "sunssf" | "count" | "pad" => {
+            Some(1)
+        }
+        "int32" | "uint32" | "acc32" | "enum32" | "bitfield32" | "float32" | "ipaddr" => Some(2),
+        "int64" | "uint64" | "acc64" | "bitfield64" | "float64" => Some(4),
+        "ipv6addr" => Some(8),
+        "eui48" => Some(4),
+        _ => None,
+    }
+}
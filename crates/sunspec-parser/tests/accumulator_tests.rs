@@ -0,0 +1,96 @@
+use sunspec_parser::{decode_accumulator, Accumulator, AccumulatorKind, AccumulatorTracker};
+
+fn acc32(count: u32) -> Accumulator {
+    Accumulator {
+        kind: AccumulatorKind::Acc32,
+        count: u64::from(count),
+        scale: 1,
+    }
+}
+
+#[test]
+fn deltas_count_rollovers_but_not_resets() {
+    assert_eq!(acc32(15).delta_since(acc32(10)), Some(50.0));
+    assert_eq!(acc32(4).delta_since(acc32(u32::MAX - 5)), Some(100.0));
+    // A decrease far from the top of the counter is a reset.
+    assert_eq!(acc32(4).delta_since(acc32(1_000)), None);
+    let unscaled = Accumulator {
+        scale: 0,
+        ..acc32(15)
+    };
+    assert_eq!(unscaled.delta_since(acc32(10)), None);
+    assert_eq!(acc32(7).value(), 70.0);
+
+    // An acc64 counter only rolls over at the top of its own range.
+    let acc64 = |count: u64| Accumulator {
+        kind: AccumulatorKind::Acc64,
+        count,
+        scale: 0,
+    };
+    assert_eq!(acc64(4).delta_since(acc64(u64::from(u32::MAX) - 5)), None);
+    assert_eq!(acc64(4).delta_since(acc64(u64::MAX - 5)), Some(10.0));
+    assert_eq!(acc64(4).delta_since(acc32(4)), None);
+}
+
+#[test]
+fn tracker_keeps_totals_monotonic_per_key() {
+    let mut tracker = AccumulatorTracker::new();
+    let first = tracker.update("a", acc32(100));
+    assert_eq!(
+        (first.interval, first.total, first.reset),
+        (0.0, 1_000.0, false)
+    );
+    let next = tracker.update("a", acc32(103));
+    assert_eq!((next.interval, next.total), (30.0, 1_030.0));
+
+    // A reset keeps the total where it was; counting resumes from the new value.
+    let reset = tracker.update("a", acc32(2));
+    assert_eq!(
+        (reset.interval, reset.total, reset.reset),
+        (0.0, 1_030.0, true)
+    );
+    let after = tracker.update("a", acc32(5));
+    assert_eq!((after.interval, after.total), (30.0, 1_060.0));
+
+    let other = tracker.update("b", acc32(u32::MAX - 1));
+    assert_eq!(other.interval, 0.0);
+    let rolled = tracker.update("b", acc32(1));
+    assert_eq!((rolled.interval, rolled.reset), (30.0, false));
+    assert_eq!(tracker.len(), 2);
+
+    tracker.remove(&"a");
+    assert_eq!(tracker.update("a", acc32(5)).total, 50.0);
+}
+
+#[test]
+fn accumulators_decode_from_model_blocks() {
+    let mut der = vec![0u16; 123];
+    der[0] = 701;
+    der[1] = 153;
+    der[19..23].copy_from_slice(&[0, 0, 1, 0]);
+    der[120] = 3;
+    assert_eq!(
+        decode_accumulator(701, &der, "TotWhInj"),
+        Some(Accumulator {
+            kind: AccumulatorKind::Acc64,
+            count: 65_536,
+            scale: 3,
+        })
+    );
+    // 0 means "not accumulated".
+    assert_eq!(decode_accumulator(701, &der, "TotWhAbs"), None);
+    assert_eq!(decode_accumulator(701, &der, "W"), None);
+
+    let mut inverter = vec![0u16; 52];
+    inverter[0] = 103;
+    inverter[1] = 50;
+    inverter[24..26].copy_from_slice(&[0, 500]);
+    assert_eq!(
+        decode_accumulator(103, &inverter, "WH").map(Accumulator::value),
+        Some(500.0)
+    );
+    // WH_SF not implemented.
+    inverter[26] = 0x8000;
+    assert_eq!(decode_accumulator(103, &inverter, "WH"), None);
+    assert_eq!(decode_accumulator(160, &inverter, "WH"), None);
+}
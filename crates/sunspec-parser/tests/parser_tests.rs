@@ -1,7 +1,12 @@
 use sunspec_parser::{
-    parse_models_from_json, parse_models_from_registers, parse_models_from_registers_lenient,
-    parse_models_from_xml, ModelCatalog,
+    apply_scale, base_address_candidates, decode_accumulator, decode_common, decode_inverter,
+    decode_meter_power, decode_points, decode_status, decode_storage, decode_typed_points,
+    has_sunspec_marker, parse_models_from_json, parse_models_from_registers,
+    parse_models_from_registers_lenient, parse_models_from_xml, point_layout, point_span,
+    resolve_scale_factors, Accumulator, AccumulatorKind, GroupCount, ModelCatalog, ParserError,
+    ScaleFactorRef, BATTERY_STATE_FAULT, INVERTER_STATE_FAULT,
 };
+use types::{PointFlag, PointValue};
 
 #[test]
 fn parse_json_fixture_models() {
@@ -29,25 +34,45 @@ fn parse_xml_fixture_models() {
     assert_eq!(models[1].length, 52);
 }
 
+#[test]
+fn parse_official_model_files() {
+    let data = include_str!("fixtures/model_dir/model_1.json");
+    let models = parse_models_from_json(data).expect("model file");
+    assert_eq!(models.len(), 1);
+    assert_eq!(models[0].id, 1);
+    assert_eq!(models[0].name, "common");
+    assert_eq!(models[0].length, 68);
+
+    let catalog = ModelCatalog::new();
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/model_dir");
+    // model_64000.json has a point without a size and is skipped.
+    assert_eq!(catalog.load_dir(&dir).expect("model dir"), 2);
+    assert_eq!(catalog.model(1).expect("common").length, 68);
+    assert!(catalog.model(64_000).is_none());
+
+    let volt_var = catalog.spec(705).expect("volt-var spec");
+    assert_eq!(volt_var.name(), "DERVoltVar");
+    // Curves repeat NCrv times and are not part of the fixed length.
+    assert_eq!(volt_var.fixed_length(), 15);
+    let enable = volt_var.point("Ena").expect("Ena");
+    assert_eq!(enable.offset, 2);
+    assert_eq!(enable.symbols[1].name, "ENABLED");
+    assert_eq!(volt_var.point("RvrtRem").expect("RvrtRem").offset, 9);
+    let curve = &volt_var.group.groups[0];
+    assert_eq!(curve.count, GroupCount::Point("NCrv".to_string()));
+    assert_eq!(
+        curve.points[3].scale_factor,
+        Some(ScaleFactorRef::Point("V_SF".to_string()))
+    );
+    assert_eq!(curve.groups[0].points[1].offset, 1);
+
+    assert!(catalog.load_dir(&dir.join("missing")).is_err());
+}
+
 #[test]
 fn parse_register_map_strict_and_lenient() {
     let base = 40_000u16;
-    let registers = vec![
-        0x5375,
-        0x6e53,
-        1,
-        2,
-        0,
-        0,
-        103,
-        4,
-        0,
-        0,
-        0,
-        0,
-        0xFFFF,
-        0,
-    ];
+    let registers = vec![0x5375, 0x6e53, 1, 2, 0, 0, 103, 4, 0, 0, 0, 0, 0xFFFF, 0];
 
     let models = parse_models_from_registers(base, &registers).expect("register parse");
     assert_eq!(models.len(), 2);
@@ -56,32 +81,37 @@ fn parse_register_map_strict_and_lenient() {
     assert_eq!(models[1].start, 40_006);
     assert_eq!(models[1].length, 6);
 
-    let truncated = vec![
-        0x5375,
-        0x6e53,
-        1,
-        2,
-        0,
-        0,
-        103,
-        4,
-        0,
-        0,
-    ];
+    let truncated = vec![0x5375, 0x6e53, 1, 2, 0, 0, 103, 4, 0, 0];
 
     assert!(parse_models_from_registers(base, &truncated).is_err());
-    let models =
-        parse_models_from_registers_lenient(base, &truncated).expect("lenient parse");
+    let models = parse_models_from_registers_lenient(base, &truncated).expect("lenient parse");
     assert_eq!(models.len(), 1);
     assert_eq!(models[0].id, 1);
 }
 
+#[test]
+fn base_address_candidates_cover_standard_and_shifted_addresses() {
+    assert_eq!(
+        base_address_candidates(40_000),
+        vec![40_000, 50_000, 0, 40_001, 39_999, 50_001, 49_999, 1]
+    );
+    // A custom address is tried first, shifts of it right after the standard ones.
+    let custom = base_address_candidates(30_000);
+    assert_eq!(&custom[..4], &[30_000, 40_000, 50_000, 0]);
+    assert_eq!(&custom[4..6], &[30_001, 29_999]);
+    assert_eq!(custom.len(), 11);
+
+    assert!(has_sunspec_marker(&[0x5375, 0x6e53, 1]));
+    assert!(!has_sunspec_marker(&[0x5375]));
+    assert!(!has_sunspec_marker(&[0, 0x5375, 0x6e53]));
+}
+
 #[test]
 fn model_catalog_caches_results() {
     let json_data = include_str!("fixtures/models.json");
     let xml_data = include_str!("fixtures/models.xml");
 
-    let mut catalog = ModelCatalog::default();
+    let catalog = ModelCatalog::default();
     let _ = catalog.parse_json(json_data).expect("json parse");
     let _ = catalog.parse_json(json_data).expect("json cache");
     assert_eq!(catalog.json_cache_len(), 1);
@@ -90,3 +120,640 @@ fn model_catalog_caches_results() {
     let _ = catalog.parse_xml(xml_data).expect("xml cache");
     assert_eq!(catalog.xml_cache_len(), 1);
 }
+
+#[test]
+fn model_catalog_clones_share_one_cache() {
+    let json_data = include_str!("fixtures/models.json");
+    let catalog = ModelCatalog::new();
+
+    let parsers: Vec<_> = (0..4)
+        .map(|_| {
+            let catalog = catalog.clone();
+            std::thread::spawn(move || catalog.parse_json(json_data).expect("json parse"))
+        })
+        .collect();
+    for parser in parsers {
+        let models = parser.join().expect("parser thread");
+        assert!(!models.is_empty());
+    }
+    assert_eq!(catalog.json_cache_len(), 1);
+
+    let first = &catalog.parse_json(json_data).expect("json cache")[0];
+    let shared = catalog.model(first.id).expect("model by id");
+    assert_eq!(shared.length, first.length);
+    assert!(catalog.model(64_999).is_none());
+}
+
+#[test]
+fn model_catalog_evicts_oldest_documents() {
+    let catalog = ModelCatalog::new().with_document_limit(2);
+    for id in [101, 102, 103] {
+        let data = format!("[{{\"id\": {id}, \"name\": \"inverter\", \"length\": 50}}]");
+        catalog.parse_json(&data).expect("json parse");
+    }
+    assert_eq!(catalog.json_cache_len(), 2);
+    // Definitions outlive their documents.
+    assert!(catalog.model(101).is_some());
+
+    assert!(catalog.remove(101));
+    assert!(catalog.model(101).is_none());
+    assert!(!catalog.remove(101));
+    catalog.clear_documents();
+    assert_eq!(catalog.json_cache_len(), 0);
+    assert!(catalog.model(103).is_some());
+}
+
+#[test]
+fn model_catalog_persists_with_version_and_checksum() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/model_dir");
+    let catalog = ModelCatalog::new();
+    catalog.load_dir(&dir).expect("model dir");
+    catalog
+        .parse_json(include_str!("fixtures/models.json"))
+        .expect("json parse");
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("sunspec_catalog_{nanos}.json"));
+    assert_eq!(catalog.save(&path).expect("save"), 3);
+
+    let restored = ModelCatalog::new();
+    assert_eq!(restored.load(&path).expect("load"), 3);
+    assert_eq!(restored.model(103).expect("inverter").length, 52);
+    assert_eq!(restored.spec(705), catalog.spec(705));
+
+    let data = std::fs::read_to_string(&path).expect("read");
+    std::fs::write(&path, data.replace("\"length\": 52", "\"length\": 53")).expect("write");
+    assert!(matches!(
+        ModelCatalog::new().load(&path),
+        Err(ParserError::CatalogChecksum { .. })
+    ));
+    std::fs::write(&path, data.replace("\"version\": 1", "\"version\": 99")).expect("write");
+    assert!(matches!(
+        ModelCatalog::new().load(&path),
+        Err(ParserError::CatalogVersion { version: 99, .. })
+    ));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn decode_inverter_points_with_scale_factors() {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[14] = 1234;
+    registers[15] = 1;
+    registers[24] = 0x0001;
+    registers[25] = 0x0000;
+    registers[26] = 1;
+    registers[38] = INVERTER_STATE_FAULT;
+
+    let reading = decode_inverter(103, &registers).expect("inverter block");
+    assert_eq!(reading.power_w, Some(12_340.0));
+    assert_eq!(reading.energy_wh, Some(655_360.0));
+    assert_eq!(reading.state, Some(INVERTER_STATE_FAULT));
+
+    registers[14] = 0x8000;
+    registers[25] = 0;
+    registers[24] = 0;
+    let reading = decode_inverter(103, &registers).expect("inverter block");
+    assert_eq!(reading.power_w, None);
+    assert_eq!(reading.energy_wh, None);
+
+    assert!(decode_inverter(160, &registers).is_none());
+    assert!(decode_inverter(103, &registers[..20]).is_none());
+}
+
+#[test]
+fn decode_float_inverter_points() {
+    let mut registers = vec![0u16; 62];
+    registers[0] = 113;
+    registers[1] = 60;
+    let mut put = |offset: usize, value: f32| {
+        let bits = value.to_bits();
+        registers[offset] = (bits >> 16) as u16;
+        registers[offset + 1] = bits as u16;
+    };
+    put(16, 230.5);
+    put(18, f32::NAN);
+    put(22, 4_250.0);
+    put(32, 1_234_567.0);
+    put(36, 612.5);
+    registers[48] = INVERTER_STATE_FAULT;
+    // Evt1: GROUND_FAULT.
+    registers[51] = 1;
+
+    let reading = decode_inverter(113, &registers).expect("float inverter block");
+    assert_eq!(reading.power_w, Some(4_250.0));
+    assert_eq!(reading.energy_wh, Some(1_234_567.0));
+    assert_eq!(reading.state, Some(INVERTER_STATE_FAULT));
+    let points = decode_points(113, &registers).expect("float inverter points");
+    assert!(points.contains(&("PhVphA", 230.5)));
+    assert!(points.contains(&("DCV", 612.5)));
+    // NaN marks a point as not implemented.
+    assert!(!points.iter().any(|(name, _)| *name == "PhVphB"));
+    // WH is a float32, not an accumulator.
+    assert_eq!(decode_accumulator(113, &registers, "WH"), None);
+
+    let status = decode_status(113, &registers).expect("float inverter status");
+    assert_eq!(status.state_symbol.as_deref(), Some("FAULT"));
+    assert_eq!(status.events.len(), 1);
+    assert_eq!(status.events[0].name, "GROUND_FAULT");
+    let layout = point_layout(113, 40_070, &registers).expect("float layout");
+    let power = layout.iter().find(|point| point.name == "W").expect("W");
+    assert_eq!((power.address, power.point_type), (40_092, "float32"));
+
+    let mut map = vec![0x5375, 0x6e53, 111, 60];
+    map.extend(vec![0u16; 60]);
+    map.extend([0xFFFF, 0]);
+    let models = parse_models_from_registers(40_000, &map).expect("register parse");
+    assert_eq!(models[0].name, "float_inverter");
+
+    assert!(decode_inverter(213, &registers).is_none());
+    assert!(decode_inverter(113, &registers[..40]).is_none());
+}
+
+#[test]
+fn decode_meter_power_with_scale_factor() {
+    let mut registers = vec![0u16; 107];
+    registers[0] = 203;
+    registers[1] = 105;
+    registers[18] = (-1_500i16) as u16;
+    registers[22] = 1;
+    assert_eq!(decode_meter_power(203, &registers), Some(-15_000.0));
+
+    registers[18] = 0x8000;
+    assert_eq!(decode_meter_power(203, &registers), None);
+    assert_eq!(decode_meter_power(103, &registers), None);
+    assert_eq!(decode_meter_power(203, &registers[..20]), None);
+}
+
+#[test]
+fn decode_meter_phases_and_energy() {
+    let mut registers = vec![0u16; 107];
+    registers[0] = 201;
+    registers[1] = 105;
+    registers[3] = 52;
+    registers[4] = 0x8000;
+    registers[6] = (-1i16) as u16;
+    registers[8] = 230;
+    registers[19] = 1_200;
+    registers[22] = 0;
+    // TotWhImp 0x0001_0000 and TotWhImpPhA, TotWh_SF 1.
+    registers[46..50].copy_from_slice(&[1, 0, 0, 42]);
+    registers[54] = 1;
+    // Evt: POWER_FAILURE.
+    registers[106] = 1 << 2;
+
+    let points = decode_points(201, &registers).expect("meter points");
+    assert!(points.contains(&("AphA", 5.2)));
+    assert!(points.contains(&("PhVphA", 230.0)));
+    assert!(points.contains(&("WphA", 1_200.0)));
+    assert!(points.contains(&("TotWhImp", 655_360.0)));
+    assert!(points.contains(&("TotWhImpPhA", 420.0)));
+    // Phase B is not implemented; acc32 0 is not accumulated.
+    assert!(!points.iter().any(|(name, _)| *name == "AphB"));
+    assert!(!points.iter().any(|(name, _)| *name == "TotWhExp"));
+    let events = decode_typed_points(201, &registers).expect("meter events");
+    assert!(matches!(
+        &events[0],
+        ("Evt", PointValue::Bitfield32 { value: 4, flags }) if flags[0].name == "POWER_FAILURE" && flags[0].set
+    ));
+    assert_eq!(
+        decode_accumulator(201, &registers, "TotWhImp"),
+        Some(Accumulator {
+            kind: AccumulatorKind::Acc32,
+            count: 65_536,
+            scale: 1
+        })
+    );
+    assert_eq!(decode_accumulator(201, &registers, "TotWhExp"), None);
+    assert_eq!(decode_accumulator(201, &registers, "W"), None);
+
+    let mut float = vec![0u16; 126];
+    float[0] = 213;
+    float[1] = 124;
+    let mut put = |offset: usize, value: f32| {
+        let bits = value.to_bits();
+        float[offset] = (bits >> 16) as u16;
+        float[offset + 1] = bits as u16;
+    };
+    put(12, 231.5);
+    put(28, -2_500.0);
+    put(30, f32::NAN);
+    put(68, 1_234_567.0);
+    let points = decode_points(213, &float).expect("float meter points");
+    assert!(points.contains(&("PhVphA", 231.5)));
+    assert!(points.contains(&("W", -2_500.0)));
+    assert!(points.contains(&("TotWhImp", 1_234_567.0)));
+    assert!(!points.iter().any(|(name, _)| *name == "WphA"));
+    assert_eq!(decode_meter_power(213, &float), Some(-2_500.0));
+    let layout = point_layout(213, 40_100, &float).expect("float layout");
+    let power = layout.iter().find(|point| point.name == "W").expect("W");
+    assert_eq!((power.point_type, power.size), ("float32", 2));
+    assert_eq!((power.scale, power.scale_address), (None, None));
+    assert_eq!(power.units, "W");
+}
+
+#[test]
+fn point_layout_lists_implemented_points() {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    // Single-phase firmware: phases B and C are not implemented.
+    registers[4] = 0xFFFF;
+    registers[5] = 0xFFFF;
+    registers[14] = 1234;
+    registers[15] = 0xFFFE;
+
+    let points = point_layout(103, 40_070, &registers).expect("inverter layout");
+    let names: Vec<&str> = points.iter().map(|point| point.name).collect();
+    assert!(names.contains(&"AphA"));
+    assert!(!names.contains(&"AphB"));
+    // acc32 zero means "not implemented".
+    assert!(!names.contains(&"WH"));
+
+    let power = points.iter().find(|point| point.name == "W").expect("W");
+    assert_eq!(power.address, 40_084);
+    assert_eq!(power.point_type, "int16");
+    assert_eq!(power.size, 1);
+    assert_eq!(power.scale, Some(-2));
+    assert_eq!(power.scale_factor, Some("W_SF"));
+    assert_eq!(power.scale_address, Some(40_085));
+    assert_eq!(power.units, "W");
+
+    assert!(point_layout(160, 40_070, &registers).is_none());
+}
+
+#[test]
+fn point_span_covers_points_and_their_scale_factors() {
+    let names = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+    // W at 14, Hz_SF at 17, St at 38.
+    assert_eq!(
+        point_span(103, &names(&["W", "Hz", "St"])).unwrap(),
+        (14, 25)
+    );
+    assert_eq!(point_span(103, &names(&["Hz"])).unwrap(), (16, 2));
+    assert_eq!(point_span(213, &names(&["W"])).unwrap(), (28, 2));
+    assert_eq!(point_span(103, &[]).unwrap(), (0, 0));
+    assert!(matches!(
+        point_span(103, &names(&["W", "Nope"])),
+        Err(ParserError::UnknownPoint { model_id: 103, ref name }) if name == "Nope"
+    ));
+    assert!(point_span(160, &names(&["W"])).is_err());
+}
+
+#[test]
+fn scale_factors_resolve_within_the_block() {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[2] = 1234;
+    registers[6] = (-2i16) as u16;
+    registers[10] = 2301;
+    registers[13] = 0x8000;
+    registers[14] = 500;
+    // Outside the -10..=10 range SunSpec allows.
+    registers[15] = 11;
+    registers[16] = 5000;
+    registers[17] = (-2i16) as u16;
+
+    let factors = resolve_scale_factors(103, &registers).expect("inverter block");
+    assert_eq!(factors.get("A_SF"), Some(-2));
+    assert_eq!(factors.get("V_SF"), None);
+    assert_eq!(factors.get("W_SF"), None);
+    assert_eq!(factors.get("Hz_SF"), Some(-2));
+    assert_eq!(factors.get("Nope_SF"), None);
+    assert_eq!(factors.apply(PointValue::U16(5000), "Hz_SF"), Some(50.0));
+    assert_eq!(factors.apply(PointValue::U16(2301), "V_SF"), None);
+
+    // Points whose factor is not implemented are left out, not left unscaled.
+    let points = decode_points(103, &registers).expect("inverter points");
+    let names: Vec<&str> = points.iter().map(|(name, _)| *name).collect();
+    assert!(names.contains(&"A"));
+    assert!(names.contains(&"Hz"));
+    assert!(!names.contains(&"PhVphA"));
+    assert!(!names.contains(&"W"));
+    assert_eq!(points[0], ("A", 12.34));
+
+    assert!(resolve_scale_factors(160, &registers).is_none());
+}
+
+#[test]
+fn decode_der_points_with_scale_factors() {
+    let mut der = vec![0u16; 155];
+    der[0] = 701;
+    der[1] = 153;
+    der[10] = 4321;
+    der[16] = 0xFFFF;
+    // Hz is uint32, TotWhInj acc64.
+    der[17] = 0;
+    der[18] = 50_020;
+    der[19..23].copy_from_slice(&[0, 0, 1, 0]);
+    der[23..27].fill(0xFFFF);
+    der[115] = (-3i16) as u16;
+    der[116] = 1;
+    der[120] = 3;
+
+    let points = decode_points(701, &der).expect("DER measurement points");
+    let point = |name: &str| {
+        points
+            .iter()
+            .find(|(point, _)| *point == name)
+            .map(|(_, value)| *value)
+    };
+    assert_eq!(point("W"), Some(43_210.0));
+    assert_eq!(point("Hz"), Some(50.02));
+    assert_eq!(point("TotWhInj"), Some(65_536_000.0));
+    assert_eq!(point("TotWhAbs"), None);
+    // V_SF is 0, LNV holds the uint16 sentinel.
+    assert_eq!(point("LNV"), None);
+
+    let layout = point_layout(701, 40_200, &der).expect("DER layout");
+    let energy = layout
+        .iter()
+        .find(|point| point.name == "TotWhInj")
+        .expect("TotWhInj");
+    assert_eq!(energy.point_type, "acc64");
+    assert_eq!(energy.size, 4);
+    assert_eq!(energy.scale_address, Some(40_320));
+    assert!(layout.iter().any(|point| point.name == "InvSt"));
+
+    let mut storage = vec![0u16; 9];
+    storage[0] = 713;
+    storage[1] = 7;
+    storage[2] = 1350;
+    storage[4] = 875;
+    storage[7] = 1;
+    storage[8] = (-1i16) as u16;
+    let points = decode_points(713, &storage).expect("DER storage points");
+    assert!(points.contains(&("WHRtg", 13_500.0)));
+    assert!(points.contains(&("SoC", 87.5)));
+
+    assert!(decode_points(713, &storage[..8]).is_none());
+    assert!(decode_points(705, &der).is_none());
+}
+
+#[test]
+fn decode_storage_and_battery_points() {
+    let mut storage = vec![0u16; 26];
+    storage[0] = 124;
+    storage[1] = 24;
+    storage[2] = 500;
+    storage[8] = 6_420;
+    // ChaSt: CHARGING.
+    storage[11] = 4;
+    storage[18] = 1;
+    storage[22] = (-2i16) as u16;
+    let reading = decode_storage(124, &storage).expect("basic storage");
+    assert_eq!(reading.soc_pct, Some(64.2));
+    assert_eq!(reading.charge_state, Some(4));
+    assert_eq!(reading.max_charge_w, Some(5_000.0));
+    assert_eq!(reading.max_discharge_w, None);
+    let typed = decode_typed_points(124, &storage).expect("storage typed points");
+    assert!(typed.contains(&(
+        "ChaSt",
+        PointValue::Enum16 {
+            value: 4,
+            symbol: Some("CHARGING".to_string()),
+        }
+    )));
+
+    let mut battery = vec![0u16; 64];
+    battery[0] = 802;
+    battery[1] = 62;
+    battery[4] = 250;
+    battery[5] = 300;
+    battery[11] = 815;
+    battery[16] = 3;
+    battery[22] = BATTERY_STATE_FAULT;
+    // Evt1: OVER_TEMP_ALARM (bit 1) and UNDER_SOC_MIN_WARNING (bit 14).
+    battery[27] = (1 << 1) | (1 << 14);
+    battery[37] = 3_412;
+    battery[40] = 3_398;
+    battery[44] = (-120i16) as u16;
+    battery[54] = 1;
+    battery[56] = (-1i16) as u16;
+    battery[60] = (-3i16) as u16;
+    battery[61] = (-1i16) as u16;
+
+    let points = decode_points(802, &battery).expect("battery points");
+    assert!(points.contains(&("SoC", 81.5)));
+    assert!(points.contains(&("CellVMax", 3.412)));
+    assert!(points.contains(&("CellVMin", 3.398)));
+    assert!(points.contains(&("A", -12.0)));
+    let reading = decode_storage(802, &battery).expect("battery");
+    assert_eq!(reading.soc_pct, Some(81.5));
+    assert_eq!(reading.charge_state, Some(3));
+    assert_eq!(reading.max_charge_w, Some(2_500.0));
+    assert_eq!(reading.max_discharge_w, Some(3_000.0));
+
+    let status = decode_status(802, &battery).expect("battery status");
+    assert_eq!(status.state_point, "State");
+    assert!(status.fault);
+    let events: Vec<&str> = status
+        .events
+        .iter()
+        .map(|event| event.name.as_str())
+        .collect();
+    assert_eq!(events, vec!["OVER_TEMP_ALARM", "UNDER_SOC_MIN_WARNING"]);
+
+    let layout = point_layout(802, 40_300, &battery).expect("battery layout");
+    let cell = layout
+        .iter()
+        .find(|point| point.name == "CellVMax")
+        .expect("CellVMax");
+    assert_eq!(cell.scale_address, Some(40_360));
+
+    let mut bank = vec![0u16; 28];
+    bank[0] = 803;
+    bank[1] = 26;
+    bank[4] = 31;
+    bank[11] = 817;
+    bank[16] = (-4i16) as u16;
+    let points = decode_points(803, &bank).expect("bank points");
+    assert!(points.contains(&("ModTmpMax", 31.0)));
+    assert!(points.contains(&("StrVMax", 817.0)));
+    assert!(points.contains(&("StrAMax", -4.0)));
+    assert!(decode_storage(803, &bank).is_none());
+
+    assert!(decode_points(802, &battery[..63]).is_none());
+    assert!(decode_storage(103, &battery).is_none());
+}
+
+#[test]
+fn decode_status_names_state_and_events() {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 103;
+    registers[1] = 50;
+    registers[38] = INVERTER_STATE_FAULT;
+    // Evt1: GROUND_FAULT and OVER_TEMP; Evt2 bit 0 is reserved.
+    registers[41] = 0b1000_0001;
+    registers[43] = 1;
+
+    let status = decode_status(103, &registers).expect("inverter block");
+    assert_eq!(status.state_point, "St");
+    assert_eq!(status.state, Some(INVERTER_STATE_FAULT));
+    assert_eq!(status.state_symbol.as_deref(), Some("FAULT"));
+    assert!(status.fault);
+    let events: Vec<(&str, &str)> = status
+        .events
+        .iter()
+        .map(|event| (event.point, event.name.as_str()))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("Evt1", "GROUND_FAULT"),
+            ("Evt1", "OVER_TEMP"),
+            ("Evt2", "BIT_0")
+        ]
+    );
+
+    // Not implemented: no state, no events.
+    registers[38] = 0xFFFF;
+    registers[40..44].fill(0xFFFF);
+    let status = decode_status(103, &registers).expect("inverter block");
+    assert_eq!(status.state, None);
+    assert!(!status.fault);
+    assert!(status.events.is_empty());
+
+    let mut der = vec![0u16; 155];
+    der[0] = 701;
+    der[1] = 153;
+    der[4] = 6;
+    // Alrm: MANUFACTURER_ALRM (bit 16).
+    der[6] = 1;
+    let status = decode_status(701, &der).expect("DER block");
+    assert_eq!(status.state_point, "InvSt");
+    assert_eq!(status.state_symbol.as_deref(), Some("FAULT"));
+    assert!(status.fault);
+    assert_eq!(status.events.len(), 1);
+    assert_eq!(status.events[0].point, "Alrm");
+    assert_eq!(status.events[0].name, "MANUFACTURER_ALRM");
+
+    assert!(decode_status(1, &der).is_none());
+    assert!(decode_status(160, &der).is_none());
+}
+
+fn encode_string(value: &str, registers: usize) -> Vec<u16> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.resize(registers * 2, 0);
+    bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect()
+}
+
+#[test]
+fn decode_common_model_strings() {
+    let mut registers = vec![1u16, 66];
+    registers.extend(encode_string("SMA", 16));
+    registers.extend(encode_string("Sunny Boy Storage 5.0", 16));
+    registers.extend(encode_string("", 8));
+    registers.extend(encode_string("3.10.5 ", 8));
+    registers.extend(encode_string("1900123456", 16));
+    registers.push(1);
+
+    let common = decode_common(1, &registers).expect("common block");
+    assert_eq!(common.manufacturer, "SMA");
+    assert_eq!(common.model, "Sunny Boy Storage 5.0");
+    assert_eq!(common.options, "");
+    assert_eq!(common.version, "3.10.5");
+    assert_eq!(common.serial_number, "1900123456");
+
+    assert!(decode_common(103, &registers).is_none());
+    assert!(decode_common(1, &registers[..40]).is_none());
+}
+
+#[test]
+fn decode_typed_inverter_points() {
+    let mut registers = vec![0u16; 52];
+    registers[0] = 101;
+    registers[1] = 50;
+    registers[38] = INVERTER_STATE_FAULT;
+    registers[39] = 0xFFFF;
+    // Evt1: GROUND_FAULT and OVER_TEMP.
+    registers[40] = 0;
+    registers[41] = 0b1000_0001;
+
+    let points = decode_typed_points(101, &registers).expect("inverter block");
+    let point = |name: &str| {
+        points
+            .iter()
+            .find(|(point, _)| *point == name)
+            .map(|(_, value)| value.clone())
+    };
+    assert_eq!(
+        point("St"),
+        Some(PointValue::Enum16 {
+            value: INVERTER_STATE_FAULT,
+            symbol: Some("FAULT".to_string()),
+        })
+    );
+    // Vendor state not implemented.
+    assert_eq!(point("StVnd"), None);
+    let Some(PointValue::Bitfield32 { value, flags }) = point("Evt1") else {
+        panic!("Evt1 is a bitfield");
+    };
+    assert_eq!(value, 0x81);
+    assert_eq!(flags.len(), 16);
+    let set: Vec<&str> = flags
+        .iter()
+        .filter(|flag| flag.set)
+        .map(|flag| flag.name.as_str())
+        .collect();
+    assert_eq!(set, vec!["GROUND_FAULT", "OVER_TEMP"]);
+    assert!(flags.contains(&PointFlag {
+        name: "AC_DISCONNECT".to_string(),
+        set: false,
+    }));
+
+    registers[38] = 42;
+    registers[40..44].fill(0xFFFF);
+    let points = decode_typed_points(101, &registers).expect("inverter block");
+    assert_eq!(
+        points,
+        vec![(
+            "St",
+            PointValue::Enum16 {
+                value: 42,
+                symbol: None,
+            }
+        )]
+    );
+
+    let layout = point_layout(101, 40_070, &registers).expect("inverter layout");
+    let state = layout.iter().find(|point| point.name == "St").expect("St");
+    assert_eq!(
+        (state.address, state.point_type, state.size),
+        (40_108, "enum16", 1)
+    );
+    assert!(layout.iter().all(|point| point.name != "Evt1"));
+
+    assert!(decode_typed_points(160, &registers).is_none());
+}
+
+#[test]
+fn decode_typed_common_strings() {
+    let mut registers = vec![1u16, 66];
+    registers.extend(encode_string("SMA", 16));
+    registers.extend(encode_string("Sunny Boy 5.0", 16));
+    registers.extend(encode_string("", 8));
+    registers.extend(encode_string("3.10.5", 8));
+    registers.extend(encode_string("1900123456", 16));
+
+    let points = decode_typed_points(1, &registers).expect("common block");
+    let names: Vec<&str> = points.iter().map(|(name, _)| *name).collect();
+    // Opt is all NUL, i.e. not implemented.
+    assert_eq!(names, vec!["Mn", "Md", "Vr", "SN"]);
+    assert_eq!(points[3].1, PointValue::String("1900123456".to_string()));
+    assert_eq!(apply_scale(points[0].1.clone(), 0), None);
+}
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 /// Raw point values before SunSpec scale factors are applied.
@@ -9,12 +11,103 @@ pub enum PointValue {
     U16(u16),
     I32(i32),
     U32(u32),
+    U64(u64),
     F32(f32),
+    /// Text with NUL padding and surrounding blanks removed.
+    String(String),
+    /// Enumerated value with the symbol name the model defines for it, if any.
+    Enum16 {
+        value: u16,
+        symbol: Option<String>,
+    },
+    /// Bit field with the state of every flag the model names.
+    Bitfield32 {
+        value: u32,
+        flags: Vec<PointFlag>,
+    },
+}
+
+/// One named bit of a bitfield point.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PointFlag {
+    pub name: String,
+    pub set: bool,
 }
 
 /// Basic identity for an inverter or battery endpoint.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct DeviceIdentity {
     pub ip: String,
     pub unit_id: u8,
+    /// Register address of the `SunS` marker, once known (configured or detected).
+    #[serde(default)]
+    pub base_address: Option<u16>,
+}
+
+impl DeviceIdentity {
+    /// Same endpoint (IP and unit ID), whatever base address each side knows.
+    pub fn same_unit(&self, other: &DeviceIdentity) -> bool {
+        self.ip == other.ip && self.unit_id == other.unit_id
+    }
+
+    /// The unit's key, the same whatever base address is known.
+    pub fn key(&self) -> DeviceKey {
+        DeviceKey {
+            ip: self.ip.clone(),
+            unit_id: self.unit_id,
+        }
+    }
+}
+
+/// One unit behind an IP: the key its poller is known by. Displays as
+/// `ip:unit_id`, the poller ID of logs and the HTTP API.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DeviceKey {
+    pub ip: String,
+    pub unit_id: u8,
+}
+
+impl std::fmt::Display for DeviceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.ip, self.unit_id)
+    }
+}
+
+/// A device as its common model identifies it, which unlike its endpoint
+/// stays the same when it is readdressed or moved to another gateway.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SerialIdentity {
+    /// Manufacturer (`Mn`), as the device reports it.
+    pub manufacturer: String,
+    /// Serial number (`SN`).
+    pub serial_number: String,
+}
+
+impl SerialIdentity {
+    /// Surrounding blanks are ignored; None without a serial number, since
+    /// devices that leave it blank cannot be told apart by it.
+    pub fn new(manufacturer: &str, serial_number: &str) -> Option<Self> {
+        let serial_number = serial_number.trim();
+        (!serial_number.is_empty()).then(|| Self {
+            manufacturer: manufacturer.trim().to_string(),
+            serial_number: serial_number.to_string(),
+        })
+    }
+}
+
+/// The site a collector is installed at, stamped onto every sample so fleets
+/// can tell collectors apart without relying on device IPs.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SiteInfo {
+    pub site_id: String,
+    #[serde(default)]
+    pub plant: Option<String>,
+    /// WGS 84 coordinates in degrees.
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+    /// Free-form labels such as an operator or a grid region.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
 }